@@ -3,24 +3,31 @@
 
 use diem_api_types::{Error, LedgerInfo, MoveConverter, TransactionOnChainData};
 use diem_crypto::HashValue;
-use diem_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
+use diem_mempool::{
+    AccountPendingTransactions, MempoolClientRequest, MempoolClientSender, SubmissionStatus,
+};
+use diem_state_view::StateView;
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
     chain_id::ChainId,
+    contract_event::ContractEvent,
+    event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     protocol_spec::DpnProto,
     transaction::{SignedTransaction, TransactionInfo},
 };
-use storage_interface::MoveDbReader;
+use storage_interface::{MoveDbReader, Order};
 
 use anyhow::{ensure, format_err, Result};
 use futures::{channel::oneshot, SinkExt};
 use std::{
     borrow::Borrow,
+    collections::HashMap,
     convert::{Infallible, TryFrom},
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 use warp::Filter;
 
@@ -90,15 +97,20 @@ impl Context {
         })
     }
 
+    pub fn state_view_at_version(&self, version: u64) -> DbStateView {
+        DbStateView {
+            context: self,
+            version,
+            account_states: RwLock::new(HashMap::new()),
+        }
+    }
+
     pub fn get_account_state_blob(
         &self,
         account: AccountAddress,
         version: u64,
     ) -> Result<Option<AccountStateBlob>> {
-        let (account_state_blob, _) = self
-            .db
-            .get_account_state_with_proof_by_version(account, version)?;
-        Ok(account_state_blob)
+        self.db.get_account_state_by_version(account, version)
     }
 
     pub fn get_transactions(
@@ -167,6 +179,23 @@ impl Context {
         callback.await.map_err(anyhow::Error::from)
     }
 
+    pub async fn get_pending_account_transactions(
+        &self,
+        address: AccountAddress,
+    ) -> Result<AccountPendingTransactions> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::GetAccountTransactions(
+                address, req_sender,
+            ))
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        callback.await.map_err(anyhow::Error::from)
+    }
+
     pub fn get_transaction_by_version(
         &self,
         version: u64,
@@ -177,4 +206,70 @@ impl Context {
             .get_transaction_by_version(version, ledger_version, true)?
             .into())
     }
+
+    pub fn get_account_transactions(
+        &self,
+        address: AccountAddress,
+        start_seq_number: u64,
+        limit: u16,
+        ledger_version: u64,
+    ) -> Result<Vec<TransactionOnChainData<TransactionInfo>>> {
+        let txns = self.db.get_account_transactions(
+            address,
+            start_seq_number,
+            limit as u64,
+            true,
+            ledger_version,
+        )?;
+        Ok(txns.into_inner().into_iter().map(|t| t.into()).collect())
+    }
+
+    pub fn get_events(
+        &self,
+        event_key: &EventKey,
+        start_seq_number: u64,
+        limit: u16,
+        ledger_version: u64,
+    ) -> Result<Vec<(u64, ContractEvent)>> {
+        let events = self.db.get_events_with_proofs(
+            event_key,
+            start_seq_number,
+            Order::Ascending,
+            limit as u64,
+            Some(ledger_version),
+        )?;
+        Ok(events
+            .into_iter()
+            .map(|e| (e.transaction_version, e.event))
+            .collect())
+    }
+}
+
+/// A view of the committed state at a ledger version, for executing transactions against it.
+pub struct DbStateView<'a> {
+    context: &'a Context,
+    version: u64,
+    account_states: RwLock<HashMap<AccountAddress, Option<AccountState>>>,
+}
+
+impl<'a> StateView for DbStateView<'a> {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let address = access_path.address;
+        if let Some(state) = self.account_states.read().unwrap().get(&address) {
+            return Ok(state
+                .as_ref()
+                .and_then(|s| s.get(&access_path.path).cloned()));
+        }
+
+        let state = self.context.get_account_state(address, self.version)?;
+        let ret = state
+            .as_ref()
+            .and_then(|s| s.get(&access_path.path).cloned());
+        self.account_states.write().unwrap().insert(address, state);
+        Ok(ret)
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,226 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    context::Context,
+    page::Page,
+    param::{EventKeyParam, LedgerVersionParam},
+};
+
+use diem_api_types::{Error, EventKey, LedgerInfo, Response, TransactionId};
+
+use anyhow::Result;
+use warp::{Filter, Rejection, Reply};
+
+pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_events(context.clone()).or(get_events_by_ledger_version(context))
+}
+
+// GET /events/<event_key>?start={u64}&limit={u16}
+pub fn get_events(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("events" / EventKeyParam)
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(context.filter())
+        .map(|event_key, page, ctx| (None, event_key, page, ctx))
+        .untuple_one()
+        .and_then(handle_get_events)
+}
+
+// GET /ledger/<version>/events/<event_key>?start={u64}&limit={u16}
+pub fn get_events_by_ledger_version(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("ledger" / LedgerVersionParam / "events" / EventKeyParam)
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(context.filter())
+        .map(|version, event_key, page, ctx| (Some(version), event_key, page, ctx))
+        .untuple_one()
+        .and_then(handle_get_events)
+}
+
+async fn handle_get_events(
+    ledger_version: Option<LedgerVersionParam>,
+    event_key: EventKeyParam,
+    page: Page,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    Ok(Events::new(ledger_version, event_key, context)?.list(page)?)
+}
+
+struct Events {
+    ledger_version: u64,
+    event_key: EventKey,
+    latest_ledger_info: LedgerInfo,
+    context: Context,
+}
+
+impl Events {
+    pub fn new(
+        ledger_version: Option<LedgerVersionParam>,
+        event_key: EventKeyParam,
+        context: Context,
+    ) -> Result<Self, Error> {
+        let latest_ledger_info = context.get_latest_ledger_info()?;
+        let ledger_version = ledger_version
+            .map(|v| v.parse("ledger version"))
+            .unwrap_or_else(|| Ok(latest_ledger_info.version()))?;
+
+        if ledger_version > latest_ledger_info.version() {
+            return Err(Error::not_found(
+                "ledger",
+                TransactionId::Version(ledger_version),
+                latest_ledger_info.version(),
+            ));
+        }
+
+        Ok(Self {
+            ledger_version,
+            event_key: event_key.parse("event key")?,
+            latest_ledger_info,
+            context,
+        })
+    }
+
+    pub fn list(self, page: Page) -> Result<impl Reply, Error> {
+        let start = page.start_sequence_number()?;
+        let limit = page.limit()?;
+
+        let events =
+            self.context
+                .get_events(&self.event_key.into(), start, limit, self.ledger_version)?;
+        let events = self
+            .context
+            .move_converter()
+            .try_into_versioned_events(&events)?;
+        Response::new(self.latest_ledger_info, &events)
+    }
+}
+
+#[cfg(any(test))]
+mod tests {
+    use crate::test_utils::{assert_json, new_test_context};
+    use serde_json::json;
+
+    static TREASURY_ACCOUNT_CREATION_EVENT_KEY: &str =
+        "0x00000000000000000000000000000000000000000a550c18";
+
+    #[tokio::test]
+    async fn test_get_events() {
+        let context = new_test_context();
+
+        let resp = context
+            .get(&format!("/events/{}", TREASURY_ACCOUNT_CREATION_EVENT_KEY))
+            .await;
+        let events = resp.as_array().unwrap();
+        assert!(!events.is_empty());
+        assert_json(
+            events[0].clone(),
+            json!({
+                "version": "0",
+                "key": TREASURY_ACCOUNT_CREATION_EVENT_KEY,
+                "sequence_number": "0",
+                "type": {
+                    "type": "struct",
+                    "address": "0x1",
+                    "module": "DiemAccount",
+                    "name": "CreateAccountEvent",
+                    "generic_type_params": []
+                },
+                "data": {
+                    "created": "0xa550c18",
+                    "role_id": "0"
+                }
+            }),
+        );
+        for (i, event) in events.iter().enumerate() {
+            assert_eq!(event["sequence_number"], i.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_start_and_limit() {
+        let context = new_test_context();
+
+        let all = context
+            .get(&format!("/events/{}", TREASURY_ACCOUNT_CREATION_EVENT_KEY))
+            .await;
+        let resp = context
+            .get(&format!(
+                "/events/{}?start=1&limit=2",
+                TREASURY_ACCOUNT_CREATION_EVENT_KEY
+            ))
+            .await;
+        assert_json(resp, json!(all.as_array().unwrap()[1..3]));
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_ledger_version() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        context.commit_block(&vec![txn]);
+
+        let genesis_events = context
+            .get(&format!(
+                "/ledger/0/events/{}",
+                TREASURY_ACCOUNT_CREATION_EVENT_KEY
+            ))
+            .await;
+        let latest_events = context
+            .get(&format!("/events/{}", TREASURY_ACCOUNT_CREATION_EVENT_KEY))
+            .await;
+        assert_eq!(
+            genesis_events.as_array().unwrap().len() + 1,
+            latest_events.as_array().unwrap().len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_events_returns_empty_array_for_unknown_event_key() {
+        let context = new_test_context();
+
+        let resp = context
+            .get("/events/0x00000000000000000000000000000000000000000000dead")
+            .await;
+        assert_eq!(json!([]), resp);
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_invalid_event_key() {
+        let context = new_test_context();
+
+        let resp = context.expect_status_code(400).get("/events/0x1").await;
+        assert_json(
+            resp,
+            json!({
+                "code": 400,
+                "message": "invalid parameter event key: 0x1"
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_ledger_version_is_too_large() {
+        let context = new_test_context();
+
+        let resp = context
+            .expect_status_code(404)
+            .get(&format!(
+                "/ledger/1000000/events/{}",
+                TREASURY_ACCOUNT_CREATION_EVENT_KEY
+            ))
+            .await;
+        assert_json(
+            resp,
+            json!({
+                "code": 404,
+                "message": "ledger not found by version(1000000)",
+                "diem_ledger_version": "0"
+            }),
+        );
+    }
+}
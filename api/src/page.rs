@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::param::{Param, TransactionVersionParam};
+use crate::param::Param;
 
 use diem_api_types::{Error, TransactionId};
 
@@ -13,17 +13,14 @@ const MAX_PAGE_SIZE: u16 = 1000;
 
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) struct Page {
-    start: Option<TransactionVersionParam>,
+    start: Option<Param<u64>>,
     limit: Option<Param<u16>>,
 }
 
 impl Page {
-    pub fn start(&self, latest_ledger_version: u64) -> Result<u64, Error> {
-        let version = self
-            .start
-            .clone()
-            .map(|v| v.parse("start"))
-            .unwrap_or_else(|| Ok(latest_ledger_version))?;
+    /// The transaction version the page starts at, defaults to the latest ledger version.
+    pub fn start_version(&self, latest_ledger_version: u64) -> Result<u64, Error> {
+        let version = self.parse_start()?.unwrap_or(latest_ledger_version);
         if version > latest_ledger_version {
             return Err(Error::not_found(
                 "transaction",
@@ -34,6 +31,12 @@ impl Page {
         Ok(version)
     }
 
+    /// The sequence number the page starts at, for paging through an account's transactions or
+    /// the events of an event stream; defaults to the first one.
+    pub fn start_sequence_number(&self) -> Result<u64, Error> {
+        Ok(self.parse_start()?.unwrap_or(0))
+    }
+
     pub fn limit(&self) -> Result<u16, Error> {
         let limit = self
             .limit
@@ -48,4 +51,8 @@ impl Page {
         }
         Ok(limit)
     }
+
+    fn parse_start(&self) -> Result<Option<u64>, Error> {
+        self.start.clone().map(|v| v.parse("start")).transpose()
+    }
 }
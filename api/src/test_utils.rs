@@ -56,7 +56,7 @@ pub fn new_test_context() -> TestContext {
     let (root_keys, genesis, genesis_waypoint, validators) = builder.build(&mut rng).unwrap();
     let validator_owner = validators[0].storage().get(OWNER_ACCOUNT).unwrap().value;
 
-    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&tmp_dir));
+    let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     let ret =
         db_bootstrapper::maybe_bootstrap::<DiemVM>(&db_rw, &genesis, genesis_waypoint).unwrap();
     assert!(ret);
@@ -223,6 +223,17 @@ impl TestContext {
         .await
     }
 
+    pub async fn post(&self, path: &str, body: Value) -> Value {
+        self.execute(
+            warp::test::request()
+                .method("POST")
+                .path(path)
+                .header(CONTENT_TYPE, mime_types::JSON)
+                .json(&body),
+        )
+        .await
+    }
+
     pub async fn execute(&self, req: warp::test::RequestBuilder) -> Value {
         let routes = index::routes(self.context.clone());
         let resp = req.reply(&routes).await;
@@ -261,7 +272,7 @@ impl TestContext {
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_micros() as u64;
-        BlockMetadata::new(id, round, timestamp, vec![], self.validator_owner)
+        BlockMetadata::new(id, round, timestamp, vec![], self.validator_owner, vec![])
     }
 
     fn new_ledger_info(
@@ -1,17 +1,26 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{context::Context, page::Page, param::TransactionIdParam};
+use crate::{
+    context::Context,
+    page::Page,
+    param::{AddressParam, LedgerVersionParam, TransactionIdParam},
+};
 
 use diem_api_types::{
-    mime_types, Error, LedgerInfo, Response, Transaction, TransactionData, TransactionId,
+    mime_types, AccountPendingTransactions, Address, Error, HexEncodedBytes, LedgerInfo,
+    MempoolTransaction, Response, SimulateTransactionRequest, SubmitTransactionRequest,
+    TimelineState, Transaction, TransactionData, TransactionId, UserTransactionRequest, U64,
 };
+use diem_crypto::signing_message;
 use diem_types::{
     mempool_status::MempoolStatusCode,
     transaction::{SignedTransaction, TransactionInfo},
 };
+use diem_vm::DiemVM;
 
 use anyhow::Result;
+use serde_json::json;
 use warp::{
     http::{header::CONTENT_TYPE, StatusCode},
     reply, Filter, Rejection, Reply,
@@ -20,7 +29,14 @@ use warp::{
 pub fn routes(context: Context) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     get_transaction(context.clone())
         .or(get_transactions(context.clone()))
-        .or(post_bcs_transactions(context))
+        .or(get_account_transactions(context.clone()))
+        .or(get_account_transactions_by_ledger_version(context.clone()))
+        .or(get_pending_account_transactions(context.clone()))
+        .or(post_bcs_transactions(context.clone()))
+        .or(post_json_transactions(context.clone()))
+        .or(create_signing_message(context.clone()))
+        .or(simulate_transaction(context.clone()))
+        .or(simulate_transaction_by_ledger_version(context))
 }
 
 // GET /transactions/{txn-hash / version}
@@ -57,6 +73,67 @@ async fn handle_get_transactions(page: Page, context: Context) -> Result<impl Re
     Ok(Transactions::new(context)?.list(page)?)
 }
 
+// GET /accounts/<address>/transactions?start={u64}&limit={u16}
+pub fn get_account_transactions(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts" / AddressParam / "transactions")
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(context.filter())
+        .map(|address, page, ctx| (None, address, page, ctx))
+        .untuple_one()
+        .and_then(handle_get_account_transactions)
+}
+
+// GET /ledger/<version>/accounts/<address>/transactions?start={u64}&limit={u16}
+pub fn get_account_transactions_by_ledger_version(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("ledger" / LedgerVersionParam / "accounts" / AddressParam / "transactions")
+        .and(warp::get())
+        .and(warp::query::<Page>())
+        .and(context.filter())
+        .map(|version, address, page, ctx| (Some(version), address, page, ctx))
+        .untuple_one()
+        .and_then(handle_get_account_transactions)
+}
+
+async fn handle_get_account_transactions(
+    ledger_version: Option<LedgerVersionParam>,
+    address: AddressParam,
+    page: Page,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let ledger_version = ledger_version
+        .map(|v| v.parse("ledger version"))
+        .transpose()?;
+    Ok(Transactions::new(context)?.list_by_account(
+        ledger_version,
+        address.parse("account address")?,
+        page,
+    )?)
+}
+
+// GET /accounts/<address>/pending_transactions
+pub fn get_pending_account_transactions(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("accounts" / AddressParam / "pending_transactions")
+        .and(warp::get())
+        .and(context.filter())
+        .and_then(handle_get_pending_account_transactions)
+}
+
+async fn handle_get_pending_account_transactions(
+    address: AddressParam,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    Ok(Transactions::new(context)?
+        .list_pending_by_account(address.parse("account address")?)
+        .await?)
+}
+
 // POST /transactions
 pub fn post_bcs_transactions(
     context: Context,
@@ -82,6 +159,93 @@ async fn handle_post_bcs_transactions(
     Ok(Transactions::new(context)?.create(txn).await?)
 }
 
+// POST /transactions
+pub fn post_json_transactions(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("transactions")
+        .and(warp::post())
+        .and(warp::header::exact(CONTENT_TYPE.as_str(), mime_types::JSON))
+        .and(warp::body::bytes())
+        .and(context.filter())
+        .and_then(handle_post_json_transactions)
+}
+
+async fn handle_post_json_transactions(
+    body: bytes::Bytes,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let txn: SubmitTransactionRequest =
+        serde_json::from_slice(&body).map_err(Error::invalid_request_body)?;
+    let txn = context
+        .move_converter()
+        .try_into_signed_transaction(txn, context.chain_id())
+        .map_err(Error::invalid_request_body)?;
+    Ok(Transactions::new(context)?.create(txn).await?)
+}
+
+// POST /transactions/signing_message
+pub fn create_signing_message(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("transactions" / "signing_message")
+        .and(warp::post())
+        .and(warp::header::exact(CONTENT_TYPE.as_str(), mime_types::JSON))
+        .and(warp::body::bytes())
+        .and(context.filter())
+        .and_then(handle_create_signing_message)
+}
+
+async fn handle_create_signing_message(
+    body: bytes::Bytes,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let txn: UserTransactionRequest =
+        serde_json::from_slice(&body).map_err(Error::invalid_request_body)?;
+    Ok(Transactions::new(context)?.signing_message(txn)?)
+}
+
+// POST /transactions/simulate
+pub fn simulate_transaction(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("transactions" / "simulate")
+        .and(warp::post())
+        .and(warp::header::exact(CONTENT_TYPE.as_str(), mime_types::JSON))
+        .and(warp::body::bytes())
+        .and(context.filter())
+        .map(|body, ctx| (None, body, ctx))
+        .untuple_one()
+        .and_then(handle_simulate_transaction)
+}
+
+// POST /ledger/<version>/transactions/simulate
+pub fn simulate_transaction_by_ledger_version(
+    context: Context,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("ledger" / LedgerVersionParam / "transactions" / "simulate")
+        .and(warp::post())
+        .and(warp::header::exact(CONTENT_TYPE.as_str(), mime_types::JSON))
+        .and(warp::body::bytes())
+        .and(context.filter())
+        .map(|version, body, ctx| (Some(version), body, ctx))
+        .untuple_one()
+        .and_then(handle_simulate_transaction)
+}
+
+async fn handle_simulate_transaction(
+    ledger_version: Option<LedgerVersionParam>,
+    body: bytes::Bytes,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let ledger_version = ledger_version
+        .map(|v| v.parse("ledger version"))
+        .transpose()?;
+    let txn: SimulateTransactionRequest =
+        serde_json::from_slice(&body).map_err(Error::invalid_request_body)?;
+    Ok(Transactions::new(context)?.simulate(ledger_version, txn)?)
+}
+
 struct Transactions {
     ledger_info: LedgerInfo,
     context: Context,
@@ -118,9 +282,22 @@ impl Transactions {
         }
     }
 
+    pub fn signing_message(self, txn: UserTransactionRequest) -> Result<impl Reply, Error> {
+        let raw_txn = self
+            .context
+            .move_converter()
+            .try_into_raw_transaction(txn, self.context.chain_id())
+            .map_err(Error::invalid_request_body)?;
+        let message = signing_message(&raw_txn).map_err(|e| Error::internal(e.into()))?;
+        Response::new(
+            self.ledger_info,
+            &json!({ "message": HexEncodedBytes::from(message) }),
+        )
+    }
+
     pub fn list(self, page: Page) -> Result<impl Reply, Error> {
         let ledger_version = self.ledger_info.version();
-        let start_version = page.start(ledger_version)?;
+        let start_version = page.start_version(ledger_version)?;
         let limit = page.limit()?;
 
         let data = self
@@ -136,6 +313,101 @@ impl Transactions {
         Response::new(self.ledger_info, &txns)
     }
 
+    pub fn list_by_account(
+        self,
+        ledger_version: Option<u64>,
+        address: Address,
+        page: Page,
+    ) -> Result<impl Reply, Error> {
+        let ledger_version = self.ledger_version(ledger_version)?;
+        let start_seq_number = page.start_sequence_number()?;
+        let limit = page.limit()?;
+
+        let data = self.context.get_account_transactions(
+            address.into(),
+            start_seq_number,
+            limit,
+            ledger_version,
+        )?;
+
+        let converter = self.context.move_converter();
+
+        let txns: Vec<Transaction> = data
+            .into_iter()
+            .map(|t| converter.try_into_onchain_transaction(t))
+            .collect::<Result<_>>()?;
+        Response::new(self.ledger_info, &txns)
+    }
+
+    pub async fn list_pending_by_account(self, address: Address) -> Result<impl Reply, Error> {
+        let pending = self
+            .context
+            .get_pending_account_transactions(address.into())
+            .await?;
+
+        let converter = self.context.move_converter();
+
+        let transactions: Vec<MempoolTransaction> = pending
+            .transactions
+            .into_iter()
+            .map(|status| {
+                let timeline_state = match status.timeline_state {
+                    diem_mempool::TimelineState::Ready(timeline_id) => TimelineState::Ready {
+                        timeline_id: timeline_id.into(),
+                    },
+                    diem_mempool::TimelineState::NotReady => TimelineState::NotReady,
+                    diem_mempool::TimelineState::NonQualified => TimelineState::NonQualified,
+                };
+                Ok(MempoolTransaction {
+                    timeline_state,
+                    parked: status.is_parked,
+                    transaction: converter.try_into_pending_transaction(status.txn)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Response::new(
+            self.ledger_info,
+            &AccountPendingTransactions {
+                next_sequence_number: pending.next_sequence_number.map(U64::from),
+                transactions,
+            },
+        )
+    }
+
+    pub fn simulate(
+        self,
+        ledger_version: Option<u64>,
+        txn: SimulateTransactionRequest,
+    ) -> Result<impl Reply, Error> {
+        let ledger_version = self.ledger_version(ledger_version)?;
+        let converter = self.context.move_converter();
+        let chain_id = self.context.chain_id();
+        let state_view = self.context.state_view_at_version(ledger_version);
+        let (vm_status, output) = match txn.signature {
+            Some(signature) => {
+                let txn = converter
+                    .try_into_signed_transaction(
+                        SubmitTransactionRequest {
+                            user_transaction_request: txn.user_transaction_request,
+                            signature,
+                        },
+                        chain_id,
+                    )
+                    .map_err(Error::invalid_request_body)?;
+                DiemVM::simulate_signed_transaction(txn, &state_view)
+            }
+            None => {
+                let raw_txn = converter
+                    .try_into_raw_transaction(txn.user_transaction_request, chain_id)
+                    .map_err(Error::invalid_request_body)?;
+                DiemVM::simulate_unsigned_transaction(raw_txn, &state_view)
+            }
+        };
+
+        let output = converter.try_into_transaction_output(&vm_status, &output)?;
+        Response::new(self.ledger_info, &output)
+    }
+
     pub async fn get_transaction(self, id: TransactionId) -> Result<impl Reply, Error> {
         let txn_data = match id.clone() {
             TransactionId::Hash(hash) => self.get_by_hash(hash.into()).await?,
@@ -149,6 +421,19 @@ impl Transactions {
         Response::new(self.ledger_info, &ret)
     }
 
+    fn ledger_version(&self, ledger_version: Option<u64>) -> Result<u64, Error> {
+        let latest_ledger_version = self.ledger_info.version();
+        let ledger_version = ledger_version.unwrap_or(latest_ledger_version);
+        if ledger_version > latest_ledger_version {
+            return Err(Error::not_found(
+                "ledger",
+                TransactionId::Version(ledger_version),
+                latest_ledger_version,
+            ));
+        }
+        Ok(ledger_version)
+    }
+
     fn transaction_not_found(&self, id: TransactionId) -> Error {
         Error::not_found("transaction", id, self.ledger_info.version())
     }
@@ -188,12 +473,13 @@ impl Transactions {
 
 #[cfg(any(test))]
 mod tests {
-    use crate::test_utils::{assert_json, find_value, new_test_context};
+    use crate::test_utils::{assert_json, find_value, new_test_context, TestContext};
 
     use diem_crypto::{
+        ed25519::Ed25519PrivateKey,
         hash::CryptoHash,
         multi_ed25519::{MultiEd25519PrivateKey, MultiEd25519PublicKey},
-        SigningKey, Uniform,
+        signing_message, SigningKey, Uniform,
     };
     use diem_sdk::{client::SignedTransaction, transaction_builder::Currency};
     use diem_types::{
@@ -210,7 +496,7 @@ mod tests {
         identifier::Identifier,
         language_storage::{ModuleId, StructTag},
     };
-    use serde_json::json;
+    use serde_json::{json, Value};
 
     #[tokio::test]
     async fn test_get_transactions_output_genesis_transaction() {
@@ -779,6 +1065,204 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_post_json_format_transaction() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let pending_txn = pending_transaction_json(&context, txn);
+
+        let resp = context
+            .expect_status_code(202)
+            .post("/transactions", submit_transaction_request(&pending_txn))
+            .await;
+        assert_json(resp, pending_txn);
+    }
+
+    #[tokio::test]
+    async fn test_post_json_format_transaction_with_invalid_argument() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let mut req = submit_transaction_request(&pending_transaction_json(&context, txn));
+        req["payload"]["arguments"][0] = json!(0);
+
+        let resp = context
+            .expect_status_code(400)
+            .post("/transactions", req)
+            .await;
+        assert_json(
+            resp,
+            json!({
+                "code": 400,
+                "message": "invalid request body: invalid argument of type U64: invalid type: integer `0`, expected a string"
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_json_format_transaction_with_invalid_signature() {
+        let mut context = new_test_context();
+        let txn = context.create_invalid_signature_transaction();
+        let req = submit_transaction_request(&pending_transaction_json(&context, txn));
+
+        let resp = context
+            .expect_status_code(400)
+            .post("/transactions", req)
+            .await;
+        assert_json(
+            resp,
+            json!({
+              "code": 400,
+              "message": "invalid transaction: INVALID_SIGNATURE"
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_signing_message() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let mut req = submit_transaction_request(&pending_transaction_json(&context, txn.clone()));
+        req.as_object_mut().unwrap().remove("signature");
+
+        let resp = context.post("/transactions/signing_message", req).await;
+        let raw_txn = txn.into_raw_transaction();
+        assert_json(
+            resp,
+            json!({
+                "message": format!("0x{}", hex::encode(signing_message(&raw_txn).unwrap())),
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let req = submit_transaction_request(&pending_transaction_json(&context, txn));
+
+        let resp = context.post("/transactions/simulate", req).await;
+        assert_eq!(resp["vm_status"], "status EXECUTED of type Execution");
+        assert_eq!(resp["success"], true);
+        assert_ne!(resp["gas_used"], "0");
+        assert!(!resp["events"].as_array().unwrap().is_empty());
+        assert!(!resp["changes"].as_array().unwrap().is_empty());
+
+        // The simulated transaction is not committed.
+        context
+            .expect_status_code(404)
+            .get(&format!(
+                "/accounts/{}/resources",
+                account.address().to_hex_literal()
+            ))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction_skips_signature_verification() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let invalid_key = Ed25519PrivateKey::generate(context.rng());
+        let txn = context
+            .create_parent_vasp(&account)
+            .into_raw_transaction()
+            .sign(&invalid_key, context.tc_account().public_key().clone())
+            .unwrap()
+            .into_inner();
+        let req = submit_transaction_request(&pending_transaction_json(&context, txn));
+
+        let resp = context.post("/transactions/simulate", req).await;
+        assert_eq!(resp["success"], true);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_unsigned_transaction() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let mut req = submit_transaction_request(&pending_transaction_json(&context, txn));
+        req.as_object_mut().unwrap().remove("signature");
+
+        let resp = context.post("/transactions/simulate", req).await;
+        assert_eq!(resp["vm_status"], "status EXECUTED of type Execution");
+        assert_eq!(resp["success"], true);
+        assert_ne!(resp["gas_used"], "0");
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction_with_discarded_output() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        context.commit_block(&vec![txn.clone()]);
+        let req = submit_transaction_request(&pending_transaction_json(&context, txn));
+
+        let resp = context.post("/transactions/simulate", req).await;
+        assert_json(
+            resp,
+            json!({
+                "vm_status": "status SEQUENCE_NUMBER_TOO_OLD of type Validation",
+                "success": false,
+                "gas_used": "0",
+                "events": [],
+                "changes": []
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction_by_ledger_version() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        context.commit_block(&vec![txn.clone()]);
+        let req = submit_transaction_request(&pending_transaction_json(&context, txn));
+
+        let resp = context.post("/ledger/0/transactions/simulate", req).await;
+        assert_eq!(resp["success"], true);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction_by_ledger_version_is_too_large() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let req = submit_transaction_request(&pending_transaction_json(&context, txn));
+
+        let resp = context
+            .expect_status_code(404)
+            .post("/ledger/1000000/transactions/simulate", req)
+            .await;
+        assert_json(
+            resp,
+            json!({
+                "code": 404,
+                "message": "ledger not found by version(1000000)",
+                "diem_ledger_version": "0"
+            }),
+        );
+    }
+
+    fn pending_transaction_json(context: &TestContext, txn: SignedTransaction) -> Value {
+        let pending_txn = context
+            .context
+            .move_converter()
+            .try_into_pending_transaction(txn)
+            .unwrap();
+        serde_json::to_value(pending_txn).unwrap()
+    }
+
+    fn submit_transaction_request(pending_txn: &Value) -> Value {
+        let mut req = pending_txn.clone();
+        let fields = req.as_object_mut().unwrap();
+        fields.remove("type");
+        fields.remove("hash");
+        req
+    }
+
     #[tokio::test]
     async fn test_post_invalid_bcs_format_transaction() {
         let context = new_test_context();
@@ -835,7 +1319,7 @@ mod tests {
             resp,
             json!({
               "code": 400,
-              "message": "transaction is rejected: InvalidUpdate - Failed to update gas price to 0"
+              "message": "transaction is rejected: InvalidUpdate - Failed to update gas price to 0, it must be higher than the current gas price 0"
             }),
         );
     }
@@ -1038,6 +1522,75 @@ mod tests {
         assert_json(resp, txns[0].clone())
     }
 
+    #[tokio::test]
+    async fn test_get_account_transactions() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        context.commit_block(&vec![txn]);
+
+        let txns = context
+            .get(&format!(
+                "/accounts/{}/transactions",
+                context.tc_account().address()
+            ))
+            .await;
+        assert_eq!(1, txns.as_array().unwrap().len());
+
+        let expected_txns = context.get("/transactions?start=2&limit=1").await;
+        assert_json(txns, expected_txns);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_by_start_sequence_number() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        context.commit_block(&vec![txn]);
+
+        let txns = context
+            .get(&format!(
+                "/accounts/{}/transactions?start=1",
+                context.tc_account().address()
+            ))
+            .await;
+        assert_eq!(json!([]), txns);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_by_ledger_version() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        context.commit_block(&vec![txn]);
+
+        let txns = context
+            .get(&format!(
+                "/ledger/1/accounts/{}/transactions",
+                context.tc_account().address()
+            ))
+            .await;
+        assert_eq!(json!([]), txns);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_by_ledger_version_is_too_large() {
+        let context = new_test_context();
+
+        let resp = context
+            .expect_status_code(404)
+            .get("/ledger/1000000/accounts/0xb1e55ed/transactions")
+            .await;
+        assert_json(
+            resp,
+            json!({
+                "code": 404,
+                "message": "ledger not found by version(1000000)",
+                "diem_ledger_version": "0"
+            }),
+        );
+    }
+
     #[tokio::test]
     async fn test_get_pending_transaction_by_hash() {
         let mut context = new_test_context();
@@ -1067,4 +1620,56 @@ mod tests {
             }),
         )
     }
+
+    #[tokio::test]
+    async fn test_get_pending_account_transactions() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+        let txn = context.create_parent_vasp(&account);
+        let sender = txn.sender();
+        let sequence_number = txn.sequence_number();
+        let body = bcs::to_bytes(&txn).unwrap();
+        let pending_txn = context
+            .expect_status_code(202)
+            .post_bcs_txn("/transactions", body)
+            .await;
+
+        let resp = context
+            .get(&format!("/accounts/{}/pending_transactions", sender))
+            .await;
+        assert_json(
+            resp,
+            json!({
+                "next_sequence_number": (sequence_number + 1).to_string(),
+                "transactions": [{
+                    "timeline_state": {
+                        "type": "ready",
+                        "timeline_id": "1"
+                    },
+                    "parked": false,
+                    "transaction": pending_txn
+                }]
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_account_transactions_of_unknown_account() {
+        let mut context = new_test_context();
+        let account = context.gen_account();
+
+        let resp = context
+            .get(&format!(
+                "/accounts/{}/pending_transactions",
+                account.address()
+            ))
+            .await;
+        assert_json(
+            resp,
+            json!({
+                "next_sequence_number": null,
+                "transactions": []
+            }),
+        );
+    }
 }
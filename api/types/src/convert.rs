@@ -2,20 +2,33 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    transaction::TransactionOnChainData, Event, MoveResource, Transaction, TransactionData,
-    TransactionPayload, WriteSetChange, WriteSetPayload,
+    transaction::TransactionOnChainData, Address, Event, HexEncodedBytes, MoveResource, MoveType,
+    SubmitTransactionRequest, Transaction, TransactionData, TransactionOutput, TransactionPayload,
+    TransactionSignature, UserTransactionRequest, VersionedEvent, WriteSetChange, WriteSetPayload,
+    U128, U64,
 };
 use diem_types::{
     access_path::Path,
+    chain_id::ChainId,
     contract_event::ContractEvent,
-    transaction::{SignedTransaction, TransactionInfoTrait},
+    transaction::{
+        Module, RawTransaction, Script, ScriptFunction, SignedTransaction, TransactionInfoTrait,
+        TransactionStatus,
+    },
+    vm_status::{KeptVMStatus, VMStatus},
     write_set::WriteOp,
 };
-use move_core_types::{language_storage::StructTag, resolver::MoveResolver};
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    resolver::MoveResolver,
+    transaction_argument::TransactionArgument,
+    value::MoveValue,
+};
 use resource_viewer::MoveValueAnnotator;
 
-use anyhow::Result;
-use std::convert::TryInto;
+use anyhow::{bail, ensure, format_err, Result};
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
 
 pub struct MoveConverter<'a, R: ?Sized> {
     inner: MoveValueAnnotator<'a, R>,
@@ -75,6 +88,24 @@ impl<'a, R: MoveResolver + ?Sized> MoveConverter<'a, R> {
         Ok(ret)
     }
 
+    pub fn try_into_transaction_output(
+        &self,
+        vm_status: &VMStatus,
+        output: &diem_types::transaction::TransactionOutput,
+    ) -> Result<TransactionOutput> {
+        Ok(TransactionOutput {
+            vm_status: vm_status.to_string(),
+            success: output.status() == &TransactionStatus::Keep(KeptVMStatus::Executed),
+            gas_used: output.gas_used().into(),
+            events: self.try_into_events(output.events())?,
+            changes: output
+                .write_set()
+                .iter()
+                .map(|(access_path, op)| self.try_into_write_set_change(access_path, op))
+                .collect::<Result<_>>()?,
+        })
+    }
+
     pub fn try_into_transaction_payload(
         &self,
         payload: &diem_types::transaction::TransactionPayload,
@@ -91,9 +122,9 @@ impl<'a, R: MoveResolver + ?Sized> MoveConverter<'a, R> {
                 arguments: self
                     .inner
                     .view_function_arguments(fun.module(), fun.function(), fun.args())?
-                    .iter()
-                    .map(|v| v.clone().into())
-                    .collect(),
+                    .into_iter()
+                    .map(|v| serde_json::to_value(crate::MoveValue::from(v)))
+                    .collect::<serde_json::Result<_>>()?,
             },
         };
         Ok(ret)
@@ -161,4 +192,199 @@ impl<'a, R: MoveResolver + ?Sized> MoveConverter<'a, R> {
         }
         Ok(ret)
     }
+
+    pub fn try_into_versioned_events(
+        &self,
+        events: &[(u64, ContractEvent)],
+    ) -> Result<Vec<VersionedEvent>> {
+        let mut ret = vec![];
+        for (version, event) in events {
+            let data = self
+                .inner
+                .view_value(event.type_tag(), event.event_data())?;
+            ret.push((*version, event, data).into());
+        }
+        Ok(ret)
+    }
+
+    pub fn try_into_signed_transaction(
+        &self,
+        txn: SubmitTransactionRequest,
+        chain_id: ChainId,
+    ) -> Result<SignedTransaction> {
+        let raw_txn = self.try_into_raw_transaction(txn.user_transaction_request, chain_id)?;
+        Ok(match txn.signature {
+            TransactionSignature::Ed25519Signature(sig) => {
+                let (public_key, signature) = sig.try_into()?;
+                SignedTransaction::new(raw_txn, public_key, signature)
+            }
+            TransactionSignature::MultiEd25519Signature(sig) => {
+                let (public_key, signature) = sig.try_into()?;
+                SignedTransaction::new_multisig(raw_txn, public_key, signature)
+            }
+            TransactionSignature::MultiAgentSignature(sig) => {
+                let (sender, secondary_signer_addresses, secondary_signers) = sig.try_into()?;
+                SignedTransaction::new_multi_agent(
+                    raw_txn,
+                    sender,
+                    secondary_signer_addresses,
+                    secondary_signers,
+                )
+            }
+        })
+    }
+
+    pub fn try_into_raw_transaction(
+        &self,
+        txn: UserTransactionRequest,
+        chain_id: ChainId,
+    ) -> Result<RawTransaction> {
+        Ok(RawTransaction::new(
+            txn.sender.into(),
+            txn.sequence_number.into(),
+            self.try_into_diem_core_transaction_payload(txn.payload)?,
+            txn.max_gas_amount.into(),
+            txn.gas_unit_price.into(),
+            txn.gas_currency_code,
+            txn.expiration_timestamp_secs.into(),
+            chain_id,
+        ))
+    }
+
+    pub fn try_into_diem_core_transaction_payload(
+        &self,
+        payload: TransactionPayload,
+    ) -> Result<diem_types::transaction::TransactionPayload> {
+        use diem_types::transaction::TransactionPayload as Target;
+
+        let ret = match payload {
+            TransactionPayload::ScriptFunctionPayload {
+                module,
+                function,
+                type_arguments,
+                arguments,
+            } => {
+                let module = module.into();
+                let ty_args = type_arguments
+                    .into_iter()
+                    .map(TypeTag::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+                let arg_types = self
+                    .inner
+                    .function_argument_types(&module, &function, &ty_args)?;
+                let args = self
+                    .try_into_move_values(arg_types, arguments)?
+                    .iter()
+                    .map(|arg| {
+                        arg.simple_serialize()
+                            .ok_or_else(|| format_err!("serialize argument failed: {:?}", arg))
+                    })
+                    .collect::<Result<_>>()?;
+                Target::ScriptFunction(ScriptFunction::new(module, function, ty_args, args))
+            }
+            TransactionPayload::ScriptPayload(script) => {
+                let ty_args = script
+                    .type_arguments
+                    .into_iter()
+                    .map(TypeTag::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+                let arg_types = script
+                    .code
+                    .abi()
+                    .params
+                    .iter()
+                    .filter(|param| !is_signer_param(param))
+                    .map(|param| TypeTag::try_from(param.clone()))
+                    .collect::<Result<Vec<_>>>()?;
+                let args = self
+                    .try_into_move_values(arg_types, script.arguments)?
+                    .into_iter()
+                    .map(try_into_transaction_argument)
+                    .collect::<Result<_>>()?;
+                Target::Script(Script::new(script.code.bytecode().to_vec(), ty_args, args))
+            }
+            TransactionPayload::ModulePayload(module) => {
+                Target::Module(Module::new(module.bytecode().to_vec()))
+            }
+            TransactionPayload::WriteSetPayload(_) => {
+                bail!("write set payload is not supported")
+            }
+        };
+        Ok(ret)
+    }
+
+    pub fn try_into_move_value(&self, typ: &TypeTag, val: Value) -> Result<MoveValue> {
+        let ret = match typ {
+            TypeTag::Bool => MoveValue::Bool(serde_json::from_value(val)?),
+            TypeTag::U8 => MoveValue::U8(serde_json::from_value(val)?),
+            TypeTag::U64 => MoveValue::U64(serde_json::from_value::<U64>(val)?.into()),
+            TypeTag::U128 => MoveValue::U128(serde_json::from_value::<U128>(val)?.into()),
+            TypeTag::Address => MoveValue::Address(serde_json::from_value::<Address>(val)?.into()),
+            TypeTag::Vector(item) if **item == TypeTag::U8 => {
+                let bytes: Vec<u8> = serde_json::from_value::<HexEncodedBytes>(val)?.into();
+                MoveValue::vector_u8(bytes)
+            }
+            TypeTag::Vector(item) => match val {
+                Value::Array(vals) => MoveValue::Vector(
+                    vals.into_iter()
+                        .map(|v| self.try_into_move_value(item, v))
+                        .collect::<Result<_>>()?,
+                ),
+                _ => bail!("expected an array of {}, got: {}", item, val),
+            },
+            TypeTag::Signer | TypeTag::Struct(_) => {
+                bail!("unsupported argument type: {}", typ)
+            }
+        };
+        Ok(ret)
+    }
+
+    fn try_into_move_values(
+        &self,
+        types: Vec<TypeTag>,
+        vals: Vec<Value>,
+    ) -> Result<Vec<MoveValue>> {
+        ensure!(
+            types.len() == vals.len(),
+            "expected {} arguments, got {}",
+            types.len(),
+            vals.len()
+        );
+        types
+            .iter()
+            .zip(vals.into_iter())
+            .map(|(typ, val)| {
+                self.try_into_move_value(typ, val)
+                    .map_err(|e| format_err!("invalid argument of type {}: {}", typ, e))
+            })
+            .collect()
+    }
+}
+
+fn is_signer_param(param: &MoveType) -> bool {
+    match param {
+        MoveType::Signer => true,
+        MoveType::Reference { to, .. } => matches!(**to, MoveType::Signer),
+        _ => false,
+    }
+}
+
+fn try_into_transaction_argument(val: MoveValue) -> Result<TransactionArgument> {
+    let ret = match val {
+        MoveValue::U8(v) => TransactionArgument::U8(v),
+        MoveValue::U64(v) => TransactionArgument::U64(v),
+        MoveValue::U128(v) => TransactionArgument::U128(v),
+        MoveValue::Bool(v) => TransactionArgument::Bool(v),
+        MoveValue::Address(v) => TransactionArgument::Address(v),
+        MoveValue::Vector(vals) => TransactionArgument::U8Vector(
+            vals.into_iter()
+                .map(|v| match v {
+                    MoveValue::U8(byte) => Ok(byte),
+                    _ => Err(format_err!("unsupported script argument: {:?}", v)),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        _ => bail!("unsupported script argument: {:?}", val),
+    };
+    Ok(ret)
 }
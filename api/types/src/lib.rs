@@ -27,7 +27,9 @@ pub use move_types::{
 };
 pub use response::{Response, X_DIEM_CHAIN_ID, X_DIEM_LEDGER_TIMESTAMP, X_DIEM_LEDGER_VERSION};
 pub use transaction::{
-    BlockMetadataTransaction, Event, GenesisTransaction, PendingTransaction, ScriptPayload,
-    Transaction, TransactionData, TransactionId, TransactionOnChainData, TransactionPayload,
-    UserTransaction, WriteSetChange, WriteSetPayload,
+    AccountPendingTransactions, BlockMetadataTransaction, Event, GenesisTransaction,
+    MempoolTransaction, PendingTransaction, ScriptPayload, SimulateTransactionRequest,
+    SubmitTransactionRequest, TimelineState, Transaction, TransactionData, TransactionId,
+    TransactionOnChainData, TransactionOutput, TransactionPayload, TransactionSignature,
+    UserTransaction, UserTransactionRequest, VersionedEvent, WriteSetChange, WriteSetPayload,
 };
@@ -26,6 +26,7 @@ use std::{
     convert::{From, Into, TryFrom, TryInto},
     fmt,
     result::Result,
+    str::FromStr,
 };
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -143,12 +144,39 @@ impl Serialize for HexEncodedBytes {
     }
 }
 
+impl<'de> Deserialize<'de> for HexEncodedBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+impl FromStr for HexEncodedBytes {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let hex = s
+            .strip_prefix("0x")
+            .ok_or_else(|| anyhow::format_err!("hex-encoded bytes must start with 0x: {}", s))?;
+        Ok(Self(hex::decode(hex)?))
+    }
+}
+
 impl From<Vec<u8>> for HexEncodedBytes {
     fn from(bytes: Vec<u8>) -> Self {
         Self(bytes)
     }
 }
 
+impl From<HexEncodedBytes> for Vec<u8> {
+    fn from(bytes: HexEncodedBytes) -> Self {
+        bytes.0
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct MoveStructValue(BTreeMap<Identifier, MoveValue>);
 
@@ -225,7 +253,7 @@ impl Serialize for MoveValue {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MoveStructTag {
     pub address: Address,
     pub module: Identifier,
@@ -244,6 +272,23 @@ impl From<StructTag> for MoveStructTag {
     }
 }
 
+impl TryFrom<MoveStructTag> for StructTag {
+    type Error = anyhow::Error;
+
+    fn try_from(tag: MoveStructTag) -> anyhow::Result<Self> {
+        Ok(Self {
+            address: tag.address.into(),
+            module: tag.module,
+            name: tag.name,
+            type_params: tag
+                .generic_type_params
+                .into_iter()
+                .map(TypeTag::try_from)
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
 impl<T: Bytecode> From<(&T, &StructHandleIndex, &Vec<SignatureToken>)> for MoveStructTag {
     fn from((m, shi, type_params): (&T, &StructHandleIndex, &Vec<SignatureToken>)) -> Self {
         let s_handle = m.struct_handle_at(*shi);
@@ -257,7 +302,7 @@ impl<T: Bytecode> From<(&T, &StructHandleIndex, &Vec<SignatureToken>)> for MoveS
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum MoveType {
     Bool,
@@ -289,6 +334,27 @@ impl From<TypeTag> for MoveType {
     }
 }
 
+impl TryFrom<MoveType> for TypeTag {
+    type Error = anyhow::Error;
+
+    fn try_from(tag: MoveType) -> anyhow::Result<Self> {
+        let ret = match tag {
+            MoveType::Bool => TypeTag::Bool,
+            MoveType::U8 => TypeTag::U8,
+            MoveType::U64 => TypeTag::U64,
+            MoveType::U128 => TypeTag::U128,
+            MoveType::Address => TypeTag::Address,
+            MoveType::Signer => TypeTag::Signer,
+            MoveType::Vector { items } => TypeTag::Vector(Box::new((*items).try_into()?)),
+            MoveType::Struct(v) => TypeTag::Struct(v.try_into()?),
+            MoveType::GenericTypeParam { index: _ } | MoveType::Reference { .. } => {
+                anyhow::bail!("invalid type tag: {:?}", tag)
+            }
+        };
+        Ok(ret)
+    }
+}
+
 impl<T: Bytecode> From<(&T, &SignatureToken)> for MoveType {
     fn from((m, token): (&T, &SignatureToken)) -> Self {
         match token {
@@ -352,7 +418,7 @@ impl From<CompiledModule> for MoveModule {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MoveModuleId {
     pub address: Address,
     pub name: Identifier,
@@ -368,6 +434,12 @@ impl From<ModuleId> for MoveModuleId {
     }
 }
 
+impl From<MoveModuleId> for ModuleId {
+    fn from(id: MoveModuleId) -> Self {
+        ModuleId::new(id.address.into(), id.name)
+    }
+}
+
 impl fmt::Display for MoveModuleId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}::{}", self.address, self.name)
@@ -589,6 +661,12 @@ pub struct MoveModuleBytecode {
     abi: MoveModule,
 }
 
+impl MoveModuleBytecode {
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode.0
+    }
+}
+
 impl TryFrom<&Module> for MoveModuleBytecode {
     type Error = anyhow::Error;
     fn try_from(m: &Module) -> anyhow::Result<Self> {
@@ -609,12 +687,33 @@ impl TryFrom<&Vec<u8>> for MoveModuleBytecode {
     }
 }
 
+/// Only the bytecode is read when deserializing, the ABI is always derived from it.
+impl<'de> Deserialize<'de> for MoveModuleBytecode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytecode = BytecodeData::deserialize(deserializer)?.bytecode;
+        Self::try_from(&bytecode.0).map_err(D::Error::custom)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct MoveScriptBytecode {
     bytecode: HexEncodedBytes,
     abi: MoveFunction,
 }
 
+impl MoveScriptBytecode {
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode.0
+    }
+
+    pub fn abi(&self) -> &MoveFunction {
+        &self.abi
+    }
+}
+
 impl TryFrom<&[u8]> for MoveScriptBytecode {
     type Error = anyhow::Error;
     fn try_from(bytes: &[u8]) -> anyhow::Result<Self> {
@@ -625,9 +724,25 @@ impl TryFrom<&[u8]> for MoveScriptBytecode {
     }
 }
 
+/// Only the bytecode is read when deserializing, the ABI is always derived from it.
+impl<'de> Deserialize<'de> for MoveScriptBytecode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytecode = BytecodeData::deserialize(deserializer)?.bytecode;
+        Self::try_from(bytecode.0.as_slice()).map_err(D::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+struct BytecodeData {
+    bytecode: HexEncodedBytes,
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{MoveResource, MoveType, U128, U64};
+    use crate::{HexEncodedBytes, MoveResource, MoveType, U128, U64};
 
     use diem_types::account_address::AccountAddress;
     use move_binary_format::file_format::AbilitySet;
@@ -638,7 +753,7 @@ mod tests {
     use resource_viewer::{AnnotatedMoveStruct, AnnotatedMoveValue};
 
     use serde_json::{json, to_value, Value};
-    use std::boxed::Box;
+    use std::{boxed::Box, convert::TryFrom};
 
     #[test]
     fn test_serialize_move_type_tag() {
@@ -819,6 +934,26 @@ mod tests {
         assert_eq!(u128::from(data), u128::MAX);
     }
 
+    #[test]
+    fn test_serialize_deserialize_hex_encoded_bytes() {
+        let val = to_value(&HexEncodedBytes::from(vec![0, 1, 255])).unwrap();
+        assert_eq!(val, json!("0x0001ff"));
+
+        let data: HexEncodedBytes = serde_json::from_value(val).unwrap();
+        assert_eq!(Vec::<u8>::from(data), vec![0, 1, 255]);
+        assert!(serde_json::from_value::<HexEncodedBytes>(json!("0001ff")).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_move_type_into_type_tag() {
+        let tag = TypeTag::Vector(Box::new(TypeTag::Struct(create_nested_struct())));
+        let typ: MoveType =
+            serde_json::from_value(to_value(MoveType::from(tag.clone())).unwrap()).unwrap();
+        assert_eq!(TypeTag::try_from(typ).unwrap(), tag);
+
+        assert!(TypeTag::try_from(MoveType::GenericTypeParam { index: 0 }).is_err());
+    }
+
     fn create_nested_struct() -> StructTag {
         let account = create_generic_type_struct();
         StructTag {
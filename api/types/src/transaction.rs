@@ -24,7 +24,7 @@ use diem_types::{
 use move_core_types::identifier::Identifier;
 use resource_viewer::AnnotatedMoveValue;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
     boxed::Box,
     convert::{From, Into, TryFrom, TryInto},
@@ -176,6 +176,15 @@ impl<T: TransactionInfoTrait> From<(u64, &BlockMetadata, &T)> for Transaction {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TransactionOutput {
+    pub vm_status: String,
+    pub success: bool,
+    pub gas_used: U64,
+    pub events: Vec<Event>,
+    pub changes: Vec<WriteSetChange>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct TransactionInfo {
     pub version: U64,
@@ -212,6 +221,57 @@ pub struct PendingTransaction {
     pub signature: TransactionSignature,
 }
 
+/// The transactions of an account pending in mempool.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AccountPendingTransactions {
+    pub next_sequence_number: Option<U64>,
+    pub transactions: Vec<MempoolTransaction>,
+}
+
+/// A pending transaction along with its state in mempool.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MempoolTransaction {
+    pub timeline_state: TimelineState,
+    pub parked: bool,
+    pub transaction: Transaction,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineState {
+    Ready { timeline_id: U64 },
+    NotReady,
+    NonQualified,
+}
+
+/// A user transaction that is not signed yet, as submitted by a client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserTransactionRequest {
+    pub sender: Address,
+    pub sequence_number: U64,
+    pub max_gas_amount: U64,
+    pub gas_unit_price: U64,
+    pub gas_currency_code: String,
+    pub expiration_timestamp_secs: U64,
+    pub payload: TransactionPayload,
+}
+
+/// A signed user transaction, as submitted by a client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubmitTransactionRequest {
+    #[serde(flatten)]
+    pub user_transaction_request: UserTransactionRequest,
+    pub signature: TransactionSignature,
+}
+
+/// A user transaction to simulate, which doesn't need to be signed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimulateTransactionRequest {
+    #[serde(flatten)]
+    pub user_transaction_request: UserTransactionRequest,
+    pub signature: Option<TransactionSignature>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct UserTransaction {
     #[serde(flatten)]
@@ -268,31 +328,59 @@ impl From<(&ContractEvent, AnnotatedMoveValue)> for Event {
     }
 }
 
+/// An event along with the version of the transaction that emitted it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct VersionedEvent {
+    pub version: U64,
+    pub key: EventKey,
+    pub sequence_number: U64,
+    #[serde(rename = "type")]
+    pub typ: MoveType,
+    pub data: MoveValue,
+}
+
+impl From<(u64, &ContractEvent, AnnotatedMoveValue)> for VersionedEvent {
+    fn from((version, event, data): (u64, &ContractEvent, AnnotatedMoveValue)) -> Self {
+        match event {
+            ContractEvent::V0(v0) => Self {
+                version: version.into(),
+                key: (*v0.key()).into(),
+                sequence_number: v0.sequence_number().into(),
+                typ: v0.type_tag().clone().into(),
+                data: data.into(),
+            },
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum GenesisPayload {
     WriteSetPayload(WriteSetPayload),
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+/// Arguments are kept in their JSON form, as converting them from and to Move values requires the
+/// parameter types of the function being called.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TransactionPayload {
     ScriptFunctionPayload {
         module: MoveModuleId,
         function: Identifier,
         type_arguments: Vec<MoveType>,
-        arguments: Vec<MoveValue>,
+        arguments: Vec<serde_json::Value>,
     },
     ScriptPayload(ScriptPayload),
     ModulePayload(MoveModuleBytecode),
+    #[serde(skip_deserializing)]
     WriteSetPayload(WriteSetPayload),
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ScriptPayload {
     pub code: MoveScriptBytecode,
     pub type_arguments: Vec<MoveType>,
-    pub arguments: Vec<MoveValue>,
+    pub arguments: Vec<serde_json::Value>,
 }
 
 impl TryFrom<&Script> for ScriptPayload {
@@ -306,7 +394,11 @@ impl TryFrom<&Script> for ScriptPayload {
                 .iter()
                 .map(|arg| arg.clone().into())
                 .collect(),
-            arguments: script.args().iter().map(|arg| arg.clone().into()).collect(),
+            arguments: script
+                .args()
+                .iter()
+                .map(|arg| serde_json::to_value(MoveValue::from(arg.clone())))
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -345,7 +437,7 @@ pub enum WriteSetChange {
     },
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TransactionSignature {
     Ed25519Signature(Ed25519Signature),
@@ -353,27 +445,27 @@ pub enum TransactionSignature {
     MultiAgentSignature(MultiAgentSignature),
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Ed25519Signature {
     public_key: HexEncodedBytes,
     signature: HexEncodedBytes,
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MultiEd25519Signature {
     signatures: Vec<Ed25519Signature>,
     threshold: u8,
     bitmap: HexEncodedBytes,
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AccountSignature {
     Ed25519Signature(Ed25519Signature),
     MultiEd25519Signature(MultiEd25519Signature),
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MultiAgentSignature {
     sender: AccountSignature,
     secondary_signer_addresses: Vec<Address>,
@@ -461,6 +553,83 @@ impl
     }
 }
 
+impl TryFrom<Ed25519Signature> for (Ed25519PublicKey, ed25519::Ed25519Signature) {
+    type Error = anyhow::Error;
+
+    fn try_from(sig: Ed25519Signature) -> anyhow::Result<Self> {
+        let public_key: Vec<u8> = sig.public_key.into();
+        let signature: Vec<u8> = sig.signature.into();
+        Ok((
+            Ed25519PublicKey::try_from(public_key.as_slice())?,
+            ed25519::Ed25519Signature::try_from(signature.as_slice())?,
+        ))
+    }
+}
+
+/// The public keys are the ones listed along with the signatures, so every key of the multi-key
+/// has to be listed for the derived authentication key to match the sender's.
+impl TryFrom<MultiEd25519Signature>
+    for (MultiEd25519PublicKey, multi_ed25519::MultiEd25519Signature)
+{
+    type Error = anyhow::Error;
+
+    fn try_from(sig: MultiEd25519Signature) -> anyhow::Result<Self> {
+        let mut public_keys = vec![];
+        let mut signature_bytes = vec![];
+        for s in sig.signatures {
+            let (public_key, signature) = s.try_into()?;
+            public_keys.push(public_key);
+            signature_bytes.extend(signature.to_bytes().iter());
+        }
+        signature_bytes.extend(Vec::<u8>::from(sig.bitmap));
+        Ok((
+            MultiEd25519PublicKey::new(public_keys, sig.threshold)?,
+            multi_ed25519::MultiEd25519Signature::try_from(signature_bytes.as_slice())?,
+        ))
+    }
+}
+
+impl TryFrom<AccountSignature> for AccountAuthenticator {
+    type Error = anyhow::Error;
+
+    fn try_from(sig: AccountSignature) -> anyhow::Result<Self> {
+        Ok(match sig {
+            AccountSignature::Ed25519Signature(sig) => {
+                let (public_key, signature) = sig.try_into()?;
+                Self::ed25519(public_key, signature)
+            }
+            AccountSignature::MultiEd25519Signature(sig) => {
+                let (public_key, signature) = sig.try_into()?;
+                Self::multi_ed25519(public_key, signature)
+            }
+        })
+    }
+}
+
+impl TryFrom<MultiAgentSignature>
+    for (
+        AccountAuthenticator,
+        Vec<AccountAddress>,
+        Vec<AccountAuthenticator>,
+    )
+{
+    type Error = anyhow::Error;
+
+    fn try_from(sig: MultiAgentSignature) -> anyhow::Result<Self> {
+        Ok((
+            sig.sender.try_into()?,
+            sig.secondary_signer_addresses
+                .into_iter()
+                .map(|address| address.into())
+                .collect(),
+            sig.secondary_signers
+                .into_iter()
+                .map(AccountAuthenticator::try_from)
+                .collect::<anyhow::Result<_>>()?,
+        ))
+    }
+}
+
 impl From<TransactionAuthenticator> for TransactionSignature {
     fn from(auth: TransactionAuthenticator) -> Self {
         use TransactionAuthenticator::*;
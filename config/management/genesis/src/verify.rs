@@ -218,8 +218,11 @@ fn compute_genesis(
         db_path,
         false,
         None,
+        None,
         RocksdbConfig::default(),
-        true, /* account_count_migration */
+        true,  /* account_count_migration */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
     )
     .map_err(|e| Error::UnexpectedError(e.to_string()))?;
     let db_rw = DbReaderWriter::new(diemdb);
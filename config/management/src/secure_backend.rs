@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Error;
-use diem_config::config::{self, GitHubConfig, OnDiskStorageConfig, Token, VaultConfig};
+use diem_config::config::{
+    self, EncryptedOnDiskStorageConfig, EncryptionSecret, GitHubConfig, OnDiskStorageConfig, Token,
+    VaultConfig,
+};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
@@ -13,6 +16,7 @@ use structopt::StructOpt;
 
 pub const BACKEND: &str = "backend";
 pub const DISK: &str = "disk";
+pub const ENCRYPTED_DISK: &str = "encrypted_disk";
 pub const GITHUB: &str = "github";
 pub const MEMORY: &str = "memory";
 pub const VAULT: &str = "vault";
@@ -80,6 +84,23 @@ impl TryInto<config::SecureBackend> for SecureBackend {
                 config.namespace = self.parameters.remove("namespace");
                 config::SecureBackend::OnDiskStorage(config)
             }
+            ENCRYPTED_DISK => {
+                let path = self
+                    .parameters
+                    .remove("path")
+                    .ok_or_else(|| Error::BackendParsingError("missing path".into()))?;
+                let secret = self
+                    .parameters
+                    .remove("secret")
+                    .ok_or_else(|| Error::BackendParsingError("missing secret".into()))?;
+                let mut config = EncryptedOnDiskStorageConfig::new(
+                    PathBuf::from(path),
+                    EncryptionSecret::FromDisk(PathBuf::from(secret)),
+                );
+                config.set_data_dir(PathBuf::from(""));
+                config.namespace = self.parameters.remove("namespace");
+                config::SecureBackend::EncryptedOnDiskStorage(config)
+            }
             GITHUB => {
                 let repository_owner = self
                     .parameters
@@ -159,6 +180,8 @@ pair: "k0=v0;k1=v1;...".  The current supported formats are:
         an optional namespace: "namespace=NAMESPACE"
     InMemory: "backend=memory"
     OnDisk: "backend=disk;path=LOCAL_PATH"
+    EncryptedOnDisk: "backend=encrypted_disk;path=LOCAL_PATH;secret=PATH_TO_KEY_FILE"
+        an optional namespace: "namespace=NAMESPACE"
                 "#)
             )]
             pub $field_name: Option<SecureBackend>,
@@ -212,6 +235,28 @@ mod tests {
         assert!(storage(disk).is_err());
     }
 
+    #[test]
+    fn test_encrypted_disk() {
+        let path = diem_temppath::TempPath::new();
+        let secret = diem_temppath::TempPath::new();
+        secret.create_as_file().unwrap();
+        let mut file = File::create(secret.path()).unwrap();
+        file.write_all(b"disk_secret").unwrap();
+
+        let disk = format!(
+            "backend=encrypted_disk;path={};secret={}",
+            path.path().to_str().unwrap(),
+            secret.path().to_str().unwrap()
+        );
+        storage(&disk).unwrap();
+
+        let disk = format!(
+            "backend=encrypted_disk;path={}",
+            path.path().to_str().unwrap()
+        );
+        assert!(storage(&disk).is_err());
+    }
+
     #[test]
     fn test_github() {
         let path = diem_temppath::TempPath::new();
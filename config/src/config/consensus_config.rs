@@ -29,6 +29,10 @@ pub struct ConsensusConfig {
     pub decoupled_execution: bool,
     pub channel_size: usize,
     pub back_pressure_limit: u64,
+    pub quorum_store: QuorumStoreConfig,
+    // File the incoming messages, local timeouts and execution results of consensus are appended
+    // to, so that they can be replayed when debugging (nothing is recorded if unset)
+    pub record_path: Option<PathBuf>,
 }
 
 impl Default for ConsensusConfig {
@@ -50,6 +54,8 @@ impl Default for ConsensusConfig {
             decoupled_execution: false, // by default, we turn of the decoupling execution feature
             channel_size: 30,           // hard-coded
             back_pressure_limit: 10,
+            quorum_store: QuorumStoreConfig::default(),
+            record_path: None,
         }
     }
 }
@@ -69,15 +75,73 @@ pub enum ConsensusProposerType {
     RotatingProposer,
     // Committed history based proposer election
     LeaderReputation(LeaderReputationConfig),
+    // Committed history based proposer election, also penalizing the proposers which failed to
+    // get their rounds committed
+    ProposerAndVoterReputation(ProposerAndVoterConfig),
     // Pre-specified proposers for each round,
     // or default proposer if round proposer not
     // specified
     RoundProposer(HashMap<Round, AccountAddress>),
 }
 
+impl ConsensusProposerType {
+    /// Whether the proposals record the proposers of the skipped rounds as failed authors: only
+    /// the proposer and voter reputation relies on them.
+    pub fn records_failed_authors(&self) -> bool {
+        matches!(self, ConsensusProposerType::ProposerAndVoterReputation(_))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct LeaderReputationConfig {
     pub active_weights: u64,
     pub inactive_weights: u64,
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProposerAndVoterConfig {
+    // Weight of the validators which proposed or voted in the window
+    pub active_weights: u64,
+    // Weight of the validators which neither proposed nor voted in the window
+    pub inactive_weights: u64,
+    // Weight of the validators whose failed proposals exceed the threshold in the window
+    pub failed_weights: u64,
+    // Percentage of failed proposals among the proposals of a validator above which it's
+    // weighted as failed
+    pub failure_threshold_percent: u32,
+}
+
+/// Configuration of the quorum store, which disseminates the transactions in batches ahead of the
+/// proposals, so that the proposals only carry the proofs that the batches are stored.
+/// It must be enabled on all the validators or on none of them.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuorumStoreConfig {
+    pub enabled: bool,
+    // Interval at which a batch is pulled from mempool and broadcast (in milliseconds)
+    pub batch_generation_interval_ms: u64,
+    // Max number of transactions in a batch
+    pub max_batch_size: u64,
+    // Max number of own batches which are neither committed nor expired, no batch is generated
+    // beyond it
+    pub max_pending_batches: usize,
+    // Time after which a batch expires and can no longer be proposed (in milliseconds)
+    pub batch_expiry_ms: u64,
+    // Timeout for fetching a missing batch from a validator storing it (in milliseconds)
+    pub batch_request_timeout_ms: u64,
+}
+
+impl Default for QuorumStoreConfig {
+    fn default() -> QuorumStoreConfig {
+        QuorumStoreConfig {
+            enabled: false,
+            batch_generation_interval_ms: 100,
+            max_batch_size: 500,
+            max_pending_batches: 10,
+            batch_expiry_ms: 60_000,
+            batch_request_timeout_ms: 1000,
+        }
+    }
+}
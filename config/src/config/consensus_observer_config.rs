@@ -0,0 +1,43 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusObserverConfig {
+    // Whether the node subscribes to the blocks ordered and committed by its upstream peers, and
+    // executes them locally instead of waiting for state sync (only supported by full nodes)
+    pub observer_enabled: bool,
+    // Whether the node streams the blocks it orders and commits (or, for an observer, receives)
+    // to its subscribed downstream peers
+    pub publisher_enabled: bool,
+    // The maximum number of downstream peers a publisher streams blocks to
+    pub max_num_subscribers: usize,
+    // The maximum number of ordered blocks an observer buffers while they wait to be executed or
+    // committed. Blocks beyond that are dropped and the observer falls back to state sync.
+    pub max_num_pending_blocks: usize,
+    // The interval at which the observer checks its subscription and progress (in milliseconds)
+    pub progress_check_interval_ms: u64,
+    // The maximum time an observer waits for a message from the peer it's subscribed to before
+    // subscribing to another peer (in milliseconds)
+    pub subscription_timeout_ms: u64,
+    // The maximum time state sync waits for the observer to commit blocks (or request a sync)
+    // before syncing proactively again, e.g., while the observer has no peer to subscribe to
+    // (in milliseconds)
+    pub progress_timeout_ms: u64,
+}
+
+impl Default for ConsensusObserverConfig {
+    fn default() -> Self {
+        Self {
+            observer_enabled: false,
+            publisher_enabled: false,
+            max_num_subscribers: 20,
+            max_num_pending_blocks: 100,
+            progress_check_interval_ms: 1_000,
+            subscription_timeout_ms: 10_000,
+            progress_timeout_ms: 30_000,
+        }
+    }
+}
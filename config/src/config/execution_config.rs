@@ -23,6 +23,7 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    pub execution_mode: TransactionExecutionMode,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +41,8 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, execution_mode: {:?} }}",
+            self.sign_vote_proposal, self.service, self.backend, self.execution_mode
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +58,7 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            execution_mode: TransactionExecutionMode::Sequential,
         }
     }
 }
@@ -91,9 +93,7 @@ impl ExecutionConfig {
     }
 
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
-        if let SecureBackend::OnDiskStorage(backend) = &mut self.backend {
-            backend.set_data_dir(data_dir);
-        }
+        self.backend.set_data_dir(data_dir);
     }
 }
 
@@ -112,6 +112,28 @@ pub enum ExecutionCorrectnessService {
     Thread,
 }
 
+/// Defines how the transactions of a block are executed by the VM
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum TransactionExecutionMode {
+    /// Transactions are executed one after the other.
+    Sequential,
+    /// Transactions are executed optimistically in parallel on `concurrency_level` threads, and
+    /// re-executed when they conflict with a transaction earlier in the block. The results are
+    /// identical to the sequential execution.
+    Optimistic { concurrency_level: usize },
+}
+
+impl TransactionExecutionMode {
+    /// The number of threads used to execute a block.
+    pub fn concurrency_level(&self) -> usize {
+        match self {
+            TransactionExecutionMode::Sequential => 1,
+            TransactionExecutionMode::Optimistic { concurrency_level } => *concurrency_level,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct RemoteExecutionService {
@@ -155,6 +177,31 @@ mod test {
         assert_eq!(config.genesis, Some(fake_genesis));
     }
 
+    #[test]
+    fn test_execution_mode() {
+        let config: ExecutionConfig = serde_yaml::from_str(
+            r#"
+            execution_mode:
+                type: optimistic
+                concurrency_level: 8
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.execution_mode,
+            TransactionExecutionMode::Optimistic {
+                concurrency_level: 8
+            }
+        );
+        assert_eq!(config.execution_mode.concurrency_level(), 8);
+        assert_eq!(
+            ExecutionConfig::default()
+                .execution_mode
+                .concurrency_level(),
+            1
+        );
+    }
+
     fn generate_config() -> (ExecutionConfig, TempPath) {
         let temp_dir = TempPath::new();
         temp_dir.create_as_dir().expect("error creating tempdir");
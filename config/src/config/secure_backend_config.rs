@@ -3,11 +3,12 @@
 
 use crate::config::Error;
 use diem_secure_storage::{
-    GitHubStorage, InMemoryStorage, Namespaced, OnDiskStorage, Storage, VaultStorage,
+    EncryptedOnDiskStorage, GitHubStorage, InMemoryStorage, Namespaced, OnDiskStorage, Storage,
+    VaultStorage,
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    fs::File,
+    fs::{self, File},
     io::Read,
     path::{Path, PathBuf},
 };
@@ -19,6 +20,7 @@ pub enum SecureBackend {
     InMemoryStorage,
     Vault(VaultConfig),
     OnDiskStorage(OnDiskStorageConfig),
+    EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig),
 }
 
 impl SecureBackend {
@@ -26,18 +28,34 @@ impl SecureBackend {
         match self {
             SecureBackend::GitHub(GitHubConfig { namespace, .. })
             | SecureBackend::Vault(VaultConfig { namespace, .. })
-            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. }) => {
-                namespace.as_deref()
-            }
+            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. })
+            | SecureBackend::EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig {
+                namespace,
+                ..
+            }) => namespace.as_deref(),
             SecureBackend::InMemoryStorage => None,
         }
     }
 
+    /// Sets the directory relative on-disk storage paths are resolved from.
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        match self {
+            SecureBackend::OnDiskStorage(config) => config.set_data_dir(data_dir),
+            SecureBackend::EncryptedOnDiskStorage(config) => config.set_data_dir(data_dir),
+            SecureBackend::GitHub(_) | SecureBackend::InMemoryStorage | SecureBackend::Vault(_) => {
+            }
+        }
+    }
+
     pub fn clear_namespace(&mut self) {
         match self {
             SecureBackend::GitHub(GitHubConfig { namespace, .. })
             | SecureBackend::Vault(VaultConfig { namespace, .. })
-            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. }) => {
+            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. })
+            | SecureBackend::EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig {
+                namespace,
+                ..
+            }) => {
                 *namespace = None;
             }
             SecureBackend::InMemoryStorage => {}
@@ -110,6 +128,42 @@ pub struct OnDiskStorageConfig {
     data_dir: PathBuf,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedOnDiskStorageConfig {
+    // Required path for on disk storage
+    pub path: PathBuf,
+    /// The secret the encryption key of the storage is derived from
+    pub secret: EncryptionSecret,
+    /// A namespace is an optional portion of the path to a key stored within
+    /// EncryptedOnDiskStorage. For example, a key, S, without a namespace would be available in S,
+    /// with a namespace, N, it would be in N/S.
+    pub namespace: Option<String>,
+    #[serde(skip)]
+    data_dir: PathBuf,
+}
+
+/// The secret of an encrypted storage can either be a passphrase directly within this config or
+/// the contents of a key file stored somewhere on disk.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionSecret {
+    FromConfig(String),
+    /// This is an absolute path and not relative to data_dir
+    FromDisk(PathBuf),
+}
+
+impl EncryptionSecret {
+    pub fn read_secret(&self) -> Result<Vec<u8>, Error> {
+        match self {
+            EncryptionSecret::FromDisk(path) => {
+                fs::read(path).map_err(|e| Error::IO(path.to_str().unwrap().to_string(), e))
+            }
+            EncryptionSecret::FromConfig(passphrase) => Ok(passphrase.as_bytes().to_vec()),
+        }
+    }
+}
+
 /// Tokens can either be directly within this config or stored somewhere on disk.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -164,6 +218,29 @@ impl OnDiskStorageConfig {
     }
 }
 
+impl EncryptedOnDiskStorageConfig {
+    pub fn new(path: PathBuf, secret: EncryptionSecret) -> Self {
+        Self {
+            path,
+            secret,
+            namespace: None,
+            data_dir: PathBuf::from("/opt/diem/data"),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        if self.path.is_relative() {
+            self.data_dir.join(&self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+}
+
 fn read_file(path: &Path) -> Result<String, Error> {
     let mut file =
         File::open(path).map_err(|e| Error::IO(path.to_str().unwrap().to_string(), e))?;
@@ -202,6 +279,18 @@ impl From<&SecureBackend> for Storage {
                     storage
                 }
             }
+            SecureBackend::EncryptedOnDiskStorage(config) => {
+                let secret = config
+                    .secret
+                    .read_secret()
+                    .expect("Unable to read encryption secret");
+                let storage = Storage::from(EncryptedOnDiskStorage::new(config.path(), &secret));
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            }
             SecureBackend::Vault(config) => {
                 let storage = Storage::from(VaultStorage::new(
                     config.server.clone(),
@@ -320,6 +409,44 @@ vault:
         serde_yaml::to_string(&from_disk).unwrap();
     }
 
+    #[test]
+    fn test_encrypted_on_disk_parsing() {
+        let mut config = EncryptedOnDiskStorageConfig::new(
+            PathBuf::from("secure_storage.json"),
+            EncryptionSecret::FromDisk(PathBuf::from("/secret")),
+        );
+        config.namespace = Some("test".to_string());
+        let from_config = SecureBackend::EncryptedOnDiskStorage(config);
+
+        let text_from_config = r#"
+type: "encrypted_on_disk_storage"
+path: "secure_storage.json"
+secret:
+    from_disk: "/secret"
+namespace: "test"
+        "#;
+
+        let mut de_from_config: SecureBackend = serde_yaml::from_str(text_from_config).unwrap();
+        de_from_config.set_data_dir(PathBuf::from("/opt/diem/data"));
+        assert_eq!(de_from_config, from_config);
+        // Just assert that it can be serialized, not about to do string comparison
+        serde_yaml::to_string(&from_config).unwrap();
+    }
+
+    #[test]
+    fn test_encryption_secret_reading() {
+        let temppath = diem_temppath::TempPath::new();
+        temppath.create_as_file().unwrap();
+        let mut file = File::create(temppath.path()).unwrap();
+        file.write_all(&[0, 1, 2, 255]).unwrap();
+
+        let disk = EncryptionSecret::FromDisk(temppath.path().to_path_buf());
+        assert_eq!(vec![0, 1, 2, 255], disk.read_secret().unwrap());
+
+        let config = EncryptionSecret::FromConfig("passphrase".to_string());
+        assert_eq!(b"passphrase".to_vec(), config.read_secret().unwrap());
+    }
+
     #[test]
     fn test_token_reading() {
         let temppath = diem_temppath::TempPath::new();
@@ -27,6 +27,8 @@ pub struct StateSyncConfig {
     pub sync_request_timeout_ms: u64,
     // interval used for checking state synchronization progress
     pub tick_interval_ms: u64,
+    // The configuration of the state sync (v2) driver
+    pub state_sync_driver: StateSyncDriverConfig,
 }
 
 impl Default for StateSyncConfig {
@@ -41,6 +43,58 @@ impl Default for StateSyncConfig {
             multicast_timeout_ms: 30_000,
             sync_request_timeout_ms: 60_000,
             tick_interval_ms: 100,
+            state_sync_driver: StateSyncDriverConfig::default(),
+        }
+    }
+}
+
+/// The bootstrapping mode used by the state sync (v2) driver to catch up
+/// to the latest epoch.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrappingMode {
+    /// Downloads the account states at the latest verified epoch ending
+    /// version (i.e., a state snapshot), and syncs from there
+    DownloadLatestAccountStates,
+    /// Executes all transactions from the latest synced version up to the
+    /// latest verified epoch ending version
+    ExecuteTransactionsFromGenesis,
+}
+
+/// The mode used by the state sync (v2) driver to keep up with the
+/// blockchain once it has been bootstrapped.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinuousSyncingMode {
+    /// Applies transaction outputs (without executing the transactions)
+    ApplyTransactionOutputs,
+    /// Executes all transactions
+    ExecuteTransactions,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSyncDriverConfig {
+    // Whether the node runs the state sync (v2) driver instead of state sync v1
+    pub enable_state_sync_v2: bool,
+    // The mode by which to bootstrap the node
+    pub bootstrapping_mode: BootstrappingMode,
+    // The mode by which to keep syncing once bootstrapped
+    pub continuous_syncing_mode: ContinuousSyncingMode,
+    // The maximum time to wait for a data notification on a stream (in milliseconds)
+    pub max_stream_wait_time_ms: u64,
+    // The interval at which the driver checks its progress (in milliseconds)
+    pub progress_check_interval_ms: u64,
+}
+
+impl Default for StateSyncDriverConfig {
+    fn default() -> Self {
+        Self {
+            enable_state_sync_v2: false,
+            bootstrapping_mode: BootstrappingMode::ExecuteTransactionsFromGenesis,
+            continuous_syncing_mode: ContinuousSyncingMode::ExecuteTransactions,
+            max_stream_wait_time_ms: 5000,
+            progress_check_interval_ms: 100,
         }
     }
 }
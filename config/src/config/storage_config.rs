@@ -42,6 +42,9 @@ pub struct StorageConfig {
     /// None disables pruning. The windows is in number of versions, consider system tps
     /// (transaction per second) when calculating proper window.
     pub prune_window: Option<u64>,
+    /// The window, in number of versions, of transactions, events and write sets to keep. None
+    /// disables pruning of the ledger history.
+    pub ledger_prune_window: Option<u64>,
     #[serde(skip)]
     data_dir: PathBuf,
     /// Read, Write, Connect timeout for network operations in milliseconds
@@ -55,6 +58,15 @@ pub struct StorageConfig {
     /// wiped and re-synced.
     #[serde(default)]
     pub account_count_migration: bool,
+    /// If enabled, the state is also stored by resource, in a Merkle tree where each resource or
+    /// module is a leaf of its own, so that it can be read (and proven) individually.
+    /// An existing DB without such a tree needs to be restored from a backup (with
+    /// `--resource-granular-state`) before this is enabled.
+    #[serde(default)]
+    pub resource_granular_state: bool,
+    /// The maximum size in bytes of the account states (and their proofs) cached in memory, so
+    /// that hot accounts can be read without walking the state Merkle tree. 0 disables the cache.
+    pub state_value_cache_size: usize,
 }
 
 impl Default for StorageConfig {
@@ -71,11 +83,16 @@ impl Default for StorageConfig {
             // conservatively safe minimal prune window. It'll take a few Gigabytes of disk space
             // depending on the size of an average account blob.
             prune_window: Some(1_000_000),
+            // Nodes keep the full ledger history by default, as it is served to clients and to
+            // other nodes syncing from them.
+            ledger_prune_window: None,
             data_dir: PathBuf::from("/opt/diem/data"),
             // Default read/write/connection timeout, in milliseconds
             timeout_ms: 30_000,
             rocksdb_config: RocksdbConfig::default(),
             account_count_migration: false,
+            resource_granular_state: false,
+            state_value_cache_size: 64 * 1024 * 1024,
         }
     }
 }
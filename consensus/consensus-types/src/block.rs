@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_data::{BlockData, BlockType},
+    block_data::{BlockData, BlockType, MAX_FAILED_AUTHORS_TO_STORE},
     common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
@@ -15,7 +15,7 @@ use diem_types::{
     block_metadata::BlockMetadata,
     epoch_state::EpochState,
     ledger_info::LedgerInfo,
-    transaction::{Transaction, Version},
+    transaction::{SignedTransaction, Transaction, Version},
     validator_signer::ValidatorSigner,
     validator_verifier::ValidatorVerifier,
 };
@@ -191,6 +191,7 @@ impl Block {
         let block_data = BlockData::new_proposal(
             payload,
             validator_signer.author(),
+            None,
             round,
             timestamp_usecs,
             quorum_cert,
@@ -224,12 +225,15 @@ impl Block {
         match self.block_data.block_type() {
             BlockType::Genesis => bail!("We should not accept genesis from others"),
             BlockType::NilBlock => self.quorum_cert().verify(validator),
-            BlockType::Proposal { author, .. } => {
+            BlockType::Proposal {
+                payload, author, ..
+            } => {
                 let signature = self
                     .signature
                     .as_ref()
                     .ok_or_else(|| format_err!("Missing signature in Proposal"))?;
                 validator.verify(*author, &self.block_data, signature)?;
+                payload.verify(validator)?;
                 self.quorum_cert().verify(validator)
             }
         }
@@ -257,6 +261,38 @@ impl Block {
                 "Reconfiguration suffix should not carry payload"
             );
         }
+        if let Some(failed_authors) = self.block_data.failed_authors() {
+            ensure!(
+                failed_authors.len() <= MAX_FAILED_AUTHORS_TO_STORE,
+                "Block has {} failed authors, more than the maximum {}",
+                failed_authors.len(),
+                MAX_FAILED_AUTHORS_TO_STORE
+            );
+            let mut previous_round = parent.round();
+            for (round, author) in failed_authors {
+                ensure!(
+                    *round > previous_round && *round < self.round(),
+                    "Failed author {} of round {} is not in a skipped round, ordered by round",
+                    author,
+                    round
+                );
+                previous_round = *round;
+            }
+        }
+        if let Some(Payload::InQuorumStore(proofs)) = self.payload() {
+            for proof in proofs {
+                ensure!(
+                    proof.epoch() == self.epoch(),
+                    "Proof of store {} is not from the epoch of the block",
+                    proof
+                );
+                ensure!(
+                    proof.expiration() > self.timestamp_usecs(),
+                    "Proof of store {} has expired",
+                    proof
+                );
+            }
+        }
         if self.is_nil_block() || parent.has_reconfiguration() {
             ensure!(
                 self.timestamp_usecs() == parent.timestamp_usecs(),
@@ -289,15 +325,15 @@ impl Block {
         Ok(())
     }
 
-    pub fn transactions_to_execute(&self) -> Vec<Transaction> {
+    /// The transactions executed by the block, given the user transactions of its payload: the
+    /// payload only references the transactions disseminated through the quorum store, which are
+    /// resolved by the caller.
+    pub fn transactions_to_execute(
+        &self,
+        payload_txns: Vec<SignedTransaction>,
+    ) -> Vec<Transaction> {
         std::iter::once(Transaction::BlockMetadata(self.into()))
-            .chain(
-                self.payload()
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .cloned()
-                    .map(Transaction::UserTransaction),
-            )
+            .chain(payload_txns.into_iter().map(Transaction::UserTransaction))
             .collect()
     }
 }
@@ -343,6 +379,12 @@ impl From<&Block> for BlockMetadata {
                 .collect(),
             // For nil block, we use 0x0 which is convention for nil address in move.
             block.author().unwrap_or(AccountAddress::ZERO),
+            block
+                .block_data()
+                .failed_authors()
+                .map_or(vec![], |failed_authors| {
+                    failed_authors.iter().map(|(_, author)| *author).collect()
+                }),
         )
     }
 }
@@ -3,6 +3,7 @@
 
 use crate::{
     common::{Author, Payload, Round},
+    proof_of_store::ProofOfStore,
     quorum_cert::QuorumCert,
     vote_data::VoteData,
 };
@@ -11,18 +12,34 @@ use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use diem_types::{
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::SignedTransaction,
 };
 use mirai_annotations::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
 
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+/// Max number of proposers of the skipped rounds recorded as failed authors in a proposal.
+/// All the validators must agree on it, as they reject the proposals whose failed authors differ
+/// from the ones they compute.
+pub const MAX_FAILED_AUTHORS_TO_STORE: usize = 10;
+
+/// The serialized form of a proposal depends on its payload: a proposal carrying its transactions
+/// is encoded the same way as before the quorum store existed, so blocks (and their ids) don't
+/// change while the quorum store is disabled. A proposal referencing quorum store batches is
+/// encoded as a variant of its own, which only validators with the quorum store can decode.
+/// Similarly, a proposal only carries failed authors (and is encoded as a variant of its own) when
+/// the proposer election relies on them.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BlockType {
     Proposal {
         /// T of the block (e.g. one or more transaction(s)
         payload: Payload,
         /// Author of the block that can be validated by the author's public key and the signature
         author: Author,
+        /// The valid proposers of the rounds skipped since the parent block, with their round:
+        /// they failed to get a block certified. Only recorded with the proposer and voter
+        /// reputation proposer election.
+        failed_authors: Option<Vec<(Round, Author)>>,
     },
     /// NIL blocks don't have authors or signatures: they're generated upon timeouts to fill in the
     /// gaps in the rounds.
@@ -34,6 +51,112 @@ pub enum BlockType {
     Genesis,
 }
 
+impl Serialize for BlockType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename = "BlockType")]
+        enum BlockTypeRef<'a> {
+            Proposal {
+                payload: &'a [SignedTransaction],
+                author: &'a Author,
+            },
+            NilBlock,
+            Genesis,
+            ProposalInQuorumStore {
+                proofs: &'a [ProofOfStore],
+                author: &'a Author,
+            },
+            ProposalWithFailedAuthors {
+                payload: &'a Payload,
+                author: &'a Author,
+                failed_authors: &'a [(Round, Author)],
+            },
+        }
+
+        match self {
+            BlockType::Proposal {
+                payload,
+                author,
+                failed_authors: Some(failed_authors),
+            } => BlockTypeRef::ProposalWithFailedAuthors {
+                payload,
+                author,
+                failed_authors,
+            },
+            BlockType::Proposal {
+                payload: Payload::DirectMempool(txns),
+                author,
+                failed_authors: None,
+            } => BlockTypeRef::Proposal {
+                payload: txns,
+                author,
+            },
+            BlockType::Proposal {
+                payload: Payload::InQuorumStore(proofs),
+                author,
+                failed_authors: None,
+            } => BlockTypeRef::ProposalInQuorumStore { proofs, author },
+            BlockType::NilBlock => BlockTypeRef::NilBlock,
+            BlockType::Genesis => BlockTypeRef::Genesis,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "BlockType")]
+        enum BlockTypeEncoding {
+            Proposal {
+                payload: Vec<SignedTransaction>,
+                author: Author,
+            },
+            NilBlock,
+            Genesis,
+            ProposalInQuorumStore {
+                proofs: Vec<ProofOfStore>,
+                author: Author,
+            },
+            ProposalWithFailedAuthors {
+                payload: Payload,
+                author: Author,
+                failed_authors: Vec<(Round, Author)>,
+            },
+        }
+
+        Ok(match BlockTypeEncoding::deserialize(deserializer)? {
+            BlockTypeEncoding::Proposal { payload, author } => BlockType::Proposal {
+                payload: Payload::DirectMempool(payload),
+                author,
+                failed_authors: None,
+            },
+            BlockTypeEncoding::ProposalInQuorumStore { proofs, author } => BlockType::Proposal {
+                payload: Payload::InQuorumStore(proofs),
+                author,
+                failed_authors: None,
+            },
+            BlockTypeEncoding::ProposalWithFailedAuthors {
+                payload,
+                author,
+                failed_authors,
+            } => BlockType::Proposal {
+                payload,
+                author,
+                failed_authors: Some(failed_authors),
+            },
+            BlockTypeEncoding::NilBlock => BlockType::NilBlock,
+            BlockTypeEncoding::Genesis => BlockType::Genesis,
+        })
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, CryptoHasher, BCSCryptoHash)]
 /// Block has the core data of a consensus block that should be persistent when necessary.
 /// Each block must know the id of its parent and keep the QuorurmCertificate to that parent.
@@ -96,6 +219,14 @@ impl BlockData {
         }
     }
 
+    pub fn failed_authors(&self) -> Option<&Vec<(Round, Author)>> {
+        if let BlockType::Proposal { failed_authors, .. } = &self.block_type {
+            failed_authors.as_ref()
+        } else {
+            None
+        }
+    }
+
     pub fn round(&self) -> Round {
         self.round
     }
@@ -188,6 +319,7 @@ impl BlockData {
     pub fn new_proposal(
         payload: Payload,
         author: Author,
+        failed_authors: Option<Vec<(Round, Author)>>,
         round: Round,
         timestamp_usecs: u64,
         quorum_cert: QuorumCert,
@@ -197,7 +329,11 @@ impl BlockData {
             round,
             timestamp_usecs,
             quorum_cert,
-            block_type: BlockType::Proposal { payload, author },
+            block_type: BlockType::Proposal {
+                payload,
+                author,
+                failed_authors,
+            },
         }
     }
 
@@ -232,7 +368,13 @@ fn test_reconfiguration_suffix() {
             BTreeMap::new(),
         ),
     );
-    let reconfig_suffix_block =
-        BlockData::new_proposal(vec![], AccountAddress::random(), 2, 2, quorum_cert);
+    let reconfig_suffix_block = BlockData::new_proposal(
+        Payload::empty(),
+        AccountAddress::random(),
+        None,
+        2,
+        2,
+        quorum_cert,
+    );
     assert!(reconfig_suffix_block.is_reconfiguration_suffix());
 }
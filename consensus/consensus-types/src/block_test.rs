@@ -6,10 +6,16 @@ use crate::{
         block_test_utils::{certificate_for_genesis, *},
         Block,
     },
+    block_data::{BlockData, BlockType},
+    common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
 use diem_crypto::hash::HashValue;
-use diem_types::{validator_signer::ValidatorSigner, validator_verifier::ValidatorVerifier};
+use diem_types::{
+    account_address::AccountAddress, block_metadata::BlockMetadata, transaction::SignedTransaction,
+    validator_signer::ValidatorSigner, validator_verifier::ValidatorVerifier,
+};
+use serde::Serialize;
 use std::{collections::BTreeMap, sync::Arc};
 
 #[test]
@@ -43,7 +49,7 @@ fn test_nil_block() {
     assert!(nil_block.verify_well_formed().is_ok());
 
     let signer = ValidatorSigner::random(None);
-    let payload = vec![];
+    let payload = Payload::empty();
     let parent_block_info = nil_block.quorum_cert().certified_block();
     let nil_block_qc = gen_test_certificate(
         vec![&signer],
@@ -78,7 +84,7 @@ fn test_block_relation() {
     // Test genesis and the next block
     let genesis_block = Block::make_genesis_block();
     let quorum_cert = certificate_for_genesis();
-    let payload = vec![];
+    let payload = Payload::empty();
     let next_block = Block::new_proposal(
         payload.clone(),
         1,
@@ -98,6 +104,40 @@ fn test_block_relation() {
     assert_eq!(cloned_block.round(), next_block.round());
 }
 
+#[test]
+fn test_failed_authors() {
+    let signer = ValidatorSigner::random(None);
+    let failed_author = ValidatorSigner::random([1u8; 32]).author();
+    let make_block = |failed_authors| {
+        Block::new_proposal_from_block_data(
+            BlockData::new_proposal(
+                Payload::empty(),
+                signer.author(),
+                Some(failed_authors),
+                4,
+                diem_infallible::duration_since_epoch().as_micros() as u64,
+                certificate_for_genesis(),
+            ),
+            &signer,
+        )
+    };
+
+    let block = make_block(vec![(1, failed_author), (3, failed_author)]);
+    block.verify_well_formed().unwrap();
+    assert_eq!(
+        BlockMetadata::from(&block).failed_proposers(),
+        &vec![failed_author, failed_author]
+    );
+
+    // The failed authors must be in the rounds skipped since the parent, ordered by round.
+    assert!(make_block(vec![(3, failed_author), (1, failed_author)])
+        .verify_well_formed()
+        .is_err());
+    assert!(make_block(vec![(4, failed_author)])
+        .verify_well_formed()
+        .is_err());
+}
+
 // Ensure that blocks that extend from the same QuorumCertificate but with different signatures
 // have different block ids.
 #[test]
@@ -105,7 +145,7 @@ fn test_same_qc_different_authors() {
     let signer = ValidatorSigner::random(None);
     let genesis_qc = certificate_for_genesis();
     let round = 1;
-    let payload = vec![];
+    let payload = Payload::empty();
     let current_timestamp = diem_infallible::duration_since_epoch().as_micros() as u64;
     let block_round_1 = Block::new_proposal(
         payload.clone(),
@@ -134,3 +174,111 @@ fn test_same_qc_different_authors() {
     assert!(block_round_1.id() != block_round_1_altered.id());
     assert_eq!(block_round_1.id(), block_round_1_same.id());
 }
+
+#[test]
+fn test_block_type_encoding() {
+    // A proposal without failed authors carrying its transactions is encoded as before the quorum
+    // store and the failed authors.
+    let author = AccountAddress::random();
+    let block_type = BlockType::Proposal {
+        payload: Payload::empty(),
+        author,
+        failed_authors: None,
+    };
+    let mut expected_bytes = vec![0];
+    expected_bytes.extend(bcs::to_bytes(&(Vec::<SignedTransaction>::new(), author)).unwrap());
+    assert_eq!(bcs::to_bytes(&block_type).unwrap(), expected_bytes);
+
+    // All the block types round trip.
+    let block_types = vec![
+        block_type,
+        BlockType::NilBlock,
+        BlockType::Genesis,
+        BlockType::Proposal {
+            payload: Payload::InQuorumStore(vec![]),
+            author,
+            failed_authors: None,
+        },
+        BlockType::Proposal {
+            payload: Payload::empty(),
+            author,
+            failed_authors: Some(vec![]),
+        },
+        BlockType::Proposal {
+            payload: Payload::InQuorumStore(vec![]),
+            author,
+            failed_authors: Some(vec![(1, author)]),
+        },
+    ];
+    for block_type in block_types {
+        let bytes = bcs::to_bytes(&block_type).unwrap();
+        assert_eq!(bcs::from_bytes::<BlockType>(&bytes).unwrap(), block_type);
+    }
+}
+
+#[test]
+fn test_decode_block_data_without_failed_authors() {
+    // The layout of the block data before the quorum store and the failed authors.
+    #[derive(Serialize)]
+    enum BaselineBlockType {
+        Proposal {
+            payload: Vec<SignedTransaction>,
+            author: Author,
+        },
+        NilBlock,
+        Genesis,
+    }
+
+    #[derive(Serialize)]
+    struct BaselineBlockData {
+        epoch: u64,
+        round: Round,
+        timestamp_usecs: u64,
+        quorum_cert: QuorumCert,
+        block_type: BaselineBlockType,
+    }
+
+    let signer = ValidatorSigner::random(None);
+    let quorum_cert = certificate_for_genesis();
+    let payload = match random_payload(3) {
+        Payload::DirectMempool(txns) => txns,
+        Payload::InQuorumStore(_) => unreachable!(),
+    };
+    let baseline_block_types = vec![
+        (
+            BaselineBlockType::Proposal {
+                payload: payload.clone(),
+                author: signer.author(),
+            },
+            BlockData::new_proposal(
+                Payload::DirectMempool(payload),
+                signer.author(),
+                None,
+                1,
+                1,
+                quorum_cert.clone(),
+            ),
+        ),
+        (
+            BaselineBlockType::NilBlock,
+            BlockData::new_nil(1, quorum_cert.clone()),
+        ),
+        (
+            BaselineBlockType::Genesis,
+            BlockData::new_genesis(1, quorum_cert.clone()),
+        ),
+    ];
+    for (baseline_block_type, block_data) in baseline_block_types {
+        let bytes = bcs::to_bytes(&BaselineBlockData {
+            epoch: block_data.epoch(),
+            round: block_data.round(),
+            timestamp_usecs: block_data.timestamp_usecs(),
+            quorum_cert: quorum_cert.clone(),
+            block_type: baseline_block_type,
+        })
+        .unwrap();
+        let decoded = bcs::from_bytes::<BlockData>(&bytes).unwrap();
+        assert_eq!(decoded, block_data);
+        assert_eq!(bcs::to_bytes(&decoded).unwrap(), bytes);
+    }
+}
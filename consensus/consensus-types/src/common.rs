@@ -1,7 +1,13 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use crate::proof_of_store::ProofOfStore;
+use diem_types::{
+    account_address::AccountAddress, transaction::SignedTransaction,
+    validator_verifier::ValidatorVerifier,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
 
 /// The round of a block is a consensus-internal counter, which starts with 0 and increases
 /// monotonically. It is used for the protocol safety and liveness (please see the detailed
@@ -11,4 +17,58 @@ pub type Round = u64;
 pub type Author = AccountAddress;
 
 /// The payload in block.
-pub type Payload = Vec<SignedTransaction>;
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum Payload {
+    /// The transactions pulled by the proposer from its mempool.
+    DirectMempool(Vec<SignedTransaction>),
+    /// The proofs of the batches disseminated through the quorum store, whose transactions are
+    /// fetched from the validators storing them.
+    InQuorumStore(Vec<ProofOfStore>),
+}
+
+impl Payload {
+    pub fn empty() -> Self {
+        Payload::DirectMempool(Vec::new())
+    }
+
+    /// The number of transactions of the payload.
+    pub fn len(&self) -> usize {
+        match self {
+            Payload::DirectMempool(txns) => txns.len(),
+            Payload::InQuorumStore(proofs) => {
+                proofs.iter().map(|proof| proof.num_txns() as usize).sum()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Payload::DirectMempool(txns) => txns.is_empty(),
+            Payload::InQuorumStore(proofs) => proofs.is_empty(),
+        }
+    }
+
+    /// Verifies the proofs of store of the payload, if any.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        match self {
+            Payload::DirectMempool(_) => Ok(()),
+            Payload::InQuorumStore(proofs) => {
+                proofs.iter().try_for_each(|proof| proof.verify(validator))
+            }
+        }
+    }
+}
+
+impl Display for Payload {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Payload::DirectMempool(txns) => write!(f, "DirectMempool: {} txns", txns.len()),
+            Payload::InQuorumStore(proofs) => write!(
+                f,
+                "InQuorumStore: {} batches, {} txns",
+                proofs.len(),
+                self.len()
+            ),
+        }
+    }
+}
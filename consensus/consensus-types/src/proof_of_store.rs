@@ -0,0 +1,310 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::Author;
+use anyhow::{ensure, Context};
+use diem_crypto::{ed25519::Ed25519Signature, hash::CryptoHasher, HashValue};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_types::{
+    transaction::SignedTransaction, validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// The metadata of a batch of transactions disseminated by a validator through the quorum store,
+/// ahead of the proposals referencing it.
+#[derive(
+    Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash, CryptoHasher, BCSCryptoHash,
+)]
+pub struct BatchInfo {
+    /// The validator which created the batch.
+    author: Author,
+    /// Increasing id of the batches of the author, making the batches of identical transactions
+    /// distinct.
+    batch_id: u64,
+    epoch: u64,
+    /// Timestamp (in microseconds) after which the batch can no longer be proposed, and its
+    /// transactions can be pruned by the validators storing it.
+    expiration: u64,
+    /// Hash of the transactions of the batch.
+    digest: HashValue,
+    num_txns: u64,
+}
+
+impl BatchInfo {
+    pub fn new(
+        author: Author,
+        batch_id: u64,
+        epoch: u64,
+        expiration: u64,
+        digest: HashValue,
+        num_txns: u64,
+    ) -> Self {
+        Self {
+            author,
+            batch_id,
+            epoch,
+            expiration,
+            digest,
+            num_txns,
+        }
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn batch_id(&self) -> u64 {
+        self.batch_id
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn expiration(&self) -> u64 {
+        self.expiration
+    }
+
+    pub fn digest(&self) -> &HashValue {
+        &self.digest
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+}
+
+impl Display for BatchInfo {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[author: {}, batch_id: {}, epoch: {}, expiration: {}, digest: {}, num_txns: {}]",
+            self.author.short_str(),
+            self.batch_id,
+            self.epoch,
+            self.expiration,
+            self.digest,
+            self.num_txns
+        )
+    }
+}
+
+/// The transactions of a batch, which the digest of the batch hashes.
+#[derive(Deserialize, Serialize, CryptoHasher)]
+struct BatchPayload(Vec<SignedTransaction>);
+
+/// A batch of transactions, broadcast by its author to all the validators.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Batch {
+    info: BatchInfo,
+    txns: Vec<SignedTransaction>,
+}
+
+impl Batch {
+    pub fn new(
+        author: Author,
+        batch_id: u64,
+        epoch: u64,
+        expiration: u64,
+        txns: Vec<SignedTransaction>,
+    ) -> Self {
+        let info = BatchInfo::new(
+            author,
+            batch_id,
+            epoch,
+            expiration,
+            Self::compute_digest(&txns),
+            txns.len() as u64,
+        );
+        Self { info, txns }
+    }
+
+    pub fn compute_digest(txns: &[SignedTransaction]) -> HashValue {
+        let mut hasher = BatchPayloadHasher::default();
+        bcs::serialize_into(&mut hasher, txns)
+            .expect("BCS serialization of BatchPayload should not fail");
+        hasher.finish()
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn digest(&self) -> &HashValue {
+        self.info.digest()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.epoch()
+    }
+
+    pub fn txns(&self) -> &[SignedTransaction] {
+        &self.txns
+    }
+
+    pub fn into_txns(self) -> Vec<SignedTransaction> {
+        self.txns
+    }
+
+    /// Verifies that the metadata of the batch matches its transactions.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.info.num_txns() == self.txns.len() as u64,
+            "Batch {} has {} transactions",
+            self.info,
+            self.txns.len()
+        );
+        ensure!(
+            *self.info.digest() == Self::compute_digest(&self.txns),
+            "Batch {} doesn't match the digest of its transactions",
+            self.info
+        );
+        Ok(())
+    }
+}
+
+impl Display for Batch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Batch {}", self.info)
+    }
+}
+
+/// RPC to get the batch with the given digest from a validator storing it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BatchRequest {
+    pub epoch: u64,
+    pub digest: HashValue,
+}
+
+impl Display for BatchRequest {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "BatchRequest: epoch {}, digest {}",
+            self.epoch, self.digest
+        )
+    }
+}
+
+/// The signature of a validator attesting that it stores the batch, sent back to the author of
+/// the batch.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SignedBatchInfo {
+    info: BatchInfo,
+    signer: Author,
+    signature: Ed25519Signature,
+}
+
+impl SignedBatchInfo {
+    pub fn new(info: BatchInfo, validator_signer: &ValidatorSigner) -> Self {
+        let signature = validator_signer.sign(&info);
+        Self::new_with_signature(info, validator_signer.author(), signature)
+    }
+
+    pub fn new_with_signature(
+        info: BatchInfo,
+        signer: Author,
+        signature: Ed25519Signature,
+    ) -> Self {
+        Self {
+            info,
+            signer,
+            signature,
+        }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn signer(&self) -> Author {
+        self.signer
+    }
+
+    pub fn signature(&self) -> &Ed25519Signature {
+        &self.signature
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.epoch()
+    }
+
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        validator
+            .verify(self.signer, &self.info, &self.signature)
+            .context("Failed to verify SignedBatchInfo")
+    }
+}
+
+impl Display for SignedBatchInfo {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "SignedBatchInfo: [signer: {}, {}]",
+            self.signer.short_str(),
+            self.info
+        )
+    }
+}
+
+/// Proof that a quorum of validators stores the batch, so that the batch can be referenced by
+/// proposals in place of its transactions.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProofOfStore {
+    info: BatchInfo,
+    signatures: BTreeMap<Author, Ed25519Signature>,
+}
+
+impl ProofOfStore {
+    pub fn new(info: BatchInfo, signatures: BTreeMap<Author, Ed25519Signature>) -> Self {
+        Self { info, signatures }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn digest(&self) -> &HashValue {
+        self.info.digest()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.epoch()
+    }
+
+    pub fn expiration(&self) -> u64 {
+        self.info.expiration()
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.info.num_txns()
+    }
+
+    /// The validators which signed the batch, and can therefore serve it.
+    pub fn signers(&self) -> impl Iterator<Item = &Author> {
+        self.signatures.keys()
+    }
+
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        validator
+            .batch_verify_aggregated_signatures(&self.info, &self.signatures)
+            .context("Failed to verify ProofOfStore")
+    }
+}
+
+impl Display for ProofOfStore {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "ProofOfStore: [{}, signers: {}]",
+            self.info,
+            self.signatures.len()
+        )
+    }
+}
@@ -6,6 +6,7 @@ use crate::serializer::SafetyRulesInput;
 use consensus_types::block::Block;
 use consensus_types::{
     block_data::{BlockData, BlockType},
+    common::{Payload, Round},
     quorum_cert::QuorumCert,
     timeout::Timeout,
     vote_data::VoteData,
@@ -94,10 +95,14 @@ prop_compose! {
     )(
         author in any::<AccountAddress>(),
         payload in prop::collection::vec(any::<SignedTransaction>(), 0..MAX_PROPOSAL_TRANSACTIONS),
+        failed_authors in prop::option::of(
+            prop::collection::vec((any::<Round>(), any::<AccountAddress>()), 0..3)
+        ),
     ) -> BlockType {
         BlockType::Proposal{
-            payload,
-            author
+            payload: Payload::DirectMempool(payload),
+            author,
+            failed_authors,
         }
     }
 }
@@ -0,0 +1,29 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays the consensus record of a node and prints the first decision it doesn't reproduce.
+//!
+//! Usage: cargo run -p consensus --features fuzzing --bin consensus-replay <record file>
+
+#![forbid(unsafe_code)]
+
+use consensus::replay::replay;
+use std::{env, path::Path, process};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 {
+        eprintln!("Incorrect number of parameters, expected a path to a consensus record");
+        process::exit(1);
+    }
+
+    match replay(Path::new(&args[1])) {
+        Ok(Some(divergence)) => println!("{}", divergence),
+        Ok(None) => println!("The replay reproduced all the recorded decisions"),
+        Err(e) => {
+            eprintln!("Unable to replay the provided record: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -4,12 +4,13 @@
 use crate::{
     block_storage::{block_store::BlockStore, BlockReader},
     persistent_liveness_storage::{LedgerRecoveryData, RecoveryData, RootMetadata},
+    quorum_store::batch_store::BatchStore,
     state_computer::ExecutionProxy,
     test_utils::{EmptyStorage, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
 use consensus_types::{block::Block, quorum_cert::QuorumCert};
-use diem_config::config::NodeConfig;
+use diem_config::config::{NodeConfig, QuorumStoreConfig};
 use diem_crypto::{ed25519::Ed25519PrivateKey, Uniform};
 use diem_types::{protocol_spec::DpnProto, validator_signer::ValidatorSigner};
 use execution_correctness::{ExecutionCorrectness, ExecutionCorrectnessManager};
@@ -66,9 +67,15 @@ fn build_inserter(
     let (consensus_notifier, _consensus_listener) =
         consensus_notifications::new_consensus_notifier_listener_pair(client_commit_timeout_ms);
 
+    let storage = Arc::new(EmptyStorage::new());
     let state_computer = Arc::new(ExecutionProxy::new(
         lec_client,
         Box::new(consensus_notifier),
+        None,
+        Arc::new(BatchStore::new(
+            storage.clone(),
+            &QuorumStoreConfig::default(),
+        )),
     ));
 
     TreeInserter::new_with_store(
@@ -77,7 +84,7 @@ fn build_inserter(
             Ed25519PrivateKey::generate_for_testing(),
         ),
         Arc::new(BlockStore::new(
-            Arc::new(EmptyStorage::new()),
+            storage,
             initial_data,
             state_computer,
             10, // max pruned blocks in mem
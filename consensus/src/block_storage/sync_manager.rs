@@ -7,6 +7,7 @@ use crate::{
     network::NetworkSender,
     network_interface::ConsensusMsg,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::batch_store::BatchStore,
     state_replication::StateComputer,
 };
 use anyhow::bail;
@@ -165,6 +166,7 @@ impl BlockStore {
         while let Some(block) = pending.pop() {
             let block_qc = block.quorum_cert().clone();
             self.insert_single_quorum_cert(block_qc)?;
+            retriever.fetch_payload(&block).await?;
             self.execute_and_insert_block(block)?;
         }
         self.insert_single_quorum_cert(qc)
@@ -267,6 +269,12 @@ impl BlockStore {
             assert_eq!(block.id(), quorum_certs[i].certified_block().id());
         }
 
+        // The blocks above the new root are executed when the tree is rebuilt, the root itself is
+        // committed by the state sync.
+        for block in blocks.iter().take(blocks.len() - 1) {
+            retriever.fetch_payload(block).await?;
+        }
+
         // If a node restarts in the middle of state synchronization, it is going to try to catch up
         // to the stored quorum certs as the new root.
         storage.save_tree(blocks.clone(), quorum_certs.clone())?;
@@ -302,16 +310,30 @@ impl BlockStore {
 pub struct BlockRetriever {
     network: NetworkSender,
     preferred_peer: Author,
+    batch_store: Arc<BatchStore>,
 }
 
 impl BlockRetriever {
-    pub fn new(network: NetworkSender, preferred_peer: Author) -> Self {
+    pub fn new(
+        network: NetworkSender,
+        preferred_peer: Author,
+        batch_store: Arc<BatchStore>,
+    ) -> Self {
         Self {
             network,
             preferred_peer,
+            batch_store,
         }
     }
 
+    /// Fetches the batches of the quorum store referenced by the block which are missing locally,
+    /// so that the retrieved block can be executed.
+    async fn fetch_payload(&mut self, block: &Block) -> anyhow::Result<()> {
+        self.batch_store
+            .fetch_payload(block, &mut self.network)
+            .await
+    }
+
     /// Retrieve n blocks for given block_id from peers
     ///
     /// Returns Result with Vec that if succeeded. This method will
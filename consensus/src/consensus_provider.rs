@@ -6,22 +6,30 @@ use crate::{
     epoch_manager::EpochManager,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
+    observer::{
+        consensus_observer::ConsensusObserver,
+        network::{ConsensusObserverEvents, ConsensusObserverSender},
+        publisher::ConsensusPublisher,
+    },
     persistent_liveness_storage::StorageWriteProxy,
+    quorum_store::batch_store::BatchStore,
     state_computer::ExecutionProxy,
     txn_manager::MempoolProxy,
     util::time_service::ClockTimeService,
 };
-use consensus_notifications::ConsensusNotificationSender;
-use diem_config::config::NodeConfig;
+use consensus_notifications::{ConsensusNotificationSender, ConsensusObserverPause};
+use diem_config::{config::NodeConfig, network_id::NetworkId};
 use diem_logger::prelude::*;
 use diem_mempool::ConsensusRequest;
 use diem_types::protocol_spec::DpnProto;
+use diem_vm::DiemVM;
 use event_notifications::ReconfigNotificationListener;
 use execution_correctness::ExecutionCorrectnessManager;
+use executor::Executor;
 use futures::channel::mpsc;
 use network::application::storage::PeerMetadataStorage;
-use std::sync::Arc;
-use storage_interface::DbReader;
+use std::{collections::HashMap, sync::Arc};
+use storage_interface::{DbReader, DbReaderWriter};
 use tokio::runtime::{self, Runtime};
 
 /// Helper function to start consensus based on configuration and return the runtime
@@ -34,13 +42,31 @@ pub fn start_consensus(
     diem_db: Arc<dyn DbReader<DpnProto>>,
     reconfig_events: ReconfigNotificationListener,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
+    observer_network_handles: Vec<(NetworkId, ConsensusObserverSender, ConsensusObserverEvents)>,
 ) -> Runtime {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
         .enable_all()
         .build()
         .expect("Failed to create Tokio runtime!");
+    let publisher = if node_config.consensus_observer.publisher_enabled {
+        let (network_senders, network_events) =
+            split_observer_network_handles(observer_network_handles);
+        let publisher = Arc::new(ConsensusPublisher::new(
+            &node_config.consensus_observer,
+            diem_db.clone(),
+            network_senders,
+        ));
+        runtime.spawn(publisher.clone().start(network_events));
+        Some(publisher)
+    } else {
+        None
+    };
     let storage = Arc::new(StorageWriteProxy::new(node_config, diem_db));
+    let batch_store = Arc::new(BatchStore::new(
+        storage.clone(),
+        &node_config.consensus.quorum_store,
+    ));
     let txn_manager = Arc::new(MempoolProxy::new(
         consensus_to_mempool_sender,
         node_config.consensus.mempool_poll_count,
@@ -52,6 +78,8 @@ pub fn start_consensus(
     let state_computer = Arc::new(ExecutionProxy::new(
         execution_correctness_manager.client(),
         state_sync_notifier,
+        publisher,
+        batch_store.clone(),
     ));
 
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
@@ -69,6 +97,7 @@ pub fn start_consensus(
         txn_manager,
         state_computer,
         storage,
+        batch_store,
         reconfig_events,
     );
 
@@ -80,3 +109,69 @@ pub fn start_consensus(
     debug!("Consensus started.");
     runtime
 }
+
+/// Helper function to start the consensus observer of a full node based on configuration and
+/// return the runtime
+pub fn start_consensus_observer(
+    node_config: &NodeConfig,
+    observer_network_handles: Vec<(NetworkId, ConsensusObserverSender, ConsensusObserverEvents)>,
+    state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    observer_pause: ConsensusObserverPause,
+    db_rw: DbReaderWriter<DpnProto>,
+) -> Runtime {
+    let runtime = runtime::Builder::new_multi_thread()
+        .thread_name("consensus-observer")
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime!");
+    let config = node_config.consensus_observer.clone();
+    let (network_senders, network_events) =
+        split_observer_network_handles(observer_network_handles);
+
+    // An observer relays the blocks it receives to its own subscribers.
+    let publisher = if config.publisher_enabled {
+        Some(Arc::new(ConsensusPublisher::new(
+            &config,
+            db_rw.reader.clone(),
+            network_senders.clone(),
+        )))
+    } else {
+        None
+    };
+    let startup_info = db_rw
+        .reader
+        .get_startup_info()
+        .expect("Failed to read the startup info")
+        .expect("DB not bootstrapped.");
+    let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(db_rw));
+
+    let observer = ConsensusObserver::new(
+        config,
+        network_senders,
+        block_executor,
+        state_sync_notifier,
+        observer_pause,
+        publisher,
+        startup_info.latest_ledger_info.ledger_info().clone(),
+        startup_info.get_epoch_state().clone(),
+    );
+    runtime.spawn(observer.start(network_events));
+
+    debug!("Consensus observer started.");
+    runtime
+}
+
+fn split_observer_network_handles(
+    observer_network_handles: Vec<(NetworkId, ConsensusObserverSender, ConsensusObserverEvents)>,
+) -> (
+    HashMap<NetworkId, ConsensusObserverSender>,
+    Vec<(NetworkId, ConsensusObserverEvents)>,
+) {
+    let mut network_senders = HashMap::new();
+    let mut network_events = Vec::new();
+    for (network_id, sender, events) in observer_network_handles {
+        network_senders.insert(network_id, sender);
+        network_events.push((network_id, events));
+    }
+    (network_senders, network_events)
+}
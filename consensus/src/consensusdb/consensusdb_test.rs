@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use consensus_types::block::block_test_utils::certificate_for_genesis;
+use consensus_types::{
+    block::block_test_utils::{certificate_for_genesis, random_payload},
+    common::Payload,
+};
 use diem_temppath::TempPath;
+use diem_types::account_address::AccountAddress;
 
 #[test]
 fn test_put_get() {
@@ -73,3 +77,25 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_put_get_delete_batches() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    let batches: Vec<_> = (0..3)
+        .map(|i| match random_payload(1) {
+            Payload::DirectMempool(txns) => Batch::new(AccountAddress::random(), i, 1, 100, txns),
+            Payload::InQuorumStore(_) => unreachable!(),
+        })
+        .collect();
+    db.save_batches(batches.clone()).unwrap();
+    let stored = db.get_batches().unwrap();
+    assert_eq!(stored.len(), 3);
+    assert_eq!(stored.get(batches[0].digest()), Some(&batches[0]));
+
+    db.delete_batches(vec![*batches[0].digest()]).unwrap();
+    let stored = db.get_batches().unwrap();
+    assert_eq!(stored.len(), 2);
+    assert!(!stored.contains_key(batches[0].digest()));
+}
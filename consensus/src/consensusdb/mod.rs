@@ -7,6 +7,7 @@ mod schema;
 
 use crate::{
     consensusdb::schema::{
+        batch::BatchSchema,
         block::BlockSchema,
         quorum_certificate::QCSchema,
         single_entry::{SingleEntryKey, SingleEntrySchema},
@@ -14,10 +15,10 @@ use crate::{
     error::DbError,
 };
 use anyhow::Result;
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{block::Block, proof_of_store::Batch, quorum_cert::QuorumCert};
 use diem_crypto::HashValue;
 use diem_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{BATCH_CF_NAME, BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{Options, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME};
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
@@ -32,6 +33,7 @@ impl ConsensusDB {
             BLOCK_CF_NAME,
             QC_CF_NAME,
             SINGLE_ENTRY_CF_NAME,
+            BATCH_CF_NAME,
         ];
 
         let path = db_root_path.as_ref().join("consensusdb");
@@ -141,6 +143,29 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    pub fn save_batches(&self, batches: Vec<Batch>) -> Result<(), DbError> {
+        let mut batch = SchemaBatch::new();
+        batches
+            .iter()
+            .try_for_each(|b| batch.put::<BatchSchema>(b.digest(), b))?;
+        self.commit(batch)
+    }
+
+    pub fn delete_batches(&self, digests: Vec<HashValue>) -> Result<(), DbError> {
+        let mut batch = SchemaBatch::new();
+        digests
+            .iter()
+            .try_for_each(|digest| batch.delete::<BatchSchema>(digest))?;
+        self.commit(batch)
+    }
+
+    /// Get all the batches of the quorum store.
+    pub fn get_batches(&self) -> Result<HashMap<HashValue, Batch>, DbError> {
+        let mut iter = self.db.iter::<BatchSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter.collect::<Result<HashMap<HashValue, Batch>>>()?)
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<(), DbError> {
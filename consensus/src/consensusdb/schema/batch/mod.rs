@@ -0,0 +1,44 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the batches of the quorum store.
+//!
+//! Serialized batch bytes identified by the digest of its transactions.
+//! ```text
+//! |<---key---->|<-value->|
+//! |   digest   |  Batch  |
+//! ```
+
+use super::BATCH_CF_NAME;
+use anyhow::Result;
+use consensus_types::proof_of_store::Batch;
+use diem_crypto::HashValue;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+
+define_schema!(BatchSchema, HashValue, Batch, BATCH_CF_NAME);
+
+impl KeyCodec<BatchSchema> for HashValue {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(HashValue::from_slice(data)?)
+    }
+}
+
+impl ValueCodec<BatchSchema> for Batch {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
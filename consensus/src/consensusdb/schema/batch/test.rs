@@ -0,0 +1,17 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use consensus_types::{block::block_test_utils::random_payload, common::Payload};
+use diem_types::account_address::AccountAddress;
+use schemadb::schema::assert_encode_decode;
+
+#[test]
+fn test_encode_decode() {
+    let txns = match random_payload(2) {
+        Payload::DirectMempool(txns) => txns,
+        Payload::InQuorumStore(_) => unreachable!(),
+    };
+    let batch = Batch::new(AccountAddress::random(), 0, 1, 100, txns);
+    assert_encode_decode::<BatchSchema>(batch.digest(), &batch);
+}
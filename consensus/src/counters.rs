@@ -89,6 +89,15 @@ pub static COMMITTED_PROPOSALS_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Failed proposals from this validator when using LeaderReputation as the ProposerElection
+pub static FAILED_PROPOSALS_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_failed_proposals_in_window",
+        "Total number of this validator's failed proposals in the current reputation window"
+    )
+    .unwrap()
+});
+
 /// Committed votes from this validator when using LeaderReputation as the ProposerElection
 pub static COMMITTED_VOTES_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
@@ -262,6 +271,16 @@ pub static PENDING_COMMIT_VOTES: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counters(queued,dequeued,dropped) related to pending quorum store messages
+pub static PENDING_QUORUM_STORE_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_pending_quorum_store_msgs",
+        "Counters(queued,dequeued,dropped) related to pending quorum store messages",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counters(queued,dequeued,dropped) related to consensus channel
 pub static CONSENSUS_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -281,3 +300,60 @@ pub static BLOCK_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Counters(queued,dequeued,dropped) related to quorum store channel
+pub static QUORUM_STORE_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_quorum_store_channel_msgs_count",
+        "Counters(queued,dequeued,dropped) related to quorum store channel",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Counters(queued,dequeued,dropped) related to batch retrieval channel
+pub static BATCH_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_batch_retrieval_channel_msgs_count",
+        "Counters(queued,dequeued,dropped) related to batch retrieval channel",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Counter of pending network events to the consensus observer
+pub static PENDING_CONSENSUS_OBSERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_observer_pending_network_events",
+        "Counters(queued,dequeued,dropped) related to pending network notifications to the consensus observer",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Count of the blocks committed by the consensus observer since last restart.
+pub static OBSERVER_COMMITTED_BLOCKS_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_observer_committed_blocks_count",
+        "Count of the blocks committed by the consensus observer since last restart."
+    )
+    .unwrap()
+});
+
+/// Count of the times the consensus observer fell back to state sync since last restart.
+pub static OBSERVER_STATE_SYNC_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_observer_state_sync_count",
+        "Count of the times the consensus observer fell back to state sync since last restart."
+    )
+    .unwrap()
+});
+
+/// Number of the peers subscribed to the consensus publisher.
+pub static PUBLISHER_NUM_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_publisher_num_subscribers",
+        "Number of the peers subscribed to the consensus publisher."
+    )
+    .unwrap()
+});
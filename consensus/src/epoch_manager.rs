@@ -11,7 +11,9 @@ use crate::{
         ordering_state_computer::OrderingStateComputer,
     },
     liveness::{
-        leader_reputation::{ActiveInactiveHeuristic, DiemDBBackend, LeaderReputation},
+        leader_reputation::{
+            ActiveInactiveHeuristic, DiemDBBackend, LeaderReputation, ProposerAndVoterHeuristic,
+        },
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
         rotating_proposer_election::{choose_leader, RotatingProposer},
@@ -20,9 +22,18 @@ use crate::{
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
-    network::{IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender},
+    network::{
+        IncomingBatchRetrievalRequest, IncomingBlockRetrievalRequest, NetworkReceivers,
+        NetworkSender,
+    },
     network_interface::{ConsensusMsg, ConsensusNetworkSender},
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
+    quorum_store::{
+        batch_store::BatchStore, coordinator::QuorumStoreCoordinator, proof_manager::ProofManager,
+    },
+    recorder::{
+        ConsensusRecorder, RecordedEvent, RecordingProposerElection, RecordingStateComputer,
+    },
     round_manager::{RecoveryManager, RoundManager, UnverifiedEvent, VerifiedEvent},
     state_replication::{StateComputer, TxnManager},
     util::time_service::TimeService,
@@ -53,7 +64,7 @@ use futures::{
     select, SinkExt, StreamExt,
 };
 use network::protocols::network::{ApplicationNetworkSender, Event};
-use safety_rules::SafetyRulesManager;
+use safety_rules::{SafetyRulesManager, TSafetyRules};
 use std::{cmp::Ordering, sync::Arc, time::Duration};
 
 /// RecoveryManager is used to process events in order to sync up with peer if we can't recover from local consensusdb
@@ -98,6 +109,13 @@ pub struct EpochManager {
     // channels to buffer manager
     buffer_manager_msg_tx: Option<diem_channel::Sender<AccountAddress, VerifiedEvent>>,
     buffer_manager_reset_tx: Option<UnboundedSender<ResetRequest>>,
+    batch_store: Arc<BatchStore>,
+    // channels to quorum store coordinator
+    quorum_store_msg_tx:
+        Option<diem_channel::Sender<AccountAddress, (AccountAddress, VerifiedEvent)>>,
+    quorum_store_shutdown_tx: Option<oneshot::Sender<oneshot::Sender<()>>>,
+    // records the inputs and decisions of the round managers when recording is enabled
+    recorder: Option<Arc<ConsensusRecorder>>,
 }
 
 impl EpochManager {
@@ -110,6 +128,7 @@ impl EpochManager {
         txn_manager: Arc<dyn TxnManager>,
         commit_state_computer: Arc<dyn StateComputer>,
         storage: Arc<dyn PersistentLivenessStorage>,
+        batch_store: Arc<BatchStore>,
         reconfig_events: ReconfigNotificationListener,
     ) -> Self {
         let author = node_config.validator_network.as_ref().unwrap().peer_id();
@@ -119,6 +138,19 @@ impl EpochManager {
             panic!("Inconsistent decoupled-execution configuration of consensus and safety-rules\nMake sure consensus.decoupled = safety_rules.decoupled_execution.")
         }
         let safety_rules_manager = SafetyRulesManager::new(sr_config);
+        let recorder = config.record_path.as_ref().and_then(|path| {
+            ConsensusRecorder::create(path, time_service.clone())
+                .map(Arc::new)
+                .map_err(|e| error!(error = ?e, "[EpochManager] Unable to create the recorder"))
+                .ok()
+        });
+        let commit_state_computer = match &recorder {
+            Some(recorder) => Arc::new(RecordingStateComputer::new(
+                commit_state_computer,
+                recorder.clone(),
+            )),
+            None => commit_state_computer,
+        };
         Self {
             author,
             config,
@@ -134,6 +166,16 @@ impl EpochManager {
             reconfig_events,
             buffer_manager_msg_tx: None,
             buffer_manager_reset_tx: None,
+            batch_store,
+            quorum_store_msg_tx: None,
+            quorum_store_shutdown_tx: None,
+            recorder,
+        }
+    }
+
+    fn record(&self, event: impl FnOnce() -> RecordedEvent) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(event());
         }
     }
 
@@ -198,6 +240,17 @@ impl EpochManager {
                 ));
                 Box::new(LeaderReputation::new(proposers, backend, heuristic))
             }
+            ConsensusProposerType::ProposerAndVoterReputation(heuristic_config) => {
+                let backend = Box::new(DiemDBBackend::new(proposers.len(), self.storage.diem_db()));
+                let heuristic = Box::new(ProposerAndVoterHeuristic::new(
+                    self.author,
+                    heuristic_config.active_weights,
+                    heuristic_config.inactive_weights,
+                    heuristic_config.failed_weights,
+                    heuristic_config.failure_threshold_percent,
+                ));
+                Box::new(LeaderReputation::new(proposers, backend, heuristic))
+            }
             ConsensusProposerType::RoundProposer(round_proposers) => {
                 // Hardcoded to the first proposer
                 let default_proposer = proposers.get(0).unwrap();
@@ -343,6 +396,48 @@ impl EpochManager {
         OrderingStateComputer::new(block_tx, self.commit_state_computer.clone(), reset_tx)
     }
 
+    /// this function spawns the quorum store coordinator of the epoch
+    /// it sets `self.quorum_store_msg_tx` to a new diem_channel::Sender and returns the
+    /// ProofManager proposing the proofs of store in place of the transactions
+    fn spawn_quorum_store(
+        &mut self,
+        epoch_state: &EpochState,
+        safety_rules_container: Arc<Mutex<MetricsSafetyRules>>,
+        network_sender: NetworkSender,
+    ) -> Arc<ProofManager> {
+        let proof_manager = Arc::new(ProofManager::new(
+            epoch_state.epoch,
+            self.batch_store.clone(),
+            self.time_service.clone(),
+        ));
+
+        let (msg_tx, msg_rx) = diem_channel::new(
+            QueueStyle::FIFO,
+            self.config.channel_size,
+            Some(&counters::PENDING_QUORUM_STORE_MSGS),
+        );
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        self.quorum_store_msg_tx = Some(msg_tx);
+        self.quorum_store_shutdown_tx = Some(shutdown_tx);
+
+        let coordinator = QuorumStoreCoordinator::new(
+            epoch_state.clone(),
+            self.config.quorum_store.clone(),
+            self.txn_manager.clone(),
+            self.batch_store.clone(),
+            proof_manager.clone(),
+            safety_rules_container,
+            network_sender,
+            self.time_service.clone(),
+            msg_rx,
+            shutdown_rx,
+        );
+        tokio::spawn(coordinator.start());
+
+        proof_manager
+    }
+
     async fn shutdown_current_processor(&mut self) {
         // Release the previous RoundManager, especially the SafetyRule client
         self.processor = None;
@@ -360,6 +455,16 @@ impl EpochManager {
                 .await
                 .expect("[EpochManager] Fail to drop buffer manager");
         }
+        self.quorum_store_msg_tx = None;
+        // Shutdown the previous quorum store coordinator, to release the SafetyRule client
+        if let Some(tx) = self.quorum_store_shutdown_tx.take() {
+            let (ack_tx, ack_rx) = oneshot::channel();
+            tx.send(ack_tx)
+                .expect("[EpochManager] Fail to drop quorum store");
+            ack_rx
+                .await
+                .expect("[EpochManager] Fail to drop quorum store");
+        }
     }
 
     async fn start_round_manager(
@@ -395,9 +500,24 @@ impl EpochManager {
         let round_state =
             self.create_round_state(self.time_service.clone(), self.timeout_sender.clone());
 
+        if let Some(recorder) = &self.recorder {
+            let safety_data = safety_rules
+                .consensus_state()
+                .map(|mut consensus_state| Box::new(consensus_state.safety_data()))
+                .ok();
+            recorder.record(RecordedEvent::EpochStart {
+                author: self.author,
+                epoch_state: epoch_state.clone(),
+                onchain_config: onchain_config.clone(),
+                recovery_data: Box::new(recovery_data.clone()),
+                safety_data,
+                decoupled_execution: self.config.decoupled_execution,
+            });
+        }
+
         info!(epoch = epoch, "Create ProposerElection");
-        let proposer_election = self.create_proposer_election(&epoch_state);
-        let network_sender = NetworkSender::new(
+        let mut proposer_election = self.create_proposer_election(&epoch_state);
+        let mut network_sender = NetworkSender::new(
             self.author,
             self.network_sender.clone(),
             self.self_sender.clone(),
@@ -406,6 +526,28 @@ impl EpochManager {
 
         let safety_rules_container = Arc::new(Mutex::new(safety_rules));
 
+        // With the quorum store, the proposals carry the proofs of store of the batches instead of
+        // the transactions pulled from mempool.
+        let proposal_txn_manager: Arc<dyn TxnManager> = if self.config.quorum_store.enabled {
+            info!(epoch = epoch, "Start QuorumStore");
+            self.spawn_quorum_store(
+                &epoch_state,
+                safety_rules_container.clone(),
+                network_sender.clone(),
+            )
+        } else {
+            self.txn_manager.clone()
+        };
+
+        // Only the round manager's decisions are recorded, not the quorum store's.
+        if let Some(recorder) = &self.recorder {
+            proposer_election = Box::new(RecordingProposerElection::new(
+                proposer_election,
+                recorder.clone(),
+            ));
+            network_sender.set_recorder(recorder.clone());
+        }
+
         let mut processor = if self.config.decoupled_execution {
             let ordering_state_computer = Arc::new(self.spawn_decoupled_execution(
                 safety_rules_container.clone(),
@@ -428,9 +570,10 @@ impl EpochManager {
             let proposal_generator = ProposalGenerator::new(
                 self.author,
                 block_store.clone(),
-                self.txn_manager.clone(),
+                proposal_txn_manager,
                 self.time_service.clone(),
                 self.config.max_block_size,
+                self.config.proposer_type.records_failed_authors(),
             );
 
             RoundManager::new_with_decoupled_execution(
@@ -443,6 +586,7 @@ impl EpochManager {
                 network_sender,
                 self.txn_manager.clone(),
                 self.storage.clone(),
+                self.batch_store.clone(),
                 self.config.sync_only,
                 self.config.back_pressure_limit,
                 onchain_config,
@@ -464,9 +608,10 @@ impl EpochManager {
             let proposal_generator = ProposalGenerator::new(
                 self.author,
                 block_store.clone(),
-                self.txn_manager.clone(),
+                proposal_txn_manager,
                 self.time_service.clone(),
                 self.config.max_block_size,
+                self.config.proposer_type.records_failed_authors(),
             );
 
             RoundManager::new(
@@ -479,6 +624,7 @@ impl EpochManager {
                 network_sender,
                 self.txn_manager.clone(),
                 self.storage.clone(),
+                self.batch_store.clone(),
                 self.config.sync_only,
                 onchain_config,
             )
@@ -512,6 +658,7 @@ impl EpochManager {
             network_sender,
             self.storage.clone(),
             self.commit_state_computer.clone(),
+            self.batch_store.clone(),
             ledger_recovery_data.commit_round(),
             onchain_config,
         )));
@@ -546,6 +693,7 @@ impl EpochManager {
         peer_id: AccountAddress,
         consensus_msg: ConsensusMsg,
     ) -> anyhow::Result<()> {
+        self.record(|| RecordedEvent::Message(peer_id, consensus_msg.clone()));
         // we can't verify signatures from a different epoch
         let maybe_unverified_event = self.process_epoch(peer_id, consensus_msg).await?;
 
@@ -592,6 +740,20 @@ impl EpochManager {
                     );
                 }
             }
+            ConsensusMsg::BatchMsg(_)
+            | ConsensusMsg::SignedBatchInfoMsg(_)
+            | ConsensusMsg::ProofOfStoreMsg(_) => {
+                let event: UnverifiedEvent = msg.into();
+                // The quorum store messages are too frequent to trigger the epoch sync, which is
+                // left to the consensus messages.
+                ensure!(
+                    event.epoch() == self.epoch(),
+                    "[EpochManager] Quorum store message from epoch {}, local epoch {}",
+                    event.epoch(),
+                    self.epoch()
+                );
+                return Ok(Some(event));
+            }
             ConsensusMsg::EpochChangeProof(proof) => {
                 let msg_epoch = proof.epoch()?;
                 debug!(
@@ -643,6 +805,11 @@ impl EpochManager {
                             "Ignoring commit vote/decision message during recovery"
                         ));
                     }
+                    VerifiedEvent::Batch(_)
+                    | VerifiedEvent::SignedBatchInfo(_)
+                    | VerifiedEvent::ProofOfStore(_) => {
+                        return Err(anyhow!("Ignoring quorum store message during recovery"));
+                    }
                 }?;
                 let epoch_state = p.epoch_state().clone();
                 let onchain_config = p.onchain_config().clone();
@@ -672,6 +839,19 @@ impl EpochManager {
                         bail!("Commit Phase not started but received Commit Message (CommitVote/CommitDecision)");
                     }
                 }
+                quorum_store_event @ VerifiedEvent::Batch(_)
+                | quorum_store_event @ VerifiedEvent::SignedBatchInfo(_)
+                | quorum_store_event @ VerifiedEvent::ProofOfStore(_) => {
+                    if let Some(sender) = &mut self.quorum_store_msg_tx {
+                        sender
+                            .push(peer_id, (peer_id, quorum_store_event))
+                            .map_err(|err| {
+                                anyhow!("Error in Passing Quorum Store Message: {}", err)
+                            })
+                    } else {
+                        bail!("Quorum store not started but received Quorum Store Message (Batch/SignedBatchInfo/ProofOfStore)");
+                    }
+                }
             },
         }
     }
@@ -692,7 +872,27 @@ impl EpochManager {
         }
     }
 
+    async fn process_batch_retrieval(
+        &self,
+        request: IncomingBatchRetrievalRequest,
+    ) -> anyhow::Result<()> {
+        // Dropping the request of a missing batch lets the requester try another peer.
+        let batch = self
+            .batch_store
+            .get_batch(&request.req.digest)
+            .ok_or_else(|| anyhow!("[EpochManager] Batch {} not found", request.req.digest))?;
+        let response_bytes = request
+            .protocol
+            .to_bytes(&ConsensusMsg::BatchMsg(Box::new(batch)))?;
+        request
+            .response_sender
+            .send(Ok(response_bytes.into()))
+            .map_err(|e| anyhow!("{:?}", e))
+            .context("[EpochManager] Failed to process batch retrieval")
+    }
+
     async fn process_local_timeout(&mut self, round: u64) -> anyhow::Result<()> {
+        self.record(|| RecordedEvent::LocalTimeout(round));
         match self.processor_mut() {
             RoundProcessor::Normal(p) => p.process_local_timeout(round).await,
             _ => unreachable!("RoundManager not started yet"),
@@ -726,6 +926,13 @@ impl EpochManager {
                     block_retrieval = network_receivers.block_retrieval.select_next_some() => {
                         monitor!("process_block_retrieval", self.process_block_retrieval(block_retrieval).await)
                     }
+                    msg = network_receivers.quorum_store_messages.select_next_some() => {
+                        let (peer, msg) = (msg.0, msg.1);
+                        monitor!("process_quorum_store_message", self.process_message(peer, msg).await.with_context(|| format!("from peer: {}", peer)))
+                    }
+                    batch_retrieval = network_receivers.batch_retrieval.select_next_some() => {
+                        monitor!("process_batch_retrieval", self.process_batch_retrieval(batch_retrieval).await)
+                    }
                     round = round_timeout_sender_rx.select_next_some() => {
                         monitor!("process_local_timeout", self.process_local_timeout(round).await)
                     }
@@ -10,6 +10,7 @@ use crate::{
 };
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
+    common::Payload,
     executed_block::ExecutedBlock,
 };
 use diem_crypto::HashValue;
@@ -62,7 +63,7 @@ fn test_execution_phase_process() {
 
     let genesis_qc = certificate_for_genesis();
     let (signers, _validators) = random_validator_verifier(1, None, false);
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc, &signers[0]);
+    let block = Block::new_proposal(Payload::empty(), 1, 1, genesis_qc, &signers[0]);
 
     timed_block_on(&mut runtime, async move {
         let resp = execution_phase
@@ -89,7 +90,7 @@ fn test_execution_phase_happy_path() {
 
     let genesis_qc = certificate_for_genesis();
     let (signers, _validators) = random_validator_verifier(1, None, false);
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc, &signers[0]);
+    let block = Block::new_proposal(Payload::empty(), 1, 1, genesis_qc, &signers[0]);
 
     timed_block_on(&mut runtime, async move {
         in_channel_tx
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{block::Block, common::Payload, quorum_cert::QuorumCert};
 use diem_types::validator_signer::ValidatorSigner;
 use rand::Rng;
 
@@ -11,7 +11,13 @@ pub fn prepare_ordering_state_computer(_channel_size: usize) {
 
 pub fn random_empty_block(signer: &ValidatorSigner, qc: QuorumCert) -> Block {
     let mut rng = rand::thread_rng();
-    Block::new_proposal(vec![], rng.gen::<u64>(), rng.gen::<u64>(), qc, signer)
+    Block::new_proposal(
+        Payload::empty(),
+        rng.gen::<u64>(),
+        rng.gen::<u64>(),
+        qc,
+        signer,
+    )
 }
 
 #[test]
@@ -26,6 +26,8 @@ mod network;
 mod network_tests;
 mod pending_votes;
 mod persistent_liveness_storage;
+mod quorum_store;
+mod recorder;
 mod round_manager;
 mod state_computer;
 mod state_replication;
@@ -40,6 +42,10 @@ mod util;
 pub mod consensus_provider;
 /// DiemNet interface.
 pub mod network_interface;
+/// Consensus observer for full nodes.
+pub mod observer;
 
+#[cfg(feature = "fuzzing")]
+pub use recorder::replay;
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    counters::{COMMITTED_PROPOSALS_IN_WINDOW, COMMITTED_VOTES_IN_WINDOW},
+    counters::{
+        COMMITTED_PROPOSALS_IN_WINDOW, COMMITTED_VOTES_IN_WINDOW, FAILED_PROPOSALS_IN_WINDOW,
+    },
     liveness::proposer_election::{next, ProposerElection},
 };
 use consensus_types::{
@@ -15,6 +17,7 @@ use diem_logger::prelude::*;
 use diem_types::{
     block_metadata::{new_block_event_key, NewBlockEvent},
     protocol_spec::DpnProto,
+    transaction::Version,
 };
 use std::{
     cmp::Ordering,
@@ -33,7 +36,8 @@ pub trait MetadataBackend: Send + Sync {
 pub struct DiemDBBackend {
     window_size: usize,
     diem_db: Arc<dyn DbReader<DpnProto>>,
-    window: Mutex<Vec<(u64, NewBlockEvent)>>,
+    // The window of the last target round, with the latest version it was read at.
+    window: Mutex<Option<(Round, Version, Vec<NewBlockEvent>)>>,
 }
 
 impl DiemDBBackend {
@@ -41,11 +45,15 @@ impl DiemDBBackend {
         Self {
             window_size,
             diem_db,
-            window: Mutex::new(vec![]),
+            window: Mutex::new(None),
         }
     }
 
-    fn refresh_window(&self, target_round: Round) -> anyhow::Result<()> {
+    /// Reads the `window_size` events ending at the last block committed at or before the target
+    /// round. The blocks committed after the target round are skipped, so that the window only
+    /// depends on the history up to the target round: it's the same on all the validators which
+    /// committed it.
+    fn read_window(&self, target_round: Round) -> anyhow::Result<Vec<NewBlockEvent>> {
         // assumes target round is not too far from latest commit
         let buffer = 10;
         let events = self.diem_db.get_events(
@@ -55,42 +63,37 @@ impl DiemDBBackend {
             self.window_size as u64 + buffer,
         )?;
         let mut result = vec![];
-        for (v, e) in events {
-            let e = bcs::from_bytes::<NewBlockEvent>(e.event_data())?;
+        for (_, event) in events {
+            let e = NewBlockEvent::try_from_bytes(event.event_data())?;
             if e.round() <= target_round && result.len() < self.window_size {
-                result.push((v, e));
+                result.push(e);
             }
         }
-        *self.window.lock() = result;
-        Ok(())
+        Ok(result)
     }
 }
 
 impl MetadataBackend for DiemDBBackend {
-    // assume the target_round only increases
     fn get_block_metadata(&self, target_round: Round) -> Vec<NewBlockEvent> {
-        let (known_version, known_round) = self
-            .window
-            .lock()
-            .first()
-            .map(|(v, e)| (*v, e.round()))
-            .unwrap_or((0, 0));
-        if !(known_round == target_round
-            || known_version == self.diem_db.get_latest_version().unwrap_or(0))
-        {
-            if let Err(e) = self.refresh_window(target_round) {
+        let latest_version = self.diem_db.get_latest_version().unwrap_or(0);
+        let mut window = self.window.lock();
+        if let Some((round, version, events)) = window.as_ref() {
+            if *round == target_round && *version == latest_version {
+                return events.clone();
+            }
+        }
+        match self.read_window(target_round) {
+            Ok(events) => {
+                *window = Some((target_round, latest_version, events.clone()));
+                events
+            }
+            Err(e) => {
                 error!(
                     error = ?e, "[leader reputation] Fail to refresh window",
                 );
-                return vec![];
+                vec![]
             }
         }
-        self.window
-            .lock()
-            .clone()
-            .into_iter()
-            .map(|(_, e)| e)
-            .collect()
     }
 }
 
@@ -156,6 +159,77 @@ impl ReputationHeuristic for ActiveInactiveHeuristic {
     }
 }
 
+/// Weighs the candidates like `ActiveInactiveHeuristic`, but also penalizes the ones which failed
+/// to get their rounds committed: a candidate whose failed proposals exceed the threshold
+/// percentage of all its proposals in the history is assigned failed_weight.
+pub struct ProposerAndVoterHeuristic {
+    author: Author,
+    active_weight: u64,
+    inactive_weight: u64,
+    failed_weight: u64,
+    failure_threshold_percent: u32,
+}
+
+impl ProposerAndVoterHeuristic {
+    pub fn new(
+        author: Author,
+        active_weight: u64,
+        inactive_weight: u64,
+        failed_weight: u64,
+        failure_threshold_percent: u32,
+    ) -> Self {
+        Self {
+            author,
+            active_weight,
+            inactive_weight,
+            failed_weight,
+            failure_threshold_percent,
+        }
+    }
+}
+
+impl ReputationHeuristic for ProposerAndVoterHeuristic {
+    fn get_weights(&self, candidates: &[Author], history: &[NewBlockEvent]) -> Vec<u64> {
+        let mut proposals: HashMap<Author, u64> = HashMap::new();
+        let mut failed_proposals: HashMap<Author, u64> = HashMap::new();
+        let mut votes: HashMap<Author, u64> = HashMap::new();
+        for meta in history {
+            *proposals.entry(meta.proposer()).or_default() += 1;
+            for failed_proposer in meta.failed_proposers() {
+                *failed_proposals.entry(*failed_proposer).or_default() += 1;
+            }
+            for vote in meta.votes() {
+                *votes.entry(vote).or_default() += 1;
+            }
+        }
+
+        let count = |counts: &HashMap<Author, u64>, author: &Author| {
+            counts.get(author).copied().unwrap_or(0)
+        };
+        COMMITTED_PROPOSALS_IN_WINDOW.set(count(&proposals, &self.author) as i64);
+        FAILED_PROPOSALS_IN_WINDOW.set(count(&failed_proposals, &self.author) as i64);
+        COMMITTED_VOTES_IN_WINDOW.set(count(&votes, &self.author) as i64);
+
+        candidates
+            .iter()
+            .map(|author| {
+                let num_proposals = count(&proposals, author);
+                let num_failed_proposals = count(&failed_proposals, author);
+                if num_failed_proposals * 100
+                    > (num_proposals + num_failed_proposals)
+                        * u64::from(self.failure_threshold_percent)
+                {
+                    self.failed_weight
+                } else if num_proposals > 0 || count(&votes, author) > 0 {
+                    self.active_weight
+                } else {
+                    self.inactive_weight
+                }
+            })
+            .collect()
+    }
+}
+
 /// Committed history based proposer election implementation that could help bias towards
 /// successful leaders to help improve performance.
 pub struct LeaderReputation {
@@ -187,6 +261,11 @@ impl ProposerElection for LeaderReputation {
         let sliding_window = self.backend.get_block_metadata(target_round);
         let mut weights = self.heuristic.get_weights(&self.proposers, &sliding_window);
         assert_eq!(weights.len(), self.proposers.len());
+        // All the candidates can be penalized down to a zero weight: fall back to the same weight
+        // for all of them.
+        if weights.iter().all(|w| *w == 0) {
+            weights = vec![1; weights.len()];
+        }
         let mut total_weight = 0;
         for w in &mut weights {
             total_weight += *w;
@@ -3,13 +3,14 @@
 
 use crate::liveness::{
     leader_reputation::{
-        ActiveInactiveHeuristic, LeaderReputation, MetadataBackend, ReputationHeuristic,
+        ActiveInactiveHeuristic, LeaderReputation, MetadataBackend, ProposerAndVoterHeuristic,
+        ReputationHeuristic,
     },
     proposer_election::{next, ProposerElection},
 };
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
-    common::{Author, Round},
+    common::{Author, Payload, Round},
 };
 use diem_types::{block_metadata::NewBlockEvent, validator_signer::ValidatorSigner};
 
@@ -36,7 +37,21 @@ impl MetadataBackend for MockHistory {
 }
 
 fn create_block(proposer: Author, voters: Vec<&ValidatorSigner>) -> NewBlockEvent {
-    NewBlockEvent::new(0, proposer, voters.iter().map(|v| v.author()).collect(), 0)
+    create_block_with_failed_proposers(proposer, voters, vec![])
+}
+
+fn create_block_with_failed_proposers(
+    proposer: Author,
+    voters: Vec<&ValidatorSigner>,
+    failed_proposers: Vec<Author>,
+) -> NewBlockEvent {
+    NewBlockEvent::new(
+        0,
+        proposer,
+        voters.iter().map(|v| v.author()).collect(),
+        0,
+        failed_proposers,
+    )
 }
 
 #[test]
@@ -130,7 +145,7 @@ fn test_api() {
     assert!(proposer_election.is_valid_proposer(proposers[expected_index], 42));
     assert!(!proposer_election.is_valid_proposer(proposers[unexpected_index], 42));
     let good_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(),
         round,
         1,
         certificate_for_genesis(),
@@ -138,7 +153,7 @@ fn test_api() {
     );
     assert!(proposer_election.is_valid_proposal(&good_proposal));
     let bad_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(),
         round,
         1,
         certificate_for_genesis(),
@@ -146,7 +161,7 @@ fn test_api() {
     );
     assert!(!proposer_election.is_valid_proposal(&bad_proposal));
     let bad_proposal_2 = Block::new_proposal(
-        vec![],
+        Payload::empty(),
         round,
         2,
         certificate_for_genesis(),
@@ -158,3 +173,106 @@ fn test_api() {
     // good proposal still passes
     assert!(proposer_election.is_valid_proposal(&good_proposal));
 }
+
+#[test]
+fn test_proposer_and_voter_heuristic() {
+    let active_weight = 9;
+    let inactive_weight = 1;
+    let failed_weight = 0;
+    let mut proposers = vec![];
+    let mut signers = vec![];
+    for i in 0..6 {
+        let signer = ValidatorSigner::random([i; 32]);
+        proposers.push(signer.author());
+        signers.push(signer);
+    }
+    let heuristic = ProposerAndVoterHeuristic::new(
+        proposers[0],
+        active_weight,
+        inactive_weight,
+        failed_weight,
+        50,
+    );
+    // proposer 0 has 2 proposals and 1 failure, proposer 1 has 1 proposal and 2 failures,
+    // proposer 2 only failed, proposer 3 only voted, proposers 4 and 5 are inactive.
+    let history = vec![
+        create_block_with_failed_proposers(proposers[0], vec![&signers[3]], vec![proposers[1]]),
+        create_block_with_failed_proposers(
+            proposers[1],
+            vec![&signers[3]],
+            vec![proposers[0], proposers[2]],
+        ),
+        create_block_with_failed_proposers(proposers[0], vec![&signers[3]], vec![proposers[1]]),
+    ];
+    let weights = heuristic.get_weights(&proposers, &history);
+    assert_eq!(
+        weights,
+        vec![
+            active_weight,
+            failed_weight,
+            failed_weight,
+            active_weight,
+            inactive_weight,
+            inactive_weight,
+        ]
+    );
+
+    // With a 100% threshold, failures alone are never penalized.
+    let heuristic = ProposerAndVoterHeuristic::new(
+        proposers[0],
+        active_weight,
+        inactive_weight,
+        failed_weight,
+        100,
+    );
+    let weights = heuristic.get_weights(&proposers, &history);
+    assert_eq!(weights[1], active_weight);
+    assert_eq!(weights[2], inactive_weight);
+}
+
+#[test]
+fn test_deterministic_election_with_failed_proposers() {
+    let mut proposers = vec![];
+    let mut signers = vec![];
+    for i in 0..4 {
+        let signer = ValidatorSigner::random([i; 32]);
+        proposers.push(signer.author());
+        signers.push(signer);
+    }
+    let history = vec![
+        create_block_with_failed_proposers(proposers[0], vec![&signers[1]], vec![proposers[2]]),
+        create_block_with_failed_proposers(proposers[1], vec![&signers[0]], vec![proposers[2]]),
+    ];
+    // Validators with different local authors elect the same proposers.
+    let elections: Vec<_> = proposers
+        .iter()
+        .map(|author| {
+            LeaderReputation::new(
+                proposers.clone(),
+                Box::new(MockHistory::new(10, history.clone())),
+                Box::new(ProposerAndVoterHeuristic::new(*author, 10, 1, 0, 10)),
+            )
+        })
+        .collect();
+    for round in 1..100 {
+        let proposer = elections[0].get_valid_proposer(round);
+        // The proposer which only failed in the window is never elected.
+        assert_ne!(proposer, proposers[2]);
+        for election in &elections[1..] {
+            assert_eq!(election.get_valid_proposer(round), proposer);
+        }
+    }
+
+    // When all the candidates are penalized to a zero weight, they're all electable.
+    let election = LeaderReputation::new(
+        proposers.clone(),
+        Box::new(MockHistory::new(10, history)),
+        Box::new(ProposerAndVoterHeuristic::new(proposers[0], 0, 0, 0, 10)),
+    );
+    let mut elected: Vec<_> = (1..100)
+        .map(|round| election.get_valid_proposer(round))
+        .collect();
+    elected.sort();
+    elected.dedup();
+    assert_eq!(elected.len(), proposers.len());
+}
@@ -2,13 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_storage::BlockReader, state_replication::TxnManager, util::time_service::TimeService,
+    block_storage::BlockReader, liveness::proposer_election::ProposerElection,
+    state_replication::TxnManager, util::time_service::TimeService,
 };
 use anyhow::{bail, ensure, format_err, Context};
 use consensus_types::{
     block::Block,
-    block_data::BlockData,
-    common::{Author, Round},
+    block_data::{BlockData, MAX_FAILED_AUTHORS_TO_STORE},
+    common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
 
@@ -40,6 +41,9 @@ pub struct ProposalGenerator {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // Whether the proposals record the proposers of the skipped rounds as failed authors, which
+    // the proposer election relies on.
+    record_failed_authors: bool,
     // Last round that a proposal was generated
     last_round_generated: Mutex<Round>,
 }
@@ -51,6 +55,7 @@ impl ProposalGenerator {
         txn_manager: Arc<dyn TxnManager>,
         time_service: Arc<dyn TimeService>,
         max_block_size: u64,
+        record_failed_authors: bool,
     ) -> Self {
         Self {
             author,
@@ -58,6 +63,7 @@ impl ProposalGenerator {
             txn_manager,
             time_service,
             max_block_size,
+            record_failed_authors,
             last_round_generated: Mutex::new(0),
         }
     }
@@ -82,7 +88,13 @@ impl ProposalGenerator {
     /// 2. The round is provided by the caller.
     /// 3. In case a given round is not greater than the calculated parent, return an OldRound
     /// error.
-    pub async fn generate_proposal(&mut self, round: Round) -> anyhow::Result<BlockData> {
+    /// 4. The valid proposers of the rounds skipped since the parent are recorded as failed
+    /// authors if the proposer election relies on them.
+    pub async fn generate_proposal(
+        &mut self,
+        round: Round,
+        proposer_election: &(dyn ProposerElection + Send + Sync),
+    ) -> anyhow::Result<BlockData> {
         {
             let mut last_round_generated = self.last_round_generated.lock();
             if *last_round_generated < round {
@@ -97,7 +109,7 @@ impl ProposalGenerator {
         let (payload, timestamp) = if hqc.certified_block().has_reconfiguration() {
             // Reconfiguration rule - we propose empty blocks with parents' timestamp
             // after reconfiguration until it's committed
-            (vec![], hqc.certified_block().timestamp_usecs())
+            (Payload::empty(), hqc.certified_block().timestamp_usecs())
         } else {
             // One needs to hold the blocks with the references to the payloads while get_block is
             // being executed: pending blocks vector keeps all the pending ancestors of the extended branch.
@@ -111,7 +123,7 @@ impl ProposalGenerator {
 
             // Exclude all the pending transactions: these are all the ancestors of
             // parent (including) up to the root (including).
-            let exclude_payload: Vec<&Vec<_>> = pending_blocks
+            let exclude_payload: Vec<&Payload> = pending_blocks
                 .iter()
                 .flat_map(|block| block.payload())
                 .collect();
@@ -130,16 +142,48 @@ impl ProposalGenerator {
             (payload, timestamp.as_micros() as u64)
         };
 
+        let failed_authors =
+            self.compute_failed_authors(round, hqc.certified_block().round(), proposer_election);
+
         // create block proposal
         Ok(BlockData::new_proposal(
             payload,
             self.author,
+            failed_authors,
             round,
             timestamp,
             hqc.as_ref().clone(),
         ))
     }
 
+    /// Returns the valid proposers of the last rounds skipped between the parent round and the
+    /// round of the proposal (both excluded), at most [`MAX_FAILED_AUTHORS_TO_STORE`] of them.
+    /// Returns None if the proposals don't record failed authors.
+    pub fn compute_failed_authors(
+        &self,
+        round: Round,
+        parent_round: Round,
+        proposer_election: &(dyn ProposerElection + Send + Sync),
+    ) -> Option<Vec<(Round, Author)>> {
+        if !self.record_failed_authors {
+            return None;
+        }
+        let start = std::cmp::max(
+            parent_round + 1,
+            round.saturating_sub(MAX_FAILED_AUTHORS_TO_STORE as u64),
+        );
+        Some(
+            (start..round)
+                .map(|failed_round| {
+                    (
+                        failed_round,
+                        proposer_election.get_valid_proposer(failed_round),
+                    )
+                })
+                .collect(),
+        )
+    }
+
     fn ensure_highest_quorum_cert(&self, round: Round) -> anyhow::Result<Arc<QuorumCert>> {
         let hqc = self.block_store.highest_quorum_cert();
         ensure!(
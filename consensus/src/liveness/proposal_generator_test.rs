@@ -3,11 +3,16 @@
 
 use crate::{
     block_storage::BlockReader,
-    liveness::proposal_generator::ProposalGenerator,
+    liveness::{
+        proposal_generator::ProposalGenerator, rotating_proposer_election::RotatingProposer,
+    },
     test_utils::{build_empty_tree, MockTransactionManager, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
-use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
+use consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    block_data::MAX_FAILED_AUTHORS_TO_STORE,
+};
 use diem_types::validator_signer::ValidatorSigner;
 use std::sync::Arc;
 
@@ -21,18 +26,26 @@ async fn test_proposal_generation_empty_tree() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        false,
     );
+    let proposer_election = RotatingProposer::new(vec![signer.author()], 1);
     let genesis = block_store.ordered_root();
 
     // Generate proposals for an empty tree.
-    let proposal_data = proposal_generator.generate_proposal(1).await.unwrap();
+    let proposal_data = proposal_generator
+        .generate_proposal(1, &proposer_election)
+        .await
+        .unwrap();
     let proposal = Block::new_proposal_from_block_data(proposal_data, &signer);
     assert_eq!(proposal.parent_id(), genesis.id());
     assert_eq!(proposal.round(), 1);
     assert_eq!(proposal.quorum_cert().certified_block().id(), genesis.id());
 
     // Duplicate proposals on the same round are not allowed
-    let proposal_err = proposal_generator.generate_proposal(1).await.err();
+    let proposal_err = proposal_generator
+        .generate_proposal(1, &proposer_election)
+        .await
+        .err();
     assert!(proposal_err.is_some());
 }
 
@@ -46,7 +59,9 @@ async fn test_proposal_generation_parent() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        false,
     );
+    let proposer_election = RotatingProposer::new(vec![inserter.signer().author()], 1);
     let genesis = block_store.ordered_root();
     let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
     let b1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 2);
@@ -55,7 +70,7 @@ async fn test_proposal_generation_parent() {
     // generate proposals for an empty tree.
     assert_eq!(
         proposal_generator
-            .generate_proposal(10)
+            .generate_proposal(10, &proposer_election)
             .await
             .unwrap()
             .parent_id(),
@@ -64,14 +79,20 @@ async fn test_proposal_generation_parent() {
 
     // Once a1 is certified, it should be the one to choose from
     inserter.insert_qc_for_block(a1.as_ref(), None);
-    let a1_child_res = proposal_generator.generate_proposal(11).await.unwrap();
+    let a1_child_res = proposal_generator
+        .generate_proposal(11, &proposer_election)
+        .await
+        .unwrap();
     assert_eq!(a1_child_res.parent_id(), a1.id());
     assert_eq!(a1_child_res.round(), 11);
     assert_eq!(a1_child_res.quorum_cert().certified_block().id(), a1.id());
 
     // Once b1 is certified, it should be the one to choose from
     inserter.insert_qc_for_block(b1.as_ref(), None);
-    let b1_child_res = proposal_generator.generate_proposal(12).await.unwrap();
+    let b1_child_res = proposal_generator
+        .generate_proposal(12, &proposer_election)
+        .await
+        .unwrap();
     assert_eq!(b1_child_res.parent_id(), b1.id());
     assert_eq!(b1_child_res.round(), 12);
     assert_eq!(b1_child_res.quorum_cert().certified_block().id(), b1.id());
@@ -87,11 +108,69 @@ async fn test_old_proposal_generation() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        false,
     );
+    let proposer_election = RotatingProposer::new(vec![inserter.signer().author()], 1);
     let genesis = block_store.ordered_root();
     let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
     inserter.insert_qc_for_block(a1.as_ref(), None);
 
-    let proposal_err = proposal_generator.generate_proposal(1).await.err();
+    let proposal_err = proposal_generator
+        .generate_proposal(1, &proposer_election)
+        .await
+        .err();
     assert!(proposal_err.is_some());
 }
+
+#[tokio::test]
+async fn test_proposal_generation_failed_authors() {
+    let authors: Vec<_> = (0..4)
+        .map(|i| ValidatorSigner::random([i; 32]).author())
+        .collect();
+    let block_store = build_empty_tree();
+    let mut proposal_generator = ProposalGenerator::new(
+        authors[0],
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        1,
+        true,
+    );
+    let proposer_election = RotatingProposer::new(authors.clone(), 1);
+
+    // The proposers of the last skipped rounds since genesis are recorded, at most
+    // MAX_FAILED_AUTHORS_TO_STORE of them.
+    let round = MAX_FAILED_AUTHORS_TO_STORE as u64 + 3;
+    let proposal_data = proposal_generator
+        .generate_proposal(round, &proposer_election)
+        .await
+        .unwrap();
+    let expected_failed_authors: Vec<_> = (3..round)
+        .map(|failed_round| (failed_round, authors[failed_round as usize % authors.len()]))
+        .collect();
+    assert_eq!(
+        proposal_data.failed_authors(),
+        Some(&expected_failed_authors)
+    );
+
+    // No round is skipped when extending the parent at the previous round.
+    assert_eq!(
+        proposal_generator.compute_failed_authors(round + 1, round, &proposer_election),
+        Some(vec![])
+    );
+
+    // Without recording the failed authors, the proposals don't carry any.
+    let mut proposal_generator = ProposalGenerator::new(
+        authors[0],
+        block_store,
+        Arc::new(MockTransactionManager::new(None)),
+        Arc::new(SimulatedTimeService::new()),
+        1,
+        false,
+    );
+    let proposal_data = proposal_generator
+        .generate_proposal(round, &proposer_election)
+        .await
+        .unwrap();
+    assert_eq!(proposal_data.failed_authors(), None);
+}
@@ -7,7 +7,7 @@ use crate::liveness::{
 use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
 use diem_types::validator_signer::ValidatorSigner;
 
-use consensus_types::common::{Author, Round};
+use consensus_types::common::{Author, Payload, Round};
 use std::collections::HashMap;
 
 #[test]
@@ -33,16 +33,21 @@ fn test_round_proposer() {
     let quorum_cert = certificate_for_genesis();
 
     let good_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(),
         1,
         1,
         quorum_cert.clone(),
         &chosen_validator_signer_round1,
     );
-    let bad_proposal =
-        Block::new_proposal(vec![], 1, 2, quorum_cert.clone(), &another_validator_signer);
+    let bad_proposal = Block::new_proposal(
+        Payload::empty(),
+        1,
+        2,
+        quorum_cert.clone(),
+        &another_validator_signer,
+    );
     let next_good_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(),
         2,
         3,
         quorum_cert.clone(),
@@ -51,8 +56,13 @@ fn test_round_proposer() {
     // In round 3, send a proposal from chosen_author_round1 (which is also the default proposer).
     // The proposal should win because the map doesn't specify proposer for round 3 hence
     // falling back on the default proposer
-    let next_next_good_proposal =
-        Block::new_proposal(vec![], 3, 4, quorum_cert, &chosen_validator_signer_round1);
+    let next_next_good_proposal = Block::new_proposal(
+        Payload::empty(),
+        3,
+        4,
+        quorum_cert,
+        &chosen_validator_signer_round1,
+    );
 
     assert!(pe.is_valid_proposal(&good_proposal));
     assert!(!pe.is_valid_proposal(&bad_proposal));
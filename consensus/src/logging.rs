@@ -16,19 +16,29 @@ pub struct LogSchema {
 
 #[derive(Serialize)]
 pub enum LogEvent {
+    BroadcastBatch,
+    BroadcastProofOfStore,
     CommitViaBlock,
     CommitViaSync,
     HelpPeerSync,
     NewEpoch,
     NewRound,
+    ObserverSubscribe,
     Propose,
+    ReceiveBatch,
+    ReceiveBatchRetrieval,
     ReceiveBlockRetrieval,
+    ReceiveCommitDecision,
     ReceiveEpochChangeProof,
     ReceiveEpochRetrieval,
     ReceiveMessageFromDifferentEpoch,
+    ReceiveOrderedBlock,
+    ReceiveProofOfStore,
     ReceiveProposal,
+    ReceiveSignedBatchInfo,
     ReceiveSyncInfo,
     ReceiveVote,
+    RetrieveBatch,
     RetrieveBlock,
     StateSync,
     SyncToPeer,
@@ -4,6 +4,7 @@
 use crate::persistent_liveness_storage::PersistentLivenessStorage;
 use consensus_types::{
     block_data::BlockData,
+    proof_of_store::BatchInfo,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
@@ -124,4 +125,8 @@ impl TSafetyRules for MetricsSafetyRules {
             )
         })
     }
+
+    fn sign_batch_info(&mut self, batch_info: &BatchInfo) -> Result<Ed25519Signature, Error> {
+        self.retry(|inner| monitor!("safety_rules", inner.sign_batch_info(batch_info)))
+    }
 }
@@ -5,6 +5,7 @@ use crate::{
     counters,
     logging::LogEvent,
     network_interface::{ConsensusMsg, ConsensusNetworkEvents, ConsensusNetworkSender},
+    recorder::{ConsensusRecorder, RecordedEvent},
 };
 use anyhow::{anyhow, ensure};
 use bytes::Bytes;
@@ -13,6 +14,7 @@ use consensus_types::{
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse, MAX_BLOCKS_PER_REQUEST},
     common::Author,
     experimental::commit_decision::CommitDecision,
+    proof_of_store::{Batch, BatchRequest},
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
 };
@@ -32,9 +34,13 @@ use network::{
 };
 use std::{
     mem::{discriminant, Discriminant},
+    sync::Arc,
     time::Duration,
 };
 
+/// Size of the per-author queues of the quorum store messages, which are processed in order.
+const QUORUM_STORE_CHANNEL_SIZE: usize = 100;
+
 /// The block retrieval request is used internally for implementing RPC: the callback is executed
 /// for carrying the response
 #[derive(Debug)]
@@ -44,6 +50,15 @@ pub struct IncomingBlockRetrievalRequest {
     pub response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
 }
 
+/// The batch retrieval request of the quorum store, carrying the callback of the RPC like
+/// `IncomingBlockRetrievalRequest`.
+#[derive(Debug)]
+pub struct IncomingBatchRetrievalRequest {
+    pub req: BatchRequest,
+    pub protocol: ProtocolId,
+    pub response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+}
+
 /// Just a convenience struct to keep all the network proxy receiving queues in one place.
 /// Will be returned by the NetworkTask upon startup.
 pub struct NetworkReceivers {
@@ -53,6 +68,11 @@ pub struct NetworkReceivers {
         (AccountAddress, ConsensusMsg),
     >,
     pub block_retrieval: diem_channel::Receiver<AccountAddress, IncomingBlockRetrievalRequest>,
+    /// Provide a FIFO buffer for each Author for the quorum store messages, which unlike the
+    /// consensus messages cannot be superseded by the latest message of the same type.
+    pub quorum_store_messages:
+        diem_channel::Receiver<AccountAddress, (AccountAddress, ConsensusMsg)>,
+    pub batch_retrieval: diem_channel::Receiver<AccountAddress, IncomingBatchRetrievalRequest>,
 }
 
 /// Implements the actual networking support for all consensus messaging.
@@ -65,6 +85,8 @@ pub struct NetworkSender {
     // Note that we do not support self rpc requests as it might cause infinite recursive calls.
     self_sender: channel::Sender<Event<ConsensusMsg>>,
     validators: ValidatorVerifier,
+    // Records the sent messages when consensus recording is enabled.
+    recorder: Option<Arc<ConsensusRecorder>>,
 }
 
 impl NetworkSender {
@@ -79,9 +101,25 @@ impl NetworkSender {
             network_sender,
             self_sender,
             validators,
+            recorder: None,
         }
     }
 
+    /// Records the messages sent from now on.
+    pub fn set_recorder(&mut self, recorder: Arc<ConsensusRecorder>) {
+        self.recorder = Some(recorder);
+    }
+
+    fn record(&self, msg: &ConsensusMsg) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(RecordedEvent::Sent(msg.clone()));
+        }
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is fulfilled with BlockRetrievalResponse.
     pub async fn request_block(
@@ -114,6 +152,35 @@ impl NetworkSender {
         Ok(response)
     }
 
+    /// Tries to retrieve the batch with the given digest from the given peer, verifying that the
+    /// returned transactions match the digest.
+    pub async fn request_batch(
+        &mut self,
+        request: BatchRequest,
+        from: Author,
+        timeout: Duration,
+    ) -> anyhow::Result<Batch> {
+        ensure!(from != self.author, "Retrieve batch from self");
+        let digest = request.digest;
+        let msg = ConsensusMsg::BatchRequestMsg(Box::new(request));
+        let response_msg = monitor!(
+            "batch_retrieval",
+            self.network_sender.send_rpc(from, msg, timeout).await?
+        );
+        let batch = match response_msg {
+            ConsensusMsg::BatchMsg(batch) => *batch,
+            _ => return Err(anyhow!("Invalid response to request")),
+        };
+        ensure!(
+            *batch.digest() == digest,
+            "Retrieved {} doesn't match the requested digest {}",
+            batch,
+            digest
+        );
+        batch.verify()?;
+        Ok(batch)
+    }
+
     /// Tries to send the given msg to all the participants.
     ///
     /// The future is fulfilled as soon as the message put into the mpsc channel to network
@@ -121,6 +188,7 @@ impl NetworkSender {
     /// out. It does not give indication about when the message is delivered to the recipients,
     /// as well as there is no indication about the network failures.
     pub async fn broadcast(&mut self, msg: ConsensusMsg) {
+        self.record(&msg);
         // Directly send the message to ourself without going through network.
         let self_msg = Event::Message(self.author, msg.clone());
         if let Err(err) = self.self_sender.send(self_msg).await {
@@ -143,6 +211,7 @@ impl NetworkSender {
 
     /// Tries to send msg to given recipients.
     pub async fn send(&self, msg: ConsensusMsg, recipients: Vec<Author>) {
+        self.record(&msg);
         let network_sender = self.network_sender.clone();
         let mut self_sender = self.self_sender.clone();
         for peer in recipients {
@@ -202,6 +271,8 @@ pub struct NetworkTask {
         (AccountAddress, ConsensusMsg),
     >,
     block_retrieval_tx: diem_channel::Sender<AccountAddress, IncomingBlockRetrievalRequest>,
+    quorum_store_messages_tx: diem_channel::Sender<AccountAddress, (AccountAddress, ConsensusMsg)>,
+    batch_retrieval_tx: diem_channel::Sender<AccountAddress, IncomingBatchRetrievalRequest>,
     all_events: Box<dyn Stream<Item = Event<ConsensusMsg>> + Send + Unpin>,
 }
 
@@ -218,16 +289,30 @@ impl NetworkTask {
             1,
             Some(&counters::BLOCK_RETRIEVAL_CHANNEL_MSGS),
         );
+        let (quorum_store_messages_tx, quorum_store_messages) = diem_channel::new(
+            QueueStyle::FIFO,
+            QUORUM_STORE_CHANNEL_SIZE,
+            Some(&counters::QUORUM_STORE_CHANNEL_MSGS),
+        );
+        let (batch_retrieval_tx, batch_retrieval) = diem_channel::new(
+            QueueStyle::FIFO,
+            QUORUM_STORE_CHANNEL_SIZE,
+            Some(&counters::BATCH_RETRIEVAL_CHANNEL_MSGS),
+        );
         let all_events = Box::new(select(network_events, self_receiver));
         (
             NetworkTask {
                 consensus_messages_tx,
                 block_retrieval_tx,
+                quorum_store_messages_tx,
+                batch_retrieval_tx,
                 all_events,
             },
             NetworkReceivers {
                 consensus_messages,
                 block_retrieval,
+                quorum_store_messages,
+                batch_retrieval,
             },
         )
     }
@@ -236,6 +321,19 @@ impl NetworkTask {
         while let Some(message) = self.all_events.next().await {
             match message {
                 Event::Message(peer_id, msg) => {
+                    if let ConsensusMsg::BatchMsg(_)
+                    | ConsensusMsg::SignedBatchInfoMsg(_)
+                    | ConsensusMsg::ProofOfStoreMsg(_) = msg
+                    {
+                        if let Err(e) = self.quorum_store_messages_tx.push(peer_id, (peer_id, msg))
+                        {
+                            warn!(
+                                remote_peer = peer_id,
+                                error = ?e, "Error pushing quorum store msg",
+                            );
+                        }
+                        continue;
+                    }
                     if let Err(e) = self
                         .consensus_messages_tx
                         .push((peer_id, discriminant(&msg)), (peer_id, msg))
@@ -271,6 +369,22 @@ impl NetworkTask {
                             warn!(error = ?e, "diem channel closed");
                         }
                     }
+                    ConsensusMsg::BatchRequestMsg(request) => {
+                        debug!(
+                            remote_peer = peer_id,
+                            event = LogEvent::ReceiveBatchRetrieval,
+                            "{}",
+                            request
+                        );
+                        let req_with_callback = IncomingBatchRetrievalRequest {
+                            req: *request,
+                            protocol,
+                            response_sender: callback,
+                        };
+                        if let Err(e) = self.batch_retrieval_tx.push(peer_id, req_with_callback) {
+                            warn!(error = ?e, "diem channel closed");
+                        }
+                    }
                     _ => {
                         warn!(remote_peer = peer_id, "Unexpected msg: {:?}", msg);
                         continue;
@@ -11,6 +11,7 @@ use consensus_types::{
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse},
     epoch_retrieval::EpochRetrievalRequest,
     experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    proof_of_store::{Batch, BatchRequest, ProofOfStore, SignedBatchInfo},
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
@@ -62,6 +63,15 @@ pub enum ConsensusMsg {
     /// than 2f + 1 signatures on the commit proposal. This part is not on the critical path, but
     /// it can save slow machines to quickly confirm the execution result.
     CommitDecisionMsg(Box<CommitDecision>),
+    /// Batch of transactions broadcast by its author through the quorum store, also sent in
+    /// response to a BatchRequestMsg.
+    BatchMsg(Box<Batch>),
+    /// The signature of a validator storing a batch, sent back to the author of the batch.
+    SignedBatchInfoMsg(Box<SignedBatchInfo>),
+    /// Proof that a quorum of validators stores a batch, broadcast by the author of the batch.
+    ProofOfStoreMsg(Box<ProofOfStore>),
+    /// RPC to get a batch referenced by a proposal from a validator storing it.
+    BatchRequestMsg(Box<BatchRequest>),
 }
 
 /// The interface from Network to Consensus layer.
@@ -9,7 +9,7 @@ use crate::{
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
-    common::Author,
+    common::{Author, Payload},
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     vote::Vote,
@@ -75,7 +75,7 @@ pub struct NetworkPlayground {
     /// Allow test code to drop direct-send messages between peers.
     drop_config: Arc<RwLock<DropConfig>>,
     /// Allow test code to drop direct-send messages between peers per round.
-    drop_config_round: DropConfigRound,
+    drop_config_round: Arc<RwLock<DropConfigRound>>,
     /// An executor for spawning node outbound network event handlers
     executor: Handle,
     /// Maps authors to twins IDs
@@ -94,7 +94,7 @@ impl NetworkPlayground {
             outbound_msgs_tx,
             outbound_msgs_rx,
             drop_config: Arc::new(RwLock::new(DropConfig::default())),
-            drop_config_round: DropConfigRound::default(),
+            drop_config_round: Arc::new(RwLock::new(DropConfigRound::default())),
             executor,
             author_to_twin_ids: Arc::new(RwLock::new(AuthorToTwinIds::default())),
             peer_metadata_storage: PeerMetadataStorage::new(&[NetworkId::Validator]),
@@ -332,7 +332,9 @@ impl NetworkPlayground {
     fn is_message_dropped(&self, src: &TwinId, dst: &TwinId, msg: ConsensusMsg) -> bool {
         self.drop_config.read().is_message_dropped(src, dst)
             || Self::get_message_round(msg).map_or(false, |r| {
-                self.drop_config_round.is_message_dropped(src, dst, r)
+                self.drop_config_round
+                    .read()
+                    .is_message_dropped(src, dst, r)
             })
     }
 
@@ -348,7 +350,9 @@ impl NetworkPlayground {
 
     /// Check if the message from 'src_twin_id' to 'dst_twin_id' should be dropped in the given round
     pub fn is_message_dropped_round(&self, src: &TwinId, dst: &TwinId, round: u64) -> bool {
-        self.drop_config_round.is_message_dropped(src, dst, round)
+        self.drop_config_round
+            .read()
+            .is_message_dropped(src, dst, round)
     }
 
     /// Creates the given per round network partitions
@@ -363,6 +367,7 @@ impl NetworkPlayground {
                 partitions.iter().skip(i + 1).for_each(|p2| {
                     ret &= self
                         .drop_config_round
+                        .write()
                         .drop_message_for_round(*round, p1, p2)
                 })
             })
@@ -370,6 +375,16 @@ impl NetworkPlayground {
         ret
     }
 
+    /// Removes the network partitions of the given rounds after the given delay, so that the
+    /// messages of these rounds sent from then on are delivered.
+    pub fn heal_network_rounds_after(&self, rounds: Vec<u64>, delay: Duration) {
+        let drop_config_round = self.drop_config_round.clone();
+        self.executor.spawn(async move {
+            tokio::time::sleep(delay).await;
+            drop_config_round.write().heal_rounds(&rounds);
+        });
+    }
+
     pub async fn start(mut self) {
         // Take the next queued message
         while let Some((src_twin_id, net_req)) = self.outbound_msgs_rx.next().await {
@@ -471,6 +486,13 @@ impl DropConfigRound {
         let config = self.0.entry(round).or_insert_with(DropConfig::default);
         config.split_network(partition_first, partition_second)
     }
+
+    /// Remove the partitions of the rounds
+    fn heal_rounds(&mut self, rounds: &[u64]) {
+        for round in rounds {
+            self.0.remove(round);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -611,7 +633,7 @@ mod tests {
         );
         let previous_qc = certificate_for_genesis();
         let proposal = ProposalMsg::new(
-            Block::new_proposal(vec![], 1, 1, previous_qc.clone(), &signers[0]),
+            Block::new_proposal(Payload::empty(), 1, 1, previous_qc.clone(), &signers[0]),
             SyncInfo::new(previous_qc.clone(), previous_qc, None, None),
         );
         timed_block_on(&mut runtime, async {
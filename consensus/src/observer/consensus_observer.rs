@@ -0,0 +1,675 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    logging::{LogEvent, LogSchema},
+    observer::{
+        network::{ConsensusObserverEvents, ConsensusObserverSender, ObserverMessage},
+        publisher::ConsensusPublisher,
+    },
+};
+use anyhow::{bail, ensure, format_err, Context};
+use consensus_notifications::{ConsensusNotificationSender, ConsensusObserverPause};
+use consensus_types::{
+    block::Block,
+    common::{Payload, Round},
+    epoch_retrieval::EpochRetrievalRequest,
+    executed_block::ExecutedBlock,
+};
+use diem_config::{
+    config::{ConsensusObserverConfig, RoleType},
+    network_id::{NetworkId, PeerNetworkId},
+};
+use diem_logger::prelude::*;
+use diem_metrics::monitor;
+use diem_types::{
+    epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::SignedTransaction,
+};
+use executor_types::{BlockExecutor, StateComputeResult};
+use futures::{stream::select_all, StreamExt};
+use network::{
+    protocols::network::{ApplicationNetworkSender, Event},
+    transport::ConnectionMetadata,
+    ProtocolId,
+};
+use rand::seq::SliceRandom;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+#[path = "consensus_observer_test.rs"]
+mod consensus_observer_test;
+
+/// A chain of ordered blocks that is not committed yet.
+struct PendingBlocks {
+    blocks: Vec<Block>,
+    /// The results of executing the blocks, empty until the blocks are executed.
+    results: Vec<StateComputeResult>,
+}
+
+impl PendingBlocks {
+    fn is_executed(&self) -> bool {
+        !self.results.is_empty()
+    }
+}
+
+struct Subscription {
+    peer: PeerNetworkId,
+    last_message_time: Instant,
+}
+
+/// Follows consensus without participating in it: receives the ordered blocks and the commit
+/// decisions published by an upstream peer, verifies them against the current epoch state, and
+/// executes and commits the blocks locally. Falls back to state sync whenever the published
+/// blocks can't be committed locally, e.g. after missing some of them.
+///
+/// State sync only writes to storage while the observer waits for it to sync to a target, or
+/// while it has paused the observer, so the two never commit at the same time.
+pub struct ConsensusObserver {
+    config: ConsensusObserverConfig,
+    network_senders: HashMap<NetworkId, ConsensusObserverSender>,
+    block_executor: Box<dyn BlockExecutor>,
+    state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    pause: ConsensusObserverPause,
+    // Relays the verified messages to the observers downstream, if enabled.
+    publisher: Option<Arc<ConsensusPublisher>>,
+    epoch_state: EpochState,
+    // The latest committed ledger info.
+    root: LedgerInfo,
+    // The ordered blocks, keyed by the (epoch, round) of the last block of each chain.
+    pending_blocks: BTreeMap<(u64, Round), PendingBlocks>,
+    connected_peers: HashMap<PeerNetworkId, ConnectionMetadata>,
+    subscription: Option<Subscription>,
+}
+
+impl ConsensusObserver {
+    pub fn new(
+        config: ConsensusObserverConfig,
+        network_senders: HashMap<NetworkId, ConsensusObserverSender>,
+        block_executor: Box<dyn BlockExecutor>,
+        state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+        pause: ConsensusObserverPause,
+        publisher: Option<Arc<ConsensusPublisher>>,
+        root: LedgerInfo,
+        epoch_state: EpochState,
+    ) -> Self {
+        Self {
+            config,
+            network_senders,
+            block_executor,
+            state_sync_notifier,
+            pause,
+            publisher,
+            epoch_state,
+            root,
+            pending_blocks: BTreeMap::new(),
+            connected_peers: HashMap::new(),
+            subscription: None,
+        }
+    }
+
+    pub async fn start(mut self, network_events: Vec<(NetworkId, ConsensusObserverEvents)>) {
+        let events: Vec<_> = network_events
+            .into_iter()
+            .map(|(network_id, events)| events.map(move |e| (network_id, e)))
+            .collect();
+        let mut network_events = select_all(events);
+        let mut interval = tokio::time::interval(Duration::from_millis(
+            self.config.progress_check_interval_ms,
+        ));
+
+        info!("[ConsensusObserver] Started.");
+        loop {
+            tokio::select! {
+                Some((network_id, event)) = network_events.next() => {
+                    self.process_network_event(network_id, event).await;
+                }
+                _ = interval.tick() => {
+                    self.check_subscription();
+                }
+            }
+        }
+    }
+
+    async fn process_network_event(
+        &mut self,
+        network_id: NetworkId,
+        event: Event<ObserverMessage>,
+    ) {
+        match event {
+            Event::NewPeer(metadata) => {
+                let peer = PeerNetworkId::new(network_id, metadata.remote_peer_id);
+                self.connected_peers.insert(peer, metadata);
+            }
+            Event::LostPeer(metadata) => {
+                let peer = PeerNetworkId::new(network_id, metadata.remote_peer_id);
+                self.connected_peers.remove(&peer);
+                if let Some(publisher) = &self.publisher {
+                    publisher.remove_subscriber(&peer);
+                }
+                if self.is_subscribed_to(&peer) {
+                    self.subscription = None;
+                    self.check_subscription();
+                }
+            }
+            Event::Message(peer_id, message) => {
+                let peer = PeerNetworkId::new(network_id, peer_id);
+                if let Err(e) = self.process_message(peer, message).await {
+                    warn!(
+                        remote_peer = peer_id,
+                        error = ?e,
+                        "[ConsensusObserver] Failed to process message"
+                    );
+                }
+            }
+            unexpected_event => {
+                warn!(
+                    "[ConsensusObserver] Received unexpected network event: {:?}",
+                    unexpected_event
+                );
+            }
+        }
+    }
+
+    async fn process_message(
+        &mut self,
+        peer: PeerNetworkId,
+        message: ObserverMessage,
+    ) -> anyhow::Result<()> {
+        match message {
+            ObserverMessage::Subscribe
+            | ObserverMessage::Unsubscribe
+            | ObserverMessage::EpochRetrievalRequest(_) => match &self.publisher {
+                Some(publisher) => publisher.process_message(peer, message),
+                None => bail!(
+                    "[ConsensusObserver] Publisher disabled, ignoring {:?}",
+                    message
+                ),
+            },
+            ObserverMessage::OrderedBlock {
+                blocks,
+                ordered_proof,
+            } => {
+                self.check_subscription_message(&peer)?;
+                self.process_ordered_block(peer, blocks, ordered_proof)
+                    .await
+            }
+            ObserverMessage::CommitDecision { commit_proof } => {
+                self.check_subscription_message(&peer)?;
+                self.process_commit_decision(peer, commit_proof).await
+            }
+            ObserverMessage::EpochChangeProof(proof) => {
+                self.check_subscription_message(&peer)?;
+                self.process_epoch_change_proof(peer, *proof).await
+            }
+        }
+    }
+
+    /// Only the messages of the subscribed peer are processed, which also keep the subscription
+    /// alive.
+    fn check_subscription_message(&mut self, peer: &PeerNetworkId) -> anyhow::Result<()> {
+        match &mut self.subscription {
+            Some(subscription) if subscription.peer == *peer => {
+                subscription.last_message_time = Instant::now();
+                Ok(())
+            }
+            _ => bail!("[ConsensusObserver] Not subscribed to {}", peer),
+        }
+    }
+
+    async fn process_ordered_block(
+        &mut self,
+        peer: PeerNetworkId,
+        blocks: Vec<Block>,
+        ordered_proof: LedgerInfoWithSignatures,
+    ) -> anyhow::Result<()> {
+        let key = block_key(ordered_proof.ledger_info());
+        debug!(
+            LogSchema::new(LogEvent::ReceiveOrderedBlock)
+                .remote_peer(peer.peer_id())
+                .epoch(key.0)
+                .round(key.1),
+            num_blocks = blocks.len(),
+        );
+        if !self.check_epoch(&peer, key.0)?
+            || key <= block_key(&self.root)
+            || self.pending_blocks.contains_key(&key)
+        {
+            return Ok(());
+        }
+        self.verify_ordered_block(&blocks, &ordered_proof)?;
+
+        let num_pending_blocks: usize = self
+            .pending_blocks
+            .values()
+            .map(|pending| pending.blocks.len())
+            .sum();
+        ensure!(
+            num_pending_blocks + blocks.len() <= self.config.max_num_pending_blocks,
+            "[ConsensusObserver] Too many pending blocks, dropping the ordered block"
+        );
+
+        if let Some(publisher) = &self.publisher {
+            publisher.publish(ObserverMessage::OrderedBlock {
+                blocks: blocks.clone(),
+                ordered_proof,
+            });
+        }
+        self.pending_blocks.insert(
+            key,
+            PendingBlocks {
+                blocks,
+                results: vec![],
+            },
+        );
+        self.execute_pending_blocks()
+    }
+
+    fn verify_ordered_block(
+        &self,
+        blocks: &[Block],
+        ordered_proof: &LedgerInfoWithSignatures,
+    ) -> anyhow::Result<()> {
+        let last_block = blocks
+            .last()
+            .ok_or_else(|| format_err!("[ConsensusObserver] Empty ordered block"))?;
+        ensure!(
+            last_block.id() == ordered_proof.commit_info().id(),
+            "[ConsensusObserver] Ordered block {} doesn't match its proof {}",
+            last_block.id(),
+            ordered_proof.commit_info()
+        );
+        for (parent, child) in blocks.iter().zip(blocks.iter().skip(1)) {
+            ensure!(
+                child.parent_id() == parent.id(),
+                "[ConsensusObserver] Ordered blocks are not a chain: {} is not the parent of {}",
+                parent.id(),
+                child.id()
+            );
+        }
+        ensure!(
+            blocks
+                .iter()
+                .all(|block| block.epoch() == self.epoch_state.epoch),
+            "[ConsensusObserver] Ordered blocks are not all in epoch {}",
+            self.epoch_state.epoch
+        );
+        ordered_proof
+            .verify_signatures(&self.epoch_state.verifier)
+            .context("[ConsensusObserver] Invalid ordered proof")?;
+        Ok(())
+    }
+
+    /// Executes the pending blocks extending the executed chain, in order. Stops at the first
+    /// gap, which is filled by state sync once the blocks after it are committed. The blocks
+    /// referencing batches of the quorum store are gaps as well: an observer doesn't receive the
+    /// batches.
+    fn execute_pending_blocks(&mut self) -> anyhow::Result<()> {
+        let mut parent_id = self.block_executor.committed_block_id()?;
+        for pending in self.pending_blocks.values_mut() {
+            if !pending.is_executed() {
+                if pending.blocks[0].parent_id() != parent_id {
+                    break;
+                }
+                let payloads: Option<Vec<_>> =
+                    pending.blocks.iter().map(direct_payload_txns).collect();
+                let payloads = match payloads {
+                    Some(payloads) => payloads,
+                    None => break,
+                };
+                let mut results = vec![];
+                for (block, payload_txns) in pending.blocks.iter().zip(payloads) {
+                    results.push(monitor!(
+                        "observer_execute_block",
+                        self.block_executor.execute_block(
+                            (block.id(), block.transactions_to_execute(payload_txns)),
+                            parent_id,
+                        )?
+                    ));
+                    parent_id = block.id();
+                }
+                pending.results = results;
+            }
+            parent_id = pending.blocks[pending.blocks.len() - 1].id();
+        }
+        Ok(())
+    }
+
+    async fn process_commit_decision(
+        &mut self,
+        peer: PeerNetworkId,
+        commit_proof: LedgerInfoWithSignatures,
+    ) -> anyhow::Result<()> {
+        let key = block_key(commit_proof.ledger_info());
+        debug!(LogSchema::new(LogEvent::ReceiveCommitDecision)
+            .remote_peer(peer.peer_id())
+            .epoch(key.0)
+            .round(key.1),);
+        if !self.check_epoch(&peer, key.0)? || key <= block_key(&self.root) {
+            return Ok(());
+        }
+        commit_proof
+            .verify_signatures(&self.epoch_state.verifier)
+            .context("[ConsensusObserver] Invalid commit proof")?;
+
+        if let Some(publisher) = &self.publisher {
+            publisher.publish(ObserverMessage::CommitDecision {
+                commit_proof: commit_proof.clone(),
+            });
+        }
+        let committed_result = self
+            .pending_blocks
+            .get(&key)
+            .filter(|pending| {
+                pending.blocks[pending.blocks.len() - 1].id() == commit_proof.commit_info().id()
+            })
+            .and_then(|pending| pending.results.last());
+        match committed_result {
+            Some(result)
+                if result.root_hash() == commit_proof.commit_info().executed_state_id()
+                    && result.version() == commit_proof.commit_info().version() =>
+            {
+                self.commit(commit_proof).await
+            }
+            Some(result) => {
+                error!(
+                    "[ConsensusObserver] Executed state {} at version {} doesn't match the commit {}",
+                    result.root_hash(),
+                    result.version(),
+                    commit_proof.commit_info()
+                );
+                self.sync_to(commit_proof).await
+            }
+            None => self.sync_to(commit_proof).await,
+        }
+    }
+
+    /// Commits the executed blocks up to the one committed by `commit_proof`.
+    async fn commit(&mut self, commit_proof: LedgerInfoWithSignatures) -> anyhow::Result<()> {
+        let (epoch, round) = block_key(commit_proof.ledger_info());
+        // All the blocks committed along with the last one must have been executed, otherwise
+        // state sync has to fill them in.
+        if self
+            .pending_blocks
+            .range(..=(epoch, round))
+            .any(|(_key, pending)| pending.blocks.len() != pending.results.len())
+        {
+            warn!(
+                "[ConsensusObserver] Some of the blocks committed by {} are not executed",
+                commit_proof.commit_info()
+            );
+            return self.sync_to(commit_proof).await;
+        }
+        let uncommitted = self.pending_blocks.split_off(&(epoch, round + 1));
+        let committed = std::mem::replace(&mut self.pending_blocks, uncommitted);
+
+        let mut block_ids = Vec::new();
+        let mut txns = Vec::new();
+        let mut reconfig_events = Vec::new();
+        for (_key, pending) in committed {
+            for (block, result) in itertools::zip_eq(pending.blocks, pending.results) {
+                let payload_txns = direct_payload_txns(&block).unwrap_or_default();
+                let block = ExecutedBlock::new(block, result);
+                block_ids.push(block.id());
+                txns.extend(block.transactions_to_commit(payload_txns));
+                reconfig_events.extend(block.reconfig_event());
+            }
+        }
+        let num_blocks = block_ids.len();
+
+        // The blocks are dropped from the pending ones either way, as state sync commits them if
+        // the observer can't.
+        let commit_result = monitor!(
+            "observer_commit_block",
+            self.pause.commit_unless_paused(|| self
+                .block_executor
+                .commit_blocks(block_ids, commit_proof.clone()))
+        );
+        match commit_result {
+            Some(Ok(())) => (),
+            Some(Err(e)) => {
+                error!(
+                    error = ?e,
+                    "[ConsensusObserver] Failed to commit {}",
+                    commit_proof.commit_info()
+                );
+                return self.sync_to(commit_proof).await;
+            }
+            None => {
+                info!(
+                    "[ConsensusObserver] Paused by state sync, syncing to {} instead of committing",
+                    commit_proof.commit_info()
+                );
+                return self.sync_to(commit_proof).await;
+            }
+        }
+        counters::OBSERVER_COMMITTED_BLOCKS_COUNT.inc_by(num_blocks as u64);
+        info!(
+            LogSchema::new(LogEvent::CommitViaBlock)
+                .epoch(epoch)
+                .round(round),
+            num_blocks = num_blocks,
+            version = commit_proof.commit_info().version(),
+        );
+
+        if let Err(e) = monitor!(
+            "observer_notify_state_sync",
+            self.state_sync_notifier
+                .notify_new_commit(txns, reconfig_events)
+                .await
+        ) {
+            error!(error = ?e, "Failed to notify state synchronizer");
+        }
+
+        self.update_root(commit_proof.ledger_info().clone());
+        self.execute_pending_blocks()
+    }
+
+    /// Falls back to state sync to reach `target`, which has been verified.
+    async fn sync_to(&mut self, target: LedgerInfoWithSignatures) -> anyhow::Result<()> {
+        let (epoch, round) = block_key(target.ledger_info());
+        info!(
+            LogSchema::new(LogEvent::StateSync)
+                .epoch(epoch)
+                .round(round),
+            "[ConsensusObserver] Falling back to state sync to version {}",
+            target.commit_info().version()
+        );
+        counters::OBSERVER_STATE_SYNC_COUNT.inc();
+
+        let result = monitor!(
+            "observer_sync_to",
+            self.state_sync_notifier
+                .sync_to_target(target.clone())
+                .await
+        );
+        // After the state synchronization, reset the cache of BlockExecutor to guarantee the
+        // latest committed state is up to date. The results of executing the pending blocks are
+        // gone along with the cache, so the blocks are executed again.
+        for pending in self.pending_blocks.values_mut() {
+            pending.results.clear();
+        }
+        self.block_executor.reset()?;
+        result.context("[ConsensusObserver] Failed to sync to target")?;
+
+        info!(LogSchema::new(LogEvent::CommitViaSync)
+            .epoch(epoch)
+            .round(round),);
+        self.update_root(target.ledger_info().clone());
+        self.execute_pending_blocks()
+    }
+
+    async fn process_epoch_change_proof(
+        &mut self,
+        peer: PeerNetworkId,
+        proof: EpochChangeProof,
+    ) -> anyhow::Result<()> {
+        info!(LogSchema::new(LogEvent::ReceiveEpochChangeProof)
+            .remote_peer(peer.peer_id())
+            .epoch(self.epoch_state.epoch),);
+        let ledger_info = proof
+            .verify(&self.epoch_state)
+            .context("[ConsensusObserver] Invalid epoch change proof")?
+            .clone();
+        ensure!(
+            ledger_info.ledger_info().ends_epoch(),
+            "[ConsensusObserver] Epoch change proof doesn't end with an epoch change"
+        );
+        if block_key(ledger_info.ledger_info()) > block_key(&self.root) {
+            self.sync_to(ledger_info).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether a message of `epoch` can be verified in the current epoch. The epoch
+    /// change proof is requested from the peer if the message is from a future epoch.
+    fn check_epoch(&self, peer: &PeerNetworkId, epoch: u64) -> anyhow::Result<bool> {
+        match epoch.cmp(&self.epoch_state.epoch) {
+            Ordering::Equal => Ok(true),
+            Ordering::Less => Ok(false),
+            Ordering::Greater => {
+                let request = EpochRetrievalRequest {
+                    start_epoch: self.epoch_state.epoch,
+                    end_epoch: epoch,
+                };
+                self.send_to(
+                    peer,
+                    ObserverMessage::EpochRetrievalRequest(Box::new(request)),
+                )?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Moves the root to the newly committed `ledger_info`, dropping the blocks it commits and
+    /// entering the next epoch if it ends the epoch.
+    fn update_root(&mut self, ledger_info: LedgerInfo) {
+        let (epoch, round) = block_key(&ledger_info);
+        self.pending_blocks = self.pending_blocks.split_off(&(epoch, round + 1));
+        if let Some(next_epoch_state) = ledger_info.next_epoch_state() {
+            info!(
+                LogSchema::new(LogEvent::NewEpoch).epoch(next_epoch_state.epoch),
+                "[ConsensusObserver] Entering new epoch"
+            );
+            self.epoch_state = next_epoch_state.clone();
+            self.pending_blocks = self.pending_blocks.split_off(&(next_epoch_state.epoch, 0));
+        }
+        self.root = ledger_info;
+    }
+
+    /// Subscribes to a new peer if there is no subscription, or if the subscribed peer has been
+    /// silent for too long.
+    fn check_subscription(&mut self) {
+        let mut previous_peer = None;
+        if let Some(subscription) = &self.subscription {
+            let timeout = Duration::from_millis(self.config.subscription_timeout_ms);
+            if subscription.last_message_time.elapsed() < timeout {
+                return;
+            }
+            warn!(
+                remote_peer = subscription.peer.peer_id(),
+                "[ConsensusObserver] No message from the subscribed peer for {:?}, resubscribing",
+                timeout
+            );
+            previous_peer = Some(subscription.peer);
+            self.unsubscribe();
+        }
+
+        if let Some(peer) = self.select_peer(previous_peer) {
+            match self.send_to(&peer, ObserverMessage::Subscribe) {
+                Ok(()) => {
+                    info!(
+                        LogSchema::new(LogEvent::ObserverSubscribe).remote_peer(peer.peer_id()),
+                        "[ConsensusObserver] Subscribed on {}",
+                        peer.network_id()
+                    );
+                    self.subscription = Some(Subscription {
+                        peer,
+                        last_message_time: Instant::now(),
+                    });
+                }
+                Err(e) => warn!(error = ?e, "[ConsensusObserver] Failed to subscribe"),
+            }
+        }
+    }
+
+    fn unsubscribe(&mut self) {
+        if let Some(subscription) = self.subscription.take() {
+            if let Err(e) = self.send_to(&subscription.peer, ObserverMessage::Unsubscribe) {
+                warn!(error = ?e, "[ConsensusObserver] Failed to unsubscribe");
+            }
+        }
+    }
+
+    fn is_subscribed_to(&self, peer: &PeerNetworkId) -> bool {
+        self.subscription
+            .as_ref()
+            .map_or(false, |subscription| subscription.peer == *peer)
+    }
+
+    /// Picks a random upstream peer supporting the observer protocol, preferring the networks
+    /// and the roles the same way as state sync prioritizes its upstream peers. The previous
+    /// peer is only picked again if there is no other candidate.
+    fn select_peer(&self, previous_peer: Option<PeerNetworkId>) -> Option<PeerNetworkId> {
+        let mut candidates: Vec<_> = self
+            .connected_peers
+            .iter()
+            .filter(|(_peer, metadata)| {
+                metadata
+                    .application_protocols
+                    .contains(ProtocolId::ConsensusObserverDirectSend)
+            })
+            .filter_map(|(peer, metadata)| {
+                let network_id = peer.network_id();
+                network_id
+                    .upstream_roles(&RoleType::FullNode)
+                    .iter()
+                    .position(|role| *role == metadata.role)
+                    .map(|role_priority| ((network_id, role_priority), *peer))
+            })
+            .collect();
+        if candidates.len() > 1 {
+            candidates.retain(|(_priority, peer)| Some(*peer) != previous_peer);
+        }
+
+        let best_priority = candidates.iter().map(|(priority, _peer)| *priority).min()?;
+        let best_peers: Vec<_> = candidates
+            .into_iter()
+            .filter(|(priority, _peer)| *priority == best_priority)
+            .map(|(_priority, peer)| peer)
+            .collect();
+        best_peers.choose(&mut rand::thread_rng()).copied()
+    }
+
+    fn send_to(&self, peer: &PeerNetworkId, message: ObserverMessage) -> anyhow::Result<()> {
+        let sender = self
+            .network_senders
+            .get(&peer.network_id())
+            .with_context(|| format!("[ConsensusObserver] Unknown network of {}", peer))?;
+        sender
+            .send_to(peer.peer_id(), message)
+            .with_context(|| format!("[ConsensusObserver] Failed to send to {}", peer))
+    }
+}
+
+/// Returns the transactions of the payload of the block, unless they're in the quorum store.
+fn direct_payload_txns(block: &Block) -> Option<Vec<SignedTransaction>> {
+    match block.payload() {
+        None => Some(vec![]),
+        Some(Payload::DirectMempool(txns)) => Some(txns.clone()),
+        Some(Payload::InQuorumStore(proofs)) if proofs.is_empty() => Some(vec![]),
+        Some(Payload::InQuorumStore(_)) => None,
+    }
+}
+
+fn block_key(ledger_info: &LedgerInfo) -> (u64, Round) {
+    (ledger_info.epoch(), ledger_info.round())
+}
@@ -0,0 +1,580 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use channel::{diem_channel, message_queues::QueueStyle};
+use consensus_notifications::Error as NotificationError;
+use consensus_types::{
+    block::block_test_utils::{certificate_for_genesis, placeholder_certificate_for_block},
+    common::Payload,
+};
+use diem_config::config::PeerRole;
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_types::{
+    block_info::BlockInfo,
+    contract_event::ContractEvent,
+    transaction::{Transaction, TransactionStatus},
+    validator_signer::ValidatorSigner,
+    validator_verifier::random_validator_verifier,
+    vm_status::KeptVMStatus,
+    PeerId,
+};
+use executor_types::Error as ExecutionError;
+use futures::{executor::block_on, FutureExt};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::{network::NewNetworkSender, wire::handshake::v1::ProtocolIdSet},
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    iter::FromIterator,
+};
+
+struct ExecutorState {
+    committed_block_id: HashValue,
+    executed_block_ids: Vec<HashValue>,
+    // The executed blocks that can be committed, i.e., the ones executed since the last reset
+    cached_block_ids: HashSet<HashValue>,
+    committed_block_ids: Vec<HashValue>,
+    num_resets: usize,
+    fail_commits: bool,
+}
+
+/// Executes every block to a state identified by the block id.
+#[derive(Clone)]
+struct MockBlockExecutor {
+    state: Arc<Mutex<ExecutorState>>,
+}
+
+impl MockBlockExecutor {
+    fn new(committed_block_id: HashValue) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ExecutorState {
+                committed_block_id,
+                executed_block_ids: vec![],
+                cached_block_ids: HashSet::new(),
+                committed_block_ids: vec![],
+                num_resets: 0,
+                fail_commits: false,
+            })),
+        }
+    }
+}
+
+impl BlockExecutor for MockBlockExecutor {
+    fn committed_block_id(&self) -> Result<HashValue, ExecutionError> {
+        Ok(self.state.lock().committed_block_id)
+    }
+
+    fn reset(&self) -> Result<(), ExecutionError> {
+        let mut state = self.state.lock();
+        state.num_resets += 1;
+        state.cached_block_ids.clear();
+        Ok(())
+    }
+
+    fn execute_block(
+        &self,
+        block: (HashValue, Vec<Transaction>),
+        _parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, ExecutionError> {
+        let (block_id, txns) = block;
+        let mut state = self.state.lock();
+        state.executed_block_ids.push(block_id);
+        state.cached_block_ids.insert(block_id);
+        Ok(StateComputeResult::new(
+            block_id,
+            vec![],
+            1,
+            vec![],
+            0,
+            None,
+            vec![TransactionStatus::Keep(KeptVMStatus::Executed); txns.len()],
+            vec![],
+            vec![],
+        ))
+    }
+
+    fn commit_blocks(
+        &self,
+        block_ids: Vec<HashValue>,
+        _ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(), ExecutionError> {
+        let mut state = self.state.lock();
+        if state.fail_commits {
+            return Err(ExecutionError::InternalError {
+                error: "Injected commit failure".into(),
+            });
+        }
+        if let Some(block_id) = block_ids
+            .iter()
+            .find(|block_id| !state.cached_block_ids.contains(block_id))
+        {
+            return Err(ExecutionError::BlockNotFound(*block_id));
+        }
+        state.committed_block_id = *block_ids.last().unwrap();
+        state.committed_block_ids.extend(block_ids);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct MockStateSyncNotifier {
+    num_committed_txns: Arc<Mutex<Vec<usize>>>,
+    sync_targets: Arc<Mutex<Vec<LedgerInfoWithSignatures>>>,
+}
+
+impl MockStateSyncNotifier {
+    fn new() -> Self {
+        Self {
+            num_committed_txns: Arc::new(Mutex::new(vec![])),
+            sync_targets: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ConsensusNotificationSender for MockStateSyncNotifier {
+    async fn notify_new_commit(
+        &self,
+        transactions: Vec<Transaction>,
+        _reconfiguration_events: Vec<ContractEvent>,
+    ) -> Result<(), NotificationError> {
+        self.num_committed_txns.lock().push(transactions.len());
+        Ok(())
+    }
+
+    async fn sync_to_target(
+        &self,
+        target: LedgerInfoWithSignatures,
+    ) -> Result<(), NotificationError> {
+        self.sync_targets.lock().push(target);
+        Ok(())
+    }
+}
+
+struct ObserverTest {
+    observer: ConsensusObserver,
+    signers: Vec<ValidatorSigner>,
+    executor: MockBlockExecutor,
+    notifier: MockStateSyncNotifier,
+    pause: ConsensusObserverPause,
+    network_reqs_rx: diem_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    publisher_peer: PeerNetworkId,
+}
+
+impl ObserverTest {
+    fn new() -> Self {
+        let (signers, verifier) = random_validator_verifier(4, None, false);
+        let genesis = LedgerInfo::mock_genesis(None);
+        let executor =
+            MockBlockExecutor::new(Block::make_genesis_block_from_ledger_info(&genesis).id());
+        let notifier = MockStateSyncNotifier::new();
+        let pause = ConsensusObserverPause::default();
+
+        let (network_reqs_tx, network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let network_sender = ConsensusObserverSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+
+        let mut observer = ConsensusObserver::new(
+            ConsensusObserverConfig::default(),
+            HashMap::from_iter(vec![(NetworkId::Vfn, network_sender)]),
+            Box::new(executor.clone()),
+            Box::new(notifier.clone()),
+            pause.clone(),
+            None,
+            genesis,
+            EpochState { epoch: 1, verifier },
+        );
+        let publisher_peer = PeerNetworkId::new(NetworkId::Vfn, signers[0].author());
+        observer.subscription = Some(Subscription {
+            peer: publisher_peer,
+            last_message_time: Instant::now(),
+        });
+
+        Self {
+            observer,
+            signers,
+            executor,
+            notifier,
+            pause,
+            network_reqs_rx,
+            publisher_peer,
+        }
+    }
+
+    /// Creates a chain of blocks in epoch 1 following the genesis block, one per round.
+    fn create_blocks(&self, num_blocks: u64) -> Vec<Block> {
+        let mut blocks: Vec<Block> = vec![];
+        for round in 1..=num_blocks {
+            let quorum_cert = match blocks.last() {
+                Some(parent) => placeholder_certificate_for_block(
+                    vec![&self.signers[0]],
+                    parent.id(),
+                    parent.round(),
+                    parent.parent_id(),
+                    parent.round() - 1,
+                ),
+                None => certificate_for_genesis(),
+            };
+            blocks.push(Block::new_proposal(
+                Payload::empty(),
+                round,
+                round,
+                quorum_cert,
+                &self.signers[0],
+            ));
+        }
+        blocks
+    }
+
+    fn receive(&mut self, message: ObserverMessage) {
+        block_on(self.observer.process_network_event(
+            NetworkId::Vfn,
+            Event::Message(self.publisher_peer.peer_id(), message),
+        ));
+    }
+
+    fn receive_ordered_block(&mut self, blocks: &[Block]) {
+        let ordered_proof = create_proof(blocks.last().unwrap(), &self.signers);
+        self.receive(ObserverMessage::OrderedBlock {
+            blocks: blocks.to_vec(),
+            ordered_proof,
+        });
+    }
+
+    fn receive_commit_decision(&mut self, block: &Block) {
+        let commit_proof = create_proof(block, &self.signers);
+        self.receive(ObserverMessage::CommitDecision { commit_proof });
+    }
+
+    fn executed_block_ids(&self) -> Vec<HashValue> {
+        self.executor.state.lock().executed_block_ids.clone()
+    }
+
+    fn committed_block_ids(&self) -> Vec<HashValue> {
+        self.executor.state.lock().committed_block_ids.clone()
+    }
+}
+
+/// Signs a ledger info of the block with the state the mock executor executes it to.
+fn create_proof(block: &Block, signers: &[ValidatorSigner]) -> LedgerInfoWithSignatures {
+    create_proof_in_epoch(block, block.epoch(), signers)
+}
+
+/// Signs a ledger info of the block in `epoch` with the state the mock executor executes it to.
+fn create_proof_in_epoch(
+    block: &Block,
+    epoch: u64,
+    signers: &[ValidatorSigner],
+) -> LedgerInfoWithSignatures {
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(
+            epoch,
+            block.round(),
+            block.id(),
+            block.id(),
+            0,
+            block.timestamp_usecs(),
+            None,
+        ),
+        HashValue::zero(),
+    );
+    let signatures = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.sign(&ledger_info)))
+        .collect::<BTreeMap<_, _>>();
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
+
+fn observer_peer_metadata(peer_id: PeerId, role: PeerRole) -> ConnectionMetadata {
+    let mut metadata = ConnectionMetadata::mock(peer_id);
+    metadata.role = role;
+    metadata.application_protocols =
+        ProtocolIdSet::from_iter([ProtocolId::ConsensusObserverDirectSend].iter());
+    metadata
+}
+
+fn block_ids(blocks: &[Block]) -> Vec<HashValue> {
+    blocks.iter().map(|block| block.id()).collect()
+}
+
+fn block_ids_of(ledger_infos: &[LedgerInfoWithSignatures]) -> Vec<HashValue> {
+    ledger_infos
+        .iter()
+        .map(|ledger_info| ledger_info.commit_info().id())
+        .collect()
+}
+
+#[test]
+fn test_execute_and_commit_ordered_blocks() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(3);
+
+    test.receive_ordered_block(&blocks[..2]);
+    test.receive_ordered_block(&blocks[2..]);
+    assert_eq!(test.executed_block_ids(), block_ids(&blocks));
+    assert!(test.committed_block_ids().is_empty());
+
+    // Committing the last block commits its ancestors too.
+    test.receive_commit_decision(&blocks[2]);
+    assert_eq!(test.committed_block_ids(), block_ids(&blocks));
+    assert_eq!(*test.notifier.num_committed_txns.lock(), vec![3]);
+    assert!(test.notifier.sync_targets.lock().is_empty());
+    assert!(test.observer.pending_blocks.is_empty());
+    assert_eq!(test.observer.root.round(), 3);
+
+    // Messages about committed blocks are ignored.
+    test.receive_ordered_block(&blocks[2..]);
+    test.receive_commit_decision(&blocks[2]);
+    assert_eq!(test.executed_block_ids().len(), 3);
+    assert_eq!(test.committed_block_ids().len(), 3);
+}
+
+#[test]
+fn test_execute_blocks_after_missing_parent() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(2);
+
+    // The child waits for its parent to be ordered.
+    test.receive_ordered_block(&blocks[1..]);
+    assert!(test.executed_block_ids().is_empty());
+    test.receive_ordered_block(&blocks[..1]);
+    assert_eq!(test.executed_block_ids(), block_ids(&blocks));
+}
+
+#[test]
+fn test_reject_invalid_ordered_blocks() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(3);
+
+    // Not enough signatures.
+    let ordered_proof = create_proof(&blocks[0], &test.signers[..1]);
+    test.receive(ObserverMessage::OrderedBlock {
+        blocks: blocks[..1].to_vec(),
+        ordered_proof,
+    });
+    // Not a chain.
+    let ordered_proof = create_proof(&blocks[2], &test.signers);
+    test.receive(ObserverMessage::OrderedBlock {
+        blocks: vec![blocks[0].clone(), blocks[2].clone()],
+        ordered_proof,
+    });
+    // The proof doesn't order the blocks.
+    let ordered_proof = create_proof(&blocks[1], &test.signers);
+    test.receive(ObserverMessage::OrderedBlock {
+        blocks: blocks[..1].to_vec(),
+        ordered_proof,
+    });
+    assert!(test.executed_block_ids().is_empty());
+    assert!(test.observer.pending_blocks.is_empty());
+
+    // Messages from peers other than the subscribed one are ignored.
+    test.publisher_peer = PeerNetworkId::new(NetworkId::Vfn, test.signers[1].author());
+    test.receive_ordered_block(&blocks[..1]);
+    assert!(test.executed_block_ids().is_empty());
+}
+
+#[test]
+fn test_sync_to_unknown_commit() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(3);
+
+    // The observer missed the first ordered block, so it can't execute the others.
+    test.receive_ordered_block(&blocks[1..2]);
+    test.receive_ordered_block(&blocks[2..]);
+    assert!(test.executed_block_ids().is_empty());
+
+    // State sync commits the block instead, and the executor continues from it.
+    test.executor.state.lock().committed_block_id = blocks[1].id();
+    test.receive_commit_decision(&blocks[1]);
+    assert!(test.committed_block_ids().is_empty());
+    assert_eq!(test.executor.state.lock().num_resets, 1);
+    assert_eq!(
+        block_ids_of(&test.notifier.sync_targets.lock()),
+        vec![blocks[1].id()]
+    );
+    assert_eq!(test.observer.root.round(), 2);
+
+    // The blocks after the synced one are executed on top of it.
+    assert_eq!(test.executed_block_ids(), vec![blocks[2].id()]);
+    test.receive_commit_decision(&blocks[2]);
+    assert_eq!(test.committed_block_ids(), vec![blocks[2].id()]);
+    assert_eq!(test.notifier.sync_targets.lock().len(), 1);
+}
+
+#[test]
+fn test_sync_to_commit_of_partially_executed_blocks() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(2);
+    test.receive_ordered_block(&blocks);
+
+    // A commit with a missing execution result doesn't panic but falls back to state sync.
+    for pending in test.observer.pending_blocks.values_mut() {
+        pending.results.pop();
+    }
+    test.receive_commit_decision(&blocks[1]);
+    assert!(test.committed_block_ids().is_empty());
+    assert_eq!(
+        block_ids_of(&test.notifier.sync_targets.lock()),
+        vec![blocks[1].id()]
+    );
+    assert!(test.observer.pending_blocks.is_empty());
+}
+
+#[test]
+fn test_sync_to_commit_after_failed_commit() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(2);
+    test.receive_ordered_block(&blocks);
+
+    // The blocks aren't lost when the commit fails, state sync commits them instead.
+    test.executor.state.lock().fail_commits = true;
+    test.receive_commit_decision(&blocks[1]);
+    assert!(test.committed_block_ids().is_empty());
+    assert_eq!(
+        block_ids_of(&test.notifier.sync_targets.lock()),
+        vec![blocks[1].id()]
+    );
+    assert!(test.observer.pending_blocks.is_empty());
+    assert_eq!(test.observer.root.round(), 2);
+}
+
+#[test]
+fn test_execute_pending_blocks_again_after_sync() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(3);
+    test.receive_ordered_block(&blocks[..1]);
+    test.receive_ordered_block(&blocks[1..2]);
+    test.receive_ordered_block(&blocks[2..]);
+    assert_eq!(test.executed_block_ids(), block_ids(&blocks));
+
+    // State sync commits the first two blocks, and the reset executor drops the last one.
+    test.observer
+        .pending_blocks
+        .values_mut()
+        .next()
+        .unwrap()
+        .results
+        .clear();
+    test.executor.state.lock().committed_block_id = blocks[1].id();
+    test.receive_commit_decision(&blocks[1]);
+    assert_eq!(test.executor.state.lock().num_resets, 1);
+
+    // The last block is executed again, so that it can be committed.
+    assert_eq!(test.executed_block_ids().last(), Some(&blocks[2].id()));
+    assert_eq!(test.executed_block_ids().len(), 4);
+    test.receive_commit_decision(&blocks[2]);
+    assert_eq!(test.committed_block_ids(), vec![blocks[2].id()]);
+    assert_eq!(test.notifier.sync_targets.lock().len(), 1);
+}
+
+#[test]
+fn test_sync_to_commit_while_paused() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(1);
+    test.receive_ordered_block(&blocks);
+
+    // State sync writes to storage while the observer is paused, so the observer doesn't commit
+    // but asks state sync to sync to the commit instead.
+    test.pause.pause();
+    test.receive_commit_decision(&blocks[0]);
+    assert!(test.committed_block_ids().is_empty());
+    assert_eq!(
+        block_ids_of(&test.notifier.sync_targets.lock()),
+        vec![blocks[0].id()]
+    );
+    assert_eq!(test.observer.root.round(), 1);
+}
+
+#[test]
+fn test_request_epoch_change_proof() {
+    let mut test = ObserverTest::new();
+    let blocks = test.create_blocks(1);
+
+    // The proof can't be verified until the observer catches up with the epoch.
+    let commit_proof = create_proof_in_epoch(&blocks[0], 3, &test.signers);
+    test.receive(ObserverMessage::CommitDecision { commit_proof });
+    assert!(test.notifier.sync_targets.lock().is_empty());
+
+    match block_on(test.network_reqs_rx.select_next_some()) {
+        PeerManagerRequest::SendDirectSend(peer_id, message) => {
+            assert_eq!(peer_id, test.publisher_peer.peer_id());
+            match message.protocol_id.from_bytes(&message.mdata).unwrap() {
+                ObserverMessage::EpochRetrievalRequest(request) => {
+                    assert_eq!(request.start_epoch, 1);
+                    assert_eq!(request.end_epoch, 3);
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+        }
+        request => panic!("Unexpected request {:?}", request),
+    }
+}
+
+#[test]
+fn test_subscribe_to_upstream_peer() {
+    let mut test = ObserverTest::new();
+    test.observer.subscription = None;
+    test.observer.config.subscription_timeout_ms = 0;
+
+    // Downstream peers and peers without the observer protocol aren't subscribed to.
+    let downstream_peer = PeerId::random();
+    let upstream_peer = PeerId::random();
+    block_on(test.observer.process_network_event(
+        NetworkId::Vfn,
+        Event::NewPeer(observer_peer_metadata(
+            downstream_peer,
+            PeerRole::ValidatorFullNode,
+        )),
+    ));
+    let mut metadata = ConnectionMetadata::mock(upstream_peer);
+    metadata.role = PeerRole::Validator;
+    block_on(
+        test.observer
+            .process_network_event(NetworkId::Vfn, Event::NewPeer(metadata)),
+    );
+    test.observer.check_subscription();
+    assert!(test.observer.subscription.is_none());
+
+    block_on(test.observer.process_network_event(
+        NetworkId::Vfn,
+        Event::NewPeer(observer_peer_metadata(upstream_peer, PeerRole::Validator)),
+    ));
+    test.observer.check_subscription();
+    assert!(test
+        .observer
+        .is_subscribed_to(&PeerNetworkId::new(NetworkId::Vfn, upstream_peer)));
+
+    // The only candidate is subscribed to again after the subscription times out.
+    test.observer.check_subscription();
+    let mut messages = vec![];
+    while let Some(request) = test.network_reqs_rx.select_next_some().now_or_never() {
+        match request {
+            PeerManagerRequest::SendDirectSend(peer_id, message) => {
+                assert_eq!(peer_id, upstream_peer);
+                messages.push(message.protocol_id.from_bytes(&message.mdata).unwrap());
+            }
+            request => panic!("Unexpected request {:?}", request),
+        }
+    }
+    assert!(matches!(
+        messages.as_slice(),
+        [
+            ObserverMessage::Subscribe,
+            ObserverMessage::Unsubscribe,
+            ObserverMessage::Subscribe
+        ]
+    ));
+
+    // Losing the subscribed peer drops the subscription.
+    block_on(test.observer.process_network_event(
+        NetworkId::Vfn,
+        Event::LostPeer(ConnectionMetadata::mock(upstream_peer)),
+    ));
+    assert!(test.observer.subscription.is_none());
+}
@@ -0,0 +1,10 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consensus observer mode: validators (and observing full nodes, as relays) publish the blocks
+//! they order and commit to the subscribed full nodes, which verify, execute and commit them
+//! locally instead of waiting for state sync to fetch the transactions.
+
+pub(crate) mod consensus_observer;
+pub mod network;
+pub(crate) mod publisher;
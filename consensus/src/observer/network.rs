@@ -0,0 +1,106 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between the consensus observer and Network layers.
+
+use crate::counters;
+use async_trait::async_trait;
+use channel::{diem_channel, message_queues::QueueStyle};
+use consensus_types::{block::Block, epoch_retrieval::EpochRetrievalRequest};
+use diem_types::{epoch_change::EpochChangeProof, ledger_info::LedgerInfoWithSignatures, PeerId};
+use network::{
+    constants::NETWORK_CHANNEL_SIZE,
+    error::NetworkError,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::{
+        AppConfig, ApplicationNetworkSender, NetworkEvents, NetworkSender, NewNetworkSender,
+        RpcError,
+    },
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Network type for the consensus observer
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ObserverMessage {
+    /// Sent by an observer to start receiving the consensus updates of the peer.
+    Subscribe,
+    /// Sent by an observer to stop receiving the consensus updates of the peer.
+    Unsubscribe,
+    /// A chain of blocks ordered by consensus, certified by the ledger info of the last block.
+    OrderedBlock {
+        /// The ordered blocks, each the parent of the next one.
+        blocks: Vec<Block>,
+        /// The ledger info ordering the last block.
+        ordered_proof: LedgerInfoWithSignatures,
+    },
+    /// The ledger info committing the executed state of previously ordered blocks.
+    CommitDecision {
+        /// The ledger info committing the last ordered block.
+        commit_proof: LedgerInfoWithSignatures,
+    },
+    /// Request to get a EpochChangeProof from current_epoch to target_epoch
+    EpochRetrievalRequest(Box<EpochRetrievalRequest>),
+    /// A vector of LedgerInfo with contiguous increasing epoch numbers to prove a sequence of
+    /// epoch changes from the first LedgerInfo's epoch.
+    EpochChangeProof(Box<EpochChangeProof>),
+}
+
+/// The interface from Network to the consensus observer layer.
+///
+/// `ConsensusObserverEvents` is a `Stream` of `PeerManagerNotification` where the
+/// raw `Bytes` direct-send messages are deserialized into `ObserverMessage` types.
+pub type ConsensusObserverEvents = NetworkEvents<ObserverMessage>;
+
+/// The interface from the consensus observer to Networking layer.
+///
+/// This is a thin wrapper around a `NetworkSender<ObserverMessage>`, so it is easy
+/// to clone and share between the publisher and the observer.
+#[derive(Clone)]
+pub struct ConsensusObserverSender {
+    inner: NetworkSender<ObserverMessage>,
+}
+
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const DIRECT_SEND: &[ProtocolId] = &[ProtocolId::ConsensusObserverDirectSend];
+
+impl NewNetworkSender for ConsensusObserverSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationNetworkSender<ObserverMessage> for ConsensusObserverSender {
+    fn send_to(&self, recipient: PeerId, message: ObserverMessage) -> Result<(), NetworkError> {
+        self.inner
+            .send_to(recipient, ProtocolId::ConsensusObserverDirectSend, message)
+    }
+
+    async fn send_rpc(
+        &self,
+        _recipient: PeerId,
+        _req_msg: ObserverMessage,
+        _timeout: Duration,
+    ) -> Result<ObserverMessage, RpcError> {
+        unimplemented!()
+    }
+}
+
+/// Configuration for the network endpoints to support the consensus observer. The messages of
+/// each peer are kept in order, as an ordered block must be processed before its commit
+/// decision.
+pub fn network_endpoint_config() -> AppConfig {
+    AppConfig::p2p(
+        DIRECT_SEND.iter().copied(),
+        diem_channel::Config::new(NETWORK_CHANNEL_SIZE)
+            .queue_style(QueueStyle::FIFO)
+            .counters(&counters::PENDING_CONSENSUS_OBSERVER_NETWORK_EVENTS),
+    )
+}
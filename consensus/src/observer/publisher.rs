@@ -0,0 +1,175 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    error::DbError,
+    logging::{LogEvent, LogSchema},
+    observer::network::{ConsensusObserverEvents, ConsensusObserverSender, ObserverMessage},
+};
+use anyhow::{bail, ensure, Context};
+use consensus_types::executed_block::ExecutedBlock;
+use diem_config::{
+    config::ConsensusObserverConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, protocol_spec::DpnProto};
+use futures::{stream::select_all, StreamExt};
+use network::protocols::network::{ApplicationNetworkSender, Event};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+use storage_interface::DbReader;
+
+/// Publishes the blocks ordered and committed by this node to the subscribed observers, and
+/// serves them the epoch change proofs they need to verify the published blocks.
+pub struct ConsensusPublisher {
+    max_num_subscribers: usize,
+    diem_db: Arc<dyn DbReader<DpnProto>>,
+    network_senders: HashMap<NetworkId, ConsensusObserverSender>,
+    subscribers: Mutex<BTreeSet<PeerNetworkId>>,
+}
+
+impl ConsensusPublisher {
+    pub fn new(
+        config: &ConsensusObserverConfig,
+        diem_db: Arc<dyn DbReader<DpnProto>>,
+        network_senders: HashMap<NetworkId, ConsensusObserverSender>,
+    ) -> Self {
+        Self {
+            max_num_subscribers: config.max_num_subscribers,
+            diem_db,
+            network_senders,
+            subscribers: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Processes the messages sent to the publisher: (un)subscriptions and epoch retrievals.
+    pub fn process_message(
+        &self,
+        peer: PeerNetworkId,
+        message: ObserverMessage,
+    ) -> anyhow::Result<()> {
+        match message {
+            ObserverMessage::Subscribe => {
+                let mut subscribers = self.subscribers.lock();
+                ensure!(
+                    subscribers.contains(&peer) || subscribers.len() < self.max_num_subscribers,
+                    "[ConsensusPublisher] Rejected subscription of {}: too many subscribers",
+                    peer
+                );
+                if subscribers.insert(peer) {
+                    info!(
+                        LogSchema::new(LogEvent::ObserverSubscribe).remote_peer(peer.peer_id()),
+                        "[ConsensusPublisher] New subscriber on {}",
+                        peer.network_id()
+                    );
+                }
+                counters::PUBLISHER_NUM_SUBSCRIBERS.set(subscribers.len() as i64);
+            }
+            ObserverMessage::Unsubscribe => self.remove_subscriber(&peer),
+            ObserverMessage::EpochRetrievalRequest(request) => {
+                debug!(
+                    LogSchema::new(LogEvent::ReceiveEpochRetrieval).remote_peer(peer.peer_id()),
+                    "[ConsensusPublisher] receive {}", request,
+                );
+                let proof = self
+                    .diem_db
+                    .get_epoch_ending_ledger_infos(request.start_epoch, request.end_epoch)
+                    .map_err(DbError::from)
+                    .context("[ConsensusPublisher] Failed to get epoch proof")?;
+                self.send_to(&peer, ObserverMessage::EpochChangeProof(Box::new(proof)))?;
+            }
+            message => bail!(
+                "[ConsensusPublisher] Unexpected message from {}: {:?}",
+                peer,
+                message
+            ),
+        }
+        Ok(())
+    }
+
+    /// Drops the subscription of the peer, e.g. after it disconnects.
+    pub fn remove_subscriber(&self, peer: &PeerNetworkId) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.remove(peer) {
+            info!(
+                LogSchema::new(LogEvent::ObserverSubscribe).remote_peer(peer.peer_id()),
+                "[ConsensusPublisher] Removed subscriber on {}",
+                peer.network_id()
+            );
+        }
+        counters::PUBLISHER_NUM_SUBSCRIBERS.set(subscribers.len() as i64);
+    }
+
+    /// Publishes the chain of blocks ordered by `ordered_proof`.
+    pub fn publish_ordered_blocks(
+        &self,
+        blocks: &[Arc<ExecutedBlock>],
+        ordered_proof: LedgerInfoWithSignatures,
+    ) {
+        self.publish(ObserverMessage::OrderedBlock {
+            blocks: blocks.iter().map(|block| block.block().clone()).collect(),
+            ordered_proof,
+        });
+    }
+
+    /// Publishes the commit of the previously published blocks.
+    pub fn publish_commit_decision(&self, commit_proof: LedgerInfoWithSignatures) {
+        self.publish(ObserverMessage::CommitDecision { commit_proof });
+    }
+
+    /// Sends the message to all the subscribers. Sending is best effort: an observer missing
+    /// messages falls back to state sync.
+    pub fn publish(&self, message: ObserverMessage) {
+        let subscribers = self.subscribers.lock().clone();
+        for peer in subscribers {
+            if let Err(e) = self.send_to(&peer, message.clone()) {
+                warn!(
+                    remote_peer = peer.peer_id(),
+                    error = ?e,
+                    "[ConsensusPublisher] Failed to publish to subscriber"
+                );
+            }
+        }
+    }
+
+    fn send_to(&self, peer: &PeerNetworkId, message: ObserverMessage) -> anyhow::Result<()> {
+        let sender = self
+            .network_senders
+            .get(&peer.network_id())
+            .with_context(|| format!("[ConsensusPublisher] Unknown network of {}", peer))?;
+        sender
+            .send_to(peer.peer_id(), message)
+            .with_context(|| format!("[ConsensusPublisher] Failed to send to {}", peer))
+    }
+
+    /// Processes the network events of the publisher. Only used when this node doesn't run an
+    /// observer, which otherwise forwards the messages to the publisher itself.
+    pub async fn start(self: Arc<Self>, network_events: Vec<(NetworkId, ConsensusObserverEvents)>) {
+        let events: Vec<_> = network_events
+            .into_iter()
+            .map(|(network_id, events)| events.map(move |e| (network_id, e)))
+            .collect();
+        let mut network_events = select_all(events);
+
+        while let Some((network_id, event)) = network_events.next().await {
+            match event {
+                Event::Message(peer_id, message) => {
+                    let peer = PeerNetworkId::new(network_id, peer_id);
+                    if let Err(e) = self.process_message(peer, message) {
+                        warn!(error = ?e, "[ConsensusPublisher] Failed to process message");
+                    }
+                }
+                Event::LostPeer(metadata) => {
+                    self.remove_subscriber(&PeerNetworkId::new(network_id, metadata.remote_peer_id))
+                }
+                _ => (),
+            }
+        }
+        info!("[ConsensusPublisher] Network events closed, stopping.");
+    }
+}
@@ -4,7 +4,7 @@
 use crate::{consensusdb::ConsensusDB, epoch_manager::LivenessStorageData, error::DbError};
 use anyhow::{format_err, Context, Result};
 use consensus_types::{
-    block::Block, common::Author, quorum_cert::QuorumCert,
+    block::Block, common::Author, proof_of_store::Batch, quorum_cert::QuorumCert,
     timeout_2chain::TwoChainTimeoutCertificate, timeout_certificate::TimeoutCertificate,
     vote::Vote, vote_data::VoteData,
 };
@@ -19,7 +19,7 @@ use diem_types::{
     transaction::Version,
 };
 use executor_types::ExecutedTrees;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{cmp::max, collections::HashSet, sync::Arc};
 use storage_interface::DbReader;
 
@@ -59,11 +59,21 @@ pub trait PersistentLivenessStorage: Send + Sync {
     /// ValidatorVerifier.
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof>;
 
+    /// Persist the batches of the quorum store, which have to be served and executed after a
+    /// restart.
+    fn save_batches(&self, batches: Vec<Batch>) -> Result<()>;
+
+    /// Delete the batches with the given digests.
+    fn delete_batches(&self, digests: Vec<HashValue>) -> Result<()>;
+
+    /// Retrieve all the persisted batches of the quorum store.
+    fn retrieve_batches(&self) -> Result<Vec<Batch>>;
+
     /// Returns a handle of the diemdb.
     fn diem_db(&self) -> Arc<dyn DbReader<DpnProto>>;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct RootInfo(
     pub Block,
     pub QuorumCert,
@@ -151,6 +161,7 @@ impl LedgerRecoveryData {
     }
 }
 
+#[derive(Clone, Deserialize, Serialize)]
 pub struct RootMetadata {
     pub accu_hash: HashValue,
     pub frozen_root_hashes: Vec<HashValue>,
@@ -178,6 +189,7 @@ impl RootMetadata {
 
 /// The recovery data constructed from raw consensusdb data, it'll find the root value and
 /// blocks that need cleanup or return error if the input data is inconsistent.
+#[derive(Clone, Deserialize, Serialize)]
 pub struct RecoveryData {
     // The last vote message sent by this validator.
     last_vote: Option<Vote>,
@@ -494,6 +506,26 @@ impl PersistentLivenessStorage for StorageWriteProxy {
         Ok(proofs)
     }
 
+    fn save_batches(&self, batches: Vec<Batch>) -> Result<()> {
+        Ok(self.db.save_batches(batches)?)
+    }
+
+    fn delete_batches(&self, digests: Vec<HashValue>) -> Result<()> {
+        if !digests.is_empty() {
+            self.db.delete_batches(digests)?;
+        }
+        Ok(())
+    }
+
+    fn retrieve_batches(&self) -> Result<Vec<Batch>> {
+        Ok(self
+            .db
+            .get_batches()?
+            .into_iter()
+            .map(|(_, batch)| batch)
+            .collect())
+    }
+
     fn diem_db(&self) -> Arc<dyn DbReader<DpnProto>> {
         self.diem_db.clone()
     }
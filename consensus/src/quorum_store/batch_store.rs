@@ -0,0 +1,190 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    logging::{LogEvent, LogSchema},
+    network::NetworkSender,
+    persistent_liveness_storage::PersistentLivenessStorage,
+};
+use anyhow::{bail, Context};
+use consensus_types::{
+    block::Block,
+    common::Payload,
+    executed_block::ExecutedBlock,
+    proof_of_store::{Batch, BatchRequest},
+};
+use diem_config::config::QuorumStoreConfig;
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::transaction::SignedTransaction;
+use rand::{seq::SliceRandom, thread_rng};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Stores the batches of the quorum store received from their authors or fetched from the
+/// validators storing them, until they expire. It is shared across epochs, as the blocks of an
+/// epoch can still be executed and committed after the next epoch starts.
+pub struct BatchStore {
+    storage: Arc<dyn PersistentLivenessStorage>,
+    batches: Mutex<HashMap<HashValue, Batch>>,
+    // Digests of the committed batches, with their expiration, so that they're not proposed again
+    // until they expire.
+    committed: Mutex<HashMap<HashValue, u64>>,
+    request_timeout: Duration,
+}
+
+impl BatchStore {
+    pub fn new(storage: Arc<dyn PersistentLivenessStorage>, config: &QuorumStoreConfig) -> Self {
+        let batches = storage
+            .retrieve_batches()
+            .expect("[BatchStore] Failed to retrieve batches from storage")
+            .into_iter()
+            .map(|batch| (*batch.digest(), batch))
+            .collect();
+        Self {
+            storage,
+            batches: Mutex::new(batches),
+            committed: Mutex::new(HashMap::new()),
+            request_timeout: Duration::from_millis(config.batch_request_timeout_ms),
+        }
+    }
+
+    /// Persists the batch before it's made available, so that it can be served after a restart.
+    pub fn persist(&self, batch: Batch) -> anyhow::Result<()> {
+        if self.exists(batch.digest()) {
+            return Ok(());
+        }
+        self.storage
+            .save_batches(vec![batch.clone()])
+            .context("[BatchStore] Failed to persist batch")?;
+        self.batches.lock().insert(*batch.digest(), batch);
+        Ok(())
+    }
+
+    pub fn get_batch(&self, digest: &HashValue) -> Option<Batch> {
+        self.batches.lock().get(digest).cloned()
+    }
+
+    pub fn exists(&self, digest: &HashValue) -> bool {
+        self.batches.lock().contains_key(digest)
+    }
+
+    pub fn is_committed(&self, digest: &HashValue) -> bool {
+        self.committed.lock().contains_key(digest)
+    }
+
+    /// Returns the transactions of the payload of the block, which must have been fetched
+    /// beforehand if the payload is in the quorum store.
+    pub fn get_transactions(&self, block: &Block) -> anyhow::Result<Vec<SignedTransaction>> {
+        match block.payload() {
+            None => Ok(vec![]),
+            Some(Payload::DirectMempool(txns)) => Ok(txns.clone()),
+            Some(Payload::InQuorumStore(proofs)) => {
+                let batches = self.batches.lock();
+                let mut txns = vec![];
+                for proof in proofs {
+                    match batches.get(proof.digest()) {
+                        Some(batch) => txns.extend(batch.txns().iter().cloned()),
+                        None => bail!(
+                            "[BatchStore] Missing batch {} of block {}",
+                            proof.info(),
+                            block.id()
+                        ),
+                    }
+                }
+                Ok(txns)
+            }
+        }
+    }
+
+    /// Fetches the batches of the payload of the block which are missing locally, from the
+    /// validators which signed their proof of store.
+    pub async fn fetch_payload(
+        &self,
+        block: &Block,
+        network: &mut NetworkSender,
+    ) -> anyhow::Result<()> {
+        let proofs = match block.payload() {
+            Some(Payload::InQuorumStore(proofs)) => proofs,
+            _ => return Ok(()),
+        };
+        for proof in proofs {
+            if self.exists(proof.digest()) {
+                continue;
+            }
+            let mut signers: Vec<_> = proof
+                .signers()
+                .filter(|signer| **signer != network.author())
+                .copied()
+                .collect();
+            signers.shuffle(&mut thread_rng());
+            let mut fetched = None;
+            for peer in signers {
+                debug!(
+                    LogSchema::new(LogEvent::RetrieveBatch).remote_peer(peer),
+                    "Fetching {}",
+                    proof.info()
+                );
+                let request = BatchRequest {
+                    epoch: proof.epoch(),
+                    digest: *proof.digest(),
+                };
+                match network
+                    .request_batch(request, peer, self.request_timeout)
+                    .await
+                {
+                    Ok(batch) => {
+                        fetched = Some(batch);
+                        break;
+                    }
+                    Err(e) => warn!(
+                        remote_peer = peer,
+                        error = ?e,
+                        "[BatchStore] Failed to fetch batch, trying another peer"
+                    ),
+                }
+            }
+            match fetched {
+                Some(batch) => self.persist(batch)?,
+                None => bail!(
+                    "[BatchStore] Failed to fetch {} of block {}: no more peers available",
+                    proof.info(),
+                    block.id()
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the batches of the committed blocks as committed, and prunes the batches which
+    /// expired by the timestamp of the last committed block: they can't be referenced by any
+    /// later block.
+    pub fn notify_commit(&self, blocks: &[Arc<ExecutedBlock>]) {
+        let timestamp = match blocks.last() {
+            Some(block) => block.timestamp_usecs(),
+            None => return,
+        };
+        let mut committed = self.committed.lock();
+        for block in blocks {
+            if let Some(Payload::InQuorumStore(proofs)) = block.payload() {
+                for proof in proofs {
+                    committed.insert(*proof.digest(), proof.expiration());
+                }
+            }
+        }
+        committed.retain(|_, expiration| *expiration > timestamp);
+
+        let mut batches = self.batches.lock();
+        let expired: Vec<_> = batches
+            .values()
+            .filter(|batch| batch.info().expiration() <= timestamp)
+            .map(|batch| *batch.digest())
+            .collect();
+        for digest in &expired {
+            batches.remove(digest);
+        }
+        if let Err(e) = self.storage.delete_batches(expired) {
+            warn!(error = ?e, "[BatchStore] Failed to delete expired batches");
+        }
+    }
+}
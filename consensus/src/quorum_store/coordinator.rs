@@ -0,0 +1,299 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    logging::{LogEvent, LogSchema},
+    metrics_safety_rules::MetricsSafetyRules,
+    network::NetworkSender,
+    network_interface::ConsensusMsg,
+    quorum_store::{batch_store::BatchStore, proof_manager::ProofManager},
+    round_manager::VerifiedEvent,
+    state_replication::TxnManager,
+    util::time_service::TimeService,
+};
+use anyhow::{bail, ensure};
+use channel::diem_channel;
+use consensus_types::{
+    common::{Author, Payload},
+    proof_of_store::{Batch, ProofOfStore, SignedBatchInfo},
+};
+use diem_config::config::QuorumStoreConfig;
+use diem_crypto::{ed25519::Ed25519Signature, HashValue};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{account_address::AccountAddress, epoch_state::EpochState};
+use futures::{channel::oneshot, StreamExt};
+use safety_rules::TSafetyRules;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Drives the quorum store for an epoch:
+/// * periodically pulls a batch of transactions from mempool and broadcasts it,
+/// * stores and signs the batches of the other validators, sending the signature to the author,
+/// * aggregates the signatures of its own batches into proofs of store and broadcasts them,
+/// * hands the received proofs of store to the `ProofManager` for the proposals.
+pub struct QuorumStoreCoordinator {
+    author: Author,
+    epoch_state: EpochState,
+    config: QuorumStoreConfig,
+    mempool: Arc<dyn TxnManager>,
+    batch_store: Arc<BatchStore>,
+    proof_manager: Arc<ProofManager>,
+    safety_rules: Arc<Mutex<MetricsSafetyRules>>,
+    network: NetworkSender,
+    time_service: Arc<dyn TimeService>,
+    msg_rx: diem_channel::Receiver<AccountAddress, (AccountAddress, VerifiedEvent)>,
+    shutdown_rx: oneshot::Receiver<oneshot::Sender<()>>,
+    next_batch_id: u64,
+    // Own batches which are neither committed nor expired: their transactions are not pulled
+    // again from mempool.
+    own_batches: HashMap<HashValue, Batch>,
+    // Signatures of the own batches without a proof of store yet.
+    signatures: HashMap<HashValue, BTreeMap<Author, Ed25519Signature>>,
+}
+
+impl QuorumStoreCoordinator {
+    pub fn new(
+        epoch_state: EpochState,
+        config: QuorumStoreConfig,
+        mempool: Arc<dyn TxnManager>,
+        batch_store: Arc<BatchStore>,
+        proof_manager: Arc<ProofManager>,
+        safety_rules: Arc<Mutex<MetricsSafetyRules>>,
+        network: NetworkSender,
+        time_service: Arc<dyn TimeService>,
+        msg_rx: diem_channel::Receiver<AccountAddress, (AccountAddress, VerifiedEvent)>,
+        shutdown_rx: oneshot::Receiver<oneshot::Sender<()>>,
+    ) -> Self {
+        // Batch ids only need to be distinct per author: starting from the current time keeps
+        // them distinct across restarts.
+        let next_batch_id = time_service.get_current_timestamp().as_micros() as u64;
+        Self {
+            author: network.author(),
+            epoch_state,
+            config,
+            mempool,
+            batch_store,
+            proof_manager,
+            safety_rules,
+            network,
+            time_service,
+            msg_rx,
+            shutdown_rx,
+            next_batch_id,
+            own_batches: HashMap::new(),
+            signatures: HashMap::new(),
+        }
+    }
+
+    fn now_usecs(&self) -> u64 {
+        self.time_service.get_current_timestamp().as_micros() as u64
+    }
+
+    fn expiry_usecs(&self) -> u64 {
+        self.config.batch_expiry_ms * 1000
+    }
+
+    async fn generate_batch(&mut self) -> anyhow::Result<()> {
+        let now = self.now_usecs();
+        let batch_store = self.batch_store.clone();
+        self.own_batches.retain(|digest, batch| {
+            batch.info().expiration() > now && !batch_store.is_committed(digest)
+        });
+        let own_batches = &self.own_batches;
+        self.signatures
+            .retain(|digest, _| own_batches.contains_key(digest));
+        if self.own_batches.len() >= self.config.max_pending_batches {
+            return Ok(());
+        }
+
+        let pending: Vec<_> = self
+            .own_batches
+            .values()
+            .map(|batch| Payload::DirectMempool(batch.txns().to_vec()))
+            .collect();
+        let txns = match self
+            .mempool
+            .pull_txns(self.config.max_batch_size, pending.iter().collect())
+            .await?
+        {
+            Payload::DirectMempool(txns) => txns,
+            Payload::InQuorumStore(_) => {
+                bail!("[QuorumStore] Mempool returned a quorum store payload")
+            }
+        };
+        if txns.is_empty() {
+            return Ok(());
+        }
+
+        let batch = Batch::new(
+            self.author,
+            self.next_batch_id,
+            self.epoch_state.epoch,
+            now + self.expiry_usecs(),
+            txns,
+        );
+        self.next_batch_id += 1;
+        debug!(
+            LogSchema::new(LogEvent::BroadcastBatch).epoch(self.epoch_state.epoch),
+            "{}", batch
+        );
+        self.own_batches.insert(*batch.digest(), batch.clone());
+        self.signatures.insert(*batch.digest(), BTreeMap::new());
+        self.network
+            .broadcast(ConsensusMsg::BatchMsg(Box::new(batch)))
+            .await;
+        Ok(())
+    }
+
+    /// Stores the batch and sends back the signature attesting it to its author.
+    async fn process_batch(&mut self, peer: Author, batch: Batch) -> anyhow::Result<()> {
+        debug!(
+            LogSchema::new(LogEvent::ReceiveBatch).remote_peer(peer),
+            "{}", batch
+        );
+        let info = batch.info().clone();
+        let now = self.now_usecs();
+        ensure!(
+            info.author() == peer,
+            "[QuorumStore] {} not sent by its author {}",
+            batch,
+            peer
+        );
+        ensure!(
+            info.epoch() == self.epoch_state.epoch,
+            "[QuorumStore] {} not in epoch {}",
+            batch,
+            self.epoch_state.epoch
+        );
+        ensure!(
+            info.expiration() > now && info.expiration() <= now + 2 * self.expiry_usecs(),
+            "[QuorumStore] {} has an invalid expiration, current time {}",
+            batch,
+            now
+        );
+        ensure!(
+            info.num_txns() <= self.config.max_batch_size,
+            "[QuorumStore] {} exceeds the max batch size {}",
+            batch,
+            self.config.max_batch_size
+        );
+        self.batch_store.persist(batch)?;
+
+        let signature = self.safety_rules.lock().sign_batch_info(&info)?;
+        let signed_info = SignedBatchInfo::new_with_signature(info, self.author, signature);
+        self.network
+            .send(
+                ConsensusMsg::SignedBatchInfoMsg(Box::new(signed_info)),
+                vec![peer],
+            )
+            .await;
+        Ok(())
+    }
+
+    /// Aggregates the signature of an own batch, and broadcasts the proof of store once the
+    /// signers have a quorum of the voting power.
+    async fn process_signed_batch_info(
+        &mut self,
+        peer: Author,
+        signed_info: SignedBatchInfo,
+    ) -> anyhow::Result<()> {
+        debug!(
+            LogSchema::new(LogEvent::ReceiveSignedBatchInfo).remote_peer(peer),
+            "{}", signed_info
+        );
+        ensure!(
+            signed_info.signer() == peer,
+            "[QuorumStore] {} not sent by its signer {}",
+            signed_info,
+            peer
+        );
+        let digest = *signed_info.info().digest();
+        match self.own_batches.get(&digest) {
+            Some(batch) if batch.info() == signed_info.info() => (),
+            // The proof of store is already formed, or the batch expired.
+            _ => return Ok(()),
+        }
+        let signatures = match self.signatures.get_mut(&digest) {
+            Some(signatures) => signatures,
+            None => return Ok(()),
+        };
+        signatures.insert(signed_info.signer(), signed_info.signature().clone());
+        if self
+            .epoch_state
+            .verifier
+            .check_voting_power(signatures.keys())
+            .is_err()
+        {
+            return Ok(());
+        }
+
+        let signatures = self.signatures.remove(&digest).unwrap_or_default();
+        let proof = ProofOfStore::new(signed_info.info().clone(), signatures);
+        debug!(
+            LogSchema::new(LogEvent::BroadcastProofOfStore).epoch(self.epoch_state.epoch),
+            "{}", proof
+        );
+        self.network
+            .broadcast(ConsensusMsg::ProofOfStoreMsg(Box::new(proof)))
+            .await;
+        Ok(())
+    }
+
+    fn process_proof_of_store(&self, peer: Author, proof: ProofOfStore) -> anyhow::Result<()> {
+        debug!(
+            LogSchema::new(LogEvent::ReceiveProofOfStore).remote_peer(peer),
+            "{}", proof
+        );
+        ensure!(
+            proof.info().author() == peer,
+            "[QuorumStore] {} not sent by its author {}",
+            proof,
+            peer
+        );
+        self.proof_manager.insert(proof);
+        Ok(())
+    }
+
+    async fn process_event(&mut self, peer: Author, event: VerifiedEvent) -> anyhow::Result<()> {
+        match event {
+            VerifiedEvent::Batch(batch) => self.process_batch(peer, *batch).await,
+            VerifiedEvent::SignedBatchInfo(signed_info) => {
+                self.process_signed_batch_info(peer, *signed_info).await
+            }
+            VerifiedEvent::ProofOfStore(proof) => self.process_proof_of_store(peer, *proof),
+            event => bail!("[QuorumStore] Unexpected event {:?}", event),
+        }
+    }
+
+    pub async fn start(mut self) {
+        info!(epoch = self.epoch_state.epoch, "Quorum store starts.");
+        let mut interval = tokio::time::interval(Duration::from_millis(
+            self.config.batch_generation_interval_ms,
+        ));
+        let ack_tx = loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.generate_batch().await {
+                        warn!(error = ?e, "[QuorumStore] Failed to generate batch");
+                    }
+                }
+                Some((peer, event)) = self.msg_rx.next() => {
+                    if let Err(e) = self.process_event(peer, event).await {
+                        warn!(remote_peer = peer, error = ?e, "[QuorumStore] Failed to process message");
+                    }
+                }
+                ack_tx = &mut self.shutdown_rx => {
+                    break ack_tx.ok();
+                }
+            }
+        };
+        if let Some(ack_tx) = ack_tx {
+            let _ = ack_tx.send(());
+        }
+        info!(epoch = self.epoch_state.epoch, "Quorum store stops.");
+    }
+}
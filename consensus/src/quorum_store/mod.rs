@@ -0,0 +1,15 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Quorum store: the validators broadcast the transactions in batches ahead of the proposals and
+//! collect the signatures of the validators storing them into proofs of store, so that the
+//! proposals only carry the proofs. The batches missing locally are fetched from the signers of
+//! their proof before executing a block, and are pruned once they expire.
+
+pub(crate) mod batch_store;
+pub(crate) mod coordinator;
+pub(crate) mod proof_manager;
+
+#[cfg(test)]
+#[path = "quorum_store_test.rs"]
+mod quorum_store_test;
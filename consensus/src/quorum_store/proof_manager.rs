@@ -0,0 +1,95 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::MempoolError, quorum_store::batch_store::BatchStore, state_replication::TxnManager,
+    util::time_service::TimeService,
+};
+use anyhow::Result;
+use consensus_types::{block::Block, common::Payload, proof_of_store::ProofOfStore};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use executor_types::StateComputeResult;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Keeps the proofs of store of the current epoch, and proposes them in place of the
+/// transactions: it's the `TxnManager` of the proposal generator when the quorum store is enabled.
+pub struct ProofManager {
+    epoch: u64,
+    batch_store: Arc<BatchStore>,
+    time_service: Arc<dyn TimeService>,
+    proofs: Mutex<HashMap<HashValue, ProofOfStore>>,
+}
+
+impl ProofManager {
+    pub fn new(
+        epoch: u64,
+        batch_store: Arc<BatchStore>,
+        time_service: Arc<dyn TimeService>,
+    ) -> Self {
+        Self {
+            epoch,
+            batch_store,
+            time_service,
+            proofs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn insert(&self, proof: ProofOfStore) {
+        if proof.epoch() == self.epoch {
+            self.proofs.lock().insert(*proof.digest(), proof);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TxnManager for ProofManager {
+    /// Returns the proofs which are neither expired, committed nor in the pending blocks, the
+    /// earliest expiring first, up to `max_size` transactions.
+    async fn pull_txns(
+        &self,
+        max_size: u64,
+        exclude: Vec<&Payload>,
+    ) -> Result<Payload, MempoolError> {
+        let pending: HashSet<_> = exclude
+            .into_iter()
+            .flat_map(|payload| match payload {
+                Payload::InQuorumStore(proofs) => proofs.iter().map(|p| *p.digest()).collect(),
+                Payload::DirectMempool(_) => vec![],
+            })
+            .collect();
+        let now = self.time_service.get_current_timestamp().as_micros() as u64;
+
+        let mut proofs = self.proofs.lock();
+        proofs.retain(|digest, proof| {
+            proof.expiration() > now && !self.batch_store.is_committed(digest)
+        });
+        let mut candidates: Vec<_> = proofs
+            .values()
+            .filter(|proof| !pending.contains(proof.digest()))
+            .collect();
+        candidates.sort_by_key(|proof| proof.expiration());
+
+        let mut size = 0;
+        let mut payload = vec![];
+        for proof in candidates {
+            if size + proof.num_txns() > max_size {
+                continue;
+            }
+            size += proof.num_txns();
+            payload.push(proof.clone());
+        }
+        Ok(Payload::InQuorumStore(payload))
+    }
+
+    async fn notify(
+        &self,
+        _block: &Block,
+        _compute_result: &StateComputeResult,
+    ) -> Result<(), MempoolError> {
+        Ok(())
+    }
+}
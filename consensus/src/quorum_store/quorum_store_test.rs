@@ -0,0 +1,147 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    persistent_liveness_storage::PersistentLivenessStorage,
+    quorum_store::{batch_store::BatchStore, proof_manager::ProofManager},
+    state_replication::TxnManager,
+    test_utils::MockStorage,
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+};
+use consensus_types::{
+    block::{
+        block_test_utils::{certificate_for_genesis, random_payload},
+        Block,
+    },
+    common::Payload,
+    executed_block::ExecutedBlock,
+    proof_of_store::{Batch, ProofOfStore},
+};
+use diem_config::config::QuorumStoreConfig;
+use diem_types::{
+    validator_signer::ValidatorSigner, validator_verifier::random_validator_verifier,
+};
+use executor_types::StateComputeResult;
+use futures::executor::block_on;
+use std::{sync::Arc, time::Duration};
+
+fn make_batch(signer: &ValidatorSigner, batch_id: u64, expiration: u64, num_txns: usize) -> Batch {
+    let txns = match random_payload(num_txns) {
+        Payload::DirectMempool(txns) => txns,
+        Payload::InQuorumStore(_) => unreachable!(),
+    };
+    Batch::new(signer.author(), batch_id, 1, expiration, txns)
+}
+
+fn make_proof(batch: &Batch, signers: &[ValidatorSigner]) -> ProofOfStore {
+    let signatures = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.sign(batch.info())))
+        .collect();
+    ProofOfStore::new(batch.info().clone(), signatures)
+}
+
+fn make_block(signer: &ValidatorSigner, proofs: Vec<ProofOfStore>, timestamp: u64) -> Block {
+    Block::new_proposal(
+        Payload::InQuorumStore(proofs),
+        1,
+        timestamp,
+        certificate_for_genesis(),
+        signer,
+    )
+}
+
+fn pulled_proofs(payload: Payload) -> Vec<ProofOfStore> {
+    match payload {
+        Payload::InQuorumStore(proofs) => proofs,
+        Payload::DirectMempool(_) => panic!("Unexpected payload"),
+    }
+}
+
+#[test]
+fn test_proof_of_store_verify() {
+    let (signers, validators) = random_validator_verifier(4, None, false);
+    let batch = make_batch(&signers[0], 0, 100, 2);
+    batch.verify().unwrap();
+
+    make_proof(&batch, &signers[..3])
+        .verify(&validators)
+        .unwrap();
+    // The signers don't have a quorum of the voting power.
+    assert!(make_proof(&batch, &signers[..2])
+        .verify(&validators)
+        .is_err());
+}
+
+#[test]
+fn test_batch_store_get_transactions() {
+    let (signers, validators) = random_validator_verifier(4, None, false);
+    let (_, storage) = MockStorage::start_for_testing((&validators).into());
+    let batch_store = BatchStore::new(storage.clone(), &QuorumStoreConfig::default());
+
+    let batch = make_batch(&signers[0], 0, 100, 3);
+    let block = make_block(&signers[0], vec![make_proof(&batch, &signers)], 50);
+    assert!(batch_store.get_transactions(&block).is_err());
+
+    batch_store.persist(batch.clone()).unwrap();
+    assert_eq!(
+        batch_store.get_transactions(&block).unwrap(),
+        batch.txns().to_vec()
+    );
+
+    // The batches are served again after a restart.
+    let batch_store = BatchStore::new(storage, &QuorumStoreConfig::default());
+    assert_eq!(batch_store.get_batch(batch.digest()), Some(batch));
+}
+
+#[test]
+fn test_batch_store_prunes_expired_batches_on_commit() {
+    let (signers, validators) = random_validator_verifier(4, None, false);
+    let (_, storage) = MockStorage::start_for_testing((&validators).into());
+    let batch_store = BatchStore::new(storage.clone(), &QuorumStoreConfig::default());
+
+    let expired = make_batch(&signers[0], 0, 100, 1);
+    let committed = make_batch(&signers[0], 1, 300, 1);
+    batch_store.persist(expired.clone()).unwrap();
+    batch_store.persist(committed.clone()).unwrap();
+
+    let block = make_block(&signers[0], vec![make_proof(&committed, &signers)], 200);
+    batch_store.notify_commit(&[Arc::new(ExecutedBlock::new(
+        block,
+        StateComputeResult::new_dummy(),
+    ))]);
+
+    assert!(!batch_store.exists(expired.digest()));
+    assert!(batch_store.exists(committed.digest()));
+    assert!(batch_store.is_committed(committed.digest()));
+    assert_eq!(storage.retrieve_batches().unwrap(), vec![committed]);
+}
+
+#[test]
+fn test_proof_manager_pull_proofs() {
+    let (signers, validators) = random_validator_verifier(4, None, false);
+    let (_, storage) = MockStorage::start_for_testing((&validators).into());
+    let batch_store = Arc::new(BatchStore::new(storage, &QuorumStoreConfig::default()));
+    let time_service = Arc::new(SimulatedTimeService::new());
+    time_service.sleep(Duration::from_micros(100));
+    let proof_manager = ProofManager::new(1, batch_store.clone(), time_service);
+
+    let expired = make_proof(&make_batch(&signers[0], 0, 100, 1), &signers);
+    let committed = make_proof(&make_batch(&signers[0], 1, 1000, 1), &signers);
+    let pending = make_proof(&make_batch(&signers[0], 2, 1000, 1), &signers);
+    let late = make_proof(&make_batch(&signers[1], 0, 900, 2), &signers);
+    let early = make_proof(&make_batch(&signers[2], 0, 800, 3), &signers);
+    let large = make_proof(&make_batch(&signers[3], 0, 850, 10), &signers);
+    for proof in [&expired, &committed, &pending, &late, &early, &large] {
+        proof_manager.insert(proof.clone());
+    }
+    batch_store.notify_commit(&[Arc::new(ExecutedBlock::new(
+        make_block(&signers[0], vec![committed], 50),
+        StateComputeResult::new_dummy(),
+    ))]);
+
+    let pending_payload = Payload::InQuorumStore(vec![pending]);
+    let payload = block_on(proof_manager.pull_txns(6, vec![&pending_payload])).unwrap();
+    // The earliest expiring proofs first, skipping the ones exceeding the block size.
+    assert_eq!(pulled_proofs(payload), vec![early, late]);
+}
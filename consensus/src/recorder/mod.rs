@@ -0,0 +1,202 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consensus recorder: when a `record_path` is configured, everything the `RoundManager` gets from
+//! outside (the incoming messages, the local timeouts, the results of the `StateComputer` and the
+//! elected proposers) is appended to a file along with the messages it sends, so that its
+//! decisions can be replayed with the `consensus-replay` binary when debugging a misbehaving
+//! validator.
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod replay;
+
+#[cfg(test)]
+#[path = "replay_test.rs"]
+mod replay_test;
+
+use crate::{
+    error::StateSyncError,
+    liveness::proposer_election::ProposerElection,
+    network_interface::ConsensusMsg,
+    persistent_liveness_storage::RecoveryData,
+    state_replication::{StateComputer, StateComputerCommitCallBackType},
+    util::time_service::TimeService,
+};
+use anyhow::Result;
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    executed_block::ExecutedBlock,
+    safety_data::SafetyData,
+};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{
+    epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::OnChainConsensusConfig,
+};
+use executor_types::{Error, StateComputeResult};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs::OpenOptions, io::Write, path::Path, sync::Arc};
+
+/// An event seen or produced by the `RoundManager`.
+#[derive(Clone, Deserialize, Serialize)]
+pub enum RecordedEvent {
+    /// The `RoundManager` of an epoch starting from the given recovery and safety data.
+    EpochStart {
+        author: Author,
+        epoch_state: EpochState,
+        onchain_config: OnChainConsensusConfig,
+        recovery_data: Box<RecoveryData>,
+        safety_data: Option<Box<SafetyData>>,
+        decoupled_execution: bool,
+    },
+    /// A message received from a peer, or sent by the node to itself.
+    Message(Author, ConsensusMsg),
+    /// The local timeout of a round.
+    LocalTimeout(Round),
+    /// The result of the execution of a block, by block id.
+    StateComputeResult(HashValue, Box<StateComputeResult>),
+    /// The proposer elected for a round.
+    Proposer(Round, Author),
+    /// A message sent by the node, reflecting its decisions.
+    Sent(ConsensusMsg),
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Record {
+    pub timestamp_usecs: u64,
+    pub event: RecordedEvent,
+}
+
+/// Appends the recorded events to a writer, one JSON record per line.
+pub struct ConsensusRecorder {
+    writer: Mutex<Box<dyn Write + Send>>,
+    time_service: Arc<dyn TimeService>,
+}
+
+impl ConsensusRecorder {
+    pub fn new(writer: Box<dyn Write + Send>, time_service: Arc<dyn TimeService>) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            time_service,
+        }
+    }
+
+    /// Creates a recorder appending to the file at the given path.
+    pub fn create(path: &Path, time_service: Arc<dyn TimeService>) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(Box::new(file), time_service))
+    }
+
+    /// Records the event, the failures are only logged so that they don't affect consensus.
+    pub fn record(&self, event: RecordedEvent) {
+        let record = Record {
+            timestamp_usecs: self.time_service.get_current_timestamp().as_micros() as u64,
+            event,
+        };
+        let result = serde_json::to_vec(&record)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                // A single write keeps the lines whole when events are recorded concurrently.
+                Ok(self.writer.lock().write_all(&line)?)
+            });
+        if let Err(e) = result {
+            error!(error = ?e, "[ConsensusRecorder] Failed to record an event");
+        }
+    }
+}
+
+/// Reads back the records written by a `ConsensusRecorder`.
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn read_records(reader: impl std::io::BufRead) -> Result<Vec<Record>> {
+    reader
+        .lines()
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+/// State computer recording the results of the blocks executed by the inner one.
+pub struct RecordingStateComputer {
+    inner: Arc<dyn StateComputer>,
+    recorder: Arc<ConsensusRecorder>,
+}
+
+impl RecordingStateComputer {
+    pub fn new(inner: Arc<dyn StateComputer>, recorder: Arc<ConsensusRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait::async_trait]
+impl StateComputer for RecordingStateComputer {
+    fn compute(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, Error> {
+        let result = self.inner.compute(block, parent_block_id)?;
+        self.recorder.record(RecordedEvent::StateComputeResult(
+            block.id(),
+            Box::new(result.clone()),
+        ));
+        Ok(result)
+    }
+
+    async fn commit(
+        &self,
+        blocks: &[Arc<ExecutedBlock>],
+        finality_proof: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
+    ) -> Result<(), Error> {
+        self.inner.commit(blocks, finality_proof, callback).await
+    }
+
+    async fn sync_to(&self, target: LedgerInfoWithSignatures) -> Result<(), StateSyncError> {
+        self.inner.sync_to(target).await
+    }
+}
+
+/// Proposer election recording the proposer of each round the first time it's elected, as the
+/// elections based on the committed history can't be reproduced without the ledger.
+pub struct RecordingProposerElection {
+    inner: Box<dyn ProposerElection + Send + Sync>,
+    recorder: Arc<ConsensusRecorder>,
+    recorded_rounds: Mutex<HashSet<Round>>,
+}
+
+impl RecordingProposerElection {
+    pub fn new(
+        inner: Box<dyn ProposerElection + Send + Sync>,
+        recorder: Arc<ConsensusRecorder>,
+    ) -> Self {
+        Self {
+            inner,
+            recorder,
+            recorded_rounds: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+impl ProposerElection for RecordingProposerElection {
+    fn is_valid_proposer(&self, author: Author, round: Round) -> bool {
+        self.get_valid_proposer(round);
+        self.inner.is_valid_proposer(author, round)
+    }
+
+    fn get_valid_proposer(&self, round: Round) -> Author {
+        let proposer = self.inner.get_valid_proposer(round);
+        if self.recorded_rounds.lock().insert(round) {
+            self.recorder
+                .record(RecordedEvent::Proposer(round, proposer));
+        }
+        proposer
+    }
+
+    fn is_valid_proposal(&self, block: &Block) -> bool {
+        self.get_valid_proposer(block.round());
+        self.inner.is_valid_proposal(block)
+    }
+}
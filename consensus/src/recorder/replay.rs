@@ -0,0 +1,656 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block_storage::BlockStore,
+    error::StateSyncError,
+    liveness::{
+        proposal_generator::ProposalGenerator,
+        round_proposer_election::RoundProposer,
+        round_state::{ExponentialTimeInterval, RoundState},
+    },
+    metrics_safety_rules::MetricsSafetyRules,
+    network::NetworkSender,
+    network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::batch_store::BatchStore,
+    recorder::{read_records, ConsensusRecorder, RecordedEvent},
+    round_manager::{RoundManager, UnverifiedEvent, VerifiedEvent},
+    state_replication::{StateComputer, StateComputerCommitCallBackType},
+    test_utils::{MockSharedStorage, MockStateComputer, MockStorage, MockTransactionManager},
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+};
+use anyhow::{ensure, format_err, Result};
+use channel::{self, diem_channel, message_queues::QueueStyle};
+use consensus_types::{
+    block::Block,
+    block_data::{BlockData, BlockType},
+    common::{Author, Payload, Round},
+    executed_block::ExecutedBlock,
+    proof_of_store::BatchInfo,
+    safety_data::SafetyData,
+    timeout::Timeout,
+    timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
+    vote::Vote,
+    vote_proposal::MaybeSignedVoteProposal,
+};
+use diem_config::config::{ConsensusConfig, QuorumStoreConfig};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519Signature},
+    HashValue, Uniform,
+};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{
+    block_info::BlockInfo,
+    epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    on_chain_config::OnChainConsensusConfig,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+    waypoint::Waypoint,
+};
+use executor_types::{Error as ExecutionError, StateComputeResult};
+use futures::channel::mpsc;
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::{Event, NewNetworkSender},
+};
+use safety_rules::{test_utils, ConsensusState, Error, SafetyRules, TSafetyRules};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::File,
+    io::{self, BufReader, Write},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+/// A decision of the round manager, as reflected by the messages it sends. The proposals are only
+/// compared by their parent, their payload not being reproducible.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Decision {
+    Proposal {
+        round: Round,
+        parent_id: HashValue,
+    },
+    Vote {
+        round: Round,
+        block_id: HashValue,
+        timeout: bool,
+    },
+    SyncInfo {
+        highest_round: Round,
+    },
+}
+
+impl Decision {
+    fn from_msg(msg: &ConsensusMsg) -> Option<Self> {
+        match msg {
+            ConsensusMsg::ProposalMsg(proposal) => Some(Decision::Proposal {
+                round: proposal.proposal().round(),
+                parent_id: proposal.proposal().parent_id(),
+            }),
+            ConsensusMsg::VoteMsg(vote_msg) => {
+                let proposed = vote_msg.vote().vote_data().proposed();
+                Some(Decision::Vote {
+                    round: proposed.round(),
+                    block_id: proposed.id(),
+                    timeout: vote_msg.vote().is_timeout(),
+                })
+            }
+            ConsensusMsg::SyncInfo(sync_info) => Some(Decision::SyncInfo {
+                highest_round: sync_info.highest_round(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The first input whose replay led to other decisions than the recorded ones.
+#[derive(Debug)]
+pub struct Divergence {
+    /// Index of the record of the input
+    pub index: usize,
+    pub timestamp_usecs: u64,
+    pub recorded: Vec<Decision>,
+    pub replayed: Vec<Decision>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Divergence at record {} (timestamp {}us): recorded {:?}, replayed {:?}",
+            self.index, self.timestamp_usecs, self.recorded, self.replayed
+        )
+    }
+}
+
+/// An input of the round manager, with the decisions that followed it.
+struct Step {
+    index: usize,
+    timestamp_usecs: u64,
+    input: RecordedEvent,
+    decisions: Vec<Decision>,
+}
+
+/// Replays the record written by a node, and returns the first divergence between the recorded
+/// decisions and the replayed ones, if any.
+///
+/// The round manager is replayed over mock storage and execution, with the recorded execution
+/// results and proposers. The node signs with a fresh consensus key (see `ReplaySafetyRules`), as
+/// the recorded one isn't needed to reproduce its decisions. The blocks retrieved from peers and
+/// the batches missing from the record can't be reproduced, so a replay needing them diverges.
+pub fn replay(path: &Path) -> Result<Option<Divergence>> {
+    let records = read_records(BufReader::new(File::open(path)?))?;
+
+    let mut steps: Vec<Step> = vec![];
+    let mut compute_results = HashMap::new();
+    let mut epoch_proposers: Vec<HashMap<Round, Author>> = vec![];
+    for (index, record) in records.into_iter().enumerate() {
+        match record.event {
+            RecordedEvent::Sent(msg) => {
+                let step = steps.last_mut().ok_or_else(|| {
+                    format_err!("[ConsensusReplay] Message sent before any input")
+                })?;
+                step.decisions.extend(Decision::from_msg(&msg));
+            }
+            RecordedEvent::StateComputeResult(block_id, result) => {
+                compute_results.insert(block_id, *result);
+            }
+            RecordedEvent::Proposer(round, author) => {
+                if let Some(proposers) = epoch_proposers.last_mut() {
+                    proposers.insert(round, author);
+                }
+            }
+            input => {
+                if let RecordedEvent::EpochStart { .. } = input {
+                    epoch_proposers.push(HashMap::new());
+                }
+                steps.push(Step {
+                    index,
+                    timestamp_usecs: record.timestamp_usecs,
+                    input,
+                    decisions: vec![],
+                });
+            }
+        }
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let compute_results = Arc::new(compute_results);
+    let time_service = Arc::new(SimulatedTimeService::new());
+    let mut epoch_proposers = epoch_proposers.into_iter();
+    let mut node: Option<ReplayNode> = None;
+    for step in steps {
+        let Step {
+            index,
+            timestamp_usecs,
+            input,
+            decisions,
+        } = step;
+        let now = time_service.get_current_timestamp();
+        time_service.sleep(Duration::from_micros(timestamp_usecs).saturating_sub(now));
+
+        let replayed = runtime.block_on(async {
+            match input {
+                RecordedEvent::EpochStart {
+                    author,
+                    epoch_state,
+                    onchain_config,
+                    recovery_data,
+                    safety_data,
+                    decoupled_execution,
+                } => {
+                    ensure!(
+                        !decoupled_execution,
+                        "[ConsensusReplay] Decoupled execution is not supported"
+                    );
+                    let signer =
+                        ValidatorSigner::new(author, Ed25519PrivateKey::generate_for_testing());
+                    let mut new_node = ReplayNode::start(
+                        signer,
+                        epoch_state,
+                        onchain_config,
+                        *recovery_data,
+                        safety_data,
+                        epoch_proposers.next().unwrap_or_default(),
+                        compute_results.clone(),
+                        time_service.clone(),
+                    )?;
+                    let last_vote = new_node.last_vote.take();
+                    new_node.round_manager.start(last_vote).await;
+                    node = Some(new_node);
+                }
+                RecordedEvent::Message(peer, msg) => {
+                    if let Some(node) = &mut node {
+                        node.process_message(peer, msg).await;
+                    }
+                }
+                RecordedEvent::LocalTimeout(round) => {
+                    if let Some(node) = &mut node {
+                        if let Err(e) = node.round_manager.process_local_timeout(round).await {
+                            debug!(error = ?e, "[ConsensusReplay] Local timeout failed");
+                        }
+                    }
+                }
+                _ => unreachable!("Not an input"),
+            }
+            Ok(node
+                .as_ref()
+                .map_or_else(Vec::new, ReplayNode::take_decisions))
+        })?;
+
+        if replayed != decisions {
+            return Ok(Some(Divergence {
+                index,
+                timestamp_usecs,
+                recorded: decisions,
+                replayed,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Shared in-memory buffer the replayed decisions are recorded to.
+#[derive(Clone)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// MockStateComputer returning the recorded execution results.
+struct ReplayStateComputer {
+    inner: MockStateComputer,
+    compute_results: Arc<HashMap<HashValue, StateComputeResult>>,
+}
+
+#[async_trait::async_trait]
+impl StateComputer for ReplayStateComputer {
+    fn compute(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, ExecutionError> {
+        self.inner.compute(block, parent_block_id)?;
+        self.compute_results
+            .get(&block.id())
+            .cloned()
+            .ok_or_else(|| ExecutionError::BlockNotFound(block.id()))
+    }
+
+    async fn commit(
+        &self,
+        blocks: &[Arc<ExecutedBlock>],
+        finality_proof: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
+    ) -> Result<(), ExecutionError> {
+        self.inner.commit(blocks, finality_proof, callback).await
+    }
+
+    async fn sync_to(&self, target: LedgerInfoWithSignatures) -> Result<(), StateSyncError> {
+        self.inner.sync_to(target).await
+    }
+}
+
+/// The round manager of a recorded epoch, with the mocks it runs over.
+struct ReplayNode {
+    round_manager: RoundManager,
+    batch_store: Arc<BatchStore>,
+    epoch_state: EpochState,
+    last_vote: Option<Vote>,
+    decisions: SharedBuffer,
+    // the mock state computer expects its receivers to be alive
+    _state_sync_receiver: mpsc::UnboundedReceiver<Payload>,
+    _commit_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    _timeout_receiver: channel::Receiver<Round>,
+}
+
+impl ReplayNode {
+    #[allow(clippy::too_many_arguments)]
+    fn start(
+        signer: ValidatorSigner,
+        epoch_state: EpochState,
+        onchain_config: OnChainConsensusConfig,
+        recovery_data: RecoveryData,
+        safety_data: Option<Box<SafetyData>>,
+        proposers: HashMap<Round, Author>,
+        compute_results: Arc<HashMap<HashValue, StateComputeResult>>,
+        time_service: Arc<SimulatedTimeService>,
+    ) -> Result<Self> {
+        let config = ConsensusConfig::default();
+        let last_vote = recovery_data.last_vote();
+
+        // The storage is seeded with the recovered tree, so that it stays consistent as the
+        // block store updates it.
+        let shared_storage = Arc::new(MockSharedStorage::new((&epoch_state.verifier).into()));
+        let (root, _, blocks, quorum_certs) = recovery_data.clone().take();
+        let storage = Arc::new(MockStorage::new_with_ledger_info(
+            shared_storage,
+            root.3.ledger_info().clone(),
+        ));
+        storage.save_tree(
+            std::iter::once(root.0).chain(blocks).collect(),
+            vec![root.1, root.2]
+                .into_iter()
+                .chain(quorum_certs)
+                .collect(),
+        )?;
+
+        let batch_store = Arc::new(BatchStore::new(
+            storage.clone(),
+            &QuorumStoreConfig::default(),
+        ));
+        let (state_sync_sender, state_sync_receiver) = mpsc::unbounded();
+        let (commit_sender, commit_receiver) = mpsc::unbounded();
+        let state_computer = Arc::new(ReplayStateComputer {
+            inner: MockStateComputer::new(
+                state_sync_sender,
+                commit_sender,
+                storage.clone(),
+                batch_store.clone(),
+            ),
+            compute_results,
+        });
+        let block_store = Arc::new(BlockStore::new(
+            storage.clone(),
+            recovery_data,
+            state_computer,
+            config.max_pruned_blocks_in_mem,
+            time_service.clone(),
+            config.back_pressure_limit,
+        ));
+
+        let safety_rules = ReplaySafetyRules::start(signer.clone(), &epoch_state, safety_data)?;
+
+        let (timeout_sender, timeout_receiver) = channel::new_test(1_024);
+        let round_state = RoundState::new(
+            Box::new(ExponentialTimeInterval::new(
+                Duration::from_millis(config.round_initial_timeout_ms),
+                1.2,
+                6,
+            )),
+            time_service.clone(),
+            timeout_sender,
+        );
+
+        let default_proposer = epoch_state
+            .verifier
+            .get_ordered_account_addresses_iter()
+            .next()
+            .ok_or_else(|| format_err!("[ConsensusReplay] Empty validator set"))?;
+        let proposer_election = Box::new(RoundProposer::new(proposers, default_proposer));
+
+        // The sent messages are only recorded, the peers and the node itself getting the recorded
+        // ones.
+        let (network_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let (self_sender, _) = channel::new_test::<Event<ConsensusMsg>>(8);
+        let mut network = NetworkSender::new(
+            signer.author(),
+            ConsensusNetworkSender::new(
+                PeerManagerRequestSender::new(network_reqs_tx),
+                ConnectionRequestSender::new(connection_reqs_tx),
+            ),
+            self_sender,
+            epoch_state.verifier.clone(),
+        );
+        let decisions = SharedBuffer(Arc::new(Mutex::new(vec![])));
+        network.set_recorder(Arc::new(ConsensusRecorder::new(
+            Box::new(decisions.clone()),
+            time_service.clone(),
+        )));
+
+        let txn_manager = Arc::new(MockTransactionManager::new(None));
+        let proposal_generator = ProposalGenerator::new(
+            signer.author(),
+            block_store.clone(),
+            txn_manager.clone(),
+            time_service,
+            config.max_block_size,
+            config.proposer_type.records_failed_authors(),
+        );
+
+        let round_manager = RoundManager::new(
+            epoch_state.clone(),
+            block_store,
+            round_state,
+            proposer_election,
+            proposal_generator,
+            Arc::new(Mutex::new(MetricsSafetyRules::new(
+                Box::new(safety_rules),
+                storage.clone(),
+            ))),
+            network,
+            txn_manager,
+            storage,
+            batch_store.clone(),
+            false,
+            onchain_config,
+        );
+        Ok(Self {
+            round_manager,
+            batch_store,
+            epoch_state,
+            last_vote,
+            decisions,
+            _state_sync_receiver: state_sync_receiver,
+            _commit_receiver: commit_receiver,
+            _timeout_receiver: timeout_receiver,
+        })
+    }
+
+    async fn process_message(&mut self, peer: Author, msg: ConsensusMsg) {
+        let event = match msg {
+            ConsensusMsg::ProposalMsg(_)
+            | ConsensusMsg::VoteMsg(_)
+            | ConsensusMsg::SyncInfo(_)
+            | ConsensusMsg::BatchMsg(_) => UnverifiedEvent::from(msg),
+            _ => return,
+        };
+        if event.epoch() != self.epoch_state.epoch {
+            return;
+        }
+        let result = match event.verify(&self.epoch_state.verifier) {
+            Ok(VerifiedEvent::ProposalMsg(proposal)) => {
+                self.round_manager.process_proposal_msg(*proposal).await
+            }
+            Ok(VerifiedEvent::VoteMsg(vote)) => self.round_manager.process_vote_msg(*vote).await,
+            Ok(VerifiedEvent::SyncInfo(sync_info)) => {
+                self.round_manager
+                    .process_sync_info_msg(*sync_info, peer)
+                    .await
+            }
+            Ok(VerifiedEvent::Batch(batch)) => self.batch_store.persist(*batch),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            debug!(error = ?e, "[ConsensusReplay] Message processing failed");
+        }
+    }
+
+    /// Returns the decisions made since the last call.
+    fn take_decisions(&self) -> Vec<Decision> {
+        let buffer = std::mem::take(&mut *self.decisions.0.lock());
+        read_records(&buffer[..])
+            .expect("[ConsensusReplay] Replayed decisions should be readable")
+            .into_iter()
+            .filter_map(|record| match record.event {
+                RecordedEvent::Sent(msg) => Decision::from_msg(&msg),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Safety rules of the replayed node.
+///
+/// The recorded consensus key isn't available, so safety rules sign as another validator, added to
+/// the recorded validator set without voting power: the recorded certificates still verify, and
+/// the votes and proposals it signs are handed over as the ones of the replayed node, signed with a
+/// fresh key.
+struct ReplaySafetyRules {
+    inner: SafetyRules,
+    signer: ValidatorSigner,
+    replay_author: Author,
+}
+
+impl ReplaySafetyRules {
+    /// Initializes safety rules with the recorded safety data, from a waypoint at the start of the
+    /// recorded epoch.
+    fn start(
+        signer: ValidatorSigner,
+        epoch_state: &EpochState,
+        safety_data: Option<Box<SafetyData>>,
+    ) -> Result<Self> {
+        let replay_signer = ValidatorSigner::random(None);
+        let mut validators: BTreeMap<_, _> = epoch_state
+            .verifier
+            .get_ordered_account_addresses_iter()
+            .filter_map(|address| {
+                let public_key = epoch_state.verifier.get_public_key(&address)?;
+                let voting_power = epoch_state.verifier.get_voting_power(&address)?;
+                Some((
+                    address,
+                    ValidatorConsensusInfo::new(public_key, voting_power),
+                ))
+            })
+            .collect();
+        validators.insert(
+            replay_signer.author(),
+            ValidatorConsensusInfo::new(replay_signer.public_key(), 0),
+        );
+        let ledger_info = LedgerInfo::new(
+            BlockInfo::new(
+                epoch_state.epoch.saturating_sub(1),
+                0,
+                HashValue::zero(),
+                HashValue::zero(),
+                0,
+                0,
+                Some(EpochState {
+                    epoch: epoch_state.epoch,
+                    verifier: ValidatorVerifier::new(validators),
+                }),
+            ),
+            HashValue::zero(),
+        );
+        let mut storage = test_utils::test_storage(&replay_signer);
+        storage.set_waypoint(&Waypoint::new_epoch_boundary(&ledger_info)?)?;
+        if let Some(safety_data) = safety_data {
+            storage.set_safety_data(*safety_data)?;
+        }
+        let mut inner = SafetyRules::new(storage, false, false, false);
+        let proof = EpochChangeProof::new(
+            vec![LedgerInfoWithSignatures::new(ledger_info, BTreeMap::new())],
+            false,
+        );
+        inner.initialize(&proof)?;
+        Ok(Self {
+            inner,
+            signer,
+            replay_author: replay_signer.author(),
+        })
+    }
+
+    /// Returns the vote of safety rules as the one of the replayed node.
+    fn node_vote(&self, vote: Vote) -> Vote {
+        if vote.author() != self.replay_author {
+            return vote;
+        }
+        Vote::new(
+            vote.vote_data().clone(),
+            self.signer.author(),
+            vote.ledger_info().clone(),
+            &self.signer,
+        )
+    }
+}
+
+impl TSafetyRules for ReplaySafetyRules {
+    fn consensus_state(&mut self) -> Result<ConsensusState, Error> {
+        self.inner.consensus_state()
+    }
+
+    fn initialize(&mut self, proof: &EpochChangeProof) -> Result<(), Error> {
+        self.inner.initialize(proof)
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        vote_proposal: &MaybeSignedVoteProposal,
+    ) -> Result<Vote, Error> {
+        let vote = self.inner.construct_and_sign_vote(vote_proposal)?;
+        Ok(self.node_vote(vote))
+    }
+
+    fn sign_proposal(&mut self, block_data: &BlockData) -> Result<Ed25519Signature, Error> {
+        // Safety rules only sign the proposals of their own author
+        if let BlockType::Proposal {
+            payload,
+            author,
+            failed_authors,
+        } = block_data.block_type()
+        {
+            if *author == self.signer.author() {
+                self.inner.sign_proposal(&BlockData::new_proposal(
+                    payload.clone(),
+                    self.replay_author,
+                    failed_authors.clone(),
+                    block_data.round(),
+                    block_data.timestamp_usecs(),
+                    block_data.quorum_cert().clone(),
+                ))?;
+                return Ok(self.signer.sign(block_data));
+            }
+        }
+        self.inner.sign_proposal(block_data)
+    }
+
+    fn sign_timeout(&mut self, timeout: &Timeout) -> Result<Ed25519Signature, Error> {
+        self.inner.sign_timeout(timeout)
+    }
+
+    fn sign_timeout_with_qc(
+        &mut self,
+        timeout: &TwoChainTimeout,
+        timeout_cert: Option<&TwoChainTimeoutCertificate>,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inner.sign_timeout_with_qc(timeout, timeout_cert)
+    }
+
+    fn construct_and_sign_vote_two_chain(
+        &mut self,
+        vote_proposal: &MaybeSignedVoteProposal,
+        timeout_cert: Option<&TwoChainTimeoutCertificate>,
+    ) -> Result<Vote, Error> {
+        let vote = self
+            .inner
+            .construct_and_sign_vote_two_chain(vote_proposal, timeout_cert)?;
+        Ok(self.node_vote(vote))
+    }
+
+    fn sign_commit_vote(
+        &mut self,
+        ledger_info: LedgerInfoWithSignatures,
+        new_ledger_info: LedgerInfo,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inner.sign_commit_vote(ledger_info, new_ledger_info)
+    }
+
+    fn sign_batch_info(&mut self, batch_info: &BatchInfo) -> Result<Ed25519Signature, Error> {
+        self.inner.sign_batch_info(batch_info)
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_interface::ConsensusMsg,
+    network_tests::NetworkPlayground,
+    recorder::{read_records, replay::replay, RecordedEvent},
+    test_utils::consensus_runtime,
+    twins::twins_node::SMRNode,
+};
+use diem_config::config::ConsensusProposerType;
+use diem_temppath::TempPath;
+use futures::StreamExt;
+use std::{
+    fs,
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Runs 4 nodes recording their consensus messages until the first one commits a block of the
+/// given round, and returns the path of its record.
+fn record_nodes(dir: &Path, round: u64) -> PathBuf {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let record_path = |idx: usize| dir.join(format!("consensus_record_{}", idx));
+    let next_idx = AtomicUsize::new(0);
+    let mut nodes = SMRNode::start_nodes_with_twins(
+        4,
+        &[],
+        &mut playground,
+        ConsensusProposerType::RotatingProposer,
+        None,
+        |config| config.record_path = Some(record_path(next_idx.fetch_add(1, Ordering::SeqCst))),
+    );
+    runtime.spawn(playground.start());
+    runtime.block_on(async {
+        loop {
+            let commit = nodes[0].commit_cb_receiver.next().await.unwrap();
+            if commit.ledger_info().commit_info().round() >= round {
+                break;
+            }
+        }
+    });
+    // Stop the nodes before the record is read.
+    drop(nodes);
+    record_path(0)
+}
+
+#[test]
+/// This test checks that the decisions of a recorded node are reproduced by its replay.
+fn consensus_replay_test() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let path = record_nodes(dir.path(), 5);
+
+    let records = read_records(BufReader::new(fs::File::open(&path).unwrap())).unwrap();
+    assert!(matches!(
+        records.first().map(|record| &record.event),
+        Some(RecordedEvent::EpochStart { .. })
+    ));
+    assert!(records
+        .iter()
+        .any(|record| matches!(record.event, RecordedEvent::StateComputeResult(..))));
+
+    if let Some(divergence) = replay(&path).unwrap() {
+        panic!("{}", divergence);
+    }
+}
+
+#[test]
+/// This test checks that the replay reports the first decision it can't reproduce.
+fn consensus_replay_divergence_test() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let path = record_nodes(dir.path(), 3);
+
+    // Drop the first proposal received: the vote for it is then recorded after the previous input
+    // and can't be reproduced.
+    let records = read_records(BufReader::new(fs::File::open(&path).unwrap())).unwrap();
+    let dropped = records
+        .iter()
+        .position(|record| {
+            matches!(
+                &record.event,
+                RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(_))
+            )
+        })
+        .unwrap();
+    let previous_input = records[..dropped]
+        .iter()
+        .rposition(|record| {
+            matches!(
+                record.event,
+                RecordedEvent::EpochStart { .. }
+                    | RecordedEvent::Message(..)
+                    | RecordedEvent::LocalTimeout(_)
+            )
+        })
+        .unwrap();
+    let tampered_path = dir.path().join("tampered_record");
+    let mut tampered = fs::File::create(&tampered_path).unwrap();
+    for (index, record) in records.iter().enumerate() {
+        if index != dropped {
+            serde_json::to_writer(&mut tampered, record).unwrap();
+            tampered.write_all(b"\n").unwrap();
+        }
+    }
+
+    let divergence = replay(&tampered_path)
+        .unwrap()
+        .expect("The replay should diverge");
+    assert_eq!(divergence.index, previous_input);
+    assert!(divergence.recorded.len() > divergence.replayed.len());
+}
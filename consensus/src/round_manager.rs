@@ -19,6 +19,7 @@ use crate::{
     network_interface::ConsensusMsg,
     pending_votes::VoteReceptionResult,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::batch_store::BatchStore,
     state_replication::{StateComputer, TxnManager},
 };
 use anyhow::{bail, ensure, Context, Result};
@@ -27,6 +28,7 @@ use consensus_types::{
     block_retrieval::{BlockRetrievalResponse, BlockRetrievalStatus},
     common::{Author, Round},
     experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    proof_of_store::{Batch, ProofOfStore, SignedBatchInfo},
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
@@ -56,6 +58,9 @@ pub enum UnverifiedEvent {
     SyncInfo(Box<SyncInfo>),
     CommitVote(Box<CommitVote>),
     CommitDecision(Box<CommitDecision>),
+    Batch(Box<Batch>),
+    SignedBatchInfo(Box<SignedBatchInfo>),
+    ProofOfStore(Box<ProofOfStore>),
 }
 
 impl UnverifiedEvent {
@@ -81,6 +86,18 @@ impl UnverifiedEvent {
                 cd.verify(validator)?;
                 VerifiedEvent::CommitDecision(cd)
             }
+            UnverifiedEvent::Batch(b) => {
+                b.verify()?;
+                VerifiedEvent::Batch(b)
+            }
+            UnverifiedEvent::SignedBatchInfo(s) => {
+                s.verify(validator)?;
+                VerifiedEvent::SignedBatchInfo(s)
+            }
+            UnverifiedEvent::ProofOfStore(p) => {
+                p.verify(validator)?;
+                VerifiedEvent::ProofOfStore(p)
+            }
         })
     }
 
@@ -91,6 +108,9 @@ impl UnverifiedEvent {
             UnverifiedEvent::SyncInfo(s) => s.epoch(),
             UnverifiedEvent::CommitVote(cv) => cv.epoch(),
             UnverifiedEvent::CommitDecision(cd) => cd.epoch(),
+            UnverifiedEvent::Batch(b) => b.epoch(),
+            UnverifiedEvent::SignedBatchInfo(s) => s.epoch(),
+            UnverifiedEvent::ProofOfStore(p) => p.epoch(),
         }
     }
 }
@@ -103,6 +123,9 @@ impl From<ConsensusMsg> for UnverifiedEvent {
             ConsensusMsg::SyncInfo(m) => UnverifiedEvent::SyncInfo(m),
             ConsensusMsg::CommitVoteMsg(m) => UnverifiedEvent::CommitVote(m),
             ConsensusMsg::CommitDecisionMsg(m) => UnverifiedEvent::CommitDecision(m),
+            ConsensusMsg::BatchMsg(m) => UnverifiedEvent::Batch(m),
+            ConsensusMsg::SignedBatchInfoMsg(m) => UnverifiedEvent::SignedBatchInfo(m),
+            ConsensusMsg::ProofOfStoreMsg(m) => UnverifiedEvent::ProofOfStore(m),
             _ => unreachable!("Unexpected conversion"),
         }
     }
@@ -115,6 +138,9 @@ pub enum VerifiedEvent {
     SyncInfo(Box<SyncInfo>),
     CommitVote(Box<CommitVote>),
     CommitDecision(Box<CommitDecision>),
+    Batch(Box<Batch>),
+    SignedBatchInfo(Box<SignedBatchInfo>),
+    ProofOfStore(Box<ProofOfStore>),
 }
 
 #[cfg(test)]
@@ -132,6 +158,7 @@ pub struct RecoveryManager {
     network: NetworkSender,
     storage: Arc<dyn PersistentLivenessStorage>,
     state_computer: Arc<dyn StateComputer>,
+    batch_store: Arc<BatchStore>,
     last_committed_round: Round,
     onchain_config: OnChainConsensusConfig,
 }
@@ -142,6 +169,7 @@ impl RecoveryManager {
         network: NetworkSender,
         storage: Arc<dyn PersistentLivenessStorage>,
         state_computer: Arc<dyn StateComputer>,
+        batch_store: Arc<BatchStore>,
         last_committed_round: Round,
         onchain_config: OnChainConsensusConfig,
     ) -> Self {
@@ -150,6 +178,7 @@ impl RecoveryManager {
             network,
             storage,
             state_computer,
+            batch_store,
             last_committed_round,
             onchain_config,
         }
@@ -182,7 +211,8 @@ impl RecoveryManager {
             sync_info.epoch() == self.epoch_state.epoch,
             "[RecoveryManager] Received sync info is in different epoch than committed block"
         );
-        let mut retriever = BlockRetriever::new(self.network.clone(), peer);
+        let mut retriever =
+            BlockRetriever::new(self.network.clone(), peer, self.batch_store.clone());
         let recovery_data = BlockStore::fast_forward_sync(
             sync_info.highest_ordered_cert(),
             sync_info.highest_ledger_info().clone(),
@@ -219,6 +249,7 @@ pub struct RoundManager {
     network: NetworkSender,
     txn_manager: Arc<dyn TxnManager>,
     storage: Arc<dyn PersistentLivenessStorage>,
+    batch_store: Arc<BatchStore>,
     sync_only: bool,
     decoupled_execution: bool,
     back_pressure_limit: u64,
@@ -236,6 +267,7 @@ impl RoundManager {
         network: NetworkSender,
         txn_manager: Arc<dyn TxnManager>,
         storage: Arc<dyn PersistentLivenessStorage>,
+        batch_store: Arc<BatchStore>,
         sync_only: bool,
         onchain_config: OnChainConsensusConfig,
     ) -> Self {
@@ -257,6 +289,7 @@ impl RoundManager {
             network,
             txn_manager,
             storage,
+            batch_store,
             sync_only,
             decoupled_execution: false,
             back_pressure_limit: 1, // arbitrary dummy value
@@ -274,6 +307,7 @@ impl RoundManager {
         network: NetworkSender,
         txn_manager: Arc<dyn TxnManager>,
         storage: Arc<dyn PersistentLivenessStorage>,
+        batch_store: Arc<BatchStore>,
         sync_only: bool,
         back_pressure_limit: u64,
         onchain_config: OnChainConsensusConfig,
@@ -288,6 +322,7 @@ impl RoundManager {
             network,
             txn_manager,
             storage,
+            batch_store,
             sync_only,
             decoupled_execution: true,
             back_pressure_limit,
@@ -300,7 +335,7 @@ impl RoundManager {
     }
 
     fn create_block_retriever(&self, author: Author) -> BlockRetriever {
-        BlockRetriever::new(self.network.clone(), author)
+        BlockRetriever::new(self.network.clone(), author, self.batch_store.clone())
     }
 
     /// Leader:
@@ -359,7 +394,7 @@ impl RoundManager {
         // Proposal generator will ensure that at most one proposal is generated per round
         let proposal = self
             .proposal_generator
-            .generate_proposal(new_round_event.round)
+            .generate_proposal(new_round_event.round, self.proposer_election.as_ref())
             .await?;
         let signature = self.safety_rules.lock().sign_proposal(&proposal)?;
         let signed_proposal =
@@ -635,6 +670,19 @@ impl RoundManager {
             proposal,
         );
 
+        let expected_failed_authors = self.proposal_generator.compute_failed_authors(
+            proposal.round(),
+            proposal.quorum_cert().certified_block().round(),
+            self.proposer_election.as_ref(),
+        );
+        ensure!(
+            proposal.block_data().failed_authors() == expected_failed_authors.as_ref(),
+            "[RoundManager] Proposal {} has failed authors {:?}, expected {:?}",
+            proposal,
+            proposal.block_data().failed_authors(),
+            expected_failed_authors,
+        );
+
         let block_time_since_epoch = Duration::from_micros(proposal.timestamp_usecs());
 
         ensure!(
@@ -647,6 +695,11 @@ impl RoundManager {
 
         observe_block(proposal.timestamp_usecs(), BlockStage::SYNCED);
 
+        self.batch_store
+            .fetch_payload(&proposal, &mut self.network)
+            .await
+            .context("[RoundManager] Failed to fetch the payload of the proposal")?;
+
         let proposal_round = proposal.round();
         let vote = self
             .execute_and_vote(proposal)
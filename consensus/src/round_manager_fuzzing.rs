@@ -12,12 +12,14 @@ use crate::{
     network::NetworkSender,
     network_interface::ConsensusNetworkSender,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::batch_store::BatchStore,
     round_manager::RoundManager,
     test_utils::{EmptyStateComputer, MockStorage, MockTransactionManager},
     util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::proposal_msg::ProposalMsg;
+use diem_config::config::QuorumStoreConfig;
 use diem_infallible::Mutex;
 use diem_types::{
     epoch_change::EpochChangeProof,
@@ -145,6 +147,7 @@ fn create_node_for_fuzzing() -> RoundManager {
         Arc::new(MockTransactionManager::new(None)),
         time_service,
         1,
+        false,
     );
 
     //
@@ -166,7 +169,8 @@ fn create_node_for_fuzzing() -> RoundManager {
         ))),
         network,
         Arc::new(MockTransactionManager::new(None)),
-        storage,
+        storage.clone(),
+        Arc::new(BatchStore::new(storage, &QuorumStoreConfig::default())),
         false,
         OnChainConsensusConfig::default(),
     )
@@ -14,6 +14,7 @@ use crate::{
     network_interface::{ConsensusMsg, ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
     persistent_liveness_storage::RecoveryData,
+    quorum_store::batch_store::BatchStore,
     round_manager::RoundManager,
     test_utils::{
         consensus_runtime, timed_block_on, MockStateComputer, MockStorage, MockTransactionManager,
@@ -24,18 +25,21 @@ use crate::{
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::{
     block::{
-        block_test_utils::{certificate_for_genesis, gen_test_certificate},
+        block_test_utils::{certificate_for_genesis, gen_test_certificate, random_payload},
         Block,
     },
+    block_data::BlockData,
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalStatus},
-    common::{Author, Payload},
+    common::{Author, Payload, Round},
+    proof_of_store::{Batch, ProofOfStore},
     proposal_msg::ProposalMsg,
+    quorum_cert::QuorumCert,
     sync_info::SyncInfo,
     timeout::Timeout,
     timeout_certificate::TimeoutCertificate,
     vote_msg::VoteMsg,
 };
-use diem_config::network_id::NetworkId;
+use diem_config::{config::QuorumStoreConfig, network_id::NetworkId};
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, Uniform};
 use diem_infallible::Mutex;
 use diem_secure_storage::Storage;
@@ -44,7 +48,7 @@ use diem_types::{
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     on_chain_config::OnChainConsensusConfig,
     validator_signer::ValidatorSigner,
-    validator_verifier::random_validator_verifier,
+    validator_verifier::{random_validator_verifier, ValidatorVerifier},
     waypoint::Waypoint,
 };
 use futures::{
@@ -71,6 +75,7 @@ pub struct NodeSetup {
     block_store: Arc<BlockStore>,
     round_manager: RoundManager,
     storage: Arc<MockStorage>,
+    batch_store: Arc<BatchStore>,
     signer: ValidatorSigner,
     proposer_author: Author,
     safety_rules_manager: SafetyRulesManager,
@@ -78,6 +83,7 @@ pub struct NodeSetup {
     commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
     _state_sync_receiver: mpsc::UnboundedReceiver<Payload>,
     id: usize,
+    record_failed_authors: bool,
 }
 
 impl NodeSetup {
@@ -96,6 +102,15 @@ impl NodeSetup {
         playground: &mut NetworkPlayground,
         executor: Handle,
         num_nodes: usize,
+    ) -> Vec<Self> {
+        Self::create_nodes_with_failed_authors(playground, executor, num_nodes, false)
+    }
+
+    fn create_nodes_with_failed_authors(
+        playground: &mut NetworkPlayground,
+        executor: Handle,
+        num_nodes: usize,
+        record_failed_authors: bool,
     ) -> Vec<Self> {
         let (signers, validators) = random_validator_verifier(num_nodes, None, false);
         let proposer_author = signers[0].author();
@@ -138,6 +153,7 @@ impl NodeSetup {
                 initial_data,
                 safety_rules_manager,
                 id,
+                record_failed_authors,
             ));
         }
         nodes
@@ -152,6 +168,7 @@ impl NodeSetup {
         initial_data: RecoveryData,
         safety_rules_manager: SafetyRulesManager,
         id: usize,
+        record_failed_authors: bool,
     ) -> Self {
         let epoch_state = EpochState {
             epoch: 1,
@@ -183,10 +200,15 @@ impl NodeSetup {
         let last_vote_sent = initial_data.last_vote();
         let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
         let (state_sync_client, _state_sync_receiver) = mpsc::unbounded();
+        let batch_store = Arc::new(BatchStore::new(
+            storage.clone(),
+            &QuorumStoreConfig::default(),
+        ));
         let state_computer = Arc::new(MockStateComputer::new(
             state_sync_client,
             commit_cb_sender,
             Arc::clone(&storage),
+            batch_store.clone(),
         ));
         let time_service = Arc::new(ClockTimeService::new(executor));
 
@@ -205,6 +227,7 @@ impl NodeSetup {
             Arc::new(MockTransactionManager::new(None)),
             time_service.clone(),
             1,
+            record_failed_authors,
         );
 
         let round_state = Self::create_round_state(time_service);
@@ -223,6 +246,7 @@ impl NodeSetup {
             network,
             Arc::new(MockTransactionManager::new(None)),
             storage.clone(),
+            batch_store.clone(),
             false,
             OnChainConsensusConfig::default(),
         );
@@ -231,6 +255,7 @@ impl NodeSetup {
             block_store,
             round_manager,
             storage,
+            batch_store,
             signer,
             proposer_author,
             safety_rules_manager,
@@ -238,6 +263,7 @@ impl NodeSetup {
             commit_cb_receiver,
             _state_sync_receiver,
             id,
+            record_failed_authors,
         }
     }
 
@@ -255,6 +281,7 @@ impl NodeSetup {
             recover_data,
             self.safety_rules_manager,
             self.id,
+            self.record_failed_authors,
         )
     }
 
@@ -345,7 +372,8 @@ fn vote_on_successful_proposal() {
         // Start round 1 and clear the message queue
         node.next_proposal().await;
 
-        let proposal = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+        let proposal =
+            Block::new_proposal(Payload::empty(), 1, 1, genesis_qc.clone(), &node.signer);
         let proposal_id = proposal.id();
         node.round_manager.process_proposal(proposal).await.unwrap();
         let vote_msg = node.next_vote().await;
@@ -359,6 +387,125 @@ fn vote_on_successful_proposal() {
     });
 }
 
+fn quorum_store_proof(signer: &ValidatorSigner, batch: &Batch) -> ProofOfStore {
+    let signatures = vec![(signer.author(), signer.sign(batch.info()))]
+        .into_iter()
+        .collect();
+    ProofOfStore::new(batch.info().clone(), signatures)
+}
+
+fn quorum_store_batch(signer: &ValidatorSigner, expiration: u64) -> Batch {
+    match random_payload(2) {
+        Payload::DirectMempool(txns) => Batch::new(signer.author(), 0, 1, expiration, txns),
+        Payload::InQuorumStore(_) => unreachable!(),
+    }
+}
+
+#[test]
+/// A proposal carrying proofs of store is voted once the batches are available locally.
+fn vote_on_quorum_store_proposal() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
+    let node = &mut nodes[0];
+
+    let genesis_qc = certificate_for_genesis();
+    let batch = quorum_store_batch(&node.signer, 100);
+    let proof = quorum_store_proof(&node.signer, &batch);
+    node.batch_store.persist(batch.clone()).unwrap();
+    timed_block_on(&mut runtime, async {
+        node.next_proposal().await;
+
+        let proposal = Block::new_proposal(
+            Payload::InQuorumStore(vec![proof]),
+            1,
+            1,
+            genesis_qc.clone(),
+            &node.signer,
+        );
+        let proposal_id = proposal.id();
+        node.round_manager.process_proposal(proposal).await.unwrap();
+        let vote_msg = node.next_vote().await;
+        assert_eq!(vote_msg.vote().vote_data().proposed().id(), proposal_id);
+        assert_eq!(
+            node.batch_store
+                .get_transactions(node.block_store.get_block(proposal_id).unwrap().block())
+                .unwrap(),
+            batch.txns().to_vec()
+        );
+    });
+}
+
+#[test]
+/// A proposal is not voted if the batches of its proofs of store can't be fetched.
+fn no_vote_on_quorum_store_proposal_with_missing_batch() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
+    let node = &mut nodes[0];
+
+    let genesis_qc = certificate_for_genesis();
+    let batch = quorum_store_batch(&node.signer, 100);
+    let proof = quorum_store_proof(&node.signer, &batch);
+    timed_block_on(&mut runtime, async {
+        node.next_proposal().await;
+
+        // The node is the only signer of the proof, there's no peer to fetch the batch from.
+        let proposal = Block::new_proposal(
+            Payload::InQuorumStore(vec![proof]),
+            1,
+            1,
+            genesis_qc.clone(),
+            &node.signer,
+        );
+        node.round_manager
+            .process_proposal(proposal)
+            .await
+            .unwrap_err();
+        assert_eq!(node.round_manager.consensus_state().last_voted_round(), 0);
+    });
+}
+
+#[test]
+/// A proposal carrying an expired or invalid proof of store doesn't pass the verification.
+fn reject_quorum_store_proposal_with_invalid_proof() {
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let genesis_qc = certificate_for_genesis();
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), None, None);
+    let proposal_msg = |proof: ProofOfStore| {
+        ProposalMsg::new(
+            Block::new_proposal(
+                Payload::InQuorumStore(vec![proof]),
+                1,
+                200,
+                genesis_qc.clone(),
+                &signer,
+            ),
+            sync_info.clone(),
+        )
+    };
+
+    let valid = quorum_store_proof(&signer, &quorum_store_batch(&signer, 300));
+    proposal_msg(valid).verify(&validator).unwrap();
+
+    let expired = quorum_store_proof(&signer, &quorum_store_batch(&signer, 100));
+    assert!(proposal_msg(expired).verify(&validator).is_err());
+
+    // The signature of the validator is forged by another signer.
+    let batch = quorum_store_batch(&signer, 300);
+    let forged = ProofOfStore::new(
+        batch.info().clone(),
+        vec![(
+            signer.author(),
+            ValidatorSigner::random([1u8; 32]).sign(batch.info()),
+        )]
+        .into_iter()
+        .collect(),
+    );
+    assert!(proposal_msg(forged).verify(&validator).is_err());
+}
+
 #[test]
 /// If the proposal does not pass voting rules,
 /// No votes are sent, but the block is still added to the block tree.
@@ -370,9 +517,9 @@ fn no_vote_on_old_proposal() {
     let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
     let node = &mut nodes[0];
     let genesis_qc = certificate_for_genesis();
-    let new_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let new_block = Block::new_proposal(Payload::empty(), 1, 1, genesis_qc.clone(), &node.signer);
     let new_block_id = new_block.id();
-    let old_block = Block::new_proposal(vec![], 1, 2, genesis_qc, &node.signer);
+    let old_block = Block::new_proposal(Payload::empty(), 1, 2, genesis_qc, &node.signer);
     let old_block_id = old_block.id();
     timed_block_on(&mut runtime, async {
         // clear the message queue
@@ -406,8 +553,10 @@ fn no_vote_on_mismatch_round() {
         .pop()
         .unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let correct_block =
+        Block::new_proposal(Payload::empty(), 1, 1, genesis_qc.clone(), &node.signer);
+    let block_skip_round =
+        Block::new_proposal(Payload::empty(), 2, 2, genesis_qc.clone(), &node.signer);
     timed_block_on(&mut runtime, async {
         let bad_proposal = ProposalMsg::new(
             block_skip_round,
@@ -487,9 +636,15 @@ fn no_vote_on_invalid_proposer() {
     let incorrect_proposer = nodes.pop().unwrap();
     let mut node = nodes.pop().unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_incorrect_proposer =
-        Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &incorrect_proposer.signer);
+    let correct_block =
+        Block::new_proposal(Payload::empty(), 1, 1, genesis_qc.clone(), &node.signer);
+    let block_incorrect_proposer = Block::new_proposal(
+        Payload::empty(),
+        1,
+        1,
+        genesis_qc.clone(),
+        &incorrect_proposer.signer,
+    );
     timed_block_on(&mut runtime, async {
         let bad_proposal = ProposalMsg::new(
             block_incorrect_proposer,
@@ -512,6 +667,32 @@ fn no_vote_on_invalid_proposer() {
     });
 }
 
+/// Creates a proposal of a single node setup which records itself as the failed author of the
+/// rounds skipped since the parent.
+fn proposal_with_failed_authors(
+    round: Round,
+    timestamp_usecs: u64,
+    parent_qc: QuorumCert,
+    signer: &ValidatorSigner,
+) -> Block {
+    let start = std::cmp::max(
+        parent_qc.certified_block().round() + 1,
+        round.saturating_sub(10),
+    );
+    let failed_authors = Some((start..round).map(|r| (r, signer.author())).collect());
+    Block::new_proposal_from_block_data(
+        BlockData::new_proposal(
+            Payload::empty(),
+            signer.author(),
+            failed_authors,
+            round,
+            timestamp_usecs,
+            parent_qc,
+        ),
+        signer,
+    )
+}
+
 #[test]
 /// We allow to 'skip' round if proposal carries timeout certificate for next round
 fn new_round_on_timeout_certificate() {
@@ -523,8 +704,10 @@ fn new_round_on_timeout_certificate() {
         .pop()
         .unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let correct_block =
+        Block::new_proposal(Payload::empty(), 1, 1, genesis_qc.clone(), &node.signer);
+    let block_skip_round =
+        Block::new_proposal(Payload::empty(), 2, 2, genesis_qc.clone(), &node.signer);
     let timeout = Timeout::new(1, 1);
     let timeout_signature = timeout.sign(&node.signer);
 
@@ -552,6 +735,85 @@ fn new_round_on_timeout_certificate() {
     });
 }
 
+#[test]
+/// A proposal which skips rounds has to record their valid proposers as failed authors
+fn reject_proposal_with_wrong_failed_authors() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut node = NodeSetup::create_nodes_with_failed_authors(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        true,
+    )
+    .pop()
+    .unwrap();
+    let genesis_qc = certificate_for_genesis();
+    let timeout = Timeout::new(1, 2);
+    let mut tc = TimeoutCertificate::new(timeout.clone());
+    tc.add_signature(node.signer.author(), timeout.sign(&node.signer));
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), Some(tc), None);
+
+    let missing_failed_authors =
+        Block::new_proposal(Payload::empty(), 3, 3, genesis_qc.clone(), &node.signer);
+    let partial_failed_authors = Block::new_proposal_from_block_data(
+        BlockData::new_proposal(
+            Payload::empty(),
+            node.signer.author(),
+            Some(vec![(2, node.signer.author())]),
+            3,
+            3,
+            genesis_qc.clone(),
+        ),
+        &node.signer,
+    );
+    let correct_block = proposal_with_failed_authors(3, 3, genesis_qc, &node.signer);
+
+    timed_block_on(&mut runtime, async {
+        for block in [missing_failed_authors, partial_failed_authors] {
+            assert!(node
+                .round_manager
+                .process_proposal_msg(ProposalMsg::new(block, sync_info.clone()))
+                .await
+                .is_err());
+        }
+        node.round_manager
+            .process_proposal_msg(ProposalMsg::new(correct_block, sync_info))
+            .await
+            .unwrap();
+    });
+}
+
+#[test]
+/// A proposal must not record failed authors if the proposer election doesn't rely on them
+fn reject_proposal_with_unexpected_failed_authors() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut node = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1)
+        .pop()
+        .unwrap();
+    let genesis_qc = certificate_for_genesis();
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), None, None);
+    let unexpected_failed_authors =
+        proposal_with_failed_authors(1, 1, genesis_qc.clone(), &node.signer);
+    let correct_block = Block::new_proposal(Payload::empty(), 1, 1, genesis_qc, &node.signer);
+
+    timed_block_on(&mut runtime, async {
+        assert!(node
+            .round_manager
+            .process_proposal_msg(ProposalMsg::new(
+                unexpected_failed_authors,
+                sync_info.clone()
+            ))
+            .await
+            .is_err());
+        node.round_manager
+            .process_proposal_msg(ProposalMsg::new(correct_block, sync_info))
+            .await
+            .unwrap();
+    });
+}
+
 #[test]
 fn response_on_block_retrieval() {
     let mut runtime = consensus_runtime();
@@ -561,7 +823,7 @@ fn response_on_block_retrieval() {
         .unwrap();
 
     let genesis_qc = certificate_for_genesis();
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let block = Block::new_proposal(Payload::empty(), 1, 1, genesis_qc.clone(), &node.signer);
     let block_id = block.id();
     let proposal = ProposalMsg::new(
         block,
@@ -665,7 +927,7 @@ fn recover_on_restart() {
     let num_proposals = 100;
     // insert a few successful proposals
     for i in 1..=num_proposals {
-        let proposal = inserter.create_block_with_qc(genesis_qc.clone(), i, i, vec![]);
+        let proposal = inserter.create_block_with_qc(genesis_qc.clone(), i, i, Payload::empty());
         let timeout = Timeout::new(1, i - 1);
         let mut tc = TimeoutCertificate::new(timeout.clone());
         tc.add_signature(inserter.signer().author(), inserter.signer().sign(&timeout));
@@ -775,7 +1037,7 @@ fn sync_info_sent_on_stale_sync_info() {
     let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 2);
     runtime.spawn(playground.start());
     let genesis_qc = certificate_for_genesis();
-    let block_0 = Block::new_proposal(vec![], 1, 1, genesis_qc, &nodes[0].signer);
+    let block_0 = Block::new_proposal(Payload::empty(), 1, 1, genesis_qc, &nodes[0].signer);
     let parent_block_info = block_0.quorum_cert().certified_block();
     let block_0_quorum_cert = gen_test_certificate(
         vec![&nodes[0].signer, &nodes[1].signer],
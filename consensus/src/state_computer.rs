@@ -3,6 +3,8 @@
 
 use crate::{
     error::StateSyncError,
+    observer::publisher::ConsensusPublisher,
+    quorum_store::batch_store::BatchStore,
     state_replication::{StateComputer, StateComputerCommitCallBackType},
 };
 use anyhow::Result;
@@ -22,16 +24,24 @@ use std::{boxed::Box, sync::Arc};
 pub struct ExecutionProxy {
     execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
     state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    // Publishes the committed blocks to the consensus observers, if enabled.
+    publisher: Option<Arc<ConsensusPublisher>>,
+    // Resolves the payloads of the blocks in the quorum store into transactions.
+    batch_store: Arc<BatchStore>,
 }
 
 impl ExecutionProxy {
     pub fn new(
         execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
         state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+        publisher: Option<Arc<ConsensusPublisher>>,
+        batch_store: Arc<BatchStore>,
     ) -> Self {
         Self {
             execution_correctness_client,
             state_sync_notifier,
+            publisher,
+            batch_store,
         }
     }
 }
@@ -56,11 +66,15 @@ impl StateComputer for ExecutionProxy {
             "Executing block",
         );
 
+        let payload_txns = self.batch_store.get_transactions(block)?;
         // TODO: figure out error handling for the prologue txn
         monitor!(
             "execute_block",
-            self.execution_correctness_client
-                .execute_block(block.clone(), parent_block_id)
+            self.execution_correctness_client.execute_block(
+                block.clone(),
+                payload_txns,
+                parent_block_id
+            )
         )
     }
 
@@ -77,16 +91,27 @@ impl StateComputer for ExecutionProxy {
 
         for block in blocks {
             block_ids.push(block.id());
-            txns.extend(block.transactions_to_commit());
+            let payload_txns = self.batch_store.get_transactions(block.block())?;
+            txns.extend(block.transactions_to_commit(payload_txns));
             reconfig_events.extend(block.reconfig_event());
         }
 
+        // Publish the blocks before committing them, so that the observers execute them while
+        // they're being committed here.
+        if let Some(publisher) = &self.publisher {
+            publisher.publish_ordered_blocks(blocks, finality_proof.clone());
+        }
+
         monitor!(
             "commit_block",
             self.execution_correctness_client
                 .commit_blocks(block_ids, finality_proof.clone())?
         );
 
+        if let Some(publisher) = &self.publisher {
+            publisher.publish_commit_decision(finality_proof.clone());
+        }
+
         if let Err(e) = monitor!(
             "notify_state_sync",
             self.state_sync_notifier
@@ -96,6 +121,8 @@ impl StateComputer for ExecutionProxy {
             error!(error = ?e, "Failed to notify state synchronizer");
         }
 
+        self.batch_store.notify_commit(blocks);
+
         callback(blocks, finality_proof);
 
         Ok(())
@@ -3,6 +3,7 @@
 
 use crate::{
     error::StateSyncError,
+    quorum_store::batch_store::BatchStore,
     state_replication::{StateComputer, StateComputerCommitCallBackType},
     test_utils::mock_storage::MockStorage,
 };
@@ -11,7 +12,7 @@ use consensus_types::{block::Block, common::Payload, executed_block::ExecutedBlo
 use diem_crypto::HashValue;
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use executor_types::{Error, StateComputeResult};
 use futures::channel::mpsc;
 use std::{collections::HashMap, sync::Arc};
@@ -21,7 +22,8 @@ pub struct MockStateComputer {
     state_sync_client: mpsc::UnboundedSender<Payload>,
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
     consensus_db: Arc<MockStorage>,
-    block_cache: Mutex<HashMap<HashValue, Payload>>,
+    batch_store: Arc<BatchStore>,
+    block_cache: Mutex<HashMap<HashValue, Vec<SignedTransaction>>>,
 }
 
 impl MockStateComputer {
@@ -29,11 +31,13 @@ impl MockStateComputer {
         state_sync_client: mpsc::UnboundedSender<Payload>,
         commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
         consensus_db: Arc<MockStorage>,
+        batch_store: Arc<BatchStore>,
     ) -> Self {
         MockStateComputer {
             state_sync_client,
             commit_callback,
             consensus_db,
+            batch_store,
             block_cache: Mutex::new(HashMap::new()),
         }
     }
@@ -46,9 +50,8 @@ impl StateComputer for MockStateComputer {
         block: &Block,
         _parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
-        self.block_cache
-            .lock()
-            .insert(block.id(), block.payload().unwrap_or(&vec![]).clone());
+        let txns = self.batch_store.get_transactions(block)?;
+        self.block_cache.lock().insert(block.id(), txns);
         let result = StateComputeResult::new_dummy();
         Ok(result)
     }
@@ -73,10 +76,14 @@ impl StateComputer for MockStateComputer {
             txns.append(&mut payload);
         }
         // they may fail during shutdown
-        let _ = self.state_sync_client.unbounded_send(txns);
+        let _ = self
+            .state_sync_client
+            .unbounded_send(Payload::DirectMempool(txns));
 
         let _ = self.commit_callback.unbounded_send(commit.clone());
 
+        self.batch_store.notify_commit(blocks);
+
         call_back(blocks, commit);
 
         Ok(())
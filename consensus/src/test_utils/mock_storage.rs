@@ -9,8 +9,9 @@ use crate::{
 };
 use anyhow::Result;
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_2chain::TwoChainTimeoutCertificate,
-    timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, proof_of_store::Batch, quorum_cert::QuorumCert,
+    timeout_2chain::TwoChainTimeoutCertificate, timeout_certificate::TimeoutCertificate,
+    vote::Vote,
 };
 use diem_crypto::HashValue;
 use diem_infallible::Mutex;
@@ -32,6 +33,7 @@ pub struct MockSharedStorage {
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub lis: Mutex<HashMap<u64, LedgerInfoWithSignatures>>,
     pub last_vote: Mutex<Option<Vote>>,
+    pub batches: Mutex<HashMap<HashValue, Batch>>,
 
     // Liveness state
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
@@ -46,6 +48,7 @@ impl MockSharedStorage {
             qc: Mutex::new(HashMap::new()),
             lis: Mutex::new(HashMap::new()),
             last_vote: Mutex::new(None),
+            batches: Mutex::new(HashMap::new()),
             highest_timeout_certificate: Mutex::new(None),
             highest_2chain_timeout_certificate: Mutex::new(None),
             validator_set,
@@ -245,6 +248,32 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(EpochChangeProof::new(vec![lis], false))
     }
 
+    fn save_batches(&self, batches: Vec<Batch>) -> Result<()> {
+        let mut stored = self.shared_storage.batches.lock();
+        for batch in batches {
+            stored.insert(*batch.digest(), batch);
+        }
+        Ok(())
+    }
+
+    fn delete_batches(&self, digests: Vec<HashValue>) -> Result<()> {
+        let mut stored = self.shared_storage.batches.lock();
+        for digest in digests {
+            stored.remove(&digest);
+        }
+        Ok(())
+    }
+
+    fn retrieve_batches(&self) -> Result<Vec<Batch>> {
+        Ok(self
+            .shared_storage
+            .batches
+            .lock()
+            .values()
+            .cloned()
+            .collect())
+    }
+
     fn diem_db(&self) -> Arc<dyn DbReader<DpnProto>> {
         unimplemented!()
     }
@@ -317,6 +346,18 @@ impl PersistentLivenessStorage for EmptyStorage {
         unimplemented!()
     }
 
+    fn save_batches(&self, _: Vec<Batch>) -> Result<()> {
+        Ok(())
+    }
+
+    fn delete_batches(&self, _: Vec<HashValue>) -> Result<()> {
+        Ok(())
+    }
+
+    fn retrieve_batches(&self) -> Result<Vec<Batch>> {
+        Ok(vec![])
+    }
+
     fn diem_db(&self) -> Arc<dyn DbReader<DpnProto>> {
         unimplemented!()
     }
@@ -129,7 +129,7 @@ impl TreeInserter {
                 parent_qc,
                 parent.timestamp_usecs() + 1,
                 round,
-                vec![],
+                Payload::empty(),
             ))
             .unwrap()
     }
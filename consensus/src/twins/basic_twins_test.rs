@@ -7,8 +7,14 @@ use crate::{
     test_utils::{consensus_runtime, timed_block_on},
     twins::twins_node::SMRNode,
 };
-use consensus_types::{block::Block, common::Round};
-use diem_config::config::ConsensusProposerType::{FixedProposer, RotatingProposer, RoundProposer};
+use consensus_types::{
+    block::Block,
+    common::{Payload, Round},
+};
+use diem_config::config::{
+    ConsensusProposerType::{FixedProposer, RotatingProposer, RoundProposer},
+    QuorumStoreConfig,
+};
 use futures::StreamExt;
 use std::collections::HashMap;
 
@@ -310,3 +316,67 @@ fn twins_commit_test() {
         }
     });
 }
+
+#[test]
+/// This test checks that the blocks proposing proofs of store are committed
+/// when the quorum store is enabled.
+///
+/// Setup:
+///
+/// 4 honest nodes, and 0 twins, with the quorum store enabled
+///
+/// Test:
+///
+/// Wait for a proposal carrying proofs of store, the batches being disseminated
+/// ahead of it. Check that all the nodes commit it.
+///
+/// Run the test:
+/// cargo xtest -p consensus quorum_store_commit_test -- --nocapture
+fn quorum_store_commit_test() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let num_nodes = 4;
+    let num_twins = 0;
+    let mut nodes = SMRNode::start_num_nodes_with_twins_and_quorum_store(
+        num_nodes,
+        num_twins,
+        &mut playground,
+        RotatingProposer,
+        None,
+        QuorumStoreConfig {
+            enabled: true,
+            ..QuorumStoreConfig::default()
+        },
+    );
+    let handle = runtime.handle().clone();
+
+    timed_block_on(&mut runtime, async {
+        let msg = playground
+            .wait_for_messages(1, |(_, msg)| match msg {
+                ConsensusMsg::ProposalMsg(proposal) => matches!(
+                    proposal.proposal().payload(),
+                    Some(Payload::InQuorumStore(proofs)) if !proofs.is_empty()
+                ),
+                _ => false,
+            })
+            .await;
+        let round = match &msg[0].1 {
+            ConsensusMsg::ProposalMsg(proposal) => proposal.proposal().round(),
+            _ => panic!("Unexpected message found"),
+        };
+        handle.spawn(playground.start());
+
+        for node in &mut nodes {
+            loop {
+                let commit = node
+                    .commit_cb_receiver
+                    .next()
+                    .await
+                    .expect("[TwinsTest] Test failed due to no commit(s)");
+                if commit.ledger_info().commit_info().round() >= round {
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,167 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    test_utils::build_simple_tree,
+    twins::twins_generator::{check_single_chain, TwinsScenario, TwinsScenarioGenerator},
+};
+use diem_temppath::TempPath;
+use std::{collections::HashSet, env, path::Path};
+
+const GENERATOR: TwinsScenarioGenerator = TwinsScenarioGenerator {
+    num_nodes: 4,
+    num_twins: 1,
+    max_partitions: 2,
+    num_partitioned_rounds: 4,
+    num_healed_rounds: 20,
+};
+
+#[test]
+/// This test checks that the generated scenarios are deterministic and well formed.
+fn twins_scenario_generator_test() {
+    let num_ids = GENERATOR.num_nodes + GENERATOR.num_twins;
+    let quorum = GENERATOR.num_nodes * 2 / 3 + 1;
+    let mut twins_split_across_quorums = false;
+    for seed in 0..100 {
+        let scenario = GENERATOR.generate(seed);
+        assert_eq!(scenario, GENERATOR.generate(seed));
+        assert_eq!(scenario.twins.len(), GENERATOR.num_twins);
+        assert_eq!(scenario.healed_round, GENERATOR.num_partitioned_rounds + 1);
+
+        for (round, leader) in &scenario.round_leaders {
+            assert!(*leader < GENERATOR.num_nodes);
+            if *round >= scenario.healed_round {
+                assert!(!scenario.twins.contains(leader));
+            }
+        }
+        assert_eq!(
+            scenario.round_leaders.len() as u64,
+            GENERATOR.num_partitioned_rounds + GENERATOR.num_healed_rounds
+        );
+        assert_eq!(
+            scenario.round_partitions.len() as u64,
+            GENERATOR.num_partitioned_rounds + GENERATOR.num_healed_rounds
+        );
+
+        // The validator a node or twin id is running
+        let validator = |id: &usize| {
+            id.checked_sub(GENERATOR.num_nodes)
+                .map_or(*id, |twin_idx| scenario.twins[twin_idx])
+        };
+        let honest_nodes: Vec<_> = (0..GENERATOR.num_nodes)
+            .filter(|idx| !scenario.twins.contains(idx))
+            .collect();
+        // The ids of the partition holding a quorum of each partitioned round.
+        let mut quorum_partitions = vec![];
+        for (round, partitions) in &scenario.round_partitions {
+            assert!(partitions.len() <= GENERATOR.max_partitions);
+            let mut ids: Vec<_> = partitions.iter().flatten().copied().collect();
+            ids.sort_unstable();
+            assert_eq!(ids, (0..num_ids).collect::<Vec<_>>());
+            if *round >= scenario.healed_round {
+                // The nodes without twin form a quorum which stays connected.
+                assert!(partitions
+                    .iter()
+                    .any(|partition| honest_nodes.iter().all(|idx| partition.contains(idx))));
+            } else {
+                let quorum_partition = partitions
+                    .iter()
+                    .find(|partition| {
+                        partition
+                            .iter()
+                            .map(validator)
+                            .collect::<HashSet<_>>()
+                            .len()
+                            >= quorum
+                    })
+                    .expect("A partition should hold a quorum");
+                quorum_partitions.push(quorum_partition);
+            }
+        }
+
+        // A node and its twin can each be part of a quorum without the other.
+        for (twin_idx, node) in scenario.twins.iter().enumerate() {
+            let twin = GENERATOR.num_nodes + twin_idx;
+            let only_contains = |partition: &&Vec<usize>, id, other| {
+                partition.contains(&id) && !partition.contains(&other)
+            };
+            twins_split_across_quorums |= quorum_partitions
+                .iter()
+                .any(|partition| only_contains(partition, *node, twin))
+                && quorum_partitions
+                    .iter()
+                    .any(|partition| only_contains(partition, twin, *node));
+        }
+    }
+    assert!(twins_split_across_quorums);
+}
+
+#[test]
+/// This test checks that a saved scenario is loaded back identically.
+fn twins_scenario_save_load_test() {
+    let dir = TempPath::new();
+    let scenario = GENERATOR.generate(42);
+    let path = scenario.save(dir.path()).unwrap();
+    assert_eq!(TwinsScenario::load(&path).unwrap(), scenario);
+}
+
+#[test]
+/// This test checks that the committed blocks are detected to conflict when they're not on a
+/// single chain.
+///
+/// Setup:
+///
+///       ╭--> A1--> A2--> A3
+/// Genesis--> B1--> B2
+///             ╰--> C1
+fn twins_single_chain_check_test() {
+    let (blocks, _) = build_simple_tree();
+    let block_infos: Vec<_> = blocks.iter().map(|block| block.block_info()).collect();
+    let blocks = blocks
+        .iter()
+        .map(|block| (block.id(), block.block().clone()))
+        .collect();
+    let (a1, a2, a3) = (&block_infos[1], &block_infos[2], &block_infos[3]);
+    let (b1, b2, c1) = (&block_infos[4], &block_infos[5], &block_infos[6]);
+
+    for commits in [vec![a1, a3], vec![a3, a2, a1, a3], vec![b1, c1], vec![b2]] {
+        let commits: Vec<_> = commits.into_iter().cloned().collect();
+        assert!(check_single_chain(&commits, &blocks).is_ok());
+    }
+    for commits in [vec![a2, b2], vec![a1, b1], vec![b2, c1], vec![a1, a3, c1]] {
+        let commits: Vec<_> = commits.into_iter().cloned().collect();
+        assert!(check_single_chain(&commits, &blocks).is_err());
+    }
+}
+
+#[test]
+/// This test runs sampled Twins scenarios and checks that no conflicting blocks are committed
+/// and that the nodes without twin make progress once the network heals.
+///
+/// Setup:
+///
+/// 4 nodes, 1 of them running a twin, split into up to 2 partitions sampled for each round. After
+/// 4 rounds, the 3 nodes without twin stay connected.
+///
+/// Run the test with other scenarios:
+/// TWINS_SEED=<first seed> cargo xtest -p consensus randomized_twins_test -- --nocapture
+fn randomized_twins_test() {
+    let first_seed = env::var("TWINS_SEED").map_or(0, |seed| seed.parse().unwrap());
+    for seed in first_seed..first_seed + 3 {
+        GENERATOR.generate(seed).run_or_save();
+    }
+}
+
+#[test]
+#[ignore]
+/// This test replays a scenario saved by a failing run of `randomized_twins_test`.
+///
+/// Run the test:
+/// TWINS_SCENARIO=<scenario file> cargo xtest -p consensus twins_replay_test -- --ignored
+fn twins_replay_test() {
+    let path = env::var("TWINS_SCENARIO").expect("TWINS_SCENARIO should point to a scenario");
+    TwinsScenario::load(Path::new(&path))
+        .unwrap()
+        .run()
+        .unwrap();
+}
@@ -0,0 +1,308 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_tests::{NetworkPlayground, TwinId},
+    test_utils::{consensus_runtime, TEST_TIMEOUT},
+    twins::twins_node::SMRNode,
+};
+use anyhow::{ensure, format_err, Result};
+use consensus_types::{block::Block, common::Round};
+use diem_config::config::{ConsensusConfig, ConsensusProposerType::RoundProposer};
+use diem_crypto::HashValue;
+use diem_types::block_info::BlockInfo;
+use futures::StreamExt;
+use rand::{rngs::StdRng, seq::index, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Environment variable pointing to the directory the failing scenarios are saved to, defaults to
+/// the temporary directory.
+pub const TWINS_SCENARIO_DIR: &str = "TWINS_SCENARIO_DIR";
+
+/// A Twins scenario: the nodes running a twin, the leader of each round and the network partitions
+/// of each round. The network heals at `healed_round`: from then on, the nodes without twin stay
+/// connected, and the network isn't partitioned anymore after the last round with partitions. The
+/// partitions of the previous rounds are also lifted after a delay, as after the global
+/// stabilization time, for the nodes left behind in these rounds to catch up.
+/// Nodes are designated by their index, the twins following the nodes in the order of `twins`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TwinsScenario {
+    /// Seed the scenario has been generated from
+    pub seed: u64,
+    pub num_nodes: usize,
+    /// Indices of the nodes running a twin
+    pub twins: Vec<usize>,
+    /// Index of the node leading each round
+    pub round_leaders: BTreeMap<Round, usize>,
+    /// Network partitions of each round, as groups of node (or twin) ids
+    pub round_partitions: BTreeMap<Round, Vec<Vec<usize>>>,
+    /// First round from which the nodes without twin stay connected
+    pub healed_round: Round,
+}
+
+/// Samples Twins scenarios where at most f nodes run a twin.
+///
+/// During the partitioned rounds, the nodes and twins are randomly split into partitions, sampled
+/// independently for each round, and the rounds are led by random nodes. One of the partitions of
+/// each round holds a quorum of nodes or twins of distinct validators, so that the round can be
+/// completed: a node and its twin can be split, each one of them being part of the quorum in a
+/// different round. After the network heals, the nodes without twin, which form a quorum, stay
+/// connected while the others are still randomly split, and the rounds are led by the nodes without
+/// twin.
+pub struct TwinsScenarioGenerator {
+    pub num_nodes: usize,
+    pub num_twins: usize,
+    /// Maximum number of partitions per round
+    pub max_partitions: usize,
+    /// Number of rounds with network partitions before the network heals
+    pub num_partitioned_rounds: Round,
+    /// Number of rounds led by nodes without twin after the network heals, the nodes with a twin
+    /// and the twins still being partitioned
+    pub num_healed_rounds: Round,
+}
+
+impl TwinsScenarioGenerator {
+    /// Returns the scenario generated from the given seed.
+    pub fn generate(&self, seed: u64) -> TwinsScenario {
+        assert!(
+            3 * self.num_twins < self.num_nodes,
+            "[TwinsTest] At most f nodes can run a twin"
+        );
+        assert!(self.max_partitions > 0);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut twins = index::sample(&mut rng, self.num_nodes, self.num_twins).into_vec();
+        twins.sort_unstable();
+        let honest_nodes: Vec<_> = (0..self.num_nodes)
+            .filter(|idx| !twins.contains(idx))
+            .collect();
+
+        let mut round_leaders = BTreeMap::new();
+        let mut round_partitions = BTreeMap::new();
+        // A quorum of nodes or twins of distinct validators, sampled for each round, is connected
+        // for the round to be completed. The liveness is only checked after the network heals, the
+        // nodes without twin then forming a quorum which stays connected.
+        let quorum = self.num_nodes * 2 / 3 + 1;
+        for round in 1..=self.num_partitioned_rounds {
+            round_leaders.insert(round, rng.gen_range(0..self.num_nodes));
+            let connected: Vec<_> = index::sample(&mut rng, self.num_nodes, quorum)
+                .into_iter()
+                .map(|idx| match twins.iter().position(|twin| *twin == idx) {
+                    Some(twin_idx) if rng.gen() => self.num_nodes + twin_idx,
+                    _ => idx,
+                })
+                .collect();
+            round_partitions.insert(round, self.sample_partitions(&mut rng, &connected));
+        }
+        let healed_round = self.num_partitioned_rounds + 1;
+        for round in healed_round..healed_round + self.num_healed_rounds {
+            round_leaders.insert(round, honest_nodes[rng.gen_range(0..honest_nodes.len())]);
+            round_partitions.insert(round, self.sample_partitions(&mut rng, &honest_nodes));
+        }
+
+        TwinsScenario {
+            seed,
+            num_nodes: self.num_nodes,
+            twins,
+            round_leaders,
+            round_partitions,
+            healed_round,
+        }
+    }
+
+    /// Randomly splits the nodes and twins into partitions, keeping the given ids connected.
+    fn sample_partitions(&self, rng: &mut StdRng, connected: &[usize]) -> Vec<Vec<usize>> {
+        let num_partitions = rng.gen_range(1..=self.max_partitions);
+        let connected_partition = rng.gen_range(0..num_partitions);
+        let mut partitions = vec![vec![]; num_partitions];
+        for id in 0..self.num_nodes + self.num_twins {
+            let partition = if connected.contains(&id) {
+                connected_partition
+            } else {
+                rng.gen_range(0..num_partitions)
+            };
+            partitions[partition].push(id);
+        }
+        partitions.retain(|partition| !partition.is_empty());
+        partitions
+    }
+}
+
+impl TwinsScenario {
+    /// Loads a scenario saved with `save`.
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Saves the scenario to the given directory, returning the path of its file.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("twins_scenario_{}.json", self.seed));
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Runs the scenario, and saves it before panicking if it fails so that it can be replayed.
+    pub fn run_or_save(&self) {
+        if let Err(e) = self.run() {
+            let dir = env::var_os(TWINS_SCENARIO_DIR).map_or_else(env::temp_dir, PathBuf::from);
+            let path = self
+                .save(&dir)
+                .expect("[TwinsTest] Unable to save the failing scenario");
+            panic!(
+                "[TwinsTest] Scenario {} failed: {}\nReplay it with:\nTWINS_SCENARIO={} cargo xtest -p consensus twins_replay_test -- --ignored",
+                self.seed,
+                e,
+                path.display()
+            );
+        }
+    }
+
+    /// Runs the scenario over the network playground, checking that the committed blocks are on
+    /// a single chain and that the nodes without twin commit a block after the network heals.
+    pub fn run(&self) -> Result<()> {
+        let runtime = consensus_runtime();
+        let mut playground = NetworkPlayground::new(runtime.handle().clone());
+        let mut nodes = SMRNode::start_nodes_with_twins(
+            self.num_nodes,
+            &self.twins,
+            &mut playground,
+            RoundProposer(HashMap::new()),
+            Some(self.round_leaders.clone().into_iter().collect()),
+            |config| {
+                // The rounds whose leader is cut from the quorum only complete with timeouts
+                config.round_initial_timeout_ms =
+                    ConsensusConfig::default().round_initial_timeout_ms
+            },
+        );
+        let genesis =
+            Block::make_genesis_block_from_ledger_info(&nodes[0].storage.get_ledger_info());
+
+        let twin_ids: Vec<TwinId> = nodes.iter().map(|node| node.id).collect();
+        let round_partitions = self
+            .round_partitions
+            .iter()
+            .map(|(round, partitions)| {
+                let partitions = partitions
+                    .iter()
+                    .map(|partition| partition.iter().map(|id| twin_ids[*id]).collect())
+                    .collect();
+                (*round, partitions)
+            })
+            .collect();
+        ensure!(
+            playground.split_network_round(&round_partitions),
+            "[TwinsTest] Overlapping partitions"
+        );
+        // A node left behind in a partitioned round only catches up with the certificates of the
+        // nodes of its partition in later rounds, which may never come: the partitions of these
+        // rounds are lifted once they had the time to complete.
+        let round_timeout =
+            Duration::from_millis(ConsensusConfig::default().round_initial_timeout_ms);
+        playground.heal_network_rounds_after(
+            (1..self.healed_round).collect(),
+            round_timeout * 2 * self.healed_round as u32,
+        );
+        runtime.spawn(playground.start());
+
+        let honest_nodes: Vec<_> = (0..self.num_nodes)
+            .filter(|idx| !self.twins.contains(idx))
+            .collect();
+        let mut commits = vec![];
+        let liveness = runtime.block_on(async {
+            tokio::time::timeout(
+                TEST_TIMEOUT,
+                wait_for_commits(&mut nodes, &honest_nodes, self.healed_round, &mut commits),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err(format_err!(
+                    "[TwinsTest] No commit after the network healed at round {}",
+                    self.healed_round
+                ))
+            })
+        });
+
+        for node in &mut nodes {
+            while let Ok(Some(commit)) = node.commit_cb_receiver.try_next() {
+                commits.push(commit.ledger_info().commit_info().clone());
+            }
+        }
+        let mut blocks = HashMap::new();
+        blocks.insert(genesis.id(), genesis);
+        for node in &nodes {
+            blocks.extend(
+                node.executed_blocks
+                    .lock()
+                    .iter()
+                    .map(|(id, block)| (*id, block.clone())),
+            );
+        }
+        check_single_chain(&commits, &blocks)?;
+        liveness
+    }
+}
+
+/// Waits for the given nodes to commit a block of the given round or higher, collecting the
+/// committed blocks along the way.
+async fn wait_for_commits(
+    nodes: &mut [SMRNode],
+    node_indices: &[usize],
+    round: Round,
+    commits: &mut Vec<BlockInfo>,
+) -> Result<()> {
+    for idx in node_indices {
+        loop {
+            let commit = nodes[*idx]
+                .commit_cb_receiver
+                .next()
+                .await
+                .ok_or_else(|| format_err!("[TwinsTest] Node {} stopped committing", idx))?;
+            let commit_info = commit.ledger_info().commit_info().clone();
+            commits.push(commit_info.clone());
+            if commit_info.round() >= round {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the committed blocks are all on a single chain, the given blocks including the
+/// ancestors of the committed blocks.
+pub fn check_single_chain(commits: &[BlockInfo], blocks: &HashMap<HashValue, Block>) -> Result<()> {
+    let block = |id: &HashValue| {
+        blocks
+            .get(id)
+            .ok_or_else(|| format_err!("[TwinsTest] Unknown block {}", id))
+    };
+    let mut committed: Vec<_> = commits
+        .iter()
+        .map(|commit_info| (commit_info.round(), commit_info.id()))
+        .collect();
+    committed.sort_unstable();
+    committed.dedup();
+    // Ancestry is transitive: checking the successive committed blocks is enough.
+    for pair in committed.windows(2) {
+        let ((round, id), (next_round, next_id)) = (pair[0], pair[1]);
+        let mut ancestor = block(&next_id)?;
+        while ancestor.round() > round {
+            ancestor = block(&ancestor.parent_id())?;
+        }
+        ensure!(
+            ancestor.id() == id,
+            "[TwinsTest] Conflicting blocks committed: {} at round {} and {} at round {}",
+            id,
+            round,
+            next_id,
+            next_round
+        );
+    }
+    Ok(())
+}
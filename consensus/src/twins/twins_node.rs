@@ -4,22 +4,32 @@
 use crate::{
     counters,
     epoch_manager::EpochManager,
+    error::StateSyncError,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
+    quorum_store::batch_store::BatchStore,
+    state_replication::{StateComputer, StateComputerCommitCallBackType},
     test_utils::{MockStateComputer, MockStorage, MockTransactionManager},
     util::time_service::ClockTimeService,
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
-use consensus_types::common::{Author, Payload, Round};
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+    executed_block::ExecutedBlock,
+};
 use diem_config::{
     config::{
+        ConsensusConfig,
         ConsensusProposerType::{self, RoundProposer},
-        NodeConfig, WaypointConfig,
+        NodeConfig, QuorumStoreConfig, WaypointConfig,
     },
     generator::{self, ValidatorSwarm},
     network_id::NetworkId,
 };
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
 use diem_mempool::mocks::MockSharedMempool;
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
@@ -28,6 +38,7 @@ use diem_types::{
     waypoint::Waypoint,
 };
 use event_notifications::{ReconfigNotification, ReconfigNotificationListener};
+use executor_types::{Error, StateComputeResult};
 use futures::channel::mpsc;
 use network::{
     peer_manager::{conn_notifs_channel, ConnectionRequestSender, PeerManagerRequestSender},
@@ -41,11 +52,47 @@ use network::{
 use std::{collections::HashMap, iter::FromIterator, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
 
+/// State computer recording the blocks executed by a node, so that the committed blocks can be
+/// traced back to their ancestors even after they are pruned from the block tree.
+struct RecordingStateComputer {
+    inner: MockStateComputer,
+    executed_blocks: Arc<Mutex<HashMap<HashValue, Block>>>,
+}
+
+#[async_trait::async_trait]
+impl StateComputer for RecordingStateComputer {
+    fn compute(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, Error> {
+        self.executed_blocks
+            .lock()
+            .insert(block.id(), block.clone());
+        self.inner.compute(block, parent_block_id)
+    }
+
+    async fn commit(
+        &self,
+        blocks: &[Arc<ExecutedBlock>],
+        commit: LedgerInfoWithSignatures,
+        call_back: StateComputerCommitCallBackType,
+    ) -> Result<(), Error> {
+        self.inner.commit(blocks, commit, call_back).await
+    }
+
+    async fn sync_to(&self, commit: LedgerInfoWithSignatures) -> Result<(), StateSyncError> {
+        self.inner.sync_to(commit).await
+    }
+}
+
 /// Auxiliary struct that is preparing SMR for the test
 pub struct SMRNode {
     pub id: TwinId,
     pub storage: Arc<MockStorage>,
     pub commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    /// All the blocks executed by the node, by id
+    pub executed_blocks: Arc<Mutex<HashMap<HashValue, Block>>>,
     _runtime: Runtime,
     _shared_mempool: MockSharedMempool,
     _state_sync: mpsc::UnboundedReceiver<Payload>,
@@ -80,11 +127,20 @@ impl SMRNode {
         let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
         let shared_mempool = MockSharedMempool::new();
         let consensus_to_mempool_sender = shared_mempool.consensus_sender.clone();
-        let state_computer = Arc::new(MockStateComputer::new(
-            state_sync_client,
-            commit_cb_sender,
-            Arc::clone(&storage),
+        let batch_store = Arc::new(BatchStore::new(
+            storage.clone(),
+            &config.consensus.quorum_store,
         ));
+        let executed_blocks = Arc::new(Mutex::new(HashMap::new()));
+        let state_computer = Arc::new(RecordingStateComputer {
+            inner: MockStateComputer::new(
+                state_sync_client,
+                commit_cb_sender,
+                Arc::clone(&storage),
+                batch_store.clone(),
+            ),
+            executed_blocks: executed_blocks.clone(),
+        });
         let txn_manager = Arc::new(MockTransactionManager::new(Some(
             consensus_to_mempool_sender,
         )));
@@ -133,6 +189,7 @@ impl SMRNode {
             txn_manager,
             state_computer,
             storage.clone(),
+            batch_store,
             reconfig_listener,
         );
         let (network_task, network_receiver) = NetworkTask::new(network_events, self_receiver);
@@ -144,6 +201,7 @@ impl SMRNode {
             _runtime: runtime,
             commit_cb_receiver,
             storage,
+            executed_blocks,
             _shared_mempool: shared_mempool,
             _state_sync: state_sync,
         }
@@ -156,8 +214,50 @@ impl SMRNode {
         playground: &mut NetworkPlayground,
         proposer_type: ConsensusProposerType,
         round_proposers_idx: Option<HashMap<Round, usize>>,
+    ) -> Vec<Self> {
+        Self::start_num_nodes_with_twins_and_quorum_store(
+            num_nodes,
+            num_twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            QuorumStoreConfig::default(),
+        )
+    }
+
+    /// Starts a given number of nodes and their twins, with the given quorum store configuration
+    pub fn start_num_nodes_with_twins_and_quorum_store(
+        num_nodes: usize,
+        num_twins: usize,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        quorum_store: QuorumStoreConfig,
     ) -> Vec<Self> {
         assert!(num_nodes >= num_twins);
+        let twins: Vec<_> = (0..num_twins).collect();
+        Self::start_nodes_with_twins(
+            num_nodes,
+            &twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            |config| config.quorum_store = quorum_store.clone(),
+        )
+    }
+
+    /// Starts a given number of nodes and a twin for each of the nodes at the given indices.
+    /// The twins get the ids following the ones of the nodes, in the order of `twins`.
+    /// The consensus configuration of all the nodes is updated with `configure` last.
+    pub fn start_nodes_with_twins(
+        num_nodes: usize,
+        twins: &[usize],
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        configure: impl Fn(&mut ConsensusConfig),
+    ) -> Vec<Self> {
+        assert!(twins.iter().all(|idx| *idx < num_nodes));
         let ValidatorSwarm {
             nodes: mut node_configs,
         } = generator::validator_swarm_for_testing(num_nodes);
@@ -205,8 +305,8 @@ impl SMRNode {
         // We don't add twins to ValidatorSet or round_proposers above
         // because a node with twins should be treated the same at the
         // consensus level
-        for i in 0..num_twins {
-            let twin = node_configs[i].clone();
+        for idx in twins {
+            let twin = node_configs[*idx].clone();
             node_configs.push(twin);
         }
 
@@ -229,6 +329,7 @@ impl SMRNode {
             config.consensus.safety_rules.verify_vote_proposal_signature = false;
             // Disable timeout in twins test to avoid flakiness
             config.consensus.round_initial_timeout_ms = 2_000_000;
+            configure(&mut config.consensus);
 
             let author = author_from_config(&config);
 
@@ -72,7 +72,7 @@ impl MempoolProxy {
                 Err(anyhow::anyhow!("[consensus] did not receive GetBlockResponse on time").into())
             }
             Ok(resp) => match resp.map_err(anyhow::Error::from)?? {
-                ConsensusResponse::GetBlockResponse(txns) => Ok(txns),
+                ConsensusResponse::GetBlockResponse(txns) => Ok(Payload::DirectMempool(txns)),
                 _ => Err(
                     anyhow::anyhow!("[consensus] did not receive expected GetBlockResponse").into(),
                 ),
@@ -92,8 +92,13 @@ impl TxnManager for MempoolProxy {
             Err(anyhow::anyhow!("Injected error in pull_txns").into())
         });
         let mut exclude_txns = vec![];
+        // The payloads in the quorum store were never pulled from this mempool by consensus.
         for payload in exclude_payloads {
-            for transaction in payload {
+            let txns = match payload {
+                Payload::DirectMempool(txns) => txns,
+                Payload::InQuorumStore(_) => continue,
+            };
+            for transaction in txns {
                 exclude_txns.push(TransactionSummary {
                     sender: transaction.sender(),
                     sequence_number: transaction.sequence_number(),
@@ -126,9 +131,10 @@ impl TxnManager for MempoolProxy {
         compute_results: &StateComputeResult,
     ) -> Result<(), MempoolError> {
         let mut rejected_txns = vec![];
+        // The transactions of the quorum store batches are not necessarily from this mempool.
         let txns = match block.payload() {
-            Some(txns) => txns,
-            None => return Ok(()),
+            Some(Payload::DirectMempool(txns)) => txns,
+            _ => return Ok(()),
         };
         // skip the block metadata txn result
         for (txn, status) in txns
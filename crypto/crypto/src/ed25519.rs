@@ -31,10 +31,7 @@
 //! testing purposes. Production code should find an alternate means for secure key generation.
 #![allow(clippy::integer_arithmetic)]
 
-use crate::{
-    hash::{CryptoHash, CryptoHasher},
-    traits::*,
-};
+use crate::{hash::CryptoHash, traits::*};
 use anyhow::{anyhow, Result};
 use core::convert::TryFrom;
 use diem_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
@@ -235,10 +232,8 @@ impl SigningKey for Ed25519PrivateKey {
     type SignatureMaterial = Ed25519Signature;
 
     fn sign<T: CryptoHash + Serialize>(&self, message: &T) -> Ed25519Signature {
-        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
-        bcs::serialize_into(&mut bytes, &message)
-            .map_err(|_| CryptoMaterialError::SerializationError)
-            .expect("Serialization of signable material should not fail.");
+        let bytes =
+            signing_message(message).expect("Serialization of signable material should not fail.");
         Ed25519PrivateKey::sign_arbitrary_message(self, bytes.as_ref())
     }
 
@@ -419,9 +414,7 @@ impl Signature for Ed25519Signature {
     ) -> Result<()> {
         // Public keys should be validated to be safe against small subgroup attacks, etc.
         precondition!(has_tag!(public_key, ValidatedPublicKeyTag));
-        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
-        bcs::serialize_into(&mut bytes, &message)
-            .map_err(|_| CryptoMaterialError::SerializationError)?;
+        let bytes = signing_message(message)?;
         Self::verify_arbitrary_msg(self, &bytes, public_key)
     }
 
@@ -455,9 +448,7 @@ impl Signature for Ed25519Signature {
         for (_, sig) in keys_and_signatures.iter() {
             Ed25519Signature::check_malleability(&sig.to_bytes())?
         }
-        let mut message_bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
-        bcs::serialize_into(&mut message_bytes, &message)
-            .map_err(|_| CryptoMaterialError::SerializationError)?;
+        let message_bytes = signing_message(message)?;
 
         let batch_argument = keys_and_signatures
             .iter()
@@ -6,7 +6,7 @@
 //! For examples on how to use these traits, see the implementations of the [`ed25519`] or
 //! [`bls12381`] modules.
 
-use crate::hash::CryptoHash;
+use crate::hash::{CryptoHash, CryptoHasher};
 use anyhow::Result;
 use core::convert::{From, TryFrom};
 use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
@@ -277,6 +277,17 @@ pub trait Uniform {
     }
 }
 
+/// Returns the bytes that are actually signed by [`SigningKey::sign`] for `message`: the seed of
+/// its domain-separation hasher followed by its BCS serialization.
+pub fn signing_message<T: CryptoHash + Serialize>(
+    message: &T,
+) -> Result<Vec<u8>, CryptoMaterialError> {
+    let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+    bcs::serialize_into(&mut bytes, &message)
+        .map_err(|_| CryptoMaterialError::SerializationError)?;
+    Ok(bytes)
+}
+
 /// A type family with a by-convention notion of genesis private key.
 pub trait Genesis: PrivateKey {
     /// Produces the genesis private key.
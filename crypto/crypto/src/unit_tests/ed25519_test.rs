@@ -374,6 +374,16 @@ proptest! {
         prop_assert!(deserialized.verify(&hashable, &keypair.public_key).is_ok());
     }
 
+    #[test]
+    fn test_signature_verification_from_signing_message(
+        x in any::<usize>(),
+        keypair in uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>()
+    ) {
+        let hashable = CryptoHashable(x);
+        let signature = keypair.private_key.sign(&hashable);
+        let message = signing_message(&hashable).unwrap();
+        prop_assert!(signature.verify_arbitrary_msg(&message, &keypair.public_key).is_ok());
+    }
 
     // Check for canonical S.
     #[test]
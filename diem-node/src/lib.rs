@@ -2,16 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use backup_service::start_backup_service;
-use consensus::consensus_provider::start_consensus;
+use consensus::consensus_provider::{start_consensus, start_consensus_observer};
+use consensus_notifications::ConsensusNotificationListener;
+use data_streaming_service::{
+    streaming_client::new_streaming_service_client_listener_pair,
+    streaming_service::DataStreamingService,
+};
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_api::runtime::bootstrap as bootstrap_api;
 use diem_config::{
     config::{NetworkConfig, NodeConfig, PersistableConfig},
+    network_id::NetworkId,
     utils::get_genesis_txn,
 };
+use diem_data_client::diemnet::DiemNetDataClient;
 use diem_infallible::RwLock;
 use diem_json_rpc::bootstrap_from_config as bootstrap_rpc;
 use diem_logger::{prelude::*, Logger};
+use diem_mempool::network::{MempoolNetworkEvents, MempoolNetworkSender};
 use diem_metrics::metric_server;
 use diem_time_service::TimeService;
 use diem_types::{
@@ -21,6 +29,7 @@ use diem_types::{
     move_resource::MoveStorage,
     on_chain_config::{VMPublishingOption, ON_CHAIN_CONFIG_REGISTRY},
     protocol_spec::DpnProto,
+    waypoint::Waypoint,
 };
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
@@ -28,12 +37,17 @@ use event_notifications::EventSubscriptionService;
 use executor::{db_bootstrapper::maybe_bootstrap, Executor};
 use executor_types::ChunkExecutor;
 use futures::{channel::mpsc::channel, executor::block_on};
+use mempool_notifications::MempoolNotifier;
 use network::application::storage::PeerMetadataStorage;
 use network_builder::builder::NetworkBuilder;
-use state_sync_v1::bootstrapper::StateSyncBootstrapper;
+use state_sync_v1::{
+    bootstrapper::StateSyncBootstrapper,
+    network::{StateSyncEvents, StateSyncSender},
+};
+use state_sync_v2::driver_factory::DriverFactory;
 use std::{
     boxed::Box,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     io::Write,
     net::ToSocketAddrs,
@@ -47,6 +61,8 @@ use std::{
 };
 use storage_interface::default_protocol::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
+use storage_service_client::{StorageServiceClient, StorageServiceNetworkSender};
+use storage_service_server::{StorageReader, StorageServiceNetworkEvents, StorageServiceServer};
 use tokio::runtime::{Builder, Runtime};
 use tokio_stream::wrappers::IntervalStream;
 
@@ -58,13 +74,42 @@ pub struct DiemHandle {
     _api: Option<Runtime>,
     _rpc: Runtime,
     _mempool: Runtime,
-    _state_sync_bootstrapper: StateSyncBootstrapper,
+    _state_sync: StateSync,
     _network_runtimes: Vec<Runtime>,
     _consensus_runtime: Option<Runtime>,
     _debug: NodeDebugService,
     _backup: Runtime,
 }
 
+/// The state sync implementation run by the node: state sync v1, or the state
+/// sync v2 driver (if `state_sync.state_sync_driver.enable_state_sync_v2` is set).
+enum StateSync {
+    V1(StateSyncBootstrapper),
+    V2 {
+        driver_factory: DriverFactory,
+        _service_runtime: Runtime,
+    },
+}
+
+impl StateSync {
+    /// Blocks until state sync has caught up to the waypoint
+    fn block_until_initialized(&mut self) {
+        debug!("Wait until state sync is initialized");
+        match self {
+            StateSync::V1(state_sync_bootstrapper) => {
+                let state_sync_client = state_sync_bootstrapper.create_client();
+                block_on(state_sync_client.wait_until_initialized())
+                    .expect("State sync initialization failure");
+            }
+            StateSync::V2 { driver_factory, .. } => {
+                block_on(driver_factory.wait_until_bootstrapped())
+                    .expect("State sync v2 bootstrapping failure");
+            }
+        }
+        debug!("State sync initialization complete.");
+    }
+}
+
 pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
     crash_handler::setup_panic_handler();
 
@@ -205,6 +250,68 @@ fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
     Box::new(Executor::<DpnProto, DiemVM>::new(db))
 }
 
+/// Starts the state sync v2 driver, along with the storage service servers (one per network), the
+/// Diem data client and the data streaming service it depends on.
+#[allow(clippy::too_many_arguments)]
+fn setup_state_sync_v2(
+    node_config: &NodeConfig,
+    waypoint: Waypoint,
+    db_rw: DbReaderWriter,
+    chunk_executor: Box<dyn ChunkExecutor>,
+    mempool_notifier: MempoolNotifier,
+    consensus_listener: ConsensusNotificationListener,
+    event_subscription_service: EventSubscriptionService,
+    storage_service_network_handles: Vec<(
+        NetworkId,
+        StorageServiceNetworkSender,
+        StorageServiceNetworkEvents,
+    )>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+) -> StateSync {
+    let service_runtime = Builder::new_multi_thread()
+        .thread_name("state-sync-v2")
+        .enable_all()
+        .build()
+        .expect("Failed to create the state sync v2 runtime!");
+
+    // Serve the storage service requests of the peers on every network
+    let storage = Arc::new(RwLock::new(db_rw.clone()));
+    let mut network_senders = HashMap::new();
+    for (network_id, network_sender, network_events) in storage_service_network_handles {
+        let storage_server =
+            StorageServiceServer::new(StorageReader::new(storage.clone()), TimeService::real());
+        service_runtime.spawn(storage_server.start(network_events));
+        network_senders.insert(network_id, network_sender);
+    }
+
+    // Create the data client and the data streaming service used by the driver
+    let (diem_data_client, data_summary_poller) = DiemNetDataClient::new(
+        StorageServiceClient::new(network_senders, peer_metadata_storage),
+    );
+    service_runtime.spawn(data_summary_poller.start());
+    let (streaming_service_client, streaming_service_listener) =
+        new_streaming_service_client_listener_pair();
+    let data_streaming_service =
+        DataStreamingService::new(diem_data_client.clone(), streaming_service_listener);
+    service_runtime.spawn(data_streaming_service.start_service());
+
+    let driver_factory = DriverFactory::create_and_spawn_driver(
+        node_config,
+        waypoint,
+        db_rw,
+        chunk_executor,
+        mempool_notifier,
+        consensus_listener,
+        event_subscription_service,
+        diem_data_client,
+        streaming_service_client,
+    );
+    StateSync::V2 {
+        driver_factory,
+        _service_runtime: service_runtime,
+    }
+}
+
 fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> NodeDebugService {
     let addr = format!(
         "{}:{}",
@@ -280,8 +387,11 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             &node_config.storage.dir(),
             false, /* readonly */
             node_config.storage.prune_window,
+            node_config.storage.ledger_prune_window,
             node_config.storage.rocksdb_config,
             node_config.storage.account_count_migration,
+            node_config.storage.resource_granular_state,
+            node_config.storage.state_value_cache_size,
         )
         .expect("DB should open."),
     );
@@ -291,6 +401,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         Arc::clone(&diem_db),
     );
 
+    // Set the concurrency level of block execution before any block is executed.
+    DiemVM::set_concurrency_level_once(node_config.execution.execution_mode.concurrency_level());
+
     let genesis_waypoint = node_config.base.waypoint.genesis_waypoint();
     // if there's genesis txn and waypoint, commit it if the result matches.
     if let Some(genesis) = get_genesis_txn(node_config) {
@@ -313,9 +426,21 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
     let chain_id = fetch_chain_id(&db_rw);
     let mut network_runtimes = vec![];
+    let state_sync_v2_enabled = node_config
+        .state_sync
+        .state_sync_driver
+        .enable_state_sync_v2;
     let mut state_sync_network_handles = vec![];
+    let mut storage_service_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
+    let mut observer_network_handles = vec![];
+    // Validators publish consensus to the observers, and full nodes observe (and relay) it.
+    let consensus_observer_enabled = if node_config.base.role.is_validator() {
+        node_config.consensus_observer.publisher_enabled
+    } else {
+        node_config.consensus_observer.observer_enabled
+    };
 
     // Create an event subscription service so that components can be notified of events and reconfigs
     let mut event_subscription_service = EventSubscriptionService::new(
@@ -382,14 +507,36 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         let network_id = network_config.network_id;
 
         // Create the endpoints to connect the Network to State Sync.
-        let (state_sync_sender, state_sync_events) =
-            network_builder.add_p2p_service(&state_sync_v1::network::network_endpoint_config());
-        state_sync_network_handles.push((network_id, state_sync_sender, state_sync_events));
+        if state_sync_v2_enabled {
+            let storage_service_sender = network_builder.add_client::<StorageServiceNetworkSender>(
+                &storage_service_client::network_endpoint_config(),
+            );
+            let storage_service_events = network_builder
+                .add_service::<StorageServiceNetworkEvents>(
+                    &storage_service_server::network_endpoint_config(),
+                );
+            storage_service_network_handles.push((
+                network_id,
+                storage_service_sender,
+                storage_service_events,
+            ));
+        } else {
+            let (mut state_sync_sender, state_sync_events) = network_builder
+                .add_p2p_service::<StateSyncSender, StateSyncEvents>(
+                    &state_sync_v1::network::network_endpoint_config(),
+                );
+            state_sync_sender.initialize(network_id, peer_metadata_storage.clone());
+            state_sync_network_handles.push((network_id, state_sync_sender, state_sync_events));
+        }
 
         // Create the endpoints to connect the Network to mempool.
-        let (mempool_sender, mempool_events) = network_builder.add_p2p_service(
-            &diem_mempool::network::network_endpoint_config(MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE),
-        );
+        let (mut mempool_sender, mempool_events) = network_builder
+            .add_p2p_service::<MempoolNetworkSender, MempoolNetworkEvents>(
+                &diem_mempool::network::network_endpoint_config(
+                    MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE,
+                ),
+            );
+        mempool_sender.initialize(network_id, peer_metadata_storage.clone());
         mempool_network_handles.push((network_id, mempool_sender, mempool_events));
 
         // Perform steps relevant specifically to Validator networks.
@@ -404,6 +551,12 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
                 network_builder
                     .add_p2p_service(&consensus::network_interface::network_endpoint_config()),
             );
+        } else if consensus_observer_enabled {
+            // Create the endpoints to stream consensus to the observers (or, on an observer, from
+            // its upstream peers).
+            let (observer_sender, observer_events) = network_builder
+                .add_p2p_service(&consensus::observer::network::network_endpoint_config());
+            observer_network_handles.push((network_id, observer_sender, observer_events));
         }
 
         let network_context = network_builder.network_context();
@@ -424,17 +577,31 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             node_config.state_sync.client_commit_timeout_ms,
         );
 
-    // Create state sync bootstrapper
-    let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap(
-        state_sync_network_handles,
-        mempool_notifier,
-        consensus_listener,
-        Arc::clone(&db_rw.reader),
-        chunk_executor,
-        node_config,
-        genesis_waypoint,
-        event_subscription_service,
-    );
+    // Create state sync (either the v2 driver or the v1 bootstrapper)
+    let mut state_sync = if state_sync_v2_enabled {
+        setup_state_sync_v2(
+            node_config,
+            genesis_waypoint,
+            db_rw.clone(),
+            chunk_executor,
+            mempool_notifier,
+            consensus_listener,
+            event_subscription_service,
+            storage_service_network_handles,
+            peer_metadata_storage.clone(),
+        )
+    } else {
+        StateSync::V1(StateSyncBootstrapper::bootstrap(
+            state_sync_network_handles,
+            mempool_notifier,
+            consensus_listener,
+            Arc::clone(&db_rw.reader),
+            chunk_executor,
+            node_config,
+            genesis_waypoint,
+            event_subscription_service,
+        ))
+    };
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
     let rpc_runtime = bootstrap_rpc(
@@ -472,16 +639,11 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // network provider -> consensus -> state synchronizer -> network provider.  This has resulted
     // in a deadlock as observed in GitHub issue #749.
     if let Some((consensus_network_sender, consensus_network_events)) = consensus_network_handles {
-        let state_sync_client = state_sync_bootstrapper.create_client();
-
         // Make sure that state synchronizer is caught up at least to its waypoint
         // (in case it's present). There is no sense to start consensus prior to that.
         // TODO: Note that we need the networking layer to be able to discover & connect to the
         // peers with potentially outdated network identity public keys.
-        debug!("Wait until state sync is initialized");
-        block_on(state_sync_client.wait_until_initialized())
-            .expect("State sync initialization failure");
-        debug!("State sync initialization complete.");
+        state_sync.block_until_initialized();
 
         // Initialize and start consensus.
         instant = Instant::now();
@@ -495,8 +657,26 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_reconfig_subscription
                 .expect("Consensus requires a reconfiguration subscription!"),
             peer_metadata_storage,
+            observer_network_handles,
         ));
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else if node_config.consensus_observer.observer_enabled {
+        // Same as consensus, the observer starts once state sync is caught up to the waypoint.
+        state_sync.block_until_initialized();
+
+        instant = Instant::now();
+        let observer_pause = consensus_notifier.observer_pause();
+        consensus_runtime = Some(start_consensus_observer(
+            node_config,
+            observer_network_handles,
+            Box::new(consensus_notifier),
+            observer_pause,
+            db_rw.clone(),
+        ));
+        debug!(
+            "Consensus observer started in {} ms",
+            instant.elapsed().as_millis()
+        );
     }
 
     // Spawn a task which will periodically dump some interesting state
@@ -509,7 +689,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         _network_runtimes: network_runtimes,
         _rpc: rpc_runtime,
         _mempool: mempool,
-        _state_sync_bootstrapper: state_sync_bootstrapper,
+        _state_sync: state_sync,
         _consensus_runtime: consensus_runtime,
         _debug: debug_if,
         _backup: backup_service,
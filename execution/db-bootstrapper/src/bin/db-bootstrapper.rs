@@ -36,6 +36,9 @@ struct Opt {
 
     #[structopt(long)]
     account_count_migration: bool,
+
+    #[structopt(long)]
+    resource_granular_state: bool,
 }
 
 fn main() -> Result<()> {
@@ -55,8 +58,11 @@ fn main() -> Result<()> {
             &opt.db_dir,
             false,
             None, /* pruner */
+            None, /* ledger_pruner */
             RocksdbConfig::default(),
             opt.account_count_migration,
+            opt.resource_granular_state,
+            0, /* state_value_cache_size */
         )
     } else {
         // When not committing, we open the DB as secondary so the tool is usable along side a
@@ -3,7 +3,7 @@
 
 use consensus_types::block::Block;
 use diem_crypto::HashValue;
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use executor_types::{Error, StateComputeResult};
 
 /// Interface for ExecutionCorrectness.
@@ -13,10 +13,11 @@ pub trait ExecutionCorrectness: Send {
 
     fn reset(&self) -> Result<(), Error>;
 
-    /// Executes a block.
+    /// Executes a block, given the user transactions of its payload.
     fn execute_block(
         &self,
         block: Block,
+        payload_txns: Vec<SignedTransaction>,
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error>;
 
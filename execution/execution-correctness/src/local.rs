@@ -4,7 +4,7 @@
 use crate::execution_correctness::ExecutionCorrectness;
 use consensus_types::{block::Block, vote_proposal::VoteProposal};
 use diem_crypto::{ed25519::Ed25519PrivateKey, traits::SigningKey, HashValue};
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use executor_types::{BlockExecutor, Error, StateComputeResult};
 use std::{boxed::Box, sync::Arc};
 
@@ -47,11 +47,12 @@ impl ExecutionCorrectness for LocalClient {
     fn execute_block(
         &self,
         block: Block,
+        payload_txns: Vec<SignedTransaction>,
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
         let local = &self.internal;
         let mut result = local.block_executor.execute_block(
-            (block.id(), block.transactions_to_execute()),
+            (block.id(), block.transactions_to_execute(payload_txns)),
             parent_block_id,
         )?;
         if let Some(prikey) = local.prikey.as_ref() {
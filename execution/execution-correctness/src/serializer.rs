@@ -4,7 +4,7 @@
 use crate::execution_correctness::ExecutionCorrectness;
 use consensus_types::{block::Block, vote_proposal::VoteProposal};
 use diem_crypto::{ed25519::Ed25519PrivateKey, traits::SigningKey, HashValue};
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use executor_types::{BlockExecutor, Error, StateComputeResult};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -13,7 +13,7 @@ use std::sync::Arc;
 pub enum ExecutionCorrectnessInput {
     CommittedBlockId,
     Reset,
-    ExecuteBlock(Box<(Block, HashValue)>),
+    ExecuteBlock(Box<(Block, Vec<SignedTransaction>, HashValue)>),
     CommitBlocks(Box<(Vec<HashValue>, LedgerInfoWithSignatures)>),
 }
 
@@ -35,29 +35,29 @@ impl SerializerService {
                 bcs::to_bytes(&self.internal.committed_block_id())
             }
             ExecutionCorrectnessInput::Reset => bcs::to_bytes(&self.internal.reset()),
-            ExecutionCorrectnessInput::ExecuteBlock(block_with_parent_id) => bcs::to_bytes(
-                &self
-                    .internal
-                    .execute_block(
-                        (
-                            block_with_parent_id.0.id(),
-                            block_with_parent_id.0.transactions_to_execute(),
-                        ),
-                        block_with_parent_id.1,
-                    )
-                    .map(|mut result| {
-                        if let Some(prikey) = self.prikey.as_ref() {
-                            let vote_proposal = VoteProposal::new(
-                                result.extension_proof(),
-                                block_with_parent_id.0.clone(),
-                                result.epoch_state().clone(),
-                            );
-                            let signature = prikey.sign(&vote_proposal);
-                            result.set_signature(signature);
-                        }
-                        result
-                    }),
-            ),
+            ExecutionCorrectnessInput::ExecuteBlock(block_with_parent_id) => {
+                let (block, payload_txns, parent_block_id) = *block_with_parent_id;
+                bcs::to_bytes(
+                    &self
+                        .internal
+                        .execute_block(
+                            (block.id(), block.transactions_to_execute(payload_txns)),
+                            parent_block_id,
+                        )
+                        .map(|mut result| {
+                            if let Some(prikey) = self.prikey.as_ref() {
+                                let vote_proposal = VoteProposal::new(
+                                    result.extension_proof(),
+                                    block,
+                                    result.epoch_state().clone(),
+                                );
+                                let signature = prikey.sign(&vote_proposal);
+                                result.set_signature(signature);
+                            }
+                            result
+                        }),
+                )
+            }
             ExecutionCorrectnessInput::CommitBlocks(blocks_with_li) => bcs::to_bytes(
                 &self
                     .internal
@@ -101,10 +101,12 @@ impl ExecutionCorrectness for SerializerClient {
     fn execute_block(
         &self,
         block: Block,
+        payload_txns: Vec<SignedTransaction>,
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
         let response = self.request(ExecutionCorrectnessInput::ExecuteBlock(Box::new((
             block,
+            payload_txns,
             parent_block_id,
         ))))?;
         bcs::from_bytes(&response)?
@@ -0,0 +1,76 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion};
+use diem_config::config::{RocksdbConfig, StorageConfig};
+use diem_temppath::TempPath;
+use diem_types::account_config::{
+    diem_root_address, testnet_dd_account_address, treasury_compliance_account_address,
+};
+use diemdb::DiemDB;
+use storage_interface::DbReader;
+
+pub const NUM_ACCOUNTS: usize = 1000;
+pub const BLOCK_SIZE: usize = 500;
+pub const INITIAL_BALANCE: u64 = 1000000;
+
+//
+// Hot account read benchmarks
+//
+
+/// Reads the accounts read by (almost) every block, at the latest version, as the executor does
+/// when executing blocks on top of the latest committed state.
+fn hot_account_read_benchmark<M: Measurement + 'static>(c: &mut Criterion<M>) {
+    let db_dir = TempPath::new();
+    db_dir.create_as_dir().unwrap();
+    executor_benchmark::db_generator::run(
+        NUM_ACCOUNTS,
+        INITIAL_BALANCE,
+        BLOCK_SIZE,
+        db_dir.path(),
+        None, /* prune_window */
+    );
+    let hot_accounts = [
+        diem_root_address(),
+        treasury_compliance_account_address(),
+        testnet_dd_account_address(),
+    ];
+
+    for (name, state_value_cache_size) in &[
+        ("bench_hot_account_reads_without_cache", 0),
+        (
+            "bench_hot_account_reads_with_cache",
+            StorageConfig::default().state_value_cache_size,
+        ),
+    ] {
+        let db = DiemDB::open(
+            db_dir.path(),
+            true, /* readonly */
+            None, /* pruner */
+            None, /* ledger_pruner */
+            RocksdbConfig::default(),
+            true,  /* account_count_migration */
+            false, /* resource_granular_state */
+            *state_value_cache_size,
+        )
+        .expect("DB should open.");
+        let version = db.get_latest_version().unwrap();
+
+        c.bench_function(name, |bencher| {
+            bencher.iter(|| {
+                for address in &hot_accounts {
+                    db.get_account_state_with_proof_by_version(*address, version)
+                        .unwrap();
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(
+    name = state_value_cache_benches;
+    config = Criterion::default().sample_size(10);
+    targets = hot_account_read_benchmark
+);
+
+criterion_main!(state_value_cache_benches);
@@ -47,8 +47,11 @@ pub fn run(
             &db_dir,
             false,        /* readonly */
             prune_window, /* pruner */
+            None,         /* ledger_pruner */
             RocksdbConfig::default(),
-            true, /* account_count_migration */
+            true,  /* account_count_migration */
+            false, /* resource_granular_state */
+            0,     /* state_value_cache_size */
         )
         .expect("DB should open."),
     );
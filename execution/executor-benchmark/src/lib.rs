@@ -33,8 +33,11 @@ pub fn create_storage_service_and_executor(
             &config.storage.dir(),
             false, /* readonly */
             None,  /* pruner */
+            None,  /* ledger_pruner */
             RocksdbConfig::default(),
-            true, /* account_count_migration */
+            true,  /* account_count_migration */
+            false, /* resource_granular_state */
+            config.storage.state_value_cache_size,
         )
         .expect("DB should open."),
     );
@@ -48,12 +51,14 @@ pub fn create_storage_service_and_executor(
     (db, executor)
 }
 
-/// Runs the benchmark with given parameters.
+/// Runs the benchmark with given parameters. `state_value_cache_size` overrides the size of the
+/// state value cache of the DB if set.
 pub fn run_benchmark(
     block_size: usize,
     num_transfer_blocks: usize,
     source_dir: impl AsRef<Path>,
     checkpoint_dir: impl AsRef<Path>,
+    state_value_cache_size: Option<usize>,
 ) {
     // Create rocksdb checkpoint.
     if checkpoint_dir.as_ref().exists() {
@@ -66,8 +71,11 @@ pub fn run_benchmark(
             &source_dir,
             false, /* readonly */
             None,  /* pruner */
+            None,  /* ledger_pruner */
             RocksdbConfig::default(),
-            true, /* account_count_migration */
+            true,  /* account_count_migration */
+            false, /* resource_granular_state */
+            0,     /* state_value_cache_size */
         )
         .expect("db open failure.")
         .create_checkpoint(checkpoint_dir.as_ref().join("diemdb"))
@@ -76,6 +84,9 @@ pub fn run_benchmark(
 
     let (mut config, genesis_key) = diem_genesis_tool::test_config();
     config.storage.dir = checkpoint_dir.as_ref().to_path_buf();
+    if let Some(state_value_cache_size) = state_value_cache_size {
+        config.storage.state_value_cache_size = state_value_cache_size;
+    }
 
     let (db, executor) = create_storage_service_and_executor(&config);
     let parent_block_id = executor.committed_block_id();
@@ -156,6 +167,7 @@ mod tests {
             5, /* num_transfer_blocks */
             storage_dir.as_ref(),
             checkpoint_dir,
+            None, /* state_value_cache_size */
         );
     }
 }
@@ -41,6 +41,12 @@ enum Command {
 
         #[structopt(long, parse(from_os_str))]
         checkpoint_dir: PathBuf,
+
+        #[structopt(
+            long,
+            about = "size of the state value cache of the DB in bytes, 0 to disable the cache"
+        )]
+        state_value_cache_size: Option<usize>,
     },
 }
 
@@ -71,9 +77,16 @@ fn main() {
             blocks,
             data_dir,
             checkpoint_dir,
+            state_value_cache_size,
         } => {
             diem_logger::Logger::new().init();
-            executor_benchmark::run_benchmark(opt.block_size, blocks, data_dir, checkpoint_dir);
+            executor_benchmark::run_benchmark(
+                opt.block_size,
+                blocks,
+                data_dir,
+                checkpoint_dir,
+                state_value_cache_size,
+            );
         }
     }
 }
@@ -39,9 +39,7 @@ pub fn test_execution_with_storage_impl() -> Arc<DiemDB> {
     let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
     let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
 
-    let path = diem_temppath::TempPath::new();
-    path.create_as_dir().unwrap();
-    let (diem_db, db, executor, waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let (diem_db, db, executor, waypoint) = create_db_and_executor(&genesis_txn);
 
     let parent_block_id = executor.committed_block_id();
     let signer = diem_types::validator_signer::ValidatorSigner::new(
@@ -511,8 +509,7 @@ pub fn test_execution_with_storage_impl() -> Arc<DiemDB> {
     diem_db
 }
 
-pub fn create_db_and_executor<P: AsRef<std::path::Path>>(
-    path: P,
+pub fn create_db_and_executor(
     genesis: &Transaction,
 ) -> (
     Arc<DiemDB>,
@@ -520,7 +517,7 @@ pub fn create_db_and_executor<P: AsRef<std::path::Path>>(
     Executor<DpnProto, DiemVM>,
     Waypoint,
 ) {
-    let (db, dbrw) = DbReaderWriter::wrap(DiemDB::new_for_test(&path));
+    let (db, dbrw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     let waypoint = bootstrap_genesis::<DiemVM>(&dbrw, genesis).unwrap();
     let executor = Executor::new(dbrw.clone());
 
@@ -21,8 +21,8 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     proof::{accumulator::InMemoryAccumulator, AccumulatorExtensionProof},
     transaction::{
-        default_protocol::TransactionListWithProof, Transaction, TransactionInfo,
-        TransactionStatus, Version,
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        Transaction, TransactionInfo, TransactionStatus, Version,
     },
 };
 use scratchpad::ProofRead;
@@ -46,6 +46,20 @@ pub trait ChunkExecutor: Send + Sync {
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<Vec<ContractEvent>>;
+
+    /// Verifies the transaction outputs based on the provided proofs and ledger info. If the
+    /// outputs are valid, applies them (without re-executing the transactions) and commits
+    /// immediately if the results match the proofs.
+    /// Returns a vector of reconfiguration events in the chunk
+    fn apply_and_commit_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+        // Target LI that has been verified independently: the proofs are relative to this version.
+        verified_target_li: LedgerInfoWithSignatures,
+        // An optional end of epoch LedgerInfo. We do not allow chunks that end epoch without
+        // carrying any epoch change LI.
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>>;
 }
 
 pub trait BlockExecutor: Send + Sync {
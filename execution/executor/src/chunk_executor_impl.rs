@@ -6,14 +6,26 @@
 use crate::logging::{LogEntry, LogSchema};
 use diem_logger::prelude::*;
 use diem_types::{
-    contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures, protocol_spec::DpnProto,
-    transaction::default_protocol::TransactionListWithProof,
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::{
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        TransactionToCommit, Version,
+    },
 };
 use diem_vm::VMExecutor;
 use executor_types::ChunkExecutor;
 use fail::fail_point;
 
-use crate::{metrics::DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS, Executor};
+use crate::{
+    metrics::{
+        DIEM_EXECUTOR_APPLY_AND_COMMIT_CHUNK_SECONDS,
+        DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS,
+    },
+    types::ProcessedVMOutput,
+    Executor,
+};
 
 impl<V: VMExecutor> ChunkExecutor for Executor<DpnProto, V> {
     fn execute_and_commit_chunk(
@@ -48,7 +60,75 @@ impl<V: VMExecutor> ChunkExecutor for Executor<DpnProto, V> {
         let (output, txns_to_commit, events) =
             self.execute_chunk(first_version, transactions, transaction_infos)?;
 
-        // 4. Commit to DB.
+        // 4. Commit to DB and maintain the cache.
+        self.commit_chunk(
+            first_version,
+            output,
+            txns_to_commit,
+            events,
+            verified_target_li,
+            epoch_change_li,
+        )
+    }
+
+    fn apply_and_commit_chunk(
+        &self,
+        txn_output_list_with_proof: TransactionOutputListWithProof,
+        // Target LI that has been verified independently: the proofs are relative to this version.
+        verified_target_li: LedgerInfoWithSignatures,
+        // An optional end of epoch LedgerInfo. We do not allow chunks that end epoch without
+        // carrying any epoch change LI.
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> anyhow::Result<Vec<ContractEvent>> {
+        let _timer = DIEM_EXECUTOR_APPLY_AND_COMMIT_CHUNK_SECONDS.start_timer();
+        // 1. Update the cache in executor to be consistent with latest synced state.
+        self.reset_cache()?;
+        let read_lock = self.cache.read();
+
+        info!(
+            LogSchema::new(LogEntry::ChunkExecutor)
+                .local_synced_version(read_lock.synced_trees().txn_accumulator().num_leaves() - 1)
+                .first_version_in_request(
+                    txn_output_list_with_proof.first_transaction_output_version
+                )
+                .num_txns_in_request(txn_output_list_with_proof.transactions_and_outputs.len()),
+            "sync_request_received",
+        );
+
+        // 2. Verify input transaction output list.
+        let (transactions_and_outputs, transaction_infos) =
+            self.verify_output_chunk(txn_output_list_with_proof, &verified_target_li)?;
+
+        // 3. Apply transaction outputs.
+        let first_version = read_lock.synced_trees().txn_accumulator().num_leaves();
+        drop(read_lock);
+        let (output, txns_to_commit, events) =
+            self.apply_chunk(first_version, transactions_and_outputs, transaction_infos)?;
+
+        // 4. Commit to DB and maintain the cache.
+        self.commit_chunk(
+            first_version,
+            output,
+            txns_to_commit,
+            events,
+            verified_target_li,
+            epoch_change_li,
+        )
+    }
+}
+
+impl<V: VMExecutor> Executor<DpnProto, V> {
+    /// Commits the verified chunk to the DB and updates the executor cache accordingly.
+    fn commit_chunk(
+        &self,
+        first_version: Version,
+        output: ProcessedVMOutput,
+        txns_to_commit: Vec<TransactionToCommit>,
+        events: Vec<ContractEvent>,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> anyhow::Result<Vec<ContractEvent>> {
+        // 1. Commit to DB.
         let ledger_info_to_commit =
             Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
         if ledger_info_to_commit.is_none() && txns_to_commit.is_empty() {
@@ -63,7 +143,7 @@ impl<V: VMExecutor> ChunkExecutor for Executor<DpnProto, V> {
             ledger_info_to_commit.as_ref(),
         )?;
 
-        // 5. Cache maintenance.
+        // 2. Cache maintenance.
         let mut write_lock = self.cache.write();
         let output_trees = output.executed_trees().clone();
         if let Some(ledger_info_with_sigs) = &ledger_info_to_commit {
@@ -42,26 +42,19 @@ fn execute_and_commit_block(
 }
 
 struct TestExecutor {
-    _path: diem_temppath::TempPath,
     db: DbReaderWriter,
     executor: Executor<DpnProto, MockVM>,
 }
 
 impl TestExecutor {
     fn new() -> TestExecutor {
-        let path = diem_temppath::TempPath::new();
-        path.create_as_dir().unwrap();
-        let db = DbReaderWriter::new(DiemDB::new_for_test(path.path()));
+        let db = DbReaderWriter::new(DiemDB::new_for_test());
         let genesis = vm_genesis::test_genesis_transaction();
         let waypoint = generate_waypoint::<MockVM>(&db, &genesis).unwrap();
         maybe_bootstrap::<MockVM>(&db, &genesis, waypoint).unwrap();
         let executor = Executor::new(db.clone());
 
-        TestExecutor {
-            _path: path,
-            db,
-            executor,
-        }
+        TestExecutor { db, executor }
     }
 }
 
@@ -260,11 +253,7 @@ fn create_transaction_chunks(
 
     // To obtain the batches of transactions, we first execute and save all these transactions in a
     // separate DB. Then we call get_transactions to retrieve them.
-    let TestExecutor {
-        _path,
-        db: _,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db: _, executor } = TestExecutor::new();
 
     let mut txns = vec![];
     for i in 1..chunk_ranges.last().unwrap().end {
@@ -320,11 +309,7 @@ fn test_executor_execute_and_commit_chunk() {
     };
 
     // Now we execute these two chunks of transactions.
-    let TestExecutor {
-        _path,
-        db,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db, executor } = TestExecutor::new();
 
     // Execute the first chunk. After that we should still get the genesis ledger info from DB.
     executor
@@ -384,11 +369,7 @@ fn test_executor_execute_and_commit_chunk_restart() {
         ])
     };
 
-    let TestExecutor {
-        _path,
-        db,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db, executor } = TestExecutor::new();
 
     // First we simulate syncing the first chunk of transactions.
     {
@@ -426,11 +407,7 @@ fn test_executor_execute_and_commit_chunk_local_result_mismatch() {
         ])
     };
 
-    let TestExecutor {
-        _path,
-        db: _,
-        executor,
-    } = TestExecutor::new();
+    let TestExecutor { db: _, executor } = TestExecutor::new();
     // commit 5 txns first.
     {
         let parent_block_id = executor.committed_block_id();
@@ -632,7 +609,7 @@ proptest! {
         let block_a = TestBlock::new(0..a_size, amount, gen_block_id(1));
         let block_b = TestBlock::new(0..b_size, amount, gen_block_id(2));
 
-        let TestExecutor { _path, db, executor } = TestExecutor::new();
+        let TestExecutor { db, executor } = TestExecutor::new();
         let mut parent_block_id;
         let mut root_hash;
 
@@ -681,7 +658,7 @@ proptest! {
                 overlap_start..overlap_end
             ]);
 
-        let TestExecutor { _path, db: _, executor } = TestExecutor::new();
+        let TestExecutor { db: _, executor } = TestExecutor::new();
         let parent_block_id = executor.committed_block_id();
 
         let overlap_txn_list_with_proof = chunks.pop().unwrap();
@@ -19,7 +19,7 @@ use diem_crypto::{
 };
 use diem_infallible::{RwLock, RwLockReadGuard};
 use diem_logger::prelude::*;
-use diem_state_view::StateViewId;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
@@ -32,7 +32,8 @@ use diem_types::{
     protocol_spec::{DpnProto, ProtocolSpec},
     transaction::{
         Transaction, TransactionInfoTrait, TransactionListWithProof, TransactionOutput,
-        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
+        TransactionOutputListWithProof, TransactionPayload, TransactionStatus, TransactionToCommit,
+        Version,
     },
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
@@ -193,6 +194,70 @@ where
                 );
             }
         };
+
+        // 2. and 3. Skip the transactions that are already persisted.
+        self.skip_committed_chunk_items(
+            first_txn_version,
+            txn_list_with_proof.transactions,
+            txn_list_with_proof.proof.transaction_infos,
+            txn_list_with_proof
+                .proof
+                .ledger_info_to_transaction_infos_proof
+                .left_siblings(),
+        )
+    }
+
+    /// Verify input chunk of transaction outputs and return the transactions and outputs to be
+    /// applied, skipping those already persisted. This follows the same steps as `verify_chunk`.
+    fn verify_output_chunk(
+        &self,
+        output_list_with_proof: TransactionOutputListWithProof<PS::TransactionInfo>,
+        verified_target_li: &LedgerInfoWithSignatures,
+    ) -> Result<(
+        Vec<(Transaction, TransactionOutput)>,
+        Vec<PS::TransactionInfo>,
+    )> {
+        // 1. Verify that input outputs belongs to the ledger represented by the ledger info.
+        output_list_with_proof.verify(
+            verified_target_li.ledger_info(),
+            output_list_with_proof.first_transaction_output_version,
+        )?;
+
+        // Return empty if there's no work to do.
+        if output_list_with_proof.transactions_and_outputs.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let first_txn_version = match output_list_with_proof.first_transaction_output_version {
+            Some(tx) => tx as Version,
+            None => {
+                bail!(
+                    "first_transaction_output_version doesn't exist in {:?}",
+                    output_list_with_proof
+                );
+            }
+        };
+
+        // 2. and 3. Skip the transaction outputs that are already persisted.
+        self.skip_committed_chunk_items(
+            first_txn_version,
+            output_list_with_proof.transactions_and_outputs,
+            output_list_with_proof.proof.transaction_infos,
+            output_list_with_proof
+                .proof
+                .ledger_info_to_transaction_infos_proof
+                .left_siblings(),
+        )
+    }
+
+    /// Verify that the chunk items (and their transaction infos) that are already persisted
+    /// match what's in the DB (no fork) and return the remaining items to be applied.
+    fn skip_committed_chunk_items<T>(
+        &self,
+        first_txn_version: Version,
+        mut items: Vec<T>,
+        mut txn_infos: Vec<PS::TransactionInfo>,
+        left_siblings: &[HashValue],
+    ) -> Result<(Vec<T>, Vec<PS::TransactionInfo>)> {
         let read_lock = self.cache.read();
 
         let num_committed_txns = read_lock.synced_trees().txn_accumulator().num_leaves();
@@ -203,12 +268,12 @@ where
             first_txn_version
         );
         let versions_between_first_and_committed = num_committed_txns - first_txn_version;
-        if txn_list_with_proof.transactions.len() <= versions_between_first_and_committed as usize {
+        if items.len() <= versions_between_first_and_committed as usize {
             // All already in DB, nothing to do.
             return Ok((Vec::new(), Vec::new()));
         }
 
-        // 2. Verify that skipped transactions match what's already persisted (no fork):
+        // Verify that skipped transactions match what's already persisted (no fork):
         let num_txns_to_skip = num_committed_txns - first_txn_version;
 
         debug!(
@@ -217,19 +282,12 @@ where
         );
 
         // If the proof is verified, then the length of txn_infos and txns must be the same.
-        let skipped_transaction_infos =
-            &txn_list_with_proof.proof.transaction_infos[..num_txns_to_skip as usize];
+        let skipped_transaction_infos = &txn_infos[..num_txns_to_skip as usize];
 
         // Left side of the proof happens to be the frozen subtree roots of the accumulator
         // right before the list of txns are applied.
-        let frozen_subtree_roots_from_proof = txn_list_with_proof
-            .proof
-            .ledger_info_to_transaction_infos_proof
-            .left_siblings()
-            .iter()
-            .rev()
-            .cloned()
-            .collect::<Vec<_>>();
+        let frozen_subtree_roots_from_proof =
+            left_siblings.iter().rev().cloned().collect::<Vec<_>>();
         let accu_from_proof = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
             frozen_subtree_roots_from_proof,
             first_txn_version,
@@ -246,13 +304,11 @@ where
             "Fork happens because the current synced_trees doesn't match the txn list provided."
         );
 
-        // 3. Return verified transactions to be applied.
-        let mut txns: Vec<_> = txn_list_with_proof.transactions;
-        txns.drain(0..num_txns_to_skip as usize);
-        let mut txn_infos = txn_list_with_proof.proof.transaction_infos;
+        // Return verified items to be applied.
+        items.drain(0..num_txns_to_skip as usize);
         txn_infos.drain(0..num_txns_to_skip as usize);
 
-        Ok((txns, txn_infos))
+        Ok((items, txn_infos))
     }
 
     /// Post-processing of what the VM outputs. Returns the entire block's output.
@@ -498,6 +554,35 @@ where
         });
         let vm_outputs = V::execute_block(transactions.clone(), &state_view)?;
 
+        Self::verify_chunk_outputs(
+            first_version,
+            transactions,
+            transaction_infos,
+            vm_outputs,
+            state_view,
+            read_lock.synced_trees(),
+        )
+    }
+
+    /// Processes the outputs of a chunk (either produced by the VM or synced from other
+    /// nodes) and verifies them against the expected transaction infos. Returns the output
+    /// of the chunk, the transactions to commit and the events in the chunk. Transactions
+    /// (and their infos) that need to be retried are returned separately.
+    #[allow(clippy::type_complexity)]
+    fn verify_chunk_outputs(
+        first_version: u64,
+        transactions: Vec<Transaction>,
+        transaction_infos: Vec<PS::TransactionInfo>,
+        vm_outputs: Vec<TransactionOutput>,
+        state_view: VerifiedStateView<DpnProto>,
+        parent_trees: &ExecutedTrees,
+    ) -> Result<(
+        ProcessedVMOutput,
+        Vec<TransactionToCommit>,
+        Vec<ContractEvent>,
+        Vec<Transaction>,
+        Vec<PS::TransactionInfo>,
+    )> {
         // Since other validators have committed these transactions, their status should all be
         // TransactionStatus::Keep.
         for output in &vm_outputs {
@@ -513,7 +598,7 @@ where
             account_to_proof,
             &transactions,
             vm_outputs,
-            read_lock.synced_trees(),
+            parent_trees,
         )?;
 
         // Since we have verified the proofs, we just need to verify that each PS::TransactionInfo
@@ -604,6 +689,55 @@ where
 
         Ok((processed_vm_output, txns_to_commit, events))
     }
+
+    /// Applies the given transaction outputs (without re-executing the transactions) and
+    /// verifies the resulting state against the given transaction infos.
+    fn apply_chunk(
+        &self,
+        first_version: u64,
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+        transaction_infos: Vec<PS::TransactionInfo>,
+    ) -> Result<(
+        ProcessedVMOutput,
+        Vec<TransactionToCommit>,
+        Vec<ContractEvent>,
+    )> {
+        let read_lock = self.cache.read();
+        let state_view = VerifiedStateView::new(
+            StateViewId::ChunkExecution { first_version },
+            Arc::clone(&self.db.reader),
+            read_lock.synced_trees().version(),
+            read_lock.synced_trees().state_root(),
+            read_lock.synced_trees().state_tree(),
+        );
+
+        // Read all accounts touched by the write sets so that their states (and proofs) are
+        // loaded into the state view, as would have happened during execution.
+        for (_, output) in &transactions_and_outputs {
+            for (access_path, _) in output.write_set() {
+                state_view.get(access_path)?;
+            }
+        }
+
+        let (transactions, transaction_outputs): (Vec<_>, Vec<_>) =
+            transactions_and_outputs.into_iter().unzip();
+        let (processed_vm_output, txns_to_commit, events, txns_to_retry, _txn_infos_to_retry) =
+            Self::verify_chunk_outputs(
+                first_version,
+                transactions,
+                transaction_infos,
+                transaction_outputs,
+                state_view,
+                read_lock.synced_trees(),
+            )?;
+
+        ensure!(
+            txns_to_retry.is_empty(),
+            "Synced transaction outputs should not contain the status of 'Retry'"
+        );
+
+        Ok((processed_vm_output, txns_to_commit, events))
+    }
 }
 
 /// For all accounts modified by this transaction, find the previous blob and update it based
@@ -788,7 +788,7 @@ impl PublicUsageTest for MempoolValidationError {
             let resp = env.submit(&txn2);
             assert_eq!(
                 resp.error.expect("error").message,
-                "Server error: Mempool submission error: \"Failed to update gas price to 0\""
+                "Server error: Mempool submission error: \"Failed to update gas price to 0, it must be higher than the current gas price 0\""
                     .to_string(),
             );
         });
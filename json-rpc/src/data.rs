@@ -28,10 +28,7 @@ pub fn get_account_state(
     address: AccountAddress,
     version: u64,
 ) -> Result<Option<AccountState>> {
-    if let Some(blob) = db
-        .get_account_state_with_proof_by_version(address, version)?
-        .0
-    {
+    if let Some(blob) = db.get_account_state_by_version(address, version)? {
         Ok(Some(AccountState::try_from(&blob)?))
     } else {
         Ok(None)
@@ -5,6 +5,7 @@ module DiemFramework::DiemBlock {
     use DiemFramework::DiemTimestamp;
     use Std::Errors;
     use Std::Event;
+    use Std::Vector;
 
     struct BlockMetadata has key {
         /// Height of the current block
@@ -20,6 +21,9 @@ module DiemFramework::DiemBlock {
 
         /// On-chain time during  he block at the given height
         time_microseconds: u64,
+
+        /// Proposers of the rounds skipped since the previous block, ordered by round
+        failed_proposers: vector<address>,
     }
 
     /// The `BlockMetadata` resource is in an invalid state
@@ -57,13 +61,33 @@ module DiemFramework::DiemBlock {
     }
 
     /// Set the metadata for the current block.
-    /// The runtime always runs this before executing the transactions in a block.
+    /// The runtime runs this before executing the transactions in a block, until `block_prologue_v2`
+    /// is enabled.
     fun block_prologue(
         vm: signer,
         round: u64,
         timestamp: u64,
         previous_block_votes: vector<address>,
         proposer: address
+    ) acquires BlockMetadata {
+        block_prologue_v2(vm, round, timestamp, previous_block_votes, proposer, Vector::empty())
+    }
+    spec block_prologue {
+        include BlockPrologueAbortsIf;
+        include BlockPrologueEnsures;
+        include BlockPrologueEmits{failed_proposers: vec()};
+    }
+
+    /// Set the metadata for the current block, with the proposers of the rounds skipped since the
+    /// previous block.
+    /// The runtime always runs this before executing the transactions in a block.
+    fun block_prologue_v2(
+        vm: signer,
+        round: u64,
+        timestamp: u64,
+        previous_block_votes: vector<address>,
+        proposer: address,
+        failed_proposers: vector<address>
     ) acquires BlockMetadata {
         DiemTimestamp::assert_operating();
         // Operational constraint: can only be invoked by the VM.
@@ -85,31 +109,42 @@ module DiemFramework::DiemBlock {
                 proposer,
                 previous_block_votes,
                 time_microseconds: timestamp,
+                failed_proposers,
             }
         );
     }
-    spec block_prologue {
+    spec block_prologue_v2 {
+        include BlockPrologueAbortsIf;
+        include BlockPrologueEnsures;
+        include BlockPrologueEmits;
+    }
+    spec schema BlockPrologueAbortsIf {
+        vm: signer;
+        proposer: address;
         include DiemTimestamp::AbortsIfNotOperating;
         include CoreAddresses::AbortsIfNotVM{account: vm};
         aborts_if proposer != @VMReserved && !DiemSystem::spec_is_validator(proposer)
             with Errors::REQUIRES_ADDRESS;
+        aborts_if get_current_block_height() + 1 > MAX_U64 with EXECUTION_FAILURE;
+    }
+    spec schema BlockPrologueEnsures {
+        timestamp: u64;
         ensures DiemTimestamp::spec_now_microseconds() == timestamp;
         ensures get_current_block_height() == old(get_current_block_height()) + 1;
-
-        aborts_if get_current_block_height() + 1 > MAX_U64 with EXECUTION_FAILURE;
-        include BlockPrologueEmits;
     }
     spec schema BlockPrologueEmits {
         round: u64;
         timestamp: u64;
         previous_block_votes: vector<address>;
         proposer: address;
+        failed_proposers: vector<address>;
         let handle = global<BlockMetadata>(@DiemRoot).new_block_events;
         let msg = NewBlockEvent {
             round,
             proposer,
             previous_block_votes,
             time_microseconds: timestamp,
+            failed_proposers,
         };
         emits msg to handle;
     }
@@ -24,8 +24,11 @@ impl DBDebuggerInterface {
             db_root_path,
             true,
             None,
+            None,
             RocksdbConfig::default(),
-            true, /* account_count_migration, ignored anyway */
+            true,  /* account_count_migration, ignored anyway */
+            false, /* resource_granular_state */
+            0,     /* state_value_cache_size */
         )?)))
     }
 }
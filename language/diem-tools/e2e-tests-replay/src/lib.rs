@@ -25,7 +25,7 @@ use diem_types::{
         from_currency_code_string, reserved_vm_address, type_tag_for_currency_code, ACCOUNT_MODULE,
     },
     block_metadata::BlockMetadata,
-    on_chain_config::DiemVersion,
+    on_chain_config::{DiemVersion, DIEM_VERSION_5},
     transaction::{
         Script, ScriptFunction, Transaction, TransactionArgument, TransactionOutput,
         TransactionPayload, TransactionStatus, WriteSetPayload,
@@ -36,8 +36,8 @@ use diem_vm::{
     natives::diem_natives,
     script_to_script_function::remapping,
     system_module_names::{
-        BLOCK_PROLOGUE, DIEM_BLOCK_MODULE, SCRIPT_PROLOGUE_NAME, USER_EPILOGUE_NAME,
-        WRITESET_EPILOGUE_NAME, WRITESET_PROLOGUE_NAME,
+        BLOCK_PROLOGUE, BLOCK_PROLOGUE_V2, DIEM_BLOCK_MODULE, SCRIPT_PROLOGUE_NAME,
+        USER_EPILOGUE_NAME, WRITESET_EPILOGUE_NAME, WRITESET_PROLOGUE_NAME,
     },
     transaction_metadata::TransactionMetadata,
 };
@@ -486,17 +486,30 @@ impl<'env> TraceReplayer<'env> {
     ) {
         // args
         let signer = reserved_vm_address();
-        let (round, timestamp, previous_votes, proposer) = block_metadata.into_inner();
-        let args: Vec<_> = vec![
+        let (round, timestamp, previous_votes, proposer, failed_proposers) =
+            block_metadata.into_inner();
+        let mut args = vec![
             MoveValue::Signer(signer),
             MoveValue::U64(round),
             MoveValue::U64(timestamp),
             MoveValue::Vector(previous_votes.into_iter().map(MoveValue::Address).collect()),
             MoveValue::Address(proposer),
-        ]
-        .into_iter()
-        .map(|v| v.simple_serialize().unwrap())
-        .collect();
+        ];
+        let prologue = if self.flags.diem_version >= DIEM_VERSION_5 {
+            args.push(MoveValue::Vector(
+                failed_proposers
+                    .into_iter()
+                    .map(MoveValue::Address)
+                    .collect(),
+            ));
+            &*BLOCK_PROLOGUE_V2
+        } else {
+            &*BLOCK_PROLOGUE
+        };
+        let args: Vec<_> = args
+            .into_iter()
+            .map(|v| v.simple_serialize().unwrap())
+            .collect();
 
         // execute
         let move_vm = MoveVM::new(diem_natives()).unwrap();
@@ -515,7 +528,7 @@ impl<'env> TraceReplayer<'env> {
             &mut session,
             xrunner.as_mut(),
             &*DIEM_BLOCK_MODULE,
-            &*BLOCK_PROLOGUE,
+            prologue,
             vec![],
             args,
         );
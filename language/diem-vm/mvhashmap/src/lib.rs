@@ -7,11 +7,15 @@ use std::{
     collections::{btree_map::BTreeMap, HashMap},
     fmt::Debug,
     hash::Hash,
+    sync::Arc,
 };
 
+mod speculative;
 #[cfg(test)]
 mod unit_tests;
 
+pub use speculative::{Incarnation, ReadError, SpeculativeMVHashMap, TxnVersion};
+
 /// A structure that holds placeholders for each write to the database
 //
 //  The structure is created by one thread creating the scheduling, and
@@ -38,7 +42,7 @@ pub enum Error {
 }
 
 #[cfg_attr(any(target_arch = "x86_64"), repr(align(128)))]
-pub(crate) struct WriteCell<V>(OnceCell<Option<V>>);
+pub(crate) struct WriteCell<V>(OnceCell<Option<Arc<V>>>);
 
 impl<V> WriteCell<V> {
     pub fn new() -> WriteCell<V> {
@@ -51,14 +55,14 @@ impl<V> WriteCell<V> {
 
     pub fn write(&self, v: V) {
         // Each cell should only be written exactly once.
-        assert!(self.0.set(Some(v)).is_ok())
+        assert!(self.0.set(Some(Arc::new(v))).is_ok())
     }
 
     pub fn skip(&self) {
         assert!(self.0.set(None).is_ok());
     }
 
-    pub fn get(&self) -> Option<&Option<V>> {
+    pub fn get(&self) -> Option<&Option<Arc<V>>> {
         self.0.get()
     }
 }
@@ -157,7 +161,7 @@ impl<K: Hash + Clone + Eq, V> MVHashMap<K, V> {
     /// Returns Ok(val) if such key is already assigned by previous transactions.
     /// Returns Err(None) if `version` is smaller than the write of all previous versions.
     /// Returns Err(Some(version)) if such key is dependent on the `version`-th transaction.
-    pub fn read(&self, key: &K, version: Version) -> Result<&V, Option<Version>> {
+    pub fn read(&self, key: &K, version: Version) -> Result<Arc<V>, Option<Version>> {
         let tree = self.data.get(key).ok_or(None)?;
 
         let mut iter = tree.range(0..version);
@@ -169,7 +173,7 @@ impl<K: Hash + Clone + Eq, V> MVHashMap<K, V> {
                     None => return Err(Some(*entry_key)),
                     // Entry is skipped, go to previous version.
                     Some(None) => continue,
-                    Some(Some(v)) => return Ok(v),
+                    Some(Some(v)) => return Ok(v.clone()),
                 }
             }
         }
@@ -183,7 +187,7 @@ const PARALLEL_THRESHOLD: usize = 1000;
 impl<K, V> MVHashMap<K, V>
 where
     K: PartialOrd + Send + Clone + Hash + Eq,
-    V: Send,
+    V: Send + Sync,
 {
     fn split_merge(
         num_cpus: usize,
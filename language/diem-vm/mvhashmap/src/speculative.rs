@@ -0,0 +1,113 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Version;
+use std::{
+    collections::{btree_map::BTreeMap, HashMap},
+    hash::Hash,
+    sync::{Arc, RwLock},
+};
+
+/// The number of times a transaction has been (re-)executed.
+pub type Incarnation = usize;
+
+/// A version of a value in the speculative map: the index of the transaction that wrote it, and
+/// the incarnation of that transaction that performed the write.
+pub type TxnVersion = (Version, Incarnation);
+
+enum Entry<V> {
+    /// The value written by the given incarnation of a transaction.
+    Written(Incarnation, Arc<V>),
+    /// The incarnation that wrote this entry was aborted, and the transaction is expected to
+    /// write to this key again when it gets re-executed.
+    Estimate,
+}
+
+/// All the writes to a single key, ordered by the index of the writing transaction.
+type VersionedEntries<V> = RwLock<BTreeMap<Version, Entry<V>>>;
+
+#[derive(Debug, PartialEq)]
+pub enum ReadError {
+    /// No transaction with a lower index wrote to the key, the value should be read from storage.
+    NotFound,
+    /// The closest lower write is an estimate left by an aborted transaction with this index.
+    Dependency(Version),
+}
+
+/// A multi-version map for optimistic execution where the set of writes is not known upfront.
+//
+//  Unlike `MVHashMap`, entries are added, overwritten and removed while transactions are being
+//  (re-)executed in parallel, so every key is guarded by a lock. The outer map is only locked for
+//  writing when a key is seen for the first time.
+//
+pub struct SpeculativeMVHashMap<K, V> {
+    data: RwLock<HashMap<K, VersionedEntries<V>>>,
+}
+
+impl<K: Hash + Clone + Eq, V> SpeculativeMVHashMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            data: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Write `data` to `key` on behalf of the transaction at `version`, replacing any value or
+    /// estimate left by a previous incarnation of the same transaction.
+    pub fn write(&self, key: &K, version: TxnVersion, data: V) {
+        let (txn_idx, incarnation) = version;
+        let entry = Entry::Written(incarnation, Arc::new(data));
+        {
+            let map = self.data.read().unwrap();
+            if let Some(tree) = map.get(key) {
+                tree.write().unwrap().insert(txn_idx, entry);
+                return;
+            }
+        }
+        let mut map = self.data.write().unwrap();
+        map.entry(key.clone())
+            .or_default()
+            .get_mut()
+            .unwrap()
+            .insert(txn_idx, entry);
+    }
+
+    /// Mark the value written to `key` by the transaction at `txn_idx` as an estimate, so that
+    /// readers wait for the transaction to be re-executed instead of reading a stale value.
+    pub fn mark_estimate(&self, key: &K, txn_idx: Version) {
+        let map = self.data.read().unwrap();
+        if let Some(tree) = map.get(key) {
+            if let Some(entry) = tree.write().unwrap().get_mut(&txn_idx) {
+                *entry = Entry::Estimate;
+            }
+        }
+    }
+
+    /// Remove the write to `key` by the transaction at `txn_idx`. Used when a re-execution of the
+    /// transaction no longer writes to a key written by a previous incarnation.
+    pub fn delete(&self, key: &K, txn_idx: Version) {
+        let map = self.data.read().unwrap();
+        if let Some(tree) = map.get(key) {
+            tree.write().unwrap().remove(&txn_idx);
+        }
+    }
+
+    /// Get the value of `key` as seen by the transaction at `txn_idx`, i.e. the value written by
+    /// the closest transaction with a lower index, along with the version of that write.
+    pub fn read(&self, key: &K, txn_idx: Version) -> Result<(TxnVersion, Arc<V>), ReadError> {
+        let map = self.data.read().unwrap();
+        let tree = map.get(key).ok_or(ReadError::NotFound)?.read().unwrap();
+        match tree.range(0..txn_idx).next_back() {
+            Some((idx, Entry::Written(incarnation, data))) => {
+                Ok(((*idx, *incarnation), data.clone()))
+            }
+            Some((idx, Entry::Estimate)) => Err(ReadError::Dependency(*idx)),
+            None => Err(ReadError::NotFound),
+        }
+    }
+}
+
+impl<K: Hash + Clone + Eq, V> Default for SpeculativeMVHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
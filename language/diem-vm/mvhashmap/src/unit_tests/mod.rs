@@ -43,7 +43,7 @@ fn create_write_read_placeholder_struct() {
 
     // Subsequent higher reads read this entry
     let r1 = mvtbl.read(&ap1, 15);
-    assert_eq!(Ok(&Some(vec![0, 0, 0])), r1);
+    assert_eq!(Ok(Arc::new(Some(vec![0, 0, 0]))), r1);
 
     // Set skip works
     assert!(mvtbl.skip(&ap1, 20).is_err());
@@ -54,5 +54,42 @@ fn create_write_read_placeholder_struct() {
 
     // Higher reads skip this entry
     let r1 = mvtbl.read(&ap2, 25);
-    assert_eq!(Ok(&Some(vec![0, 0, 0])), r1);
+    assert_eq!(Ok(Arc::new(Some(vec![0, 0, 0]))), r1);
+}
+
+#[test]
+fn speculative_write_estimate_delete() {
+    let ap1 = b"/foo/b".to_vec();
+    let ap2 = b"/foo/c".to_vec();
+
+    let mvtbl = SpeculativeMVHashMap::new();
+
+    // Reads of keys that nobody wrote go to storage.
+    assert_eq!(Err(ReadError::NotFound), mvtbl.read(&ap1, 5));
+
+    mvtbl.write(&ap1, (10, 0), vec![0, 0, 0]);
+    mvtbl.write(&ap2, (10, 0), vec![1, 1, 1]);
+
+    // Reads at or below the version of the write go to storage.
+    assert_eq!(Err(ReadError::NotFound), mvtbl.read(&ap1, 10));
+    assert_eq!(Ok(((10, 0), Arc::new(vec![0, 0, 0]))), mvtbl.read(&ap1, 15));
+
+    // A later incarnation overwrites the previous one.
+    mvtbl.write(&ap1, (10, 1), vec![0, 0, 1]);
+    assert_eq!(Ok(((10, 1), Arc::new(vec![0, 0, 1]))), mvtbl.read(&ap1, 15));
+
+    // Higher reads see the closest lower write.
+    mvtbl.write(&ap1, (12, 0), vec![0, 1, 2]);
+    assert_eq!(Ok(((12, 0), Arc::new(vec![0, 1, 2]))), mvtbl.read(&ap1, 15));
+    assert_eq!(Ok(((10, 1), Arc::new(vec![0, 0, 1]))), mvtbl.read(&ap1, 12));
+
+    // Estimates turn into a dependency on the writing transaction.
+    mvtbl.mark_estimate(&ap1, 12);
+    assert_eq!(Err(ReadError::Dependency(12)), mvtbl.read(&ap1, 15));
+
+    // Deleted writes expose the previous version again.
+    mvtbl.delete(&ap1, 12);
+    assert_eq!(Ok(((10, 1), Arc::new(vec![0, 0, 1]))), mvtbl.read(&ap1, 15));
+    mvtbl.delete(&ap2, 10);
+    assert_eq!(Err(ReadError::NotFound), mvtbl.read(&ap2, 15));
 }
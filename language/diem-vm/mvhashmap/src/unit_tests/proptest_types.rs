@@ -115,7 +115,7 @@ where
                         let baseline = baseline.get(key, idx);
                         let mut retry_attempts = 0;
                         loop {
-                            match map.read(key, idx) {
+                            match map.read(key, idx).as_deref() {
                                 Ok(Some(v)) => {
                                     assert_eq!(
                                         baseline,
@@ -3,12 +3,14 @@
 
 use crate::{
     errors::*,
+    optimistic_executor::ReadDescriptor,
+    optimistic_scheduler::OptimisticScheduler,
     outcome_array::OutcomeArray,
     scheduler::Scheduler,
     task::{ExecutionStatus, ExecutorTask, ReadWriteSetInferencer, Transaction, TransactionOutput},
 };
 use anyhow::{bail, Result as AResult};
-use mvhashmap::{MVHashMap, Version};
+use mvhashmap::{MVHashMap, ReadError, SpeculativeMVHashMap, Version};
 use num_cpus;
 use rayon::{prelude::*, scope};
 use std::{
@@ -17,30 +19,100 @@ use std::{
     marker::PhantomData,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
 };
 
+enum VersionedMap<'a, K, V> {
+    /// Placeholders for the writes estimated by the inferencer.
+    Static {
+        map: &'a MVHashMap<K, V>,
+        scheduler: &'a Scheduler,
+    },
+    /// Writes recorded by the optimistic executor, along with the reads performed by the current
+    /// execution so that they can be validated later.
+    Speculative {
+        map: &'a SpeculativeMVHashMap<K, V>,
+        scheduler: &'a OptimisticScheduler,
+        captured_reads: Mutex<Vec<ReadDescriptor<K>>>,
+    },
+}
+
 pub struct MVHashMapView<'a, K, V> {
-    map: &'a MVHashMap<K, V>,
+    map: VersionedMap<'a, K, V>,
     version: Version,
-    scheduler: &'a Scheduler,
     has_unexpected_read: AtomicBool,
 }
 
 impl<'a, K: Hash + Clone + Eq, V> MVHashMapView<'a, K, V> {
-    pub fn read(&self, key: &K) -> AResult<Option<&V>> {
-        match self.map.read(key, self.version) {
-            Ok(v) => Ok(Some(v)),
-            Err(None) => Ok(None),
-            Err(Some(dep_idx)) => {
-                // Don't start execution transaction `self.version` until `dep_idx` is computed.
-                if !self.scheduler.add_dependency(self.version, dep_idx) {
-                    // dep_idx is already executed, push `self.version` to ready queue.
-                    self.scheduler.add_transaction(self.version);
+    pub(crate) fn new_speculative(
+        map: &'a SpeculativeMVHashMap<K, V>,
+        version: Version,
+        scheduler: &'a OptimisticScheduler,
+    ) -> Self {
+        Self {
+            map: VersionedMap::Speculative {
+                map,
+                scheduler,
+                captured_reads: Mutex::new(Vec::new()),
+            },
+            version,
+            has_unexpected_read: AtomicBool::new(false),
+        }
+    }
+
+    pub fn read(&self, key: &K) -> AResult<Option<Arc<V>>> {
+        match &self.map {
+            VersionedMap::Static { map, scheduler } => match map.read(key, self.version) {
+                Ok(v) => Ok(Some(v)),
+                Err(None) => Ok(None),
+                Err(Some(dep_idx)) => {
+                    // Don't start execution transaction `self.version` until `dep_idx` is computed.
+                    if !scheduler.add_dependency(self.version, dep_idx) {
+                        // dep_idx is already executed, push `self.version` to ready queue.
+                        scheduler.add_transaction(self.version);
+                    }
+                    self.has_unexpected_read.fetch_or(true, Ordering::Relaxed);
+                    bail!("Read dependency is not computed, retry later")
+                }
+            },
+            VersionedMap::Speculative {
+                map,
+                scheduler,
+                captured_reads,
+            } => {
+                if self.has_unexpected_read() {
+                    // The transaction is already waiting on a dependency, this execution will be
+                    // discarded.
+                    bail!("Read dependency is not computed, retry later")
+                }
+                loop {
+                    match map.read(key, self.version) {
+                        Ok((version, v)) => {
+                            captured_reads
+                                .lock()
+                                .unwrap()
+                                .push(ReadDescriptor::from_version(key.clone(), version));
+                            return Ok(Some(v));
+                        }
+                        Err(ReadError::NotFound) => {
+                            captured_reads
+                                .lock()
+                                .unwrap()
+                                .push(ReadDescriptor::from_storage(key.clone()));
+                            return Ok(None);
+                        }
+                        Err(ReadError::Dependency(dep_idx)) => {
+                            // Retry the read if `dep_idx` was re-executed in the meantime,
+                            // otherwise `self.version` gets rescheduled once it is.
+                            if !scheduler.wait_for_dependency(self.version, dep_idx) {
+                                continue;
+                            }
+                            self.has_unexpected_read.fetch_or(true, Ordering::Relaxed);
+                            bail!("Read dependency is not computed, retry later")
+                        }
+                    }
                 }
-                self.has_unexpected_read.fetch_or(true, Ordering::Relaxed);
-                bail!("Read dependency is not computed, retry later")
             }
         }
     }
@@ -52,6 +124,16 @@ impl<'a, K: Hash + Clone + Eq, V> MVHashMapView<'a, K, V> {
     pub fn has_unexpected_read(&self) -> bool {
         self.has_unexpected_read.load(Ordering::Relaxed)
     }
+
+    /// Take the reads performed through a speculative view.
+    pub(crate) fn take_reads(&self) -> Vec<ReadDescriptor<K>> {
+        match &self.map {
+            VersionedMap::Static { .. } => vec![],
+            VersionedMap::Speculative { captured_reads, .. } => {
+                std::mem::take(&mut *captured_reads.lock().unwrap())
+            }
+        }
+    }
 }
 
 pub struct ParallelTransactionExecutor<T: Transaction, E: ExecutorTask, I: ReadWriteSetInferencer> {
@@ -151,9 +233,11 @@ where
 
                         // Process the output of a transaction
                         let view = MVHashMapView {
-                            map: &versioned_data_cache,
+                            map: VersionedMap::Static {
+                                map: &versioned_data_cache,
+                                scheduler: &scheduler,
+                            },
                             version: idx,
-                            scheduler: &scheduler,
                             has_unexpected_read: AtomicBool::new(false),
                         };
                         let execute_result = task.execute_transaction(&view, txn);
@@ -3,6 +3,8 @@
 
 pub mod errors;
 pub mod executor;
+pub mod optimistic_executor;
+mod optimistic_scheduler;
 mod outcome_array;
 pub mod proptest_types;
 mod scheduler;
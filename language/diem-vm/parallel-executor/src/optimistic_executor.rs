@@ -0,0 +1,279 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    errors::*,
+    executor::MVHashMapView,
+    optimistic_scheduler::{OptimisticScheduler, SchedulerTask},
+    task::{ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
+};
+use mvhashmap::{ReadError, SpeculativeMVHashMap, TxnVersion, Version};
+use num_cpus;
+use rayon::scope;
+use std::{
+    cmp::{max, min},
+    collections::HashSet,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ReadKind {
+    /// The value was read from storage as no lower transaction wrote to the key.
+    Storage,
+    /// The value was written by the given version of a lower transaction.
+    Version(TxnVersion),
+}
+
+/// A read performed by a transaction, recorded to validate the execution once it completes.
+#[derive(Clone, Debug)]
+pub(crate) struct ReadDescriptor<K> {
+    key: K,
+    kind: ReadKind,
+}
+
+impl<K> ReadDescriptor<K> {
+    pub(crate) fn from_storage(key: K) -> Self {
+        Self {
+            key,
+            kind: ReadKind::Storage,
+        }
+    }
+
+    pub(crate) fn from_version(key: K, version: TxnVersion) -> Self {
+        Self {
+            key,
+            kind: ReadKind::Version(version),
+        }
+    }
+}
+
+/// The reads, writes and output recorded by the last execution of each transaction.
+struct TxnLastInputOutput<K, T, E> {
+    reads: Vec<Mutex<Arc<Vec<ReadDescriptor<K>>>>>,
+    modified_keys: Vec<Mutex<Vec<K>>>,
+    outputs: Vec<Mutex<Option<ExecutionStatus<T, E>>>>,
+}
+
+impl<K: Clone, T, E> TxnLastInputOutput<K, T, E> {
+    fn new(num_txns: usize) -> Self {
+        Self {
+            reads: (0..num_txns)
+                .map(|_| Mutex::new(Arc::new(vec![])))
+                .collect(),
+            modified_keys: (0..num_txns).map(|_| Mutex::new(vec![])).collect(),
+            outputs: (0..num_txns).map(|_| Mutex::new(None)).collect(),
+        }
+    }
+
+    fn record(
+        &self,
+        txn_idx: Version,
+        reads: Vec<ReadDescriptor<K>>,
+        modified_keys: Vec<K>,
+        output: ExecutionStatus<T, E>,
+    ) {
+        *self.reads[txn_idx].lock().unwrap() = Arc::new(reads);
+        *self.modified_keys[txn_idx].lock().unwrap() = modified_keys;
+        *self.outputs[txn_idx].lock().unwrap() = Some(output);
+    }
+
+    fn read_set(&self, txn_idx: Version) -> Arc<Vec<ReadDescriptor<K>>> {
+        self.reads[txn_idx].lock().unwrap().clone()
+    }
+
+    fn modified_keys(&self, txn_idx: Version) -> Vec<K> {
+        self.modified_keys[txn_idx].lock().unwrap().clone()
+    }
+}
+
+/// Executes a block of transactions in parallel without knowing their read and write sets
+/// upfront. Transactions are executed speculatively against the writes of lower transactions,
+/// validated once executed, and re-executed whenever a validation detects that a value they read
+/// was since overwritten. The output is identical to executing the block sequentially.
+pub struct OptimisticTransactionExecutor<T: Transaction, E: ExecutorTask> {
+    concurrency_level: usize,
+    phantom: PhantomData<(T, E)>,
+}
+
+impl<T, E> OptimisticTransactionExecutor<T, E>
+where
+    T: Transaction,
+    E: ExecutorTask<T = T>,
+{
+    /// Create an executor using one thread per CPU.
+    pub fn new() -> Self {
+        Self::with_concurrency_level(num_cpus::get())
+    }
+
+    /// Create an executor using `concurrency_level` threads.
+    pub fn with_concurrency_level(concurrency_level: usize) -> Self {
+        Self {
+            concurrency_level: max(1, concurrency_level),
+            phantom: PhantomData,
+        }
+    }
+
+    fn execute(
+        &self,
+        version: TxnVersion,
+        signature_verified_block: &[T],
+        last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        versioned_data_cache: &SpeculativeMVHashMap<T::Key, T::Value>,
+        scheduler: &OptimisticScheduler,
+        executor: &E,
+    ) -> SchedulerTask {
+        let (idx_to_execute, incarnation) = version;
+        let txn = &signature_verified_block[idx_to_execute];
+
+        let view = MVHashMapView::new_speculative(versioned_data_cache, idx_to_execute, scheduler);
+        let execute_result = executor.execute_transaction(&view, txn);
+        if view.has_unexpected_read() {
+            // The transaction was suspended on a dependency by the view, and will be re-executed
+            // once the dependency is resolved.
+            return SchedulerTask::NoTask;
+        }
+
+        // Replace the writes of the previous incarnation with the writes of this one.
+        let mut prev_modified_keys: HashSet<_> = last_input_output
+            .modified_keys(idx_to_execute)
+            .into_iter()
+            .collect();
+        let writes = match &execute_result {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                output.get_writes()
+            }
+            ExecutionStatus::Abort(_) => vec![],
+        };
+        let mut updates_outside = false;
+        let mut modified_keys = Vec::with_capacity(writes.len());
+        for (k, v) in writes {
+            if !prev_modified_keys.remove(&k) {
+                updates_outside = true;
+            }
+            versioned_data_cache.write(&k, version, v);
+            modified_keys.push(k);
+        }
+        for k in prev_modified_keys {
+            versioned_data_cache.delete(&k, idx_to_execute);
+        }
+
+        last_input_output.record(
+            idx_to_execute,
+            view.take_reads(),
+            modified_keys,
+            execute_result,
+        );
+        scheduler.finish_execution(idx_to_execute, incarnation, updates_outside)
+    }
+
+    fn validate(
+        &self,
+        version: TxnVersion,
+        last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        versioned_data_cache: &SpeculativeMVHashMap<T::Key, T::Value>,
+        scheduler: &OptimisticScheduler,
+    ) -> SchedulerTask {
+        let (idx_to_validate, incarnation) = version;
+        let read_set = last_input_output.read_set(idx_to_validate);
+
+        let valid =
+            read_set.iter().all(
+                |r| match versioned_data_cache.read(&r.key, idx_to_validate) {
+                    Ok((written_version, _)) => r.kind == ReadKind::Version(written_version),
+                    Err(ReadError::NotFound) => r.kind == ReadKind::Storage,
+                    Err(ReadError::Dependency(_)) => false,
+                },
+            );
+
+        let aborted = !valid && scheduler.try_abort(idx_to_validate, incarnation);
+        if aborted {
+            // Readers of the aborted writes wait for the next incarnation instead of reading
+            // values that are likely to change.
+            for k in last_input_output.modified_keys(idx_to_validate) {
+                versioned_data_cache.mark_estimate(&k, idx_to_validate);
+            }
+        }
+        scheduler.finish_validation(idx_to_validate, aborted)
+    }
+
+    pub fn execute_transactions_parallel(
+        &self,
+        task_initial_arguments: E::Argument,
+        signature_verified_block: Vec<T>,
+    ) -> Result<Vec<E::Output>, E::Error> {
+        let num_txns = signature_verified_block.len();
+        if num_txns == 0 {
+            return Ok(vec![]);
+        }
+
+        let versioned_data_cache = SpeculativeMVHashMap::new();
+        let last_input_output = TxnLastInputOutput::new(num_txns);
+        let scheduler = OptimisticScheduler::new(num_txns);
+
+        scope(|s| {
+            for _ in 0..min(self.concurrency_level, num_txns) {
+                s.spawn(|_| {
+                    // Make a new executor per thread.
+                    let executor = E::init(task_initial_arguments);
+
+                    let mut task = SchedulerTask::NoTask;
+                    loop {
+                        task = match task {
+                            SchedulerTask::ExecutionTask(version) => self.execute(
+                                version,
+                                &signature_verified_block,
+                                &last_input_output,
+                                &versioned_data_cache,
+                                &scheduler,
+                                &executor,
+                            ),
+                            SchedulerTask::ValidationTask(version) => self.validate(
+                                version,
+                                &last_input_output,
+                                &versioned_data_cache,
+                                &scheduler,
+                            ),
+                            SchedulerTask::NoTask => scheduler.next_task(),
+                            SchedulerTask::Done => break,
+                        }
+                    }
+                });
+            }
+        });
+
+        // Every transaction has been executed and validated, its last output is the one a
+        // sequential execution would produce, up until the first transaction that halts the block.
+        let mut final_results = Vec::with_capacity(num_txns);
+        for output in last_input_output.outputs {
+            match output.into_inner().unwrap() {
+                Some(ExecutionStatus::Success(t)) => final_results.push(t),
+                Some(ExecutionStatus::SkipRest(t)) => {
+                    final_results.push(t);
+                    break;
+                }
+                Some(ExecutionStatus::Abort(err)) => return Err(Error::UserError(err)),
+                None => return Err(Error::InvariantViolation),
+            }
+        }
+        final_results.resize_with(num_txns, E::Output::skip_output);
+
+        // Dropping large structures is expensive -- do this is a separate thread.
+        ::std::thread::spawn(move || {
+            drop(signature_verified_block); // Explicit drops to measure their cost.
+            drop(versioned_data_cache);
+        });
+
+        Ok(final_results)
+    }
+}
+
+impl<T, E> Default for OptimisticTransactionExecutor<T, E>
+where
+    T: Transaction,
+    E: ExecutorTask<T = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
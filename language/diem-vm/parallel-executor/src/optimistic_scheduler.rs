@@ -0,0 +1,253 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use mvhashmap::{Incarnation, TxnVersion, Version};
+use std::{
+    cmp::min,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// A task handed out to a worker thread by the scheduler.
+#[derive(Debug, PartialEq)]
+pub enum SchedulerTask {
+    /// Execute the given incarnation of a transaction.
+    ExecutionTask(TxnVersion),
+    /// Validate the read set recorded by the given incarnation of a transaction.
+    ValidationTask(TxnVersion),
+    /// Nothing to do at the moment, ask again.
+    NoTask,
+    /// All transactions are executed and validated.
+    Done,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TxnStatus {
+    ReadyToExecute(Incarnation),
+    Executing(Incarnation),
+    Executed(Incarnation),
+    Aborting(Incarnation),
+}
+
+/// Scheduler for optimistic execution: transactions are executed in order of their index without
+/// knowing their read or write sets upfront, and each execution is followed by a validation of
+/// the values it read. A failed validation aborts the transaction and schedules a new incarnation
+/// of it, as well as a re-validation of all the transactions with a higher index.
+//
+//  The scheduler keeps two ordered task queues in the form of the `execution_idx` and
+//  `validation_idx` counters. Whenever an abort or a write to a new key could invalidate the work
+//  done by higher transactions, the corresponding counter is decreased, and `decrease_cnt` is
+//  bumped so that threads checking for completion notice the change.
+//
+pub struct OptimisticScheduler {
+    /// Next transaction to execute, unless it is already being or has been executed.
+    execution_idx: AtomicUsize,
+    /// Next transaction to validate, unless it hasn't been executed yet.
+    validation_idx: AtomicUsize,
+    /// Number of times `execution_idx` or `validation_idx` were decreased.
+    decrease_cnt: AtomicUsize,
+    /// Number of tasks currently handed out to worker threads.
+    num_active_tasks: AtomicUsize,
+    /// Set once all transactions are executed and validated.
+    done_marker: AtomicBool,
+    num_txns: usize,
+
+    /// Transactions waiting for the given transaction to finish its execution.
+    txn_dependency: Vec<Mutex<Vec<Version>>>,
+    txn_status: Vec<Mutex<TxnStatus>>,
+}
+
+impl OptimisticScheduler {
+    pub fn new(num_txns: usize) -> Self {
+        Self {
+            execution_idx: AtomicUsize::new(0),
+            validation_idx: AtomicUsize::new(0),
+            decrease_cnt: AtomicUsize::new(0),
+            num_active_tasks: AtomicUsize::new(0),
+            done_marker: AtomicBool::new(false),
+            num_txns,
+            txn_dependency: (0..num_txns).map(|_| Mutex::new(Vec::new())).collect(),
+            txn_status: (0..num_txns)
+                .map(|_| Mutex::new(TxnStatus::ReadyToExecute(0)))
+                .collect(),
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.done_marker.load(Ordering::Acquire)
+    }
+
+    /// Return the next task for a worker thread, validations being prioritized over executions
+    /// of transactions with a higher index.
+    pub fn next_task(&self) -> SchedulerTask {
+        if self.done() {
+            return SchedulerTask::Done;
+        }
+        let validation_idx = self.validation_idx.load(Ordering::SeqCst);
+        let execution_idx = self.execution_idx.load(Ordering::SeqCst);
+        if validation_idx < execution_idx {
+            if let Some(version) = self.next_version_to_validate() {
+                return SchedulerTask::ValidationTask(version);
+            }
+        } else if let Some(version) = self.next_version_to_execute() {
+            return SchedulerTask::ExecutionTask(version);
+        }
+        SchedulerTask::NoTask
+    }
+
+    /// Record that the transaction at `txn_idx`, which is being executed, read a value estimated
+    /// by the transaction at `dep_txn_idx`. Returns false if `dep_txn_idx` was executed in the
+    /// meantime and the read can be retried, otherwise the current execution is abandoned and the
+    /// transaction is scheduled again once `dep_txn_idx` finishes executing.
+    pub fn wait_for_dependency(&self, txn_idx: Version, dep_txn_idx: Version) -> bool {
+        let mut stored_deps = self.txn_dependency[dep_txn_idx].lock().unwrap();
+        if let TxnStatus::Executed(_) = *self.txn_status[dep_txn_idx].lock().unwrap() {
+            return false;
+        }
+        {
+            let mut status = self.txn_status[txn_idx].lock().unwrap();
+            if let TxnStatus::Executing(incarnation) = *status {
+                *status = TxnStatus::Aborting(incarnation);
+            }
+        }
+        stored_deps.push(txn_idx);
+        drop(stored_deps);
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        true
+    }
+
+    /// Mark the given incarnation as executed, and resume the transactions that were waiting for
+    /// it. If the execution wrote to a key the previous incarnation didn't write to, all higher
+    /// transactions need to be validated again, otherwise only the transaction itself is.
+    pub fn finish_execution(
+        &self,
+        txn_idx: Version,
+        incarnation: Incarnation,
+        revalidate_suffix: bool,
+    ) -> SchedulerTask {
+        *self.txn_status[txn_idx].lock().unwrap() = TxnStatus::Executed(incarnation);
+
+        let deps = std::mem::take(&mut *self.txn_dependency[txn_idx].lock().unwrap());
+        self.resume_dependencies(deps);
+
+        if self.validation_idx.load(Ordering::SeqCst) > txn_idx {
+            if revalidate_suffix {
+                self.decrease_validation_idx(txn_idx);
+            } else {
+                return SchedulerTask::ValidationTask((txn_idx, incarnation));
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        SchedulerTask::NoTask
+    }
+
+    /// Try to abort the given incarnation after its validation failed. Only one validation of an
+    /// incarnation succeeds in aborting it.
+    pub fn try_abort(&self, txn_idx: Version, incarnation: Incarnation) -> bool {
+        let mut status = self.txn_status[txn_idx].lock().unwrap();
+        if *status == TxnStatus::Executed(incarnation) {
+            *status = TxnStatus::Aborting(incarnation);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Finish the validation of a transaction. If the validation aborted the transaction, all
+    /// higher transactions need to be validated again and a new incarnation is returned for
+    /// execution when possible.
+    pub fn finish_validation(&self, txn_idx: Version, aborted: bool) -> SchedulerTask {
+        if aborted {
+            self.set_ready_status(txn_idx);
+            self.decrease_validation_idx(txn_idx + 1);
+            if self.execution_idx.load(Ordering::SeqCst) > txn_idx {
+                if let Some(incarnation) = self.try_incarnate(txn_idx) {
+                    return SchedulerTask::ExecutionTask((txn_idx, incarnation));
+                }
+                // `try_incarnate` already released the task.
+                return SchedulerTask::NoTask;
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        SchedulerTask::NoTask
+    }
+
+    fn resume_dependencies(&self, deps: Vec<Version>) {
+        if let Some(min_dep) = deps.iter().min().copied() {
+            for dep in deps {
+                self.set_ready_status(dep);
+            }
+            self.decrease_execution_idx(min_dep);
+        }
+    }
+
+    fn set_ready_status(&self, txn_idx: Version) {
+        let mut status = self.txn_status[txn_idx].lock().unwrap();
+        if let TxnStatus::Aborting(incarnation) = *status {
+            *status = TxnStatus::ReadyToExecute(incarnation + 1);
+        }
+    }
+
+    fn decrease_execution_idx(&self, target_idx: Version) {
+        self.execution_idx.fetch_min(target_idx, Ordering::SeqCst);
+        self.decrease_cnt.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn decrease_validation_idx(&self, target_idx: Version) {
+        self.validation_idx.fetch_min(target_idx, Ordering::SeqCst);
+        self.decrease_cnt.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn check_done(&self) {
+        let observed_cnt = self.decrease_cnt.load(Ordering::SeqCst);
+        let execution_idx = self.execution_idx.load(Ordering::SeqCst);
+        let validation_idx = self.validation_idx.load(Ordering::SeqCst);
+        if min(execution_idx, validation_idx) >= self.num_txns
+            && self.num_active_tasks.load(Ordering::SeqCst) == 0
+            && observed_cnt == self.decrease_cnt.load(Ordering::SeqCst)
+        {
+            self.done_marker.store(true, Ordering::Release);
+        }
+    }
+
+    fn try_incarnate(&self, txn_idx: Version) -> Option<Incarnation> {
+        if txn_idx < self.num_txns {
+            let mut status = self.txn_status[txn_idx].lock().unwrap();
+            if let TxnStatus::ReadyToExecute(incarnation) = *status {
+                *status = TxnStatus::Executing(incarnation);
+                return Some(incarnation);
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        None
+    }
+
+    fn next_version_to_execute(&self) -> Option<TxnVersion> {
+        if self.execution_idx.load(Ordering::SeqCst) >= self.num_txns {
+            self.check_done();
+            return None;
+        }
+        self.num_active_tasks.fetch_add(1, Ordering::SeqCst);
+        let txn_idx = self.execution_idx.fetch_add(1, Ordering::SeqCst);
+        self.try_incarnate(txn_idx)
+            .map(|incarnation| (txn_idx, incarnation))
+    }
+
+    fn next_version_to_validate(&self) -> Option<TxnVersion> {
+        if self.validation_idx.load(Ordering::SeqCst) >= self.num_txns {
+            self.check_done();
+            return None;
+        }
+        self.num_active_tasks.fetch_add(1, Ordering::SeqCst);
+        let txn_idx = self.validation_idx.fetch_add(1, Ordering::SeqCst);
+        if txn_idx < self.num_txns {
+            if let TxnStatus::Executed(incarnation) = *self.txn_status[txn_idx].lock().unwrap() {
+                return Some((txn_idx, incarnation));
+            }
+        }
+        self.num_active_tasks.fetch_sub(1, Ordering::SeqCst);
+        None
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::{
     executor::ParallelTransactionExecutor,
+    optimistic_executor::OptimisticTransactionExecutor,
     proptest_types::types::{
         ExpectedOutput, ImpreciseInferencer, Inferencer, Task, Transaction, TransactionGen,
     },
@@ -10,12 +11,21 @@ use crate::{
 use proptest::{collection::vec, prelude::*, sample::Index, strategy::Strategy};
 use std::{fmt::Debug, hash::Hash};
 
+enum ExecutorMode {
+    /// Inference based executor, with the full read set exposed by the inferencer.
+    PreciseInference,
+    /// Inference based executor, with reads missing from the inferred read set.
+    ImpreciseInference,
+    /// Optimistic executor, which doesn't use an inferencer at all.
+    Optimistic,
+}
+
 fn run_transactions<K, V>(
     key_universe: Vec<K>,
     transaction_gens: Vec<TransactionGen<V>>,
     abort_transactions: Vec<Index>,
     skip_rest_transactions: Vec<Index>,
-    imprecise_read: bool,
+    mode: ExecutorMode,
 ) -> bool
 where
     K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + 'static,
@@ -38,16 +48,23 @@ where
 
     let baseline = ExpectedOutput::generate_baseline(&transactions);
 
-    let output = if imprecise_read {
-        ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, ImpreciseInferencer<K, V>>::new(
-            ImpreciseInferencer::new(),
-        )
+    let output = match mode {
+        ExecutorMode::PreciseInference => {
+            ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, Inferencer<K, V>>::new(
+                Inferencer::new(),
+            )
             .execute_transactions_parallel((), transactions)
-    } else {
-        ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, Inferencer<K, V>>::new(
-            Inferencer::new(),
-        )
-        .execute_transactions_parallel((), transactions)
+        }
+        ExecutorMode::ImpreciseInference => ParallelTransactionExecutor::<
+            Transaction<K, V>,
+            Task<K, V>,
+            ImpreciseInferencer<K, V>,
+        >::new(ImpreciseInferencer::new())
+        .execute_transactions_parallel((), transactions),
+        ExecutorMode::Optimistic => {
+            OptimisticTransactionExecutor::<Transaction<K, V>, Task<K, V>>::new()
+                .execute_transactions_parallel((), transactions)
+        }
     };
 
     baseline.check_output(&output)
@@ -62,7 +79,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 0),
         skip_rest_transactions in vec(any::<Index>(), 0),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::PreciseInference));
     }
 
     #[test]
@@ -72,7 +89,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 0),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::PreciseInference));
     }
 
     #[test]
@@ -82,7 +99,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 0),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::PreciseInference));
     }
 
 
@@ -93,7 +110,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::PreciseInference));
     }
 
     #[test]
@@ -103,6 +120,36 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, true));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::ImpreciseInference));
+    }
+
+    #[test]
+    fn optimistic_no_early_termination(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 5000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 0),
+        skip_rest_transactions in vec(any::<Index>(), 0),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Optimistic));
+    }
+
+    #[test]
+    fn optimistic_mixed_transactions(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 5000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 5),
+        skip_rest_transactions in vec(any::<Index>(), 5),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Optimistic));
+    }
+
+    #[test]
+    fn optimistic_high_contention(
+        universe in vec(any::<[u8; 32]>(), 10),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 3000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 0),
+        skip_rest_transactions in vec(any::<Index>(), 0),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Optimistic));
     }
 }
@@ -58,7 +58,7 @@ pub trait VMAdapter {
     fn run_prologue<S: MoveResolver>(
         &self,
         session: &mut Session<S>,
-        transaction: &SignatureCheckedTransaction,
+        transaction: &SignedTransaction,
         log_context: &AdapterLogSchema,
     ) -> Result<(), VMStatus>;
 
@@ -141,10 +141,22 @@ fn get_account_role<S: StateView>(
 
 pub(crate) fn validate_signature_checked_transaction<S: MoveResolver, A: VMAdapter>(
     adapter: &A,
-    mut session: &mut Session<S>,
+    session: &mut Session<S>,
     transaction: &SignatureCheckedTransaction,
     allow_too_new: bool,
     log_context: &AdapterLogSchema,
+) -> Result<(), VMStatus> {
+    validate_transaction(adapter, session, transaction, allow_too_new, log_context)
+}
+
+/// Validates the transaction whether its signature was checked or not. Only the simulations, whose
+/// output is never committed, get to validate transactions without checking their signature.
+pub(crate) fn validate_transaction<S: MoveResolver, A: VMAdapter>(
+    adapter: &A,
+    mut session: &mut Session<S>,
+    transaction: &SignedTransaction,
+    allow_too_new: bool,
+    log_context: &AdapterLogSchema,
 ) -> Result<(), VMStatus> {
     adapter.check_transaction_format(transaction)?;
 
@@ -5,9 +5,10 @@ use crate::{
     adapter_common,
     adapter_common::{
         discard_error_output, discard_error_vm_status, validate_signature_checked_transaction,
-        validate_signed_transaction, PreprocessedTransaction, VMAdapter,
+        validate_signed_transaction, validate_transaction, PreprocessedTransaction, VMAdapter,
     },
     counters::*,
+    create_access_path,
     data_cache::StateViewCache,
     diem_vm_impl::{
         charge_global_write_gas_usage, convert_changeset_and_events, get_currency_info,
@@ -15,42 +16,51 @@ use crate::{
     },
     errors::expect_only_successful_execution,
     logging::AdapterLogSchema,
+    parallel_executor::ParallelDiemVM,
     script_to_script_function,
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
     VMExecutor, VMValidator,
 };
 use anyhow::Result;
+use diem_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, ED25519_PRIVATE_KEY_LENGTH};
 use diem_logger::prelude::*;
 use diem_state_view::StateView;
 use diem_types::{
-    account_config,
+    access_path::AccessPath,
+    account_config::{self, AccountResource},
     block_metadata::BlockMetadata,
-    on_chain_config::{DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3},
+    on_chain_config::{
+        DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_5,
+    },
     transaction::{
-        ChangeSet, Module, SignatureCheckedTransaction, SignedTransaction, Transaction,
-        TransactionOutput, TransactionPayload, TransactionStatus, VMValidatorResult,
-        WriteSetPayload,
+        authenticator::AuthenticationKey, ChangeSet, Module, RawTransaction,
+        SignatureCheckedTransaction, SignedTransaction, Transaction, TransactionOutput,
+        TransactionPayload, TransactionStatus, VMValidatorResult, WriteSetPayload,
     },
     vm_status::{KeptVMStatus, StatusCode, VMStatus},
-    write_set::{WriteSet, WriteSetMut},
+    write_set::{WriteOp, WriteSet, WriteSetMut},
 };
 use fail::fail_point;
 use move_core_types::{
     account_address::AccountAddress,
     gas_schedule::GasAlgebra,
     identifier::IdentStr,
+    move_resource::MoveStructType,
     resolver::MoveResolver,
     transaction_argument::convert_txn_args,
     value::{serialize_values, MoveValue},
 };
 use move_vm_runtime::session::Session;
 use move_vm_types::gas_schedule::GasStatus;
+use once_cell::sync::OnceCell;
 use std::{
     collections::HashSet,
-    convert::{AsMut, AsRef},
+    convert::{AsMut, AsRef, TryFrom},
 };
 
+static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
+
 #[derive(Clone)]
 pub struct DiemVM(pub(crate) DiemVMImpl);
 
@@ -59,6 +69,20 @@ impl DiemVM {
         Self(DiemVMImpl::new(state))
     }
 
+    /// Sets the number of threads used to execute blocks. A level above 1 enables optimistic
+    /// parallel execution. Only the first call has an effect.
+    pub fn set_concurrency_level_once(concurrency_level: usize) {
+        EXECUTION_CONCURRENCY_LEVEL.get_or_init(|| concurrency_level);
+    }
+
+    /// Returns the number of threads used to execute blocks, 1 unless set otherwise.
+    pub fn get_concurrency_level() -> usize {
+        match EXECUTION_CONCURRENCY_LEVEL.get() {
+            Some(concurrency_level) => *concurrency_level,
+            None => 1,
+        }
+    }
+
     pub fn new_for_validation<S: StateView>(state: &S) -> Self {
         info!(
             AdapterLogSchema::new(state.id(), 0),
@@ -312,6 +336,17 @@ impl DiemVM {
         storage: &S,
         txn: &SignatureCheckedTransaction,
         log_context: &AdapterLogSchema,
+    ) -> (VMStatus, TransactionOutput) {
+        self.execute_user_transaction_impl(storage, txn, log_context)
+    }
+
+    /// Executes a user transaction whether its signature was checked or not, see
+    /// `validate_transaction`.
+    fn execute_user_transaction_impl<S: MoveResolver>(
+        &self,
+        storage: &S,
+        txn: &SignedTransaction,
+        log_context: &AdapterLogSchema,
     ) -> (VMStatus, TransactionOutput) {
         macro_rules! unwrap_or_discard {
             ($res: expr) => {
@@ -335,13 +370,9 @@ impl DiemVM {
 
         // Revalidate the transaction.
         let mut session = self.0.new_session(storage);
-        if let Err(err) = validate_signature_checked_transaction::<S, Self>(
-            self,
-            &mut session,
-            txn,
-            false,
-            log_context,
-        ) {
+        if let Err(err) =
+            validate_transaction::<S, Self>(self, &mut session, txn, false, log_context)
+        {
             return discard_error_vm_status(err);
         };
 
@@ -509,26 +540,31 @@ impl DiemVM {
         let mut gas_status = GasStatus::new_unmetered();
         let mut session = self.0.new_session(storage);
 
-        let (round, timestamp, previous_vote, proposer) = block_metadata.into_inner();
-        let args = serialize_values(&vec![
+        let (round, timestamp, previous_vote, proposer, failed_proposers) =
+            block_metadata.into_inner();
+        let mut args = vec![
             MoveValue::Signer(txn_data.sender),
             MoveValue::U64(round),
             MoveValue::U64(timestamp),
             MoveValue::Vector(previous_vote.into_iter().map(MoveValue::Address).collect()),
             MoveValue::Address(proposer),
-        ]);
+        ];
+        let prologue = if self.0.get_diem_version()? >= DIEM_VERSION_5 {
+            args.push(MoveValue::Vector(
+                failed_proposers
+                    .into_iter()
+                    .map(MoveValue::Address)
+                    .collect(),
+            ));
+            BLOCK_PROLOGUE_V2
+        } else {
+            BLOCK_PROLOGUE
+        };
+        let args = serialize_values(&args);
         session
-            .execute_function(
-                &DIEM_BLOCK_MODULE,
-                BLOCK_PROLOGUE,
-                vec![],
-                args,
-                &mut gas_status,
-            )
+            .execute_function(&DIEM_BLOCK_MODULE, prologue, vec![], args, &mut gas_status)
             .map(|_return_vals| ())
-            .or_else(|e| {
-                expect_only_successful_execution(e, BLOCK_PROLOGUE.as_str(), log_context)
-            })?;
+            .or_else(|e| expect_only_successful_execution(e, prologue.as_str(), log_context))?;
         SYSTEM_TRANSACTIONS_EXECUTED.inc();
 
         let output = get_transaction_output(
@@ -684,6 +720,59 @@ impl DiemVM {
         ))
     }
 
+    /// Executes `txn` against `state_view` without verifying its signature. The output is never
+    /// committed, it shows the outcome and gas cost the transaction would have if it were
+    /// submitted on top of `state_view`.
+    pub fn simulate_signed_transaction(
+        txn: SignedTransaction,
+        state_view: &impl StateView,
+    ) -> (VMStatus, TransactionOutput) {
+        let data_cache = StateViewCache::new(state_view);
+        let vm = DiemVM::new(&data_cache);
+        let log_context = AdapterLogSchema::new(data_cache.id(), 0);
+        vm.execute_user_transaction_impl(&data_cache, &txn, &log_context)
+    }
+
+    /// Executes the unsigned `raw_txn` against `state_view` as if its sender had signed it. The
+    /// sender is authenticated by a simulation key for the duration of the execution, and its own
+    /// authentication key is put back in the output, which is never committed either.
+    pub fn simulate_unsigned_transaction(
+        raw_txn: RawTransaction,
+        state_view: &impl StateView,
+    ) -> (VMStatus, TransactionOutput) {
+        let private_key = Ed25519PrivateKey::try_from(&[1u8; ED25519_PRIVATE_KEY_LENGTH][..])
+            .expect("Unable to create the simulation key");
+        let public_key = Ed25519PublicKey::from(&private_key);
+        let simulation_auth_key = AuthenticationKey::ed25519(&public_key).to_vec();
+
+        let mut data_cache = StateViewCache::new(state_view);
+        let account_path = create_access_path(raw_txn.sender(), AccountResource::struct_tag());
+        let sender_auth_key = match data_cache.get(&account_path) {
+            Ok(Some(blob)) => bcs::from_bytes::<AccountResource>(&blob).ok(),
+            _ => None,
+        }
+        .map(|account| {
+            let auth_key = account.authentication_key().to_vec();
+            let simulation_account = account.with_authentication_key(simulation_auth_key.clone());
+            data_cache.push_write_set(&account_write_set(&account_path, &simulation_account));
+            auth_key
+        });
+
+        let txn = raw_txn
+            .sign(&private_key, public_key)
+            .expect("Unable to sign the simulated transaction");
+        let vm = DiemVM::new(&data_cache);
+        let log_context = AdapterLogSchema::new(data_cache.id(), 0);
+        let (vm_status, output) = vm.execute_user_transaction(&data_cache, &txn, &log_context);
+        match sender_auth_key {
+            Some(auth_key) => (
+                vm_status,
+                restore_authentication_key(output, &account_path, &simulation_auth_key, auth_key),
+            ),
+            None => (vm_status, output),
+        }
+    }
+
     /// Alternate form of 'execute_block' that keeps the vm_status before it goes into the
     /// `TransactionOutput`
     pub fn execute_block_and_keep_vm_status(
@@ -717,7 +806,12 @@ impl VMExecutor for DiemVM {
             ))
         });
 
-        let output = Self::execute_block_and_keep_vm_status(transactions, state_view)?;
+        let concurrency_level = Self::get_concurrency_level();
+        let output = if concurrency_level > 1 {
+            ParallelDiemVM::execute_block(transactions, state_view, concurrency_level)?
+        } else {
+            Self::execute_block_and_keep_vm_status(transactions, state_view)?
+        };
         Ok(output
             .into_iter()
             .map(|(_vm_status, txn_output)| txn_output)
@@ -789,7 +883,7 @@ impl VMAdapter for DiemVM {
     fn run_prologue<S: MoveResolver>(
         &self,
         session: &mut Session<S>,
-        transaction: &SignatureCheckedTransaction,
+        transaction: &SignedTransaction,
         log_context: &AdapterLogSchema,
     ) -> Result<(), VMStatus> {
         let currency_code = get_gas_currency_code(transaction)?;
@@ -889,3 +983,56 @@ impl AsMut<DiemVMImpl> for DiemVM {
         &mut self.0
     }
 }
+
+fn account_write_set(account_path: &AccessPath, account: &AccountResource) -> WriteSet {
+    WriteSetMut::new(vec![(account_path.clone(), account_write_op(account))])
+        .freeze()
+        .expect("A single write should form a valid write set")
+}
+
+fn account_write_op(account: &AccountResource) -> WriteOp {
+    WriteOp::Value(bcs::to_bytes(account).expect("Unable to serialize AccountResource"))
+}
+
+/// Puts the authentication key of the sender of a simulated transaction back in its account, unless
+/// the transaction rotated the simulation key.
+fn restore_authentication_key(
+    output: TransactionOutput,
+    account_path: &AccessPath,
+    simulation_auth_key: &[u8],
+    auth_key: Vec<u8>,
+) -> TransactionOutput {
+    let account = output
+        .write_set()
+        .iter()
+        .find(|(path, _)| path == account_path)
+        .and_then(|(_, op)| match op {
+            WriteOp::Value(blob) => bcs::from_bytes::<AccountResource>(blob).ok(),
+            WriteOp::Deletion => None,
+        });
+    let restored_op = match account {
+        Some(account) if account.authentication_key() == simulation_auth_key => {
+            account_write_op(&account.with_authentication_key(auth_key))
+        }
+        _ => return output,
+    };
+    let write_set = output
+        .write_set()
+        .iter()
+        .map(|(path, op)| {
+            if path == account_path {
+                (path.clone(), restored_op.clone())
+            } else {
+                (path.clone(), op.clone())
+            }
+        })
+        .collect::<WriteSetMut>()
+        .freeze()
+        .expect("Replacing a write should keep the write set valid");
+    TransactionOutput::new(
+        write_set,
+        output.events().to_vec(),
+        output.gas_used(),
+        output.status().clone(),
+    )
+}
@@ -0,0 +1,127 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+mod storage_wrapper;
+mod vm_wrapper;
+
+use crate::{
+    adapter_common::{preprocess_transaction, PreprocessedTransaction},
+    counters::BLOCK_TRANSACTION_COUNT,
+    diem_vm::DiemVM,
+    logging::AdapterLogSchema,
+    parallel_executor::vm_wrapper::DiemVMWrapper,
+};
+use diem_logger::prelude::*;
+use diem_parallel_executor::{
+    errors::Error,
+    optimistic_executor::OptimisticTransactionExecutor,
+    task::{Transaction as PTransaction, TransactionOutput as PTransactionOutput},
+};
+use diem_state_view::StateView;
+use diem_types::{
+    access_path::AccessPath,
+    transaction::{Transaction, TransactionOutput, TransactionPayload, TransactionStatus},
+    vm_status::{StatusCode, VMStatus},
+    write_set::{WriteOp, WriteSet},
+};
+use rayon::prelude::*;
+
+impl PTransaction for PreprocessedTransaction {
+    type Key = AccessPath;
+    type Value = WriteOp;
+}
+
+/// The output of a transaction executed by the parallel executor, keeping the VM status around
+/// like `DiemVM::execute_block_and_keep_vm_status` does.
+pub(crate) struct DiemTransactionOutput {
+    vm_status: VMStatus,
+    output: TransactionOutput,
+}
+
+impl DiemTransactionOutput {
+    pub fn new(vm_status: VMStatus, output: TransactionOutput) -> Self {
+        Self { vm_status, output }
+    }
+
+    pub fn into_inner(self) -> (VMStatus, TransactionOutput) {
+        (self.vm_status, self.output)
+    }
+}
+
+impl PTransactionOutput for DiemTransactionOutput {
+    type T = PreprocessedTransaction;
+
+    fn get_writes(&self) -> Vec<(AccessPath, WriteOp)> {
+        self.output
+            .write_set()
+            .iter()
+            .map(|(ap, write_op)| (ap.clone(), write_op.clone()))
+            .collect()
+    }
+
+    /// Execution output for transactions that comes after SkipRest signal.
+    fn skip_output() -> Self {
+        Self::new(
+            VMStatus::Error(StatusCode::UNKNOWN_STATUS),
+            TransactionOutput::new(WriteSet::default(), vec![], 0, TransactionStatus::Retry),
+        )
+    }
+}
+
+pub struct ParallelDiemVM();
+
+impl ParallelDiemVM {
+    /// Executes a block optimistically on `concurrency_level` threads. The outputs are identical
+    /// to the ones of `DiemVM::execute_block_and_keep_vm_status`.
+    pub fn execute_block<S: StateView>(
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        // Each thread loads and caches modules in its own VM, so blocks that might publish code
+        // are executed sequentially to not run transactions against stale modules.
+        if transactions.iter().any(may_publish_modules) {
+            return DiemVM::execute_block_and_keep_vm_status(transactions, state_view);
+        }
+
+        let count = transactions.len();
+        info!(
+            AdapterLogSchema::new(state_view.id(), 0),
+            "Executing block in parallel, transaction count: {}", count
+        );
+
+        // Verify the signatures of all the transactions in parallel.
+        let signature_verified_block: Vec<PreprocessedTransaction> = transactions
+            .into_par_iter()
+            .map(preprocess_transaction::<DiemVM>)
+            .collect();
+
+        let executor: OptimisticTransactionExecutor<_, DiemVMWrapper<S>> =
+            OptimisticTransactionExecutor::with_concurrency_level(concurrency_level);
+        let result =
+            match executor.execute_transactions_parallel(state_view, signature_verified_block) {
+                Ok(results) => Ok(results
+                    .into_iter()
+                    .map(DiemTransactionOutput::into_inner)
+                    .collect()),
+                Err(Error::UserError(err)) => Err(err),
+                Err(_) => Err(VMStatus::Error(
+                    StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+                )),
+            };
+        // Record the histogram count for transactions per block.
+        BLOCK_TRANSACTION_COUNT.observe(count as f64);
+        result
+    }
+}
+
+fn may_publish_modules(txn: &Transaction) -> bool {
+    match txn {
+        Transaction::UserTransaction(txn) => matches!(
+            txn.payload(),
+            TransactionPayload::Module(_) | TransactionPayload::WriteSet(_)
+        ),
+        Transaction::GenesisTransaction(_) => true,
+        Transaction::BlockMetadata(_) => false,
+    }
+}
@@ -24,3 +24,4 @@ pub const WRITESET_PROLOGUE_NAME: &IdentStr = ident_str!("writeset_prologue");
 pub const WRITESET_EPILOGUE_NAME: &IdentStr = ident_str!("writeset_epilogue");
 pub const USER_EPILOGUE_NAME: &IdentStr = ident_str!("epilogue");
 pub const BLOCK_PROLOGUE: &IdentStr = ident_str!("block_prologue");
+pub const BLOCK_PROLOGUE_V2: &IdentStr = ident_str!("block_prologue_v2");
@@ -0,0 +1,253 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs the Move compiler over a package and collects everything the server needs to answer
+//! requests about the files in that package.
+
+use crate::{
+    symbols::{self, Symbols},
+    utils::FileOffsets,
+};
+use anyhow::Result;
+use lsp_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, Url};
+use move_lang::{
+    diagnostics::Diagnostics,
+    parser::ast as P,
+    shared::{Flags, NumberFormat, NumericalAddress},
+    Compiler, PASS_PARSER, PASS_TYPING,
+};
+use move_package::{source_package::layout::SourcePackageLayout, BuildConfig};
+use move_symbol_pool::Symbol;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+/// The results of compiling a package
+#[derive(Debug, Default, Clone)]
+pub struct Analysis {
+    pub offsets: FileOffsets,
+    pub diagnostics: BTreeMap<Symbol, Vec<Diagnostic>>,
+    /// `None` if the package did not get through type checking
+    pub symbols: Option<Symbols>,
+    /// The files that were compiled from unsaved contents, mapping each of them to the file that
+    /// is being edited. Locations in `offsets` and `symbols` refer to the compiled files.
+    pub shadowed_files: BTreeMap<Symbol, Symbol>,
+}
+
+/// Returns the root of the package containing `path`, i.e. the closest ancestor with a manifest
+pub fn find_package_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(SourcePackageLayout::Manifest.path()).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Returns the file that the client knows a compiled file by, given the files shadowed by an
+/// analysis
+pub fn original_file(shadowed_files: &BTreeMap<Symbol, Symbol>, file: Symbol) -> Symbol {
+    shadowed_files.get(&file).copied().unwrap_or(file)
+}
+
+/// Compiles the package rooted at `package_root`, along with its dependencies, through the typing
+/// pass to compute symbols, and on to bytecode generation to collect all diagnostics. Files that
+/// have an entry in `overlays` are compiled from those contents instead of what is on disk.
+pub fn analyze_package(
+    package_root: &Path,
+    overlays: &BTreeMap<PathBuf, String>,
+) -> Result<Analysis> {
+    let build_config = BuildConfig {
+        dev_mode: true,
+        test_mode: true,
+        generate_docs: false,
+        generate_abis: false,
+    };
+    let resolution_graph = build_config.resolution_graph_for_package(package_root)?;
+
+    // Targets are all files in the root package, dependencies all files in any other package
+    let root_name = &resolution_graph.root_package.package.name;
+    let root_package = resolution_graph.get_package(root_name);
+    let targets: Vec<_> = root_package
+        .get_sources(&resolution_graph.build_options)?
+        .into_iter()
+        .map(|symbol| symbol.to_string())
+        .collect();
+    let mut deps = vec![];
+    for (name, package) in &resolution_graph.package_table {
+        if name != root_name {
+            deps.extend(
+                package
+                    .get_sources(&resolution_graph.build_options)?
+                    .into_iter()
+                    .map(|symbol| symbol.to_string()),
+            )
+        }
+    }
+    let named_addresses: BTreeMap<_, _> = root_package
+        .resolution_table
+        .iter()
+        .map(|(ident, addr)| {
+            let addr = NumericalAddress::new(addr.into_bytes(), NumberFormat::Hex);
+            (ident.to_string(), addr)
+        })
+        .collect();
+
+    // The compiler only reads from the file system, so unsaved contents are written to files of
+    // the same name in a temporary directory, which are compiled in place of the edited ones
+    let shadow_dir = tempfile::tempdir()?;
+    let mut shadowed_files = BTreeMap::new();
+    let targets = shadow_files(&shadow_dir, overlays, targets, &mut shadowed_files)?;
+    let deps = shadow_files(&shadow_dir, overlays, deps, &mut shadowed_files)?;
+
+    // Test-only code is analyzed as well, unless the package can't be compiled in test mode for
+    // lack of the UnitTest module of the standard library among its dependencies
+    let (files, res) = Compiler::new(&targets, &deps)
+        .set_flags(Flags::testing())
+        .set_named_address_values(named_addresses.clone())
+        .run::<PASS_PARSER>()?;
+    let res = match res {
+        Err(diags) => Err(diags),
+        Ok((_comments, stepped)) => {
+            let (empty_compiler, parsed_program) = stepped.into_ast();
+            if has_unit_test_module(&parsed_program) {
+                empty_compiler
+                    .at_parser(parsed_program)
+                    .run::<PASS_TYPING>()
+            } else {
+                Compiler::new(&targets, &deps)
+                    .set_flags(Flags::empty())
+                    .set_named_address_values(named_addresses)
+                    .run::<PASS_TYPING>()?
+                    .1
+                    .map(|(_comments, stepped)| stepped)
+            }
+        }
+    };
+    let (symbols, diags) = match res {
+        Err(diags) => (None, diags),
+        Ok(stepped) => {
+            let (empty_compiler, typed_program) = stepped.into_ast();
+            let symbols = symbols::symbolicate(&typed_program);
+            let diags = match empty_compiler.at_typing(typed_program).build() {
+                Ok((_units, warnings)) => warnings,
+                Err(diags) => diags,
+            };
+            (Some(symbols), diags)
+        }
+    };
+
+    let offsets = FileOffsets::new(&files);
+    let mut diagnostics: BTreeMap<Symbol, Vec<Diagnostic>> = BTreeMap::new();
+    // Files without diagnostics are still reported so that stale diagnostics get cleared
+    for fname in files.keys() {
+        diagnostics.insert(*fname, vec![]);
+    }
+    for (file, diag) in lsp_diagnostics(&offsets, &shadowed_files, diags) {
+        diagnostics.entry(file).or_insert_with(Vec::new).push(diag)
+    }
+    let diagnostics = diagnostics
+        .into_iter()
+        .map(|(file, diags)| (original_file(&shadowed_files, file), diags))
+        .collect();
+    Ok(Analysis {
+        offsets,
+        diagnostics,
+        symbols,
+        shadowed_files,
+    })
+}
+
+/// Replaces every file that has an overlay with a file in `shadow_dir` holding the overlay's
+/// contents, recording the replacements in `shadowed_files`
+fn shadow_files(
+    shadow_dir: &TempDir,
+    overlays: &BTreeMap<PathBuf, String>,
+    files: Vec<String>,
+    shadowed_files: &mut BTreeMap<Symbol, Symbol>,
+) -> Result<Vec<String>> {
+    files
+        .into_iter()
+        .map(|file| {
+            let contents = match overlays.get(Path::new(&file)) {
+                Some(contents) => contents,
+                None => return Ok(file),
+            };
+            // Every shadowed file gets its own directory as different packages may have source
+            // files of the same name
+            let dir = shadow_dir.path().join(shadowed_files.len().to_string());
+            fs::create_dir(&dir)?;
+            let shadow = dir.join(Path::new(&file).file_name().unwrap_or_default());
+            fs::write(&shadow, contents)?;
+            let shadow = shadow.to_string_lossy().to_string();
+            shadowed_files.insert(Symbol::from(shadow.as_str()), Symbol::from(file));
+            Ok(shadow)
+        })
+        .collect()
+}
+
+/// Returns whether the program contains the `Std::UnitTest` module, which compiling in test mode
+/// requires
+fn has_unit_test_module(program: &P::Program) -> bool {
+    program
+        .lib_definitions
+        .iter()
+        .chain(&program.source_definitions)
+        .any(|def| match def {
+            P::Definition::Module(mdef) => {
+                mdef.name.0.value.as_str() == "UnitTest"
+                    && matches!(
+                        mdef.address.as_ref().map(|addr| &addr.value),
+                        Some(P::LeadingNameAccess_::Name(name)) if name.value.as_str() == "Std"
+                    )
+            }
+            _ => false,
+        })
+}
+
+fn lsp_diagnostics(
+    offsets: &FileOffsets,
+    shadowed_files: &BTreeMap<Symbol, Symbol>,
+    diags: Diagnostics,
+) -> Vec<(Symbol, Diagnostic)> {
+    use codespan_reporting::diagnostic::Severity as CSRSeverity;
+    let mut result = vec![];
+    for (severity, message, (loc, label), secondary_labels) in diags.into_codespan_format() {
+        let range = match offsets.loc_to_range(loc) {
+            Some(range) => range,
+            None => continue,
+        };
+        let severity = match severity {
+            CSRSeverity::Bug | CSRSeverity::Error => DiagnosticSeverity::Error,
+            CSRSeverity::Warning => DiagnosticSeverity::Warning,
+            CSRSeverity::Note => DiagnosticSeverity::Information,
+            CSRSeverity::Help => DiagnosticSeverity::Hint,
+        };
+        let related_information = secondary_labels
+            .into_iter()
+            .filter_map(|(loc, msg)| {
+                let uri = file_url(original_file(shadowed_files, loc.file()))?;
+                let range = offsets.loc_to_range(loc)?;
+                Some(DiagnosticRelatedInformation {
+                    location: Location::new(uri, range),
+                    message: msg,
+                })
+            })
+            .collect::<Vec<_>>();
+        let diag = Diagnostic::new(
+            range,
+            Some(severity),
+            None,
+            Some("move-analyzer".to_string()),
+            format!("{}: {}", message, label),
+            Some(related_information),
+            None,
+        );
+        result.push((loc.file(), diag))
+    }
+    result
+}
+
+pub fn file_url(file: Symbol) -> Option<Url> {
+    Url::from_file_path(file.as_str()).ok()
+}
@@ -0,0 +1,283 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The state of the language server and the handlers for the messages it receives.
+
+use crate::{
+    analysis::{self, file_url, original_file, Analysis},
+    symbols::{DefKind, IdentInfo, OutlineItem, Symbols},
+    utils::FileOffsets,
+};
+use lsp_server::{ErrorCode, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Notification as _, PublishDiagnostics, ShowMessage,
+    },
+    request::{DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _},
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    LanguageString, Location, MarkedString, MessageType, Position, PublishDiagnosticsParams,
+    ShowMessageParams, SymbolKind, TextDocumentPositionParams, Url,
+};
+use move_symbol_pool::Symbol;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// The latest successful symbolication of a package, along with the file contents it refers to
+struct PackageSymbols {
+    symbols: Symbols,
+    offsets: FileOffsets,
+    /// The files compiled from unsaved contents, mapped to the files being edited
+    shadowed_files: BTreeMap<Symbol, Symbol>,
+}
+
+#[derive(Default)]
+pub struct Context {
+    /// Packages that have at least one open file, keyed by package root
+    packages: BTreeMap<PathBuf, Option<PackageSymbols>>,
+    /// The contents of open files that have been edited since they were last saved
+    overlays: BTreeMap<PathBuf, String>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a request from the client, returning the response to send back
+    pub fn on_request(&mut self, request: Request) -> Response {
+        let Request { id, method, params } = request;
+        match method.as_str() {
+            GotoDefinition::METHOD => {
+                self.respond(id, params, |ctx, params: GotoDefinitionParams| {
+                    ctx.definition(&params.text_document_position_params)
+                })
+            }
+            HoverRequest::METHOD => self.respond(id, params, |ctx, params: HoverParams| {
+                ctx.hover(&params.text_document_position_params)
+            }),
+            DocumentSymbolRequest::METHOD => {
+                self.respond(id, params, |ctx, params: DocumentSymbolParams| {
+                    ctx.document_symbols(&params.text_document.uri)
+                })
+            }
+            _ => Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request: {}", method),
+            ),
+        }
+    }
+
+    /// Handles a notification from the client, returning the notifications to send back
+    pub fn on_notification(&mut self, notification: Notification) -> Vec<Notification> {
+        let Notification { method, params } = notification;
+        let path = match method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                match serde_json::from_value::<DidOpenTextDocumentParams>(params) {
+                    Ok(params) => params.text_document.uri.to_file_path().ok(),
+                    Err(_) => return vec![],
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                match serde_json::from_value::<DidChangeTextDocumentParams>(params) {
+                    Ok(params) => {
+                        let path = params.text_document.uri.to_file_path().ok();
+                        // With full document sync every change carries the whole text
+                        if let (Some(path), Some(change)) = (&path, params.content_changes.last()) {
+                            self.overlays.insert(path.clone(), change.text.clone());
+                        }
+                        path
+                    }
+                    Err(_) => return vec![],
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                match serde_json::from_value::<DidSaveTextDocumentParams>(params) {
+                    Ok(params) => {
+                        let path = params.text_document.uri.to_file_path().ok();
+                        if let Some(path) = &path {
+                            self.overlays.remove(path);
+                        }
+                        path
+                    }
+                    Err(_) => return vec![],
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                match serde_json::from_value::<DidCloseTextDocumentParams>(params) {
+                    // Unsaved changes are discarded on close, so the package only needs to be
+                    // analyzed again if the file had any
+                    Ok(params) => match params.text_document.uri.to_file_path() {
+                        Ok(path) if self.overlays.remove(&path).is_some() => Some(path),
+                        _ => return vec![],
+                    },
+                    Err(_) => return vec![],
+                }
+            }
+            _ => return vec![],
+        };
+        let package_root = match path.and_then(|path| analysis::find_package_root(&path)) {
+            Some(root) => root,
+            None => return vec![],
+        };
+        // Opening another file of a package that has already been analyzed needs no new analysis
+        if method == DidOpenTextDocument::METHOD && self.packages.contains_key(&package_root) {
+            return vec![];
+        }
+        self.analyze(package_root)
+    }
+
+    fn analyze(&mut self, package_root: PathBuf) -> Vec<Notification> {
+        let Analysis {
+            offsets,
+            diagnostics,
+            symbols,
+            shadowed_files,
+        } = match analysis::analyze_package(&package_root, &self.overlays) {
+            Ok(analysis) => analysis,
+            Err(err) => {
+                let message = format!(
+                    "Failed to analyze package at {}: {:#}",
+                    package_root.display(),
+                    err
+                );
+                return vec![Notification::new(
+                    ShowMessage::METHOD.to_string(),
+                    ShowMessageParams {
+                        typ: MessageType::Error,
+                        message,
+                    },
+                )];
+            }
+        };
+
+        let package = self.packages.entry(package_root).or_insert(None);
+        // Keep serving the last symbols that type checked while the package has errors
+        if let Some(symbols) = symbols {
+            *package = Some(PackageSymbols {
+                symbols,
+                offsets,
+                shadowed_files,
+            });
+        }
+
+        diagnostics
+            .into_iter()
+            .filter_map(|(file, diagnostics)| {
+                let uri = file_url(file)?;
+                Some(Notification::new(
+                    PublishDiagnostics::METHOD.to_string(),
+                    PublishDiagnosticsParams::new(uri, diagnostics, None),
+                ))
+            })
+            .collect()
+    }
+
+    //**********************************************************************************************
+    // Requests
+    //**********************************************************************************************
+
+    fn respond<P, R>(
+        &self,
+        id: RequestId,
+        params: serde_json::Value,
+        handler: impl FnOnce(&Self, P) -> R,
+    ) -> Response
+    where
+        P: DeserializeOwned,
+        R: serde::Serialize,
+    {
+        match serde_json::from_value::<P>(params) {
+            Ok(params) => Response::new_ok(id, handler(self, params)),
+            Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    /// Returns the package containing the file at `uri`, along with the file as it was compiled
+    fn package_for(&self, uri: &Url) -> Option<(Symbol, &PackageSymbols)> {
+        let path = uri.to_file_path().ok()?;
+        let root = analysis::find_package_root(&path)?;
+        let package = self.packages.get(&root)?.as_ref()?;
+        let file = file_symbol(&path);
+        let file = package
+            .shadowed_files
+            .iter()
+            .find(|(_, original)| **original == file)
+            .map(|(shadow, _)| *shadow)
+            .unwrap_or(file);
+        Some((file, package))
+    }
+
+    fn definition(&self, params: &TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let (file, package) = self.package_for(&params.text_document.uri)?;
+        let ident = ident_at(package, file, params.position)?;
+        let uri = file_url(original_file(&package.shadowed_files, ident.def_loc.file()))?;
+        let range = package.offsets.loc_to_range(ident.def_loc)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(uri, range)))
+    }
+
+    fn hover(&self, params: &TextDocumentPositionParams) -> Option<Hover> {
+        let (file, package) = self.package_for(&params.text_document.uri)?;
+        let ident = ident_at(package, file, params.position)?;
+        Some(Hover {
+            contents: HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
+                language: "move".to_string(),
+                value: ident.hover.clone(),
+            })),
+            range: package.offsets.loc_to_range(ident.loc),
+        })
+    }
+
+    fn document_symbols(&self, uri: &Url) -> Option<DocumentSymbolResponse> {
+        let (file, package) = self.package_for(uri)?;
+        let symbols = package
+            .symbols
+            .outline(file)
+            .iter()
+            .filter_map(|item| document_symbol(&package.offsets, item))
+            .collect();
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+}
+
+fn file_symbol(path: &Path) -> Symbol {
+    Symbol::from(path.to_string_lossy().to_string())
+}
+
+fn ident_at(package: &PackageSymbols, file: Symbol, position: Position) -> Option<&IdentInfo> {
+    let offset = package.offsets.position_to_offset(file, position)?;
+    package.symbols.ident_at(file, offset)
+}
+
+#[allow(deprecated)]
+fn document_symbol(offsets: &FileOffsets, item: &OutlineItem) -> Option<DocumentSymbol> {
+    let range = offsets.loc_to_range(item.loc)?;
+    let kind = match item.kind {
+        DefKind::Module => SymbolKind::Module,
+        DefKind::Struct => SymbolKind::Struct,
+        DefKind::Field => SymbolKind::Field,
+        DefKind::Function => SymbolKind::Function,
+        DefKind::Constant => SymbolKind::Constant,
+    };
+    let children = item
+        .children
+        .iter()
+        .filter_map(|child| document_symbol(offsets, child))
+        .collect();
+    Some(DocumentSymbol {
+        name: item.name.clone(),
+        detail: Some(item.detail.clone()),
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: Some(children),
+    })
+}
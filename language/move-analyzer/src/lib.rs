@@ -1,2 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+#[macro_use(sp)]
+extern crate move_ir_types;
+
+pub mod analysis;
+pub mod context;
+pub mod symbols;
+pub mod utils;
@@ -1,6 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use lsp_server::{Connection, Message};
+use lsp_types::{
+    HoverProviderCapability, OneOf, SaveOptions, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextDocumentSyncOptions, TextDocumentSyncSaveOptions,
+};
+use move_analyzer::context::Context;
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -9,5 +15,61 @@ struct Options {}
 
 fn main() {
     Options::from_args();
-    todo!()
+
+    // The server communicates with the client over stdin/stdout. Packages are analyzed again
+    // whenever a file is opened, edited, saved or closed with unsaved changes.
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::Full),
+                will_save: None,
+                will_save_wait_until: None,
+                save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                    include_text: Some(false),
+                })),
+            },
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    })
+    .expect("server capabilities should serialize");
+    connection
+        .initialize(capabilities)
+        .expect("could not initialize the connection");
+
+    let mut context = Context::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection
+                    .handle_shutdown(&request)
+                    .expect("could not handle the shutdown request")
+                {
+                    break;
+                }
+                let response = context.on_request(request);
+                connection
+                    .sender
+                    .send(Message::Response(response))
+                    .expect("could not send a response");
+            }
+            Message::Notification(notification) => {
+                for outgoing in context.on_notification(notification) {
+                    connection
+                        .sender
+                        .send(Message::Notification(outgoing))
+                        .expect("could not send a notification");
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+    // The writer thread only exits once the connection, and with it the sending side of its
+    // channel, is dropped
+    drop(connection);
+    io_threads.join().expect("could not join the I/O threads");
 }
@@ -0,0 +1,711 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Computes, from a typed program, the information needed to answer go-to-definition, hover and
+//! document symbol requests.
+//!
+//! Every identifier occurrence that the server knows about (definitions as well as uses) is
+//! recorded as an `IdentInfo` pointing at the location of its definition, together with the text
+//! shown on hover. Requests are answered by looking up the narrowest occurrence enclosing the
+//! cursor.
+
+use move_ir_types::location::*;
+use move_lang::{
+    expansion::ast::{ModuleIdent, ModuleIdent_},
+    naming::ast::{
+        FunctionSignature, StructDefinition, StructFields, TParam, Type, TypeName_, Type_,
+    },
+    parser::ast::{ConstantName, FunctionName, StructName, Var, Visibility},
+    shared::{format_comma, unique_map::UniqueMap},
+    typing::ast::{
+        BuiltinFunction_, Constant, ExpListItem, Function, FunctionBody_, LValue, LValueList,
+        LValue_, ModuleCall, Program, Sequence, SequenceItem_, UnannotatedExp_,
+    },
+};
+use move_symbol_pool::Symbol;
+use std::collections::BTreeMap;
+
+/// An occurrence of an identifier in the source
+#[derive(Debug, Clone)]
+pub struct IdentInfo {
+    /// Location of the occurrence itself
+    pub loc: Loc,
+    /// Location of the definition the occurrence refers to
+    pub def_loc: Loc,
+    /// Text displayed when hovering over the occurrence
+    pub hover: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefKind {
+    Module,
+    Struct,
+    Field,
+    Function,
+    Constant,
+}
+
+/// A definition shown in the outline of a file
+#[derive(Debug, Clone)]
+pub struct OutlineItem {
+    pub name: String,
+    pub kind: DefKind,
+    pub detail: String,
+    pub loc: Loc,
+    pub children: Vec<OutlineItem>,
+}
+
+/// Symbolication results for a whole package, indexed by file
+#[derive(Debug, Default, Clone)]
+pub struct Symbols {
+    idents: BTreeMap<Symbol, Vec<IdentInfo>>,
+    outlines: BTreeMap<Symbol, Vec<OutlineItem>>,
+}
+
+impl Symbols {
+    /// Returns the narrowest identifier occurrence in `file` that encloses the byte `offset`
+    pub fn ident_at(&self, file: Symbol, offset: usize) -> Option<&IdentInfo> {
+        let offset = offset as ByteIndex;
+        self.idents
+            .get(&file)?
+            .iter()
+            .filter(|info| info.loc.start() <= offset && offset <= info.loc.end())
+            .min_by_key(|info| info.loc.end() - info.loc.start())
+    }
+
+    pub fn outline(&self, file: Symbol) -> &[OutlineItem] {
+        self.outlines
+            .get(&file)
+            .map(|items| items.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+//**************************************************************************************************
+// Definitions
+//**************************************************************************************************
+
+#[derive(Debug, Clone)]
+struct MemberDef {
+    loc: Loc,
+    hover: String,
+}
+
+#[derive(Debug, Clone)]
+struct StructDef {
+    def: MemberDef,
+    fields: BTreeMap<Symbol, MemberDef>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ModuleDefs {
+    structs: BTreeMap<Symbol, StructDef>,
+    functions: BTreeMap<Symbol, MemberDef>,
+    constants: BTreeMap<Symbol, MemberDef>,
+}
+
+struct Symbolicator {
+    modules: BTreeMap<ModuleIdent_, ModuleDefs>,
+    /// Constants visible without a module qualifier, i.e. those of the current module or script
+    local_constants: BTreeMap<Symbol, MemberDef>,
+    /// Stack of lexical scopes mapping local variable names to their definition
+    scopes: Vec<BTreeMap<Symbol, Loc>>,
+    idents: Vec<IdentInfo>,
+}
+
+pub fn symbolicate(program: &Program) -> Symbols {
+    let mut symbolicator = Symbolicator {
+        modules: BTreeMap::new(),
+        local_constants: BTreeMap::new(),
+        scopes: vec![],
+        idents: vec![],
+    };
+    let mut outlines: BTreeMap<Symbol, Vec<OutlineItem>> = BTreeMap::new();
+
+    for (mloc, mident_, mdef) in &program.modules {
+        let defs = collect_module_defs(mident_, &mdef.structs, &mdef.functions, &mdef.constants);
+        let module_item = outline_module(mloc, mident_, &defs, mdef);
+        outlines
+            .entry(mloc.file())
+            .or_insert_with(Vec::new)
+            .push(module_item);
+        symbolicator.modules.insert(*mident_, defs);
+    }
+
+    for (mloc, mident_, mdef) in &program.modules {
+        symbolicator.local_constants = symbolicator.modules[mident_].constants.clone();
+        symbolicator.add_ident(mloc, mloc, format!("module {}", mident_));
+        for (sloc, sname, sdef) in &mdef.structs {
+            symbolicator.struct_def(mident_, sloc, sname, sdef);
+        }
+        for (cloc, cname, constant) in &mdef.constants {
+            symbolicator.constant_def(cloc, cname, constant);
+        }
+        for (floc, fname, fdef) in source_functions(&mdef.functions) {
+            let hover = symbolicator.modules[mident_].functions[fname].hover.clone();
+            symbolicator.add_ident(floc, floc, hover);
+            symbolicator.function(fdef);
+        }
+    }
+
+    for script in program.scripts.values() {
+        symbolicator.local_constants = script
+            .constants
+            .iter()
+            .map(|(cloc, cname, c)| (*cname, constant_member(cloc, cname, c)))
+            .collect();
+        for (cloc, cname, constant) in &script.constants {
+            symbolicator.constant_def(cloc, cname, constant);
+        }
+        let floc = script.function_name.0.loc;
+        let hover = function_hover(
+            None,
+            &script.function_name.0.value,
+            &script.function.visibility,
+            &script.function.signature,
+        );
+        symbolicator.add_ident(floc, floc, hover.clone());
+        symbolicator.function(&script.function);
+        outlines
+            .entry(floc.file())
+            .or_insert_with(Vec::new)
+            .push(OutlineItem {
+                name: script.function_name.0.value.to_string(),
+                kind: DefKind::Function,
+                detail: hover,
+                loc: floc,
+                children: vec![],
+            });
+    }
+
+    let mut idents: BTreeMap<Symbol, Vec<IdentInfo>> = BTreeMap::new();
+    for info in symbolicator.idents {
+        idents
+            .entry(info.loc.file())
+            .or_insert_with(Vec::new)
+            .push(info);
+    }
+    Symbols { idents, outlines }
+}
+
+fn collect_module_defs(
+    mident_: &ModuleIdent_,
+    structs: &UniqueMap<StructName, StructDefinition>,
+    functions: &UniqueMap<FunctionName, Function>,
+    constants: &UniqueMap<ConstantName, Constant>,
+) -> ModuleDefs {
+    let mut defs = ModuleDefs::default();
+    for (sloc, sname, sdef) in structs {
+        let fields = match &sdef.fields {
+            StructFields::Defined(fields) => fields
+                .iter()
+                .map(|(floc, fname, (_, ty))| {
+                    let def = MemberDef {
+                        loc: floc,
+                        hover: format!("{}: {}", fname, type_to_string(ty)),
+                    };
+                    (*fname, def)
+                })
+                .collect(),
+            StructFields::Native(_) => BTreeMap::new(),
+        };
+        let def = MemberDef {
+            loc: sloc,
+            hover: struct_hover(mident_, sname, sdef),
+        };
+        defs.structs.insert(*sname, StructDef { def, fields });
+    }
+    for (floc, fname, fdef) in source_functions(functions) {
+        let def = MemberDef {
+            loc: floc,
+            hover: function_hover(Some(mident_), fname, &fdef.visibility, &fdef.signature),
+        };
+        defs.functions.insert(*fname, def);
+    }
+    for (cloc, cname, constant) in constants {
+        defs.constants
+            .insert(*cname, constant_member(cloc, cname, constant));
+    }
+    defs
+}
+
+fn outline_module(
+    mloc: Loc,
+    mident_: &ModuleIdent_,
+    defs: &ModuleDefs,
+    mdef: &move_lang::typing::ast::ModuleDefinition,
+) -> OutlineItem {
+    let mut children = vec![];
+    for (sloc, sname, _) in &mdef.structs {
+        let sdef = &defs.structs[sname];
+        let fields = sdef
+            .fields
+            .iter()
+            .map(|(fname, fdef)| OutlineItem {
+                name: fname.to_string(),
+                kind: DefKind::Field,
+                detail: fdef.hover.clone(),
+                loc: fdef.loc,
+                children: vec![],
+            })
+            .collect();
+        children.push(OutlineItem {
+            name: sname.to_string(),
+            kind: DefKind::Struct,
+            detail: sdef.def.hover.clone(),
+            loc: sloc,
+            children: fields,
+        });
+    }
+    for (cloc, cname, _) in &mdef.constants {
+        children.push(OutlineItem {
+            name: cname.to_string(),
+            kind: DefKind::Constant,
+            detail: defs.constants[cname].hover.clone(),
+            loc: cloc,
+            children: vec![],
+        });
+    }
+    for (floc, fname, _) in source_functions(&mdef.functions) {
+        children.push(OutlineItem {
+            name: fname.to_string(),
+            kind: DefKind::Function,
+            detail: defs.functions[fname].hover.clone(),
+            loc: floc,
+            children: vec![],
+        });
+    }
+    children.sort_by_key(|item| item.loc.start());
+    OutlineItem {
+        name: mident_.module.to_string(),
+        kind: DefKind::Module,
+        detail: format!("module {}", mident_),
+        loc: mloc,
+        children,
+    }
+}
+
+/// Iterates over the functions of a module, leaving out the one inserted in every module by the
+/// compilation in test mode
+fn source_functions(
+    functions: &UniqueMap<FunctionName, Function>,
+) -> impl Iterator<Item = (Loc, &Symbol, &Function)> {
+    functions
+        .iter()
+        .filter(|(_, fname, _)| fname.as_str() != "unit_test_poison")
+}
+
+impl Symbolicator {
+    fn add_ident(&mut self, loc: Loc, def_loc: Loc, hover: String) {
+        self.idents.push(IdentInfo {
+            loc,
+            def_loc,
+            hover,
+        })
+    }
+
+    fn add_member_use(&mut self, loc: Loc, def: Option<MemberDef>) {
+        if let Some(MemberDef {
+            loc: def_loc,
+            hover,
+        }) = def
+        {
+            self.add_ident(loc, def_loc, hover)
+        }
+    }
+
+    fn struct_def(
+        &mut self,
+        mident_: &ModuleIdent_,
+        sloc: Loc,
+        sname: &Symbol,
+        sdef: &StructDefinition,
+    ) {
+        let def = &self.modules[mident_].structs[sname];
+        let mut idents = vec![(sloc, def.def.clone())];
+        idents.extend(def.fields.values().map(|fdef| (fdef.loc, fdef.clone())));
+        for (loc, def) in idents {
+            self.add_member_use(loc, Some(def));
+        }
+        if let StructFields::Defined(fields) = &sdef.fields {
+            for (_, _, (_, ty)) in fields {
+                self.type_annotation(ty)
+            }
+        }
+    }
+
+    fn constant_def(&mut self, cloc: Loc, cname: &Symbol, constant: &Constant) {
+        let def = constant_member(cloc, cname, constant);
+        self.add_member_use(cloc, Some(def));
+        self.type_annotation(&constant.signature);
+    }
+
+    fn function(&mut self, fdef: &Function) {
+        assert!(self.scopes.is_empty());
+        self.scopes.push(BTreeMap::new());
+        for (var, ty) in &fdef.signature.parameters {
+            self.define_local(var, ty);
+            self.type_annotation(ty);
+        }
+        self.type_annotation(&fdef.signature.return_type);
+        if let FunctionBody_::Defined(seq) = &fdef.body.value {
+            self.sequence(seq)
+        }
+        self.scopes.pop();
+    }
+
+    //**********************************************************************************************
+    // Locals
+    //**********************************************************************************************
+
+    fn define_local(&mut self, var: &Var, ty: &Type) {
+        let loc = var.0.loc;
+        self.scopes.last_mut().unwrap().insert(var.0.value, loc);
+        self.add_ident(loc, loc, format!("{}: {}", var, type_to_string(ty)));
+    }
+
+    fn use_local(&mut self, var: &Var, ty: &Type) {
+        let def_loc = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&var.0.value).copied());
+        if let Some(def_loc) = def_loc {
+            self.add_ident(
+                var.0.loc,
+                def_loc,
+                format!("{}: {}", var, type_to_string(ty)),
+            )
+        }
+    }
+
+    //**********************************************************************************************
+    // Expressions
+    //**********************************************************************************************
+
+    fn sequence(&mut self, seq: &Sequence) {
+        self.scopes.push(BTreeMap::new());
+        for item in seq {
+            match &item.value {
+                SequenceItem_::Seq(e) => self.exp(e),
+                SequenceItem_::Declare(lvalues) => self.lvalues(lvalues, true),
+                SequenceItem_::Bind(lvalues, _, e) => {
+                    self.exp(e);
+                    self.lvalues(lvalues, true)
+                }
+            }
+        }
+        self.scopes.pop();
+    }
+
+    fn lvalues(&mut self, lvalues: &LValueList, is_declaration: bool) {
+        for lvalue in &lvalues.value {
+            self.lvalue(lvalue, is_declaration)
+        }
+    }
+
+    fn lvalue(&mut self, lvalue: &LValue, is_declaration: bool) {
+        match &lvalue.value {
+            LValue_::Ignore => (),
+            LValue_::Var(var, ty) if is_declaration => self.define_local(var, ty),
+            LValue_::Var(var, ty) => self.use_local(var, ty),
+            LValue_::Unpack(mident, sname, _, fields)
+            | LValue_::BorrowUnpack(_, mident, sname, _, fields) => {
+                self.struct_use(lvalue.loc, mident, sname);
+                for (floc, fname, (_, (_, field_lvalue))) in fields {
+                    let def = self.field_def(&mident.value, &sname.0.value, fname);
+                    self.add_member_use(floc, def);
+                    self.lvalue(field_lvalue, is_declaration)
+                }
+            }
+        }
+    }
+
+    fn exp(&mut self, e: &move_lang::typing::ast::Exp) {
+        use UnannotatedExp_ as E;
+        match &e.exp.value {
+            E::Unit { .. }
+            | E::Value(_)
+            | E::Break
+            | E::Continue
+            | E::Spec(_, _)
+            | E::UnresolvedError => (),
+            E::Move { var, .. } | E::Copy { var, .. } | E::Use(var) => self.use_local(var, &e.ty),
+            E::BorrowLocal(_, var) => {
+                let ty = match &e.ty.value {
+                    Type_::Ref(_, inner) => inner.as_ref(),
+                    _ => &e.ty,
+                };
+                self.use_local(var, ty)
+            }
+            E::Constant(mident_opt, cname) => {
+                let def = match mident_opt {
+                    None => self.local_constants.get(&cname.0.value).cloned(),
+                    Some(mident) => self
+                        .modules
+                        .get(&mident.value)
+                        .and_then(|defs| defs.constants.get(&cname.0.value))
+                        .cloned(),
+                };
+                self.add_member_use(cname.0.loc, def)
+            }
+            E::ModuleCall(call) => self.module_call(call),
+            E::Builtin(builtin, args) => {
+                use BuiltinFunction_ as B;
+                match &builtin.value {
+                    B::MoveTo(ty) | B::MoveFrom(ty) | B::BorrowGlobal(_, ty) | B::Exists(ty) => {
+                        self.type_annotation(ty)
+                    }
+                    B::Freeze(_) | B::Assert => (),
+                }
+                self.exp(args)
+            }
+            E::IfElse(cond, if_true, if_false) => {
+                self.exp(cond);
+                self.exp(if_true);
+                self.exp(if_false)
+            }
+            E::While(cond, body) => {
+                self.exp(cond);
+                self.exp(body)
+            }
+            E::Loop { body, .. } => self.exp(body),
+            E::Block(seq) => self.sequence(seq),
+            E::Assign(lvalues, _, rhs) => {
+                self.exp(rhs);
+                self.lvalues(lvalues, false)
+            }
+            E::Mutate(lhs, rhs) => {
+                self.exp(lhs);
+                self.exp(rhs)
+            }
+            E::Return(e)
+            | E::Abort(e)
+            | E::Dereference(e)
+            | E::UnaryExp(_, e)
+            | E::TempBorrow(_, e) => self.exp(e),
+            E::BinopExp(lhs, _, _, rhs) => {
+                self.exp(lhs);
+                self.exp(rhs)
+            }
+            E::Pack(mident, sname, _, fields) => {
+                self.struct_use(e.exp.loc, mident, sname);
+                for (floc, fname, (_, (_, field_exp))) in fields {
+                    let def = self.field_def(&mident.value, &sname.0.value, fname);
+                    self.add_member_use(floc, def);
+                    self.exp(field_exp)
+                }
+            }
+            E::ExpList(items) => {
+                for item in items {
+                    match item {
+                        ExpListItem::Single(e, _) | ExpListItem::Splat(_, e, _) => self.exp(e),
+                    }
+                }
+            }
+            E::Borrow(_, base, field) => {
+                self.exp(base);
+                let def = match struct_of_type(&base.ty) {
+                    Some((mident_, sname)) => self.field_def(&mident_, &sname, &field.0.value),
+                    None => None,
+                };
+                self.add_member_use(field.0.loc, def)
+            }
+            E::Cast(e, ty) | E::Annotate(e, ty) => {
+                self.exp(e);
+                self.type_annotation(ty)
+            }
+        }
+    }
+
+    fn module_call(&mut self, call: &ModuleCall) {
+        let def = self
+            .modules
+            .get(&call.module.value)
+            .and_then(|defs| defs.functions.get(&call.name.0.value))
+            .cloned();
+        self.add_member_use(call.name.0.loc, def);
+        self.exp(&call.arguments)
+    }
+
+    /// Record a use of a struct by a pack or unpack at `loc`. The name carried by the typed AST
+    /// points at the struct definition, so the whole expression is used as the reference instead.
+    fn struct_use(&mut self, loc: Loc, mident: &ModuleIdent, sname: &StructName) {
+        let def = self
+            .modules
+            .get(&mident.value)
+            .and_then(|defs| defs.structs.get(&sname.0.value))
+            .map(|sdef| sdef.def.clone());
+        self.add_member_use(loc, def)
+    }
+
+    fn field_def(
+        &self,
+        mident_: &ModuleIdent_,
+        sname: &Symbol,
+        fname: &Symbol,
+    ) -> Option<MemberDef> {
+        self.modules
+            .get(mident_)?
+            .structs
+            .get(sname)?
+            .fields
+            .get(fname)
+            .cloned()
+    }
+
+    /// Records references to structs in a type written by the user. Inferred types are not
+    /// visited as their locations do not correspond to identifiers in the source.
+    fn type_annotation(&mut self, ty: &Type) {
+        match &ty.value {
+            Type_::Ref(_, inner) => self.type_annotation(inner),
+            Type_::Apply(_, sp!(tloc, tname), args) => {
+                if let TypeName_::ModuleType(mident, sname) = tname {
+                    let def = self
+                        .modules
+                        .get(&mident.value)
+                        .and_then(|defs| defs.structs.get(&sname.0.value))
+                        .map(|sdef| sdef.def.clone());
+                    self.add_member_use(*tloc, def)
+                }
+                for arg in args {
+                    self.type_annotation(arg)
+                }
+            }
+            Type_::Unit
+            | Type_::Param(_)
+            | Type_::Var(_)
+            | Type_::Anything
+            | Type_::UnresolvedError => (),
+        }
+    }
+}
+
+//**************************************************************************************************
+// Display
+//**************************************************************************************************
+
+fn struct_of_type(ty: &Type) -> Option<(ModuleIdent_, Symbol)> {
+    match &ty.value {
+        Type_::Ref(_, inner) => struct_of_type(inner),
+        Type_::Apply(_, sp!(_, TypeName_::ModuleType(mident, sname)), _) => {
+            Some((mident.value, sname.0.value))
+        }
+        _ => None,
+    }
+}
+
+pub fn type_to_string(ty: &Type) -> String {
+    match &ty.value {
+        Type_::Unit => "()".to_string(),
+        Type_::Ref(true, inner) => format!("&mut {}", type_to_string(inner)),
+        Type_::Ref(false, inner) => format!("&{}", type_to_string(inner)),
+        Type_::Param(tparam) => tparam.user_specified_name.value.to_string(),
+        Type_::Apply(_, sp!(_, TypeName_::Multiple(_)), args) => {
+            format!("({})", format_comma(args.iter().map(type_to_string)))
+        }
+        Type_::Apply(_, sp!(_, tname), args) => {
+            let name = match tname {
+                TypeName_::Builtin(builtin) => builtin.to_string(),
+                TypeName_::ModuleType(mident, sname) => {
+                    format!("{}::{}", mident.value.module, sname)
+                }
+                TypeName_::Multiple(_) => unreachable!(),
+            };
+            if args.is_empty() {
+                name
+            } else {
+                format!(
+                    "{}<{}>",
+                    name,
+                    format_comma(args.iter().map(type_to_string))
+                )
+            }
+        }
+        Type_::Var(_) | Type_::Anything => "_".to_string(),
+        Type_::UnresolvedError => "<unknown>".to_string(),
+    }
+}
+
+fn type_parameters_to_string<'a>(tparams: impl IntoIterator<Item = &'a TParam>) -> String {
+    let tparams = tparams
+        .into_iter()
+        .map(|tp| {
+            if tp.abilities.is_empty() {
+                tp.user_specified_name.value.to_string()
+            } else {
+                format!(
+                    "{}: {}",
+                    tp.user_specified_name,
+                    tp.abilities
+                        .iter()
+                        .map(|a| a.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" + ")
+                )
+            }
+        })
+        .collect::<Vec<_>>();
+    if tparams.is_empty() {
+        "".to_string()
+    } else {
+        format!("<{}>", tparams.join(", "))
+    }
+}
+
+fn function_hover(
+    mident_: Option<&ModuleIdent_>,
+    fname: &Symbol,
+    visibility: &Visibility,
+    signature: &FunctionSignature,
+) -> String {
+    let visibility = match visibility {
+        Visibility::Internal => "".to_string(),
+        v => format!("{} ", v),
+    };
+    let qualifier = match mident_ {
+        Some(mident_) => format!("{}::", mident_),
+        None => "".to_string(),
+    };
+    let params = format_comma(
+        signature
+            .parameters
+            .iter()
+            .map(|(var, ty)| format!("{}: {}", var, type_to_string(ty))),
+    );
+    let ret = match &signature.return_type.value {
+        Type_::Unit => "".to_string(),
+        _ => format!(": {}", type_to_string(&signature.return_type)),
+    };
+    format!(
+        "{}fun {}{}{}({}){}",
+        visibility,
+        qualifier,
+        fname,
+        type_parameters_to_string(&signature.type_parameters),
+        params,
+        ret
+    )
+}
+
+fn struct_hover(mident_: &ModuleIdent_, sname: &Symbol, sdef: &StructDefinition) -> String {
+    let abilities = if sdef.abilities.is_empty() {
+        "".to_string()
+    } else {
+        format!(" has {}", format_comma(sdef.abilities.iter()))
+    };
+    format!(
+        "struct {}::{}{}{}",
+        mident_,
+        sname,
+        type_parameters_to_string(sdef.type_parameters.iter().map(|tp| &tp.param)),
+        abilities
+    )
+}
+
+fn constant_member(cloc: Loc, cname: &Symbol, constant: &Constant) -> MemberDef {
+    MemberDef {
+        loc: cloc,
+        hover: format!("const {}: {}", cname, type_to_string(&constant.signature)),
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use lsp_types::{Position, Range};
+use move_ir_types::location::Loc;
+use move_lang::diagnostics::FilesSourceText;
+use move_symbol_pool::Symbol;
+use std::collections::HashMap;
+
+/// Converts between the byte offsets used by `Loc` and the (line, UTF-16 column) positions used
+/// by the Language Server Protocol.
+#[derive(Debug, Default, Clone)]
+pub struct FileOffsets {
+    files: HashMap<Symbol, LineIndex>,
+}
+
+#[derive(Debug, Clone)]
+struct LineIndex {
+    text: String,
+    /// Byte offset of the first character of every line
+    line_starts: Vec<usize>,
+}
+
+impl FileOffsets {
+    pub fn new(files: &FilesSourceText) -> Self {
+        Self {
+            files: files
+                .iter()
+                .map(|(fname, text)| (*fname, LineIndex::new(text.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn loc_to_range(&self, loc: Loc) -> Option<Range> {
+        let index = self.files.get(&loc.file())?;
+        Some(Range::new(
+            index.position(loc.start() as usize),
+            index.position(loc.end() as usize),
+        ))
+    }
+
+    pub fn position_to_offset(&self, file: Symbol, position: Position) -> Option<usize> {
+        self.files.get(&file)?.offset(position)
+    }
+}
+
+impl LineIndex {
+    fn new(text: String) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(idx, _)| idx + 1));
+        Self { text, line_starts }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let offset = std::cmp::min(offset, self.text.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    fn offset(&self, position: Position) -> Option<usize> {
+        let line_start = *self.line_starts.get(position.line as usize)?;
+        let line_end = self
+            .line_starts
+            .get(position.line as usize + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        let mut utf16_col = 0;
+        for (idx, c) in self.text[line_start..line_end].char_indices() {
+            if utf16_col >= position.character as usize {
+                return Some(line_start + idx);
+            }
+            utf16_col += c.len_utf16();
+        }
+        Some(line_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_round_trip() {
+        let index = LineIndex::new("module M {\n  fun f() {}\n}\n".to_string());
+        assert_eq!(index.position(0), Position::new(0, 0));
+        assert_eq!(index.position(13), Position::new(1, 2));
+        assert_eq!(index.offset(Position::new(1, 2)), Some(13));
+        assert_eq!(index.position(24), Position::new(2, 0));
+        assert_eq!(index.offset(Position::new(7, 0)), None);
+    }
+
+    #[test]
+    fn utf16_columns() {
+        let index = LineIndex::new("// \u{1F600} x".to_string());
+        // The emoji is four bytes in UTF-8 but two code units in UTF-16
+        assert_eq!(index.position(8), Position::new(0, 6));
+        assert_eq!(index.offset(Position::new(0, 6)), Some(8));
+    }
+}
@@ -452,6 +452,7 @@ impl FakeExecutor {
             self.block_time,
             vec![],
             *validator_set.payload()[0].account_address(),
+            vec![],
         );
         let output = self
             .execute_transaction_block(vec![Transaction::BlockMetadata(new_block)])
@@ -461,7 +462,7 @@ impl FakeExecutor {
         // check if we emit the expected event, there might be more events for transaction fees
         let event = output.events()[0].clone();
         assert_eq!(event.key(), &new_block_event_key());
-        assert!(bcs::from_bytes::<NewBlockEvent>(event.event_data()).is_ok());
+        assert!(NewBlockEvent::try_from_bytes(event.event_data()).is_ok());
         self.apply_write_set(output.write_set());
     }
 
@@ -107,6 +107,30 @@ impl<'a, T: MoveResolver + ?Sized> MoveValueAnnotator<'a, T> {
             .collect::<Result<_>>()
     }
 
+    /// Returns the types of the arguments passed to a script function, i.e. its parameters other
+    /// than signers, instantiated with `ty_args`.
+    pub fn function_argument_types(
+        &self,
+        module: &ModuleId,
+        function: &IdentStr,
+        ty_args: &[TypeTag],
+    ) -> Result<Vec<TypeTag>> {
+        let ty_args = ty_args
+            .iter()
+            .map(|ty| self.cache.resolve_type(ty))
+            .collect::<Result<Vec<_>>>()?;
+        self.cache
+            .resolve_function_arguments(module, function)?
+            .into_iter()
+            .filter(|t| !matches!(t, FatType::Signer))
+            .map(|t| {
+                t.subst(&ty_args)
+                    .and_then(|t| t.type_tag())
+                    .map_err(|e| anyhow!(into_vm_status(e)))
+            })
+            .collect()
+    }
+
     pub fn view_resource(&self, tag: &StructTag, blob: &[u8]) -> Result<AnnotatedMoveStruct> {
         let ty = self.cache.resolve_struct(tag)?;
         let struct_def = (&ty).try_into().map_err(into_vm_status)?;
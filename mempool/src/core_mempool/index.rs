@@ -65,6 +65,21 @@ impl PriorityIndex {
         self.data.iter().rev()
     }
 
+    /// Returns the lowest ranked non-governance transaction of another account than the given
+    /// transaction's, if the given transaction ranks strictly higher by governance role and gas
+    /// ranking score.
+    pub(crate) fn get_evictable(&self, txn: &MempoolTransaction) -> Option<TxnPointer> {
+        let rank = (txn.governance_role.priority(), txn.ranking_score);
+        self.data
+            .iter()
+            .take_while(|key| {
+                key.governance_role == GovernanceRole::NonGovernanceRole
+                    && (key.governance_role.priority(), key.gas_ranking_score) < rank
+            })
+            .find(|key| key.address != txn.get_sender())
+            .map(TxnPointer::from)
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.data.len()
     }
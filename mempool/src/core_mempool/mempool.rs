@@ -6,7 +6,7 @@
 use crate::{
     core_mempool::{
         index::TxnPointer,
-        transaction::{MempoolTransaction, TimelineState},
+        transaction::{AccountPendingTransactions, MempoolTransaction, TimelineState},
         transaction_store::TransactionStore,
         ttl_cache::TtlCache,
     },
@@ -123,6 +123,29 @@ impl Mempool {
         self.transactions.get_by_hash(hash)
     }
 
+    /// Returns all the transactions of an account with their state in mempool, and the sequence
+    /// number expected for the account's next transaction.
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> AccountPendingTransactions {
+        let transactions = self.transactions.get_account_transactions(address);
+        let next_sequence_number = self.sequence_number_cache.get(address).map(|&current| {
+            transactions
+                .iter()
+                .map(|status| status.txn.sequence_number())
+                .skip_while(|&seq| seq < current)
+                .fold(
+                    current,
+                    |next, seq| if seq == next { next + 1 } else { next },
+                )
+        });
+        AccountPendingTransactions {
+            transactions,
+            next_sequence_number,
+        }
+    }
+
     /// Used to add a transaction to the Mempool.
     /// Performs basic validation: checks account's sequence number.
     pub(crate) fn add_txn(
@@ -68,6 +68,28 @@ pub enum TimelineState {
     NonQualified,
 }
 
+/// A transaction pending in mempool along with its state in the queue.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingTransactionStatus {
+    pub txn: SignedTransaction,
+    pub timeline_state: TimelineState,
+    // Whether the transaction is in the parking lot, i.e. it can't be included in the next block
+    // until the transactions preceding it (by sequence number) reach mempool.
+    pub is_parked: bool,
+}
+
+/// All the transactions pending in mempool for an account.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountPendingTransactions {
+    // Ordered by sequence number.
+    pub transactions: Vec<PendingTransactionStatus>,
+    // The sequence number expected for the account's next transaction, i.e. the first one not in
+    // mempool following the account's current sequence number. If it is lower than the sequence
+    // number of a parked transaction, that transaction is stuck behind a sequence number gap.
+    // `None` if mempool doesn't know the account's current sequence number.
+    pub next_sequence_number: Option<u64>,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct SequenceInfo {
     pub transaction_sequence_number: u64,
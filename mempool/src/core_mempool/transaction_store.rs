@@ -7,7 +7,7 @@ use crate::{
             AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
             TimelineIndex,
         },
-        transaction::{MempoolTransaction, TimelineState},
+        transaction::{MempoolTransaction, PendingTransactionStatus, TimelineState},
         ttl_cache::TtlCache,
     },
     counters,
@@ -102,6 +102,25 @@ impl TransactionStore {
         }
     }
 
+    /// Fetch all transactions of an account, ordered by sequence number, along with their state.
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> Vec<PendingTransactionStatus> {
+        self.transactions
+            .get(address)
+            .map(|txns| {
+                txns.iter()
+                    .map(|(seq, txn)| PendingTransactionStatus {
+                        txn: txn.txn.clone(),
+                        timeline_state: txn.timeline_state,
+                        is_parked: self.parking_lot_index.contains(address, seq),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Fetch mempool transaction by account address + sequence_number.
     pub(crate) fn get_mempool_txn(
         &self,
@@ -121,27 +140,30 @@ impl TransactionStore {
 
         // check if transaction is already present in Mempool
         // e.g. given request is update
-        // we allow increase in gas price to speed up process.
+        // a transaction with a higher gas price replaces the current one (replace-by-fee), which
+        // is removed from all indexes, including the timeline used for broadcasting.
         // ignores the case transaction hash is same for retrying submit transaction.
         if let Some(txns) = self.transactions.get_mut(&address) {
-            if let Some(current_version) =
-                txns.get_mut(&sequence_number.transaction_sequence_number)
-            {
+            if let Some(current_version) = txns.get(&sequence_number.transaction_sequence_number) {
                 if current_version.txn == txn.txn {
                     return MempoolStatus::new(MempoolStatusCode::Accepted);
                 }
-                if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
-                    && current_version.txn.payload() == txn.txn.payload()
-                    && current_version.txn.expiration_timestamp_secs()
-                        == txn.txn.expiration_timestamp_secs()
-                    && current_version.get_gas_price() < txn.get_gas_price()
-                {
+                if current_version.get_gas_price() < txn.get_gas_price() {
                     if let Some(txn) = txns.remove(&txn.sequence_info.transaction_sequence_number) {
+                        debug!(LogSchema::new(LogEntry::ReplacedTxn).txns(TxnsLog::new_txn(
+                            txn.get_sender(),
+                            txn.sequence_info.transaction_sequence_number
+                        )));
+                        counters::CORE_MEMPOOL_REPLACED_TXNS.inc();
                         self.index_remove(&txn);
                     }
                 } else {
                     return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        format!("Failed to update gas price to {}", txn.get_gas_price()),
+                        format!(
+                            "Failed to update gas price to {}, it must be higher than the current gas price {}",
+                            txn.get_gas_price(),
+                            current_version.get_gas_price(),
+                        ),
                     );
                 }
             }
@@ -224,7 +246,8 @@ impl TransactionStore {
     }
 
     /// Checks if Mempool is full.
-    /// If it's full, tries to free some space by evicting transactions from the ParkingLot.
+    /// If it's full, tries to free some space by evicting transactions from the ParkingLot, or
+    /// otherwise the lowest ranked non-governance transaction if it ranks below the new one.
     /// We only evict on attempt to insert a transaction that would be ready for broadcast upon insertion.
     fn check_is_full_after_eviction(
         &mut self,
@@ -247,13 +270,52 @@ impl TransactionStore {
                             txn.sequence_info.transaction_sequence_number
                         ))
                     );
+                    counters::CORE_MEMPOOL_EVICTED_TXNS
+                        .with_label_values(&[counters::EVICT_PARKED_TXN_LABEL])
+                        .inc();
                     self.index_remove(&txn);
                 }
+            } else if let Some((address, sequence_number)) = self.priority_index.get_evictable(txn)
+            {
+                self.evict_ready_transaction(&address, sequence_number);
             }
         }
         self.system_ttl_index.size() >= self.capacity
     }
 
+    /// Evicts a ready transaction. The following transactions of a non-CRSN account can't be
+    /// included in a block without it anymore, so they are parked.
+    fn evict_ready_transaction(&mut self, address: &AccountAddress, sequence_number: u64) {
+        if let Some(txns) = self.transactions.get_mut(address) {
+            if let Some(txn) = txns.remove(&sequence_number) {
+                if let AccountSequenceInfo::Sequential(_) =
+                    txn.sequence_info.account_sequence_number_type
+                {
+                    for (_, t) in
+                        txns.range_mut((Bound::Excluded(sequence_number), Bound::Unbounded))
+                    {
+                        self.priority_index.remove(t);
+                        self.timeline_index.remove(t);
+                        if let TimelineState::Ready(_) = t.timeline_state {
+                            t.timeline_state = TimelineState::NotReady;
+                        }
+                        self.parking_lot_index.insert(t);
+                    }
+                }
+                debug!(
+                    LogSchema::new(LogEntry::MempoolFullEvictedTxn).txns(TxnsLog::new_txn(
+                        txn.get_sender(),
+                        txn.sequence_info.transaction_sequence_number
+                    ))
+                );
+                counters::CORE_MEMPOOL_EVICTED_TXNS
+                    .with_label_values(&[counters::EVICT_READY_TXN_LABEL])
+                    .inc();
+                self.index_remove(&txn);
+            }
+        }
+    }
+
     /// Check if a transaction would be ready for broadcast in mempool upon insertion (without inserting it).
     /// Two ways this can happen:
     /// 1. txn sequence number == curr_sequence_number
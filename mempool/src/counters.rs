@@ -33,6 +33,10 @@ pub const GC_CLIENT_EXP_LABEL: &str = "client_expiration";
 pub const GC_ACTIVE_TXN_LABEL: &str = "active";
 pub const GC_PARKED_TXN_LABEL: &str = "parked";
 
+// Core mempool eviction txn status labels
+pub const EVICT_PARKED_TXN_LABEL: &str = "parked";
+pub const EVICT_READY_TXN_LABEL: &str = "ready";
+
 // Mempool service request type labels
 pub const GET_BLOCK_LABEL: &str = "get_block";
 pub const COMMIT_STATE_SYNC_LABEL: &str = "commit_accepted";
@@ -53,6 +57,7 @@ pub const SUCCESS_LABEL: &str = "success";
 // Bounded executor task labels
 pub const CLIENT_EVENT_LABEL: &str = "client_event";
 pub const CLIENT_EVENT_GET_TXN_LABEL: &str = "client_event_get_txn";
+pub const CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL: &str = "client_event_get_account_txns";
 pub const RECONFIG_EVENT_LABEL: &str = "reconfig";
 pub const PEER_BROADCAST_EVENT_LABEL: &str = "peer_broadcast";
 
@@ -102,6 +107,25 @@ pub static CORE_MEMPOOL_REMOVED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter tracking number of txns evicted from core mempool to make space for new txns when full
+pub static CORE_MEMPOOL_EVICTED_TXNS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_core_mempool_evicted_txns_count",
+        "Number of txns evicted from core mempool when full",
+        &["status"]
+    )
+    .unwrap()
+});
+
+/// Counter tracking number of txns replaced by a txn with the same sequence number and a higher gas price
+pub static CORE_MEMPOOL_REPLACED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_core_mempool_replaced_txns_count",
+        "Number of txns replaced by a txn with a higher gas price in core mempool"
+    )
+    .unwrap()
+});
+
 /// Counter tracking latency of txns reaching various stages in committing
 /// (e.g. time from txn entering core mempool to being pulled in consensus block)
 pub static CORE_MEMPOOL_TXN_COMMIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
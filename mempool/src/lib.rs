@@ -57,6 +57,7 @@
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod tests;
+pub use core_mempool::{AccountPendingTransactions, PendingTransactionStatus, TimelineState};
 pub use shared_mempool::{
     bootstrap, network,
     types::{
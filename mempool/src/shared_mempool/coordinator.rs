@@ -147,6 +147,27 @@ async fn handle_client_request<V>(
                 ))
                 .await;
         }
+        MempoolClientRequest::GetAccountTransactions(address, callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_get_account_transactions(
+                    smp.clone(),
+                    address,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        }
     }
 }
 
@@ -92,13 +92,20 @@ pub type MempoolNetworkEvents = NetworkEvents<MempoolSyncMsg>;
 #[derive(Clone, Debug)]
 pub struct MempoolNetworkSender {
     inner: NetworkSender<MempoolSyncMsg>,
+    peer_metadata: Option<(NetworkId, Arc<PeerMetadataStorage>)>,
 }
 
-/// Create a new Sender that only sends for the `MEMPOOL_DIRECT_SEND_PROTOCOL` ProtocolId and a
-/// Receiver (Events) that explicitly returns only said ProtocolId..
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const DIRECT_SEND: &[ProtocolId] = &[
+    ProtocolId::MempoolDirectSendCompressed,
+    ProtocolId::MempoolDirectSend,
+];
+
+/// Create a new Sender that only sends for the `MEMPOOL_DIRECT_SEND_PROTOCOL` ProtocolIds and a
+/// Receiver (Events) that explicitly returns only said ProtocolIds..
 pub fn network_endpoint_config(max_broadcasts_per_peer: usize) -> AppConfig {
     AppConfig::p2p(
-        [ProtocolId::MempoolDirectSend],
+        DIRECT_SEND.iter().copied(),
         diem_channel::Config::new(max_broadcasts_per_peer)
             .queue_style(QueueStyle::KLAST)
             .counters(&counters::PENDING_MEMPOOL_NETWORK_EVENTS),
@@ -112,17 +119,47 @@ impl NewNetworkSender for MempoolNetworkSender {
     ) -> Self {
         Self {
             inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+            peer_metadata: None,
         }
     }
 }
 
+impl MempoolNetworkSender {
+    /// Initialize the metadata storage of the peers on the given network, which is used to pick
+    /// the protocols supported by each peer.
+    pub fn initialize(
+        &mut self,
+        network_id: NetworkId,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) {
+        self.peer_metadata = Some((network_id, peer_metadata_storage));
+    }
+
+    /// Choose the most preferred protocol supported by the peer. Messages are sent uncompressed
+    /// if the peer's connection metadata is unknown.
+    fn preferred_protocol_for_peer(&self, peer: PeerId) -> ProtocolId {
+        self.peer_metadata
+            .as_ref()
+            .and_then(|(network_id, peer_metadata_storage)| {
+                peer_metadata_storage.read(PeerNetworkId::new(*network_id, peer))
+            })
+            .and_then(|peer_info| {
+                peer_info
+                    .active_connection
+                    .application_protocols
+                    .preferred_protocol(DIRECT_SEND)
+            })
+            .unwrap_or(ProtocolId::MempoolDirectSend)
+    }
+}
+
 #[async_trait]
 impl ApplicationNetworkSender<MempoolSyncMsg> for MempoolNetworkSender {
     fn send_to(&self, recipient: PeerId, message: MempoolSyncMsg) -> Result<(), NetworkError> {
         fail_point!("mempool::send_to", |_| {
             Err(anyhow::anyhow!("Injected error in mempool::send_to").into())
         });
-        let protocol = ProtocolId::MempoolDirectSend;
+        let protocol = self.preferred_protocol_for_peer(recipient);
         self.inner.send_to(recipient, protocol, message)
     }
 
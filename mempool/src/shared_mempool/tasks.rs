@@ -3,7 +3,7 @@
 
 //! Tasks that are executed by coordinators (short-lived compared to coordinators)
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TxnPointer},
+    core_mempool::{AccountPendingTransactions, CoreMempool, TimelineState, TxnPointer},
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
     network::MempoolSyncMsg,
@@ -20,6 +20,7 @@ use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
 use diem_metrics::HistogramTimer;
 use diem_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::OnChainConfigPayload,
     transaction::SignedTransaction,
@@ -123,6 +124,27 @@ pub(crate) async fn process_client_get_transaction<V>(
     }
 }
 
+/// Processes a get account transactions request from a client.
+pub(crate) async fn process_client_get_account_transactions<V>(
+    smp: SharedMempool<V>,
+    address: AccountAddress,
+    callback: oneshot::Sender<AccountPendingTransactions>,
+    timer: HistogramTimer,
+) where
+    V: TransactionValidation,
+{
+    timer.stop_and_record();
+    let txns = smp.mempool.lock().get_account_transactions(&address);
+
+    if callback.send(txns).is_err() {
+        error!(LogSchema::event_log(
+            LogEntry::GetAccountTransactions,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
 /// Processes transactions from other nodes.
 pub(crate) async fn process_transaction_broadcast<V>(
     smp: SharedMempool<V>,
@@ -3,7 +3,8 @@
 
 //! Objects used by/related to shared mempool
 use crate::{
-    core_mempool::CoreMempool, network::MempoolNetworkInterface,
+    core_mempool::{AccountPendingTransactions, CoreMempool},
+    network::MempoolNetworkInterface,
     shared_mempool::network::MempoolNetworkSender,
 };
 use anyhow::Result;
@@ -215,6 +216,7 @@ pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 pub enum MempoolClientRequest {
     SubmitTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
     GetTransactionByHash(HashValue, oneshot::Sender<Option<SignedTransaction>>),
+    GetAccountTransactions(AccountAddress, oneshot::Sender<AccountPendingTransactions>),
 }
 
 pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
@@ -323,7 +323,7 @@ fn test_fail_for_same_gas_amount_and_not_same_expiration_time_crsn() {
 }
 
 #[test]
-fn test_update_invalid_transaction_in_mempool() {
+fn test_update_transaction_with_different_max_gas_amount_in_mempool() {
     let (mut mempool, mut consensus) = setup_mempool();
     let txns = add_txns_to_mempool(
         &mut mempool,
@@ -333,18 +333,16 @@ fn test_update_invalid_transaction_in_mempool() {
         &TestTransaction::new(0, 0, 5),
         200,
     );
-    let _added_tnx = add_signed_txn(&mut mempool, updated_txn);
+    add_signed_txn(&mut mempool, updated_txn.clone()).unwrap();
 
-    // Since both gas price and mas gas amount were updated, the ordering should not have changed.
-    // The second transaction with gas price 2 should come first.
+    // The higher gas price replaces the transaction, even though its max gas amount changed.
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![updated_txn]);
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
-    let next_tnx = consensus.get_block(&mut mempool, 1);
-    assert_eq!(next_tnx, vec![txns[0].clone()]);
-    assert_eq!(next_tnx[0].gas_unit_price(), 1);
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![]);
 }
 
 #[test]
-fn test_update_invalid_transaction_in_mempool_crsn() {
+fn test_update_transaction_with_different_max_gas_amount_in_mempool_crsn() {
     let (mut mempool, mut consensus) = setup_mempool();
     let txns = add_txns_to_mempool(
         &mut mempool,
@@ -353,18 +351,62 @@ fn test_update_invalid_transaction_in_mempool_crsn() {
             TestTransaction::new(1, 0, 2).crsn(0),
         ],
     );
-    let updated_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
-        &TestTransaction::new(0, 0, 5).crsn(0),
-        200,
+    let updated_txn = TestTransaction::new(0, 0, 5).crsn(0);
+    let signed_updated_txn = updated_txn.make_signed_transaction_with_max_gas_amount(200);
+    mempool.add_txn(
+        signed_updated_txn.clone(),
+        0,
+        signed_updated_txn.gas_unit_price(),
+        updated_txn.account_seqno_type,
+        TimelineState::NotReady,
+        updated_txn.governance_role,
     );
-    let _added_tnx = add_signed_txn(&mut mempool, updated_txn);
 
-    // Since both gas price and mas gas amount were updated, the ordering should not have changed.
-    // The second transaction with gas price 2 should come first.
+    // The higher gas price replaces the transaction, even though its max gas amount changed.
+    assert_eq!(
+        consensus.get_block(&mut mempool, 1),
+        vec![signed_updated_txn]
+    );
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
-    let next_tnx = consensus.get_block(&mut mempool, 1);
-    assert_eq!(next_tnx, vec![txns[0].clone()]);
-    assert_eq!(next_tnx[0].gas_unit_price(), 1);
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![]);
+}
+
+#[test]
+fn test_fail_to_update_transaction_without_higher_gas_price() {
+    let (mut mempool, mut consensus) = setup_mempool();
+    let txns = add_txns_to_mempool(&mut mempool, vec![TestTransaction::new(0, 0, 5)]);
+
+    let lower_gas_price_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
+        &TestTransaction::new(0, 0, 1),
+        200,
+    );
+    assert!(add_signed_txn(&mut mempool, lower_gas_price_txn).is_err());
+    let same_gas_price_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
+        &TestTransaction::new(0, 0, 5),
+        200,
+    );
+    assert!(add_signed_txn(&mut mempool, same_gas_price_txn).is_err());
+
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[0].clone()]);
+}
+
+#[test]
+fn test_replaced_transaction_removed_from_timeline() {
+    let mut pool = setup_mempool().0;
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(0, 0, 1), TestTransaction::new(0, 1, 1)],
+    );
+    let (timeline, last_timeline_id) = pool.read_timeline(0, 10);
+    assert_eq!(timeline.len(), 2);
+
+    // The replacement is broadcast again, while the replaced transaction is gone for good.
+    let replacement = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(0, 0, 5)]);
+    let (timeline, _) = pool.read_timeline(last_timeline_id, 10);
+    assert_eq!(timeline, replacement);
+    let (timeline, _) = pool.read_timeline(0, 10);
+    assert_eq!(timeline.len(), 2);
+    assert!(timeline.contains(&replacement[0]));
 }
 
 #[test]
@@ -603,6 +645,73 @@ fn test_parking_lot_evict_only_for_ready_txn_insertion() {
     }
 }
 
+#[test]
+fn test_evict_lowest_ranked_transaction_when_full() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 3;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 3)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(2, 0, 2)).unwrap();
+
+    // Transactions that don't rank higher than the lowest ranked one are still rejected.
+    assert!(add_txn(&mut pool, TestTransaction::new(3, 0, 1)).is_err());
+
+    // A higher ranked transaction evicts the lowest ranked one.
+    let txn = TestTransaction::new(3, 0, 4).make_signed_transaction();
+    add_signed_txn(&mut pool, txn.clone()).unwrap();
+    let block = pool.get_block(10, HashSet::new());
+    assert_eq!(block.len(), 3);
+    assert_eq!(block[0], txn);
+    assert!(block
+        .iter()
+        .all(|txn| txn.sender() != TestTransaction::get_address(0)));
+}
+
+#[test]
+fn test_eviction_parks_following_transactions() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 3;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 5)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 2)).unwrap();
+
+    add_txn(&mut pool, TestTransaction::new(2, 0, 3)).unwrap();
+    // The account's transaction following the evicted one can't be included in a block anymore.
+    assert_eq!(pool.get_parking_lot_size(), 1);
+    let mut block: Vec<_> = pool
+        .get_block(10, HashSet::new())
+        .iter()
+        .map(SignedTransaction::gas_unit_price)
+        .collect();
+    block.sort_unstable();
+    assert_eq!(block, vec![2, 3]);
+
+    // The parked transaction is evicted first.
+    add_txn(&mut pool, TestTransaction::new(3, 0, 3)).unwrap();
+    assert_eq!(pool.get_parking_lot_size(), 0);
+    assert_eq!(pool.get_block(10, HashSet::new()).len(), 3);
+}
+
+#[test]
+fn test_no_eviction_of_governance_transactions() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 1;
+    let mut pool = CoreMempool::new(&config);
+    let mut gov_txn = TestTransaction::new(0, 0, 1);
+    gov_txn.governance_role = GovernanceRole::DesignatedDealer;
+    let gov_txns = add_txns_to_mempool(&mut pool, vec![gov_txn]);
+
+    let txn = TestTransaction::new(1, 0, 100).make_signed_transaction();
+    assert!(add_signed_txn(&mut pool, txn.clone()).is_err());
+    assert_eq!(
+        pool.get_by_hash(gov_txns[0].clone().committed_hash()),
+        Some(gov_txns[0].clone())
+    );
+    assert!(pool.get_by_hash(txn.committed_hash()).is_none());
+}
+
 #[test]
 fn test_gc_ready_transaction() {
     let mut pool = setup_mempool().0;
@@ -740,3 +849,53 @@ fn test_get_transaction_by_hash_after_the_txn_is_updated() {
     let txn_by_new_hash = pool.get_by_hash(new_txn_hash);
     assert_eq!(txn_by_new_hash, Some(new_txn));
 }
+
+#[test]
+fn test_get_account_transactions() {
+    let (mut pool, _) = setup_mempool();
+    let address = TestTransaction::get_address(0);
+    let unknown = pool.get_account_transactions(&TestTransaction::get_address(1));
+    assert!(unknown.transactions.is_empty());
+    assert_eq!(unknown.next_sequence_number, None);
+
+    // Transaction 3 is stuck behind the missing transaction 2.
+    for seq in &[0, 1, 3] {
+        add_txn(&mut pool, TestTransaction::new(0, *seq, 1)).unwrap();
+    }
+    let pending = pool.get_account_transactions(&address);
+    let states: Vec<_> = pending
+        .transactions
+        .iter()
+        .map(|status| {
+            (
+                status.txn.sequence_number(),
+                status.timeline_state,
+                status.is_parked,
+            )
+        })
+        .collect();
+    assert_eq!(
+        states,
+        vec![
+            (0, TimelineState::Ready(1), false),
+            (1, TimelineState::Ready(2), false),
+            (3, TimelineState::NotReady, true),
+        ]
+    );
+    assert_eq!(pending.next_sequence_number, Some(2));
+
+    // Filling the gap unblocks transaction 3.
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+    let pending = pool.get_account_transactions(&address);
+    assert!(pending
+        .transactions
+        .iter()
+        .all(|status| !status.is_parked && status.timeline_state != TimelineState::NotReady));
+    assert_eq!(pending.next_sequence_number, Some(4));
+
+    // Committed transactions are not pending anymore.
+    pool.remove_transaction(&address, 1, false);
+    let pending = pool.get_account_transactions(&address);
+    assert_eq!(pending.transactions.len(), 2);
+    assert_eq!(pending.next_sequence_number, Some(4));
+}
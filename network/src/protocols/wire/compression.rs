@@ -0,0 +1,102 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements the compression of application message payloads, used by the
+//! compressed variants of [`ProtocolId`]s.
+//!
+//! Payloads are compressed using the LZ4 block format, prefixed by the little-endian `u32` size
+//! of the uncompressed payload. The size prefix is checked against a caller provided limit before
+//! anything is decompressed, so that a small malicious payload cannot make us allocate an
+//! arbitrary amount of memory (i.e., a decompression bomb).
+//!
+//! [`ProtocolId`]: crate::protocols::wire::handshake::v1::ProtocolId
+
+use std::{convert::TryInto, io};
+use thiserror::Error;
+
+/// The number of bytes used to prefix a compressed payload with its uncompressed size.
+const SIZE_PREFIX_LEN: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    #[error("Failed to compress the payload: {0}")]
+    CompressionFailed(io::Error),
+    #[error("Failed to decompress the payload: {0}")]
+    DecompressionFailed(io::Error),
+    #[error("The compressed payload is too short to contain its size: {0} bytes")]
+    MissingSize(usize),
+    #[error("The decompressed payload size {0} exceeds the limit of {1} bytes")]
+    SizeLimitExceeded(usize, usize),
+}
+
+/// Compresses the given payload.
+pub fn compress(payload: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    lz4::block::compress(payload, None, true).map_err(CompressionError::CompressionFailed)
+}
+
+/// Decompresses the given payload, failing if its uncompressed size exceeds `max_size` bytes.
+pub fn decompress(payload: &[u8], max_size: usize) -> Result<Vec<u8>, CompressionError> {
+    let size = uncompressed_size(payload)?;
+    if size > max_size {
+        return Err(CompressionError::SizeLimitExceeded(size, max_size));
+    }
+    lz4::block::decompress(payload, None).map_err(CompressionError::DecompressionFailed)
+}
+
+/// Reads the uncompressed size prefixed to a compressed payload.
+fn uncompressed_size(payload: &[u8]) -> Result<usize, CompressionError> {
+    let size_bytes = payload
+        .get(..SIZE_PREFIX_LEN)
+        .ok_or(CompressionError::MissingSize(payload.len()))?;
+    let size = u32::from_le_bytes(size_bytes.try_into().expect("size prefix has 4 bytes"));
+    Ok(size as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compress_decompress_round_trip() {
+        let payload = vec![7u8; 1024];
+        let compressed = compress(&payload).unwrap();
+        assert!(compressed.len() < payload.len());
+        assert_eq!(decompress(&compressed, payload.len()).unwrap(), payload);
+
+        let compressed = compress(&[]).unwrap();
+        assert!(decompress(&compressed, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decompress_enforces_size_limit() {
+        let payload = vec![7u8; 1024];
+        let compressed = compress(&payload).unwrap();
+        assert!(matches!(
+            decompress(&compressed, payload.len() - 1),
+            Err(CompressionError::SizeLimitExceeded(1024, 1023))
+        ));
+
+        // A tiny payload claiming a huge uncompressed size is rejected before decompression
+        let mut bomb = u32::MAX.to_le_bytes().to_vec();
+        bomb.extend_from_slice(&compressed[SIZE_PREFIX_LEN..]);
+        assert!(matches!(
+            decompress(&bomb, payload.len()),
+            Err(CompressionError::SizeLimitExceeded(_, 1024))
+        ));
+    }
+
+    #[test]
+    fn decompress_rejects_malformed_payloads() {
+        assert!(matches!(
+            decompress(&[1, 2], 1024),
+            Err(CompressionError::MissingSize(2))
+        ));
+
+        let mut compressed = compress(&[7u8; 1024]).unwrap();
+        compressed.truncate(compressed.len() / 2);
+        assert!(matches!(
+            decompress(&compressed, 1024),
+            Err(CompressionError::DecompressionFailed(_))
+        ));
+    }
+}
@@ -12,10 +12,11 @@
 //!
 //! [DiemNet Handshake v1 Specification]: https://github.com/diem/diem/blob/main/specifications/network/handshake-v1.md
 
+use crate::{constants::MAX_DECOMPRESSED_MESSAGE_SIZE, protocols::wire::compression};
 use anyhow::anyhow;
 use diem_config::network_id::NetworkId;
 use diem_types::chain_id::ChainId;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fmt,
@@ -49,6 +50,18 @@ pub enum ProtocolId {
     // json provides flexibility for backwards compatible upgrade
     ConsensusDirectSendJson = 6,
     ConsensusRpcJson = 7,
+    // compressed bcs, only used with peers that advertise support for it
+    MempoolDirectSendCompressed = 8,
+    StateSyncDirectSendCompressed = 9,
+    StorageServiceRpc = 10,
+    ConsensusObserverDirectSend = 11,
+}
+
+/// The encoding of the messages sent over a [`ProtocolId`].
+enum Encoding {
+    Bcs,
+    CompressedBcs,
+    Json,
 }
 
 impl ProtocolId {
@@ -63,6 +76,10 @@ impl ProtocolId {
             HealthCheckerRpc => "HealthCheckerRpc",
             ConsensusDirectSendJson => "ConsensusDirectSendJson",
             ConsensusRpcJson => "ConsensusRpcJson",
+            MempoolDirectSendCompressed => "MempoolDirectSendCompressed",
+            StateSyncDirectSendCompressed => "StateSyncDirectSendCompressed",
+            StorageServiceRpc => "StorageServiceRpc",
+            ConsensusObserverDirectSend => "ConsensusObserverDirectSend",
         }
     }
 
@@ -76,6 +93,10 @@ impl ProtocolId {
             ProtocolId::HealthCheckerRpc,
             ProtocolId::ConsensusDirectSendJson,
             ProtocolId::ConsensusRpcJson,
+            ProtocolId::MempoolDirectSendCompressed,
+            ProtocolId::StateSyncDirectSendCompressed,
+            ProtocolId::StorageServiceRpc,
+            ProtocolId::ConsensusObserverDirectSend,
         ]
     }
 
@@ -84,21 +105,38 @@ impl ProtocolId {
         ProtocolId::DiscoveryDirectSend
     }
 
-    pub fn to_bytes<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
+    fn encoding(self) -> Encoding {
         match self {
-            ProtocolId::ConsensusDirectSendJson | ProtocolId::ConsensusRpcJson => {
-                serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e))
+            ProtocolId::ConsensusDirectSendJson | ProtocolId::ConsensusRpcJson => Encoding::Json,
+            ProtocolId::MempoolDirectSendCompressed
+            | ProtocolId::StateSyncDirectSendCompressed
+            | ProtocolId::ConsensusObserverDirectSend => Encoding::CompressedBcs,
+            _ => Encoding::Bcs,
+        }
+    }
+
+    pub fn to_bytes<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
+        match self.encoding() {
+            Encoding::Bcs => bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e}),
+            Encoding::CompressedBcs => {
+                let bytes = bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e})?;
+                compression::compress(&bytes).map_err(|e| anyhow!("{:?}", e))
             }
-            _ => bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e}),
+            Encoding::Json => serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e)),
         }
     }
 
-    pub fn from_bytes<'a, T: Deserialize<'a>>(&self, bytes: &'a [u8]) -> anyhow::Result<T> {
-        match self {
-            ProtocolId::ConsensusDirectSendJson | ProtocolId::ConsensusRpcJson => {
-                serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e))
+    /// Deserializes a message received over this protocol. The size of decompressed messages is
+    /// limited to [`MAX_DECOMPRESSED_MESSAGE_SIZE`].
+    pub fn from_bytes<T: DeserializeOwned>(&self, bytes: &[u8]) -> anyhow::Result<T> {
+        match self.encoding() {
+            Encoding::Bcs => bcs::from_bytes(bytes).map_err(|e| anyhow! {"{:?}", e}),
+            Encoding::CompressedBcs => {
+                let bytes = compression::decompress(bytes, MAX_DECOMPRESSED_MESSAGE_SIZE)
+                    .map_err(|e| anyhow!("{:?}", e))?;
+                bcs::from_bytes(&bytes).map_err(|e| anyhow! {"{:?}", e})
             }
-            _ => bcs::from_bytes(bytes).map_err(|e| anyhow! {"{:?}", e}),
+            Encoding::Json => serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e)),
         }
     }
 }
@@ -169,6 +207,15 @@ impl ProtocolIdSet {
     pub fn contains(&self, protocol: ProtocolId) -> bool {
         self.0.is_set(protocol as u8)
     }
+
+    /// Returns the first of the given protocols, sorted from most to least preferred, that is
+    /// in the set.
+    pub fn preferred_protocol(&self, protocols: &[ProtocolId]) -> Option<ProtocolId> {
+        protocols
+            .iter()
+            .copied()
+            .find(|protocol| self.contains(*protocol))
+    }
 }
 
 impl FromIterator<ProtocolId> for ProtocolIdSet {
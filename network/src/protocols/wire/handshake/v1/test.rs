@@ -182,3 +182,47 @@ fn ignore_unknown_protocols() {
         ProtocolIdSet::empty(),
     );
 }
+
+#[test]
+fn compressed_protocols_round_trip() {
+    let value = vec![42u64; 1024];
+    for protocol in [
+        ProtocolId::MempoolDirectSendCompressed,
+        ProtocolId::StateSyncDirectSendCompressed,
+    ] {
+        let bytes = protocol.to_bytes(&value).unwrap();
+        assert!(bytes.len() < bcs::to_bytes(&value).unwrap().len());
+        assert_eq!(protocol.from_bytes::<Vec<u64>>(&bytes).unwrap(), value);
+
+        // Uncompressed messages can't be read by the compressed protocols
+        let bytes = ProtocolId::MempoolDirectSend.to_bytes(&value).unwrap();
+        protocol.from_bytes::<Vec<u64>>(&bytes).unwrap_err();
+    }
+}
+
+// Ensure peers that don't know about compression keep using uncompressed protocols.
+#[test]
+fn preferred_protocol_falls_back_to_uncompressed() {
+    let preferred = &[
+        ProtocolId::MempoolDirectSendCompressed,
+        ProtocolId::MempoolDirectSend,
+    ];
+    let ours = HandshakeMsg::from_supported(ProtocolIdSet::from_iter(preferred));
+    let old_peer =
+        HandshakeMsg::from_supported(ProtocolIdSet::from_iter([ProtocolId::MempoolDirectSend]));
+    let new_peer = HandshakeMsg::from_supported(ProtocolIdSet::from_iter(preferred));
+
+    let (_, common_protos) = ours.perform_handshake(&old_peer).unwrap();
+    assert_eq!(
+        common_protos.preferred_protocol(preferred),
+        Some(ProtocolId::MempoolDirectSend)
+    );
+
+    let (_, common_protos) = ours.perform_handshake(&new_peer).unwrap();
+    assert_eq!(
+        common_protos.preferred_protocol(preferred),
+        Some(ProtocolId::MempoolDirectSendCompressed)
+    );
+
+    assert_eq!(ProtocolIdSet::empty().preferred_protocol(preferred), None);
+}
@@ -7,6 +7,9 @@
 //! determine the version of messaging protocol to use. Each node only supports one version of the
 //! handshake protocol on an end-point, and that is advertised as part of its discovery
 //! NetworkAddress.
+//! Application messages sent over the messaging protocol may be compressed, as determined by
+//! their protocol id.
 
+pub mod compression;
 pub mod handshake;
 pub mod messaging;
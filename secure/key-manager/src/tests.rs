@@ -82,7 +82,8 @@ impl<T: DiemInterface> Node<T> {
         let timestamp = self.time.now_unix_time().as_micros() as u64;
         let owner_account = self.get_account_from_storage(OWNER_ACCOUNT);
         let block_id = HashValue::zero();
-        let block_metadata = BlockMetadata::new(block_id, 0, timestamp, vec![], owner_account);
+        let block_metadata =
+            BlockMetadata::new(block_id, 0, timestamp, vec![], owner_account, vec![]);
         let prologue = Transaction::BlockMetadata(block_metadata);
         block.insert(0, prologue);
 
@@ -338,7 +339,7 @@ fn setup_node_using_test_mocks() -> Node<MockDiemInterface> {
 
 // Creates and returns a diem database and database reader/writer pair bootstrapped with genesis.
 fn setup_diem_db(config: &NodeConfig) -> (Arc<DiemDB>, DbReaderWriter) {
-    let (storage, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(&config.storage.dir()));
+    let (storage, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
     executor_test_helpers::bootstrap_genesis::<DiemVM>(&db_rw, get_genesis_txn(config).unwrap())
         .expect("Failed to execute genesis");
 
@@ -0,0 +1,197 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{from_base64, to_base64, CryptoKVStorage, Error, GetResponse, KVStorage};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    Aes256Gcm,
+};
+use diem_crypto::hkdf::Hkdf;
+use diem_temppath::TempPath;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroize;
+
+const KEY_DERIVATION_INFO: &[u8] = b"DIEM_SECURE_STORAGE_ENCRYPTED_ON_DISK";
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const SALT_SIZE: usize = 32;
+
+/// The contents of the storage file. The salt is generated when the file is created and is kept
+/// for its lifetime, while a new nonce is used for every write.
+#[derive(Deserialize, Serialize)]
+struct EncryptedData {
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    salt: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    nonce: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    ciphertext: Vec<u8>,
+}
+
+/// EncryptedOnDiskStorage is a key value store persisted to a single file on the local filesystem,
+/// like OnDiskStorage, except that the data is encrypted at rest with AES-256-GCM. The encryption
+/// key is derived using HKDF-SHA256 from a secret (e.g., a passphrase or the contents of a key
+/// file) and a random salt stored alongside the data. As HKDF does not slow down brute forcing,
+/// the secret is expected to have high entropy. Writes are atomic: the data is written to a
+/// temporary file which then replaces the storage file.
+///
+/// Like OnDiskStorage, it provides no permission checks and is intended for single threads (or
+/// must be wrapped by a Arc<RwLock<>>).
+pub struct EncryptedOnDiskStorage {
+    file_path: PathBuf,
+    temp_path: TempPath,
+    salt: Vec<u8>,
+    key: Vec<u8>,
+    time_service: TimeService,
+}
+
+impl EncryptedOnDiskStorage {
+    pub fn new(file_path: PathBuf, secret: &[u8]) -> Self {
+        Self::new_with_time_service(file_path, secret, TimeService::real())
+    }
+
+    fn new_with_time_service(file_path: PathBuf, secret: &[u8], time_service: TimeService) -> Self {
+        // The parent will be one when only a filename is supplied. Therefore use the current
+        // working directory provided by PathBuf::new().
+        let file_dir = file_path
+            .parent()
+            .map_or(PathBuf::new(), |p| p.to_path_buf());
+
+        let existing_data = Self::read_encrypted_data(&file_path).expect("Unable to read storage");
+        let salt = match &existing_data {
+            Some(data) => data.salt.clone(),
+            None => {
+                let mut salt = vec![0; SALT_SIZE];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        };
+        // HKDF requires a minimum seed length, so the secret is first hashed, which allows for
+        // secrets of any length.
+        let key = Hkdf::<Sha256>::extract_then_expand(
+            Some(&salt),
+            &Sha256::digest(secret),
+            Some(KEY_DERIVATION_INFO),
+            KEY_SIZE,
+        )
+        .expect("Unable to derive the storage encryption key");
+
+        let storage = Self {
+            file_path,
+            temp_path: TempPath::new_with_temp_dir(file_dir),
+            salt,
+            key,
+            time_service,
+        };
+        if existing_data.is_none() {
+            storage
+                .write(&HashMap::new())
+                .expect("Unable to create storage");
+        }
+        storage
+    }
+
+    fn read_encrypted_data(file_path: &Path) -> Result<Option<EncryptedData>, Error> {
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        let mut file = File::open(file_path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        if contents.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    fn read(&self) -> Result<HashMap<String, Value>, Error> {
+        let data = match Self::read_encrypted_data(&self.file_path)? {
+            Some(data) => data,
+            None => return Ok(HashMap::new()),
+        };
+        if data.salt != self.salt || data.nonce.len() != NONCE_SIZE {
+            return Err(Error::EncryptionError(
+                "The storage file was modified externally".into(),
+            ));
+        }
+
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(&self.key));
+        let plaintext = cipher
+            .decrypt(
+                GenericArray::from_slice(&data.nonce),
+                data.ciphertext.as_slice(),
+            )
+            .map_err(|_| {
+                Error::EncryptionError("Unable to decrypt storage, is the secret correct?".into())
+            })?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
+        let plaintext = serde_json::to_vec(data)?;
+        let mut nonce = vec![0; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(&self.key));
+        let ciphertext = cipher
+            .encrypt(GenericArray::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| Error::EncryptionError("Unable to encrypt storage".into()))?;
+        let contents = serde_json::to_vec(&EncryptedData {
+            salt: self.salt.clone(),
+            nonce,
+            ciphertext,
+        })?;
+
+        let mut file = File::create(self.temp_path.path())?;
+        file.write_all(&contents)?;
+        file.sync_all()?;
+        fs::rename(&self.temp_path, &self.file_path)?;
+        Ok(())
+    }
+}
+
+impl KVStorage for EncryptedOnDiskStorage {
+    fn available(&self) -> Result<(), Error> {
+        self.read().map(|_| ())
+    }
+
+    fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
+        let mut data = self.read()?;
+        data.remove(key)
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))
+            .and_then(|value| serde_json::from_value(value).map_err(|e| e.into()))
+    }
+
+    fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let mut data = self.read()?;
+        data.insert(
+            key.to_string(),
+            serde_json::to_value(&GetResponse::new(value, now))?,
+        );
+        self.write(&data)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.write(&HashMap::new())
+    }
+}
+
+impl CryptoKVStorage for EncryptedOnDiskStorage {}
+
+impl Drop for EncryptedOnDiskStorage {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
@@ -21,6 +21,8 @@ pub enum Error {
     SerializationError(String),
     #[error("Key version not found, key name: {0}, version: {1}")]
     KeyVersionNotFound(String, String),
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
 }
 
 impl From<base64::DecodeError> for Error {
@@ -5,6 +5,7 @@
 
 mod crypto_kv_storage;
 mod crypto_storage;
+mod encrypted_on_disk;
 mod error;
 mod github;
 mod in_memory;
@@ -18,6 +19,7 @@ mod vault;
 pub use crate::{
     crypto_kv_storage::CryptoKVStorage,
     crypto_storage::{CryptoStorage, PublicKeyResponse},
+    encrypted_on_disk::EncryptedOnDiskStorage,
     error::Error,
     github::GitHubStorage,
     in_memory::InMemoryStorage,
@@ -0,0 +1,50 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{tests::suite, EncryptedOnDiskStorage, Error, KVStorage, Storage};
+use diem_temppath::TempPath;
+use std::fs;
+
+const SECRET: &[u8] = b"correct horse battery staple";
+
+#[test]
+fn encrypted_on_disk() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(EncryptedOnDiskStorage::new(path_buf, SECRET));
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+#[test]
+fn encrypted_on_disk_persists_data() {
+    let temp_path = TempPath::new();
+    let path_buf = temp_path.path().to_path_buf();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), SECRET);
+    storage.set("key", "plaintext_value").unwrap();
+
+    let contents = fs::read_to_string(&path_buf).unwrap();
+    assert!(!contents.contains("plaintext_value"));
+
+    let storage = EncryptedOnDiskStorage::new(path_buf, SECRET);
+    assert_eq!(
+        storage.get::<String>("key").unwrap().value,
+        "plaintext_value"
+    );
+}
+
+#[test]
+fn encrypted_on_disk_wrong_secret() {
+    let temp_path = TempPath::new();
+    let path_buf = temp_path.path().to_path_buf();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), SECRET);
+    storage.set("key", 1u64).unwrap();
+
+    let storage = EncryptedOnDiskStorage::new(path_buf, b"wrong secret");
+    assert!(matches!(
+        storage.available().unwrap_err(),
+        Error::EncryptionError(_)
+    ));
+    assert!(matches!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::EncryptionError(_)
+    ));
+}
@@ -0,0 +1,391 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `DiemNetDataClient`, an implementation of the
+//! `DiemDataClient` that fetches data from the storage services of peers over
+//! DiemNet. The client periodically polls the storage server summaries of all
+//! connected peers (see `DataSummaryPoller`) to build a global data summary,
+//! and uses the summaries to only send requests to peers advertising the
+//! requested data. Peers are scored according to the quality of their
+//! responses, and peers with low scores are ignored.
+
+use crate::{
+    diemnet::state::{ErrorType, PeerStates},
+    DataClientPayload, DataClientResponse, DiemDataClient, Error, GlobalDataSummary, ResponseError,
+};
+use async_trait::async_trait;
+use diem_config::network_id::PeerNetworkId;
+use diem_infallible::RwLock;
+use futures::future::join_all;
+use rand::seq::SliceRandom;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use storage_service_client::StorageServiceClient;
+use storage_service_types::{
+    AccountStatesChunkWithProofRequest, EpochEndingLedgerInfoRequest, StorageServiceRequest,
+    StorageServiceResponse, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+};
+
+mod state;
+#[cfg(test)]
+mod tests;
+
+// TODO(joshlind): make these configurable.
+/// Data client constants.
+pub const DATA_SUMMARY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The maximum number of responses tracked for `notify_bad_response()`.
+const MAX_TRACKED_RESPONSES: usize = 10_000;
+
+/// A `DiemDataClient` that fetches data from peers over DiemNet.
+///
+/// The client is cheap to clone: all clones share the same peer states and
+/// global data summary.
+#[derive(Clone, Debug)]
+pub struct DiemNetDataClient {
+    /// The network client used to send storage service requests to peers.
+    network_client: StorageServiceClient,
+    /// The storage summaries and scores of the peers.
+    peer_states: Arc<RwLock<PeerStates>>,
+    /// The latest global data summary, recomputed after each summary poll.
+    global_summary_cache: Arc<RwLock<GlobalDataSummary>>,
+    /// The peers that sent the most recent responses, keyed by response id.
+    response_id_to_peer: Arc<RwLock<BTreeMap<u64, PeerNetworkId>>>,
+    /// Generates the ids of the responses returned to callers.
+    next_response_id: Arc<AtomicU64>,
+}
+
+impl DiemNetDataClient {
+    /// Creates a new data client, along with the poller that must be started
+    /// (e.g., on a runtime) to keep the peer storage summaries up-to-date.
+    pub fn new(network_client: StorageServiceClient) -> (Self, DataSummaryPoller) {
+        let data_client = Self {
+            network_client,
+            peer_states: Arc::new(RwLock::new(PeerStates::default())),
+            global_summary_cache: Arc::new(RwLock::new(GlobalDataSummary::empty())),
+            response_id_to_peer: Arc::new(RwLock::new(BTreeMap::new())),
+            next_response_id: Arc::new(AtomicU64::new(0)),
+        };
+        let data_summary_poller =
+            DataSummaryPoller::new(data_client.clone(), DATA_SUMMARY_POLL_INTERVAL);
+        (data_client, data_summary_poller)
+    }
+
+    /// Fetches the storage server summaries of all connected peers and
+    /// recomputes the global data summary.
+    pub async fn poll_peer_summaries(&self) {
+        let peers = self.network_client.get_available_peers();
+        let poll_requests = peers.iter().map(|peer| self.poll_peer_summary(*peer));
+        join_all(poll_requests).await;
+        self.update_global_summary_cache();
+    }
+
+    /// Fetches the storage server summary of the given peer. Polling errors
+    /// only affect the peer score, so they are not returned.
+    async fn poll_peer_summary(&self, peer: PeerNetworkId) {
+        let response = self
+            .send_request_to_peer(peer, StorageServiceRequest::GetStorageServerSummary)
+            .await;
+        match response {
+            Ok((_, StorageServiceResponse::StorageServerSummary(summary))) => {
+                self.peer_states.write().update_summary(peer, summary)
+            }
+            Ok(_) => self
+                .peer_states
+                .write()
+                .update_score_error(peer, ErrorType::Malicious),
+            Err(_) => {
+                // The peer has already been penalized by `send_request_to_peer()`
+            }
+        }
+    }
+
+    /// Recomputes the global data summary from the summaries of the
+    /// currently connected peers.
+    fn update_global_summary_cache(&self) {
+        let peers = self.network_client.get_available_peers();
+        let global_data_summary = self.peer_states.read().calculate_aggregate_summary(&peers);
+        *self.global_summary_cache.write() = global_data_summary;
+    }
+
+    /// Chooses a connected peer that advertises the data required to service
+    /// the request. Peers with higher scores are more likely to be chosen.
+    fn choose_peer_for_request(
+        &self,
+        request: &StorageServiceRequest,
+    ) -> Result<PeerNetworkId, Error> {
+        let peer_states = self.peer_states.read();
+        let serviceable_peers: Vec<_> = self
+            .network_client
+            .get_available_peers()
+            .into_iter()
+            .filter(|peer| peer_states.can_service_request(peer, request))
+            .collect();
+        serviceable_peers
+            .choose_weighted(&mut rand::thread_rng(), |peer| peer_states.score(peer))
+            .copied()
+            .map_err(|_| {
+                Error::DataIsUnavailable(format!(
+                    "No connected peers are advertising data for the request: {:?}",
+                    request
+                ))
+            })
+    }
+
+    /// Sends the request to a peer that can service it and converts the
+    /// response into a data client payload using `extract_payload`. Responses
+    /// with an unexpected type penalize the peer.
+    async fn send_request_and_decode(
+        &self,
+        request: StorageServiceRequest,
+        extract_payload: impl FnOnce(StorageServiceResponse) -> Option<DataClientPayload>,
+    ) -> Result<DataClientResponse, Error> {
+        let peer = self.choose_peer_for_request(&request)?;
+        let (response_id, response) = self.send_request_to_peer(peer, request).await?;
+        let response_type = format!("{:?}", response);
+        match extract_payload(response) {
+            Some(response_payload) => Ok(DataClientResponse {
+                response_id,
+                response_payload,
+            }),
+            None => {
+                self.peer_states
+                    .write()
+                    .update_score_error(peer, ErrorType::Malicious);
+                Err(Error::UnexpectedErrorEncountered(format!(
+                    "Received an unexpected response type: {}",
+                    response_type
+                )))
+            }
+        }
+    }
+
+    /// Sends the request to the given peer, updates the peer score according
+    /// to the result, and tracks the response id so that the response can be
+    /// reported using `notify_bad_response()`.
+    async fn send_request_to_peer(
+        &self,
+        peer: PeerNetworkId,
+        request: StorageServiceRequest,
+    ) -> Result<(u64, StorageServiceResponse), Error> {
+        let result = self
+            .network_client
+            .send_request(peer, request, RESPONSE_TIMEOUT)
+            .await;
+
+        match result {
+            Ok(StorageServiceResponse::StorageServiceError(error)) => {
+                self.peer_states
+                    .write()
+                    .update_score_error(peer, ErrorType::NotUseful);
+                Err(Error::UnexpectedErrorEncountered(format!(
+                    "The peer responded with an error: {:?}",
+                    error
+                )))
+            }
+            Ok(response) => {
+                self.peer_states.write().update_score_success(peer);
+                let response_id = self.next_response_id.fetch_add(1, Ordering::Relaxed);
+                self.track_response(response_id, peer);
+                Ok((response_id, response))
+            }
+            Err(error) => {
+                self.peer_states
+                    .write()
+                    .update_score_error(peer, ErrorType::NotUseful);
+                Err(match error {
+                    storage_service_client::Error::TimeoutWaitingForResponse(error) => {
+                        Error::TimeoutWaitingForResponse(error)
+                    }
+                    error => Error::UnexpectedErrorEncountered(error.to_string()),
+                })
+            }
+        }
+    }
+
+    /// Remembers the peer that sent the response with the given id, forgetting
+    /// the oldest responses once too many are tracked.
+    fn track_response(&self, response_id: u64, peer: PeerNetworkId) {
+        let mut response_id_to_peer = self.response_id_to_peer.write();
+        response_id_to_peer.insert(response_id, peer);
+        while response_id_to_peer.len() > MAX_TRACKED_RESPONSES {
+            let oldest_response_id = *response_id_to_peer
+                .keys()
+                .next()
+                .expect("The tracked responses should not be empty!");
+            response_id_to_peer.remove(&oldest_response_id);
+        }
+    }
+}
+
+/// Returns the number of items in the inclusive range [start, end].
+fn num_items_in_range(start: u64, end: u64) -> Result<u64, Error> {
+    end.checked_sub(start)
+        .and_then(|delta| delta.checked_add(1))
+        .ok_or_else(|| {
+            Error::UnexpectedErrorEncountered(format!(
+                "Invalid range requested! Start: {:?}, end: {:?}",
+                start, end
+            ))
+        })
+}
+
+#[async_trait]
+impl DiemDataClient for DiemNetDataClient {
+    async fn get_account_states_with_proof(
+        &self,
+        version: u64,
+        start_index: u64,
+        end_index: u64,
+    ) -> Result<DataClientResponse, Error> {
+        num_items_in_range(start_index, end_index)?;
+        let request = StorageServiceRequest::GetAccountStatesChunkWithProof(
+            AccountStatesChunkWithProofRequest {
+                version,
+                start_account_index: start_index,
+                end_account_index: end_index,
+            },
+        );
+        self.send_request_and_decode(request, |response| match response {
+            StorageServiceResponse::AccountStatesChunkWithProof(account_states) => {
+                Some(DataClientPayload::AccountStatesWithProof(account_states))
+            }
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<DataClientResponse, Error> {
+        num_items_in_range(start_epoch, end_epoch)?;
+        let request =
+            StorageServiceRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+                start_epoch,
+                expected_end_epoch: end_epoch,
+            });
+        self.send_request_and_decode(request, |response| match response {
+            StorageServiceResponse::EpochEndingLedgerInfos(epoch_change_proof) => Some(
+                DataClientPayload::EpochEndingLedgerInfos(epoch_change_proof.ledger_info_with_sigs),
+            ),
+            _ => None,
+        })
+        .await
+    }
+
+    fn get_global_data_summary(&self) -> Result<DataClientResponse, Error> {
+        let global_data_summary = self.global_summary_cache.read().clone();
+        Ok(DataClientResponse {
+            response_id: self.next_response_id.fetch_add(1, Ordering::Relaxed),
+            response_payload: DataClientPayload::GlobalDataSummary(global_data_summary),
+        })
+    }
+
+    async fn get_number_of_account_states(
+        &self,
+        version: u64,
+    ) -> Result<DataClientResponse, Error> {
+        let request = StorageServiceRequest::GetNumberOfAccountsAtVersion(version);
+        self.send_request_and_decode(request, |response| match response {
+            StorageServiceResponse::NumberOfAccountsAtVersion(number_of_accounts) => {
+                Some(DataClientPayload::NumberOfAccountStates(number_of_accounts))
+            }
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_transaction_outputs_with_proof(
+        &self,
+        proof_version: u64,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<DataClientResponse, Error> {
+        let request = StorageServiceRequest::GetTransactionOutputsWithProof(
+            TransactionOutputsWithProofRequest {
+                proof_version,
+                start_version,
+                expected_num_outputs: num_items_in_range(start_version, end_version)?,
+            },
+        );
+        self.send_request_and_decode(request, |response| match response {
+            StorageServiceResponse::TransactionOutputsWithProof(outputs_with_proof) => Some(
+                DataClientPayload::TransactionOutputsWithProof(outputs_with_proof),
+            ),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_transactions_with_proof(
+        &self,
+        proof_version: u64,
+        start_version: u64,
+        end_version: u64,
+        include_events: bool,
+    ) -> Result<DataClientResponse, Error> {
+        let request =
+            StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+                proof_version,
+                start_version,
+                expected_num_transactions: num_items_in_range(start_version, end_version)?,
+                include_events,
+            });
+        self.send_request_and_decode(request, |response| match response {
+            StorageServiceResponse::TransactionsWithProof(transactions_with_proof) => Some(
+                DataClientPayload::TransactionsWithProof(transactions_with_proof),
+            ),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn notify_bad_response(
+        &self,
+        response_id: u64,
+        response_error: ResponseError,
+    ) -> Result<(), Error> {
+        // The response may no longer be tracked (e.g., if it's too old), in
+        // which case there's nobody to penalize.
+        let peer = self.response_id_to_peer.write().remove(&response_id);
+        if let Some(peer) = peer {
+            self.peer_states
+                .write()
+                .update_score_error(peer, response_error.into());
+        }
+        Ok(())
+    }
+}
+
+/// Periodically polls the storage server summaries of all connected peers, to
+/// keep the global data summary of the `DiemNetDataClient` up-to-date.
+pub struct DataSummaryPoller {
+    data_client: DiemNetDataClient,
+    poll_interval: Duration,
+}
+
+impl DataSummaryPoller {
+    fn new(data_client: DiemNetDataClient, poll_interval: Duration) -> Self {
+        Self {
+            data_client,
+            poll_interval,
+        }
+    }
+
+    /// Polls the peer summaries forever (this should be spawned on a runtime).
+    pub async fn start(self) {
+        let mut interval = tokio::time::interval(self.poll_interval);
+        loop {
+            interval.tick().await;
+            self.data_client.poll_peer_summaries().await;
+        }
+    }
+}
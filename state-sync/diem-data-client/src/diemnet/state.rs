@@ -0,0 +1,195 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{AdvertisedData, GlobalDataSummary, OptimalChunkSizes, ResponseError};
+use diem_config::network_id::PeerNetworkId;
+use std::collections::HashMap;
+use storage_service_types::{StorageServerSummary, StorageServiceRequest};
+
+/// Scores for peer rankings based on preferences and behavior.
+const MAX_SCORE: f64 = 100.0;
+const MIN_SCORE: f64 = 0.0;
+const STARTING_SCORE: f64 = 50.0;
+/// Add this score on a successful response.
+const SUCCESSFUL_RESPONSE_DELTA: f64 = 1.0;
+/// Not necessarily a malicious response, but not super useful.
+const NOT_USEFUL_MULTIPLIER: f64 = 0.95;
+/// Likely to be a malicious response.
+const MALICIOUS_MULTIPLIER: f64 = 0.8;
+/// Ignore a peer when their score dips below this threshold.
+const IGNORE_PEER_THRESHOLD: f64 = 25.0;
+
+/// The type of error that a peer is being penalized for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ErrorType {
+    /// A response or error that's not actively malicious but also doesn't help
+    /// us make progress, e.g., timeouts, remote errors, invalid data, etc...
+    NotUseful,
+    /// A response or error that appears to be actively hindering progress or
+    /// attempting to deceive us, e.g., invalid proof.
+    Malicious,
+}
+
+impl From<ResponseError> for ErrorType {
+    fn from(error: ResponseError) -> Self {
+        match error {
+            ResponseError::InvalidPayloadDataType | ResponseError::MissingData => {
+                ErrorType::NotUseful
+            }
+            ResponseError::ProofVerificationError => ErrorType::Malicious,
+        }
+    }
+}
+
+/// The state of a single peer, as seen by the data client.
+#[derive(Clone, Debug)]
+struct PeerState {
+    /// The latest storage summary advertised by the peer (if any).
+    storage_summary: Option<StorageServerSummary>,
+    /// The score of the peer, derived from the quality of its past responses.
+    score: f64,
+}
+
+impl Default for PeerState {
+    fn default() -> Self {
+        Self {
+            storage_summary: None,
+            score: STARTING_SCORE,
+        }
+    }
+}
+
+impl PeerState {
+    fn is_ignored(&self) -> bool {
+        self.score <= IGNORE_PEER_THRESHOLD
+    }
+
+    fn update_score_success(&mut self) {
+        self.score = f64::min(self.score + SUCCESSFUL_RESPONSE_DELTA, MAX_SCORE);
+    }
+
+    fn update_score_error(&mut self, error: ErrorType) {
+        let multiplier = match error {
+            ErrorType::NotUseful => NOT_USEFUL_MULTIPLIER,
+            ErrorType::Malicious => MALICIOUS_MULTIPLIER,
+        };
+        self.score = f64::max(self.score * multiplier, MIN_SCORE);
+    }
+}
+
+/// Tracks the storage summaries and scores of all the peers the data client
+/// has interacted with.
+#[derive(Debug, Default)]
+pub(crate) struct PeerStates {
+    peer_to_state: HashMap<PeerNetworkId, PeerState>,
+}
+
+impl PeerStates {
+    /// Returns true iff the peer is not ignored and advertises the data
+    /// required to service the given request.
+    pub fn can_service_request(
+        &self,
+        peer: &PeerNetworkId,
+        request: &StorageServiceRequest,
+    ) -> bool {
+        match self.peer_to_state.get(peer) {
+            Some(peer_state) => {
+                !peer_state.is_ignored()
+                    && peer_state
+                        .storage_summary
+                        .as_ref()
+                        .map_or(false, |summary| summary.data_summary.can_service(request))
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the score of the given peer, used to weight its selection.
+    pub fn score(&self, peer: &PeerNetworkId) -> f64 {
+        self.peer_to_state
+            .get(peer)
+            .map_or(STARTING_SCORE, |peer_state| peer_state.score)
+    }
+
+    pub fn update_summary(&mut self, peer: PeerNetworkId, summary: StorageServerSummary) {
+        self.peer_to_state.entry(peer).or_default().storage_summary = Some(summary);
+    }
+
+    pub fn update_score_success(&mut self, peer: PeerNetworkId) {
+        self.peer_to_state
+            .entry(peer)
+            .or_default()
+            .update_score_success();
+    }
+
+    pub fn update_score_error(&mut self, peer: PeerNetworkId, error: ErrorType) {
+        self.peer_to_state
+            .entry(peer)
+            .or_default()
+            .update_score_error(error);
+    }
+
+    /// Aggregates the storage summaries of the given peers (ignoring peers
+    /// with low scores) into a global data summary. The optimal chunk sizes
+    /// are the medians of the maximum chunk sizes supported by the peers.
+    pub fn calculate_aggregate_summary(&self, peers: &[PeerNetworkId]) -> GlobalDataSummary {
+        let mut aggregate_data = AdvertisedData {
+            account_states: vec![],
+            epoch_ending_ledger_infos: vec![],
+            synced_ledger_infos: vec![],
+            transactions: vec![],
+            transaction_outputs: vec![],
+        };
+        let mut max_account_states_chunk_sizes = vec![];
+        let mut max_epoch_chunk_sizes = vec![];
+        let mut max_transaction_chunk_sizes = vec![];
+        let mut max_transaction_output_chunk_sizes = vec![];
+
+        let summaries = peers
+            .iter()
+            .filter_map(|peer| self.peer_to_state.get(peer))
+            .filter(|peer_state| !peer_state.is_ignored())
+            .filter_map(|peer_state| peer_state.storage_summary.as_ref());
+        for summary in summaries {
+            let data_summary = &summary.data_summary;
+            aggregate_data
+                .account_states
+                .push(data_summary.account_states.clone());
+            aggregate_data
+                .epoch_ending_ledger_infos
+                .push(data_summary.epoch_ending_ledger_infos.clone());
+            aggregate_data
+                .synced_ledger_infos
+                .push(data_summary.synced_ledger_info.clone());
+            aggregate_data
+                .transactions
+                .push(data_summary.transactions.clone());
+            aggregate_data
+                .transaction_outputs
+                .push(data_summary.transaction_outputs.clone());
+
+            let protocol_metadata = &summary.protocol_metadata;
+            max_account_states_chunk_sizes.push(protocol_metadata.max_account_states_chunk_size);
+            max_epoch_chunk_sizes.push(protocol_metadata.max_epoch_chunk_size);
+            max_transaction_chunk_sizes.push(protocol_metadata.max_transaction_chunk_size);
+            max_transaction_output_chunk_sizes
+                .push(protocol_metadata.max_transaction_output_chunk_size);
+        }
+
+        GlobalDataSummary {
+            advertised_data: aggregate_data,
+            optimal_chunk_sizes: OptimalChunkSizes {
+                account_states_chunk_size: median(max_account_states_chunk_sizes),
+                epoch_chunk_size: median(max_epoch_chunk_sizes),
+                transaction_chunk_size: median(max_transaction_chunk_sizes),
+                transaction_output_chunk_size: median(max_transaction_output_chunk_sizes),
+            },
+        }
+    }
+}
+
+/// Returns the median of the given values, or 0 if there are none.
+fn median(mut values: Vec<u64>) -> u64 {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied().unwrap_or(0)
+}
@@ -0,0 +1,378 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{diemnet::DiemNetDataClient, DataClientPayload, DiemDataClient, Error, ResponseError};
+use bytes::Bytes;
+use channel::{diem_channel, message_queues::QueueStyle};
+use claim::{assert_err, assert_matches, assert_ok};
+use diem_config::network_id::{NetworkId, PeerNetworkId};
+use diem_crypto::HashValue;
+use diem_types::{
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::default_protocol::TransactionListWithProof,
+    PeerId,
+};
+use futures::{channel::oneshot, StreamExt};
+use maplit::hashmap;
+use network::{
+    application::{storage::PeerMetadataStorage, types::PeerInfo},
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::{network::NewNetworkSender, wire::handshake::v1::ProtocolIdSet},
+    transport::ConnectionMetadata,
+    ProtocolId,
+};
+use std::{collections::BTreeMap, iter::FromIterator, sync::Arc};
+use storage_service_client::{StorageServiceClient, StorageServiceNetworkSender};
+use storage_service_types::{
+    CompleteDataRange, DataSummary, ProtocolMetadata, StorageServerSummary, StorageServiceError,
+    StorageServiceMessage, StorageServiceRequest, StorageServiceResponse,
+};
+
+/// A mock of the network, used to inspect the requests sent by the data client
+/// and to respond to them.
+struct MockNetwork {
+    peer_mgr_reqs_rx: diem_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+}
+
+impl MockNetwork {
+    fn new() -> (Self, DiemNetDataClient) {
+        let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 10, None);
+        let (connection_reqs_tx, _connection_reqs_rx) =
+            diem_channel::new(QueueStyle::FIFO, 10, None);
+        let network_sender = StorageServiceNetworkSender::new(
+            PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        let peer_metadata_storage = PeerMetadataStorage::new(&[NetworkId::Validator]);
+        let network_client = StorageServiceClient::new(
+            hashmap! { NetworkId::Validator => network_sender },
+            peer_metadata_storage.clone(),
+        );
+        let (data_client, _) = DiemNetDataClient::new(network_client);
+
+        let mock_network = Self {
+            peer_mgr_reqs_rx,
+            peer_metadata_storage,
+        };
+        (mock_network, data_client)
+    }
+
+    /// Adds a new connected peer that supports the storage service.
+    fn add_peer(&self) -> PeerNetworkId {
+        let peer_id = PeerId::random();
+        let mut connection_metadata = ConnectionMetadata::mock(peer_id);
+        connection_metadata.application_protocols =
+            ProtocolIdSet::from_iter([ProtocolId::StorageServiceRpc]);
+        let peer = PeerNetworkId::new(NetworkId::Validator, peer_id);
+        self.peer_metadata_storage
+            .insert(peer, PeerInfo::new(connection_metadata));
+        peer
+    }
+
+    /// Returns the next request sent by the data client, along with the
+    /// channel to respond on.
+    async fn next_request(
+        &mut self,
+    ) -> (
+        PeerNetworkId,
+        StorageServiceRequest,
+        oneshot::Sender<Result<Bytes, network::protocols::network::RpcError>>,
+    ) {
+        match self.peer_mgr_reqs_rx.next().await.unwrap() {
+            PeerManagerRequest::SendRpc(peer_id, outbound_rpc_request) => {
+                let protocol_id = outbound_rpc_request.protocol_id;
+                assert_eq!(protocol_id, ProtocolId::StorageServiceRpc);
+                let message: StorageServiceMessage =
+                    protocol_id.from_bytes(&outbound_rpc_request.data).unwrap();
+                let request = match message {
+                    StorageServiceMessage::Request(request) => request,
+                    message => panic!("Unexpected message: {:?}", message),
+                };
+                let peer = PeerNetworkId::new(NetworkId::Validator, peer_id);
+                (peer, request, outbound_rpc_request.res_tx)
+            }
+            request => panic!("Unexpected peer manager request: {:?}", request),
+        }
+    }
+
+    /// Responds to the next request with the given response.
+    async fn respond_to_next_request(&mut self, response: StorageServiceResponse) -> PeerNetworkId {
+        let (peer, _, response_sender) = self.next_request().await;
+        let message = StorageServiceMessage::Response(response);
+        let response_bytes = ProtocolId::StorageServiceRpc.to_bytes(&message).unwrap();
+        response_sender.send(Ok(response_bytes.into())).unwrap();
+        peer
+    }
+}
+
+/// Creates a storage server summary advertising all data up to the given
+/// version, with the given maximum chunk size.
+fn create_storage_summary(highest_version: u64, max_chunk_size: u64) -> StorageServerSummary {
+    let block_info = BlockInfo::new(
+        0,
+        0,
+        HashValue::zero(),
+        HashValue::zero(),
+        highest_version,
+        0,
+        None,
+    );
+    let synced_ledger_info = LedgerInfoWithSignatures::new(
+        LedgerInfo::new(block_info, HashValue::zero()),
+        BTreeMap::new(),
+    );
+    StorageServerSummary {
+        protocol_metadata: ProtocolMetadata {
+            max_epoch_chunk_size: max_chunk_size,
+            max_transaction_chunk_size: max_chunk_size,
+            max_transaction_output_chunk_size: max_chunk_size,
+            max_account_states_chunk_size: max_chunk_size,
+        },
+        data_summary: DataSummary {
+            synced_ledger_info,
+            epoch_ending_ledger_infos: CompleteDataRange::new(0, 0),
+            transactions: CompleteDataRange::new(0, highest_version),
+            transaction_outputs: CompleteDataRange::new(0, highest_version),
+            account_states: CompleteDataRange::new(0, highest_version),
+        },
+    }
+}
+
+/// Polls the summaries of the given number of peers, responding to each poll
+/// with the given summary.
+async fn poll_peer_summaries(
+    mock_network: &mut MockNetwork,
+    data_client: &DiemNetDataClient,
+    num_peers: usize,
+    summary: StorageServerSummary,
+) {
+    let poll = data_client.poll_peer_summaries();
+    let respond = async {
+        for _ in 0..num_peers {
+            mock_network
+                .respond_to_next_request(StorageServiceResponse::StorageServerSummary(
+                    summary.clone(),
+                ))
+                .await;
+        }
+    };
+    futures::join!(poll, respond);
+}
+
+#[tokio::test]
+async fn request_works_only_when_data_available() {
+    let (mut mock_network, data_client) = MockNetwork::new();
+
+    // Requests fail if there are no peers
+    assert_matches!(
+        data_client
+            .get_transactions_with_proof(100, 50, 100, false)
+            .await,
+        Err(Error::DataIsUnavailable(_))
+    );
+
+    // Requests fail if the peers haven't advertised the data
+    let peer = mock_network.add_peer();
+    assert_matches!(
+        data_client
+            .get_transactions_with_proof(100, 50, 100, false)
+            .await,
+        Err(Error::DataIsUnavailable(_))
+    );
+
+    // Requests fail if the advertised data doesn't cover the request
+    poll_peer_summaries(
+        &mut mock_network,
+        &data_client,
+        1,
+        create_storage_summary(75, 1000),
+    )
+    .await;
+    assert_matches!(
+        data_client
+            .get_transactions_with_proof(100, 50, 100, false)
+            .await,
+        Err(Error::DataIsUnavailable(_))
+    );
+
+    // Requests are sent to the peer once it advertises the data
+    poll_peer_summaries(
+        &mut mock_network,
+        &data_client,
+        1,
+        create_storage_summary(200, 1000),
+    )
+    .await;
+    let request = data_client.get_transactions_with_proof(100, 50, 100, false);
+    let respond = async {
+        let (request_peer, request, response_sender) = mock_network.next_request().await;
+        assert_eq!(request_peer, peer);
+        match request {
+            StorageServiceRequest::GetTransactionsWithProof(request) => {
+                assert_eq!(request.start_version, 50);
+                assert_eq!(request.expected_num_transactions, 51);
+            }
+            request => panic!("Unexpected request: {:?}", request),
+        }
+        let message = StorageServiceMessage::Response(
+            StorageServiceResponse::TransactionsWithProof(TransactionListWithProof::new_empty()),
+        );
+        let response_bytes = ProtocolId::StorageServiceRpc.to_bytes(&message).unwrap();
+        response_sender.send(Ok(response_bytes.into())).unwrap();
+    };
+    let (response, _) = futures::join!(request, respond);
+    assert_matches!(
+        response.unwrap().response_payload,
+        DataClientPayload::TransactionsWithProof(_)
+    );
+}
+
+#[tokio::test]
+async fn global_data_summary_aggregates_peers() {
+    let (mut mock_network, data_client) = MockNetwork::new();
+
+    // The global data summary is empty before any peer is polled
+    let global_data_summary = match data_client
+        .get_global_data_summary()
+        .unwrap()
+        .response_payload
+    {
+        DataClientPayload::GlobalDataSummary(global_data_summary) => global_data_summary,
+        payload => panic!("Unexpected payload: {:?}", payload),
+    };
+    assert!(global_data_summary.advertised_data.transactions.is_empty());
+
+    // Poll three peers advertising different chunk sizes
+    for _ in 0..3 {
+        mock_network.add_peer();
+    }
+    let poll = data_client.poll_peer_summaries();
+    let respond = async {
+        for max_chunk_size in [10, 500, 1000] {
+            mock_network
+                .respond_to_next_request(StorageServiceResponse::StorageServerSummary(
+                    create_storage_summary(max_chunk_size, max_chunk_size),
+                ))
+                .await;
+        }
+    };
+    futures::join!(poll, respond);
+
+    // Verify the global data summary contains all advertised data and uses
+    // the median chunk sizes.
+    let global_data_summary = match data_client
+        .get_global_data_summary()
+        .unwrap()
+        .response_payload
+    {
+        DataClientPayload::GlobalDataSummary(global_data_summary) => global_data_summary,
+        payload => panic!("Unexpected payload: {:?}", payload),
+    };
+    let advertised_data = &global_data_summary.advertised_data;
+    assert_eq!(advertised_data.transactions.len(), 3);
+    assert_eq!(advertised_data.synced_ledger_infos.len(), 3);
+    for highest_version in [10, 500, 1000] {
+        assert!(advertised_data
+            .transactions
+            .contains(&CompleteDataRange::new(0, highest_version)));
+    }
+    let optimal_chunk_sizes = &global_data_summary.optimal_chunk_sizes;
+    assert_eq!(optimal_chunk_sizes.transaction_chunk_size, 500);
+    assert_eq!(optimal_chunk_sizes.epoch_chunk_size, 500);
+}
+
+#[tokio::test]
+async fn bad_peers_are_ignored() {
+    let (mut mock_network, data_client) = MockNetwork::new();
+    mock_network.add_peer();
+    poll_peer_summaries(
+        &mut mock_network,
+        &data_client,
+        1,
+        create_storage_summary(200, 1000),
+    )
+    .await;
+
+    // Fetch the number of accounts and notify the client that the responses
+    // failed proof verification, until the peer score drops below the threshold.
+    for _ in 0..4 {
+        let request = data_client.get_number_of_account_states(100);
+        let respond = mock_network
+            .respond_to_next_request(StorageServiceResponse::NumberOfAccountsAtVersion(10));
+        let (response, _) = futures::join!(request, respond);
+        let response_id = response.unwrap().response_id;
+        assert_ok!(
+            data_client
+                .notify_bad_response(response_id, ResponseError::ProofVerificationError)
+                .await
+        );
+    }
+
+    // The peer is now ignored, so nobody can service the request
+    assert_matches!(
+        data_client.get_number_of_account_states(100).await,
+        Err(Error::DataIsUnavailable(_))
+    );
+
+    // The ignored peer is no longer included in the global data summary
+    poll_peer_summaries(
+        &mut mock_network,
+        &data_client,
+        1,
+        create_storage_summary(200, 1000),
+    )
+    .await;
+    match data_client
+        .get_global_data_summary()
+        .unwrap()
+        .response_payload
+    {
+        DataClientPayload::GlobalDataSummary(global_data_summary) => {
+            assert!(global_data_summary.advertised_data.transactions.is_empty())
+        }
+        payload => panic!("Unexpected payload: {:?}", payload),
+    }
+}
+
+#[tokio::test]
+async fn error_responses_are_returned() {
+    let (mut mock_network, data_client) = MockNetwork::new();
+    mock_network.add_peer();
+    poll_peer_summaries(
+        &mut mock_network,
+        &data_client,
+        1,
+        create_storage_summary(200, 1000),
+    )
+    .await;
+
+    // A storage service error is returned as an error
+    let request = data_client.get_number_of_account_states(100);
+    let respond = mock_network.respond_to_next_request(
+        StorageServiceResponse::StorageServiceError(StorageServiceError::InternalError),
+    );
+    let (response, _) = futures::join!(request, respond);
+    assert_err!(response);
+
+    // A response of the wrong type is returned as an error
+    let request = data_client.get_number_of_account_states(100);
+    let respond =
+        mock_network.respond_to_next_request(StorageServiceResponse::NumberOfAccountsAtVersion(10));
+    let (response, _) = futures::join!(request, respond);
+    assert_ok!(response);
+    let request = data_client.get_epoch_ending_ledger_infos(0, 0);
+    let respond =
+        mock_network.respond_to_next_request(StorageServiceResponse::NumberOfAccountsAtVersion(10));
+    let (response, _) = futures::join!(request, respond);
+    assert_matches!(response, Err(Error::UnexpectedErrorEncountered(_)));
+
+    // Invalid ranges are rejected before any request is sent
+    assert_matches!(
+        data_client
+            .get_transactions_with_proof(100, 50, 49, false)
+            .await,
+        Err(Error::UnexpectedErrorEncountered(_))
+    );
+}
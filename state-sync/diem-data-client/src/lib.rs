@@ -3,7 +3,6 @@
 
 #![forbid(unsafe_code)]
 use async_trait::async_trait;
-use diem_crypto::hash::HashValue;
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
     ledger_info::LedgerInfoWithSignatures,
@@ -16,6 +15,8 @@ use serde::{Deserialize, Serialize};
 use storage_service_types::{CompleteDataRange, Epoch};
 use thiserror::Error;
 
+pub mod diemnet;
+
 /// An error returned by the Diem Data Client for failed API calls.
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
@@ -43,12 +44,13 @@ pub enum ResponseError {
 pub trait DiemDataClient {
     /// Returns a single account states chunk with proof, containing the accounts
     /// from start to end index (inclusive) at the specified version. The proof
-    /// version is the same as the specified version.
+    /// version is the same as the specified version. Accounts are indexed by
+    /// the order of their hashed keys in the account state tree.
     async fn get_account_states_with_proof(
         &self,
         version: u64,
-        start_index: HashValue,
-        end_index: HashValue,
+        start_index: u64,
+        end_index: u64,
     ) -> Result<DataClientResponse, Error>;
 
     /// Returns all epoch ending ledger infos between start and end (inclusive).
@@ -5,6 +5,7 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
+use diem_infallible::Mutex;
 use diem_types::{
     contract_event::ContractEvent, ledger_info::LedgerInfoWithSignatures, transaction::Transaction,
 };
@@ -16,6 +17,7 @@ use futures::{
 use serde::{Deserialize, Serialize};
 use std::{
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 use thiserror::Error;
@@ -54,9 +56,12 @@ pub fn new_consensus_notifier_listener_pair(
     timeout_ms: u64,
 ) -> (ConsensusNotifier, ConsensusNotificationListener) {
     let (notification_sender, notification_receiver) = mpsc::unbounded();
+    let observer_pause = ConsensusObserverPause::default();
 
-    let consensus_notifier = ConsensusNotifier::new(notification_sender, timeout_ms);
-    let consensus_listener = ConsensusNotificationListener::new(notification_receiver);
+    let consensus_notifier =
+        ConsensusNotifier::new(notification_sender, timeout_ms, observer_pause.clone());
+    let consensus_listener =
+        ConsensusNotificationListener::new(notification_receiver, observer_pause);
 
     (consensus_notifier, consensus_listener)
 }
@@ -73,18 +78,28 @@ pub struct ConsensusNotifier {
     /// Timeout for state sync to respond to consensus when handling a commit
     /// notification.
     timeout_ms: u64,
+
+    observer_pause: ConsensusObserverPause,
 }
 
 impl ConsensusNotifier {
     fn new(
         notification_sender: mpsc::UnboundedSender<ConsensusNotification>,
         timeout_ms: u64,
+        observer_pause: ConsensusObserverPause,
     ) -> Self {
         ConsensusNotifier {
             notification_sender,
             timeout_ms,
+            observer_pause,
         }
     }
+
+    /// Returns the pause shared with state sync, which the consensus observer (if any) has to
+    /// commit under.
+    pub fn observer_pause(&self) -> ConsensusObserverPause {
+        self.observer_pause.clone()
+    }
 }
 
 #[async_trait]
@@ -166,15 +181,26 @@ impl ConsensusNotificationSender for ConsensusNotifier {
 #[derive(Debug)]
 pub struct ConsensusNotificationListener {
     notification_receiver: mpsc::UnboundedReceiver<ConsensusNotification>,
+    observer_pause: ConsensusObserverPause,
 }
 
 impl ConsensusNotificationListener {
-    fn new(notification_receiver: mpsc::UnboundedReceiver<ConsensusNotification>) -> Self {
+    fn new(
+        notification_receiver: mpsc::UnboundedReceiver<ConsensusNotification>,
+        observer_pause: ConsensusObserverPause,
+    ) -> Self {
         ConsensusNotificationListener {
             notification_receiver,
+            observer_pause,
         }
     }
 
+    /// Returns the pause shared with the consensus observer (if any), which state sync has to
+    /// pause the observer with before syncing on its own.
+    pub fn observer_pause(&self) -> ConsensusObserverPause {
+        self.observer_pause.clone()
+    }
+
     /// Respond to the commit notification previously sent by consensus.
     pub async fn respond_to_commit_notification(
         &mut self,
@@ -268,6 +294,49 @@ impl ConsensusSyncNotification {
     }
 }
 
+/// Makes sure the consensus observer of a full node and state sync never commit to storage at the
+/// same time. State sync pauses the observer before syncing on its own (e.g., when the observer
+/// has stopped making progress), and resumes it once the observer asks to sync to a target, which
+/// the observer waits on before committing again.
+#[derive(Clone, Debug)]
+pub struct ConsensusObserverPause {
+    paused: Arc<Mutex<bool>>,
+}
+
+impl Default for ConsensusObserverPause {
+    fn default() -> Self {
+        Self {
+            paused: Arc::new(Mutex::new(false)),
+        }
+    }
+}
+
+impl ConsensusObserverPause {
+    /// Pauses the observer, waiting for the commit it is running (if any) to complete.
+    pub fn pause(&self) {
+        *self.paused.lock() = true;
+    }
+
+    pub fn resume(&self) {
+        *self.paused.lock() = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock()
+    }
+
+    /// Runs `commit` unless the observer is paused, in which case `None` is returned. The observer
+    /// can't be paused while `commit` is running.
+    pub fn commit_unless_paused<T>(&self, commit: impl FnOnce() -> T) -> Option<T> {
+        let paused = self.paused.lock();
+        if *paused {
+            None
+        } else {
+            Some(commit())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{ConsensusNotification, ConsensusNotificationSender, Error};
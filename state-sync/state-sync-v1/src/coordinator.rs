@@ -15,7 +15,7 @@ use crate::{
 };
 use consensus_notifications::{
     ConsensusCommitNotification, ConsensusNotification, ConsensusNotificationListener,
-    ConsensusSyncNotification,
+    ConsensusObserverPause, ConsensusSyncNotification,
 };
 use diem_config::{
     config::{NodeConfig, RoleType, StateSyncConfig},
@@ -85,6 +85,15 @@ pub(crate) struct StateSyncCoordinator<T, M> {
     config: StateSyncConfig,
     // role of node
     role: RoleType,
+    // Whether the consensus observer executes and commits blocks on this (full) node, in which
+    // case state sync only syncs on request, as for validators
+    consensus_observer_enabled: bool,
+    // The last time the consensus observer committed blocks or requested a sync (if ever). Until
+    // it makes progress within the observer's progress timeout, state sync pauses the observer and
+    // keeps syncing the node proactively.
+    last_observer_progress: Option<SystemTime>,
+    observer_progress_timeout: Duration,
+    observer_pause: ConsensusObserverPause,
     // An initial waypoint: for as long as the local version is less than a version determined by
     // waypoint a node is not going to be abl
     waypoint: Waypoint,
@@ -138,6 +147,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             network_senders,
         );
 
+        let observer_pause = consensus_listener.observer_pause();
         Ok(Self {
             client_events,
             mempool_notifier,
@@ -145,6 +155,12 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             local_state: initial_state,
             config: node_config.state_sync.clone(),
             role,
+            consensus_observer_enabled: node_config.consensus_observer.observer_enabled,
+            last_observer_progress: None,
+            observer_progress_timeout: Duration::from_millis(
+                node_config.consensus_observer.progress_timeout_ms,
+            ),
+            observer_pause,
             waypoint,
             request_manager,
             subscriptions: HashMap::new(),
@@ -374,10 +390,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             consensus_sync_notification: sync_notification,
         };
 
-        // Full nodes don't support sync requests
-        if self.role == RoleType::FullNode {
+        // Full nodes don't support sync requests, unless they run the consensus observer
+        if !self.is_driven_by_consensus() {
             return Err(Error::FullNodeSyncRequest);
         }
+        // The observer waits for the sync to complete before it commits again
+        self.last_observer_progress = Some(SystemTime::now());
+        self.observer_pause.resume();
 
         let local_li_version = self.local_state.committed_version();
         let target_version = request
@@ -479,6 +498,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 
         // Notify consensus of the commit response
         if let Some(commit_notification) = commit_notification {
+            self.last_observer_progress = Some(SystemTime::now());
             if let Err(error) = self
                 .notify_consensus_of_commit_response(commit_response, commit_notification)
                 .await
@@ -1478,7 +1498,34 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// therefore not write to storage. Reads are still permitted (e.g., to
     /// handle chunk requests).
     fn is_consensus_executing(&mut self) -> bool {
-        self.is_initialized() && self.role == RoleType::Validator && self.sync_request.is_none()
+        if self.is_initialized() && self.is_driven_by_consensus() && self.sync_request.is_none() {
+            if !self.is_consensus_observer_stalled() {
+                return true;
+            }
+            // The stalled observer must not commit while state sync writes to storage
+            self.observer_pause.pause();
+        }
+        false
+    }
+
+    /// Returns true if blocks are executed by consensus (or the consensus observer) on this node,
+    /// so that state sync only syncs when requested to.
+    fn is_driven_by_consensus(&self) -> bool {
+        self.role == RoleType::Validator || self.consensus_observer_enabled
+    }
+
+    /// Returns true if the consensus observer runs on this node but hasn't committed blocks (or
+    /// requested a sync) within its progress timeout, e.g., because it has no live subscription.
+    /// State sync then pauses the observer and keeps the node up to date proactively, as for other
+    /// full nodes.
+    fn is_consensus_observer_stalled(&self) -> bool {
+        self.role == RoleType::FullNode
+            && self.consensus_observer_enabled
+            && self.last_observer_progress.map_or(true, |last_progress| {
+                SystemTime::now()
+                    .duration_since(last_progress)
+                    .map_or(false, |elapsed| elapsed >= self.observer_progress_timeout)
+            })
     }
 
     /// Ensures that state sync is making progress:
@@ -1765,6 +1812,21 @@ mod tests {
         let process_result = block_on(full_node_coordinator.process_sync_request(sync_request));
         assert_matches!(process_result, Err(Error::FullNodeSyncRequest));
 
+        // Verify that fullnodes running the consensus observer can process sync requests
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.consensus_observer.observer_enabled = true;
+        let mut observer_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+        let (sync_request, mut callback_receiver) = create_sync_notification_at_version(0);
+        assert_ok!(block_on(
+            observer_coordinator.process_sync_request(sync_request)
+        ));
+        match callback_receiver.try_recv() {
+            Ok(Some(notification_result)) => assert_ok!(notification_result.result),
+            result => panic!("Expected okay but got: {:?}", result),
+        };
+
         // Create a coordinator for a validator node
         let mut validator_coordinator = test_utils::create_validator_coordinator();
 
@@ -1948,6 +2010,57 @@ mod tests {
         // TODO(joshlind): test that check progress passes when there are valid peers.
     }
 
+    #[test]
+    fn test_check_progress_with_consensus_observer() {
+        // Create a coordinator for a full node running the consensus observer
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.consensus_observer.observer_enabled = true;
+        let mut observer_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config.clone(), Waypoint::default());
+
+        // Verify state sync pauses the observer and keeps syncing (i.e., the no available peers
+        // error is returned) until the observer makes progress
+        let observer_pause = observer_coordinator.observer_pause.clone();
+        let progress_result = observer_coordinator.check_progress();
+        assert_matches!(progress_result, Err(Error::NoAvailablePeers(_)));
+        assert!(observer_pause.is_paused());
+
+        // Verify the observer is resumed by syncing to a target, after which state sync stops
+        let (sync_request, _callback_receiver) = create_sync_notification_at_version(0);
+        assert_ok!(block_on(
+            observer_coordinator.process_sync_request(sync_request)
+        ));
+        assert!(!observer_pause.is_paused());
+        assert_ok!(observer_coordinator.check_progress());
+        let (commit_notification, _) = create_commit_notification(vec![], vec![]);
+        assert_ok!(block_on(observer_coordinator.process_commit_notification(
+            vec![],
+            vec![],
+            Some(commit_notification),
+            None,
+        )));
+        assert_ok!(observer_coordinator.check_progress());
+
+        // Create an observer coordinator with a tiny progress timeout
+        node_config.consensus_observer.progress_timeout_ms = 0;
+        let mut observer_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+
+        // Verify state sync pauses the observer and syncs again once the observer stops making
+        // progress
+        let (commit_notification, _) = create_commit_notification(vec![], vec![]);
+        assert_ok!(block_on(observer_coordinator.process_commit_notification(
+            vec![],
+            vec![],
+            Some(commit_notification),
+            None,
+        )));
+        let progress_result = observer_coordinator.check_progress();
+        assert_matches!(progress_result, Err(Error::NoAvailablePeers(_)));
+        assert!(observer_coordinator.observer_pause.is_paused());
+    }
+
     #[test]
     fn test_new_and_lost_peers() {
         // Create a coordinator for a validator node
@@ -373,9 +373,7 @@ mod tests {
         let (genesis, _validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
 
         // Create test diem database
-        let db_path = diem_temppath::TempPath::new();
-        assert_ok!(db_path.create_as_dir());
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Boostrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
@@ -566,9 +564,7 @@ mod tests {
     #[test]
     fn test_missing_on_chain_config() {
         // Create a test diem database
-        let db_path = diem_temppath::TempPath::new();
-        db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Bootstrap the database with regular genesis
         let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
@@ -638,9 +634,7 @@ mod tests {
         let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
 
         // Create test diem database
-        let db_path = diem_temppath::TempPath::new();
-        assert_ok!(db_path.create_as_dir());
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Boostrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
@@ -710,6 +704,7 @@ mod tests {
             (index as u64 + 1) * 100000010,
             vec![],
             validator_account,
+            vec![],
         ))
     }
 
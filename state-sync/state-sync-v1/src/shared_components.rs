@@ -142,9 +142,7 @@ pub(crate) mod test_utils {
         let (genesis, _) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
 
         // Create test diem database
-        let db_path = diem_temppath::TempPath::new();
-        db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
 
         // Bootstrap the genesis transaction
         let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
@@ -2,7 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::streaming_client::Epoch;
-use diem_crypto::HashValue;
 use diem_data_client::DataClientResponse;
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
@@ -28,6 +27,9 @@ pub struct DataNotification {
 #[derive(Debug)]
 pub enum DataPayload {
     AccountStatesWithProof(AccountStatesChunkWithProof),
+    ContinuousTransactionOutputsWithProof(LedgerInfoWithSignatures, TransactionOutputListWithProof),
+    ContinuousTransactionsWithProof(LedgerInfoWithSignatures, TransactionListWithProof),
+    EndOfStream,
     EpochEndingLedgerInfos(Vec<LedgerInfoWithSignatures>),
     TransactionOutputsWithProof(TransactionOutputListWithProof),
     TransactionsWithProof(TransactionListWithProof),
@@ -39,6 +41,8 @@ pub enum DataPayload {
 pub struct SentDataNotification {
     pub client_request: DataClientRequest,
     pub client_response: DataClientResponse,
+    // The ledger info the payload was proven against (only for continuous streams)
+    pub target_ledger_info: Option<LedgerInfoWithSignatures>,
 }
 
 /// A request that has been sent to the Diem data client.
@@ -46,6 +50,7 @@ pub struct SentDataNotification {
 pub enum DataClientRequest {
     AccountsWithProof(AccountsWithProofRequest),
     EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest),
+    NumberOfAccounts(NumberOfAccountsRequest),
     TransactionsWithProof(TransactionsWithProofRequest),
     TransactionOutputsWithProof(TransactionOutputsWithProofRequest),
 }
@@ -54,8 +59,8 @@ pub enum DataClientRequest {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AccountsWithProofRequest {
     pub version: Version,
-    pub start_index: HashValue,
-    pub end_index: HashValue,
+    pub start_index: u64,
+    pub end_index: u64,
 }
 
 /// A client request for fetching epoch ending ledger infos.
@@ -65,6 +70,12 @@ pub struct EpochEndingLedgerInfosRequest {
     pub end_epoch: Epoch,
 }
 
+/// A client request for fetching the number of accounts at a version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NumberOfAccountsRequest {
+    pub version: Version,
+}
+
 /// A client request for fetching transactions with proofs.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TransactionsWithProofRequest {
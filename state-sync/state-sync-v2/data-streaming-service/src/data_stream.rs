@@ -8,11 +8,11 @@ use crate::{
     },
     error::Error,
     stream_progress_tracker::StreamProgressTracker,
-    streaming_client::StreamRequest,
+    streaming_client::{PayloadRefetchReason, RefetchNotificationPayloadRequest, StreamRequest},
 };
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_data_client::{
-    AdvertisedData, DataClientPayload, DataClientResponse, DiemDataClient, OptimalChunkSizes,
+    AdvertisedData, DataClientPayload, DataClientResponse, DiemDataClient, GlobalDataSummary,
     ResponseError,
 };
 use diem_infallible::Mutex;
@@ -68,6 +68,9 @@ pub struct DataStream<T> {
     // The channel on which to send data notifications when they are ready.
     notification_sender: channel::diem_channel::Sender<(), DataNotification>,
 
+    // True iff an end of stream notification has been sent to the client
+    end_of_stream_sent: bool,
+
     // A unique notification ID generator
     notification_id_generator: Arc<AtomicU64>,
 }
@@ -79,14 +82,56 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
         notification_id_generator: Arc<AtomicU64>,
         advertised_data: &AdvertisedData,
     ) -> Result<(Self, DataStreamListener), Error> {
+        // Create a new stream progress tracker
+        let stream_progress_tracker = StreamProgressTracker::new(stream_request, advertised_data)?;
+
+        Ok(Self::new_with_progress_tracker(
+            stream_progress_tracker,
+            diem_data_client,
+            notification_id_generator,
+        ))
+    }
+
+    /// Creates a new data stream that refetches the payload of the given sent
+    /// data notification. The Diem data client is notified of the bad response.
+    pub fn new_refetch_stream(
+        request: &RefetchNotificationPayloadRequest,
+        sent_data_notification: &SentDataNotification,
+        diem_data_client: T,
+        notification_id_generator: Arc<AtomicU64>,
+    ) -> (Self, DataStreamListener) {
+        // Create a new refetch progress tracker
+        let stream_progress_tracker =
+            StreamProgressTracker::new_refetch_stream_tracker(request, sent_data_notification);
+
+        // Create a new data stream
+        let (data_stream, data_stream_listener) = Self::new_with_progress_tracker(
+            stream_progress_tracker,
+            diem_data_client,
+            notification_id_generator,
+        );
+
+        // Notify the data client of the bad response
+        let response_error = match request.refetch_reason {
+            PayloadRefetchReason::InvalidPayloadData
+            | PayloadRefetchReason::PayloadTypeIsIncorrect => ResponseError::InvalidPayloadDataType,
+            PayloadRefetchReason::ProofVerificationFailed => ResponseError::ProofVerificationError,
+        };
+        data_stream.notify_bad_response(&sent_data_notification.client_response, response_error);
+
+        (data_stream, data_stream_listener)
+    }
+
+    fn new_with_progress_tracker(
+        stream_progress_tracker: StreamProgressTracker,
+        diem_data_client: T,
+        notification_id_generator: Arc<AtomicU64>,
+    ) -> (Self, DataStreamListener) {
         // Create a new data stream listener
         let (notification_sender, notification_receiver) =
             diem_channel::new(QueueStyle::KLAST, DATA_STREAM_CHANNEL_SIZE, None);
         let data_stream_listener = DataStreamListener::new(notification_receiver);
 
-        // Create a new stream progress tracker
-        let stream_progress_tracker = StreamProgressTracker::new(stream_request, advertised_data)?;
-
         // Create a new data stream
         let data_stream = Self {
             diem_data_client,
@@ -94,10 +139,11 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
             sent_data_requests: None,
             sent_notifications: HashMap::new(),
             notification_sender,
+            end_of_stream_sent: false,
             notification_id_generator,
         };
 
-        Ok((data_stream, data_stream_listener))
+        (data_stream, data_stream_listener)
     }
 
     /// Returns true iff the first batch of data client requests has been sent
@@ -108,24 +154,35 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
     /// Initializes the data client requests by sending out the first batch
     pub fn initialize_data_requests(
         &mut self,
-        optimal_chunk_sizes: OptimalChunkSizes,
+        global_data_summary: GlobalDataSummary,
     ) -> Result<(), Error> {
         // Initialize the data client requests queue
         self.sent_data_requests = Some(VecDeque::new());
 
         // Create and send the data client requests to the network
-        self.create_and_send_client_requests(MAX_CONCURRENT_REQUESTS, &optimal_chunk_sizes)
+        self.create_and_send_client_requests(&global_data_summary)
     }
 
-    /// Creates and sends a batch of diem data client requests (at most
-    /// `max_number_of_requests`).
+    /// Returns the data notification with the given ID if it was sent along
+    /// this stream.
+    pub fn get_sent_notification(
+        &self,
+        notification_id: &NotificationId,
+    ) -> Option<&SentDataNotification> {
+        self.sent_notifications.get(notification_id)
+    }
+
+    /// Creates and sends a batch of diem data client requests, such that there
+    /// are at most `MAX_CONCURRENT_REQUESTS` in-flight requests.
     fn create_and_send_client_requests(
         &mut self,
-        max_number_of_requests: u64,
-        optimal_chunk_sizes: &OptimalChunkSizes,
+        global_data_summary: &GlobalDataSummary,
     ) -> Result<(), Error> {
-        for client_request in
-            self.create_data_client_requests(max_number_of_requests, optimal_chunk_sizes)?
+        let num_in_flight_requests = self.get_sent_data_requests().len() as u64;
+        let max_number_of_requests = MAX_CONCURRENT_REQUESTS.saturating_sub(num_in_flight_requests);
+        for client_request in self
+            .stream_progress_tracker
+            .create_data_client_requests(max_number_of_requests, global_data_summary)?
         {
             // Send the client request
             let pending_client_response = self.send_client_request(client_request.clone());
@@ -141,21 +198,6 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
         Ok(())
     }
 
-    /// Creates a batch of diem data client requests (at most `max_number_of_requests`).
-    fn create_data_client_requests(
-        &mut self,
-        max_number_of_requests: u64,
-        optimal_chunk_sizes: &OptimalChunkSizes,
-    ) -> Result<Vec<DataClientRequest>, Error> {
-        match &mut self.stream_progress_tracker {
-            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => stream_tracker
-                .create_epoch_ending_client_requests(
-                    max_number_of_requests,
-                    optimal_chunk_sizes.epoch_chunk_size,
-                ),
-        }
-    }
-
     /// Sends a given request to the data client to be forwarded to the network
     /// and returns a pending client response.
     fn send_client_request(
@@ -173,28 +215,59 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
         // Send the request to the network
         let diem_data_client = self.diem_data_client.clone();
         let pending_response = pending_client_response.clone();
-        match data_client_request {
-            DataClientRequest::EpochEndingLedgerInfos(request) => {
-                tokio::spawn(async move {
-                    let client_response = diem_data_client
-                        .get_epoch_ending_ledger_infos(request.start_epoch, request.end_epoch);
-                    let client_response = client_response.await;
-                    pending_response.lock().client_response = Some(client_response);
-                });
-            }
-            _ => {
-                panic!("Data client request is currently unsupported!");
-            }
-        }
+        tokio::spawn(async move {
+            let client_response = match data_client_request {
+                DataClientRequest::AccountsWithProof(request) => {
+                    diem_data_client
+                        .get_account_states_with_proof(
+                            request.version,
+                            request.start_index,
+                            request.end_index,
+                        )
+                        .await
+                }
+                DataClientRequest::EpochEndingLedgerInfos(request) => {
+                    diem_data_client
+                        .get_epoch_ending_ledger_infos(request.start_epoch, request.end_epoch)
+                        .await
+                }
+                DataClientRequest::NumberOfAccounts(request) => {
+                    diem_data_client
+                        .get_number_of_account_states(request.version)
+                        .await
+                }
+                DataClientRequest::TransactionsWithProof(request) => {
+                    diem_data_client
+                        .get_transactions_with_proof(
+                            request.max_proof_version,
+                            request.start_version,
+                            request.end_version,
+                            request.include_events,
+                        )
+                        .await
+                }
+                DataClientRequest::TransactionOutputsWithProof(request) => {
+                    diem_data_client
+                        .get_transaction_outputs_with_proof(
+                            request.max_proof_version,
+                            request.start_version,
+                            request.end_version,
+                        )
+                        .await
+                }
+            };
+            pending_response.lock().client_response = Some(client_response);
+        });
 
         pending_client_response
     }
 
-    /// Processes any data client responses that have been received. Note: the
+    /// Processes any data client responses that have been received and sends
+    /// new data client requests to keep the stream progressing. Note: the
     /// responses must be processed in FIFO order.
     pub fn process_data_responses(
         &mut self,
-        optimal_chunk_sizes: OptimalChunkSizes,
+        global_data_summary: GlobalDataSummary,
     ) -> Result<(), Error> {
         for _ in 0..MAX_CONCURRENT_REQUESTS {
             // Get the data client response at the head of the queue if it's ready
@@ -210,15 +283,28 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
                             &pending_response.client_request,
                             client_response,
                         ) {
-                            // Send a data notification and make the next data client request
-                            self.send_data_notification_to_client(
-                                &pending_response.client_request,
-                                client_response,
-                            )?;
-                            self.create_and_send_client_requests(1, &optimal_chunk_sizes)?;
+                            // Send a data notification (if required) and make the
+                            // next data client request.
+                            if let Some(data_payload) = self
+                                .stream_progress_tracker
+                                .transform_client_response_into_payload(
+                                    &pending_response.client_request,
+                                    client_response,
+                                )?
+                            {
+                                self.send_data_notification_to_client(
+                                    &pending_response.client_request,
+                                    client_response,
+                                    data_payload,
+                                )?;
+                            }
+                            self.create_and_send_client_requests(&global_data_summary)?;
                         } else {
                             // Notify the data client and re-fetch the data
-                            self.notify_bad_response(client_response);
+                            self.notify_bad_response(
+                                client_response,
+                                ResponseError::InvalidPayloadDataType,
+                            );
                             return self
                                 .resend_data_client_request(&pending_response.client_request);
                         }
@@ -229,10 +315,16 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
                     }
                 }
             } else {
-                return Ok(()); // The first response hasn't arrived yet.
+                break; // The first response hasn't arrived yet.
             }
         }
-        Ok(())
+
+        // Notify the client if all the requested data has been sent
+        self.send_end_of_stream_notification()?;
+
+        // Ensure the stream keeps making progress (e.g., continuous streams
+        // may have new data to request even if no responses have arrived).
+        self.create_and_send_client_requests(&global_data_summary)
     }
 
     /// Pops and returns the first pending client response if the response has
@@ -279,9 +371,12 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
     }
 
     /// Notifies the Diem data client of a bad client response
-    fn notify_bad_response(&self, data_client_response: &DataClientResponse) {
+    fn notify_bad_response(
+        &self,
+        data_client_response: &DataClientResponse,
+        response_error: ResponseError,
+    ) {
         let response_id = data_client_response.response_id;
-        let response_error = ResponseError::InvalidPayloadDataType;
         let diem_data_client = self.diem_data_client.clone();
 
         tokio::spawn(async move {
@@ -291,21 +386,54 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
         });
     }
 
+    /// Sends an end of stream notification to the client if all the data
+    /// required by the stream has been sent (and the notification hasn't
+    /// already been sent).
+    fn send_end_of_stream_notification(&mut self) -> Result<(), Error> {
+        if self.end_of_stream_sent || !self.stream_progress_tracker.is_stream_complete() {
+            return Ok(());
+        }
+
+        let notification_id = self
+            .notification_id_generator
+            .fetch_add(1, Ordering::Relaxed);
+        let data_notification = DataNotification {
+            notification_id,
+            data_payload: DataPayload::EndOfStream,
+        };
+        self.notification_sender
+            .push((), data_notification)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+        self.end_of_stream_sent = true;
+
+        Ok(())
+    }
+
     /// Sends a data notification to the client along the stream
     fn send_data_notification_to_client(
         &mut self,
         data_client_request: &DataClientRequest,
         data_client_response: &DataClientResponse,
+        data_payload: DataPayload,
     ) -> Result<(), Error> {
         // Create a new notification id
         let notification_id = self
             .notification_id_generator
             .fetch_add(1, Ordering::Relaxed);
 
+        // Identify the ledger info the payload is proven against (if any)
+        let target_ledger_info = match &data_payload {
+            DataPayload::ContinuousTransactionOutputsWithProof(ledger_info, _)
+            | DataPayload::ContinuousTransactionsWithProof(ledger_info, _) => {
+                Some(ledger_info.clone())
+            }
+            _ => None,
+        };
+
         // Send a data notification to the client
         let data_notification = DataNotification {
             notification_id,
-            data_payload: extract_data_payload(data_client_response),
+            data_payload,
         };
         self.notification_sender
             .push((), data_notification)
@@ -315,6 +443,7 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
         let sent_data_notification = SentDataNotification {
             client_request: data_client_request.clone(),
             client_response: data_client_response.clone(),
+            target_ledger_info,
         };
         if let Some(existing_notification) = self
             .sent_notifications
@@ -391,31 +520,6 @@ impl FusedStream for DataStreamListener {
     }
 }
 
-/// Extracts the `DataPayload` out of a `DataClientResponse`. Assumes that the
-/// response has already been sanity checked.
-fn extract_data_payload(data_client_response: &DataClientResponse) -> DataPayload {
-    match &data_client_response.response_payload {
-        DataClientPayload::AccountStatesWithProof(accounts_chunk) => {
-            DataPayload::AccountStatesWithProof(accounts_chunk.clone())
-        }
-        DataClientPayload::EpochEndingLedgerInfos(ledger_infos) => {
-            DataPayload::EpochEndingLedgerInfos(ledger_infos.clone())
-        }
-        DataClientPayload::TransactionsWithProof(transactions_chunk) => {
-            DataPayload::TransactionsWithProof(transactions_chunk.clone())
-        }
-        DataClientPayload::TransactionOutputsWithProof(transactions_output_chunk) => {
-            DataPayload::TransactionOutputsWithProof(transactions_output_chunk.clone())
-        }
-        _ => {
-            panic!(
-                "The response was already sanity checked but is now type mismatched: {:?}",
-                data_client_response
-            );
-        }
-    }
-}
-
 /// Returns true iff the data client response payload matches the expected type
 /// of the original request. No other sanity checks are done.
 fn sanity_check_client_response(
@@ -435,6 +539,12 @@ fn sanity_check_client_response(
                 DataClientPayload::EpochEndingLedgerInfos(_)
             )
         }
+        DataClientRequest::NumberOfAccounts(_) => {
+            matches!(
+                data_client_response.response_payload,
+                DataClientPayload::NumberOfAccountStates(_)
+            )
+        }
         DataClientRequest::TransactionsWithProof(_) => {
             matches!(
                 data_client_response.response_payload,
@@ -3,21 +3,33 @@
 
 use crate::{
     data_notification::{
-        DataClientRequest, DataClientRequest::EpochEndingLedgerInfos,
-        EpochEndingLedgerInfosRequest, SentDataNotification,
+        AccountsWithProofRequest, DataClientRequest, DataClientRequest::EpochEndingLedgerInfos,
+        DataPayload, EpochEndingLedgerInfosRequest, NumberOfAccountsRequest, SentDataNotification,
+        TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
     },
     error::Error,
-    streaming_client::{Epoch, GetAllEpochEndingLedgerInfosRequest, StreamRequest},
+    streaming_client::{
+        Epoch, GetAllAccountsRequest, GetAllEpochEndingLedgerInfosRequest,
+        RefetchNotificationPayloadRequest, StreamRequest,
+    },
+};
+use diem_data_client::{
+    AdvertisedData, DataClientPayload, DataClientResponse, GlobalDataSummary, OptimalChunkSizes,
 };
-use diem_data_client::AdvertisedData;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::Version};
 use itertools::Itertools;
 use std::cmp;
 
 /// An enum holding different types of data streams and progress tracking
 /// indicators for tracking and serving that data along the stream.
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum StreamProgressTracker {
+    AccountsStreamTracker(AccountsStreamTracker),
+    ContinuousTransactionStreamTracker(ContinuousTransactionStreamTracker),
     EpochEndingStreamTracker(EpochEndingStreamTracker),
+    RefetchStreamTracker(RefetchStreamTracker),
+    TransactionStreamTracker(TransactionStreamTracker),
 }
 
 impl StreamProgressTracker {
@@ -26,16 +38,47 @@ impl StreamProgressTracker {
         advertised_data: &AdvertisedData,
     ) -> Result<StreamProgressTracker, Error> {
         match stream_request {
+            StreamRequest::GetAllAccounts(request) => Ok(
+                StreamProgressTracker::AccountsStreamTracker(AccountsStreamTracker::new(request)),
+            ),
             StreamRequest::GetAllEpochEndingLedgerInfos(request) => {
                 StreamProgressTracker::new_epoch_ending_stream_tracker(request, advertised_data)
             }
-            _ => Err(Error::UnsupportedRequestEncountered(format!(
-                "Stream request not currently supported: {:?}",
-                stream_request
-            ))),
+            StreamRequest::GetAllTransactions(_) | StreamRequest::GetAllTransactionOutputs(_) => {
+                Ok(StreamProgressTracker::TransactionStreamTracker(
+                    TransactionStreamTracker::new(stream_request)?,
+                ))
+            }
+            StreamRequest::ContinuouslyStreamTransactions(_)
+            | StreamRequest::ContinuouslyStreamTransactionOutputs(_) => {
+                Ok(StreamProgressTracker::ContinuousTransactionStreamTracker(
+                    ContinuousTransactionStreamTracker::new(stream_request)?,
+                ))
+            }
+            StreamRequest::RefetchNotificationPayload(_) => {
+                Err(Error::UnsupportedRequestEncountered(format!(
+                    "Refetch requests must be created using the original sent notification: {:?}",
+                    stream_request
+                )))
+            }
         }
     }
 
+    /// Creates a new progress tracker that refetches the payload of the given
+    /// sent data notification.
+    pub fn new_refetch_stream_tracker(
+        request: &RefetchNotificationPayloadRequest,
+        sent_data_notification: &SentDataNotification,
+    ) -> StreamProgressTracker {
+        StreamProgressTracker::RefetchStreamTracker(RefetchStreamTracker {
+            request: request.clone(),
+            client_request: sent_data_notification.client_request.clone(),
+            target_ledger_info: sent_data_notification.target_ledger_info.clone(),
+            client_request_sent: false,
+            notification_sent: false,
+        })
+    }
+
     fn new_epoch_ending_stream_tracker(
         request: &GetAllEpochEndingLedgerInfosRequest,
         advertised_data: &AdvertisedData,
@@ -77,33 +120,302 @@ impl StreamProgressTracker {
         ))
     }
 
+    /// Creates a batch of diem data client requests for the stream (at most
+    /// `max_number_of_requests`), using the given global data summary to
+    /// choose chunk sizes (and targets for continuous streams).
+    pub fn create_data_client_requests(
+        &mut self,
+        max_number_of_requests: u64,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<Vec<DataClientRequest>, Error> {
+        let optimal_chunk_sizes = &global_data_summary.optimal_chunk_sizes;
+        match self {
+            StreamProgressTracker::AccountsStreamTracker(stream_tracker) => stream_tracker
+                .create_accounts_client_requests(
+                    max_number_of_requests,
+                    optimal_chunk_sizes.account_states_chunk_size,
+                ),
+            StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => {
+                stream_tracker
+                    .create_continuous_client_requests(max_number_of_requests, global_data_summary)
+            }
+            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => {
+                if stream_tracker.next_request_epoch > stream_tracker.end_epoch {
+                    return Ok(vec![]); // All epochs have already been requested
+                }
+                stream_tracker.create_epoch_ending_client_requests(
+                    max_number_of_requests,
+                    optimal_chunk_sizes.epoch_chunk_size,
+                )
+            }
+            StreamProgressTracker::RefetchStreamTracker(stream_tracker) => {
+                Ok(stream_tracker.create_refetch_client_requests(max_number_of_requests))
+            }
+            StreamProgressTracker::TransactionStreamTracker(stream_tracker) => stream_tracker
+                .create_transaction_client_requests(max_number_of_requests, optimal_chunk_sizes),
+        }
+    }
+
+    /// Transforms the given client response into the payload of the data
+    /// notification to send along the stream. Returns `None` if the response
+    /// was only required internally (e.g., the number of accounts) and no
+    /// notification should be sent.
+    pub fn transform_client_response_into_payload(
+        &mut self,
+        client_request: &DataClientRequest,
+        client_response: &DataClientResponse,
+    ) -> Result<Option<DataPayload>, Error> {
+        match self {
+            StreamProgressTracker::AccountsStreamTracker(stream_tracker) => {
+                stream_tracker.transform_client_response_into_payload(client_response)
+            }
+            StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => {
+                stream_tracker.transform_client_response_into_payload(client_response)
+            }
+            StreamProgressTracker::RefetchStreamTracker(stream_tracker) => Ok(Some(
+                stream_tracker
+                    .transform_client_response_into_payload(client_request, client_response),
+            )),
+            StreamProgressTracker::EpochEndingStreamTracker(_)
+            | StreamProgressTracker::TransactionStreamTracker(_) => {
+                Ok(Some(extract_data_payload(client_response)))
+            }
+        }
+    }
+
     /// Updates the progress of the sent notifications using the given notification
     pub fn update_notification_progress(
         &mut self,
         sent_data_notification: &SentDataNotification,
     ) -> Result<(), Error> {
-        let StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) = self;
+        let client_request = &sent_data_notification.client_request;
+        match self {
+            StreamProgressTracker::AccountsStreamTracker(stream_tracker) => {
+                stream_tracker.update_notification_progress(client_request)
+            }
+            StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => {
+                stream_tracker.update_notification_progress(client_request)
+            }
+            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => {
+                stream_tracker.update_notification_progress(client_request)
+            }
+            StreamProgressTracker::RefetchStreamTracker(stream_tracker) => {
+                stream_tracker.update_notification_progress(client_request);
+                Ok(())
+            }
+            StreamProgressTracker::TransactionStreamTracker(stream_tracker) => {
+                stream_tracker.update_notification_progress(client_request)
+            }
+        }
+    }
 
-        match &sent_data_notification.client_request {
-            EpochEndingLedgerInfos(request) => {
-                let expected_next_epoch = stream_tracker.next_stream_epoch;
-                if request.start_epoch != expected_next_epoch
-                    || request.end_epoch < expected_next_epoch
+    /// Updates the progress of the requested data using the given data request
+    /// TODO(joshlind): look to clean up a lot of these range contains methods
+    pub fn update_request_progress(
+        &mut self,
+        client_request: &DataClientRequest,
+    ) -> Result<(), Error> {
+        match self {
+            StreamProgressTracker::AccountsStreamTracker(stream_tracker) => {
+                stream_tracker.update_request_progress(client_request)
+            }
+            StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => {
+                stream_tracker.update_request_progress(client_request)
+            }
+            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => {
+                stream_tracker.update_request_progress(client_request)
+            }
+            StreamProgressTracker::RefetchStreamTracker(stream_tracker) => {
+                stream_tracker.update_request_progress(client_request);
+                Ok(())
+            }
+            StreamProgressTracker::TransactionStreamTracker(stream_tracker) => {
+                stream_tracker.update_request_progress(client_request)
+            }
+        }
+    }
+
+    /// Returns true iff all the data required by the stream has been sent to
+    /// the client. Continuous streams never complete.
+    pub fn is_stream_complete(&self) -> bool {
+        match self {
+            StreamProgressTracker::AccountsStreamTracker(stream_tracker) => {
+                matches!(stream_tracker.number_of_accounts, Some(number_of_accounts)
+                    if stream_tracker.next_stream_index >= number_of_accounts)
+            }
+            StreamProgressTracker::ContinuousTransactionStreamTracker(_) => false,
+            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => {
+                stream_tracker.next_stream_epoch > stream_tracker.end_epoch
+            }
+            StreamProgressTracker::RefetchStreamTracker(stream_tracker) => {
+                stream_tracker.notification_sent
+            }
+            StreamProgressTracker::TransactionStreamTracker(stream_tracker) => {
+                stream_tracker.next_stream_version > stream_tracker.end_version
+            }
+        }
+    }
+
+    /// Verifies that the data required by the stream can be satisfied using the
+    /// currently advertised data in the network. If not, returns an error.
+    pub fn ensure_data_is_available(&self, advertised_data: &AdvertisedData) -> Result<(), Error> {
+        let data_is_available = match self {
+            StreamProgressTracker::AccountsStreamTracker(stream_tracker) => {
+                stream_tracker.accounts_available(advertised_data)
+            }
+            StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => {
+                stream_tracker.transactions_available(advertised_data)
+            }
+            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => {
+                stream_tracker.epoch_ending_ledger_infos_available(advertised_data)
+            }
+            StreamProgressTracker::RefetchStreamTracker(_) => {
+                true // The payload was already fetched once
+            }
+            StreamProgressTracker::TransactionStreamTracker(stream_tracker) => {
+                stream_tracker.transactions_available(advertised_data)
+            }
+        };
+
+        if data_is_available {
+            Ok(())
+        } else {
+            Err(Error::DataIsUnavailable(format!(
+                "Unable to satisfy requested data stream: {:?}, with advertised data: {:?}",
+                self, advertised_data
+            )))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AccountsStreamTracker {
+    // The original accounts request made by the client
+    pub request: GetAllAccountsRequest,
+
+    // The total number of accounts at the requested version. This is only
+    // known once the number of accounts has been fetched from the network.
+    pub number_of_accounts: Option<u64>,
+
+    // True iff the number of accounts has been requested from the network
+    pub number_of_accounts_requested: bool,
+
+    // The next account index that we're waiting to send to the client along
+    // the stream. All accounts before this have already been sent.
+    pub next_stream_index: u64,
+
+    // The next account index that we're waiting to request from the network.
+    // All accounts before this have already been requested.
+    pub next_request_index: u64,
+}
+
+impl AccountsStreamTracker {
+    fn new(request: &GetAllAccountsRequest) -> Self {
+        Self {
+            request: request.clone(),
+            number_of_accounts: None,
+            number_of_accounts_requested: false,
+            next_stream_index: 0,
+            next_request_index: 0,
+        }
+    }
+
+    /// Returns true iff the account states at the requested version are
+    /// available in the advertised data.
+    pub fn accounts_available(&self, advertised_data: &AdvertisedData) -> bool {
+        advertised_data
+            .account_states
+            .iter()
+            .any(|account_range| account_range.contains(self.request.version))
+    }
+
+    /// Creates account payload requests for the Diem data client. If the
+    /// number of accounts is not yet known, it is requested first. At most
+    /// `max_number_of_requests` will be created.
+    pub fn create_accounts_client_requests(
+        &mut self,
+        max_number_of_requests: u64,
+        optimal_account_chunk_size: u64,
+    ) -> Result<Vec<DataClientRequest>, Error> {
+        if max_number_of_requests == 0 {
+            return Ok(vec![]);
+        }
+
+        let number_of_accounts = match self.number_of_accounts {
+            Some(number_of_accounts) => number_of_accounts,
+            None => {
+                // We must wait for the number of accounts before requesting chunks
+                if self.number_of_accounts_requested {
+                    return Ok(vec![]);
+                }
+                return Ok(vec![DataClientRequest::NumberOfAccounts(
+                    NumberOfAccountsRequest {
+                        version: self.request.version,
+                    },
+                )]);
+            }
+        };
+
+        if self.next_request_index >= number_of_accounts {
+            return Ok(vec![]); // All accounts have already been requested
+        }
+        let end_index = number_of_accounts
+            .checked_sub(1)
+            .ok_or_else(|| Error::IntegerOverflow("End account index has overflown!".into()))?;
+
+        let version = self.request.version;
+        create_data_client_request_batch(
+            self.next_request_index,
+            end_index,
+            max_number_of_requests,
+            optimal_account_chunk_size,
+            |start_index, end_index| {
+                DataClientRequest::AccountsWithProof(AccountsWithProofRequest {
+                    version,
+                    start_index,
+                    end_index,
+                })
+            },
+        )
+    }
+
+    fn transform_client_response_into_payload(
+        &mut self,
+        client_response: &DataClientResponse,
+    ) -> Result<Option<DataPayload>, Error> {
+        if let DataClientPayload::NumberOfAccountStates(number_of_accounts) =
+            client_response.response_payload
+        {
+            self.number_of_accounts = Some(number_of_accounts);
+            Ok(None)
+        } else {
+            Ok(Some(extract_data_payload(client_response)))
+        }
+    }
+
+    fn update_notification_progress(
+        &mut self,
+        client_request: &DataClientRequest,
+    ) -> Result<(), Error> {
+        match client_request {
+            DataClientRequest::AccountsWithProof(request) => {
+                let expected_next_index = self.next_stream_index;
+                if request.start_index != expected_next_index
+                    || request.end_index < expected_next_index
                 {
                     panic!(
-                        "Updating an epoch ending tracker with an old notification! Given {:?} but expected epoch: {:?}",
-                        request, expected_next_epoch
+                        "Updating an accounts tracker with an old notification! Given {:?} but expected index: {:?}",
+                        request, expected_next_index
                     );
                 }
-                stream_tracker.next_stream_epoch =
-                    request.end_epoch.checked_add(1).ok_or_else(|| {
-                        Error::IntegerOverflow("Next stream epoch has overflown!".into())
-                    })?;
+                self.next_stream_index = request.end_index.checked_add(1).ok_or_else(|| {
+                    Error::IntegerOverflow("Next stream index has overflown!".into())
+                })?;
             }
             _ => {
                 panic!(
                     "Invalid client request {:?} found for the data stream tracker {:?}",
-                    sent_data_notification.client_request, stream_tracker
+                    client_request, self
                 );
             }
         }
@@ -111,56 +423,327 @@ impl StreamProgressTracker {
         Ok(())
     }
 
-    /// Updates the progress of the requested data using the given data request
-    /// TODO(joshlind): look to clean up a lot of these range contains methods
-    pub fn update_request_progress(
-        &mut self,
-        client_request: &DataClientRequest,
-    ) -> Result<(), Error> {
-        let StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) = self;
-
+    fn update_request_progress(&mut self, client_request: &DataClientRequest) -> Result<(), Error> {
         match client_request {
-            EpochEndingLedgerInfos(request) => {
-                let expected_next_epoch = stream_tracker.next_request_epoch;
-                if request.start_epoch != expected_next_epoch
-                    || request.end_epoch < expected_next_epoch
+            DataClientRequest::NumberOfAccounts(request) => {
+                if request.version != self.request.version || self.number_of_accounts_requested {
+                    panic!(
+                        "Updating an accounts tracker with an unexpected number of accounts request! Given {:?}",
+                        request
+                    );
+                }
+                self.number_of_accounts_requested = true;
+            }
+            DataClientRequest::AccountsWithProof(request) => {
+                let expected_next_index = self.next_request_index;
+                if request.start_index != expected_next_index
+                    || request.end_index < expected_next_index
                 {
                     panic!(
-                        "Updating an epoch ending tracker with an old request! Given {:?} but expected epoch: {:?}",
-                        request, expected_next_epoch
+                        "Updating an accounts tracker with an old request! Given {:?} but expected index: {:?}",
+                        request, expected_next_index
                     );
                 }
-                stream_tracker.next_request_epoch =
-                    request.end_epoch.checked_add(1).ok_or_else(|| {
-                        Error::IntegerOverflow("Next stream epoch has overflown!".into())
-                    })?;
+                self.next_request_index = request.end_index.checked_add(1).ok_or_else(|| {
+                    Error::IntegerOverflow("Next request index has overflown!".into())
+                })?;
             }
             _ => {
                 panic!(
                     "Invalid client request {:?} found for the data stream tracker {:?}",
-                    client_request, stream_tracker
+                    client_request, self
                 );
             }
         }
 
         Ok(())
     }
+}
 
-    /// Verifies that the data required by the stream can be satisfied using the
-    /// currently advertised data in the network. If not, returns an error.
-    pub fn ensure_data_is_available(&self, advertised_data: &AdvertisedData) -> Result<(), Error> {
-        match self {
-            StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => {
-                if stream_tracker.epoch_ending_ledger_infos_available(advertised_data) {
-                    return Ok(());
+#[derive(Debug)]
+pub struct ContinuousTransactionStreamTracker {
+    // The original continuous transaction (or transaction output) request
+    // made by the client
+    pub request: StreamRequest,
+
+    // The ledger info that all in-flight data requests are proven against.
+    // This is `None` until a target has been chosen from the advertised data.
+    pub current_target_ledger_info: Option<LedgerInfoWithSignatures>,
+
+    // True iff the epoch ending ledger info for `next_request_epoch` has been
+    // requested from the network (to be used as the next target).
+    pub end_of_epoch_requested: bool,
+
+    // The epoch of the next data to request from the network
+    pub next_request_epoch: Epoch,
+
+    // The next version that we're waiting to send to the client along the
+    // stream. All versions before this have already been sent.
+    pub next_stream_version: Version,
+
+    // The next version that we're waiting to request from the network. All
+    // versions before this have already been requested.
+    pub next_request_version: Version,
+}
+
+impl ContinuousTransactionStreamTracker {
+    fn new(stream_request: &StreamRequest) -> Result<Self, Error> {
+        let (start_version, start_epoch) = match stream_request {
+            StreamRequest::ContinuouslyStreamTransactions(request) => {
+                (request.start_version, request.start_epoch)
+            }
+            StreamRequest::ContinuouslyStreamTransactionOutputs(request) => {
+                (request.start_version, request.start_epoch)
+            }
+            request => {
+                return Err(Error::UnsupportedRequestEncountered(format!(
+                    "Invalid request for a continuous transaction stream: {:?}",
+                    request
+                )))
+            }
+        };
+
+        Ok(Self {
+            request: stream_request.clone(),
+            current_target_ledger_info: None,
+            end_of_epoch_requested: false,
+            next_request_epoch: start_epoch,
+            next_stream_version: start_version,
+            next_request_version: start_version,
+        })
+    }
+
+    /// Returns true iff the data at the start of the stream has not been pruned
+    /// by all advertising peers. Data after the start version may still be
+    /// missing, in which case the stream waits for the blockchain to grow.
+    pub fn transactions_available(&self, advertised_data: &AdvertisedData) -> bool {
+        let advertised_ranges = match &self.request {
+            StreamRequest::ContinuouslyStreamTransactionOutputs(_) => {
+                &advertised_data.transaction_outputs
+            }
+            _ => &advertised_data.transactions,
+        };
+        advertised_ranges
+            .iter()
+            .any(|range| range.lowest <= self.next_stream_version)
+    }
+
+    /// Creates continuous payload requests for the Diem data client. If there
+    /// is no current target ledger info, a new target is selected from the
+    /// highest advertised ledger info. If the target is in a later epoch, the
+    /// epoch ending ledger info of the current epoch is fetched and used as
+    /// the target instead. At most `max_number_of_requests` will be created.
+    pub fn create_continuous_client_requests(
+        &mut self,
+        max_number_of_requests: u64,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<Vec<DataClientRequest>, Error> {
+        if max_number_of_requests == 0 {
+            return Ok(vec![]);
+        }
+
+        // Identify the target ledger info (or fetch a new one)
+        let target_ledger_info = match &self.current_target_ledger_info {
+            Some(target_ledger_info) => target_ledger_info.clone(),
+            None => {
+                let highest_ledger_info =
+                    match highest_synced_ledger_info(&global_data_summary.advertised_data) {
+                        Some(highest_ledger_info) => highest_ledger_info,
+                        None => return Ok(vec![]), // Nothing is advertised yet
+                    };
+                if highest_ledger_info.ledger_info().epoch() > self.next_request_epoch {
+                    // The epoch has changed, so the target is the epoch ending ledger info
+                    if self.end_of_epoch_requested {
+                        return Ok(vec![]);
+                    }
+                    return Ok(vec![DataClientRequest::EpochEndingLedgerInfos(
+                        EpochEndingLedgerInfosRequest {
+                            start_epoch: self.next_request_epoch,
+                            end_epoch: self.next_request_epoch,
+                        },
+                    )]);
+                } else if highest_ledger_info.ledger_info().version() < self.next_request_version {
+                    return Ok(vec![]); // We're already up-to-date
                 }
+                self.current_target_ledger_info = Some(highest_ledger_info.clone());
+                highest_ledger_info
             }
+        };
+
+        // Create the requests up to the target version
+        let target_version = target_ledger_info.ledger_info().version();
+        if self.next_request_version > target_version {
+            return Ok(vec![]); // Wait for the stream to reach the current target
         }
+        let optimal_chunk_sizes = &global_data_summary.optimal_chunk_sizes;
+        match &self.request {
+            StreamRequest::ContinuouslyStreamTransactions(request) => {
+                let include_events = request.include_events;
+                create_data_client_request_batch(
+                    self.next_request_version,
+                    target_version,
+                    max_number_of_requests,
+                    optimal_chunk_sizes.transaction_chunk_size,
+                    |start_version, end_version| {
+                        DataClientRequest::TransactionsWithProof(TransactionsWithProofRequest {
+                            start_version,
+                            end_version,
+                            max_proof_version: target_version,
+                            include_events,
+                        })
+                    },
+                )
+            }
+            _ => create_data_client_request_batch(
+                self.next_request_version,
+                target_version,
+                max_number_of_requests,
+                optimal_chunk_sizes.transaction_output_chunk_size,
+                |start_version, end_version| {
+                    DataClientRequest::TransactionOutputsWithProof(
+                        TransactionOutputsWithProofRequest {
+                            start_version,
+                            end_version,
+                            max_proof_version: target_version,
+                        },
+                    )
+                },
+            ),
+        }
+    }
 
-        Err(Error::DataIsUnavailable(format!(
-            "Unable to satisfy requested data stream: {:?}, with advertised data: {:?}",
-            self, advertised_data
-        )))
+    fn transform_client_response_into_payload(
+        &mut self,
+        client_response: &DataClientResponse,
+    ) -> Result<Option<DataPayload>, Error> {
+        match &client_response.response_payload {
+            DataClientPayload::EpochEndingLedgerInfos(ledger_infos) => {
+                match &ledger_infos[..] {
+                    [ledger_info]
+                        if ledger_info.ledger_info().epoch() == self.next_request_epoch
+                            && ledger_info.ledger_info().ends_epoch() =>
+                    {
+                        self.current_target_ledger_info = Some(ledger_info.clone());
+                        Ok(None)
+                    }
+                    _ => {
+                        // Allow the epoch ending ledger info to be requested again
+                        self.end_of_epoch_requested = false;
+                        Err(Error::DiemDataClientResponseIsInvalid(format!(
+                            "Expected the epoch ending ledger info for epoch: {:?}, but got: {:?}",
+                            self.next_request_epoch, ledger_infos
+                        )))
+                    }
+                }
+            }
+            DataClientPayload::TransactionsWithProof(transactions_chunk) => {
+                Ok(Some(DataPayload::ContinuousTransactionsWithProof(
+                    self.get_target_ledger_info()?,
+                    transactions_chunk.clone(),
+                )))
+            }
+            DataClientPayload::TransactionOutputsWithProof(transaction_outputs_chunk) => {
+                Ok(Some(DataPayload::ContinuousTransactionOutputsWithProof(
+                    self.get_target_ledger_info()?,
+                    transaction_outputs_chunk.clone(),
+                )))
+            }
+            _ => Ok(Some(extract_data_payload(client_response))),
+        }
+    }
+
+    fn get_target_ledger_info(&self) -> Result<LedgerInfoWithSignatures, Error> {
+        self.current_target_ledger_info.clone().ok_or_else(|| {
+            Error::UnexpectedErrorEncountered(
+                "No target ledger info found for the continuous stream!".into(),
+            )
+        })
+    }
+
+    fn update_notification_progress(
+        &mut self,
+        client_request: &DataClientRequest,
+    ) -> Result<(), Error> {
+        let (start_version, end_version) = self.get_request_versions(client_request);
+        let expected_next_version = self.next_stream_version;
+        if start_version != expected_next_version || end_version < expected_next_version {
+            panic!(
+                "Updating a continuous transaction tracker with an old notification! Given {:?} but expected version: {:?}",
+                client_request, expected_next_version
+            );
+        }
+        self.next_stream_version = end_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Next stream version has overflown!".into()))?;
+
+        // Check if we've reached the current target
+        if let Some(target_ledger_info) = &self.current_target_ledger_info {
+            let target_ledger_info = target_ledger_info.ledger_info();
+            if end_version == target_ledger_info.version() {
+                if target_ledger_info.ends_epoch() {
+                    self.next_request_epoch =
+                        self.next_request_epoch.checked_add(1).ok_or_else(|| {
+                            Error::IntegerOverflow("Next request epoch has overflown!".into())
+                        })?;
+                }
+                self.current_target_ledger_info = None;
+                self.end_of_epoch_requested = false;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_request_progress(&mut self, client_request: &DataClientRequest) -> Result<(), Error> {
+        if let DataClientRequest::EpochEndingLedgerInfos(request) = client_request {
+            let expected_epoch = self.next_request_epoch;
+            if request.start_epoch != expected_epoch
+                || request.end_epoch != expected_epoch
+                || self.end_of_epoch_requested
+            {
+                panic!(
+                    "Updating a continuous transaction tracker with an unexpected epoch ending request! Given {:?} but expected epoch: {:?}",
+                    request, expected_epoch
+                );
+            }
+            self.end_of_epoch_requested = true;
+            return Ok(());
+        }
+
+        let (start_version, end_version) = self.get_request_versions(client_request);
+        let expected_next_version = self.next_request_version;
+        if start_version != expected_next_version || end_version < expected_next_version {
+            panic!(
+                "Updating a continuous transaction tracker with an old request! Given {:?} but expected version: {:?}",
+                client_request, expected_next_version
+            );
+        }
+        self.next_request_version = end_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Next request version has overflown!".into()))?;
+
+        Ok(())
+    }
+
+    /// Returns the (start, end) versions of the given client request. Panics
+    /// if the request type doesn't match the type of the stream.
+    fn get_request_versions(&self, client_request: &DataClientRequest) -> (Version, Version) {
+        match (&self.request, client_request) {
+            (
+                StreamRequest::ContinuouslyStreamTransactions(_),
+                DataClientRequest::TransactionsWithProof(request),
+            ) => (request.start_version, request.end_version),
+            (
+                StreamRequest::ContinuouslyStreamTransactionOutputs(_),
+                DataClientRequest::TransactionOutputsWithProof(request),
+            ) => (request.start_version, request.end_version),
+            _ => {
+                panic!(
+                    "Invalid client request {:?} found for the data stream tracker {:?}",
+                    client_request, self
+                );
+            }
+        }
     }
 }
 
@@ -213,57 +796,426 @@ impl EpochEndingStreamTracker {
         max_number_of_requests: u64,
         optimal_epoch_chunk_size: u64,
     ) -> Result<Vec<DataClientRequest>, Error> {
-        // Calculate the total number of epochs left to satisfy the stream
-        let start_epoch = self.next_request_epoch;
-        let end_epoch = self.end_epoch;
-        let mut total_epochs_to_fetch = end_epoch
-            .checked_sub(start_epoch)
-            .and_then(|e| e.checked_add(1)) // = end_epoch - start_epoch + 1
-            .ok_or_else(|| Error::IntegerOverflow("Total epochs to fetch has overflown!".into()))?;
-
-        // Iterate until we've requested all epochs or hit the maximum number of requests
-        let mut data_client_requests = vec![];
-        let mut num_requests_made = 0;
-        let mut next_epoch_to_request = self.next_request_epoch;
-        while total_epochs_to_fetch > 0 && num_requests_made < max_number_of_requests {
-            // Calculate the number of epochs to fetch in this request
-            let num_epochs_to_fetch = cmp::min(total_epochs_to_fetch, optimal_epoch_chunk_size);
-
-            // Calculate the start and end epochs for the request
-            let request_start_epoch = next_epoch_to_request;
-            let request_end_epoch = request_start_epoch
-                .checked_add(num_epochs_to_fetch)
-                .and_then(|e| e.checked_sub(1)) // = request_start_epoch + num_epochs_to_fetch - 1
-                .ok_or_else(|| {
-                    Error::IntegerOverflow("End epoch to fetch has overflown!".into())
+        create_data_client_request_batch(
+            self.next_request_epoch,
+            self.end_epoch,
+            max_number_of_requests,
+            optimal_epoch_chunk_size,
+            |start_epoch, end_epoch| {
+                DataClientRequest::EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest {
+                    start_epoch,
+                    end_epoch,
+                })
+            },
+        )
+    }
+
+    fn update_notification_progress(
+        &mut self,
+        client_request: &DataClientRequest,
+    ) -> Result<(), Error> {
+        match client_request {
+            EpochEndingLedgerInfos(request) => {
+                let expected_next_epoch = self.next_stream_epoch;
+                if request.start_epoch != expected_next_epoch
+                    || request.end_epoch < expected_next_epoch
+                {
+                    panic!(
+                        "Updating an epoch ending tracker with an old notification! Given {:?} but expected epoch: {:?}",
+                        request, expected_next_epoch
+                    );
+                }
+                self.next_stream_epoch = request.end_epoch.checked_add(1).ok_or_else(|| {
+                    Error::IntegerOverflow("Next stream epoch has overflown!".into())
                 })?;
+            }
+            _ => {
+                panic!(
+                    "Invalid client request {:?} found for the data stream tracker {:?}",
+                    client_request, self
+                );
+            }
+        }
 
-            // Create the data client requests
-            let data_client_request =
-                DataClientRequest::EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest {
-                    start_epoch: request_start_epoch,
-                    end_epoch: request_end_epoch,
-                });
-            data_client_requests.push(data_client_request);
-
-            // Update the local loop state
-            next_epoch_to_request = request_end_epoch.checked_add(1).ok_or_else(|| {
-                Error::IntegerOverflow("Next epoch to request has overflown!".into())
-            })?;
-            total_epochs_to_fetch = total_epochs_to_fetch
-                .checked_sub(num_epochs_to_fetch)
-                .ok_or_else(|| {
-                    Error::IntegerOverflow("Total epochs to fetch has overflown!".into())
+        Ok(())
+    }
+
+    fn update_request_progress(&mut self, client_request: &DataClientRequest) -> Result<(), Error> {
+        match client_request {
+            EpochEndingLedgerInfos(request) => {
+                let expected_next_epoch = self.next_request_epoch;
+                if request.start_epoch != expected_next_epoch
+                    || request.end_epoch < expected_next_epoch
+                {
+                    panic!(
+                        "Updating an epoch ending tracker with an old request! Given {:?} but expected epoch: {:?}",
+                        request, expected_next_epoch
+                    );
+                }
+                self.next_request_epoch = request.end_epoch.checked_add(1).ok_or_else(|| {
+                    Error::IntegerOverflow("Next stream epoch has overflown!".into())
                 })?;
-            num_requests_made = num_requests_made.checked_add(1).ok_or_else(|| {
-                Error::IntegerOverflow("Number of payload requests has overflown!".into())
-            })?;
+            }
+            _ => {
+                panic!(
+                    "Invalid client request {:?} found for the data stream tracker {:?}",
+                    client_request, self
+                );
+            }
         }
 
-        Ok(data_client_requests)
+        Ok(())
     }
 }
 
+#[derive(Debug)]
+pub struct RefetchStreamTracker {
+    // The original refetch request made by the client
+    pub request: RefetchNotificationPayloadRequest,
+
+    // The data client request of the notification whose payload is refetched
+    pub client_request: DataClientRequest,
+
+    // The ledger info the original payload was proven against (only set if
+    // the notification was sent along a continuous stream).
+    pub target_ledger_info: Option<LedgerInfoWithSignatures>,
+
+    // True iff the client request has been resent to the network
+    pub client_request_sent: bool,
+
+    // True iff the refetched payload has been sent to the client
+    pub notification_sent: bool,
+}
+
+impl RefetchStreamTracker {
+    /// Creates the single data client request to refetch the payload (if it
+    /// hasn't already been sent).
+    pub fn create_refetch_client_requests(
+        &self,
+        max_number_of_requests: u64,
+    ) -> Vec<DataClientRequest> {
+        if self.client_request_sent || max_number_of_requests == 0 {
+            vec![]
+        } else {
+            vec![self.client_request.clone()]
+        }
+    }
+
+    fn transform_client_response_into_payload(
+        &self,
+        client_request: &DataClientRequest,
+        client_response: &DataClientResponse,
+    ) -> DataPayload {
+        if *client_request != self.client_request {
+            panic!(
+                "Invalid client request {:?} found for the data stream tracker {:?}",
+                client_request, self
+            );
+        }
+
+        match (&self.target_ledger_info, &client_response.response_payload) {
+            (Some(target_ledger_info), DataClientPayload::TransactionsWithProof(chunk)) => {
+                DataPayload::ContinuousTransactionsWithProof(
+                    target_ledger_info.clone(),
+                    chunk.clone(),
+                )
+            }
+            (Some(target_ledger_info), DataClientPayload::TransactionOutputsWithProof(chunk)) => {
+                DataPayload::ContinuousTransactionOutputsWithProof(
+                    target_ledger_info.clone(),
+                    chunk.clone(),
+                )
+            }
+            _ => extract_data_payload(client_response),
+        }
+    }
+
+    fn update_notification_progress(&mut self, client_request: &DataClientRequest) {
+        if *client_request != self.client_request || self.notification_sent {
+            panic!(
+                "Updating a refetch tracker with an unexpected notification! Given {:?} but expected: {:?}",
+                client_request, self.client_request
+            );
+        }
+        self.notification_sent = true;
+    }
+
+    fn update_request_progress(&mut self, client_request: &DataClientRequest) {
+        if *client_request != self.client_request || self.client_request_sent {
+            panic!(
+                "Updating a refetch tracker with an unexpected request! Given {:?} but expected: {:?}",
+                client_request, self.client_request
+            );
+        }
+        self.client_request_sent = true;
+    }
+}
+
+#[derive(Debug)]
+pub struct TransactionStreamTracker {
+    // The original transaction (or transaction output) request made by the client
+    pub request: StreamRequest,
+
+    // The last version that this stream will send to the client
+    pub end_version: Version,
+
+    // The version that all transaction proofs will be relative to
+    pub max_proof_version: Version,
+
+    // The next version that we're waiting to send to the client along the
+    // stream. All versions before this have already been sent.
+    pub next_stream_version: Version,
+
+    // The next version that we're waiting to request from the network. All
+    // versions before this have already been requested.
+    pub next_request_version: Version,
+}
+
+impl TransactionStreamTracker {
+    fn new(stream_request: &StreamRequest) -> Result<Self, Error> {
+        let (start_version, end_version, max_proof_version) = match stream_request {
+            StreamRequest::GetAllTransactions(request) => (
+                request.start_version,
+                request.end_version,
+                request.max_proof_version,
+            ),
+            StreamRequest::GetAllTransactionOutputs(request) => (
+                request.start_version,
+                request.end_version,
+                request.max_proof_version,
+            ),
+            request => {
+                return Err(Error::UnsupportedRequestEncountered(format!(
+                    "Invalid request for a transaction stream: {:?}",
+                    request
+                )))
+            }
+        };
+
+        if end_version < start_version || max_proof_version < end_version {
+            return Err(Error::UnsupportedRequestEncountered(format!(
+                "The stream versions are invalid! Start: {:?}, end: {:?}, max proof: {:?}",
+                start_version, end_version, max_proof_version
+            )));
+        }
+
+        Ok(Self {
+            request: stream_request.clone(),
+            end_version,
+            max_proof_version,
+            next_stream_version: start_version,
+            next_request_version: start_version,
+        })
+    }
+
+    /// Returns true iff all versions required by the stream are available
+    /// in a single advertised data range.
+    pub fn transactions_available(&self, advertised_data: &AdvertisedData) -> bool {
+        let advertised_ranges = match &self.request {
+            StreamRequest::GetAllTransactionOutputs(_) => &advertised_data.transaction_outputs,
+            _ => &advertised_data.transactions,
+        };
+        advertised_ranges.iter().any(|range| {
+            range.contains(self.next_stream_version)
+                && range.contains(self.end_version)
+                && range.contains(self.max_proof_version)
+        })
+    }
+
+    /// Creates transaction (or transaction output) payload requests for the
+    /// Diem data client. At most `max_number_of_requests` will be created.
+    pub fn create_transaction_client_requests(
+        &mut self,
+        max_number_of_requests: u64,
+        optimal_chunk_sizes: &OptimalChunkSizes,
+    ) -> Result<Vec<DataClientRequest>, Error> {
+        if self.next_request_version > self.end_version {
+            return Ok(vec![]); // All versions have already been requested
+        }
+
+        let max_proof_version = self.max_proof_version;
+        match &self.request {
+            StreamRequest::GetAllTransactions(request) => {
+                let include_events = request.include_events;
+                create_data_client_request_batch(
+                    self.next_request_version,
+                    self.end_version,
+                    max_number_of_requests,
+                    optimal_chunk_sizes.transaction_chunk_size,
+                    |start_version, end_version| {
+                        DataClientRequest::TransactionsWithProof(TransactionsWithProofRequest {
+                            start_version,
+                            end_version,
+                            max_proof_version,
+                            include_events,
+                        })
+                    },
+                )
+            }
+            _ => create_data_client_request_batch(
+                self.next_request_version,
+                self.end_version,
+                max_number_of_requests,
+                optimal_chunk_sizes.transaction_output_chunk_size,
+                |start_version, end_version| {
+                    DataClientRequest::TransactionOutputsWithProof(
+                        TransactionOutputsWithProofRequest {
+                            start_version,
+                            end_version,
+                            max_proof_version,
+                        },
+                    )
+                },
+            ),
+        }
+    }
+
+    fn update_notification_progress(
+        &mut self,
+        client_request: &DataClientRequest,
+    ) -> Result<(), Error> {
+        let (start_version, end_version) = self.get_request_versions(client_request);
+        let expected_next_version = self.next_stream_version;
+        if start_version != expected_next_version || end_version < expected_next_version {
+            panic!(
+                "Updating a transaction tracker with an old notification! Given {:?} but expected version: {:?}",
+                client_request, expected_next_version
+            );
+        }
+        self.next_stream_version = end_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Next stream version has overflown!".into()))?;
+
+        Ok(())
+    }
+
+    fn update_request_progress(&mut self, client_request: &DataClientRequest) -> Result<(), Error> {
+        let (start_version, end_version) = self.get_request_versions(client_request);
+        let expected_next_version = self.next_request_version;
+        if start_version != expected_next_version || end_version < expected_next_version {
+            panic!(
+                "Updating a transaction tracker with an old request! Given {:?} but expected version: {:?}",
+                client_request, expected_next_version
+            );
+        }
+        self.next_request_version = end_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Next request version has overflown!".into()))?;
+
+        Ok(())
+    }
+
+    /// Returns the (start, end) versions of the given client request. Panics
+    /// if the request type doesn't match the type of the stream.
+    fn get_request_versions(&self, client_request: &DataClientRequest) -> (Version, Version) {
+        match (&self.request, client_request) {
+            (
+                StreamRequest::GetAllTransactions(_),
+                DataClientRequest::TransactionsWithProof(request),
+            ) => (request.start_version, request.end_version),
+            (
+                StreamRequest::GetAllTransactionOutputs(_),
+                DataClientRequest::TransactionOutputsWithProof(request),
+            ) => (request.start_version, request.end_version),
+            _ => {
+                panic!(
+                    "Invalid client request {:?} found for the data stream tracker {:?}",
+                    client_request, self
+                );
+            }
+        }
+    }
+}
+
+/// Creates a batch of data client requests that cover the range from
+/// `start_index` to `end_index` (inclusive), where each request covers at most
+/// `optimal_chunk_size` items. At most `max_number_of_requests` will be
+/// created, using `create_client_request` to build each request.
+fn create_data_client_request_batch(
+    start_index: u64,
+    end_index: u64,
+    max_number_of_requests: u64,
+    optimal_chunk_size: u64,
+    create_client_request: impl Fn(u64, u64) -> DataClientRequest,
+) -> Result<Vec<DataClientRequest>, Error> {
+    // Calculate the total number of items left to satisfy the stream
+    let mut total_items_to_fetch = end_index
+        .checked_sub(start_index)
+        .and_then(|e| e.checked_add(1)) // = end_index - start_index + 1
+        .ok_or_else(|| Error::IntegerOverflow("Total items to fetch has overflown!".into()))?;
+
+    // Iterate until we've requested all items or hit the maximum number of requests
+    let mut data_client_requests = vec![];
+    let mut num_requests_made = 0;
+    let mut next_index_to_request = start_index;
+    while total_items_to_fetch > 0 && num_requests_made < max_number_of_requests {
+        // Calculate the number of items to fetch in this request
+        let num_items_to_fetch = cmp::min(total_items_to_fetch, optimal_chunk_size);
+
+        // Calculate the start and end indices for the request
+        let request_start_index = next_index_to_request;
+        let request_end_index = request_start_index
+            .checked_add(num_items_to_fetch)
+            .and_then(|e| e.checked_sub(1)) // = request_start_index + num_items_to_fetch - 1
+            .ok_or_else(|| Error::IntegerOverflow("End index to fetch has overflown!".into()))?;
+
+        // Create the data client requests
+        data_client_requests.push(create_client_request(
+            request_start_index,
+            request_end_index,
+        ));
+
+        // Update the local loop state
+        next_index_to_request = request_end_index
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Next index to request has overflown!".into()))?;
+        total_items_to_fetch = total_items_to_fetch
+            .checked_sub(num_items_to_fetch)
+            .ok_or_else(|| Error::IntegerOverflow("Total items to fetch has overflown!".into()))?;
+        num_requests_made = num_requests_made.checked_add(1).ok_or_else(|| {
+            Error::IntegerOverflow("Number of payload requests has overflown!".into())
+        })?;
+    }
+
+    Ok(data_client_requests)
+}
+
+/// Extracts the `DataPayload` out of a `DataClientResponse`. Assumes that the
+/// response has already been sanity checked.
+fn extract_data_payload(data_client_response: &DataClientResponse) -> DataPayload {
+    match &data_client_response.response_payload {
+        DataClientPayload::AccountStatesWithProof(accounts_chunk) => {
+            DataPayload::AccountStatesWithProof(accounts_chunk.clone())
+        }
+        DataClientPayload::EpochEndingLedgerInfos(ledger_infos) => {
+            DataPayload::EpochEndingLedgerInfos(ledger_infos.clone())
+        }
+        DataClientPayload::TransactionsWithProof(transactions_chunk) => {
+            DataPayload::TransactionsWithProof(transactions_chunk.clone())
+        }
+        DataClientPayload::TransactionOutputsWithProof(transactions_output_chunk) => {
+            DataPayload::TransactionOutputsWithProof(transactions_output_chunk.clone())
+        }
+        _ => {
+            panic!(
+                "The response was already sanity checked but is now type mismatched: {:?}",
+                data_client_response
+            );
+        }
+    }
+}
+
+/// Returns the highest synced ledger info advertised in the network (if any).
+fn highest_synced_ledger_info(
+    advertised_data: &AdvertisedData,
+) -> Option<LedgerInfoWithSignatures> {
+    advertised_data
+        .synced_ledger_infos
+        .iter()
+        .max_by_key(|ledger_info| ledger_info.ledger_info().version())
+        .cloned()
+}
+
 /// Returns the most common highest epoch advertised in the network.
 /// Note: we use this to reduce the likelihood of malicious nodes
 /// interfering with syncing progress by advertising non-existent epochs.
@@ -4,7 +4,10 @@
 use crate::{
     data_stream::{DataStream, DataStreamId, DataStreamListener},
     error::Error,
-    streaming_client::{StreamRequestMessage, StreamingServiceListener},
+    streaming_client::{
+        RefetchNotificationPayloadRequest, StreamRequest, StreamRequestMessage,
+        StreamingServiceListener,
+    },
 };
 use diem_data_client::{DataClientPayload, DiemDataClient, GlobalDataSummary, OptimalChunkSizes};
 use futures::StreamExt;
@@ -97,12 +100,17 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStreamingService<T> {
         self.refresh_global_data_summary()?;
 
         // Create a new data stream
-        let (data_stream, stream_listener) = DataStream::new(
-            &request_message.stream_request,
-            self.diem_data_client.clone(),
-            self.notification_id_generator.clone(),
-            &self.global_data_summary.advertised_data,
-        )?;
+        let (data_stream, stream_listener) = match &request_message.stream_request {
+            StreamRequest::RefetchNotificationPayload(request) => {
+                self.create_refetch_stream(request)?
+            }
+            stream_request => DataStream::new(
+                stream_request,
+                self.diem_data_client.clone(),
+                self.notification_id_generator.clone(),
+                &self.global_data_summary.advertised_data,
+            )?,
+        };
 
         // Verify the data stream can be fulfilled using the currently advertised data
         data_stream.ensure_data_is_available(&self.global_data_summary.advertised_data)?;
@@ -120,6 +128,31 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStreamingService<T> {
         Ok(stream_listener)
     }
 
+    /// Creates a new data stream that refetches the payload of a data
+    /// notification previously sent along one of the existing streams.
+    fn create_refetch_stream(
+        &self,
+        request: &RefetchNotificationPayloadRequest,
+    ) -> Result<(DataStream<T>, DataStreamListener), Error> {
+        let sent_data_notification = self
+            .data_streams
+            .values()
+            .find_map(|data_stream| data_stream.get_sent_notification(&request.notification_id))
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "No sent data notification was found with ID: {:?}",
+                    request.notification_id
+                ))
+            })?;
+
+        Ok(DataStream::new_refetch_stream(
+            request,
+            sent_data_notification,
+            self.diem_data_client.clone(),
+            self.notification_id_generator.clone(),
+        ))
+    }
+
     /// Refreshes the global data summary by communicating with the Diem data client
     fn refresh_global_data_summary(&mut self) -> Result<(), Error> {
         match self.diem_data_client.get_global_data_summary() {
@@ -163,15 +196,15 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStreamingService<T> {
         &mut self,
         data_stream_id: &DataStreamId,
     ) -> Result<(), Error> {
-        let optimal_chunk_sizes = self.global_data_summary.optimal_chunk_sizes.clone();
+        let global_data_summary = self.global_data_summary.clone();
 
         let data_stream = self.get_data_stream(data_stream_id);
         if !data_stream.data_requests_initialized() {
             // Initialize the request batch by sending out data client requests
-            data_stream.initialize_data_requests(optimal_chunk_sizes)?;
+            data_stream.initialize_data_requests(global_data_summary)?;
         } else {
             // Process any data client requests that have received responses
-            data_stream.process_data_responses(optimal_chunk_sizes)?;
+            data_stream.process_data_responses(global_data_summary)?;
         }
 
         Ok(())
@@ -3,25 +3,34 @@
 
 use crate::{
     data_notification::{
-        DataClientRequest, DataPayload, EpochEndingLedgerInfosRequest, PendingClientResponse,
+        DataClientRequest, DataPayload, EpochEndingLedgerInfosRequest, NumberOfAccountsRequest,
+        PendingClientResponse, TransactionsWithProofRequest,
     },
     data_stream::{DataStream, DataStreamListener},
-    streaming_client::{GetAllEpochEndingLedgerInfosRequest, StreamRequest},
+    streaming_client::{
+        ContinuouslyStreamTransactionsRequest, GetAllAccountsRequest,
+        GetAllEpochEndingLedgerInfosRequest, PayloadRefetchReason,
+        RefetchNotificationPayloadRequest, StreamRequest,
+    },
     tests::utils::{
-        create_epoch_ending_client_response, create_ledger_info, MockDiemDataClient,
-        MAX_ADVERTISED_EPOCH, MAX_NOTIFICATION_TIMEOUT_SECS, MIN_ADVERTISED_EPOCH,
+        create_advertised_data, create_data_client_response, create_epoch_ending_client_response,
+        create_ledger_info, MockDiemDataClient, MAX_ADVERTISED_EPOCH, MAX_ADVERTISED_TRANSACTION,
+        MAX_NOTIFICATION_TIMEOUT_SECS, MIN_ADVERTISED_EPOCH,
     },
 };
-use claim::{assert_ge, assert_none};
-use diem_data_client::{AdvertisedData, DataClientPayload, DataClientResponse, OptimalChunkSizes};
+use claim::{assert_ge, assert_matches, assert_none, assert_some};
+use diem_data_client::{
+    DataClientPayload, DataClientResponse, GlobalDataSummary, OptimalChunkSizes,
+};
 use diem_infallible::Mutex;
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures, transaction::default_protocol::TransactionListWithProof,
+};
 use futures::{FutureExt, StreamExt};
 use std::{
     sync::{atomic::AtomicU64, Arc},
     time::Duration,
 };
-use storage_service_types::CompleteDataRange;
 use tokio::time::timeout;
 
 #[tokio::test]
@@ -32,7 +41,7 @@ async fn test_epoch_ending_requests() {
     // Initialize the data stream
     let epoch_chunk_size = 80;
     data_stream
-        .initialize_data_requests(create_optimal_chunk_sizes(epoch_chunk_size))
+        .initialize_data_requests(create_global_data_summary(epoch_chunk_size))
         .unwrap();
 
     // Verify valid client requests have been created
@@ -60,7 +69,7 @@ async fn test_epoch_ending_notifications() {
     // Initialize the data stream
     let epoch_chunk_size = 100;
     data_stream
-        .initialize_data_requests(create_optimal_chunk_sizes(epoch_chunk_size))
+        .initialize_data_requests(create_global_data_summary(epoch_chunk_size))
         .unwrap();
 
     // Clear the pending queue and insert a response
@@ -77,15 +86,58 @@ async fn test_epoch_ending_notifications() {
 
     // Process the response and verify a notification is sent to the client
     data_stream
-        .process_data_responses(create_optimal_chunk_sizes(epoch_chunk_size))
+        .process_data_responses(create_global_data_summary(epoch_chunk_size))
         .unwrap();
     verify_epoch_ending_notification(
         &mut stream_listener,
-        create_ledger_info(MIN_ADVERTISED_EPOCH),
+        create_ledger_info(MIN_ADVERTISED_EPOCH, 0, true),
     )
     .await;
 }
 
+#[tokio::test]
+async fn test_end_of_stream_notification() {
+    // Create an epoch ending data stream for the last advertised epoch
+    let end_epoch = MAX_ADVERTISED_EPOCH - 1;
+    let (mut data_stream, mut stream_listener) = create_epoch_ending_stream(end_epoch);
+
+    // Initialize the data stream
+    let global_data_summary = create_global_data_summary(100);
+    data_stream
+        .initialize_data_requests(global_data_summary.clone())
+        .unwrap();
+
+    // Insert a response for the last epoch and process it
+    let pending_response = PendingClientResponse {
+        client_request: DataClientRequest::EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest {
+            start_epoch: end_epoch,
+            end_epoch,
+        }),
+        client_response: Some(Ok(create_epoch_ending_client_response(end_epoch))),
+    };
+    insert_response_into_pending_queue(&mut data_stream, pending_response);
+    data_stream
+        .process_data_responses(global_data_summary.clone())
+        .unwrap();
+
+    // Verify the data notification is followed by an end of stream notification
+    verify_epoch_ending_notification(&mut stream_listener, create_ledger_info(end_epoch, 0, true))
+        .await;
+    let data_notification = timeout(
+        Duration::from_secs(MAX_NOTIFICATION_TIMEOUT_SECS),
+        stream_listener.select_next_some(),
+    )
+    .await
+    .unwrap();
+    assert_matches!(data_notification.data_payload, DataPayload::EndOfStream);
+
+    // Process the responses again and verify no more notifications are sent
+    data_stream
+        .process_data_responses(global_data_summary)
+        .unwrap();
+    assert_none!(stream_listener.select_next_some().now_or_never());
+}
+
 #[tokio::test]
 async fn test_stream_initialization() {
     // Create an epoch ending data stream
@@ -96,7 +148,7 @@ async fn test_stream_initialization() {
 
     // Initialize the data stream
     data_stream
-        .initialize_data_requests(create_optimal_chunk_sizes(100))
+        .initialize_data_requests(create_global_data_summary(100))
         .unwrap();
 
     // Verify the data stream is now initialized
@@ -113,9 +165,9 @@ async fn test_stream_data_error() {
     let (mut data_stream, mut stream_listener) = create_epoch_ending_stream(MIN_ADVERTISED_EPOCH);
 
     // Initialize the data stream
-    let optimal_chunk_sizes = create_optimal_chunk_sizes(100);
+    let global_data_summary = create_global_data_summary(100);
     data_stream
-        .initialize_data_requests(optimal_chunk_sizes.clone())
+        .initialize_data_requests(global_data_summary.clone())
         .unwrap();
 
     // Clear the pending queue and insert an error response
@@ -133,7 +185,7 @@ async fn test_stream_data_error() {
 
     // Process the responses and verify the data client request was resent to the network
     data_stream
-        .process_data_responses(optimal_chunk_sizes)
+        .process_data_responses(global_data_summary)
         .unwrap();
     assert_none!(stream_listener.select_next_some().now_or_never());
     verify_client_request_resubmitted(&mut data_stream, client_request);
@@ -145,9 +197,9 @@ async fn test_stream_invalid_response() {
     let (mut data_stream, mut stream_listener) = create_epoch_ending_stream(MIN_ADVERTISED_EPOCH);
 
     // Initialize the data stream
-    let optimal_chunk_sizes = create_optimal_chunk_sizes(100);
+    let global_data_summary = create_global_data_summary(100);
     data_stream
-        .initialize_data_requests(optimal_chunk_sizes.clone())
+        .initialize_data_requests(global_data_summary.clone())
         .unwrap();
 
     // Clear the pending queue and insert a response with an invalid type
@@ -166,7 +218,7 @@ async fn test_stream_invalid_response() {
 
     // Process the responses and verify the data client request was resent to the network
     data_stream
-        .process_data_responses(optimal_chunk_sizes)
+        .process_data_responses(global_data_summary)
         .unwrap();
     assert_none!(stream_listener.select_next_some().now_or_never());
     verify_client_request_resubmitted(&mut data_stream, client_request);
@@ -178,9 +230,9 @@ async fn test_stream_out_of_order_responses() {
     let (mut data_stream, mut stream_listener) = create_epoch_ending_stream(MIN_ADVERTISED_EPOCH);
 
     // Initialize the data stream
-    let optimal_chunk_sizes = create_optimal_chunk_sizes(1);
+    let global_data_summary = create_global_data_summary(1);
     data_stream
-        .initialize_data_requests(optimal_chunk_sizes.clone())
+        .initialize_data_requests(global_data_summary.clone())
         .unwrap();
 
     // Verify at least three requests have been made
@@ -190,19 +242,19 @@ async fn test_stream_out_of_order_responses() {
     // Set a response for the second request and verify no notifications
     set_epoch_ending_response_in_queue(&mut data_stream, 1);
     data_stream
-        .process_data_responses(optimal_chunk_sizes.clone())
+        .process_data_responses(global_data_summary.clone())
         .unwrap();
     assert_none!(stream_listener.select_next_some().now_or_never());
 
     // Set a response for the first request and verify two notifications
     set_epoch_ending_response_in_queue(&mut data_stream, 0);
     data_stream
-        .process_data_responses(optimal_chunk_sizes.clone())
+        .process_data_responses(global_data_summary.clone())
         .unwrap();
     for _ in 0..2 {
         verify_epoch_ending_notification(
             &mut stream_listener,
-            create_ledger_info(MIN_ADVERTISED_EPOCH),
+            create_ledger_info(MIN_ADVERTISED_EPOCH, 0, true),
         )
         .await;
     }
@@ -212,11 +264,11 @@ async fn test_stream_out_of_order_responses() {
     set_epoch_ending_response_in_queue(&mut data_stream, 0);
     set_epoch_ending_response_in_queue(&mut data_stream, 2);
     data_stream
-        .process_data_responses(optimal_chunk_sizes.clone())
+        .process_data_responses(global_data_summary.clone())
         .unwrap();
     verify_epoch_ending_notification(
         &mut stream_listener,
-        create_ledger_info(MIN_ADVERTISED_EPOCH),
+        create_ledger_info(MIN_ADVERTISED_EPOCH, 0, true),
     )
     .await;
     assert_none!(stream_listener.select_next_some().now_or_never());
@@ -225,40 +277,190 @@ async fn test_stream_out_of_order_responses() {
     set_epoch_ending_response_in_queue(&mut data_stream, 0);
     set_epoch_ending_response_in_queue(&mut data_stream, 2);
     data_stream
-        .process_data_responses(optimal_chunk_sizes.clone())
+        .process_data_responses(global_data_summary.clone())
         .unwrap();
     for _ in 0..3 {
         verify_epoch_ending_notification(
             &mut stream_listener,
-            create_ledger_info(MIN_ADVERTISED_EPOCH),
+            create_ledger_info(MIN_ADVERTISED_EPOCH, 0, true),
         )
         .await;
     }
     assert_none!(stream_listener.select_next_some().now_or_never());
 }
 
+#[tokio::test]
+async fn test_accounts_requests() {
+    // Create an accounts data stream
+    let (mut data_stream, mut stream_listener) =
+        create_data_stream(StreamRequest::GetAllAccounts(GetAllAccountsRequest {
+            version: MAX_ADVERTISED_TRANSACTION,
+        }));
+
+    // Initialize the data stream and verify only the number of accounts is requested
+    let global_data_summary = create_global_data_summary(10);
+    data_stream
+        .initialize_data_requests(global_data_summary.clone())
+        .unwrap();
+    let number_of_accounts_request = DataClientRequest::NumberOfAccounts(NumberOfAccountsRequest {
+        version: MAX_ADVERTISED_TRANSACTION,
+    });
+    let (sent_requests, _) = data_stream.get_sent_requests_and_notifications();
+    assert_eq!(sent_requests.as_ref().unwrap().len(), 1);
+    assert_eq!(
+        sent_requests.as_ref().unwrap()[0].lock().client_request,
+        number_of_accounts_request
+    );
+
+    // Insert the number of accounts response and process it
+    let pending_response = PendingClientResponse {
+        client_request: number_of_accounts_request,
+        client_response: Some(Ok(create_data_client_response(
+            DataClientPayload::NumberOfAccountStates(100),
+        ))),
+    };
+    insert_response_into_pending_queue(&mut data_stream, pending_response);
+    data_stream
+        .process_data_responses(global_data_summary)
+        .unwrap();
+
+    // Verify no notification was sent and that account chunks are now requested
+    assert_none!(stream_listener.select_next_some().now_or_never());
+    let (sent_requests, _) = data_stream.get_sent_requests_and_notifications();
+    let sent_requests = sent_requests.as_ref().unwrap();
+    assert_eq!(sent_requests.len(), 3);
+    for (i, sent_request) in sent_requests.iter().enumerate() {
+        let i = i as u64;
+        assert_matches!(
+            &sent_request.lock().client_request,
+            DataClientRequest::AccountsWithProof(request) if request.start_index == i * 10 && request.end_index == (i * 10) + 9
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_notifications() {
+    // Create a continuous transaction data stream
+    let start_version = MAX_ADVERTISED_TRANSACTION - 9;
+    let (mut data_stream, mut stream_listener) = create_data_stream(
+        StreamRequest::ContinuouslyStreamTransactions(ContinuouslyStreamTransactionsRequest {
+            start_version,
+            start_epoch: MAX_ADVERTISED_EPOCH,
+            include_events: false,
+        }),
+    );
+
+    // Initialize the data stream and verify the request targets the highest ledger info
+    let global_data_summary = create_global_data_summary(100);
+    data_stream
+        .initialize_data_requests(global_data_summary.clone())
+        .unwrap();
+    let client_request = DataClientRequest::TransactionsWithProof(TransactionsWithProofRequest {
+        start_version,
+        end_version: MAX_ADVERTISED_TRANSACTION,
+        max_proof_version: MAX_ADVERTISED_TRANSACTION,
+        include_events: false,
+    });
+    let (sent_requests, _) = data_stream.get_sent_requests_and_notifications();
+    assert_eq!(
+        sent_requests.as_ref().unwrap()[0].lock().client_request,
+        client_request
+    );
+
+    // Insert a response and verify the notification contains the target ledger info
+    let pending_response = PendingClientResponse {
+        client_request,
+        client_response: Some(Ok(create_data_client_response(
+            DataClientPayload::TransactionsWithProof(TransactionListWithProof::new_empty()),
+        ))),
+    };
+    insert_response_into_pending_queue(&mut data_stream, pending_response);
+    data_stream
+        .process_data_responses(global_data_summary)
+        .unwrap();
+    let data_notification = timeout(
+        Duration::from_secs(MAX_NOTIFICATION_TIMEOUT_SECS),
+        stream_listener.select_next_some(),
+    )
+    .await
+    .unwrap();
+    let expected_ledger_info =
+        create_ledger_info(MAX_ADVERTISED_EPOCH, MAX_ADVERTISED_TRANSACTION, false);
+    assert_matches!(
+        data_notification.data_payload,
+        DataPayload::ContinuousTransactionsWithProof(ledger_info, _) if ledger_info == expected_ledger_info
+    );
+}
+
+#[tokio::test]
+async fn test_refetch_stream() {
+    // Create an epoch ending data stream
+    let (mut data_stream, mut stream_listener) = create_epoch_ending_stream(MIN_ADVERTISED_EPOCH);
+
+    // Initialize the data stream
+    let global_data_summary = create_global_data_summary(100);
+    data_stream
+        .initialize_data_requests(global_data_summary.clone())
+        .unwrap();
+
+    // Insert a response and verify a notification is sent to the client
+    let client_request = DataClientRequest::EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest {
+        start_epoch: MIN_ADVERTISED_EPOCH,
+        end_epoch: MIN_ADVERTISED_EPOCH,
+    });
+    let pending_response = PendingClientResponse {
+        client_request: client_request.clone(),
+        client_response: Some(Ok(create_epoch_ending_client_response(
+            MIN_ADVERTISED_EPOCH,
+        ))),
+    };
+    insert_response_into_pending_queue(&mut data_stream, pending_response);
+    data_stream
+        .process_data_responses(global_data_summary.clone())
+        .unwrap();
+    let notification_id = timeout(
+        Duration::from_secs(MAX_NOTIFICATION_TIMEOUT_SECS),
+        stream_listener.select_next_some(),
+    )
+    .await
+    .unwrap()
+    .notification_id;
+
+    // Create a refetch stream for the sent notification
+    let sent_data_notification = assert_some!(data_stream.get_sent_notification(&notification_id));
+    let (mut refetch_stream, _) = DataStream::new_refetch_stream(
+        &RefetchNotificationPayloadRequest {
+            notification_id,
+            refetch_reason: PayloadRefetchReason::InvalidPayloadData,
+        },
+        sent_data_notification,
+        MockDiemDataClient {},
+        Arc::new(AtomicU64::new(0)),
+    );
+
+    // Initialize the refetch stream and verify the original request is resent
+    refetch_stream
+        .initialize_data_requests(global_data_summary)
+        .unwrap();
+    let (sent_requests, _) = refetch_stream.get_sent_requests_and_notifications();
+    let sent_requests = sent_requests.as_ref().unwrap();
+    assert_eq!(sent_requests.len(), 1);
+    assert_eq!(sent_requests[0].lock().client_request, client_request);
+}
+
 /// Creates an epoch ending stream starting at `start_epoch`
 fn create_epoch_ending_stream(
     start_epoch: u64,
 ) -> (DataStream<MockDiemDataClient>, DataStreamListener) {
-    // Create an epoch ending stream request
-    let stream_request =
-        StreamRequest::GetAllEpochEndingLedgerInfos(GetAllEpochEndingLedgerInfosRequest {
-            start_epoch,
-        });
-
-    // Create an advertised data containing only epoch ending ledger infos
-    let advertised_data = AdvertisedData {
-        account_states: vec![],
-        epoch_ending_ledger_infos: vec![CompleteDataRange::new(
-            MIN_ADVERTISED_EPOCH,
-            MAX_ADVERTISED_EPOCH,
-        )],
-        synced_ledger_infos: vec![],
-        transactions: vec![],
-        transaction_outputs: vec![],
-    };
+    create_data_stream(StreamRequest::GetAllEpochEndingLedgerInfos(
+        GetAllEpochEndingLedgerInfosRequest { start_epoch },
+    ))
+}
 
+/// Creates a data stream for the given stream request
+fn create_data_stream(
+    stream_request: StreamRequest,
+) -> (DataStream<MockDiemDataClient>, DataStreamListener) {
     // Create a diem data client mock and notification generator
     let diem_data_client = MockDiemDataClient {};
     let notification_generator = Arc::new(AtomicU64::new(0));
@@ -268,17 +470,20 @@ fn create_epoch_ending_stream(
         &stream_request,
         diem_data_client,
         notification_generator,
-        &advertised_data,
+        &create_advertised_data(),
     )
     .unwrap()
 }
 
-fn create_optimal_chunk_sizes(chunk_size: u64) -> OptimalChunkSizes {
-    OptimalChunkSizes {
-        account_states_chunk_size: chunk_size,
-        epoch_chunk_size: chunk_size,
-        transaction_chunk_size: chunk_size,
-        transaction_output_chunk_size: chunk_size,
+fn create_global_data_summary(chunk_size: u64) -> GlobalDataSummary {
+    GlobalDataSummary {
+        advertised_data: create_advertised_data(),
+        optimal_chunk_sizes: OptimalChunkSizes {
+            account_states_chunk_size: chunk_size,
+            epoch_chunk_size: chunk_size,
+            transaction_chunk_size: chunk_size,
+            transaction_output_chunk_size: chunk_size,
+        },
     }
 }
 
@@ -2,22 +2,37 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    data_notification::{DataClientRequest, EpochEndingLedgerInfosRequest, SentDataNotification},
+    data_notification::{
+        AccountsWithProofRequest, DataClientRequest, DataPayload, EpochEndingLedgerInfosRequest,
+        NumberOfAccountsRequest, SentDataNotification, TransactionOutputsWithProofRequest,
+        TransactionsWithProofRequest,
+    },
     error::Error,
-    stream_progress_tracker::StreamProgressTracker,
-    streaming_client::{GetAllEpochEndingLedgerInfosRequest, StreamRequest},
+    stream_progress_tracker::{
+        AccountsStreamTracker, ContinuousTransactionStreamTracker, EpochEndingStreamTracker,
+        StreamProgressTracker,
+    },
+    streaming_client::{
+        ContinuouslyStreamTransactionsRequest, GetAllAccountsRequest,
+        GetAllEpochEndingLedgerInfosRequest, GetAllTransactionOutputsRequest,
+        GetAllTransactionsRequest, PayloadRefetchReason, RefetchNotificationPayloadRequest,
+        StreamRequest,
+    },
+    tests::utils::{create_data_client_response, create_ledger_info},
 };
-use claim::assert_matches;
+use claim::{assert_matches, assert_none, assert_ok};
 use diem_data_client::{DataClientPayload, DataClientResponse, GlobalDataSummary};
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures, transaction::default_protocol::TransactionListWithProof,
+};
 use std::cmp;
 use storage_service_types::CompleteDataRange;
 
 #[test]
 fn test_epoch_ending_requests() {
     // Create a new data stream progress tracker
-    let stream_progress_tracker = create_epoch_ending_progress_tracker(0, 900);
-    let StreamProgressTracker::EpochEndingStreamTracker(mut stream_tracker) =
-        stream_progress_tracker;
+    let mut stream_progress_tracker = create_epoch_ending_progress_tracker(0, 900);
+    let stream_tracker = get_epoch_ending_stream_tracker(&mut stream_progress_tracker);
 
     // Create a batch of large client requests and verify the result
     let client_requests = stream_tracker
@@ -63,9 +78,8 @@ fn test_epoch_ending_requests() {
 #[test]
 fn test_epoch_ending_requests_dynamic() {
     // Create a new data stream progress tracker
-    let stream_progress_tracker = create_epoch_ending_progress_tracker(0, 1000);
-    let StreamProgressTracker::EpochEndingStreamTracker(mut stream_tracker) =
-        stream_progress_tracker;
+    let mut stream_progress_tracker = create_epoch_ending_progress_tracker(0, 1000);
+    let stream_tracker = get_epoch_ending_stream_tracker(&mut stream_progress_tracker);
 
     // Update the tracker with a new next request epoch
     stream_tracker.next_request_epoch = 150;
@@ -168,9 +182,9 @@ fn test_epoch_ending_stream_tracker() {
 
     // Create a new data stream progress tracker and verify the most common highest
     // epoch is chosen.
-    let stream_progress_tracker =
+    let mut stream_progress_tracker =
         StreamProgressTracker::new(&stream_request, &global_data_summary.advertised_data).unwrap();
-    let StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) = stream_progress_tracker;
+    let stream_tracker = get_epoch_ending_stream_tracker(&mut stream_progress_tracker);
     assert_eq!(stream_tracker.end_epoch, 99); // End epoch is highest - 1
 }
 
@@ -193,8 +207,7 @@ fn test_epoch_ending_request_progress() {
             .unwrap();
 
         // Verify internal state
-        let StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) =
-            &stream_progress_tracker;
+        let stream_tracker = get_epoch_ending_stream_tracker(&mut stream_progress_tracker);
         assert_eq!(stream_tracker.next_request_epoch, end_epoch + 1);
     }
 }
@@ -220,8 +233,7 @@ fn test_epoch_ending_stream_progress() {
             .unwrap();
 
         // Verify internal state
-        let StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) =
-            &stream_progress_tracker;
+        let stream_tracker = get_epoch_ending_stream_tracker(&mut stream_progress_tracker);
         assert_eq!(stream_tracker.next_stream_epoch, end_epoch + 1);
     }
 }
@@ -282,6 +294,465 @@ fn test_epoch_ending_stream_progress_panic() {
         .unwrap();
 }
 
+#[test]
+fn test_accounts_requests() {
+    // Create a new accounts stream progress tracker
+    let mut stream_progress_tracker = StreamProgressTracker::new(
+        &StreamRequest::GetAllAccounts(GetAllAccountsRequest { version: 100 }),
+        &GlobalDataSummary::empty().advertised_data,
+    )
+    .unwrap();
+    let global_data_summary = create_global_data_summary(10);
+
+    // Verify the number of accounts is requested first (and only once)
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert_eq!(
+        client_requests,
+        vec![DataClientRequest::NumberOfAccounts(
+            NumberOfAccountsRequest { version: 100 }
+        )]
+    );
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert!(client_requests.is_empty());
+
+    // Process the number of accounts and verify no notification payload is created
+    let data_payload = stream_progress_tracker
+        .transform_client_response_into_payload(
+            &DataClientRequest::NumberOfAccounts(NumberOfAccountsRequest { version: 100 }),
+            &create_data_client_response(DataClientPayload::NumberOfAccountStates(25)),
+        )
+        .unwrap();
+    assert_none!(data_payload);
+    assert_eq!(
+        get_accounts_stream_tracker(&mut stream_progress_tracker).number_of_accounts,
+        Some(25)
+    );
+
+    // Verify the account chunks are now requested
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 5, &global_data_summary);
+    let expected_requests: Vec<_> = [(0, 9), (10, 19), (20, 24)]
+        .iter()
+        .map(|(start_index, end_index)| {
+            DataClientRequest::AccountsWithProof(AccountsWithProofRequest {
+                version: 100,
+                start_index: *start_index,
+                end_index: *end_index,
+            })
+        })
+        .collect();
+    assert_eq!(client_requests, expected_requests);
+
+    // Verify no more requests are made once all accounts have been requested
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 5, &global_data_summary);
+    assert!(client_requests.is_empty());
+
+    // Update the notification progress and verify the internal state
+    for client_request in expected_requests {
+        stream_progress_tracker
+            .update_notification_progress(&create_sent_data_notification(client_request))
+            .unwrap();
+    }
+    assert_eq!(
+        get_accounts_stream_tracker(&mut stream_progress_tracker).next_stream_index,
+        25
+    );
+}
+
+#[test]
+fn test_accounts_stream_tracker() {
+    // Create an accounts stream request
+    let stream_request = StreamRequest::GetAllAccounts(GetAllAccountsRequest { version: 100 });
+    let stream_progress_tracker =
+        StreamProgressTracker::new(&stream_request, &GlobalDataSummary::empty().advertised_data)
+            .unwrap();
+
+    // Verify the data is unavailable when no peer advertises the version
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary.advertised_data.account_states = vec![CompleteDataRange::new(101, 200)];
+    let result =
+        stream_progress_tracker.ensure_data_is_available(&global_data_summary.advertised_data);
+    assert_matches!(result, Err(Error::DataIsUnavailable(_)));
+
+    // Verify the data is available when a peer advertises the version
+    global_data_summary
+        .advertised_data
+        .account_states
+        .push(CompleteDataRange::new(0, 100));
+    let result =
+        stream_progress_tracker.ensure_data_is_available(&global_data_summary.advertised_data);
+    assert_ok!(result);
+}
+
+#[test]
+fn test_transaction_requests() {
+    // Create a new transaction stream progress tracker
+    let mut stream_progress_tracker = StreamProgressTracker::new(
+        &StreamRequest::GetAllTransactions(GetAllTransactionsRequest {
+            start_version: 10,
+            end_version: 54,
+            max_proof_version: 100,
+            include_events: true,
+        }),
+        &GlobalDataSummary::empty().advertised_data,
+    )
+    .unwrap();
+
+    // Create a batch of client requests and verify the result
+    let client_requests = create_and_track_requests(
+        &mut stream_progress_tracker,
+        10,
+        &create_global_data_summary(20),
+    );
+    let expected_requests: Vec<_> = [(10, 29), (30, 49), (50, 54)]
+        .iter()
+        .map(|(start_version, end_version)| {
+            DataClientRequest::TransactionsWithProof(TransactionsWithProofRequest {
+                start_version: *start_version,
+                end_version: *end_version,
+                max_proof_version: 100,
+                include_events: true,
+            })
+        })
+        .collect();
+    assert_eq!(client_requests, expected_requests);
+
+    // Verify no more requests are made once all transactions have been requested
+    let client_requests = create_and_track_requests(
+        &mut stream_progress_tracker,
+        10,
+        &create_global_data_summary(20),
+    );
+    assert!(client_requests.is_empty());
+}
+
+#[test]
+fn test_transaction_output_requests() {
+    // Create a new transaction output stream progress tracker
+    let mut stream_progress_tracker = StreamProgressTracker::new(
+        &StreamRequest::GetAllTransactionOutputs(GetAllTransactionOutputsRequest {
+            start_version: 0,
+            end_version: 99,
+            max_proof_version: 99,
+        }),
+        &GlobalDataSummary::empty().advertised_data,
+    )
+    .unwrap();
+
+    // Create a limited batch of client requests and verify the result
+    let client_requests = create_and_track_requests(
+        &mut stream_progress_tracker,
+        2,
+        &create_global_data_summary(30),
+    );
+    let expected_requests: Vec<_> = [(0, 29), (30, 59)]
+        .iter()
+        .map(|(start_version, end_version)| {
+            DataClientRequest::TransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+                start_version: *start_version,
+                end_version: *end_version,
+                max_proof_version: 99,
+            })
+        })
+        .collect();
+    assert_eq!(client_requests, expected_requests);
+
+    // Create the next batch and verify it continues from the last request
+    let client_requests = create_and_track_requests(
+        &mut stream_progress_tracker,
+        5,
+        &create_global_data_summary(30),
+    );
+    let expected_requests: Vec<_> = [(60, 89), (90, 99)]
+        .iter()
+        .map(|(start_version, end_version)| {
+            DataClientRequest::TransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+                start_version: *start_version,
+                end_version: *end_version,
+                max_proof_version: 99,
+            })
+        })
+        .collect();
+    assert_eq!(client_requests, expected_requests);
+}
+
+#[test]
+fn test_transaction_stream_tracker() {
+    // Try to create a transaction stream with an end version lower than the
+    // start version and verify an error is returned.
+    let stream_request = StreamRequest::GetAllTransactions(GetAllTransactionsRequest {
+        start_version: 10,
+        end_version: 9,
+        max_proof_version: 100,
+        include_events: false,
+    });
+    let result =
+        StreamProgressTracker::new(&stream_request, &GlobalDataSummary::empty().advertised_data);
+    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+
+    // Try to create a refetch stream directly and verify an error is returned
+    let stream_request =
+        StreamRequest::RefetchNotificationPayload(RefetchNotificationPayloadRequest {
+            notification_id: 0,
+            refetch_reason: PayloadRefetchReason::InvalidPayloadData,
+        });
+    let result =
+        StreamProgressTracker::new(&stream_request, &GlobalDataSummary::empty().advertised_data);
+    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+
+    // Create a valid transaction stream
+    let stream_request = StreamRequest::GetAllTransactions(GetAllTransactionsRequest {
+        start_version: 10,
+        end_version: 90,
+        max_proof_version: 100,
+        include_events: false,
+    });
+    let stream_progress_tracker =
+        StreamProgressTracker::new(&stream_request, &GlobalDataSummary::empty().advertised_data)
+            .unwrap();
+
+    // Verify the data is unavailable if it's not contained in a single range
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary.advertised_data.transactions = vec![
+        CompleteDataRange::new(0, 50),
+        CompleteDataRange::new(50, 100),
+    ];
+    let result =
+        stream_progress_tracker.ensure_data_is_available(&global_data_summary.advertised_data);
+    assert_matches!(result, Err(Error::DataIsUnavailable(_)));
+
+    // Verify the data is available if it's contained in a single range
+    global_data_summary
+        .advertised_data
+        .transactions
+        .push(CompleteDataRange::new(10, 100));
+    let result =
+        stream_progress_tracker.ensure_data_is_available(&global_data_summary.advertised_data);
+    assert_ok!(result);
+}
+
+#[test]
+#[should_panic(expected = "Updating a transaction tracker with an old notification!")]
+fn test_transaction_stream_progress_panic() {
+    // Create a new transaction stream progress tracker
+    let mut stream_progress_tracker = StreamProgressTracker::new(
+        &StreamRequest::GetAllTransactions(GetAllTransactionsRequest {
+            start_version: 0,
+            end_version: 100,
+            max_proof_version: 100,
+            include_events: false,
+        }),
+        &GlobalDataSummary::empty().advertised_data,
+    )
+    .unwrap();
+
+    // Update the tracker with a notification that skips data and verify a panic
+    let sent_data_notification = create_sent_data_notification(
+        DataClientRequest::TransactionsWithProof(TransactionsWithProofRequest {
+            start_version: 1,
+            end_version: 10,
+            max_proof_version: 100,
+            include_events: false,
+        }),
+    );
+    stream_progress_tracker
+        .update_notification_progress(&sent_data_notification)
+        .unwrap();
+}
+
+#[test]
+fn test_continuous_requests() {
+    // Create a new continuous transaction stream progress tracker
+    let mut stream_progress_tracker = StreamProgressTracker::new(
+        &StreamRequest::ContinuouslyStreamTransactions(ContinuouslyStreamTransactionsRequest {
+            start_version: 10,
+            start_epoch: 5,
+            include_events: false,
+        }),
+        &GlobalDataSummary::empty().advertised_data,
+    )
+    .unwrap();
+
+    // Verify no requests are made if no ledger infos are advertised
+    let client_requests = create_and_track_requests(
+        &mut stream_progress_tracker,
+        3,
+        &create_global_data_summary(100),
+    );
+    assert!(client_requests.is_empty());
+
+    // Advertise a ledger info in the same epoch and verify the requests
+    let target_ledger_info = create_ledger_info(5, 50, false);
+    let global_data_summary = create_summary_with_ledger_info(100, target_ledger_info.clone());
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    let expected_request =
+        DataClientRequest::TransactionsWithProof(create_transactions_request(10, 50, 50));
+    assert_eq!(client_requests, vec![expected_request.clone()]);
+    let stream_tracker = get_continuous_stream_tracker(&mut stream_progress_tracker);
+    assert_eq!(
+        stream_tracker.current_target_ledger_info,
+        Some(target_ledger_info.clone())
+    );
+
+    // Verify the payload contains the target ledger info
+    let data_payload = stream_progress_tracker
+        .transform_client_response_into_payload(
+            &expected_request,
+            &create_data_client_response(DataClientPayload::TransactionsWithProof(
+                TransactionListWithProof::new_empty(),
+            )),
+        )
+        .unwrap();
+    assert_matches!(
+        data_payload,
+        Some(DataPayload::ContinuousTransactionsWithProof(ledger_info, _)) if ledger_info == target_ledger_info
+    );
+
+    // Verify no more requests are made until the target is reached
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert!(client_requests.is_empty());
+    stream_progress_tracker
+        .update_notification_progress(&create_sent_data_notification(expected_request))
+        .unwrap();
+    let stream_tracker = get_continuous_stream_tracker(&mut stream_progress_tracker);
+    assert_none!(&stream_tracker.current_target_ledger_info);
+    assert_eq!(stream_tracker.next_stream_version, 51);
+
+    // Advertise a ledger info in a later epoch and verify the epoch ending
+    // ledger info is requested (only once).
+    let global_data_summary =
+        create_summary_with_ledger_info(100, create_ledger_info(6, 80, false));
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert_eq!(
+        client_requests,
+        vec![DataClientRequest::EpochEndingLedgerInfos(
+            EpochEndingLedgerInfosRequest {
+                start_epoch: 5,
+                end_epoch: 5,
+            }
+        )]
+    );
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert!(client_requests.is_empty());
+
+    // Process the epoch ending ledger info and verify it becomes the target
+    let epoch_ending_ledger_info = create_ledger_info(5, 60, true);
+    let data_payload = stream_progress_tracker
+        .transform_client_response_into_payload(
+            &create_epoch_ending_request(5),
+            &create_data_client_response(DataClientPayload::EpochEndingLedgerInfos(vec![
+                epoch_ending_ledger_info.clone(),
+            ])),
+        )
+        .unwrap();
+    assert_none!(data_payload);
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    let expected_request =
+        DataClientRequest::TransactionsWithProof(create_transactions_request(51, 60, 60));
+    assert_eq!(client_requests, vec![expected_request.clone()]);
+
+    // Reach the end of the epoch and verify the next epoch is requested
+    stream_progress_tracker
+        .update_notification_progress(&create_sent_data_notification(expected_request))
+        .unwrap();
+    let stream_tracker = get_continuous_stream_tracker(&mut stream_progress_tracker);
+    assert_eq!(stream_tracker.next_request_epoch, 6);
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert_eq!(
+        client_requests,
+        vec![DataClientRequest::TransactionsWithProof(
+            create_transactions_request(61, 80, 80)
+        )]
+    );
+}
+
+#[test]
+fn test_continuous_invalid_epoch_ending_response() {
+    // Create a new continuous transaction stream progress tracker
+    let mut stream_progress_tracker = StreamProgressTracker::new(
+        &StreamRequest::ContinuouslyStreamTransactions(ContinuouslyStreamTransactionsRequest {
+            start_version: 10,
+            start_epoch: 5,
+            include_events: false,
+        }),
+        &GlobalDataSummary::empty().advertised_data,
+    )
+    .unwrap();
+
+    // Advertise a ledger info in a later epoch and verify the epoch ending
+    // ledger info is requested.
+    let global_data_summary =
+        create_summary_with_ledger_info(100, create_ledger_info(6, 80, false));
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert_eq!(client_requests, vec![create_epoch_ending_request(5)]);
+
+    // Process an epoch ending ledger info for the wrong epoch and verify an error
+    let result = stream_progress_tracker.transform_client_response_into_payload(
+        &create_epoch_ending_request(5),
+        &create_data_client_response(DataClientPayload::EpochEndingLedgerInfos(vec![
+            create_ledger_info(4, 60, true),
+        ])),
+    );
+    assert_matches!(result, Err(Error::DiemDataClientResponseIsInvalid(_)));
+
+    // Verify the epoch ending ledger info is requested again
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert_eq!(client_requests, vec![create_epoch_ending_request(5)]);
+}
+
+#[test]
+fn test_refetch_requests() {
+    // Create a refetch progress tracker for a continuous stream notification
+    let client_request =
+        DataClientRequest::TransactionsWithProof(create_transactions_request(10, 20, 30));
+    let target_ledger_info = create_ledger_info(1, 30, false);
+    let mut sent_data_notification = create_sent_data_notification(client_request.clone());
+    sent_data_notification.target_ledger_info = Some(target_ledger_info.clone());
+    let mut stream_progress_tracker = StreamProgressTracker::new_refetch_stream_tracker(
+        &RefetchNotificationPayloadRequest {
+            notification_id: 0,
+            refetch_reason: PayloadRefetchReason::ProofVerificationFailed,
+        },
+        &sent_data_notification,
+    );
+
+    // Verify the original client request is sent (only once)
+    let global_data_summary = create_global_data_summary(1);
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert_eq!(client_requests, vec![client_request.clone()]);
+    let client_requests =
+        create_and_track_requests(&mut stream_progress_tracker, 3, &global_data_summary);
+    assert!(client_requests.is_empty());
+
+    // Verify the refetched payload contains the original target ledger info
+    let data_payload = stream_progress_tracker
+        .transform_client_response_into_payload(
+            &client_request,
+            &create_data_client_response(DataClientPayload::TransactionsWithProof(
+                TransactionListWithProof::new_empty(),
+            )),
+        )
+        .unwrap();
+    assert_matches!(
+        data_payload,
+        Some(DataPayload::ContinuousTransactionsWithProof(ledger_info, _)) if ledger_info == target_ledger_info
+    );
+    assert_ok!(stream_progress_tracker
+        .ensure_data_is_available(&GlobalDataSummary::empty().advertised_data));
+}
+
 fn create_epoch_ending_progress_tracker(
     start_epoch: u64,
     max_advertised_epoch: u64,
@@ -310,5 +781,103 @@ fn create_sent_data_notification(client_request: DataClientRequest) -> SentDataN
             response_id: 0,
             response_payload: DataClientPayload::EpochEndingLedgerInfos(vec![]),
         },
+        target_ledger_info: None,
+    }
+}
+
+/// Creates a batch of client requests using the given tracker and updates
+/// the request progress of the tracker for each request.
+fn create_and_track_requests(
+    stream_progress_tracker: &mut StreamProgressTracker,
+    max_number_of_requests: u64,
+    global_data_summary: &GlobalDataSummary,
+) -> Vec<DataClientRequest> {
+    let client_requests = stream_progress_tracker
+        .create_data_client_requests(max_number_of_requests, global_data_summary)
+        .unwrap();
+    for client_request in &client_requests {
+        stream_progress_tracker
+            .update_request_progress(client_request)
+            .unwrap();
+    }
+    client_requests
+}
+
+fn create_epoch_ending_request(epoch: u64) -> DataClientRequest {
+    DataClientRequest::EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest {
+        start_epoch: epoch,
+        end_epoch: epoch,
+    })
+}
+
+fn create_transactions_request(
+    start_version: u64,
+    end_version: u64,
+    max_proof_version: u64,
+) -> TransactionsWithProofRequest {
+    TransactionsWithProofRequest {
+        start_version,
+        end_version,
+        max_proof_version,
+        include_events: false,
+    }
+}
+
+/// Creates an empty global data summary where all chunk sizes are `chunk_size`
+fn create_global_data_summary(chunk_size: u64) -> GlobalDataSummary {
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary
+        .optimal_chunk_sizes
+        .account_states_chunk_size = chunk_size;
+    global_data_summary.optimal_chunk_sizes.epoch_chunk_size = chunk_size;
+    global_data_summary
+        .optimal_chunk_sizes
+        .transaction_chunk_size = chunk_size;
+    global_data_summary
+        .optimal_chunk_sizes
+        .transaction_output_chunk_size = chunk_size;
+    global_data_summary
+}
+
+/// Creates a global data summary that advertises a single synced ledger info
+fn create_summary_with_ledger_info(
+    chunk_size: u64,
+    synced_ledger_info: LedgerInfoWithSignatures,
+) -> GlobalDataSummary {
+    let mut global_data_summary = create_global_data_summary(chunk_size);
+    global_data_summary.advertised_data.synced_ledger_infos = vec![synced_ledger_info];
+    global_data_summary
+}
+
+fn get_accounts_stream_tracker(
+    stream_progress_tracker: &mut StreamProgressTracker,
+) -> &mut AccountsStreamTracker {
+    match stream_progress_tracker {
+        StreamProgressTracker::AccountsStreamTracker(stream_tracker) => stream_tracker,
+        stream_tracker => panic!("Expected an accounts tracker but got: {:?}", stream_tracker),
+    }
+}
+
+fn get_continuous_stream_tracker(
+    stream_progress_tracker: &mut StreamProgressTracker,
+) -> &mut ContinuousTransactionStreamTracker {
+    match stream_progress_tracker {
+        StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => stream_tracker,
+        stream_tracker => panic!(
+            "Expected a continuous transaction tracker but got: {:?}",
+            stream_tracker
+        ),
+    }
+}
+
+fn get_epoch_ending_stream_tracker(
+    stream_progress_tracker: &mut StreamProgressTracker,
+) -> &mut EpochEndingStreamTracker {
+    match stream_progress_tracker {
+        StreamProgressTracker::EpochEndingStreamTracker(stream_tracker) => stream_tracker,
+        stream_tracker => panic!(
+            "Expected an epoch ending tracker but got: {:?}",
+            stream_tracker
+        ),
     }
 }
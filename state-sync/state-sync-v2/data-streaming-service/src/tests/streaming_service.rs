@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    data_notification::DataPayload,
+    data_notification::{DataNotification, DataPayload},
+    data_stream::DataStreamListener,
     error::Error,
     streaming_client::{
         new_streaming_service_client_listener_pair, DataStreamingClient, PayloadRefetchReason,
@@ -10,14 +11,15 @@ use crate::{
     },
     streaming_service::DataStreamingService,
     tests::utils::{
-        MockDiemDataClient, MAX_ADVERTISED_EPOCH, MAX_NOTIFICATION_TIMEOUT_SECS,
-        MIN_ADVERTISED_EPOCH,
+        MockDiemDataClient, MAX_ADVERTISED_EPOCH, MAX_ADVERTISED_TRANSACTION,
+        MAX_NOTIFICATION_TIMEOUT_SECS, MIN_ADVERTISED_EPOCH, MIN_ADVERTISED_TRANSACTION,
+        TOTAL_NUM_ACCOUNTS,
     },
 };
 use claim::{assert_le, assert_matches, assert_ok};
 use futures::StreamExt;
 use std::time::Duration;
-use tokio::time::timeout;
+use tokio::time::{error::Elapsed, timeout};
 
 #[tokio::test]
 async fn test_notifications_epoch_ending() {
@@ -41,6 +43,10 @@ async fn test_notifications_epoch_ending() {
         )
         .await
         {
+            if matches!(data_notification.data_payload, DataPayload::EndOfStream) {
+                assert_eq!(next_expected_epoch, expected_end_epoch + 1);
+                return; // We hit the end of the stream!
+            }
             if let DataPayload::EpochEndingLedgerInfos(ledger_infos_with_sigs) =
                 data_notification.data_payload
             {
@@ -57,9 +63,6 @@ async fn test_notifications_epoch_ending() {
                 );
             }
         } else {
-            if next_expected_epoch == expected_end_epoch + 1 {
-                return; // We hit the end of the stream!
-            }
             panic!(
                 "Timed out waiting for a data notification! Next expected epoch: {:?}",
                 next_expected_epoch
@@ -96,49 +99,269 @@ async fn test_stream_epoch_ending() {
 }
 
 #[tokio::test]
-async fn test_stream_unsupported() {
+async fn test_notifications_accounts() {
     // Create a new streaming client and service
     let (streaming_client, streaming_service) = create_new_streaming_client_and_service();
     tokio::spawn(streaming_service.start_service());
 
-    // Request an account stream and verify it's unsupported
-    let streaming_client_clone = streaming_client.clone();
-    let result = streaming_client_clone.get_all_accounts(0).await;
-    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+    // Request an account stream and get a data stream listener
+    let mut stream_listener = streaming_client
+        .get_all_accounts(MAX_ADVERTISED_TRANSACTION)
+        .await
+        .unwrap();
 
-    // Request a transaction stream and verify it's unsupported
-    let streaming_client_clone = streaming_client.clone();
-    let result = streaming_client_clone
-        .get_all_transactions(0, 100, 200, true)
+    // Read the data notifications from the stream and verify index ordering
+    let mut next_expected_index = 0;
+    loop {
+        if let Ok(data_notification) = get_data_notification(&mut stream_listener).await {
+            if matches!(data_notification.data_payload, DataPayload::EndOfStream) {
+                assert_eq!(next_expected_index, TOTAL_NUM_ACCOUNTS);
+                return; // We hit the end of the stream!
+            }
+            if let DataPayload::AccountStatesWithProof(accounts_chunk) =
+                data_notification.data_payload
+            {
+                assert_eq!(accounts_chunk.first_index, next_expected_index);
+                next_expected_index += accounts_chunk.account_blobs.len() as u64;
+            } else {
+                panic!(
+                    "Expected an account states chunk payload, but got: {:?}",
+                    data_notification
+                );
+            }
+        } else {
+            panic!(
+                "Timed out waiting for a data notification! Next expected index: {:?}",
+                next_expected_index
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_notifications_continuous_transactions() {
+    // Create a new streaming client and service
+    let (streaming_client, streaming_service) = create_new_streaming_client_and_service();
+    tokio::spawn(streaming_service.start_service());
+
+    // Request a continuous transaction stream and get a data stream listener
+    let mut stream_listener = streaming_client
+        .continuously_stream_transactions(MIN_ADVERTISED_TRANSACTION, MAX_ADVERTISED_EPOCH, true)
+        .await
+        .unwrap();
+
+    // Read the data notifications from the stream and verify version ordering
+    let mut next_expected_version = MIN_ADVERTISED_TRANSACTION;
+    loop {
+        if let Ok(data_notification) = get_data_notification(&mut stream_listener).await {
+            if let DataPayload::ContinuousTransactionsWithProof(ledger_info, transactions_chunk) =
+                data_notification.data_payload
+            {
+                assert_eq!(
+                    ledger_info.ledger_info().version(),
+                    MAX_ADVERTISED_TRANSACTION
+                );
+                assert_eq!(
+                    transactions_chunk.first_transaction_version,
+                    Some(next_expected_version)
+                );
+                next_expected_version += transactions_chunk.transactions.len() as u64;
+            } else {
+                panic!(
+                    "Expected a continuous transactions payload, but got: {:?}",
+                    data_notification
+                );
+            }
+        } else {
+            if next_expected_version == MAX_ADVERTISED_TRANSACTION + 1 {
+                return; // We've caught up to the highest advertised version!
+            }
+            panic!(
+                "Timed out waiting for a data notification! Next expected version: {:?}",
+                next_expected_version
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_notifications_transaction_outputs() {
+    // Create a new streaming client and service
+    let (streaming_client, streaming_service) = create_new_streaming_client_and_service();
+    tokio::spawn(streaming_service.start_service());
+
+    // Request a transaction output stream and get a data stream listener
+    let mut stream_listener = streaming_client
+        .get_all_transaction_outputs(
+            MIN_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+        )
+        .await
+        .unwrap();
+
+    // Read the data notifications from the stream and verify version ordering
+    let mut next_expected_version = MIN_ADVERTISED_TRANSACTION;
+    loop {
+        if let Ok(data_notification) = get_data_notification(&mut stream_listener).await {
+            if matches!(data_notification.data_payload, DataPayload::EndOfStream) {
+                assert_eq!(next_expected_version, MAX_ADVERTISED_TRANSACTION + 1);
+                return; // We hit the end of the stream!
+            }
+            if let DataPayload::TransactionOutputsWithProof(outputs_chunk) =
+                data_notification.data_payload
+            {
+                assert_eq!(
+                    outputs_chunk.first_transaction_output_version,
+                    Some(next_expected_version)
+                );
+                next_expected_version += outputs_chunk.transactions_and_outputs.len() as u64;
+            } else {
+                panic!(
+                    "Expected a transaction outputs payload, but got: {:?}",
+                    data_notification
+                );
+            }
+        } else {
+            panic!(
+                "Timed out waiting for a data notification! Next expected version: {:?}",
+                next_expected_version
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_notifications_transactions() {
+    // Create a new streaming client and service
+    let (streaming_client, streaming_service) = create_new_streaming_client_and_service();
+    tokio::spawn(streaming_service.start_service());
+
+    // Request a transaction stream and get a data stream listener
+    let mut stream_listener = streaming_client
+        .get_all_transactions(
+            MIN_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+            true,
+        )
+        .await
+        .unwrap();
+
+    // Read the data notifications from the stream and verify version ordering
+    let mut next_expected_version = MIN_ADVERTISED_TRANSACTION;
+    loop {
+        if let Ok(data_notification) = get_data_notification(&mut stream_listener).await {
+            if matches!(data_notification.data_payload, DataPayload::EndOfStream) {
+                assert_eq!(next_expected_version, MAX_ADVERTISED_TRANSACTION + 1);
+                return; // We hit the end of the stream!
+            }
+            if let DataPayload::TransactionsWithProof(transactions_chunk) =
+                data_notification.data_payload
+            {
+                assert_eq!(
+                    transactions_chunk.first_transaction_version,
+                    Some(next_expected_version)
+                );
+                next_expected_version += transactions_chunk.transactions.len() as u64;
+            } else {
+                panic!(
+                    "Expected a transactions payload, but got: {:?}",
+                    data_notification
+                );
+            }
+        } else {
+            panic!(
+                "Timed out waiting for a data notification! Next expected version: {:?}",
+                next_expected_version
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_refetch_notification_payload() {
+    // Create a new streaming client and service
+    let (streaming_client, streaming_service) = create_new_streaming_client_and_service();
+    tokio::spawn(streaming_service.start_service());
+
+    // Try to refetch a notification that was never sent and verify an error
+    let result = streaming_client
+        .refetch_notification_payload(0, PayloadRefetchReason::InvalidPayloadData)
         .await;
-    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+    assert_matches!(result, Err(Error::UnexpectedErrorEncountered(_)));
 
-    // Request a transaction output stream and verify it's unsupported
-    let streaming_client_clone = streaming_client.clone();
-    let result = streaming_client_clone
-        .get_all_transaction_outputs(0, 100, 200)
+    // Request an epoch ending stream and read the first notification
+    let mut stream_listener = streaming_client
+        .get_all_epoch_ending_ledger_infos(MIN_ADVERTISED_EPOCH)
+        .await
+        .unwrap();
+    let data_notification = get_data_notification(&mut stream_listener).await.unwrap();
+    let expected_ledger_infos = match data_notification.data_payload {
+        DataPayload::EpochEndingLedgerInfos(ledger_infos) => ledger_infos,
+        data_payload => panic!(
+            "Expected an epoch ending ledger info payload, but got: {:?}",
+            data_payload
+        ),
+    };
+
+    // Refetch the notification payload and verify the same data is returned
+    let mut refetch_listener = streaming_client
+        .refetch_notification_payload(
+            data_notification.notification_id,
+            PayloadRefetchReason::ProofVerificationFailed,
+        )
+        .await
+        .unwrap();
+    let refetch_notification = get_data_notification(&mut refetch_listener).await.unwrap();
+    assert_ne!(
+        refetch_notification.notification_id,
+        data_notification.notification_id
+    );
+    match refetch_notification.data_payload {
+        DataPayload::EpochEndingLedgerInfos(ledger_infos) => {
+            assert_eq!(ledger_infos, expected_ledger_infos)
+        }
+        data_payload => panic!(
+            "Expected an epoch ending ledger info payload, but got: {:?}",
+            data_payload
+        ),
+    }
+}
+
+#[tokio::test]
+async fn test_stream_unavailable() {
+    // Create a new streaming client and service
+    let (streaming_client, streaming_service) = create_new_streaming_client_and_service();
+    tokio::spawn(streaming_service.start_service());
+
+    // Request an account stream where the version is not advertised
+    let result = streaming_client
+        .get_all_accounts(MAX_ADVERTISED_TRANSACTION + 1)
         .await;
-    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+    assert_matches!(result, Err(Error::DataIsUnavailable(_)));
 
-    // Request a continuous transaction stream and verify it's unsupported
-    let streaming_client_clone = streaming_client.clone();
-    let result = streaming_client_clone
-        .continuously_stream_transactions(0, 0, true)
+    // Request a transaction stream where the data was pruned
+    let result = streaming_client
+        .get_all_transactions(0, 100, MAX_ADVERTISED_TRANSACTION, true)
         .await;
-    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+    assert_matches!(result, Err(Error::DataIsUnavailable(_)));
 
-    // Request a continuous transaction output stream and verify it's unsupported
-    let streaming_client_clone = streaming_client.clone();
-    let result = streaming_client_clone
-        .continuously_stream_transaction_outputs(0, 0)
+    // Request a transaction output stream higher than anything advertised
+    let result = streaming_client
+        .get_all_transaction_outputs(
+            MIN_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION + 1,
+            MAX_ADVERTISED_TRANSACTION + 1,
+        )
         .await;
-    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+    assert_matches!(result, Err(Error::DataIsUnavailable(_)));
 
-    // Request a refetch notification payload stream and verify it's unsupported
+    // Request a continuous transaction output stream where the data was pruned
     let result = streaming_client
-        .refetch_notification_payload(0, PayloadRefetchReason::InvalidPayloadData)
+        .continuously_stream_transaction_outputs(0, 0)
         .await;
-    assert_matches!(result, Err(Error::UnsupportedRequestEncountered(_)));
+    assert_matches!(result, Err(Error::DataIsUnavailable(_)));
 }
 
 fn create_new_streaming_client_and_service() -> (
@@ -155,3 +378,14 @@ fn create_new_streaming_client_and_service() -> (
 
     (streaming_client, streaming_service)
 }
+
+/// Waits for the next data notification along the given stream (or times out)
+async fn get_data_notification(
+    stream_listener: &mut DataStreamListener,
+) -> Result<DataNotification, Elapsed> {
+    timeout(
+        Duration::from_secs(MAX_NOTIFICATION_TIMEOUT_SECS),
+        stream_listener.select_next_some(),
+    )
+    .await
+}
@@ -9,8 +9,19 @@ use diem_data_client::{
     OptimalChunkSizes, ResponseError,
 };
 use diem_types::{
+    account_address::AccountAddress,
+    account_state_blob::{AccountStateBlob, AccountStatesChunkWithProof},
     block_info::BlockInfo,
+    block_metadata::BlockMetadata,
+    epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::SparseMerkleRangeProof,
+    transaction::{
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        Transaction, TransactionOutput, TransactionStatus, Version,
+    },
+    vm_status::KeptVMStatus,
+    write_set::WriteSet,
 };
 use rand::{rngs::OsRng, RngCore};
 use std::{collections::BTreeMap, thread, time::Duration};
@@ -20,6 +31,9 @@ use storage_service_types::CompleteDataRange;
 pub const MAX_RESPONSE_ID: u64 = 100000;
 pub const MIN_ADVERTISED_EPOCH: u64 = 100;
 pub const MAX_ADVERTISED_EPOCH: u64 = 1000;
+pub const MIN_ADVERTISED_TRANSACTION: u64 = 1000;
+pub const MAX_ADVERTISED_TRANSACTION: u64 = 1500;
+pub const TOTAL_NUM_ACCOUNTS: u64 = 500;
 
 /// Test timeout constant
 pub const MAX_NOTIFICATION_TIMEOUT_SECS: u64 = 4;
@@ -33,10 +47,28 @@ impl DiemDataClient for MockDiemDataClient {
     async fn get_account_states_with_proof(
         &self,
         _version: u64,
-        _start_index: HashValue,
-        _end_index: HashValue,
+        start_index: u64,
+        end_index: u64,
     ) -> Result<DataClientResponse, diem_data_client::Error> {
-        unimplemented!();
+        emulate_network_latency();
+
+        // Create account states according to the requested indices
+        let mut account_blobs = vec![];
+        for _ in start_index..=end_index {
+            account_blobs.push((HashValue::random(), AccountStateBlob::from(vec![])));
+        }
+        let accounts_chunk = AccountStatesChunkWithProof {
+            first_index: start_index,
+            last_index: end_index,
+            first_key: HashValue::zero(),
+            last_key: HashValue::zero(),
+            account_blobs,
+            proof: SparseMerkleRangeProof::new(vec![]),
+        };
+        let response_payload = DataClientPayload::AccountStatesWithProof(accounts_chunk);
+
+        // Return the account states
+        Ok(create_data_client_response(response_payload))
     }
 
     async fn get_epoch_ending_ledger_infos(
@@ -44,13 +76,12 @@ impl DiemDataClient for MockDiemDataClient {
         start_epoch: u64,
         end_epoch: u64,
     ) -> Result<DataClientResponse, diem_data_client::Error> {
-        // Sleep a random amount of time (< 1 second) to emulate network latencies
-        thread::sleep(Duration::from_millis(create_random_u64(1000)));
+        emulate_network_latency();
 
         // Create epoch ending ledger infos according to the requested epochs
         let mut epoch_ending_ledger_infos = vec![];
         for epoch in start_epoch..=end_epoch {
-            epoch_ending_ledger_infos.push(create_ledger_info(epoch));
+            epoch_ending_ledger_infos.push(create_ledger_info(epoch, 0, true));
         }
         let response_payload = DataClientPayload::EpochEndingLedgerInfos(epoch_ending_ledger_infos);
 
@@ -68,16 +99,7 @@ impl DiemDataClient for MockDiemDataClient {
         };
 
         // Create a global data summary with a fixed set of data
-        let advertised_data = AdvertisedData {
-            account_states: vec![],
-            epoch_ending_ledger_infos: vec![CompleteDataRange::new(
-                MIN_ADVERTISED_EPOCH,
-                MAX_ADVERTISED_EPOCH,
-            )],
-            synced_ledger_infos: vec![],
-            transactions: vec![],
-            transaction_outputs: vec![],
-        };
+        let advertised_data = create_advertised_data();
         let response_payload = DataClientPayload::GlobalDataSummary(GlobalDataSummary {
             advertised_data,
             optimal_chunk_sizes,
@@ -91,26 +113,62 @@ impl DiemDataClient for MockDiemDataClient {
         &self,
         _version: u64,
     ) -> Result<DataClientResponse, diem_data_client::Error> {
-        unimplemented!();
+        emulate_network_latency();
+
+        let response_payload = DataClientPayload::NumberOfAccountStates(TOTAL_NUM_ACCOUNTS);
+        Ok(create_data_client_response(response_payload))
     }
 
     async fn get_transaction_outputs_with_proof(
         &self,
         _proof_version: u64,
-        _start_version: u64,
-        _end_version: u64,
+        start_version: u64,
+        end_version: u64,
     ) -> Result<DataClientResponse, diem_data_client::Error> {
-        unimplemented!();
+        emulate_network_latency();
+
+        // Create transaction outputs according to the requested versions
+        let mut transactions_and_outputs = vec![];
+        for _ in start_version..=end_version {
+            let transaction_output = TransactionOutput::new(
+                WriteSet::default(),
+                vec![],
+                0,
+                TransactionStatus::Keep(KeptVMStatus::Executed),
+            );
+            transactions_and_outputs.push((create_transaction(), transaction_output));
+        }
+        let mut transaction_outputs_chunk = TransactionOutputListWithProof::new_empty();
+        transaction_outputs_chunk.transactions_and_outputs = transactions_and_outputs;
+        transaction_outputs_chunk.first_transaction_output_version = Some(start_version);
+        let response_payload =
+            DataClientPayload::TransactionOutputsWithProof(transaction_outputs_chunk);
+
+        // Return the transaction outputs
+        Ok(create_data_client_response(response_payload))
     }
 
     async fn get_transactions_with_proof(
         &self,
         _proof_version: u64,
-        _start_version: u64,
-        _end_version: u64,
+        start_version: u64,
+        end_version: u64,
         _include_events: bool,
     ) -> Result<DataClientResponse, diem_data_client::Error> {
-        unimplemented!();
+        emulate_network_latency();
+
+        // Create transactions according to the requested versions
+        let mut transactions = vec![];
+        for _ in start_version..=end_version {
+            transactions.push(create_transaction());
+        }
+        let mut transactions_chunk = TransactionListWithProof::new_empty();
+        transactions_chunk.transactions = transactions;
+        transactions_chunk.first_transaction_version = Some(start_version);
+        let response_payload = DataClientPayload::TransactionsWithProof(transactions_chunk);
+
+        // Return the transactions
+        Ok(create_data_client_response(response_payload))
     }
 
     async fn notify_bad_response(
@@ -118,7 +176,34 @@ impl DiemDataClient for MockDiemDataClient {
         _response_id: u64,
         _response_error: ResponseError,
     ) -> Result<(), diem_data_client::Error> {
-        unimplemented!();
+        Ok(())
+    }
+}
+
+/// Creates the fixed set of data advertised by the mock data client
+pub fn create_advertised_data() -> AdvertisedData {
+    AdvertisedData {
+        account_states: vec![CompleteDataRange::new(
+            MIN_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+        )],
+        epoch_ending_ledger_infos: vec![CompleteDataRange::new(
+            MIN_ADVERTISED_EPOCH,
+            MAX_ADVERTISED_EPOCH,
+        )],
+        synced_ledger_infos: vec![create_ledger_info(
+            MAX_ADVERTISED_EPOCH,
+            MAX_ADVERTISED_TRANSACTION,
+            false,
+        )],
+        transactions: vec![CompleteDataRange::new(
+            MIN_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+        )],
+        transaction_outputs: vec![CompleteDataRange::new(
+            MIN_ADVERTISED_TRANSACTION,
+            MAX_ADVERTISED_TRANSACTION,
+        )],
     }
 }
 
@@ -131,9 +216,27 @@ pub fn create_data_client_response(response_payload: DataClientPayload) -> DataC
     }
 }
 
-/// Creates a ledger info with the given epoch
-pub fn create_ledger_info(epoch: Epoch) -> LedgerInfoWithSignatures {
-    let block_info = BlockInfo::new(epoch, 0, HashValue::zero(), HashValue::zero(), 0, 0, None);
+/// Creates a ledger info with the given epoch and version. If `epoch_ending`
+/// is true, the ledger info will end the epoch.
+pub fn create_ledger_info(
+    epoch: Epoch,
+    version: Version,
+    epoch_ending: bool,
+) -> LedgerInfoWithSignatures {
+    let next_epoch_state = if epoch_ending {
+        Some(EpochState::empty())
+    } else {
+        None
+    };
+    let block_info = BlockInfo::new(
+        epoch,
+        0,
+        HashValue::zero(),
+        HashValue::zero(),
+        version,
+        0,
+        next_epoch_state,
+    );
     LedgerInfoWithSignatures::new(
         LedgerInfo::new(block_info, HashValue::zero()),
         BTreeMap::new(),
@@ -143,10 +246,27 @@ pub fn create_ledger_info(epoch: Epoch) -> LedgerInfoWithSignatures {
 /// Creates an epoch ending client response with a single ledger info
 pub fn create_epoch_ending_client_response(epoch: Epoch) -> DataClientResponse {
     let response_payload =
-        DataClientPayload::EpochEndingLedgerInfos(vec![create_ledger_info(epoch)]);
+        DataClientPayload::EpochEndingLedgerInfos(vec![create_ledger_info(epoch, 0, true)]);
     create_data_client_response(response_payload)
 }
 
+/// Creates a simple transaction for test purposes
+pub fn create_transaction() -> Transaction {
+    Transaction::BlockMetadata(BlockMetadata::new(
+        HashValue::zero(),
+        0,
+        0,
+        vec![],
+        AccountAddress::ZERO,
+        vec![],
+    ))
+}
+
+/// Sleeps a random amount of time (< 1 second) to emulate network latencies
+fn emulate_network_latency() {
+    thread::sleep(Duration::from_millis(create_random_u64(1000)));
+}
+
 /// Returns a random u64 with a value between 0 and `max_value` - 1 (inclusive).
 pub fn create_random_u64(max_value: u64) -> u64 {
     let mut rng = OsRng;
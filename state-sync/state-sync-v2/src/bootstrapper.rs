@@ -0,0 +1,712 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::DriverConfiguration, error::Error, notification_handlers::CommitNotification,
+    storage_synchronizer::StorageSynchronizerInterface, utils,
+};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload, NotificationId},
+    data_stream::DataStreamListener,
+    streaming_client::{DataStreamingClient, Epoch, PayloadRefetchReason},
+};
+use diem_config::config::BootstrappingMode;
+use diem_data_client::{AdvertisedData, GlobalDataSummary};
+use diem_logger::prelude::*;
+use diem_types::{
+    account_state_blob::AccountStatesChunkWithProof,
+    epoch_change::Verifier,
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::{
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        TransactionInfoTrait, Version,
+    },
+};
+use std::{collections::BTreeMap, sync::Arc};
+use storage_interface::DbReader;
+
+/// The epoch ending ledger infos (and epoch states) that have been fetched
+/// from the network and verified by the bootstrapper.
+struct VerifiedEpochStates {
+    // True iff all epoch ending ledger infos advertised by the network have
+    // been fetched and verified.
+    fetched_epoch_ending_ledger_infos: bool,
+
+    // The epoch state of the highest verified epoch
+    latest_epoch_state: EpochState,
+
+    // The newly verified epoch ending ledger infos (indexed by version)
+    new_epoch_ending_ledger_infos: BTreeMap<Version, LedgerInfoWithSignatures>,
+
+    // True iff the waypoint has been verified by an epoch ending ledger info
+    verified_waypoint: bool,
+}
+
+impl VerifiedEpochStates {
+    fn new(latest_epoch_state: EpochState) -> Self {
+        Self {
+            fetched_epoch_ending_ledger_infos: false,
+            latest_epoch_state,
+            new_epoch_ending_ledger_infos: BTreeMap::new(),
+            verified_waypoint: false,
+        }
+    }
+
+    /// Verifies the given epoch ending ledger info against the latest epoch
+    /// state (and the waypoint, if the versions match). If verification
+    /// succeeds, the latest epoch state is moved to the next epoch.
+    fn verify_epoch_ending_ledger_info(
+        &mut self,
+        epoch_ending_ledger_info: &LedgerInfoWithSignatures,
+        driver_configuration: &DriverConfiguration,
+    ) -> Result<(), Error> {
+        let ledger_info = epoch_ending_ledger_info.ledger_info();
+        self.latest_epoch_state
+            .verify(epoch_ending_ledger_info)
+            .map_err(|error| {
+                Error::VerificationError(format!(
+                    "Failed to verify the epoch ending ledger info: {:?}",
+                    error
+                ))
+            })?;
+        let next_epoch_state = ledger_info.next_epoch_state().ok_or_else(|| {
+            Error::VerificationError(format!(
+                "The ledger info does not end the epoch: {:?}",
+                ledger_info
+            ))
+        })?;
+
+        // Verify the waypoint (if the waypoint version has been reached)
+        let waypoint = &driver_configuration.waypoint;
+        if ledger_info.version() == waypoint.version() {
+            waypoint.verify(ledger_info).map_err(|error| {
+                Error::VerificationError(format!("Failed to verify the waypoint: {:?}", error))
+            })?;
+            self.verified_waypoint = true;
+        }
+
+        self.latest_epoch_state = next_epoch_state.clone();
+        self.new_epoch_ending_ledger_infos
+            .insert(ledger_info.version(), epoch_ending_ledger_info.clone());
+        Ok(())
+    }
+
+    /// Returns the highest verified epoch ending ledger info (if any)
+    fn get_highest_epoch_ending_ledger_info(&self) -> Option<&LedgerInfoWithSignatures> {
+        self.new_epoch_ending_ledger_infos.values().next_back()
+    }
+
+    /// Returns the first verified epoch ending ledger info at or after the
+    /// given version. This is the ledger info that ends the epoch containing
+    /// `version`.
+    fn get_epoch_ending_ledger_info_for_version(
+        &self,
+        version: Version,
+    ) -> Option<&LedgerInfoWithSignatures> {
+        self.new_epoch_ending_ledger_infos
+            .range(version..)
+            .next()
+            .map(|(_, ledger_info)| ledger_info)
+    }
+
+    /// Returns all verified epoch ending ledger infos up to (and including)
+    /// the given version.
+    fn get_epoch_ending_ledger_infos_up_to(
+        &self,
+        version: Version,
+    ) -> Vec<LedgerInfoWithSignatures> {
+        self.new_epoch_ending_ledger_infos
+            .range(..=version)
+            .map(|(_, ledger_info)| ledger_info.clone())
+            .collect()
+    }
+}
+
+/// The state of the account states being synced (only used when bootstrapping
+/// by downloading the latest account states, i.e., fast syncing).
+#[derive(Default)]
+struct AccountStateSyncer {
+    // The epoch ending ledger info at which the account states are synced
+    ledger_info_to_sync: Option<LedgerInfoWithSignatures>,
+
+    // The next account index expected along the account states stream
+    next_account_index_to_process: u64,
+
+    // The verified transaction output at the account states version
+    transaction_output_to_sync: Option<TransactionOutputListWithProof>,
+}
+
+/// The bootstrapper brings the node up to date with the latest epoch advertised
+/// in the network. It first fetches and verifies all epoch ending ledger infos
+/// (and the waypoint), then either downloads the account states at the highest
+/// verified epoch ending version (and applies all later transaction outputs),
+/// or executes all transactions up to that version.
+pub struct Bootstrapper<StorageSyncer, StreamingClient> {
+    // The currently active data stream (provided by the data streaming service)
+    active_data_stream: Option<DataStreamListener>,
+
+    // The state of the account states being synced (if fast syncing)
+    account_state_syncer: AccountStateSyncer,
+
+    // True iff the node has been bootstrapped
+    bootstrapped: bool,
+
+    // The config of the state sync driver
+    driver_configuration: DriverConfiguration,
+
+    // The storage to read from (writes are done by the storage synchronizer)
+    storage: Arc<dyn DbReader<DpnProto>>,
+
+    // The storage synchronizer used to commit data to storage
+    storage_synchronizer: StorageSyncer,
+
+    // The client through which to stream data from the Diem network
+    streaming_client: StreamingClient,
+
+    // The epoch states verified by this node (held in memory)
+    verified_epoch_states: VerifiedEpochStates,
+}
+
+impl<
+        StorageSyncer: StorageSynchronizerInterface,
+        StreamingClient: DataStreamingClient + Send + Clone + 'static,
+    > Bootstrapper<StorageSyncer, StreamingClient>
+{
+    pub fn new(
+        driver_configuration: DriverConfiguration,
+        streaming_client: StreamingClient,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        storage_synchronizer: StorageSyncer,
+    ) -> Self {
+        let latest_epoch_state = utils::fetch_latest_epoch_state(storage.clone())
+            .expect("Unable to fetch the latest epoch state from storage!");
+
+        Self {
+            active_data_stream: None,
+            account_state_syncer: AccountStateSyncer::default(),
+            bootstrapped: false,
+            driver_configuration,
+            storage,
+            storage_synchronizer,
+            streaming_client,
+            verified_epoch_states: VerifiedEpochStates::new(latest_epoch_state),
+        }
+    }
+
+    /// Returns true iff the node has already completed bootstrapping
+    pub fn is_bootstrapped(&self) -> bool {
+        self.bootstrapped
+    }
+
+    /// Marks bootstrapping as complete
+    fn bootstrapping_complete(&mut self) {
+        info!("The node has successfully bootstrapped!");
+        self.bootstrapped = true;
+    }
+
+    /// Drives progress of the bootstrapper by processing the notifications of
+    /// the active data stream (or by creating a new stream if none exists).
+    /// Returns a commit notification if new data was committed to storage.
+    pub async fn drive_progress(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<Option<CommitNotification>, Error> {
+        if self.is_bootstrapped() {
+            return Err(Error::AlreadyBootstrapped);
+        }
+
+        if self.active_data_stream.is_some() {
+            self.process_active_stream_notifications().await
+        } else {
+            self.initialize_active_data_stream(global_data_summary)
+                .await?;
+            Ok(None)
+        }
+    }
+
+    /// Initializes a new data stream based on the current bootstrapping state
+    async fn initialize_active_data_stream(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<(), Error> {
+        let advertised_data = &global_data_summary.advertised_data;
+        let latest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+
+        // If nothing is advertised, validators that have already reached the
+        // waypoint can bootstrap (e.g., a single validator network). All other
+        // nodes must wait for peers.
+        if advertised_data.epoch_ending_ledger_infos.is_empty() {
+            if self.driver_configuration.role.is_validator()
+                && latest_synced_version >= self.driver_configuration.waypoint.version()
+            {
+                self.bootstrapping_complete();
+            }
+            return Ok(());
+        }
+
+        // Fetch and verify all epoch ending ledger infos
+        if !self.verified_epoch_states.fetched_epoch_ending_ledger_infos {
+            return self.fetch_epoch_ending_ledger_infos(advertised_data).await;
+        }
+
+        // Ensure the waypoint has been verified
+        let waypoint_version = self.driver_configuration.waypoint.version();
+        if !self.verified_epoch_states.verified_waypoint && latest_synced_version < waypoint_version
+        {
+            // Reset the verified epoch states so that they can be refetched
+            let latest_epoch_state = utils::fetch_latest_epoch_state(self.storage.clone())?;
+            self.verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state);
+            return Err(Error::VerificationError(format!(
+                "Failed to verify the waypoint (version: {:?}) using the advertised epoch ending ledger infos!",
+                waypoint_version
+            )));
+        }
+
+        // Check if we've already synced to the highest epoch ending version
+        let highest_ledger_info = match self
+            .verified_epoch_states
+            .get_highest_epoch_ending_ledger_info()
+        {
+            Some(highest_ledger_info)
+                if highest_ledger_info.ledger_info().version() > latest_synced_version =>
+            {
+                highest_ledger_info.clone()
+            }
+            _ => {
+                self.bootstrapping_complete();
+                return Ok(());
+            }
+        };
+
+        // Fast sync (i.e., download the account states) only if the node has
+        // not yet synced anything beyond genesis. Otherwise, sync the missing
+        // transactions (or transaction outputs).
+        let bootstrapping_mode = self.driver_configuration.config.bootstrapping_mode;
+        if bootstrapping_mode == BootstrappingMode::DownloadLatestAccountStates
+            && (latest_synced_version == 0
+                || self.account_state_syncer.ledger_info_to_sync.is_some())
+        {
+            self.fetch_all_account_states(highest_ledger_info).await
+        } else {
+            self.fetch_missing_transaction_data(latest_synced_version)
+                .await
+        }
+    }
+
+    /// Creates a data stream for all epoch ending ledger infos after the
+    /// latest verified epoch.
+    async fn fetch_epoch_ending_ledger_infos(
+        &mut self,
+        advertised_data: &AdvertisedData,
+    ) -> Result<(), Error> {
+        let next_epoch_to_fetch = self.verified_epoch_states.latest_epoch_state.epoch;
+        match highest_advertised_epoch_end(advertised_data) {
+            Some(highest_epoch_end) if highest_epoch_end >= next_epoch_to_fetch => {
+                let data_stream = self
+                    .streaming_client
+                    .get_all_epoch_ending_ledger_infos(next_epoch_to_fetch)
+                    .await?;
+                self.active_data_stream = Some(data_stream);
+            }
+            _ => {
+                // There are no new epoch ending ledger infos to fetch
+                self.verified_epoch_states.fetched_epoch_ending_ledger_infos = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a data stream for the account states at the version of the
+    /// given epoch ending ledger info. The transaction output at the same
+    /// version is fetched first (to identify the expected state root hash).
+    async fn fetch_all_account_states(
+        &mut self,
+        ledger_info_to_sync: LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        // If the ledger info to sync has changed, restart the account syncer
+        if self.account_state_syncer.ledger_info_to_sync.as_ref() != Some(&ledger_info_to_sync) {
+            self.account_state_syncer = AccountStateSyncer {
+                ledger_info_to_sync: Some(ledger_info_to_sync.clone()),
+                ..AccountStateSyncer::default()
+            };
+        }
+
+        let version = ledger_info_to_sync.ledger_info().version();
+        let data_stream = match &self.account_state_syncer.transaction_output_to_sync {
+            None => {
+                self.streaming_client
+                    .get_all_transaction_outputs(version, version, version)
+                    .await?
+            }
+            Some(transaction_output_to_sync) => {
+                let expected_root_hash = transaction_output_to_sync
+                    .proof
+                    .transaction_infos
+                    .first()
+                    .ok_or_else(|| {
+                        Error::InvalidPayload("The transaction output has no proof!".into())
+                    })?
+                    .state_root_hash();
+                self.storage_synchronizer
+                    .initialize_account_synchronizer(version, expected_root_hash)?;
+                self.account_state_syncer.next_account_index_to_process = 0;
+                self.streaming_client.get_all_accounts(version).await?
+            }
+        };
+        self.active_data_stream = Some(data_stream);
+        Ok(())
+    }
+
+    /// Creates a data stream for the transactions (or transaction outputs)
+    /// from the latest synced version to the end of the current epoch. Streams
+    /// never cross epoch boundaries, so that every chunk can be proven against
+    /// the verified epoch ending ledger info.
+    async fn fetch_missing_transaction_data(
+        &mut self,
+        latest_synced_version: Version,
+    ) -> Result<(), Error> {
+        let start_version = latest_synced_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("The start version has overflown!".into()))?;
+        let end_version = self
+            .verified_epoch_states
+            .get_epoch_ending_ledger_info_for_version(start_version)
+            .ok_or_else(|| {
+                Error::UnexpectedError(format!(
+                    "No epoch ending ledger info found for version: {:?}",
+                    start_version
+                ))
+            })?
+            .ledger_info()
+            .version();
+
+        let data_stream = match self.driver_configuration.config.bootstrapping_mode {
+            BootstrappingMode::DownloadLatestAccountStates => {
+                self.streaming_client
+                    .get_all_transaction_outputs(start_version, end_version, end_version)
+                    .await?
+            }
+            BootstrappingMode::ExecuteTransactionsFromGenesis => {
+                self.streaming_client
+                    .get_all_transactions(start_version, end_version, end_version, false)
+                    .await?
+            }
+        };
+        self.active_data_stream = Some(data_stream);
+        Ok(())
+    }
+
+    /// Processes the next notification along the active data stream
+    async fn process_active_stream_notifications(
+        &mut self,
+    ) -> Result<Option<CommitNotification>, Error> {
+        let active_data_stream = self
+            .active_data_stream
+            .as_mut()
+            .expect("The active data stream must exist!");
+        let data_notification = match utils::get_data_notification(
+            self.driver_configuration.config.max_stream_wait_time_ms,
+            active_data_stream,
+        )
+        .await
+        {
+            Ok(data_notification) => data_notification,
+            Err(error) => {
+                self.active_data_stream = None;
+                return Err(error);
+            }
+        };
+
+        let DataNotification {
+            notification_id,
+            data_payload,
+        } = data_notification;
+        match data_payload {
+            DataPayload::AccountStatesWithProof(account_states_chunk_with_proof) => self
+                .process_account_states_payload(notification_id, account_states_chunk_with_proof)
+                .await
+                .map(|_| None),
+            DataPayload::EndOfStream => self.process_end_of_stream().map(|_| None),
+            DataPayload::EpochEndingLedgerInfos(epoch_ending_ledger_infos) => self
+                .process_epoch_ending_payload(notification_id, epoch_ending_ledger_infos)
+                .await
+                .map(|_| None),
+            DataPayload::TransactionOutputsWithProof(transaction_outputs_with_proof) => {
+                self.process_transaction_outputs_payload(
+                    notification_id,
+                    transaction_outputs_with_proof,
+                )
+                .await
+            }
+            DataPayload::TransactionsWithProof(transactions_with_proof) => {
+                self.process_transactions_payload(notification_id, transactions_with_proof)
+                    .await
+            }
+            data_payload => {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::PayloadTypeIsIncorrect,
+                )
+                .await?;
+                Err(Error::InvalidPayload(format!(
+                    "Received an unexpected payload type: {:?}",
+                    data_payload
+                )))
+            }
+        }
+    }
+
+    /// Processes a chunk of account states by adding it to the state snapshot
+    async fn process_account_states_payload(
+        &mut self,
+        notification_id: NotificationId,
+        account_states_chunk_with_proof: AccountStatesChunkWithProof,
+    ) -> Result<(), Error> {
+        let expected_index = self.account_state_syncer.next_account_index_to_process;
+        if account_states_chunk_with_proof.first_index != expected_index {
+            self.reset_active_stream(notification_id, PayloadRefetchReason::InvalidPayloadData)
+                .await?;
+            return Err(Error::InvalidPayload(format!(
+                "The account states chunk starts at an unexpected index: {:?}, expected: {:?}",
+                account_states_chunk_with_proof.first_index, expected_index
+            )));
+        }
+
+        let next_index = account_states_chunk_with_proof
+            .last_index
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("The next index has overflown!".into()))?;
+        if let Err(error) = self
+            .storage_synchronizer
+            .save_account_states(account_states_chunk_with_proof)
+        {
+            self.reset_active_stream(
+                notification_id,
+                PayloadRefetchReason::ProofVerificationFailed,
+            )
+            .await?;
+            return Err(error);
+        }
+        self.account_state_syncer.next_account_index_to_process = next_index;
+        Ok(())
+    }
+
+    /// Processes the end of the active data stream
+    fn process_end_of_stream(&mut self) -> Result<(), Error> {
+        self.active_data_stream = None;
+
+        // The epoch ending ledger infos have all been fetched
+        if !self.verified_epoch_states.fetched_epoch_ending_ledger_infos {
+            self.verified_epoch_states.fetched_epoch_ending_ledger_infos = true;
+            return Ok(());
+        }
+
+        // All account states have been fetched, so finalize the state snapshot
+        if let Some(transaction_output_to_sync) =
+            self.account_state_syncer.transaction_output_to_sync.take()
+        {
+            let ledger_info_to_sync = self
+                .account_state_syncer
+                .ledger_info_to_sync
+                .take()
+                .expect("The ledger info to sync must exist!");
+            let ledger_infos_to_save = self
+                .verified_epoch_states
+                .get_epoch_ending_ledger_infos_up_to(ledger_info_to_sync.ledger_info().version());
+            self.storage_synchronizer
+                .finalize_account_synchronizer(transaction_output_to_sync, ledger_infos_to_save)?;
+            info!(
+                "Finished syncing the account states at version: {:?}",
+                ledger_info_to_sync.ledger_info().version()
+            );
+        }
+        Ok(())
+    }
+
+    /// Processes a chunk of epoch ending ledger infos by verifying each one
+    async fn process_epoch_ending_payload(
+        &mut self,
+        notification_id: NotificationId,
+        epoch_ending_ledger_infos: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        if self.verified_epoch_states.fetched_epoch_ending_ledger_infos {
+            self.reset_active_stream(
+                notification_id,
+                PayloadRefetchReason::PayloadTypeIsIncorrect,
+            )
+            .await?;
+            return Err(Error::InvalidPayload(
+                "Received epoch ending ledger infos after they were all fetched!".into(),
+            ));
+        }
+
+        for epoch_ending_ledger_info in epoch_ending_ledger_infos {
+            if let Err(error) = self.verified_epoch_states.verify_epoch_ending_ledger_info(
+                &epoch_ending_ledger_info,
+                &self.driver_configuration,
+            ) {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::ProofVerificationFailed,
+                )
+                .await?;
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes a chunk of transaction outputs. If fast syncing and the
+    /// output at the account states version is still missing, the output is
+    /// stored (and the account states are fetched next). Otherwise, the
+    /// outputs are applied and committed to storage.
+    async fn process_transaction_outputs_payload(
+        &mut self,
+        notification_id: NotificationId,
+        transaction_outputs_with_proof: TransactionOutputListWithProof,
+    ) -> Result<Option<CommitNotification>, Error> {
+        if let Some(ledger_info_to_sync) = self.account_state_syncer.ledger_info_to_sync.clone() {
+            if self
+                .account_state_syncer
+                .transaction_output_to_sync
+                .is_none()
+            {
+                let ledger_info = ledger_info_to_sync.ledger_info();
+                if let Err(error) =
+                    transaction_outputs_with_proof.verify(ledger_info, Some(ledger_info.version()))
+                {
+                    self.reset_active_stream(
+                        notification_id,
+                        PayloadRefetchReason::ProofVerificationFailed,
+                    )
+                    .await?;
+                    return Err(Error::VerificationError(format!(
+                        "The transaction output at the account states version failed verification: {:?}",
+                        error
+                    )));
+                }
+                self.account_state_syncer.transaction_output_to_sync =
+                    Some(transaction_outputs_with_proof);
+
+                // Drop the outputs stream so that the account states are streamed next
+                self.active_data_stream = None;
+                return Ok(None);
+            }
+        }
+
+        let first_version = match transaction_outputs_with_proof.first_transaction_output_version {
+            Some(first_version) => first_version,
+            None => {
+                self.reset_active_stream(notification_id, PayloadRefetchReason::InvalidPayloadData)
+                    .await?;
+                return Err(Error::InvalidPayload(
+                    "Received an empty transaction output list!".into(),
+                ));
+            }
+        };
+        let target_ledger_info = self.get_target_ledger_info(first_version)?;
+        let committed_transactions = transaction_outputs_with_proof
+            .transactions_and_outputs
+            .iter()
+            .map(|(transaction, _)| transaction.clone())
+            .collect();
+        match self.storage_synchronizer.apply_transaction_outputs(
+            transaction_outputs_with_proof,
+            target_ledger_info,
+            None,
+        ) {
+            Ok(committed_events) => Ok(Some(CommitNotification::new(
+                committed_events,
+                committed_transactions,
+            ))),
+            Err(error) => {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::ProofVerificationFailed,
+                )
+                .await?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Processes a chunk of transactions by executing and committing them
+    async fn process_transactions_payload(
+        &mut self,
+        notification_id: NotificationId,
+        transactions_with_proof: TransactionListWithProof,
+    ) -> Result<Option<CommitNotification>, Error> {
+        let first_version = match transactions_with_proof.first_transaction_version {
+            Some(first_version) => first_version,
+            None => {
+                self.reset_active_stream(notification_id, PayloadRefetchReason::InvalidPayloadData)
+                    .await?;
+                return Err(Error::InvalidPayload(
+                    "Received an empty transaction list!".into(),
+                ));
+            }
+        };
+        let target_ledger_info = self.get_target_ledger_info(first_version)?;
+        let committed_transactions = transactions_with_proof.transactions.clone();
+        match self.storage_synchronizer.execute_transactions(
+            transactions_with_proof,
+            target_ledger_info,
+            None,
+        ) {
+            Ok(committed_events) => Ok(Some(CommitNotification::new(
+                committed_events,
+                committed_transactions,
+            ))),
+            Err(error) => {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::ProofVerificationFailed,
+                )
+                .await?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Returns the verified epoch ending ledger info that the data starting at
+    /// `first_version` must be proven against.
+    fn get_target_ledger_info(
+        &self,
+        first_version: Version,
+    ) -> Result<LedgerInfoWithSignatures, Error> {
+        self.verified_epoch_states
+            .get_epoch_ending_ledger_info_for_version(first_version)
+            .cloned()
+            .ok_or_else(|| {
+                Error::UnexpectedError(format!(
+                    "No epoch ending ledger info found for version: {:?}",
+                    first_version
+                ))
+            })
+    }
+
+    /// Notifies the streaming service of the bad payload and drops the active
+    /// data stream. A new stream will be created on the next progress check.
+    async fn reset_active_stream(
+        &mut self,
+        notification_id: NotificationId,
+        refetch_reason: PayloadRefetchReason,
+    ) -> Result<(), Error> {
+        self.active_data_stream = None;
+        utils::notify_bad_payload(&self.streaming_client, notification_id, refetch_reason).await
+    }
+}
+
+/// Returns the highest epoch for which an epoch ending ledger info can be
+/// fetched from the network (if any). This matches the end epoch chosen by
+/// the data streaming service for epoch ending streams.
+fn highest_advertised_epoch_end(advertised_data: &AdvertisedData) -> Option<Epoch> {
+    advertised_data
+        .epoch_ending_ledger_infos
+        .iter()
+        .map(|epoch_range| epoch_range.highest)
+        .max()
+        .and_then(|highest_epoch| highest_epoch.checked_sub(1))
+}
@@ -0,0 +1,372 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::DriverConfiguration,
+    error::Error,
+    notification_handlers::{CommitNotification, ConsensusSyncRequest},
+    storage_synchronizer::StorageSynchronizerInterface,
+    utils,
+};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload, NotificationId},
+    data_stream::DataStreamListener,
+    streaming_client::{DataStreamingClient, PayloadRefetchReason},
+};
+use diem_config::config::ContinuousSyncingMode;
+use diem_types::{
+    epoch_change::Verifier,
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+};
+use std::sync::Arc;
+use storage_interface::DbReader;
+
+/// The continuous syncer keeps the node up to date with the blockchain once
+/// bootstrapping has completed. Full nodes continuously stream new data from
+/// the network, while validators only sync when consensus requests it (i.e.,
+/// to a specified sync target).
+pub struct ContinuousSyncer<StorageSyncer, StreamingClient> {
+    // The currently active data stream (provided by the data streaming service)
+    active_data_stream: Option<DataStreamListener>,
+
+    // The consensus sync target of the active data stream. This is only set
+    // for finite streams that sync to a target within the current epoch.
+    active_sync_target: Option<LedgerInfoWithSignatures>,
+
+    // The config of the state sync driver
+    driver_configuration: DriverConfiguration,
+
+    // The storage to read from (writes are done by the storage synchronizer)
+    storage: Arc<dyn DbReader<DpnProto>>,
+
+    // The storage synchronizer used to commit data to storage
+    storage_synchronizer: StorageSyncer,
+
+    // The client through which to stream data from the Diem network
+    streaming_client: StreamingClient,
+}
+
+impl<
+        StorageSyncer: StorageSynchronizerInterface,
+        StreamingClient: DataStreamingClient + Send + Clone + 'static,
+    > ContinuousSyncer<StorageSyncer, StreamingClient>
+{
+    pub fn new(
+        driver_configuration: DriverConfiguration,
+        streaming_client: StreamingClient,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        storage_synchronizer: StorageSyncer,
+    ) -> Self {
+        Self {
+            active_data_stream: None,
+            active_sync_target: None,
+            driver_configuration,
+            storage,
+            storage_synchronizer,
+            streaming_client,
+        }
+    }
+
+    /// Drives progress of the continuous syncer by processing the
+    /// notifications of the active data stream (or by creating a new stream
+    /// if none exists). Returns a commit notification if new data was
+    /// committed to storage.
+    pub async fn drive_progress(
+        &mut self,
+        consensus_sync_request: Option<&ConsensusSyncRequest>,
+    ) -> Result<Option<CommitNotification>, Error> {
+        // Validators only sync when consensus has sent a sync request
+        let sync_target = consensus_sync_request.map(|sync_request| sync_request.get_sync_target());
+        if self.driver_configuration.role.is_validator() && sync_target.is_none() {
+            self.drop_active_stream();
+            return Ok(None);
+        }
+
+        // Reset the active stream if the sync target has changed, or if a
+        // continuous stream has reached the epoch of the sync target (so that
+        // we don't sync beyond the target).
+        if self.active_data_stream.is_some() {
+            if let Some(sync_target) = &sync_target {
+                let current_epoch = utils::fetch_latest_epoch_state(self.storage.clone())?.epoch;
+                let target_changed = match &self.active_sync_target {
+                    Some(active_sync_target) => active_sync_target != sync_target,
+                    None => sync_target.ledger_info().epoch() == current_epoch,
+                };
+                if target_changed {
+                    self.drop_active_stream();
+                }
+            }
+        }
+
+        if self.active_data_stream.is_some() {
+            self.process_active_stream_notifications().await
+        } else {
+            self.initialize_active_data_stream(sync_target).await?;
+            Ok(None)
+        }
+    }
+
+    /// Initializes a new data stream. If the sync target is in the current
+    /// epoch, the stream is bounded by the target. Otherwise, a continuous
+    /// stream is created from the latest synced version.
+    async fn initialize_active_data_stream(
+        &mut self,
+        sync_target: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let latest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+        let latest_epoch_state = utils::fetch_latest_epoch_state(self.storage.clone())?;
+        let start_version = latest_synced_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("The start version has overflown!".into()))?;
+        let continuous_syncing_mode = self.driver_configuration.config.continuous_syncing_mode;
+
+        let data_stream = match sync_target {
+            Some(sync_target) if sync_target.ledger_info().epoch() == latest_epoch_state.epoch => {
+                latest_epoch_state.verify(&sync_target).map_err(|error| {
+                    Error::VerificationError(format!(
+                        "Failed to verify the sync target: {:?}",
+                        error
+                    ))
+                })?;
+                let target_version = sync_target.ledger_info().version();
+                let data_stream = match continuous_syncing_mode {
+                    ContinuousSyncingMode::ApplyTransactionOutputs => {
+                        self.streaming_client
+                            .get_all_transaction_outputs(
+                                start_version,
+                                target_version,
+                                target_version,
+                            )
+                            .await?
+                    }
+                    ContinuousSyncingMode::ExecuteTransactions => {
+                        self.streaming_client
+                            .get_all_transactions(
+                                start_version,
+                                target_version,
+                                target_version,
+                                false,
+                            )
+                            .await?
+                    }
+                };
+                self.active_sync_target = Some(sync_target);
+                data_stream
+            }
+            _ => match continuous_syncing_mode {
+                ContinuousSyncingMode::ApplyTransactionOutputs => {
+                    self.streaming_client
+                        .continuously_stream_transaction_outputs(
+                            start_version,
+                            latest_epoch_state.epoch,
+                        )
+                        .await?
+                }
+                ContinuousSyncingMode::ExecuteTransactions => {
+                    self.streaming_client
+                        .continuously_stream_transactions(
+                            start_version,
+                            latest_epoch_state.epoch,
+                            false,
+                        )
+                        .await?
+                }
+            },
+        };
+        self.active_data_stream = Some(data_stream);
+        Ok(())
+    }
+
+    /// Processes the next notification along the active data stream
+    async fn process_active_stream_notifications(
+        &mut self,
+    ) -> Result<Option<CommitNotification>, Error> {
+        let active_data_stream = self
+            .active_data_stream
+            .as_mut()
+            .expect("The active data stream must exist!");
+        let data_notification = match utils::get_data_notification(
+            self.driver_configuration.config.max_stream_wait_time_ms,
+            active_data_stream,
+        )
+        .await
+        {
+            Ok(data_notification) => data_notification,
+            Err(error) => {
+                self.drop_active_stream();
+                return Err(error);
+            }
+        };
+
+        let DataNotification {
+            notification_id,
+            data_payload,
+        } = data_notification;
+        match (data_payload, self.active_sync_target.clone()) {
+            (
+                DataPayload::ContinuousTransactionOutputsWithProof(
+                    ledger_info_with_sigs,
+                    transaction_outputs_with_proof,
+                ),
+                None,
+            ) => {
+                self.verify_ledger_info(notification_id, &ledger_info_with_sigs)
+                    .await?;
+                self.apply_transaction_outputs(
+                    notification_id,
+                    ledger_info_with_sigs,
+                    transaction_outputs_with_proof,
+                )
+                .await
+            }
+            (
+                DataPayload::ContinuousTransactionsWithProof(
+                    ledger_info_with_sigs,
+                    transactions_with_proof,
+                ),
+                None,
+            ) => {
+                self.verify_ledger_info(notification_id, &ledger_info_with_sigs)
+                    .await?;
+                self.execute_transactions(
+                    notification_id,
+                    ledger_info_with_sigs,
+                    transactions_with_proof,
+                )
+                .await
+            }
+            (
+                DataPayload::TransactionOutputsWithProof(transaction_outputs_with_proof),
+                Some(sync_target),
+            ) => {
+                self.apply_transaction_outputs(
+                    notification_id,
+                    sync_target,
+                    transaction_outputs_with_proof,
+                )
+                .await
+            }
+            (DataPayload::TransactionsWithProof(transactions_with_proof), Some(sync_target)) => {
+                self.execute_transactions(notification_id, sync_target, transactions_with_proof)
+                    .await
+            }
+            (DataPayload::EndOfStream, _) => {
+                self.drop_active_stream();
+                Ok(None)
+            }
+            (data_payload, _) => {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::PayloadTypeIsIncorrect,
+                )
+                .await?;
+                Err(Error::InvalidPayload(format!(
+                    "Received an unexpected payload type: {:?}",
+                    data_payload
+                )))
+            }
+        }
+    }
+
+    /// Verifies the given ledger info using the latest epoch state in storage
+    async fn verify_ledger_info(
+        &mut self,
+        notification_id: NotificationId,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        let latest_epoch_state = utils::fetch_latest_epoch_state(self.storage.clone())?;
+        if let Err(error) = latest_epoch_state.verify(ledger_info_with_sigs) {
+            self.reset_active_stream(
+                notification_id,
+                PayloadRefetchReason::ProofVerificationFailed,
+            )
+            .await?;
+            return Err(Error::VerificationError(format!(
+                "Failed to verify the ledger info: {:?}",
+                error
+            )));
+        }
+        Ok(())
+    }
+
+    /// Applies and commits the given transaction outputs (proven against the
+    /// verified target ledger info).
+    async fn apply_transaction_outputs(
+        &mut self,
+        notification_id: NotificationId,
+        target_ledger_info: LedgerInfoWithSignatures,
+        transaction_outputs_with_proof: TransactionOutputListWithProof,
+    ) -> Result<Option<CommitNotification>, Error> {
+        let committed_transactions = transaction_outputs_with_proof
+            .transactions_and_outputs
+            .iter()
+            .map(|(transaction, _)| transaction.clone())
+            .collect();
+        match self.storage_synchronizer.apply_transaction_outputs(
+            transaction_outputs_with_proof,
+            target_ledger_info,
+            None,
+        ) {
+            Ok(committed_events) => Ok(Some(CommitNotification::new(
+                committed_events,
+                committed_transactions,
+            ))),
+            Err(error) => {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::ProofVerificationFailed,
+                )
+                .await?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Executes and commits the given transactions (proven against the
+    /// verified target ledger info).
+    async fn execute_transactions(
+        &mut self,
+        notification_id: NotificationId,
+        target_ledger_info: LedgerInfoWithSignatures,
+        transactions_with_proof: TransactionListWithProof,
+    ) -> Result<Option<CommitNotification>, Error> {
+        let committed_transactions = transactions_with_proof.transactions.clone();
+        match self.storage_synchronizer.execute_transactions(
+            transactions_with_proof,
+            target_ledger_info,
+            None,
+        ) {
+            Ok(committed_events) => Ok(Some(CommitNotification::new(
+                committed_events,
+                committed_transactions,
+            ))),
+            Err(error) => {
+                self.reset_active_stream(
+                    notification_id,
+                    PayloadRefetchReason::ProofVerificationFailed,
+                )
+                .await?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Drops the active data stream (and the associated sync target)
+    fn drop_active_stream(&mut self) {
+        self.active_data_stream = None;
+        self.active_sync_target = None;
+    }
+
+    /// Notifies the streaming service of the bad payload and drops the active
+    /// data stream. A new stream will be created on the next progress check.
+    async fn reset_active_stream(
+        &mut self,
+        notification_id: NotificationId,
+        refetch_reason: PayloadRefetchReason,
+    ) -> Result<(), Error> {
+        self.drop_active_stream();
+        utils::notify_bad_payload(&self.streaming_client, notification_id, refetch_reason).await
+    }
+}
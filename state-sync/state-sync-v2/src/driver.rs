@@ -0,0 +1,338 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bootstrapper::Bootstrapper,
+    continuous_syncer::ContinuousSyncer,
+    error::Error,
+    notification_handlers::{
+        CommitNotification, ConsensusNotificationHandler, MempoolNotificationHandler,
+    },
+    storage_synchronizer::StorageSynchronizerInterface,
+    utils,
+};
+use consensus_notifications::{
+    ConsensusCommitNotification, ConsensusNotification, ConsensusSyncNotification,
+};
+use data_streaming_service::streaming_client::DataStreamingClient;
+use diem_config::config::{RoleType, StateSyncDriverConfig};
+use diem_data_client::{DataClientPayload, DiemDataClient, GlobalDataSummary};
+use diem_logger::prelude::*;
+use diem_types::{protocol_spec::DpnProto, waypoint::Waypoint};
+use event_notifications::EventSubscriptionService;
+use futures::{channel::oneshot, StreamExt};
+use mempool_notifications::MempoolNotificationSender;
+use std::{sync::Arc, time::Duration};
+use storage_interface::DbReader;
+use tokio::time::interval;
+use tokio_stream::wrappers::IntervalStream;
+
+/// The configuration of the state sync driver
+#[derive(Clone)]
+pub struct DriverConfiguration {
+    // The config file of the driver
+    pub config: StateSyncDriverConfig,
+
+    // The role of the node
+    pub role: RoleType,
+
+    // The trusted waypoint for the node
+    pub waypoint: Waypoint,
+}
+
+impl DriverConfiguration {
+    pub fn new(config: StateSyncDriverConfig, role: RoleType, waypoint: Waypoint) -> Self {
+        Self {
+            config,
+            role,
+            waypoint,
+        }
+    }
+}
+
+/// The state sync driver that drives synchronization progress. The driver
+/// first bootstraps the node (using the bootstrapper) and then keeps the node
+/// up to date (using the continuous syncer). It also handles notifications
+/// from consensus and notifies mempool and the event subscription service of
+/// newly committed data.
+pub struct StateSyncDriver<DataClient, MempoolNotifier, StorageSyncer, StreamingClient> {
+    // The component that bootstraps the node
+    bootstrapper: Bootstrapper<StorageSyncer, StreamingClient>,
+
+    // The notifier to alert the waiting listener (if any) once bootstrapping completes
+    bootstrap_notifier: Option<oneshot::Sender<()>>,
+
+    // The handler for notifications from consensus
+    consensus_notification_handler: ConsensusNotificationHandler,
+
+    // The component that keeps the node up to date once bootstrapped
+    continuous_syncer: ContinuousSyncer<StorageSyncer, StreamingClient>,
+
+    // The client for checking the global data summary of our peers
+    diem_data_client: DataClient,
+
+    // The configuration for the driver
+    driver_configuration: DriverConfiguration,
+
+    // The event subscription service to notify listeners of on-chain events
+    event_subscription_service: EventSubscriptionService,
+
+    // The handler for notifications to mempool
+    mempool_notification_handler: MempoolNotificationHandler<MempoolNotifier>,
+
+    // The storage to read the latest synced state from
+    storage: Arc<dyn DbReader<DpnProto>>,
+}
+
+impl<
+        DataClient: DiemDataClient + Send + Clone + 'static,
+        MempoolNotifier: MempoolNotificationSender,
+        StorageSyncer: StorageSynchronizerInterface + Clone,
+        StreamingClient: DataStreamingClient + Send + Clone + 'static,
+    > StateSyncDriver<DataClient, MempoolNotifier, StorageSyncer, StreamingClient>
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        consensus_notification_handler: ConsensusNotificationHandler,
+        driver_configuration: DriverConfiguration,
+        event_subscription_service: EventSubscriptionService,
+        mempool_notification_handler: MempoolNotificationHandler<MempoolNotifier>,
+        storage_synchronizer: StorageSyncer,
+        diem_data_client: DataClient,
+        streaming_client: StreamingClient,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        bootstrap_notifier: oneshot::Sender<()>,
+    ) -> Self {
+        let bootstrapper = Bootstrapper::new(
+            driver_configuration.clone(),
+            streaming_client.clone(),
+            storage.clone(),
+            storage_synchronizer.clone(),
+        );
+        let continuous_syncer = ContinuousSyncer::new(
+            driver_configuration.clone(),
+            streaming_client,
+            storage.clone(),
+            storage_synchronizer,
+        );
+
+        Self {
+            bootstrapper,
+            bootstrap_notifier: Some(bootstrap_notifier),
+            consensus_notification_handler,
+            continuous_syncer,
+            diem_data_client,
+            driver_configuration,
+            event_subscription_service,
+            mempool_notification_handler,
+            storage,
+        }
+    }
+
+    /// Starts the state sync driver, driving progress on every progress check
+    /// and handling consensus notifications as they arrive.
+    pub async fn start_driver(mut self) {
+        let mut progress_check_interval = IntervalStream::new(interval(Duration::from_millis(
+            self.driver_configuration.config.progress_check_interval_ms,
+        )))
+        .fuse();
+
+        loop {
+            ::futures::select! {
+                notification = self.consensus_notification_handler.select_next_some() => {
+                    self.handle_consensus_notification(notification).await;
+                }
+                _ = progress_check_interval.select_next_some() => {
+                    self.drive_progress().await;
+                }
+            }
+        }
+    }
+
+    /// Handles a notification sent by consensus
+    async fn handle_consensus_notification(&mut self, notification: ConsensusNotification) {
+        let result = match notification {
+            ConsensusNotification::NotifyCommit(commit_notification) => {
+                self.handle_consensus_commit_notification(commit_notification)
+                    .await
+            }
+            ConsensusNotification::SyncToTarget(sync_notification) => {
+                self.handle_consensus_sync_notification(sync_notification)
+                    .await
+            }
+        };
+        if let Err(error) = result {
+            error!("Failed to handle the consensus notification: {:?}", error);
+        }
+    }
+
+    /// Handles a commit notification sent by consensus
+    async fn handle_consensus_commit_notification(
+        &mut self,
+        commit_notification: ConsensusCommitNotification,
+    ) -> Result<(), Error> {
+        if let Err(error) = self.check_consensus_notification_validity() {
+            self.consensus_notification_handler
+                .respond_to_commit_notification(commit_notification, Err(error.clone()))
+                .await?;
+            return Err(error);
+        }
+
+        // Notify mempool and the event subscription service of the commit
+        let latest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+        let committed_data = CommitNotification::new(
+            commit_notification.reconfiguration_events.clone(),
+            commit_notification.transactions.clone(),
+        );
+        let result = self
+            .handle_commit_notification(committed_data, latest_synced_version)
+            .await;
+
+        // Respond to consensus and check the progress of any sync request
+        self.consensus_notification_handler
+            .respond_to_commit_notification(commit_notification, result.clone())
+            .await?;
+        result?;
+        self.consensus_notification_handler
+            .check_sync_request_progress(latest_synced_version)
+            .await
+    }
+
+    /// Handles a sync notification sent by consensus
+    async fn handle_consensus_sync_notification(
+        &mut self,
+        sync_notification: ConsensusSyncNotification,
+    ) -> Result<(), Error> {
+        if let Err(error) = self.check_consensus_notification_validity() {
+            self.consensus_notification_handler
+                .respond_to_sync_notification(sync_notification, Err(error.clone()))
+                .await?;
+            return Err(error);
+        }
+
+        let latest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+        self.consensus_notification_handler
+            .initialize_sync_request(sync_notification, latest_synced_version)
+            .await
+    }
+
+    /// Verifies that consensus notifications can be handled by this node.
+    /// Only validators that have finished bootstrapping handle notifications.
+    fn check_consensus_notification_validity(&self) -> Result<(), Error> {
+        if !self.driver_configuration.role.is_validator() {
+            return Err(Error::FullNodeConsensusNotification(
+                "Full nodes should not receive consensus notifications!".into(),
+            ));
+        }
+        if !self.bootstrapper.is_bootstrapped() {
+            return Err(Error::BootstrapNotComplete(
+                "Unable to handle consensus notifications until bootstrapping completes!".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Notifies mempool and the event subscription service of newly
+    /// committed data.
+    async fn handle_commit_notification(
+        &mut self,
+        commit_notification: CommitNotification,
+        latest_synced_version: u64,
+    ) -> Result<(), Error> {
+        let latest_synced_ledger_info =
+            utils::fetch_latest_synced_ledger_info(self.storage.clone())?;
+        commit_notification
+            .handle_commit_notification(
+                latest_synced_version,
+                &latest_synced_ledger_info,
+                &mut self.mempool_notification_handler,
+                &mut self.event_subscription_service,
+            )
+            .await
+    }
+
+    /// Checks the progress of any active consensus sync request and drives
+    /// the bootstrapper or continuous syncer (depending on whether the node
+    /// has already bootstrapped).
+    async fn drive_progress(&mut self) {
+        if let Err(error) = self.check_sync_request_progress().await {
+            error!("Failed to check the sync request progress: {:?}", error);
+        }
+
+        let global_data_summary = match self.fetch_global_data_summary() {
+            Ok(global_data_summary) => global_data_summary,
+            Err(error) => {
+                error!("Failed to fetch the global data summary: {:?}", error);
+                return;
+            }
+        };
+
+        let result = if self.bootstrapper.is_bootstrapped() {
+            let consensus_sync_request = self.consensus_notification_handler.get_sync_request();
+            self.continuous_syncer
+                .drive_progress(consensus_sync_request)
+                .await
+        } else {
+            self.bootstrapper.drive_progress(&global_data_summary).await
+        };
+
+        match result {
+            Ok(Some(commit_notification)) => {
+                let result = match utils::fetch_latest_synced_version(self.storage.clone()) {
+                    Ok(latest_synced_version) => {
+                        self.handle_commit_notification(commit_notification, latest_synced_version)
+                            .await
+                    }
+                    Err(error) => Err(error),
+                };
+                if let Err(error) = result {
+                    error!("Failed to handle the commit notification: {:?}", error);
+                }
+            }
+            Ok(None) => {}
+            Err(error) => {
+                warn!("Failed to drive state sync progress: {:?}", error);
+            }
+        }
+
+        if self.bootstrapper.is_bootstrapped() {
+            self.notify_bootstrap_listener();
+        }
+    }
+
+    /// Notifies the bootstrap listener that the node has bootstrapped (only
+    /// the first call sends a notification).
+    fn notify_bootstrap_listener(&mut self) {
+        if let Some(bootstrap_notifier) = self.bootstrap_notifier.take() {
+            if bootstrap_notifier.send(()).is_err() {
+                debug!("The bootstrap listener was dropped before the node bootstrapped");
+            }
+        }
+    }
+
+    /// Checks if the active consensus sync request (if any) has been reached
+    async fn check_sync_request_progress(&mut self) -> Result<(), Error> {
+        if !self.consensus_notification_handler.active_sync_request() {
+            return Ok(());
+        }
+        let latest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+        self.consensus_notification_handler
+            .check_sync_request_progress(latest_synced_version)
+            .await
+    }
+
+    /// Fetches the global data summary from the Diem data client
+    fn fetch_global_data_summary(&self) -> Result<GlobalDataSummary, Error> {
+        let data_client_response = self
+            .diem_data_client
+            .get_global_data_summary()
+            .map_err(|error| Error::AdvertisedDataError(error.to_string()))?;
+        match data_client_response.response_payload {
+            DataClientPayload::GlobalDataSummary(global_data_summary) => Ok(global_data_summary),
+            response_payload => Err(Error::AdvertisedDataError(format!(
+                "Expected a global data summary, but got: {:?}",
+                response_payload
+            ))),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::{DriverConfiguration, StateSyncDriver},
+    error::Error,
+    notification_handlers::{ConsensusNotificationHandler, MempoolNotificationHandler},
+    storage_synchronizer::StorageSynchronizer,
+    utils,
+};
+use consensus_notifications::ConsensusNotificationListener;
+use data_streaming_service::streaming_client::StreamingServiceClient;
+use diem_config::config::NodeConfig;
+use diem_data_client::DiemDataClient;
+use diem_types::{protocol_spec::DpnProto, waypoint::Waypoint};
+use event_notifications::{EventNotificationSender, EventSubscriptionService};
+use executor_types::ChunkExecutor;
+use futures::channel::oneshot;
+use mempool_notifications::MempoolNotificationSender;
+use storage_interface::DbReaderWriter;
+use tokio::runtime::{Builder, Runtime};
+
+/// Creates a new state sync driver and spawns it on a dedicated runtime
+pub struct DriverFactory {
+    bootstrap_listener: Option<oneshot::Receiver<()>>,
+    _driver_runtime: Runtime,
+}
+
+impl DriverFactory {
+    /// Creates and spawns a new state sync driver
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_and_spawn_driver<
+        DataClient: DiemDataClient + Send + Sync + Clone + 'static,
+        MempoolNotifier: MempoolNotificationSender + 'static,
+    >(
+        node_config: &NodeConfig,
+        waypoint: Waypoint,
+        storage: DbReaderWriter<DpnProto>,
+        chunk_executor: Box<dyn ChunkExecutor>,
+        mempool_notifier: MempoolNotifier,
+        consensus_listener: ConsensusNotificationListener,
+        mut event_subscription_service: EventSubscriptionService,
+        diem_data_client: DataClient,
+        streaming_service_client: StreamingServiceClient,
+    ) -> Self {
+        // Notify all reconfiguration subscribers of the initial on-chain configs
+        let latest_synced_version = utils::fetch_latest_synced_version(storage.reader.clone())
+            .expect("Unable to fetch the latest synced version!");
+        event_subscription_service
+            .notify_initial_configs(latest_synced_version)
+            .expect("Failed to notify reconfig subscribers on initialization!");
+
+        // Create the notification handlers
+        let consensus_notification_handler = ConsensusNotificationHandler::new(consensus_listener);
+        let mempool_notification_handler = MempoolNotificationHandler::new(
+            mempool_notifier,
+            node_config.state_sync.mempool_commit_timeout_ms,
+        );
+
+        // Create the driver and spawn it on the runtime
+        let (bootstrap_notifier, bootstrap_listener) = oneshot::channel();
+        let driver_configuration = DriverConfiguration::new(
+            node_config.state_sync.state_sync_driver.clone(),
+            node_config.base.role,
+            waypoint,
+        );
+        let storage_synchronizer = StorageSynchronizer::new(chunk_executor, storage.clone());
+        let state_sync_driver = StateSyncDriver::new(
+            consensus_notification_handler,
+            driver_configuration,
+            event_subscription_service,
+            mempool_notification_handler,
+            storage_synchronizer,
+            diem_data_client,
+            streaming_service_client,
+            storage.reader,
+            bootstrap_notifier,
+        );
+        let driver_runtime = Builder::new_multi_thread()
+            .thread_name("state-sync-driver")
+            .enable_all()
+            .build()
+            .expect("Failed to create the state sync driver runtime!");
+        driver_runtime.spawn(state_sync_driver.start_driver());
+
+        Self {
+            bootstrap_listener: Some(bootstrap_listener),
+            _driver_runtime: driver_runtime,
+        }
+    }
+
+    /// Waits until the driver has bootstrapped the node. This can only be
+    /// waited on once.
+    pub async fn wait_until_bootstrapped(&mut self) -> Result<(), Error> {
+        let bootstrap_listener = self.bootstrap_listener.take().ok_or_else(|| {
+            Error::UnexpectedError("The bootstrap listener was already consumed!".into())
+        })?;
+        bootstrap_listener.await.map_err(|_| {
+            Error::UnexpectedError("The driver stopped before the node bootstrapped!".into())
+        })
+    }
+}
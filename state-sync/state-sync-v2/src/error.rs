@@ -0,0 +1,59 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
+pub enum Error {
+    #[error("Advertised data error: {0}")]
+    AdvertisedDataError(String),
+    #[error("The node has already been bootstrapped!")]
+    AlreadyBootstrapped,
+    #[error("The bootstrapper has not yet completed: {0}")]
+    BootstrapNotComplete(String),
+    #[error("Failed to send callback: {0}")]
+    CallbackSendFailed(String),
+    #[error("Timed-out waiting for a data stream notification: {0}")]
+    DataStreamNotificationTimeout(String),
+    #[error("Error encountered in the event subscription service: {0}")]
+    EventNotificationError(String),
+    #[error("A consensus notification was sent to a full node: {0}")]
+    FullNodeConsensusNotification(String),
+    #[error("An integer overflow has occurred: {0}")]
+    IntegerOverflow(String),
+    #[error("Received an invalid payload: {0}")]
+    InvalidPayload(String),
+    #[error("Failed to notify mempool of the newly committed transactions: {0}")]
+    NotifyMempoolError(String),
+    #[error("A sync request was sent for an old target: {0}")]
+    OldSyncRequest(String),
+    #[error("Unexpected storage error: {0}")]
+    StorageError(String),
+    #[error("Unexpected error from the data streaming service: {0}")]
+    StreamingServiceError(String),
+    #[error("Synced beyond the target version! Synced version: {0}, target version: {1}")]
+    SyncedBeyondTarget(u64, u64),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+    #[error("Verification error: {0}")]
+    VerificationError(String),
+}
+
+impl From<data_streaming_service::error::Error> for Error {
+    fn from(error: data_streaming_service::error::Error) -> Self {
+        Error::StreamingServiceError(error.to_string())
+    }
+}
+
+impl From<event_notifications::Error> for Error {
+    fn from(error: event_notifications::Error) -> Self {
+        Error::EventNotificationError(error.to_string())
+    }
+}
+
+impl From<mempool_notifications::Error> for Error {
+    fn from(error: mempool_notifications::Error) -> Self {
+        Error::NotifyMempoolError(error.to_string())
+    }
+}
@@ -1,7 +1,22 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! This is an empty skeleton library for state sync v2.
-//!
-//! TODO(joshlind): update this description when we start implementing state sync v2.
+//! State sync v2 keeps the node's storage up to date with the rest of the
+//! network using the data streaming service. The driver first bootstraps the
+//! node (by verifying all epoch ending ledger infos and then either downloading
+//! the latest account states or replaying transactions), and then continuously
+//! syncs new data as the blockchain grows. All data is committed to storage
+//! through the chunk executor.
 #![forbid(unsafe_code)]
+
+mod bootstrapper;
+mod continuous_syncer;
+pub mod driver;
+pub mod driver_factory;
+pub mod error;
+pub mod notification_handlers;
+pub mod storage_synchronizer;
+mod utils;
+
+#[cfg(test)]
+mod tests;
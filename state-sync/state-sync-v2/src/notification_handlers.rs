@@ -0,0 +1,256 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use consensus_notifications::{
+    ConsensusCommitNotification, ConsensusNotification, ConsensusNotificationListener,
+    ConsensusSyncNotification,
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{Transaction, Version},
+};
+use event_notifications::{EventNotificationSender, EventSubscriptionService};
+use futures::{stream::FusedStream, Stream};
+use mempool_notifications::MempoolNotificationSender;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A notification for new data that has been committed to storage
+#[derive(Clone, Debug)]
+pub struct CommitNotification {
+    pub committed_events: Vec<ContractEvent>,
+    pub committed_transactions: Vec<Transaction>,
+}
+
+impl CommitNotification {
+    pub fn new(
+        committed_events: Vec<ContractEvent>,
+        committed_transactions: Vec<Transaction>,
+    ) -> Self {
+        Self {
+            committed_events,
+            committed_transactions,
+        }
+    }
+
+    /// Handles the commit notification by notifying mempool and the event
+    /// subscription service of the newly committed data.
+    pub async fn handle_commit_notification<M: MempoolNotificationSender>(
+        self,
+        latest_synced_version: Version,
+        latest_synced_ledger_info: &LedgerInfoWithSignatures,
+        mempool_notification_handler: &mut MempoolNotificationHandler<M>,
+        event_subscription_service: &mut EventSubscriptionService,
+    ) -> Result<(), Error> {
+        // Notify mempool of the committed transactions
+        let block_timestamp_usecs = latest_synced_ledger_info.ledger_info().timestamp_usecs();
+        mempool_notification_handler
+            .notify_mempool_of_committed_transactions(
+                self.committed_transactions,
+                block_timestamp_usecs,
+            )
+            .await?;
+
+        // Notify the event subscription service of the committed events
+        event_subscription_service
+            .notify_events(latest_synced_version, self.committed_events)
+            .map_err(|error| error.into())
+    }
+}
+
+/// A consensus sync request for a specified target ledger info
+#[derive(Debug)]
+pub struct ConsensusSyncRequest {
+    sync_notification: ConsensusSyncNotification,
+}
+
+impl ConsensusSyncRequest {
+    pub fn new(sync_notification: ConsensusSyncNotification) -> Self {
+        Self { sync_notification }
+    }
+
+    pub fn get_sync_target(&self) -> LedgerInfoWithSignatures {
+        self.sync_notification.target.clone()
+    }
+
+    pub fn get_sync_target_version(&self) -> Version {
+        self.sync_notification.target.ledger_info().version()
+    }
+}
+
+/// The handler for notifications received from consensus. At most one
+/// consensus sync request is held at any time.
+pub struct ConsensusNotificationHandler {
+    consensus_listener: ConsensusNotificationListener,
+    sync_request: Option<ConsensusSyncRequest>,
+}
+
+impl ConsensusNotificationHandler {
+    pub fn new(consensus_listener: ConsensusNotificationListener) -> Self {
+        Self {
+            consensus_listener,
+            sync_request: None,
+        }
+    }
+
+    /// Returns true iff there is a sync request currently blocking consensus
+    pub fn active_sync_request(&self) -> bool {
+        self.sync_request.is_some()
+    }
+
+    /// Returns the active sync request that consensus is waiting on
+    pub fn get_sync_request(&self) -> Option<&ConsensusSyncRequest> {
+        self.sync_request.as_ref()
+    }
+
+    /// Initializes the sync request received from consensus. If the target has
+    /// already been reached, consensus is notified immediately.
+    pub async fn initialize_sync_request(
+        &mut self,
+        sync_notification: ConsensusSyncNotification,
+        latest_synced_version: Version,
+    ) -> Result<(), Error> {
+        let sync_target_version = sync_notification.target.ledger_info().version();
+        if sync_target_version < latest_synced_version {
+            let error = Err(Error::OldSyncRequest(format!(
+                "Target version: {}, latest synced version: {}",
+                sync_target_version, latest_synced_version
+            )));
+            self.respond_to_sync_notification(sync_notification, error.clone())
+                .await?;
+            return error;
+        }
+        if sync_target_version == latest_synced_version {
+            return self
+                .respond_to_sync_notification(sync_notification, Ok(()))
+                .await;
+        }
+
+        // Replace any existing sync request (the old request is superseded)
+        if let Some(sync_request) = self.sync_request.take() {
+            let error = Err(Error::UnexpectedError(
+                "The sync request was superseded by a new request!".into(),
+            ));
+            self.respond_to_sync_notification(sync_request.sync_notification, error)
+                .await?;
+        }
+        self.sync_request = Some(ConsensusSyncRequest::new(sync_notification));
+        Ok(())
+    }
+
+    /// Checks if the active sync request (if any) has been satisfied by the
+    /// latest synced version and responds to consensus if so.
+    pub async fn check_sync_request_progress(
+        &mut self,
+        latest_synced_version: Version,
+    ) -> Result<(), Error> {
+        let sync_target_version = match &self.sync_request {
+            Some(sync_request) => sync_request.get_sync_target_version(),
+            None => return Ok(()),
+        };
+        if latest_synced_version < sync_target_version {
+            return Ok(()); // We're still syncing to the target
+        }
+
+        let sync_request = self.sync_request.take().expect("Sync request must exist!");
+        let result = if latest_synced_version > sync_target_version {
+            Err(Error::SyncedBeyondTarget(
+                latest_synced_version,
+                sync_target_version,
+            ))
+        } else {
+            Ok(())
+        };
+        self.respond_to_sync_notification(sync_request.sync_notification, result.clone())
+            .await?;
+        result
+    }
+
+    /// Responds to consensus for a commit notification using the given result
+    pub async fn respond_to_commit_notification(
+        &mut self,
+        commit_notification: ConsensusCommitNotification,
+        result: Result<(), Error>,
+    ) -> Result<(), Error> {
+        debug!(
+            "Responding to consensus commit notification with result: {:?}",
+            result
+        );
+        let result = result.map_err(|error| {
+            consensus_notifications::Error::UnexpectedErrorEncountered(format!("{:?}", error))
+        });
+        self.consensus_listener
+            .respond_to_commit_notification(commit_notification, result)
+            .await
+            .map_err(|error| Error::CallbackSendFailed(format!("{:?}", error)))
+    }
+
+    /// Responds to consensus for a sync notification using the given result
+    pub async fn respond_to_sync_notification(
+        &mut self,
+        sync_notification: ConsensusSyncNotification,
+        result: Result<(), Error>,
+    ) -> Result<(), Error> {
+        debug!(
+            "Responding to consensus sync notification with result: {:?}",
+            result
+        );
+        let result = result.map_err(|error| {
+            consensus_notifications::Error::UnexpectedErrorEncountered(format!("{:?}", error))
+        });
+        self.consensus_listener
+            .respond_to_sync_notification(sync_notification, result)
+            .await
+            .map_err(|error| Error::CallbackSendFailed(format!("{:?}", error)))
+    }
+}
+
+impl Stream for ConsensusNotificationHandler {
+    type Item = ConsensusNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().consensus_listener).poll_next(cx)
+    }
+}
+
+impl FusedStream for ConsensusNotificationHandler {
+    fn is_terminated(&self) -> bool {
+        self.consensus_listener.is_terminated()
+    }
+}
+
+/// The handler for notifying mempool of newly committed transactions
+pub struct MempoolNotificationHandler<M> {
+    mempool_commit_timeout_ms: u64,
+    mempool_notification_sender: M,
+}
+
+impl<M: MempoolNotificationSender> MempoolNotificationHandler<M> {
+    pub fn new(mempool_notification_sender: M, mempool_commit_timeout_ms: u64) -> Self {
+        Self {
+            mempool_commit_timeout_ms,
+            mempool_notification_sender,
+        }
+    }
+
+    /// Notifies mempool of the newly committed transactions
+    pub async fn notify_mempool_of_committed_transactions(
+        &mut self,
+        committed_transactions: Vec<Transaction>,
+        block_timestamp_usecs: u64,
+    ) -> Result<(), Error> {
+        self.mempool_notification_sender
+            .notify_new_commit(
+                committed_transactions,
+                block_timestamp_usecs,
+                self.mempool_commit_timeout_ms,
+            )
+            .await
+            .map_err(|error| error.into())
+    }
+}
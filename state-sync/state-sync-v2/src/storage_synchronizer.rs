@@ -0,0 +1,188 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_types::{
+    account_state_blob::{AccountStateBlob, AccountStatesChunkWithProof},
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::{
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        Version,
+    },
+};
+use executor_types::ChunkExecutor;
+use std::sync::Arc;
+use storage_interface::{DbReaderWriter, StateSnapshotReceiver};
+
+/// The interface between state sync and storage. All data written to storage
+/// is expected to have been fully verified (e.g., proofs and ledger infos)
+/// against trusted state, except for the proofs checked by the executor and
+/// the state snapshot receiver themselves.
+pub trait StorageSynchronizerInterface {
+    /// Applies the given transaction outputs (without re-executing the
+    /// transactions) and commits them to storage. Returns the reconfiguration
+    /// events emitted by the outputs.
+    fn apply_transaction_outputs(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error>;
+
+    /// Executes the given transactions and commits them to storage. Returns
+    /// the reconfiguration events emitted by the transactions.
+    fn execute_transactions(
+        &mut self,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error>;
+
+    /// Prepares storage to receive a state snapshot at the given version. All
+    /// subsequent account state chunks must be proven against the
+    /// `expected_root_hash`.
+    fn initialize_account_synchronizer(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<(), Error>;
+
+    /// Saves the given chunk of account states to the state snapshot
+    /// currently being synced.
+    fn save_account_states(
+        &mut self,
+        account_states_chunk_with_proof: AccountStatesChunkWithProof,
+    ) -> Result<(), Error>;
+
+    /// Completes the state snapshot currently being synced and commits the
+    /// single transaction output (at the snapshot version) and the given
+    /// epoch ending ledger infos to storage.
+    fn finalize_account_synchronizer(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        ledger_infos_to_save: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error>;
+}
+
+/// The default storage synchronizer that wraps the chunk executor (to
+/// execute/apply and commit chunks) and storage (to save state snapshots).
+/// Clones share the same executor and state snapshot receiver.
+#[derive(Clone)]
+pub struct StorageSynchronizer {
+    chunk_executor: Arc<dyn ChunkExecutor>,
+    state_snapshot_receiver: Arc<Mutex<Option<Box<dyn StateSnapshotReceiver<AccountStateBlob>>>>>,
+    storage: DbReaderWriter<DpnProto>,
+}
+
+impl StorageSynchronizer {
+    pub fn new(chunk_executor: Box<dyn ChunkExecutor>, storage: DbReaderWriter<DpnProto>) -> Self {
+        Self {
+            chunk_executor: Arc::from(chunk_executor),
+            state_snapshot_receiver: Arc::new(Mutex::new(None)),
+            storage,
+        }
+    }
+}
+
+impl StorageSynchronizerInterface for StorageSynchronizer {
+    fn apply_transaction_outputs(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error> {
+        self.chunk_executor
+            .apply_and_commit_chunk(
+                output_list_with_proof,
+                target_ledger_info,
+                end_of_epoch_ledger_info,
+            )
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Apply and commit chunk failed: {}", error))
+            })
+    }
+
+    fn execute_transactions(
+        &mut self,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error> {
+        self.chunk_executor
+            .execute_and_commit_chunk(
+                transaction_list_with_proof,
+                target_ledger_info,
+                end_of_epoch_ledger_info,
+            )
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Execute and commit chunk failed: {}", error))
+            })
+    }
+
+    fn initialize_account_synchronizer(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<(), Error> {
+        let state_snapshot_receiver = self
+            .storage
+            .writer
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to initialize the state snapshot receiver: {}",
+                    error
+                ))
+            })?;
+        *self.state_snapshot_receiver.lock() = Some(state_snapshot_receiver);
+        Ok(())
+    }
+
+    fn save_account_states(
+        &mut self,
+        account_states_chunk_with_proof: AccountStatesChunkWithProof,
+    ) -> Result<(), Error> {
+        let mut state_snapshot_receiver = self.state_snapshot_receiver.lock();
+        let state_snapshot_receiver = state_snapshot_receiver.as_mut().ok_or_else(|| {
+            Error::UnexpectedError("The state snapshot receiver has not been initialized!".into())
+        })?;
+        state_snapshot_receiver
+            .add_chunk(
+                account_states_chunk_with_proof.account_blobs,
+                account_states_chunk_with_proof.proof,
+            )
+            .map_err(|error| {
+                Error::VerificationError(format!("Failed to add the account chunk: {}", error))
+            })
+    }
+
+    fn finalize_account_synchronizer(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        ledger_infos_to_save: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let state_snapshot_receiver =
+            self.state_snapshot_receiver.lock().take().ok_or_else(|| {
+                Error::UnexpectedError(
+                    "The state snapshot receiver has not been initialized!".into(),
+                )
+            })?;
+        state_snapshot_receiver.finish_box().map_err(|error| {
+            Error::StorageError(format!("Failed to finish the state snapshot: {}", error))
+        })?;
+
+        let version = output_list_with_proof
+            .first_transaction_output_version
+            .ok_or_else(|| Error::InvalidPayload("The transaction output list is empty!".into()))?;
+        self.storage
+            .writer
+            .finalize_state_snapshot(version, output_list_with_proof, &ledger_infos_to_save)
+            .map_err(|error| {
+                Error::StorageError(format!("Failed to finalize the state snapshot: {}", error))
+            })
+    }
+}
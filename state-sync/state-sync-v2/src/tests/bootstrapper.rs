@@ -0,0 +1,240 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bootstrapper::Bootstrapper,
+    error::Error,
+    tests::utils::{
+        create_driver_configuration, create_global_summary, create_ledger_info, create_output_list,
+        create_transaction_list, verify_refetch_request, MockStorage, MockStorageSynchronizer,
+        MockStreamingClient,
+    },
+};
+use claim::{assert_matches, assert_none, assert_ok, assert_some};
+use data_streaming_service::{
+    data_notification::DataPayload,
+    streaming_client::{
+        GetAllEpochEndingLedgerInfosRequest, GetAllTransactionOutputsRequest,
+        GetAllTransactionsRequest, PayloadRefetchReason, StreamRequest,
+    },
+};
+use diem_config::config::{BootstrappingMode, RoleType};
+use diem_data_client::GlobalDataSummary;
+use diem_types::{validator_signer::ValidatorSigner, waypoint::Waypoint};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_bootstrap_no_advertised_data() {
+    // A full node can't bootstrap without peers
+    let (mut bootstrapper, _, streaming_client, _) = create_bootstrapper(
+        RoleType::FullNode,
+        BootstrappingMode::ExecuteTransactionsFromGenesis,
+    );
+    let result = bootstrapper
+        .drive_progress(&GlobalDataSummary::empty())
+        .await;
+    assert_none!(result.unwrap());
+    assert!(!bootstrapper.is_bootstrapped());
+    assert!(streaming_client.get_stream_requests().is_empty());
+
+    // A validator at the waypoint bootstraps immediately
+    let (mut bootstrapper, _, streaming_client, _) = create_bootstrapper(
+        RoleType::Validator,
+        BootstrappingMode::ExecuteTransactionsFromGenesis,
+    );
+    assert_ok!(
+        bootstrapper
+            .drive_progress(&GlobalDataSummary::empty())
+            .await
+    );
+    assert!(bootstrapper.is_bootstrapped());
+    assert!(streaming_client.get_stream_requests().is_empty());
+
+    // Verify bootstrapping can't continue
+    let result = bootstrapper
+        .drive_progress(&GlobalDataSummary::empty())
+        .await;
+    assert_matches!(result, Err(Error::AlreadyBootstrapped));
+}
+
+#[tokio::test]
+async fn test_bootstrap_by_executing_transactions() {
+    let (mut bootstrapper, storage, streaming_client, signer) = create_bootstrapper(
+        RoleType::FullNode,
+        BootstrappingMode::ExecuteTransactionsFromGenesis,
+    );
+    let global_data_summary = create_global_summary(3);
+
+    // Fetch and verify the epoch ending ledger infos
+    fetch_epoch_ending_ledger_infos(&mut bootstrapper, &streaming_client, &signer).await;
+
+    // Execute the transactions for each epoch (one stream per epoch)
+    for (start_version, end_version) in [(1, 100), (101, 200)] {
+        assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+        verify_last_stream_request(
+            &streaming_client,
+            StreamRequest::GetAllTransactions(GetAllTransactionsRequest {
+                start_version,
+                end_version,
+                max_proof_version: end_version,
+                include_events: false,
+            }),
+        );
+
+        let num_transactions = end_version - start_version + 1;
+        streaming_client.send_data_payload(DataPayload::TransactionsWithProof(
+            create_transaction_list(start_version, num_transactions),
+        ));
+        streaming_client.send_data_payload(DataPayload::EndOfStream);
+        let commit_notification = bootstrapper.drive_progress(&global_data_summary).await;
+        let commit_notification = assert_some!(commit_notification.unwrap());
+        assert_eq!(
+            commit_notification.committed_transactions.len() as u64,
+            num_transactions
+        );
+        assert_eq!(storage.get_synced_version(), end_version);
+        assert_none!(bootstrapper
+            .drive_progress(&global_data_summary)
+            .await
+            .unwrap());
+        assert!(!bootstrapper.is_bootstrapped());
+    }
+
+    // Verify the node is now bootstrapped
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+    assert!(bootstrapper.is_bootstrapped());
+}
+
+#[tokio::test]
+async fn test_bootstrap_invalid_epoch_ending_ledger_info() {
+    let (mut bootstrapper, _, streaming_client, _) = create_bootstrapper(
+        RoleType::FullNode,
+        BootstrappingMode::ExecuteTransactionsFromGenesis,
+    );
+    let global_data_summary = create_global_summary(3);
+
+    // Send an epoch ending ledger info signed by the wrong validator
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+    let invalid_signer = ValidatorSigner::random([1; 32]);
+    streaming_client.send_data_payload(DataPayload::EpochEndingLedgerInfos(vec![
+        create_ledger_info(1, 100, true, &invalid_signer),
+    ]));
+    let result = bootstrapper.drive_progress(&global_data_summary).await;
+    assert_matches!(result, Err(Error::VerificationError(_)));
+    verify_refetch_request(
+        &streaming_client,
+        PayloadRefetchReason::ProofVerificationFailed,
+    );
+
+    // Verify the epoch ending ledger infos are requested again
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+    verify_last_stream_request(
+        &streaming_client,
+        StreamRequest::GetAllEpochEndingLedgerInfos(GetAllEpochEndingLedgerInfosRequest {
+            start_epoch: 1,
+        }),
+    );
+}
+
+#[tokio::test]
+async fn test_bootstrap_invalid_account_states_output() {
+    let (mut bootstrapper, _, streaming_client, signer) = create_bootstrapper(
+        RoleType::FullNode,
+        BootstrappingMode::DownloadLatestAccountStates,
+    );
+    let global_data_summary = create_global_summary(3);
+
+    // Fetch and verify the epoch ending ledger infos
+    fetch_epoch_ending_ledger_infos(&mut bootstrapper, &streaming_client, &signer).await;
+
+    // Verify the transaction output at the highest epoch ending version is requested
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+    verify_last_stream_request(
+        &streaming_client,
+        StreamRequest::GetAllTransactionOutputs(GetAllTransactionOutputsRequest {
+            start_version: 200,
+            end_version: 200,
+            max_proof_version: 200,
+        }),
+    );
+
+    // Send an output that fails verification and verify a refetch
+    streaming_client.send_data_payload(DataPayload::TransactionOutputsWithProof(
+        create_output_list(200),
+    ));
+    let result = bootstrapper.drive_progress(&global_data_summary).await;
+    assert_matches!(result, Err(Error::VerificationError(_)));
+    verify_refetch_request(
+        &streaming_client,
+        PayloadRefetchReason::ProofVerificationFailed,
+    );
+    assert!(!bootstrapper.is_bootstrapped());
+}
+
+/// Creates a bootstrapper (at genesis) with a mock storage and streaming client
+fn create_bootstrapper(
+    role: RoleType,
+    bootstrapping_mode: BootstrappingMode,
+) -> (
+    Bootstrapper<MockStorageSynchronizer, MockStreamingClient>,
+    MockStorage,
+    MockStreamingClient,
+    ValidatorSigner,
+) {
+    let signer = ValidatorSigner::random(None);
+    let genesis_ledger_info = create_ledger_info(0, 0, true, &signer);
+    let waypoint = Waypoint::new_epoch_boundary(genesis_ledger_info.ledger_info()).unwrap();
+
+    let mut driver_configuration = create_driver_configuration(role, waypoint);
+    driver_configuration.config.bootstrapping_mode = bootstrapping_mode;
+    let storage = MockStorage::new(genesis_ledger_info);
+    let streaming_client = MockStreamingClient::default();
+    let bootstrapper = Bootstrapper::new(
+        driver_configuration,
+        streaming_client.clone(),
+        Arc::new(storage.clone()),
+        MockStorageSynchronizer::new(storage.clone()),
+    );
+
+    (bootstrapper, storage, streaming_client, signer)
+}
+
+/// Drives the bootstrapper to fetch and verify the epoch ending ledger infos
+/// for epochs 1 (ending at version 100) and 2 (ending at version 200).
+async fn fetch_epoch_ending_ledger_infos(
+    bootstrapper: &mut Bootstrapper<MockStorageSynchronizer, MockStreamingClient>,
+    streaming_client: &MockStreamingClient,
+    signer: &ValidatorSigner,
+) {
+    let global_data_summary = create_global_summary(3);
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+    verify_last_stream_request(
+        streaming_client,
+        StreamRequest::GetAllEpochEndingLedgerInfos(GetAllEpochEndingLedgerInfosRequest {
+            start_epoch: 1,
+        }),
+    );
+
+    streaming_client.send_data_payload(DataPayload::EpochEndingLedgerInfos(vec![
+        create_ledger_info(1, 100, true, signer),
+        create_ledger_info(2, 200, true, signer),
+    ]));
+    streaming_client.send_data_payload(DataPayload::EndOfStream);
+    for _ in 0..2 {
+        assert_none!(bootstrapper
+            .drive_progress(&global_data_summary)
+            .await
+            .unwrap());
+    }
+}
+
+/// Verifies that the last stream request matches the expected request
+fn verify_last_stream_request(
+    streaming_client: &MockStreamingClient,
+    expected_request: StreamRequest,
+) {
+    assert_eq!(
+        streaming_client.get_last_stream_request(),
+        Some(expected_request)
+    );
+}
@@ -0,0 +1,155 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    continuous_syncer::ContinuousSyncer,
+    error::Error,
+    notification_handlers::ConsensusSyncRequest,
+    tests::utils::{
+        create_driver_configuration, create_ledger_info, create_transaction_list,
+        verify_refetch_request, MockStorage, MockStorageSynchronizer, MockStreamingClient,
+    },
+};
+use claim::{assert_matches, assert_none, assert_ok, assert_some};
+use consensus_notifications::ConsensusSyncNotification;
+use data_streaming_service::{
+    data_notification::DataPayload,
+    streaming_client::{
+        ContinuouslyStreamTransactionsRequest, GetAllTransactionsRequest, PayloadRefetchReason,
+        StreamRequest,
+    },
+};
+use diem_config::config::RoleType;
+use diem_types::{validator_signer::ValidatorSigner, waypoint::Waypoint};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_continuous_sync_full_node() {
+    let (mut continuous_syncer, storage, streaming_client, signer) =
+        create_continuous_syncer(RoleType::FullNode);
+
+    // Verify a continuous stream is created from the latest synced version
+    assert_none!(continuous_syncer.drive_progress(None).await.unwrap());
+    assert_eq!(
+        streaming_client.get_last_stream_request(),
+        Some(StreamRequest::ContinuouslyStreamTransactions(
+            ContinuouslyStreamTransactionsRequest {
+                start_version: 1,
+                start_epoch: 1,
+                include_events: false,
+            }
+        ))
+    );
+
+    // Send transactions with a valid ledger info and verify they're committed
+    streaming_client.send_data_payload(DataPayload::ContinuousTransactionsWithProof(
+        create_ledger_info(1, 10, false, &signer),
+        create_transaction_list(1, 10),
+    ));
+    let commit_notification = continuous_syncer.drive_progress(None).await.unwrap();
+    let commit_notification = assert_some!(commit_notification);
+    assert_eq!(commit_notification.committed_transactions.len(), 10);
+    assert_eq!(storage.get_synced_version(), 10);
+}
+
+#[tokio::test]
+async fn test_continuous_sync_invalid_ledger_info() {
+    let (mut continuous_syncer, storage, streaming_client, _) =
+        create_continuous_syncer(RoleType::FullNode);
+    assert_ok!(continuous_syncer.drive_progress(None).await);
+
+    // Send transactions with a ledger info signed by the wrong validator
+    let invalid_signer = ValidatorSigner::random([1; 32]);
+    streaming_client.send_data_payload(DataPayload::ContinuousTransactionsWithProof(
+        create_ledger_info(1, 10, false, &invalid_signer),
+        create_transaction_list(1, 10),
+    ));
+    let result = continuous_syncer.drive_progress(None).await;
+    assert_matches!(result, Err(Error::VerificationError(_)));
+    assert_eq!(storage.get_synced_version(), 0);
+    verify_refetch_request(
+        &streaming_client,
+        PayloadRefetchReason::ProofVerificationFailed,
+    );
+}
+
+#[tokio::test]
+async fn test_continuous_sync_validator() {
+    let (mut continuous_syncer, storage, streaming_client, signer) =
+        create_continuous_syncer(RoleType::Validator);
+
+    // Verify validators don't sync without a consensus sync request
+    assert_none!(continuous_syncer.drive_progress(None).await.unwrap());
+    assert!(streaming_client.get_stream_requests().is_empty());
+
+    // Verify a stream to the sync target is created
+    let (sync_notification, _) =
+        ConsensusSyncNotification::new(create_ledger_info(1, 50, false, &signer));
+    let sync_request = ConsensusSyncRequest::new(sync_notification);
+    assert_none!(continuous_syncer
+        .drive_progress(Some(&sync_request))
+        .await
+        .unwrap());
+    assert_eq!(
+        streaming_client.get_last_stream_request(),
+        Some(StreamRequest::GetAllTransactions(
+            GetAllTransactionsRequest {
+                start_version: 1,
+                end_version: 50,
+                max_proof_version: 50,
+                include_events: false,
+            }
+        ))
+    );
+
+    // Send an invalid payload type and verify a refetch
+    streaming_client.send_data_payload(DataPayload::ContinuousTransactionsWithProof(
+        create_ledger_info(1, 10, false, &signer),
+        create_transaction_list(1, 10),
+    ));
+    let result = continuous_syncer.drive_progress(Some(&sync_request)).await;
+    assert_matches!(result, Err(Error::InvalidPayload(_)));
+    verify_refetch_request(
+        &streaming_client,
+        PayloadRefetchReason::PayloadTypeIsIncorrect,
+    );
+
+    // Sync to the target and verify the transactions are committed
+    assert_ok!(continuous_syncer.drive_progress(Some(&sync_request)).await);
+    streaming_client.send_data_payload(DataPayload::TransactionsWithProof(
+        create_transaction_list(1, 50),
+    ));
+    let commit_notification = continuous_syncer
+        .drive_progress(Some(&sync_request))
+        .await
+        .unwrap();
+    assert_some!(commit_notification);
+    assert_eq!(storage.get_synced_version(), 50);
+}
+
+/// Creates a continuous syncer (at genesis) with a mock storage and
+/// streaming client.
+fn create_continuous_syncer(
+    role: RoleType,
+) -> (
+    ContinuousSyncer<MockStorageSynchronizer, MockStreamingClient>,
+    MockStorage,
+    MockStreamingClient,
+    ValidatorSigner,
+) {
+    let signer = ValidatorSigner::random(None);
+    let genesis_ledger_info = create_ledger_info(0, 0, true, &signer);
+    let waypoint = Waypoint::new_epoch_boundary(genesis_ledger_info.ledger_info()).unwrap();
+
+    let driver_configuration = create_driver_configuration(role, waypoint);
+    let storage = MockStorage::new(genesis_ledger_info);
+    let streaming_client = MockStreamingClient::default();
+    let continuous_syncer = ContinuousSyncer::new(
+        driver_configuration,
+        streaming_client.clone(),
+        Arc::new(storage.clone()),
+        MockStorageSynchronizer::new(storage.clone()),
+    );
+
+    (continuous_syncer, storage, streaming_client, signer)
+}
@@ -0,0 +1,7 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+mod bootstrapper;
+mod continuous_syncer;
+mod notification_handlers;
+mod utils;
@@ -0,0 +1,85 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error, notification_handlers::ConsensusNotificationHandler,
+    tests::utils::create_ledger_info,
+};
+use claim::{assert_err, assert_matches, assert_none, assert_ok};
+use consensus_notifications::{new_consensus_notifier_listener_pair, ConsensusSyncNotification};
+use diem_types::validator_signer::ValidatorSigner;
+
+#[tokio::test]
+async fn test_old_sync_request() {
+    let mut consensus_notification_handler = create_consensus_notification_handler();
+
+    // Send a sync request for a version older than the synced version
+    let signer = ValidatorSigner::random(None);
+    let (sync_notification, callback_receiver) =
+        ConsensusSyncNotification::new(create_ledger_info(1, 50, false, &signer));
+    let result = consensus_notification_handler
+        .initialize_sync_request(sync_notification, 100)
+        .await;
+    assert_matches!(result, Err(Error::OldSyncRequest(_)));
+
+    // Verify consensus was notified of the error
+    assert_err!(callback_receiver.await.unwrap().result);
+    assert!(!consensus_notification_handler.active_sync_request());
+}
+
+#[tokio::test]
+async fn test_sync_request_already_satisfied() {
+    let mut consensus_notification_handler = create_consensus_notification_handler();
+
+    // Send a sync request for the synced version and verify an immediate response
+    let signer = ValidatorSigner::random(None);
+    let (sync_notification, callback_receiver) =
+        ConsensusSyncNotification::new(create_ledger_info(1, 100, false, &signer));
+    assert_ok!(
+        consensus_notification_handler
+            .initialize_sync_request(sync_notification, 100)
+            .await
+    );
+    assert_ok!(callback_receiver.await.unwrap().result);
+    assert!(!consensus_notification_handler.active_sync_request());
+}
+
+#[tokio::test]
+async fn test_sync_request_progress() {
+    let mut consensus_notification_handler = create_consensus_notification_handler();
+
+    // Send a sync request for a version ahead of the synced version
+    let signer = ValidatorSigner::random(None);
+    let (sync_notification, mut callback_receiver) =
+        ConsensusSyncNotification::new(create_ledger_info(1, 100, false, &signer));
+    assert_ok!(
+        consensus_notification_handler
+            .initialize_sync_request(sync_notification, 50)
+            .await
+    );
+    assert!(consensus_notification_handler.active_sync_request());
+
+    // Verify no response is sent until the target is reached
+    assert_ok!(
+        consensus_notification_handler
+            .check_sync_request_progress(99)
+            .await
+    );
+    assert_none!(callback_receiver.try_recv().unwrap());
+    assert!(consensus_notification_handler.active_sync_request());
+
+    // Reach the target and verify consensus is notified
+    assert_ok!(
+        consensus_notification_handler
+            .check_sync_request_progress(100)
+            .await
+    );
+    assert_ok!(callback_receiver.await.unwrap().result);
+    assert!(!consensus_notification_handler.active_sync_request());
+}
+
+/// Creates a consensus notification handler (the notifier is dropped)
+fn create_consensus_notification_handler() -> ConsensusNotificationHandler {
+    let (_, consensus_listener) = new_consensus_notifier_listener_pair(1000);
+    ConsensusNotificationHandler::new(consensus_listener)
+}
@@ -0,0 +1,657 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::DriverConfiguration, error::Error, storage_synchronizer::StorageSynchronizerInterface,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use channel::{diem_channel, message_queues::QueueStyle};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload, NotificationId},
+    data_stream::DataStreamListener,
+    error,
+    streaming_client::{
+        ContinuouslyStreamTransactionOutputsRequest, ContinuouslyStreamTransactionsRequest,
+        DataStreamingClient, Epoch, GetAllAccountsRequest, GetAllEpochEndingLedgerInfosRequest,
+        GetAllTransactionOutputsRequest, GetAllTransactionsRequest, PayloadRefetchReason,
+        RefetchNotificationPayloadRequest, StreamRequest,
+    },
+};
+use diem_config::config::{RoleType, StateSyncDriverConfig};
+use diem_crypto::HashValue;
+use diem_data_client::{AdvertisedData, GlobalDataSummary};
+use diem_infallible::Mutex;
+use diem_types::{
+    account_address::AccountAddress,
+    account_state_blob::{
+        default_protocol::AccountStateWithProof, AccountStateBlob, AccountStatesChunkWithProof,
+    },
+    block_info::BlockInfo,
+    block_metadata::BlockMetadata,
+    contract_event::{
+        default_protocol::{EventByVersionWithProof, EventWithProof},
+        ContractEvent,
+    },
+    epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
+    event::EventKey,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{SparseMerkleProof, TransactionAccumulatorRangeProof, TransactionInfoListWithProof},
+    protocol_spec::DpnProto,
+    state_proof::StateProof,
+    transaction::{
+        default_protocol::{
+            AccountTransactionsWithProof, TransactionListWithProof, TransactionOutputListWithProof,
+            TransactionWithProof,
+        },
+        Transaction, TransactionInfo, TransactionInfoTrait, Version,
+    },
+    validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+    vm_status::KeptVMStatus,
+    waypoint::Waypoint,
+};
+use std::{collections::BTreeMap, sync::Arc};
+use storage_interface::{DbReader, Order, StartupInfo, TreeState};
+use storage_service_types::CompleteDataRange;
+
+/// Creates a driver configuration for the given role and waypoint
+pub fn create_driver_configuration(role: RoleType, waypoint: Waypoint) -> DriverConfiguration {
+    let config = StateSyncDriverConfig {
+        max_stream_wait_time_ms: 100,
+        ..StateSyncDriverConfig::default()
+    };
+    DriverConfiguration::new(config, role, waypoint)
+}
+
+/// Returns the epoch state for the given epoch (using the given signer)
+pub fn create_epoch_state(epoch: Epoch, signer: &ValidatorSigner) -> EpochState {
+    EpochState {
+        epoch,
+        verifier: ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    }
+}
+
+/// Returns a ledger info at the given epoch and version, signed by the
+/// given signer. If `ends_epoch` is true, the ledger info carries the epoch
+/// state of the next epoch.
+pub fn create_ledger_info(
+    epoch: Epoch,
+    version: Version,
+    ends_epoch: bool,
+    signer: &ValidatorSigner,
+) -> LedgerInfoWithSignatures {
+    let next_epoch_state = if ends_epoch {
+        Some(create_epoch_state(epoch + 1, signer))
+    } else {
+        None
+    };
+    let block_info = BlockInfo::new(
+        epoch,
+        0,
+        HashValue::zero(),
+        HashValue::zero(),
+        version,
+        0,
+        next_epoch_state,
+    );
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    let mut signatures = BTreeMap::new();
+    signatures.insert(signer.author(), signer.sign(&ledger_info));
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
+
+/// Returns a global data summary advertising epoch ending ledger infos up
+/// to the given highest epoch.
+pub fn create_global_summary(highest_epoch: Epoch) -> GlobalDataSummary {
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary.advertised_data = AdvertisedData {
+        account_states: vec![],
+        epoch_ending_ledger_infos: vec![CompleteDataRange::new(0, highest_epoch)],
+        synced_ledger_infos: vec![],
+        transactions: vec![],
+        transaction_outputs: vec![],
+    };
+    global_data_summary
+}
+
+/// Returns a dummy transaction list (with the given number of transactions)
+pub fn create_transaction_list(
+    first_version: Version,
+    num_transactions: u64,
+) -> TransactionListWithProof {
+    let transactions = (0..num_transactions)
+        .map(|_| create_transaction())
+        .collect();
+    TransactionListWithProof::new(
+        transactions,
+        None,
+        Some(first_version),
+        TransactionInfoListWithProof::new_empty(),
+    )
+}
+
+/// Returns a dummy transaction output list with a single (unverifiable)
+/// output at the given version.
+pub fn create_output_list(version: Version) -> TransactionOutputListWithProof {
+    let transaction_info = TransactionInfo::new(
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+        0,
+        KeptVMStatus::Executed,
+    );
+    let proof = TransactionInfoListWithProof::new(
+        TransactionAccumulatorRangeProof::new_empty(),
+        vec![transaction_info],
+    );
+    TransactionOutputListWithProof::new(vec![], Some(version), proof)
+}
+
+/// Returns a dummy transaction
+pub fn create_transaction() -> Transaction {
+    Transaction::BlockMetadata(BlockMetadata::new(
+        HashValue::zero(),
+        0,
+        0,
+        vec![],
+        AccountAddress::ZERO,
+        vec![],
+    ))
+}
+
+/// A simple mock of storage that holds the latest synced version and
+/// ledger info (shared by all clones).
+#[derive(Clone)]
+pub struct MockStorage {
+    state: Arc<Mutex<MockStorageState>>,
+}
+
+struct MockStorageState {
+    latest_epoch_state: EpochState,
+    latest_ledger_info: LedgerInfoWithSignatures,
+    synced_version: Version,
+}
+
+impl MockStorage {
+    /// Creates a mock storage at the given (epoch ending) ledger info
+    pub fn new(latest_ledger_info: LedgerInfoWithSignatures) -> Self {
+        let latest_epoch_state = latest_ledger_info
+            .ledger_info()
+            .next_epoch_state()
+            .cloned()
+            .expect("The ledger info must end the epoch!");
+        let synced_version = latest_ledger_info.ledger_info().version();
+        let state = MockStorageState {
+            latest_epoch_state,
+            latest_ledger_info,
+            synced_version,
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn get_synced_version(&self) -> Version {
+        self.state.lock().synced_version
+    }
+
+    /// Commits data up to the given version (and the ledger info, if any)
+    pub fn commit(&self, version: Version, ledger_info: Option<LedgerInfoWithSignatures>) {
+        let mut state = self.state.lock();
+        state.synced_version = version;
+        if let Some(ledger_info) = ledger_info {
+            if let Some(next_epoch_state) = ledger_info.ledger_info().next_epoch_state() {
+                state.latest_epoch_state = next_epoch_state.clone();
+            }
+            state.latest_ledger_info = ledger_info;
+        }
+    }
+}
+
+impl DbReader<DpnProto> for MockStorage {
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        _start_epoch: u64,
+        _end_epoch: u64,
+    ) -> Result<EpochChangeProof> {
+        unimplemented!()
+    }
+
+    fn get_transactions(
+        &self,
+        _start_version: Version,
+        _batch_size: u64,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<TransactionListWithProof> {
+        unimplemented!()
+    }
+
+    fn get_transaction_by_hash(
+        &self,
+        _hash: HashValue,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_transaction_by_version(
+        &self,
+        _version: u64,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<TransactionWithProof> {
+        unimplemented!()
+    }
+
+    fn get_transaction_outputs(
+        &self,
+        _start_version: Version,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof> {
+        unimplemented!()
+    }
+
+    fn get_events(
+        &self,
+        _event_key: &EventKey,
+        _start: u64,
+        _order: Order,
+        _limit: u64,
+    ) -> Result<Vec<(u64, ContractEvent)>> {
+        unimplemented!()
+    }
+
+    fn get_events_with_proofs(
+        &self,
+        _event_key: &EventKey,
+        _start: u64,
+        _order: Order,
+        _limit: u64,
+        _known_version: Option<u64>,
+    ) -> Result<Vec<EventWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_block_timestamp(&self, _version: u64) -> Result<u64> {
+        unimplemented!()
+    }
+
+    fn get_event_by_version_with_proof(
+        &self,
+        _event_key: &EventKey,
+        _version: u64,
+        _proof_version: u64,
+    ) -> Result<EventByVersionWithProof> {
+        unimplemented!()
+    }
+
+    fn get_latest_account_state(
+        &self,
+        _address: AccountAddress,
+    ) -> Result<Option<AccountStateBlob>> {
+        unimplemented!()
+    }
+
+    fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        Ok(self.state.lock().latest_ledger_info.clone())
+    }
+
+    fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
+        let state = self.state.lock();
+        let tree_state = TreeState::new(state.synced_version, vec![], HashValue::zero());
+        Ok(Some(StartupInfo::new(
+            state.latest_ledger_info.clone(),
+            Some(state.latest_epoch_state.clone()),
+            tree_state,
+            None,
+        )))
+    }
+
+    fn get_account_transaction(
+        &self,
+        _address: AccountAddress,
+        _seq_num: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_account_transactions(
+        &self,
+        _address: AccountAddress,
+        _start_seq_num: u64,
+        _limit: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        unimplemented!()
+    }
+
+    fn get_state_proof_with_ledger_info(
+        &self,
+        _known_version: u64,
+        _ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<StateProof> {
+        unimplemented!()
+    }
+
+    fn get_state_proof(&self, _known_version: u64) -> Result<StateProof> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStateWithProof> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof_by_version(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+    ) -> Result<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        unimplemented!()
+    }
+
+    fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
+        unimplemented!()
+    }
+
+    fn get_latest_tree_state(&self) -> Result<TreeState> {
+        unimplemented!()
+    }
+
+    fn get_epoch_ending_ledger_info(
+        &self,
+        _known_version: u64,
+    ) -> Result<LedgerInfoWithSignatures> {
+        unimplemented!()
+    }
+
+    fn get_latest_transaction_info_option(&self) -> Result<Option<(Version, TransactionInfo)>> {
+        let transaction_info = TransactionInfo::new(
+            HashValue::zero(),
+            HashValue::zero(),
+            HashValue::zero(),
+            0,
+            KeptVMStatus::Executed,
+        );
+        Ok(Some((self.get_synced_version(), transaction_info)))
+    }
+}
+
+/// A mock storage synchronizer that commits chunks to the mock storage
+/// without executing or verifying anything.
+#[derive(Clone)]
+pub struct MockStorageSynchronizer {
+    storage: MockStorage,
+}
+
+impl MockStorageSynchronizer {
+    pub fn new(storage: MockStorage) -> Self {
+        Self { storage }
+    }
+
+    fn commit_chunk(
+        &self,
+        first_version: Option<Version>,
+        num_items: usize,
+        target_ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<Vec<ContractEvent>, Error> {
+        let first_version =
+            first_version.ok_or_else(|| Error::UnexpectedError("The chunk is empty!".into()))?;
+        let last_version = first_version + num_items as u64 - 1;
+        if last_version == target_ledger_info.ledger_info().version() {
+            self.storage.commit(last_version, Some(target_ledger_info));
+        } else {
+            self.storage.commit(last_version, None);
+        }
+        Ok(vec![])
+    }
+}
+
+impl StorageSynchronizerInterface for MockStorageSynchronizer {
+    fn apply_transaction_outputs(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        _end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error> {
+        self.commit_chunk(
+            output_list_with_proof.first_transaction_output_version,
+            output_list_with_proof.transactions_and_outputs.len(),
+            target_ledger_info,
+        )
+    }
+
+    fn execute_transactions(
+        &mut self,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        _end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error> {
+        self.commit_chunk(
+            transaction_list_with_proof.first_transaction_version,
+            transaction_list_with_proof.transactions.len(),
+            target_ledger_info,
+        )
+    }
+
+    fn initialize_account_synchronizer(
+        &mut self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn save_account_states(
+        &mut self,
+        _account_states_chunk_with_proof: AccountStatesChunkWithProof,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn finalize_account_synchronizer(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        ledger_infos_to_save: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let version = output_list_with_proof
+            .first_transaction_output_version
+            .expect("The output list must not be empty!");
+        self.storage
+            .commit(version, ledger_infos_to_save.last().cloned());
+        Ok(())
+    }
+}
+
+/// A mock streaming client that records all stream requests and allows
+/// tests to send data notifications along the most recently created stream.
+#[derive(Clone)]
+pub struct MockStreamingClient {
+    state: Arc<Mutex<MockStreamingClientState>>,
+}
+
+#[derive(Default)]
+struct MockStreamingClientState {
+    next_notification_id: NotificationId,
+    notification_sender: Option<diem_channel::Sender<(), DataNotification>>,
+    stream_requests: Vec<StreamRequest>,
+}
+
+impl Default for MockStreamingClient {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockStreamingClientState::default())),
+        }
+    }
+}
+
+impl MockStreamingClient {
+    /// Returns all stream requests made by the client (in order)
+    pub fn get_stream_requests(&self) -> Vec<StreamRequest> {
+        self.state.lock().stream_requests.clone()
+    }
+
+    /// Returns the most recent stream request made by the client
+    pub fn get_last_stream_request(&self) -> Option<StreamRequest> {
+        self.state.lock().stream_requests.last().cloned()
+    }
+
+    /// Sends the given payload along the most recently created stream
+    pub fn send_data_payload(&self, data_payload: DataPayload) {
+        let mut state = self.state.lock();
+        let notification_id = state.next_notification_id;
+        state.next_notification_id += 1;
+        state
+            .notification_sender
+            .as_mut()
+            .expect("No data stream has been created!")
+            .push(
+                (),
+                DataNotification {
+                    notification_id,
+                    data_payload,
+                },
+            )
+            .unwrap();
+    }
+
+    fn create_data_stream(&self, stream_request: StreamRequest) -> DataStreamListener {
+        let (notification_sender, notification_receiver) =
+            diem_channel::new(QueueStyle::FIFO, 100, None);
+        let mut state = self.state.lock();
+        state.notification_sender = Some(notification_sender);
+        state.stream_requests.push(stream_request);
+        DataStreamListener::new(notification_receiver)
+    }
+}
+
+#[async_trait]
+impl DataStreamingClient for MockStreamingClient {
+    async fn get_all_accounts(&self, version: Version) -> Result<DataStreamListener, error::Error> {
+        Ok(
+            self.create_data_stream(StreamRequest::GetAllAccounts(GetAllAccountsRequest {
+                version,
+            })),
+        )
+    }
+
+    async fn get_all_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: Epoch,
+    ) -> Result<DataStreamListener, error::Error> {
+        Ok(
+            self.create_data_stream(StreamRequest::GetAllEpochEndingLedgerInfos(
+                GetAllEpochEndingLedgerInfosRequest { start_epoch },
+            )),
+        )
+    }
+
+    async fn get_all_transactions(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        max_proof_version: Version,
+        include_events: bool,
+    ) -> Result<DataStreamListener, error::Error> {
+        Ok(self.create_data_stream(StreamRequest::GetAllTransactions(
+            GetAllTransactionsRequest {
+                start_version,
+                end_version,
+                max_proof_version,
+                include_events,
+            },
+        )))
+    }
+
+    async fn get_all_transaction_outputs(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        max_proof_version: Version,
+    ) -> Result<DataStreamListener, error::Error> {
+        Ok(
+            self.create_data_stream(StreamRequest::GetAllTransactionOutputs(
+                GetAllTransactionOutputsRequest {
+                    start_version,
+                    end_version,
+                    max_proof_version,
+                },
+            )),
+        )
+    }
+
+    async fn refetch_notification_payload(
+        &self,
+        notification_id: NotificationId,
+        refetch_reason: PayloadRefetchReason,
+    ) -> Result<DataStreamListener, error::Error> {
+        Ok(
+            self.create_data_stream(StreamRequest::RefetchNotificationPayload(
+                RefetchNotificationPayloadRequest {
+                    notification_id,
+                    refetch_reason,
+                },
+            )),
+        )
+    }
+
+    async fn continuously_stream_transactions(
+        &self,
+        start_version: Version,
+        start_epoch: Epoch,
+        include_events: bool,
+    ) -> Result<DataStreamListener, error::Error> {
+        Ok(
+            self.create_data_stream(StreamRequest::ContinuouslyStreamTransactions(
+                ContinuouslyStreamTransactionsRequest {
+                    start_version,
+                    start_epoch,
+                    include_events,
+                },
+            )),
+        )
+    }
+
+    async fn continuously_stream_transaction_outputs(
+        &self,
+        start_version: Version,
+        start_epoch: Epoch,
+    ) -> Result<DataStreamListener, error::Error> {
+        Ok(
+            self.create_data_stream(StreamRequest::ContinuouslyStreamTransactionOutputs(
+                ContinuouslyStreamTransactionOutputsRequest {
+                    start_version,
+                    start_epoch,
+                },
+            )),
+        )
+    }
+}
+
+/// Verifies that the last stream request was a refetch for the given reason
+pub fn verify_refetch_request(
+    streaming_client: &MockStreamingClient,
+    expected_reason: PayloadRefetchReason,
+) {
+    match streaming_client.get_last_stream_request() {
+        Some(StreamRequest::RefetchNotificationPayload(request)) => {
+            assert_eq!(request.refetch_reason, expected_reason)
+        }
+        request => panic!("Expected a refetch request, but got: {:?}", request),
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use data_streaming_service::{
+    data_notification::{DataNotification, NotificationId},
+    data_stream::DataStreamListener,
+    streaming_client::{DataStreamingClient, PayloadRefetchReason},
+};
+use diem_types::{
+    epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures, move_resource::MoveStorage,
+    protocol_spec::DpnProto, transaction::Version,
+};
+use futures::StreamExt;
+use std::{sync::Arc, time::Duration};
+use storage_interface::DbReader;
+use tokio::time::timeout;
+
+/// Fetches the next data notification from the given data stream listener.
+/// Returns an error if no notification arrives within `max_stream_wait_time_ms`
+/// or if the stream has been terminated.
+pub async fn get_data_notification(
+    max_stream_wait_time_ms: u64,
+    active_data_stream: &mut DataStreamListener,
+) -> Result<DataNotification, Error> {
+    let timeout_ms = Duration::from_millis(max_stream_wait_time_ms);
+    match timeout(timeout_ms, active_data_stream.next()).await {
+        Ok(Some(data_notification)) => Ok(data_notification),
+        Ok(None) => Err(Error::StreamingServiceError(
+            "The data stream has been terminated!".into(),
+        )),
+        Err(_) => Err(Error::DataStreamNotificationTimeout(format!(
+            "{:?}",
+            timeout_ms
+        ))),
+    }
+}
+
+/// Notifies the streaming service that the payload of the specified data
+/// notification is invalid (so that the responsible peer can be penalized).
+/// The refetch stream is dropped immediately as the caller will reset its
+/// active stream and resume syncing from local storage.
+pub async fn notify_bad_payload<StreamingClient: DataStreamingClient>(
+    streaming_client: &StreamingClient,
+    notification_id: NotificationId,
+    refetch_reason: PayloadRefetchReason,
+) -> Result<(), Error> {
+    streaming_client
+        .refetch_notification_payload(notification_id, refetch_reason)
+        .await?;
+    Ok(())
+}
+
+/// Returns the highest synced version in storage
+pub fn fetch_latest_synced_version(storage: Arc<dyn DbReader<DpnProto>>) -> Result<Version, Error> {
+    (&*storage).fetch_synced_version().map_err(|error| {
+        Error::StorageError(format!(
+            "Failed to fetch the latest synced version: {:?}",
+            error
+        ))
+    })
+}
+
+/// Returns the latest ledger info in storage
+pub fn fetch_latest_synced_ledger_info(
+    storage: Arc<dyn DbReader<DpnProto>>,
+) -> Result<LedgerInfoWithSignatures, Error> {
+    storage.get_latest_ledger_info().map_err(|error| {
+        Error::StorageError(format!(
+            "Failed to fetch the latest ledger info: {:?}",
+            error
+        ))
+    })
+}
+
+/// Returns the epoch state of the latest epoch in storage
+pub fn fetch_latest_epoch_state(storage: Arc<dyn DbReader<DpnProto>>) -> Result<EpochState, Error> {
+    let startup_info = storage
+        .get_startup_info()
+        .map_err(|error| {
+            Error::StorageError(format!("Failed to fetch the startup info: {:?}", error))
+        })?
+        .ok_or_else(|| Error::StorageError("The startup info is missing!".into()))?;
+    Ok(startup_info.get_epoch_state().clone())
+}
@@ -0,0 +1,140 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use async_trait::async_trait;
+use diem_config::network_id::{NetworkId, PeerNetworkId};
+use diem_types::PeerId;
+use network::{
+    application::{interface::MultiNetworkSender, storage::PeerMetadataStorage, types::PeerState},
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::{
+        AppConfig, ApplicationNetworkSender, NetworkSender, NewNetworkSender, RpcError,
+    },
+    ProtocolId,
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use storage_service_types::{StorageServiceMessage, StorageServiceRequest, StorageServiceResponse};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error)]
+pub enum Error {
+    #[error("Network error encountered: {0}")]
+    NetworkError(String),
+    #[error("Timed out waiting for a response: {0}")]
+    TimeoutWaitingForResponse(String),
+    #[error("Unexpected error encountered: {0}")]
+    UnexpectedErrorEncountered(String),
+}
+
+impl From<RpcError> for Error {
+    fn from(error: RpcError) -> Self {
+        match error {
+            RpcError::TimedOut => Error::TimeoutWaitingForResponse(error.to_string()),
+            error => Error::NetworkError(error.to_string()),
+        }
+    }
+}
+
+/// The interface from the storage service client to the network layer.
+///
+/// This is a thin wrapper around a `NetworkSender<StorageServiceMessage>`, so
+/// it is easy to clone and send off to a separate task.
+#[derive(Clone, Debug)]
+pub struct StorageServiceNetworkSender {
+    inner: NetworkSender<StorageServiceMessage>,
+}
+
+impl NewNetworkSender for StorageServiceNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationNetworkSender<StorageServiceMessage> for StorageServiceNetworkSender {
+    async fn send_rpc(
+        &self,
+        recipient: PeerId,
+        req_msg: StorageServiceMessage,
+        timeout: Duration,
+    ) -> Result<StorageServiceMessage, RpcError> {
+        self.inner
+            .send_rpc(recipient, ProtocolId::StorageServiceRpc, req_msg, timeout)
+            .await
+    }
+}
+
+/// Configuration for the network endpoints to support the storage service client.
+pub fn network_endpoint_config() -> AppConfig {
+    AppConfig::client([ProtocolId::StorageServiceRpc])
+}
+
+/// The client-side interface to the storage service. This sends storage
+/// service requests to the connected peers (across all networks) that support
+/// the storage service protocol.
+#[derive(Clone, Debug)]
+pub struct StorageServiceClient {
+    network_sender: MultiNetworkSender<StorageServiceMessage, StorageServiceNetworkSender>,
+    network_ids: Vec<NetworkId>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+}
+
+impl StorageServiceClient {
+    pub fn new(
+        network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) -> Self {
+        let mut network_ids: Vec<_> = network_senders.keys().copied().collect();
+        network_ids.sort();
+        Self {
+            network_sender: MultiNetworkSender::new(network_senders),
+            network_ids,
+            peer_metadata_storage,
+        }
+    }
+
+    /// Returns all connected peers that support the storage service protocol.
+    pub fn get_available_peers(&self) -> Vec<PeerNetworkId> {
+        self.network_ids
+            .iter()
+            .flat_map(|network_id| {
+                self.peer_metadata_storage
+                    .read_filtered(*network_id, |(_, peer_info)| {
+                        peer_info.status == PeerState::Connected
+                            && peer_info
+                                .active_connection
+                                .application_protocols
+                                .contains(ProtocolId::StorageServiceRpc)
+                    })
+                    .into_keys()
+            })
+            .collect()
+    }
+
+    /// Sends the given request to the specified peer and waits for the
+    /// response (at most for the given timeout).
+    pub async fn send_request(
+        &self,
+        recipient: PeerNetworkId,
+        request: StorageServiceRequest,
+        timeout: Duration,
+    ) -> Result<StorageServiceResponse, Error> {
+        let response = self
+            .network_sender
+            .send_rpc(recipient, StorageServiceMessage::Request(request), timeout)
+            .await?;
+        match response {
+            StorageServiceMessage::Response(response) => Ok(response),
+            StorageServiceMessage::Request(request) => Err(Error::UnexpectedErrorEncountered(
+                format!("Received a request instead of a response: {:?}", request),
+            )),
+        }
+    }
+}
@@ -3,40 +3,63 @@
 
 #![forbid(unsafe_code)]
 
-use diem_crypto::HashValue;
-use diem_infallible::RwLock;
+use ::network::{
+    protocols::network::{Event, RpcError},
+    ProtocolId,
+};
+use bytes::Bytes;
+use diem_infallible::{Mutex, RwLock};
+use diem_time_service::{TimeService, TimeServiceTrait};
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
     epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
     transaction::{
         default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
         Version,
     },
+    PeerId,
 };
+use futures::{channel::oneshot, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{
+    cmp::min,
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use storage_interface::default_protocol::DbReaderWriter;
 use storage_service_types::{
-    AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
-    StorageServiceError, StorageServiceRequest, StorageServiceResponse,
+    AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary, Epoch,
+    EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
+    NewTransactionsWithProofRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
+    StorageServiceError, StorageServiceMessage, StorageServiceRequest, StorageServiceResponse,
     TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
 };
 use thiserror::Error;
 
+pub mod network;
+
 #[cfg(test)]
 mod tests;
 
+pub use network::{network_endpoint_config, StorageServiceNetworkEvents};
+
 // TODO(joshlind): make these configurable.
 /// Storage server constants.
 pub const MAX_EPOCH_CHUNK_SIZE: u64 = 1000;
 pub const MAX_TRANSACTION_CHUNK_SIZE: u64 = 1000;
 pub const MAX_TRANSACTION_OUTPUT_CHUNK_SIZE: u64 = 1000;
 pub const MAX_ACCOUNT_STATES_CHUNK_SIZE: u64 = 1000;
+pub const MAX_OPTIMISTIC_FETCHES_PER_PEER: usize = 10;
+pub const MAX_OPTIMISTIC_FETCH_PERIOD_MS: u64 = 5000;
+pub const OPTIMISTIC_FETCH_REFRESH_INTERVAL_MS: u64 = 100;
 pub const STORAGE_SERVER_VERSION: u64 = 1;
 
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
+    #[error("Invalid request received: {0}")]
+    InvalidRequest(String),
     #[error("Storage error encountered: {0}")]
     StorageErrorEncountered(String),
     #[error("Unexpected error encountered: {0}")]
@@ -46,25 +69,82 @@ pub enum Error {
 /// The server-side implementation of the storage service. This provides all the
 /// functionality required to handle storage service requests (i.e., from clients).
 pub struct StorageServiceServer<T> {
+    // The optimistic fetches (per peer) waiting for new data to be committed
+    optimistic_fetches: Mutex<HashMap<PeerId, Vec<OptimisticFetch>>>,
     storage: T,
+    time_service: TimeService,
 }
 
 impl<T: StorageReaderInterface> StorageServiceServer<T> {
-    pub fn new(storage: T) -> Self {
-        Self { storage }
+    pub fn new(storage: T, time_service: TimeService) -> Self {
+        Self {
+            optimistic_fetches: Mutex::new(HashMap::new()),
+            storage,
+            time_service,
+        }
     }
 
     pub fn handle_request(
         &self,
         request: StorageServiceRequest,
     ) -> Result<StorageServiceResponse, Error> {
-        let response = match request {
+        let response = self
+            .validate_request(&request)
+            .and_then(|_| self.process_request(request));
+
+        // If any requests resulted in an unexpected error, return an InternalStorageError to the
+        // client and log the actual error. Requests that can't be serviced are explicitly rejected.
+        match response {
+            Err(Error::InvalidRequest(error)) => Ok(StorageServiceResponse::StorageServiceError(
+                StorageServiceError::InvalidRequest(error),
+            )),
+            Err(_error) => {
+                // TODO(joshlind): add logging support to this library so we can log _error
+                Ok(StorageServiceResponse::StorageServiceError(
+                    StorageServiceError::InternalError,
+                ))
+            }
+            response => response,
+        }
+    }
+
+    /// Verifies that the given request can be serviced using the data held in
+    /// storage (e.g., the requested data hasn't been pruned).
+    fn validate_request(&self, request: &StorageServiceRequest) -> Result<(), Error> {
+        match request {
+            StorageServiceRequest::GetServerProtocolVersion
+            | StorageServiceRequest::GetStorageServerSummary => Ok(()),
+            request => {
+                let data_summary = self.storage.get_data_summary()?;
+                if data_summary.can_service(request) {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidRequest(format!(
+                        "The request can't be serviced! Request: {:?}, data summary: {:?}",
+                        request, data_summary
+                    )))
+                }
+            }
+        }
+    }
+
+    fn process_request(
+        &self,
+        request: StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        match request {
             StorageServiceRequest::GetAccountStatesChunkWithProof(request) => {
                 self.get_account_states_chunk_with_proof(request)
             }
             StorageServiceRequest::GetEpochEndingLedgerInfos(request) => {
                 self.get_epoch_ending_ledger_infos(request)
             }
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(request) => {
+                self.get_new_transaction_outputs_with_proof(request)
+            }
+            StorageServiceRequest::GetNewTransactionsWithProof(request) => {
+                self.get_new_transactions_with_proof(request)
+            }
             StorageServiceRequest::GetNumberOfAccountsAtVersion(version) => {
                 self.get_number_of_accounts_at_version(version)
             }
@@ -76,28 +156,190 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
             StorageServiceRequest::GetTransactionsWithProof(request) => {
                 self.get_transactions_with_proof(request)
             }
+        }
+    }
+
+    /// Handles a storage service message received over the network, and
+    /// sends the serialized response back to the requesting peer. Optimistic
+    /// fetches that can't be served yet are held until new data is available.
+    fn handle_network_request(
+        &self,
+        peer_id: PeerId,
+        message: StorageServiceMessage,
+        protocol: ProtocolId,
+        response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        match message {
+            StorageServiceMessage::Request(request) if request.is_optimistic_fetch() => {
+                self.handle_optimistic_fetch(peer_id, request, protocol, response_sender)
+            }
+            StorageServiceMessage::Request(request) => {
+                send_response(protocol, response_sender, self.handle_request(request))
+            }
+            StorageServiceMessage::Response(_) => {
+                // The requesting peer may have timed out or disconnected, so ignore send errors
+                let _ = response_sender.send(Err(RpcError::Error(anyhow::anyhow!(
+                    "Unexpected response message received by the storage service server!"
+                ))));
+            }
+        }
+    }
+
+    /// Handles an optimistic fetch sent by the given peer. If new data is
+    /// already available, the fetch is served immediately. Otherwise, it is
+    /// held until new data is committed or the fetch expires.
+    fn handle_optimistic_fetch(
+        &self,
+        peer_id: PeerId,
+        request: StorageServiceRequest,
+        protocol: ProtocolId,
+        response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        let expiry_time =
+            self.time_service.now() + Duration::from_millis(MAX_OPTIMISTIC_FETCH_PERIOD_MS);
+        let optimistic_fetch = OptimisticFetch {
+            expiry_time,
+            protocol,
+            request,
+            response_sender,
         };
 
-        // If any requests resulted in an unexpected error, return an InternalStorageError to the
-        // client and log the actual error.
-        if let Err(_error) = response {
-            // TODO(joshlind): add logging support to this library so we can log _error
-            Ok(StorageServiceResponse::StorageServiceError(
-                StorageServiceError::InternalError,
-            ))
+        // Serve the fetch immediately if new data is available
+        match self.fetch_synced_version() {
+            Ok(synced_version) if optimistic_fetch.known_version() < synced_version => {
+                let response = self.handle_request(optimistic_fetch.request.clone());
+                return optimistic_fetch.send_response(response);
+            }
+            Ok(_) => {}
+            Err(error) => return optimistic_fetch.send_response(Err(error)),
+        }
+
+        // Otherwise, hold the fetch (if the peer hasn't reached its limit)
+        let mut optimistic_fetches = self.optimistic_fetches.lock();
+        let peer_optimistic_fetches = optimistic_fetches.entry(peer_id).or_default();
+        if peer_optimistic_fetches.len() >= MAX_OPTIMISTIC_FETCHES_PER_PEER {
+            optimistic_fetch.send_response(Ok(StorageServiceResponse::StorageServiceError(
+                StorageServiceError::TooManyOptimisticFetches,
+            )));
         } else {
-            response
+            peer_optimistic_fetches.push(optimistic_fetch);
+        }
+    }
+
+    /// Responds to all held optimistic fetches that can now be served (i.e.,
+    /// new data has been committed) and to all those that have expired.
+    fn refresh_optimistic_fetches(&self) {
+        let synced_version = match self.fetch_synced_version() {
+            Ok(synced_version) => synced_version,
+            Err(_error) => {
+                // TODO(joshlind): add logging support to this library so we can log _error
+                return;
+            }
+        };
+
+        // Remove all fetches that are ready to be responded to
+        let now = self.time_service.now();
+        let mut ready_optimistic_fetches = vec![];
+        {
+            let mut optimistic_fetches = self.optimistic_fetches.lock();
+            for peer_optimistic_fetches in optimistic_fetches.values_mut() {
+                let (ready_fetches, pending_fetches) = peer_optimistic_fetches.drain(..).partition(
+                    |optimistic_fetch: &OptimisticFetch| {
+                        optimistic_fetch.known_version() < synced_version
+                            || optimistic_fetch.expiry_time <= now
+                    },
+                );
+                *peer_optimistic_fetches = pending_fetches;
+                ready_optimistic_fetches.extend(ready_fetches);
+            }
+            optimistic_fetches
+                .retain(|_, peer_optimistic_fetches| !peer_optimistic_fetches.is_empty());
+        }
+
+        // Respond with new data (if available), otherwise notify the peer of the expiry
+        for optimistic_fetch in ready_optimistic_fetches {
+            let response = if optimistic_fetch.known_version() < synced_version {
+                self.handle_request(optimistic_fetch.request.clone())
+            } else {
+                Ok(StorageServiceResponse::StorageServiceError(
+                    StorageServiceError::OptimisticFetchExpired,
+                ))
+            };
+            optimistic_fetch.send_response(response);
         }
     }
 
+    /// Returns the highest synced version in storage
+    fn fetch_synced_version(&self) -> Result<Version, Error> {
+        let data_summary = self.storage.get_data_summary()?;
+        Ok(data_summary.synced_ledger_info.ledger_info().version())
+    }
+
+    /// Returns the ledger info that an optimistic fetch (from the given known
+    /// version and epoch) should be served relative to, as well as the number
+    /// of new versions to serve. If the known epoch has ended, the epoch ending
+    /// ledger info is used (so that the client can verify the response).
+    fn get_optimistic_fetch_target(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+        max_chunk_size: u64,
+    ) -> Result<(LedgerInfoWithSignatures, u64), Error> {
+        let synced_ledger_info = self.storage.get_data_summary()?.synced_ledger_info;
+        let synced_epoch = synced_ledger_info.ledger_info().epoch();
+        let mut target_ledger_info =
+            self.get_epoch_target_ledger_info(known_epoch, &synced_ledger_info)?;
+
+        // If the client is at the end of the known epoch, the new data is in the next epoch
+        let known_epoch_ended = known_epoch < synced_epoch;
+        if known_epoch_ended && known_version == target_ledger_info.ledger_info().version() {
+            target_ledger_info =
+                self.get_epoch_target_ledger_info(known_epoch + 1, &synced_ledger_info)?;
+        }
+
+        let target_version = target_ledger_info.ledger_info().version();
+        if known_version >= target_version {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "No new data to serve! Known version: {}, target version: {}",
+                known_version, target_version
+            )));
+        }
+        let num_versions = min(target_version - known_version, max_chunk_size);
+
+        Ok((target_ledger_info, num_versions))
+    }
+
+    /// Returns the epoch ending ledger info of the given epoch, or the synced
+    /// ledger info if the epoch hasn't ended yet.
+    fn get_epoch_target_ledger_info(
+        &self,
+        epoch: Epoch,
+        synced_ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<LedgerInfoWithSignatures, Error> {
+        if epoch >= synced_ledger_info.ledger_info().epoch() {
+            return Ok(synced_ledger_info.clone());
+        }
+        self.storage
+            .get_epoch_ending_ledger_infos(epoch, epoch + 1)?
+            .ledger_info_with_sigs
+            .into_iter()
+            .find(|ledger_info| ledger_info.ledger_info().epoch() == epoch)
+            .ok_or_else(|| {
+                Error::StorageErrorEncountered(format!(
+                    "Missing the epoch ending ledger info for epoch: {}",
+                    epoch
+                ))
+            })
+    }
+
     fn get_account_states_chunk_with_proof(
         &self,
         request: AccountStatesChunkWithProofRequest,
     ) -> Result<StorageServiceResponse, Error> {
         let account_states_chunk_with_proof = self.storage.get_account_states_chunk_with_proof(
             request.version,
-            request.start_account_key,
-            request.expected_num_account_states,
+            request.start_account_index,
+            request.end_account_index,
         )?;
 
         Ok(StorageServiceResponse::AccountStatesChunkWithProof(
@@ -118,6 +360,49 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         ))
     }
 
+    fn get_new_transaction_outputs_with_proof(
+        &self,
+        request: NewTransactionOutputsWithProofRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        let (target_ledger_info, num_outputs) = self.get_optimistic_fetch_target(
+            request.known_version,
+            request.known_epoch,
+            MAX_TRANSACTION_OUTPUT_CHUNK_SIZE,
+        )?;
+        let transaction_output_list_with_proof = self.storage.get_transaction_outputs_with_proof(
+            target_ledger_info.ledger_info().version(),
+            request.known_version + 1,
+            num_outputs,
+        )?;
+
+        Ok(StorageServiceResponse::NewTransactionOutputsWithProof((
+            transaction_output_list_with_proof,
+            target_ledger_info,
+        )))
+    }
+
+    fn get_new_transactions_with_proof(
+        &self,
+        request: NewTransactionsWithProofRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        let (target_ledger_info, num_transactions) = self.get_optimistic_fetch_target(
+            request.known_version,
+            request.known_epoch,
+            MAX_TRANSACTION_CHUNK_SIZE,
+        )?;
+        let transactions_with_proof = self.storage.get_transactions_with_proof(
+            target_ledger_info.ledger_info().version(),
+            request.known_version + 1,
+            num_transactions,
+            request.include_events,
+        )?;
+
+        Ok(StorageServiceResponse::NewTransactionsWithProof((
+            transactions_with_proof,
+            target_ledger_info,
+        )))
+    }
+
     fn get_number_of_accounts_at_version(
         &self,
         version: Version,
@@ -186,6 +471,87 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
     }
 }
 
+impl<T: StorageReaderInterface + Send + Sync + 'static> StorageServiceServer<T> {
+    /// Serves the storage service requests received from the network, until
+    /// the network event stream terminates. Each request is handled on a
+    /// blocking thread, as serving it requires reading from storage. Held
+    /// optimistic fetches are refreshed periodically.
+    pub async fn start(self, mut network_requests: StorageServiceNetworkEvents) {
+        let server = Arc::new(self);
+        let refresh_handle = tokio::spawn(Self::refresh_optimistic_fetches_periodically(
+            server.clone(),
+        ));
+
+        while let Some(event) = network_requests.next().await {
+            if let Event::RpcRequest(peer_id, message, protocol, response_sender) = event {
+                let server = server.clone();
+                tokio::task::spawn_blocking(move || {
+                    server.handle_network_request(peer_id, message, protocol, response_sender)
+                });
+            }
+        }
+        refresh_handle.abort();
+    }
+
+    /// Refreshes the held optimistic fetches on every refresh interval
+    async fn refresh_optimistic_fetches_periodically(server: Arc<Self>) {
+        let refresh_interval = server
+            .time_service
+            .interval(Duration::from_millis(OPTIMISTIC_FETCH_REFRESH_INTERVAL_MS));
+        tokio::pin!(refresh_interval);
+        while refresh_interval.next().await.is_some() {
+            let server = server.clone();
+            let _ = tokio::task::spawn_blocking(move || server.refresh_optimistic_fetches()).await;
+        }
+    }
+}
+
+/// An optimistic fetch held by the server until new data is committed (or
+/// the fetch expires).
+struct OptimisticFetch {
+    expiry_time: Instant,
+    protocol: ProtocolId,
+    request: StorageServiceRequest,
+    response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+}
+
+impl OptimisticFetch {
+    /// Returns the highest version known by the requesting peer
+    fn known_version(&self) -> Version {
+        match &self.request {
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(request) => {
+                request.known_version
+            }
+            StorageServiceRequest::GetNewTransactionsWithProof(request) => request.known_version,
+            request => unreachable!("Unexpected optimistic fetch request: {:?}", request),
+        }
+    }
+
+    /// Sends the response for the fetch to the requesting peer
+    fn send_response(self, response: Result<StorageServiceResponse, Error>) {
+        send_response(self.protocol, self.response_sender, response)
+    }
+}
+
+/// Serializes the given response and sends it to the requesting peer
+fn send_response(
+    protocol: ProtocolId,
+    response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+    response: Result<StorageServiceResponse, Error>,
+) {
+    let response = response
+        .map_err(|error| RpcError::Error(error.into()))
+        .and_then(|response| {
+            protocol
+                .to_bytes(&StorageServiceMessage::Response(response))
+                .map(Bytes::from)
+                .map_err(RpcError::Error)
+        });
+
+    // The requesting peer may have timed out or disconnected, so ignore send errors
+    let _ = response_sender.send(response);
+}
+
 /// The interface into local storage (e.g., the Diem DB) used by the storage
 /// server to handle client requests.
 pub trait StorageReaderInterface {
@@ -227,12 +593,12 @@ pub trait StorageReaderInterface {
     fn get_number_of_accounts(&self, version: u64) -> Result<u64, Error>;
 
     /// Returns a chunk holding a list of account states starting at the
-    /// specified account key with *at most* `expected_num_account_states`.
+    /// specified account index and ending at the specified end index (inclusive).
     fn get_account_states_chunk_with_proof(
         &self,
         version: u64,
-        start_account_key: HashValue,
-        expected_num_account_states: u64,
+        start_account_index: u64,
+        end_account_index: u64,
     ) -> Result<AccountStatesChunkWithProof, Error>;
 }
 
@@ -261,15 +627,29 @@ impl StorageReaderInterface for StorageReader {
         let latest_epoch = latest_ledger_info.epoch();
         let latest_version = latest_ledger_info.version();
 
-        // TODO(joshlind): Update the DiemDB to support fetching all of this data!
-        // For now we assume everything (since genesis) is held.
-        // Return the relevant data summary
+        // Fetch the first versions that haven't been pruned. Transactions and
+        // their outputs are pruned together, but the state is pruned separately.
+        let first_txn_version = self
+            .storage
+            .read()
+            .reader
+            .get_first_txn_version()
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        let first_state_version = self
+            .storage
+            .read()
+            .reader
+            .get_first_state_version()
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+
+        // Return the relevant data summary. Epoch ending ledger infos are never pruned. The
+        // genesis ledger info ends epoch 0, so it is advertised even before the first commit.
         let data_summary = DataSummary {
             synced_ledger_info: latest_ledger_info_with_sigs,
-            epoch_ending_ledger_infos: CompleteDataRange::new(0, latest_epoch - 1),
-            transactions: CompleteDataRange::new(0, latest_version),
-            transaction_outputs: CompleteDataRange::new(0, latest_version),
-            account_states: CompleteDataRange::new(0, latest_version),
+            epoch_ending_ledger_infos: CompleteDataRange::new(0, latest_epoch.saturating_sub(1)),
+            transactions: CompleteDataRange::new(first_txn_version, latest_version),
+            transaction_outputs: CompleteDataRange::new(first_txn_version, latest_version),
+            account_states: CompleteDataRange::new(first_state_version, latest_version),
         };
 
         Ok(data_summary)
@@ -324,20 +704,40 @@ impl StorageReaderInterface for StorageReader {
 
     fn get_account_states_chunk_with_proof(
         &self,
-        _version: u64,
-        _start_account_key: HashValue,
-        _expected_num_account_states: u64,
+        version: u64,
+        start_account_index: u64,
+        end_account_index: u64,
     ) -> Result<AccountStatesChunkWithProof, Error> {
-        // TODO(joshlind): implement this once DbReaderWriter supports these calls.
-        Err(Error::UnexpectedErrorEncountered(
-            "Unimplemented! This API call needs to be implemented!".into(),
-        ))
+        // The end account index is inclusive, so calculate the expected chunk size
+        let expected_num_accounts = end_account_index
+            .checked_sub(start_account_index)
+            .and_then(|num_accounts| num_accounts.checked_add(1))
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Invalid account index range! Start: {:?}, end: {:?}",
+                    start_account_index, end_account_index
+                ))
+            })?;
+        let account_states_chunk_with_proof = self
+            .storage
+            .read()
+            .reader
+            .get_account_chunk_with_proof(
+                version,
+                start_account_index as usize,
+                expected_num_accounts as usize,
+            )
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        Ok(account_states_chunk_with_proof)
     }
 
-    fn get_number_of_accounts(&self, _version: u64) -> Result<u64, Error> {
-        // TODO(joshlind): implement this once DbReaderWriter supports these calls.
-        Err(Error::UnexpectedErrorEncountered(
-            "Unimplemented! This API call needs to be implemented!".into(),
-        ))
+    fn get_number_of_accounts(&self, version: u64) -> Result<u64, Error> {
+        let number_of_accounts = self
+            .storage
+            .read()
+            .reader
+            .get_account_count(version)
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        Ok(number_of_accounts as u64)
     }
 }
@@ -0,0 +1,29 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between the storage service server and the network layer.
+
+use channel::{diem_channel, message_queues::QueueStyle};
+use network::{
+    protocols::network::{AppConfig, NetworkEvents},
+    ProtocolId,
+};
+use storage_service_types::StorageServiceMessage;
+
+/// The maximum number of pending storage service requests per peer.
+const STORAGE_SERVICE_MAX_BUFFER_SIZE: usize = 100;
+
+/// The interface from the network to the storage service server.
+///
+/// `StorageServiceNetworkEvents` is a `Stream` of `PeerManagerNotification`
+/// where the raw `Bytes` rpc requests are deserialized into
+/// `StorageServiceMessage` types.
+pub type StorageServiceNetworkEvents = NetworkEvents<StorageServiceMessage>;
+
+/// Configuration for the network endpoints to support the storage service.
+pub fn network_endpoint_config() -> AppConfig {
+    AppConfig::service(
+        [ProtocolId::StorageServiceRpc],
+        diem_channel::Config::new(STORAGE_SERVICE_MAX_BUFFER_SIZE).queue_style(QueueStyle::FIFO),
+    )
+}
@@ -3,14 +3,21 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{StorageReader, StorageServiceServer};
+use crate::{
+    Error, StorageReader, StorageReaderInterface, StorageServiceNetworkEvents,
+    StorageServiceServer, MAX_OPTIMISTIC_FETCHES_PER_PEER, MAX_OPTIMISTIC_FETCH_PERIOD_MS,
+};
 use anyhow::Result;
+use channel::{diem_channel, message_queues::QueueStyle};
 use claim::{assert_matches, assert_none, assert_some};
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
 use diem_infallible::RwLock;
+use diem_time_service::TimeService;
 use diem_types::{
     account_address::AccountAddress,
-    account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
+    account_state_blob::{
+        default_protocol::AccountStateWithProof, AccountStateBlob, AccountStatesChunkWithProof,
+    },
     block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::{
@@ -20,7 +27,7 @@ use diem_types::{
     epoch_change::EpochChangeProof,
     event::EventKey,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    proof::{SparseMerkleProof, TransactionInfoListWithProof},
+    proof::{SparseMerkleProof, SparseMerkleRangeProof, TransactionInfoListWithProof},
     protocol_spec::DpnProto,
     state_proof::StateProof,
     transaction::{
@@ -31,17 +38,39 @@ use diem_types::{
         RawTransaction, Script, SignedTransaction, Transaction, TransactionPayload,
         TransactionToCommit, Version,
     },
+    PeerId,
 };
+use futures::channel::oneshot;
 use move_core_types::language_storage::TypeTag;
+use network::{
+    peer_manager::PeerManagerNotification,
+    protocols::{
+        network::{NewNetworkEvents, RpcError},
+        rpc::InboundRpcRequest,
+    },
+    ProtocolId,
+};
 use std::{collections::BTreeMap, sync::Arc};
 use storage_interface::{DbReader, DbReaderWriter, DbWriter, Order, StartupInfo, TreeState};
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
-    StorageServiceError, StorageServiceRequest, StorageServiceResponse,
-    TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+    EpochEndingLedgerInfoRequest, NewTransactionsWithProofRequest, ProtocolMetadata,
+    ServerProtocolVersion, StorageServerSummary, StorageServiceError, StorageServiceMessage,
+    StorageServiceRequest, StorageServiceResponse, TransactionOutputsWithProofRequest,
+    TransactionsWithProofRequest,
 };
 
+/// The number of accounts returned by the mock db at every version
+const NUM_ACCOUNTS_AT_VERSION: u64 = 1000;
+
+/// The latest epoch and version synced by the mock db
+const MOCK_LATEST_EPOCH: u64 = 30;
+const MOCK_LATEST_VERSION: u64 = 1000;
+
+/// The first versions (i.e., not yet pruned) held by the mock db
+const MOCK_FIRST_STATE_VERSION: u64 = 50;
+const MOCK_FIRST_TXN_VERSION: u64 = 10;
+
 // TODO(joshlind): Expand these test cases to better test storage interaction
 // and functionality. This will likely require a better mock db abstraction.
 
@@ -51,11 +80,14 @@ fn test_get_account_states_chunk_with_proof() {
     let storage_server = create_storage_server();
 
     // Create a request to fetch an account states chunk with a proof
+    let version = 101;
+    let start_account_index = 100;
+    let end_account_index = 199;
     let account_states_chunk_request =
         StorageServiceRequest::GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest {
-            version: 0,
-            start_account_key: HashValue::random(),
-            expected_num_account_states: 0,
+            version,
+            start_account_index,
+            end_account_index,
         });
 
     // Process the request
@@ -63,7 +95,42 @@ fn test_get_account_states_chunk_with_proof() {
         .handle_request(account_states_chunk_request)
         .unwrap();
 
-    // Verify the response is correct (the API call is currently unsupported)
+    // Verify the response is correct
+    match account_states_chunk_response {
+        StorageServiceResponse::AccountStatesChunkWithProof(account_states_chunk_with_proof) => {
+            assert_eq!(
+                account_states_chunk_with_proof,
+                create_test_account_states_chunk(start_account_index, end_account_index)
+            );
+        }
+        result => {
+            panic!(
+                "Expected account states chunk with proof but got: {:?}",
+                result
+            );
+        }
+    };
+}
+
+#[test]
+fn test_get_account_states_chunk_with_proof_invalid_range() {
+    // Create a storage service server
+    let storage_server = create_storage_server();
+
+    // Create a request with an end index smaller than the start index
+    let account_states_chunk_request =
+        StorageServiceRequest::GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest {
+            version: 100,
+            start_account_index: 10,
+            end_account_index: 9,
+        });
+
+    // Process the request
+    let account_states_chunk_response = storage_server
+        .handle_request(account_states_chunk_request)
+        .unwrap();
+
+    // Verify the response is an error
     assert_matches!(
         account_states_chunk_response,
         StorageServiceResponse::StorageServiceError(StorageServiceError::InternalError)
@@ -89,26 +156,103 @@ fn test_get_server_protocol_version() {
     );
 }
 
+#[tokio::test]
+async fn test_serve_network_requests() {
+    // Create the network channels and start the storage service server
+    let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) = diem_channel::new(QueueStyle::FIFO, 10, None);
+    let (_connection_notifs_tx, connection_notifs_rx) =
+        diem_channel::new(QueueStyle::FIFO, 10, None);
+    let network_requests =
+        StorageServiceNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx);
+    tokio::spawn(create_storage_server().start(network_requests));
+
+    // Send a request to fetch the protocol version over the network
+    let protocol_id = ProtocolId::StorageServiceRpc;
+    let request = StorageServiceMessage::Request(StorageServiceRequest::GetServerProtocolVersion);
+    let (res_tx, res_rx) = oneshot::channel();
+    let inbound_rpc_request = InboundRpcRequest {
+        protocol_id,
+        data: protocol_id.to_bytes(&request).unwrap().into(),
+        res_tx,
+    };
+    let peer_id = PeerId::random();
+    peer_mgr_notifs_tx
+        .push(
+            (peer_id, protocol_id),
+            PeerManagerNotification::RecvRpc(peer_id, inbound_rpc_request),
+        )
+        .unwrap();
+
+    // Verify the response is correct
+    let response_bytes = res_rx.await.unwrap().unwrap();
+    let response: StorageServiceMessage = protocol_id.from_bytes(&response_bytes).unwrap();
+    let expected_protocol_version = ServerProtocolVersion {
+        protocol_version: 1,
+    };
+    assert_eq!(
+        response,
+        StorageServiceMessage::Response(StorageServiceResponse::ServerProtocolVersion(
+            expected_protocol_version
+        ))
+    );
+}
+
 #[test]
 fn test_get_number_of_accounts_at_version() {
     // Create a storage service server
     let storage_server = create_storage_server();
 
     // Create a request to fetch the number of accounts at the specified version
-    let number_of_accounts_request = StorageServiceRequest::GetNumberOfAccountsAtVersion(10);
+    let number_of_accounts_request = StorageServiceRequest::GetNumberOfAccountsAtVersion(100);
 
     // Process the request
     let number_of_accounts_response = storage_server
         .handle_request(number_of_accounts_request)
         .unwrap();
 
-    // Verify the response is correct (the API call is currently unsupported)
-    assert_matches!(
+    // Verify the response is correct
+    assert_eq!(
         number_of_accounts_response,
-        StorageServiceResponse::StorageServiceError(StorageServiceError::InternalError)
+        StorageServiceResponse::NumberOfAccountsAtVersion(NUM_ACCOUNTS_AT_VERSION)
     );
 }
 
+#[test]
+fn test_get_pruned_data() {
+    // Create a storage service server
+    let storage_server = create_storage_server();
+
+    // Create requests for data that has already been pruned
+    let pruned_data_requests = vec![
+        StorageServiceRequest::GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest {
+            version: MOCK_FIRST_STATE_VERSION - 1,
+            start_account_index: 0,
+            end_account_index: 99,
+        }),
+        StorageServiceRequest::GetNumberOfAccountsAtVersion(MOCK_FIRST_STATE_VERSION - 1),
+        StorageServiceRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+            proof_version: MOCK_LATEST_VERSION,
+            start_version: MOCK_FIRST_TXN_VERSION - 1,
+            expected_num_outputs: 10,
+        }),
+        StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+            proof_version: MOCK_LATEST_VERSION,
+            start_version: MOCK_FIRST_TXN_VERSION - 1,
+            expected_num_transactions: 10,
+            include_events: false,
+        }),
+    ];
+
+    // Verify each request is rejected as invalid
+    for request in pruned_data_requests {
+        let response = storage_server.handle_request(request).unwrap();
+        assert_matches!(
+            response,
+            StorageServiceResponse::StorageServiceError(StorageServiceError::InvalidRequest(_))
+        );
+    }
+}
+
 #[test]
 fn test_get_storage_server_summary() {
     // Create a storage service server
@@ -119,8 +263,8 @@ fn test_get_storage_server_summary() {
     let summary_response = storage_server.handle_request(summary_request).unwrap();
 
     // Verify the response is correct
-    let highest_version = 100;
-    let highest_epoch = 10;
+    let highest_version = MOCK_LATEST_VERSION;
+    let highest_epoch = MOCK_LATEST_EPOCH;
     let expected_server_summary = StorageServerSummary {
         protocol_metadata: ProtocolMetadata {
             max_epoch_chunk_size: 1000,
@@ -131,9 +275,9 @@ fn test_get_storage_server_summary() {
         data_summary: DataSummary {
             synced_ledger_info: create_test_ledger_info_with_sigs(highest_epoch, highest_version),
             epoch_ending_ledger_infos: CompleteDataRange::new(0, highest_epoch - 1),
-            transactions: CompleteDataRange::new(0, highest_version),
-            transaction_outputs: CompleteDataRange::new(0, highest_version),
-            account_states: CompleteDataRange::new(0, highest_version),
+            transactions: CompleteDataRange::new(MOCK_FIRST_TXN_VERSION, highest_version),
+            transaction_outputs: CompleteDataRange::new(MOCK_FIRST_TXN_VERSION, highest_version),
+            account_states: CompleteDataRange::new(MOCK_FIRST_STATE_VERSION, highest_version),
         },
     };
     assert_eq!(
@@ -148,7 +292,7 @@ fn test_get_transactions_with_proof_events() {
     let storage_server = create_storage_server();
 
     // Create a request to fetch transactions with a proof
-    let start_version = 0;
+    let start_version = MOCK_FIRST_TXN_VERSION;
     let expected_num_transactions = 10;
     let transactions_proof_request =
         StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
@@ -191,7 +335,7 @@ fn test_get_transaction_outputs_with_proof() {
     let transaction_outputs_proof_request =
         StorageServiceRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
             proof_version: 1000,
-            start_version: 0,
+            start_version: MOCK_FIRST_TXN_VERSION,
             expected_num_outputs: 10,
         });
 
@@ -289,10 +433,210 @@ fn test_get_epoch_ending_ledger_infos() {
     };
 }
 
+#[test]
+fn test_optimistic_fetch_new_data_available() {
+    // Create a storage service server synced to version 550 (in epoch 5)
+    let (storage_server, _, _) = create_optimistic_storage_server(5, 550);
+
+    // Send an optimistic fetch for data beyond version 500
+    let mut response_receiver = send_optimistic_fetch(&storage_server, PeerId::random(), 500, 5);
+
+    // Verify the fetch is served immediately
+    verify_new_transactions_response(&mut response_receiver, 501, 50, 5, 550);
+}
+
+#[test]
+fn test_optimistic_fetch_epoch_change() {
+    // Create a storage service server synced to version 650 (in epoch 6)
+    let (storage_server, _, _) = create_optimistic_storage_server(6, 650);
+
+    // Send an optimistic fetch for data beyond version 550 (in epoch 5)
+    let mut response_receiver = send_optimistic_fetch(&storage_server, PeerId::random(), 550, 5);
+
+    // Verify the fetch is served up to (and proven by) the end of epoch 5
+    verify_new_transactions_response(&mut response_receiver, 551, 50, 5, 600);
+}
+
+#[test]
+fn test_optimistic_fetch_end_of_epoch() {
+    // Create a storage service server synced to version 650 (in epoch 6)
+    let (storage_server, _, _) = create_optimistic_storage_server(6, 650);
+
+    // Send an optimistic fetch for data beyond version 600 (the end of epoch 5)
+    let mut response_receiver = send_optimistic_fetch(&storage_server, PeerId::random(), 600, 5);
+
+    // Verify the fetch is served with the data of epoch 6
+    verify_new_transactions_response(&mut response_receiver, 601, 50, 6, 650);
+}
+
+#[test]
+fn test_optimistic_fetch_held_until_new_data() {
+    // Create a storage service server synced to version 550 (in epoch 5)
+    let (storage_server, storage_reader, _) = create_optimistic_storage_server(5, 550);
+
+    // Send an optimistic fetch for data beyond the synced version
+    let mut response_receiver = send_optimistic_fetch(&storage_server, PeerId::random(), 550, 5);
+
+    // Verify the fetch is held until new data is committed
+    assert_none!(response_receiver.try_recv().unwrap());
+    storage_server.refresh_optimistic_fetches();
+    assert_none!(response_receiver.try_recv().unwrap());
+
+    // Commit new data and verify the fetch is served
+    storage_reader.set_synced_ledger_info(5, 560);
+    storage_server.refresh_optimistic_fetches();
+    verify_new_transactions_response(&mut response_receiver, 551, 10, 5, 560);
+    assert!(storage_server.optimistic_fetches.lock().is_empty());
+}
+
+#[test]
+fn test_optimistic_fetch_expiry() {
+    // Create a storage service server synced to version 550 (in epoch 5)
+    let (storage_server, _, time_service) = create_optimistic_storage_server(5, 550);
+
+    // Send an optimistic fetch for data beyond the synced version
+    let mut response_receiver = send_optimistic_fetch(&storage_server, PeerId::random(), 550, 5);
+
+    // Elapse time (but not enough to expire the fetch) and verify it's still held
+    let time_service = time_service.into_mock();
+    time_service.advance_ms(MAX_OPTIMISTIC_FETCH_PERIOD_MS - 1);
+    storage_server.refresh_optimistic_fetches();
+    assert_none!(response_receiver.try_recv().unwrap());
+
+    // Elapse the rest of the fetch period and verify the fetch expires
+    time_service.advance_ms(1);
+    storage_server.refresh_optimistic_fetches();
+    assert_eq!(
+        get_storage_service_response(&mut response_receiver),
+        StorageServiceResponse::StorageServiceError(StorageServiceError::OptimisticFetchExpired)
+    );
+    assert!(storage_server.optimistic_fetches.lock().is_empty());
+}
+
+#[test]
+fn test_optimistic_fetch_peer_limit() {
+    // Create a storage service server synced to version 550 (in epoch 5)
+    let (storage_server, storage_reader, _) = create_optimistic_storage_server(5, 550);
+
+    // Send the maximum number of optimistic fetches for a single peer
+    let peer_id = PeerId::random();
+    let mut response_receivers: Vec<_> = (0..MAX_OPTIMISTIC_FETCHES_PER_PEER)
+        .map(|_| send_optimistic_fetch(&storage_server, peer_id, 550, 5))
+        .collect();
+
+    // Verify any additional fetch for the peer is rejected
+    let mut rejected_receiver = send_optimistic_fetch(&storage_server, peer_id, 550, 5);
+    assert_eq!(
+        get_storage_service_response(&mut rejected_receiver),
+        StorageServiceResponse::StorageServiceError(StorageServiceError::TooManyOptimisticFetches)
+    );
+
+    // Verify fetches for other peers are still held
+    let mut other_peer_receiver = send_optimistic_fetch(&storage_server, PeerId::random(), 550, 5);
+    assert_none!(other_peer_receiver.try_recv().unwrap());
+
+    // Commit new data and verify all held fetches are served
+    storage_reader.set_synced_ledger_info(5, 551);
+    storage_server.refresh_optimistic_fetches();
+    response_receivers.push(other_peer_receiver);
+    for response_receiver in response_receivers.iter_mut() {
+        verify_new_transactions_response(response_receiver, 551, 1, 5, 551);
+    }
+}
+
 fn create_storage_server() -> StorageServiceServer<StorageReader> {
     let storage = Arc::new(RwLock::new(DbReaderWriter::new(MockDbReaderWriter)));
     let storage_reader = StorageReader::new(storage);
-    StorageServiceServer::new(storage_reader)
+    StorageServiceServer::new(storage_reader, TimeService::mock())
+}
+
+/// Creates a storage service server (using a mock storage reader and time
+/// service) synced to the given epoch and version.
+fn create_optimistic_storage_server(
+    epoch: u64,
+    version: u64,
+) -> (
+    StorageServiceServer<MockStorageReader>,
+    MockStorageReader,
+    TimeService,
+) {
+    let storage_reader = MockStorageReader::new(epoch, version);
+    let time_service = TimeService::mock();
+    let storage_server = StorageServiceServer::new(storage_reader.clone(), time_service.clone());
+    (storage_server, storage_reader, time_service)
+}
+
+/// Sends an optimistic fetch for new transactions to the server (from the
+/// given peer) and returns the receiver for the response.
+fn send_optimistic_fetch(
+    storage_server: &StorageServiceServer<MockStorageReader>,
+    peer_id: PeerId,
+    known_version: u64,
+    known_epoch: u64,
+) -> oneshot::Receiver<Result<bytes::Bytes, RpcError>> {
+    let request =
+        StorageServiceRequest::GetNewTransactionsWithProof(NewTransactionsWithProofRequest {
+            known_version,
+            known_epoch,
+            include_events: false,
+        });
+    let (response_sender, response_receiver) = oneshot::channel();
+    storage_server.handle_network_request(
+        peer_id,
+        StorageServiceMessage::Request(request),
+        ProtocolId::StorageServiceRpc,
+        response_sender,
+    );
+    response_receiver
+}
+
+/// Returns the storage service response that has already been sent along
+/// the given receiver.
+fn get_storage_service_response(
+    response_receiver: &mut oneshot::Receiver<Result<bytes::Bytes, RpcError>>,
+) -> StorageServiceResponse {
+    let response_bytes = response_receiver.try_recv().unwrap().unwrap().unwrap();
+    match ProtocolId::StorageServiceRpc
+        .from_bytes(&response_bytes)
+        .unwrap()
+    {
+        StorageServiceMessage::Response(response) => response,
+        message => panic!("Expected a storage service response but got: {:?}", message),
+    }
+}
+
+/// Verifies that the response holds the expected new transactions, proven
+/// by the ledger info at the given epoch and version.
+fn verify_new_transactions_response(
+    response_receiver: &mut oneshot::Receiver<Result<bytes::Bytes, RpcError>>,
+    expected_first_version: u64,
+    expected_num_transactions: u64,
+    expected_epoch: u64,
+    expected_proof_version: u64,
+) {
+    match get_storage_service_response(response_receiver) {
+        StorageServiceResponse::NewTransactionsWithProof((
+            transactions_with_proof,
+            ledger_info_with_sigs,
+        )) => {
+            assert_eq!(
+                transactions_with_proof.transactions.len(),
+                expected_num_transactions as usize
+            );
+            assert_eq!(
+                transactions_with_proof.first_transaction_version,
+                Some(expected_first_version)
+            );
+            assert_eq!(
+                ledger_info_with_sigs,
+                create_test_ledger_info_with_sigs(expected_epoch, expected_proof_version)
+            );
+        }
+        response => panic!(
+            "Expected new transactions with proof but got: {:?}",
+            response
+        ),
+    }
 }
 
 fn create_test_event(sequence_number: u64) -> ContractEvent {
@@ -346,6 +690,30 @@ fn create_test_ledger_info_with_sigs(epoch: u64, version: u64) -> LedgerInfoWith
 }
 
 /// This is a mock of the DbReader and DbWriter for unit testing.
+/// Creates a test account states chunk holding the specified (inclusive) range
+fn create_test_account_states_chunk(
+    start_account_index: u64,
+    end_account_index: u64,
+) -> AccountStatesChunkWithProof {
+    let account_blobs: Vec<_> = (start_account_index..=end_account_index)
+        .map(|index| {
+            (
+                HashValue::sha3_256_of(&index.to_le_bytes()),
+                AccountStateBlob::from(index.to_le_bytes().to_vec()),
+            )
+        })
+        .collect();
+
+    AccountStatesChunkWithProof {
+        first_index: start_account_index,
+        last_index: end_account_index,
+        first_key: account_blobs.first().unwrap().0,
+        last_key: account_blobs.last().unwrap().0,
+        account_blobs,
+        proof: SparseMerkleRangeProof::new(vec![]),
+    }
+}
+
 struct MockDbReaderWriter;
 
 impl DbReader<DpnProto> for MockDbReaderWriter {
@@ -469,7 +837,10 @@ impl DbReader<DpnProto> for MockDbReaderWriter {
 
     /// Returns the latest ledger info.
     fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
-        Ok(create_test_ledger_info_with_sigs(10, 100))
+        Ok(create_test_ledger_info_with_sigs(
+            MOCK_LATEST_EPOCH,
+            MOCK_LATEST_VERSION,
+        ))
     }
 
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
@@ -543,6 +914,32 @@ impl DbReader<DpnProto> for MockDbReaderWriter {
     ) -> Result<LedgerInfoWithSignatures> {
         unimplemented!()
     }
+
+    fn get_first_txn_version(&self) -> Result<Version> {
+        Ok(MOCK_FIRST_TXN_VERSION)
+    }
+
+    fn get_first_state_version(&self) -> Result<Version> {
+        Ok(MOCK_FIRST_STATE_VERSION)
+    }
+
+    fn get_account_count(&self, _version: Version) -> Result<usize> {
+        Ok(NUM_ACCOUNTS_AT_VERSION as usize)
+    }
+
+    fn get_account_chunk_with_proof(
+        &self,
+        _version: Version,
+        first_index: usize,
+        chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        let start_account_index = first_index as u64;
+        let end_account_index = start_account_index + chunk_size as u64 - 1;
+        Ok(create_test_account_states_chunk(
+            start_account_index,
+            end_account_index,
+        ))
+    }
 }
 
 impl DbWriter<DpnProto> for MockDbReaderWriter {
@@ -555,3 +952,94 @@ impl DbWriter<DpnProto> for MockDbReaderWriter {
         unimplemented!()
     }
 }
+
+/// A mock of the storage reader used to test optimistic fetches. Epoch `N`
+/// ends at version `(N + 1) * 100`, and the synced ledger info can be updated
+/// to emulate new data being committed.
+#[derive(Clone)]
+struct MockStorageReader {
+    synced_ledger_info: Arc<RwLock<LedgerInfoWithSignatures>>,
+}
+
+impl MockStorageReader {
+    fn new(epoch: u64, version: u64) -> Self {
+        Self {
+            synced_ledger_info: Arc::new(RwLock::new(create_test_ledger_info_with_sigs(
+                epoch, version,
+            ))),
+        }
+    }
+
+    fn set_synced_ledger_info(&self, epoch: u64, version: u64) {
+        *self.synced_ledger_info.write() = create_test_ledger_info_with_sigs(epoch, version);
+    }
+}
+
+impl StorageReaderInterface for MockStorageReader {
+    fn get_data_summary(&self) -> Result<DataSummary, Error> {
+        let synced_ledger_info = self.synced_ledger_info.read().clone();
+        let synced_epoch = synced_ledger_info.ledger_info().epoch();
+        let synced_version = synced_ledger_info.ledger_info().version();
+        Ok(DataSummary {
+            synced_ledger_info,
+            epoch_ending_ledger_infos: CompleteDataRange::new(0, synced_epoch - 1),
+            transactions: CompleteDataRange::new(0, synced_version),
+            transaction_outputs: CompleteDataRange::new(0, synced_version),
+            account_states: CompleteDataRange::new(0, synced_version),
+        })
+    }
+
+    fn get_transactions_with_proof(
+        &self,
+        _proof_version: u64,
+        start_version: u64,
+        expected_num_transactions: u64,
+        _include_events: bool,
+    ) -> Result<TransactionListWithProof, Error> {
+        let transactions = (0..expected_num_transactions)
+            .map(create_test_transaction)
+            .collect();
+        Ok(TransactionListWithProof {
+            transactions,
+            events: None,
+            first_transaction_version: Some(start_version),
+            proof: TransactionInfoListWithProof::new_empty(),
+        })
+    }
+
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        expected_end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error> {
+        let ledger_info_with_sigs = (start_epoch..=expected_end_epoch)
+            .map(|epoch| create_test_ledger_info_with_sigs(epoch, (epoch + 1) * 100))
+            .collect();
+        Ok(EpochChangeProof {
+            ledger_info_with_sigs,
+            more: false,
+        })
+    }
+
+    fn get_transaction_outputs_with_proof(
+        &self,
+        _proof_version: u64,
+        _start_version: u64,
+        _expected_num_transaction_outputs: u64,
+    ) -> Result<TransactionOutputListWithProof, Error> {
+        unimplemented!()
+    }
+
+    fn get_number_of_accounts(&self, _version: u64) -> Result<u64, Error> {
+        unimplemented!()
+    }
+
+    fn get_account_states_chunk_with_proof(
+        &self,
+        _version: u64,
+        _start_account_index: u64,
+        _end_account_index: u64,
+    ) -> Result<AccountStatesChunkWithProof, Error> {
+        unimplemented!()
+    }
+}
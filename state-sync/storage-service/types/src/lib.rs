@@ -3,7 +3,6 @@
 
 #![forbid(unsafe_code)]
 
-use diem_crypto::HashValue;
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
     epoch_change::EpochChangeProof,
@@ -13,12 +12,24 @@ use diem_types::{
         Version,
     },
 };
+use serde::{Deserialize, Serialize};
+
+/// A storage service message, sent over the network between storage service
+/// clients and servers.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StorageServiceMessage {
+    Request(StorageServiceRequest),
+    Response(StorageServiceResponse),
+}
 
 /// A storage service request.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StorageServiceRequest {
     GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest), // Fetches a list of account states with a proof
     GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest), // Fetches a list of epoch ending ledger infos
+    GetNewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest), // Optimistically fetches new transaction outputs (i.e., once they're committed)
+    GetNewTransactionsWithProof(NewTransactionsWithProofRequest), // Optimistically fetches new transactions (i.e., once they're committed)
     GetNumberOfAccountsAtVersion(Version), // Fetches the number of accounts at the specified version
     GetServerProtocolVersion,              // Fetches the protocol version run by the server
     GetStorageServerSummary,               // Fetches a summary of the storage server state
@@ -26,11 +37,25 @@ pub enum StorageServiceRequest {
     GetTransactionsWithProof(TransactionsWithProofRequest), // Fetches a list of transactions with a proof
 }
 
+impl StorageServiceRequest {
+    /// Returns true iff the request is an optimistic fetch, i.e., the server
+    /// may hold onto the request until new data is available to respond with.
+    pub fn is_optimistic_fetch(&self) -> bool {
+        matches!(
+            self,
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(_)
+                | StorageServiceRequest::GetNewTransactionsWithProof(_)
+        )
+    }
+}
+
 /// A storage service response.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StorageServiceResponse {
     AccountStatesChunkWithProof(AccountStatesChunkWithProof),
     EpochEndingLedgerInfos(EpochChangeProof),
+    NewTransactionOutputsWithProof((TransactionOutputListWithProof, LedgerInfoWithSignatures)),
+    NewTransactionsWithProof((TransactionListWithProof, LedgerInfoWithSignatures)),
     NumberOfAccountsAtVersion(u64),
     ServerProtocolVersion(ServerProtocolVersion),
     StorageServiceError(StorageServiceError),
@@ -40,26 +65,50 @@ pub enum StorageServiceResponse {
 }
 
 /// A storage service request for fetching a list of account states at a
-/// specified version.
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// specified version. Accounts are indexed by the order of their hashed keys
+/// in the account state tree.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct AccountStatesChunkWithProofRequest {
-    pub version: u64,                     // The version to fetch the account states at
-    pub start_account_key: HashValue,     // The account key to start fetching account states
-    pub expected_num_account_states: u64, // Expected number of account states to fetch
+    pub version: u64,             // The version to fetch the account states at
+    pub start_account_index: u64, // The account index to start fetching account states
+    pub end_account_index: u64,   // The account index to stop fetching account states (inclusive)
 }
 
 /// A storage service request for fetching a transaction output list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TransactionOutputsWithProofRequest {
     pub proof_version: u64,        // The version the proof should be relative to
     pub start_version: u64,        // The starting version of the transaction output list
     pub expected_num_outputs: u64, // Expected number of transaction outputs in the list
 }
 
+/// A storage service request for fetching a transaction output list (with a
+/// corresponding proof) beyond the versions already known by the client. If
+/// the server has no new data, the request is held until new data is
+/// committed (or the request expires). The response contains the ledger info
+/// that the proof is relative to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NewTransactionOutputsWithProofRequest {
+    pub known_version: u64, // The highest known output version
+    pub known_epoch: u64,   // The highest known epoch
+}
+
+/// A storage service request for fetching a transaction list (with a
+/// corresponding proof) beyond the versions already known by the client. If
+/// the server has no new data, the request is held until new data is
+/// committed (or the request expires). The response contains the ledger info
+/// that the proof is relative to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NewTransactionsWithProofRequest {
+    pub known_version: u64,   // The highest known transaction version
+    pub known_epoch: u64,     // The highest known epoch
+    pub include_events: bool, // Whether or not to include events in the response
+}
+
 /// A storage service request for fetching a transaction list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TransactionsWithProofRequest {
     pub proof_version: u64, // The version the proof should be relative to
     pub start_version: u64, // The starting version of the transaction list
@@ -68,7 +117,7 @@ pub struct TransactionsWithProofRequest {
 }
 
 /// A storage service request for fetching a list of epoch ending ledger infos.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct EpochEndingLedgerInfoRequest {
     pub start_epoch: u64,
     pub expected_end_epoch: u64,
@@ -76,14 +125,17 @@ pub struct EpochEndingLedgerInfoRequest {
 
 /// A storage service error that can be returned to the client on a failure
 /// to process a service request.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StorageServiceError {
     InternalError,
+    InvalidRequest(String), // The request can't be serviced using the data held by the server (e.g., the data was pruned)
+    OptimisticFetchExpired, // No new data was committed before the request expired
+    TooManyOptimisticFetches, // The peer has too many optimistic fetches held by the server
 }
 
 /// The protocol version run by this server. Clients request this first to
 /// identify what API calls and data requests the server supports.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ServerProtocolVersion {
     pub protocol_version: u64, // The storage server version run by this instance.
 }
@@ -91,7 +143,7 @@ pub struct ServerProtocolVersion {
 /// A storage server summary, containing a summary of the information held
 /// by the corresponding server instance. This is useful for identifying the
 /// data that a server instance can provide, as well as relevant metadata.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct StorageServerSummary {
     pub protocol_metadata: ProtocolMetadata,
     pub data_summary: DataSummary,
@@ -99,7 +151,7 @@ pub struct StorageServerSummary {
 
 /// A summary of the protocol metadata for the storage service instance, such as
 /// the maximum chunk sizes supported for different requests.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ProtocolMetadata {
     pub max_epoch_chunk_size: u64, // The max number of epochs the server can return in a single chunk
     pub max_transaction_chunk_size: u64, // The max number of transactions the server can return in a single chunk
@@ -111,7 +163,7 @@ pub struct ProtocolMetadata {
 pub type Epoch = u64;
 
 /// A summary of the data actually held by the storage service instance.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct DataSummary {
     /// The ledger info corresponding to the highest synced version in storage.
     /// This indicates the highest version and epoch that storage can prove.
@@ -133,11 +185,92 @@ pub struct DataSummary {
     pub account_states: CompleteDataRange<Version>,
 }
 
+impl DataSummary {
+    /// Returns true iff the request can be serviced using the data held in
+    /// storage (as described by this summary).
+    pub fn can_service(&self, request: &StorageServiceRequest) -> bool {
+        match request {
+            StorageServiceRequest::GetServerProtocolVersion
+            | StorageServiceRequest::GetStorageServerSummary => true,
+            StorageServiceRequest::GetAccountStatesChunkWithProof(request) => {
+                self.account_states.contains(request.version)
+            }
+            StorageServiceRequest::GetEpochEndingLedgerInfos(request) => {
+                self.epoch_ending_ledger_infos.contains(request.start_epoch)
+                    && self
+                        .epoch_ending_ledger_infos
+                        .contains(request.expected_end_epoch)
+            }
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(request) => self
+                .can_service_optimistic_fetch(
+                    &self.transaction_outputs,
+                    request.known_version,
+                    request.known_epoch,
+                ),
+            StorageServiceRequest::GetNewTransactionsWithProof(request) => self
+                .can_service_optimistic_fetch(
+                    &self.transactions,
+                    request.known_version,
+                    request.known_epoch,
+                ),
+            StorageServiceRequest::GetNumberOfAccountsAtVersion(version) => {
+                self.account_states.contains(*version)
+            }
+            StorageServiceRequest::GetTransactionOutputsWithProof(request) => self
+                .can_service_versions(
+                    &self.transaction_outputs,
+                    request.proof_version,
+                    request.start_version,
+                    request.expected_num_outputs,
+                ),
+            StorageServiceRequest::GetTransactionsWithProof(request) => self.can_service_versions(
+                &self.transactions,
+                request.proof_version,
+                request.start_version,
+                request.expected_num_transactions,
+            ),
+        }
+    }
+
+    /// Returns true iff an optimistic fetch from the given known version and
+    /// epoch can be serviced, i.e., the known version is held in the given
+    /// range and storage has synced at least up to the known version and epoch.
+    fn can_service_optimistic_fetch(
+        &self,
+        range: &CompleteDataRange<Version>,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> bool {
+        let synced_ledger_info = self.synced_ledger_info.ledger_info();
+        range.contains(known_version)
+            && known_version <= synced_ledger_info.version()
+            && known_epoch <= synced_ledger_info.epoch()
+    }
+
+    /// Returns true iff `num_versions` versions starting at `start_version`
+    /// are held in the given range and can be proven relative to `proof_version`.
+    fn can_service_versions(
+        &self,
+        range: &CompleteDataRange<Version>,
+        proof_version: Version,
+        start_version: Version,
+        num_versions: u64,
+    ) -> bool {
+        let end_version = match start_version.checked_add(num_versions.saturating_sub(1)) {
+            Some(end_version) => end_version,
+            None => return false,
+        };
+        proof_version <= self.synced_ledger_info.ledger_info().version()
+            && range.contains(start_version)
+            && range.contains(end_version)
+    }
+}
+
 /// A struct representing a data range (lowest to highest, inclusive) where data
 /// is complete (i.e. there are no missing pieces of data).
 /// This is used to provide a summary of the data currently held in storage, e.g.
 /// a CompleteDataRange<Version> of (A,B) means all versions A->B (inclusive).
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CompleteDataRange<T> {
     pub lowest: T,
     pub highest: T,
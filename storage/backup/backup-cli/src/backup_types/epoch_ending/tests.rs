@@ -15,7 +15,7 @@ use crate::{
     },
 };
 use backup_service::start_backup_service;
-use diem_config::utils::get_available_port;
+use diem_config::{config::RocksdbConfig, utils::get_available_port};
 use diem_temppath::TempPath;
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
@@ -36,7 +36,7 @@ use warp::Filter;
 
 #[test]
 fn end_to_end() {
-    let (_src_db_dir, src_db, blocks) = tmp_db_with_random_content();
+    let (src_db, blocks) = tmp_db_with_random_content();
     let tgt_db_dir = TempPath::new();
     tgt_db_dir.create_as_dir().unwrap();
 
@@ -103,7 +103,17 @@ fn end_to_end() {
         .map(|li| li.ledger_info().next_block_epoch())
         .unwrap_or(0);
 
-    let tgt_db = DiemDB::new_for_test(&tgt_db_dir);
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* readonly */
+        None, /* pruner */
+        None, /* ledger_pruner */
+        RocksdbConfig::default(),
+        true,  /* account_count_migration, ignored anyway */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
+    )
+    .unwrap();
     assert_eq!(
         tgt_db
             .get_epoch_ending_ledger_infos(0, target_version_next_block_epoch)
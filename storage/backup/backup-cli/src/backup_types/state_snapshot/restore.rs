@@ -144,6 +144,7 @@ impl StateSnapshotRestoreController {
         }
 
         receiver.finish()?;
+        self.run_mode.save_resource_state_snapshot(self.version)?;
         Ok(())
     }
 
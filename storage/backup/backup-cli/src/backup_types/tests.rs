@@ -19,6 +19,7 @@ use crate::{
         RocksdbOpt, TrustedWaypointOpt,
     },
 };
+use diem_config::config::RocksdbConfig;
 use diem_temppath::TempPath;
 use diem_types::transaction::Version;
 use diemdb::DiemDB;
@@ -149,7 +150,17 @@ fn test_end_to_end_impl(d: TestData) {
     .unwrap();
 
     // Check
-    let tgt_db = DiemDB::new_for_test(&tgt_db_dir);
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* readonly */
+        None, /* pruner */
+        None, /* ledger_pruner */
+        RocksdbConfig::default(),
+        true,  /* account_count_migration, ignored anyway */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
+    )
+    .unwrap();
     assert_eq!(
         d.db.get_transactions(
             d.txn_start_ver,
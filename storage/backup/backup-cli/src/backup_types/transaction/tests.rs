@@ -13,6 +13,7 @@ use crate::{
         ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
     },
 };
+use diem_config::config::RocksdbConfig;
 use diem_temppath::TempPath;
 use diem_types::transaction::Version;
 use diemdb::DiemDB;
@@ -22,7 +23,7 @@ use tokio::time::Duration;
 
 #[test]
 fn end_to_end() {
-    let (_src_db_dir, src_db, blocks) = tmp_db_with_random_content();
+    let (src_db, blocks) = tmp_db_with_random_content();
     let tgt_db_dir = TempPath::new();
     tgt_db_dir.create_as_dir().unwrap();
     let backup_dir = TempPath::new();
@@ -98,7 +99,17 @@ fn end_to_end() {
     // We don't write down any ledger infos when recovering transactions. State-sync needs to take
     // care of it before running consensus. The latest transactions are deemed "synced" instead of
     // "committed" most likely.
-    let tgt_db = DiemDB::new_for_test(&tgt_db_dir);
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* readonly */
+        None, /* pruner */
+        None, /* ledger_pruner */
+        RocksdbConfig::default(),
+        true,  /* account_count_migration, ignored anyway */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
+    )
+    .unwrap();
     assert_eq!(
         tgt_db
             .get_latest_transaction_info_option()
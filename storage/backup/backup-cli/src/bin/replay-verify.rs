@@ -57,8 +57,11 @@ async fn main_impl() -> Result<()> {
         opt.db_dir,
         false, /* read_only */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         opt.rocksdb_opt.into(),
-        true, /* account_count_migration */
+        true,  /* account_count_migration */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
     )?)
     .get_restore_handler();
     ReplayVerifyCoordinator::new(
@@ -99,6 +99,13 @@ pub struct GlobalRestoreOpt {
         but incompatible with older node versions."
     )]
     pub account_count_migration: bool,
+
+    #[structopt(
+        long,
+        help = "If StateDB is written into, store the state by resource as well, so that the DB can \
+        be used with the resource-granular state storage turned on."
+    )]
+    pub resource_granular_state: bool,
 }
 
 pub enum RestoreRunMode {
@@ -129,6 +136,17 @@ impl RestoreRunMode {
         }
     }
 
+    /// Builds the state stored by resource (if the DB is configured to) out of the account state
+    /// snapshot just restored at `version`.
+    pub fn save_resource_state_snapshot(&self, version: Version) -> Result<()> {
+        match self {
+            Self::Restore { restore_handler } => {
+                restore_handler.save_resource_state_snapshot(version)
+            }
+            Self::Verify => Ok(()),
+        }
+    }
+
     pub fn get_state_restore_receiver(
         &self,
         version: Version,
@@ -171,8 +189,11 @@ impl TryFrom<GlobalRestoreOpt> for GlobalRestoreOptions {
                 db_dir,
                 false, /* read_only */
                 None,  /* pruner */
+                None,  /* ledger_pruner */
                 opt.rocksdb_opt.into(),
                 opt.account_count_migration,
+                opt.resource_granular_state,
+                0, /* state_value_cache_size */
             )?)
             .get_restore_handler();
             RestoreRunMode::Restore { restore_handler }
@@ -38,7 +38,6 @@ mod tests {
     use super::*;
     use diem_config::utils::get_available_port;
     use diem_crypto::hash::HashValue;
-    use diem_temppath::TempPath;
     use reqwest::blocking::get;
     use std::net::{IpAddr, Ipv4Addr};
 
@@ -49,8 +48,7 @@ mod tests {
     /// And failure on one endpoint doesn't result in warp::Rejection which makes it fallback to other matches.
     #[test]
     fn routing_and_error_codes() {
-        let tmpdir = TempPath::new();
-        let db = Arc::new(DiemDB::new_for_test(&tmpdir));
+        let db = Arc::new(DiemDB::new_for_test());
         let port = get_available_port();
         let _rt = start_backup_service(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), db);
 
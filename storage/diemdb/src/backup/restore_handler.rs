@@ -64,26 +64,14 @@ impl RestoreHandler {
         )
     }
 
-    pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
-        ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
-
-        let mut cs = ChangeSet::new();
-        ledger_infos
-            .iter()
-            .map(|li| self.ledger_store.put_ledger_info(li, &mut cs))
-            .collect::<Result<Vec<_>>>()?;
-        self.db.write_schemas(cs.batch)?;
-
-        if let Some(li) = self.ledger_store.get_latest_ledger_info_option() {
-            if li.ledger_info().epoch() > ledger_infos.last().unwrap().ledger_info().epoch() {
-                // No need to update latest ledger info.
-                return Ok(());
-            }
-        }
+    /// If the DB stores the state by resource as well, builds that from the account state
+    /// snapshot restored at `version`. Otherwise does nothing.
+    pub fn save_resource_state_snapshot(&self, version: Version) -> Result<()> {
+        self.diemdb.save_resource_state_snapshot(version)
+    }
 
-        self.ledger_store
-            .set_latest_ledger_info(ledger_infos.last().unwrap().clone());
-        Ok(())
+    pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
+        save_ledger_infos(&self.db, &self.ledger_store, ledger_infos)
     }
 
     pub fn confirm_or_save_frozen_subtrees(
@@ -91,34 +79,7 @@ impl RestoreHandler {
         num_leaves: LeafCount,
         frozen_subtrees: &[HashValue],
     ) -> Result<()> {
-        let mut cs = ChangeSet::new();
-        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
-
-        ensure!(
-            positions.len() == frozen_subtrees.len(),
-            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
-            positions.len(),
-            frozen_subtrees.len(),
-        );
-
-        positions
-            .iter()
-            .zip(frozen_subtrees.iter().rev())
-            .map(|(p, h)| {
-                if let Some(_h) = self.db.get::<TransactionAccumulatorSchema>(p)? {
-                    ensure!(
-                        h == &_h,
-                        "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
-                        h,
-                        _h,
-                    );
-                } else {
-                    cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
-                }
-                Ok(())
-            })
-            .collect::<Result<Vec<_>>>()?;
-        self.db.write_schemas(cs.batch)
+        confirm_or_save_frozen_subtrees(&self.db, num_leaves, frozen_subtrees)
     }
 
     pub fn save_transactions(
@@ -167,3 +128,67 @@ impl RestoreHandler {
             .map_or(0, |(ver, _txn_info)| ver + 1))
     }
 }
+
+/// Saves the given ledger infos to the ledger store and updates the latest ledger info
+/// (unless a ledger info in a later epoch is already known).
+pub(crate) fn save_ledger_infos(
+    db: &DB,
+    ledger_store: &LedgerStore,
+    ledger_infos: &[LedgerInfoWithSignatures],
+) -> Result<()> {
+    ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
+
+    let mut cs = ChangeSet::new();
+    ledger_infos
+        .iter()
+        .map(|li| ledger_store.put_ledger_info(li, &mut cs))
+        .collect::<Result<Vec<_>>>()?;
+    db.write_schemas(cs.batch)?;
+
+    if let Some(li) = ledger_store.get_latest_ledger_info_option() {
+        if li.ledger_info().epoch() > ledger_infos.last().unwrap().ledger_info().epoch() {
+            // No need to update latest ledger info.
+            return Ok(());
+        }
+    }
+
+    ledger_store.set_latest_ledger_info(ledger_infos.last().unwrap().clone());
+    Ok(())
+}
+
+/// Confirms the given frozen subtree roots of the transaction accumulator (with `num_leaves`
+/// leaves) match those already in the DB, or saves them if they don't exist yet.
+pub(crate) fn confirm_or_save_frozen_subtrees(
+    db: &DB,
+    num_leaves: LeafCount,
+    frozen_subtrees: &[HashValue],
+) -> Result<()> {
+    let mut cs = ChangeSet::new();
+    let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+
+    ensure!(
+        positions.len() == frozen_subtrees.len(),
+        "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+        positions.len(),
+        frozen_subtrees.len(),
+    );
+
+    positions
+        .iter()
+        .zip(frozen_subtrees.iter().rev())
+        .map(|(p, h)| {
+            if let Some(_h) = db.get::<TransactionAccumulatorSchema>(p)? {
+                ensure!(
+                    h == &_h,
+                    "Frozen subtree root does not match that already in DB. Provided: {}, in db: {}.",
+                    h,
+                    _h,
+                );
+            } else {
+                cs.batch.put::<TransactionAccumulatorSchema>(p, h)?;
+            }
+            Ok(())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    db.write_schemas(cs.batch)
+}
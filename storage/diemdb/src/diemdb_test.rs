@@ -10,7 +10,6 @@ use crate::{
 use diem_crypto::hash::CryptoHash;
 #[allow(unused_imports)]
 use diem_jellyfish_merkle::node_type::{Node, NodeKey};
-use diem_temppath::TempPath;
 use diem_types::transaction::Transaction;
 #[allow(unused_imports)]
 use diem_types::{
@@ -78,8 +77,7 @@ fn verify_epochs(db: &DiemDB, ledger_infos_with_sigs: &[LedgerInfoWithSignatures
 }
 
 pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     let num_batches = input.len();
     let mut cur_ver = 0;
@@ -140,8 +138,7 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
 }
 
 fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     let num_batches = input.len();
     let mut cur_ver = 0;
@@ -536,7 +533,7 @@ fn verify_committed_transactions(
         txn_output_list_with_proof
             .verify(ledger_info, Some(cur_ver))
             .unwrap();
-        assert_eq!(txn_output_list_with_proof.transaction_outputs.len(), 1);
+        assert_eq!(txn_output_list_with_proof.transactions_and_outputs.len(), 1);
 
         // Fetch and verify account states.
         for (addr, expected_blob) in txn_to_commit.account_states() {
@@ -613,8 +610,7 @@ fn test_get_first_seq_num_and_limit() {
 
 #[test]
 fn test_too_many_requested() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
     assert!(db.get_transaction_outputs(0, 1001 /* limit */, 0).is_err());
@@ -622,8 +618,7 @@ fn test_too_many_requested() {
 
 #[test]
 fn test_get_latest_tree_state() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
 
     // entirely emtpy db
     let empty = db.get_latest_tree_state().unwrap();
@@ -24,8 +24,11 @@ impl Diemsum {
             db_root_path,
             true, /* read only */
             None, /* no prune_window */
+            None, /* no ledger_prune_window */
             RocksdbConfig::default(),
-            true, /* account_count_migration, ignored anyway */
+            true,  /* account_count_migration, ignored anyway */
+            false, /* resource_granular_state */
+            0,     /* state_value_cache_size */
         )?;
         Ok(Diemsum { db })
     }
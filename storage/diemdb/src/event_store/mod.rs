@@ -18,7 +18,7 @@ use crate::{
 use accumulator::{HashReader, MerkleAccumulator};
 use anyhow::{ensure, format_err, Result};
 use diem_crypto::{
-    hash::{CryptoHash, EventAccumulatorHasher},
+    hash::{CryptoHash, EventAccumulatorHasher, ACCUMULATOR_PLACEHOLDER_HASH},
     HashValue,
 };
 use diem_types::{
@@ -194,6 +194,14 @@ impl EventStore {
             if path != *event_key || ver > ledger_version {
                 break;
             }
+            // The events at the beginning of the requested range have been pruned.
+            if result.is_empty() && seq > cur_seq {
+                return Err(DiemDbError::Pruned(format!(
+                    "Event {} of seq num {}",
+                    event_key, cur_seq
+                ))
+                .into());
+            }
             ensure!(
                 seq == cur_seq,
                 "DB corrupt: Sequence number not continuous, expected: {}, actual: {}.",
@@ -265,6 +273,15 @@ impl EventStore {
         Ok(root_hash)
     }
 
+    /// Get the root hash of the event accumulator of the transaction at `version` from the
+    /// persisted accumulator nodes, given the number of events emitted by the transaction.
+    pub fn get_event_root_hash(&self, version: Version, num_events: u64) -> Result<HashValue> {
+        if num_events == 0 {
+            return Ok(*ACCUMULATOR_PLACEHOLDER_HASH);
+        }
+        Accumulator::get_root_hash(&EventHashReader::new(self, version), num_events)
+    }
+
     pub(crate) fn put_events_multiple_versions(
         &self,
         first_version: u64,
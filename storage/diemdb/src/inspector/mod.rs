@@ -0,0 +1,423 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides [`DbInspector`], which browses the column families of a [`DiemDB`] at
+//! the schema level and checks the consistency of the data across schemas. It's meant for offline
+//! debugging (e.g. by the `db-inspector` tool), so everything it returns is serializable.
+//!
+//! [`DiemDB`]: crate::DiemDB
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    event_store::EventStore,
+    ledger_store::LedgerStore,
+    schema::{
+        epoch_by_version::EpochByVersionSchema, event::EventSchema,
+        event_accumulator::EventAccumulatorSchema, event_by_key::EventByKeySchema,
+        event_by_version::EventByVersionSchema, jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+        ledger_counters::LedgerCountersSchema, ledger_info::LedgerInfoSchema,
+        resource_merkle_node::ResourceMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        stale_resource_node_index::StaleResourceNodeIndexSchema, transaction::TransactionSchema,
+        transaction_accumulator::TransactionAccumulatorSchema,
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_by_hash::TransactionByHashSchema, transaction_info::TransactionInfoSchema,
+        write_set::WriteSetSchema, *,
+    },
+    DiemDB,
+};
+use anyhow::{format_err, Error, Result};
+use diem_crypto::hash::{CryptoHash, EventAccumulatorHasher};
+use diem_jellyfish_merkle::{node_type::NodeKey, StaleNodeIndex};
+use diem_types::{
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{TransactionInfoTrait, Version},
+};
+use schemadb::{schema::Schema, ColumnFamilyName, ReadOptions, DB, DEFAULT_CF_NAME};
+use serde::Serialize;
+use std::{fmt, str::FromStr, sync::Arc};
+
+/// A row of a column family, with the key and the value decoded and formatted for humans.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct DecodedRow {
+    pub key: String,
+    pub value: String,
+}
+
+impl DecodedRow {
+    fn new<S: Schema>(key: &S::Key, value: &S::Value) -> Self {
+        Self {
+            key: format!("{:?}", key),
+            value: format!("{:?}", value),
+        }
+    }
+}
+
+/// The consistency checks across schemas supported by [`DbInspector::check_consistency`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyCheck {
+    /// Every entry in `TransactionByHashSchema` points at an existing transaction of that hash.
+    TransactionByHash,
+    /// The events of each transaction, and the event accumulator persisted for it, hash to the
+    /// event root hash in its `TransactionInfo`.
+    EventAccumulator,
+    /// Every stale node index of the account state Merkle tree is sane, and is not left behind
+    /// after the node it indexes is pruned.
+    StaleNodeIndex,
+    /// Same as `StaleNodeIndex`, for the resource-granular state Merkle tree.
+    StaleResourceNodeIndex,
+}
+
+impl ConsistencyCheck {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::TransactionByHash,
+            Self::EventAccumulator,
+            Self::StaleNodeIndex,
+            Self::StaleResourceNodeIndex,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TransactionByHash => "transaction_by_hash",
+            Self::EventAccumulator => "event_accumulator",
+            Self::StaleNodeIndex => "stale_node_index",
+            Self::StaleResourceNodeIndex => "stale_resource_node_index",
+        }
+    }
+}
+
+impl fmt::Display for ConsistencyCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ConsistencyCheck {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::all()
+            .into_iter()
+            .find(|check| check.name() == s)
+            .ok_or_else(|| {
+                format_err!(
+                    "Unknown consistency check {}. Supported: {}.",
+                    s,
+                    Self::all()
+                        .iter()
+                        .map(ConsistencyCheck::name)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            })
+    }
+}
+
+/// An entry found inconsistent by a consistency check.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Inconsistency {
+    /// The decoded key of the offending entry.
+    pub key: String,
+    pub description: String,
+}
+
+/// The result of a consistency check.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ConsistencyCheckReport {
+    pub check: ConsistencyCheck,
+    pub num_entries_checked: usize,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl ConsistencyCheckReport {
+    fn new(check: ConsistencyCheck) -> Self {
+        Self {
+            check,
+            num_entries_checked: 0,
+            inconsistencies: Vec::new(),
+        }
+    }
+
+    fn add_inconsistency(&mut self, key: &impl fmt::Debug, description: String) {
+        self.inconsistencies.push(Inconsistency {
+            key: format!("{:?}", key),
+            description,
+        });
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+/// `DbInspector` provides schema-level read access to DiemDB and consistency checks over it.
+pub struct DbInspector {
+    db: Arc<DB>,
+    ledger_store: Arc<LedgerStore>,
+    event_store: Arc<EventStore>,
+}
+
+impl DbInspector {
+    pub(crate) fn new(
+        db: Arc<DB>,
+        ledger_store: Arc<LedgerStore>,
+        event_store: Arc<EventStore>,
+    ) -> Self {
+        Self {
+            db,
+            ledger_store,
+            event_store,
+        }
+    }
+
+    /// Names of all the column families in the DB.
+    pub fn column_families(&self) -> Vec<ColumnFamilyName> {
+        DiemDB::column_families()
+    }
+
+    /// Gets up to `limit` rows of the column family named `cf_name`, from the first key, or from
+    /// the last key backwards if `reverse` is set.
+    pub fn dump_column_family(
+        &self,
+        cf_name: &str,
+        limit: usize,
+        reverse: bool,
+    ) -> Result<Vec<DecodedRow>> {
+        match cf_name {
+            DEFAULT_CF_NAME => self.dump::<LedgerInfoSchema>(limit, reverse),
+            EPOCH_BY_VERSION_CF_NAME => self.dump::<EpochByVersionSchema>(limit, reverse),
+            EVENT_ACCUMULATOR_CF_NAME => self.dump::<EventAccumulatorSchema>(limit, reverse),
+            EVENT_BY_KEY_CF_NAME => self.dump::<EventByKeySchema>(limit, reverse),
+            EVENT_BY_VERSION_CF_NAME => self.dump::<EventByVersionSchema>(limit, reverse),
+            EVENT_CF_NAME => self.dump::<EventSchema>(limit, reverse),
+            JELLYFISH_MERKLE_NODE_CF_NAME => self.dump::<JellyfishMerkleNodeSchema>(limit, reverse),
+            LEDGER_COUNTERS_CF_NAME => self.dump::<LedgerCountersSchema>(limit, reverse),
+            RESOURCE_MERKLE_NODE_CF_NAME => self.dump::<ResourceMerkleNodeSchema>(limit, reverse),
+            STALE_NODE_INDEX_CF_NAME => self.dump::<StaleNodeIndexSchema>(limit, reverse),
+            STALE_RESOURCE_NODE_INDEX_CF_NAME => {
+                self.dump::<StaleResourceNodeIndexSchema>(limit, reverse)
+            }
+            TRANSACTION_CF_NAME => self.dump::<TransactionSchema>(limit, reverse),
+            TRANSACTION_ACCUMULATOR_CF_NAME => {
+                self.dump::<TransactionAccumulatorSchema>(limit, reverse)
+            }
+            TRANSACTION_BY_ACCOUNT_CF_NAME => {
+                self.dump::<TransactionByAccountSchema>(limit, reverse)
+            }
+            TRANSACTION_BY_HASH_CF_NAME => self.dump::<TransactionByHashSchema>(limit, reverse),
+            TRANSACTION_INFO_CF_NAME => self.dump::<TransactionInfoSchema>(limit, reverse),
+            WRITE_SET_CF_NAME => self.dump::<WriteSetSchema>(limit, reverse),
+            _ => Err(format_err!(
+                "Unknown column family {}. Column families: {}.",
+                cf_name,
+                self.column_families().join(", "),
+            )),
+        }
+    }
+
+    /// Runs a consistency check over the whole DB.
+    pub fn check_consistency(&self, check: ConsistencyCheck) -> Result<ConsistencyCheckReport> {
+        match check {
+            ConsistencyCheck::TransactionByHash => self.check_transaction_by_hash(),
+            ConsistencyCheck::EventAccumulator => self.check_event_accumulator(),
+            ConsistencyCheck::StaleNodeIndex => self
+                .check_stale_node_index::<StaleNodeIndexSchema, JellyfishMerkleNodeSchema>(check),
+            ConsistencyCheck::StaleResourceNodeIndex => self
+                .check_stale_node_index::<StaleResourceNodeIndexSchema, ResourceMerkleNodeSchema>(
+                    check,
+                ),
+        }
+    }
+
+    fn dump<S: Schema>(&self, limit: usize, reverse: bool) -> Result<Vec<DecodedRow>> {
+        let mut iter = if reverse {
+            let mut iter = self.db.rev_iter::<S>(ReadOptions::default())?;
+            iter.seek_to_last();
+            iter
+        } else {
+            let mut iter = self.db.iter::<S>(ReadOptions::default())?;
+            iter.seek_to_first();
+            iter
+        };
+
+        iter.by_ref()
+            .take(limit)
+            .map(|res| res.map(|(key, value)| DecodedRow::new::<S>(&key, &value)))
+            .collect()
+    }
+
+    fn check_transaction_by_hash(&self) -> Result<ConsistencyCheckReport> {
+        let mut report = ConsistencyCheckReport::new(ConsistencyCheck::TransactionByHash);
+        let mut iter = self
+            .db
+            .iter::<TransactionByHashSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        for res in iter {
+            let (hash, version) = res?;
+            report.num_entries_checked += 1;
+            match self.db.get::<TransactionSchema>(&version)? {
+                None => report.add_inconsistency(
+                    &hash,
+                    format!("Points at transaction {}, which doesn't exist.", version),
+                ),
+                Some(txn) if txn.hash() != hash => report.add_inconsistency(
+                    &hash,
+                    format!(
+                        "Points at transaction {}, which is of hash {:x}.",
+                        version,
+                        txn.hash(),
+                    ),
+                ),
+                Some(_) => (),
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn check_event_accumulator(&self) -> Result<ConsistencyCheckReport> {
+        let mut report = ConsistencyCheckReport::new(ConsistencyCheck::EventAccumulator);
+        let mut iter = self
+            .db
+            .iter::<TransactionInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        for res in iter {
+            let (version, txn_info) = res?;
+            report.num_entries_checked += 1;
+            let expected_root_hash = txn_info.event_root_hash();
+
+            let event_hashes = self
+                .event_store
+                .get_events_by_version(version)?
+                .iter()
+                .map(CryptoHash::hash)
+                .collect::<Vec<_>>();
+            let root_hash =
+                InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                    .root_hash();
+            if root_hash != expected_root_hash {
+                report.add_inconsistency(
+                    &version,
+                    format!(
+                        "The {} events hash to {:x}, but the event root hash in TransactionInfo \
+                         is {:x}.",
+                        event_hashes.len(),
+                        root_hash,
+                        expected_root_hash,
+                    ),
+                );
+            }
+
+            match self
+                .event_store
+                .get_event_root_hash(version, event_hashes.len() as u64)
+            {
+                Ok(accumulator_root_hash) if accumulator_root_hash != expected_root_hash => report
+                    .add_inconsistency(
+                        &version,
+                        format!(
+                            "The root hash of the event accumulator is {:x}, but the event root \
+                             hash in TransactionInfo is {:x}.",
+                            accumulator_root_hash, expected_root_hash,
+                        ),
+                    ),
+                Ok(_) => (),
+                Err(err) => report.add_inconsistency(
+                    &version,
+                    format!("Failed to read the event accumulator: {}", err),
+                ),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Checks the stale node indices in `I` of the tree whose nodes are in `N`:
+    ///
+    /// 1. A node becomes stale after it's created, at a version already committed.
+    /// 2. The pruner deletes the nodes that became stale before a version together with their
+    ///    indices, in the order of the indices. So once an index whose node exists is met, the nodes
+    ///    of all the indices that follow must exist. Otherwise the indices are orphans, left behind
+    ///    by deleting the nodes without the indices.
+    ///
+    /// The root of the empty tree that a state snapshot is built upon is marked stale without
+    /// ever being written, so a missing root at the version before the stale since version is
+    /// not reported.
+    fn check_stale_node_index<I, N>(
+        &self,
+        check: ConsistencyCheck,
+    ) -> Result<ConsistencyCheckReport>
+    where
+        I: Schema<Key = StaleNodeIndex, Value = ()>,
+        N: Schema<Key = NodeKey>,
+    {
+        let mut report = ConsistencyCheckReport::new(check);
+        let latest_version = self
+            .ledger_store
+            .get_latest_transaction_info_option()?
+            .map(|(version, _txn_info)| version);
+        let mut iter = self.db.iter::<I>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        // The stale since version of the first index whose node is not pruned yet.
+        let mut first_unpruned_version: Option<Version> = None;
+        for res in iter {
+            let (index, ()) = res?;
+            report.num_entries_checked += 1;
+
+            if index.node_key.version() >= index.stale_since_version {
+                report.add_inconsistency(
+                    &index,
+                    format!(
+                        "The node created at version {} becomes stale at version {}.",
+                        index.node_key.version(),
+                        index.stale_since_version,
+                    ),
+                );
+            }
+            if latest_version.map_or(true, |latest_version| {
+                index.stale_since_version > latest_version
+            }) {
+                report.add_inconsistency(
+                    &index,
+                    format!(
+                        "The node becomes stale at version {}, after the latest version {:?}.",
+                        index.stale_since_version, latest_version,
+                    ),
+                );
+            }
+
+            if self.db.get::<N>(&index.node_key)?.is_some() {
+                first_unpruned_version.get_or_insert(index.stale_since_version);
+            } else if is_empty_root_before_snapshot(&index) {
+                // Never written, see below.
+            } else if let Some(first_unpruned_version) = first_unpruned_version {
+                report.add_inconsistency(
+                    &index,
+                    format!(
+                        "Orphan index: the node doesn't exist, while nodes stale since version \
+                         {} are not pruned.",
+                        first_unpruned_version,
+                    ),
+                );
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+fn is_empty_root_before_snapshot(index: &StaleNodeIndex) -> bool {
+    index
+        .stale_since_version
+        .checked_sub(1)
+        .map_or(false, |version| {
+            index.node_key == NodeKey::new_empty_path(version)
+        })
+}
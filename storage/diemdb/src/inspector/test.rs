@@ -0,0 +1,123 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::test_helper::arb_blocks_to_commit;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures, nibble::nibble_path::NibblePath,
+    transaction::TransactionToCommit,
+};
+use proptest::prelude::*;
+use schemadb::SchemaBatch;
+use storage_interface::DbWriter;
+
+fn save_blocks(db: &DiemDB, input: &[(Vec<TransactionToCommit>, LedgerInfoWithSignatures)]) -> u64 {
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    cur_ver
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_dump_column_family(input in arb_blocks_to_commit()) {
+        let db = DiemDB::new_for_test();
+        let num_txns = save_blocks(&db, &input);
+        let inspector = db.get_inspector();
+
+        let rows = inspector.dump_column_family(TRANSACTION_CF_NAME, 2, false).unwrap();
+        prop_assert_eq!(rows.len(), std::cmp::min(2, num_txns as usize));
+        prop_assert_eq!(&rows[0].key, &format!("{:?}", 0u64));
+
+        let rows = inspector.dump_column_family(TRANSACTION_CF_NAME, usize::MAX, true).unwrap();
+        prop_assert_eq!(rows.len(), num_txns as usize);
+        prop_assert_eq!(&rows[0].key, &format!("{:?}", num_txns - 1));
+
+        for cf_name in inspector.column_families() {
+            inspector.dump_column_family(cf_name, 10, false).unwrap();
+        }
+        prop_assert!(inspector.dump_column_family("no_such_cf", 10, false).is_err());
+    }
+
+    #[test]
+    fn test_check_consistency(input in arb_blocks_to_commit()) {
+        let db = DiemDB::new_for_test();
+        let num_txns = save_blocks(&db, &input);
+        let inspector = db.get_inspector();
+
+        for check in ConsistencyCheck::all() {
+            let report = inspector.check_consistency(check).unwrap();
+            prop_assert!(report.is_consistent(), "{:?}", report);
+        }
+
+        let txns_to_commit = input
+            .iter()
+            .flat_map(|(txns_to_commit, _ledger_info_with_sigs)| txns_to_commit)
+            .collect::<Vec<_>>();
+        let mut batch = SchemaBatch::new();
+        // Drop the first transaction.
+        batch.delete::<TransactionSchema>(&0).unwrap();
+        // Drop the last event of the first transaction with any.
+        let version_with_events = txns_to_commit
+            .iter()
+            .position(|txn_to_commit| !txn_to_commit.events().is_empty());
+        if let Some(version) = version_with_events {
+            let num_events = txns_to_commit[version].events().len();
+            batch
+                .delete::<EventSchema>(&(version as Version, num_events as u64 - 1))
+                .unwrap();
+        }
+        // Mark a node that doesn't exist stale after a node that does.
+        let orphan_index = StaleNodeIndex {
+            stale_since_version: num_txns,
+            node_key: NodeKey::new(0, NibblePath::new(vec![0xff; 32])),
+        };
+        batch
+            .put::<StaleNodeIndexSchema>(
+                &StaleNodeIndex {
+                    stale_since_version: num_txns,
+                    node_key: NodeKey::new_empty_path(0),
+                },
+                &(),
+            )
+            .unwrap();
+        batch.put::<StaleNodeIndexSchema>(&orphan_index, &()).unwrap();
+        db.db.write_schemas(batch).unwrap();
+
+        let report = inspector
+            .check_consistency(ConsistencyCheck::TransactionByHash)
+            .unwrap();
+        prop_assert_eq!(report.inconsistencies.len(), 1);
+        prop_assert_eq!(
+            &report.inconsistencies[0].key,
+            &format!("{:?}", txns_to_commit[0].transaction().hash())
+        );
+
+        let report = inspector
+            .check_consistency(ConsistencyCheck::EventAccumulator)
+            .unwrap();
+        prop_assert_eq!(report.num_entries_checked, num_txns as usize);
+        prop_assert_eq!(report.is_consistent(), version_with_events.is_none());
+        for inconsistency in &report.inconsistencies {
+            prop_assert_eq!(
+                &inconsistency.key,
+                &format!("{:?}", version_with_events.unwrap() as Version)
+            );
+        }
+
+        // Both indices are after the latest version, and one of them is an orphan.
+        let report = inspector
+            .check_consistency(ConsistencyCheck::StaleNodeIndex)
+            .unwrap();
+        prop_assert_eq!(report.inconsistencies.len(), 3);
+        prop_assert_eq!(
+            &report.inconsistencies[2].key,
+            &format!("{:?}", orphan_index)
+        );
+    }
+}
@@ -3,13 +3,11 @@
 
 use super::*;
 use crate::{change_set::ChangeSet, DiemDB};
-use diem_temppath::TempPath;
 use diem_types::{
     proptest_types::{AccountInfoUniverse, LedgerInfoWithSignaturesGen},
     transaction::Version,
 };
 use proptest::{collection::vec, prelude::*};
-use std::path::Path;
 
 fn arb_ledger_infos_with_sigs() -> impl Strategy<Value = Vec<LedgerInfoWithSignatures>> {
     (
@@ -71,8 +69,7 @@ proptest! {
                 )
             })
     ) {
-        let tmp_dir = TempPath::new();
-        let db = set_up(&tmp_dir, &ledger_infos_with_sigs);
+        let db = set_up(&ledger_infos_with_sigs);
 
         let actual = db
             .ledger_store
@@ -103,8 +100,7 @@ proptest! {
                 )
             })
     ) {
-        let tmp_dir = TempPath::new();
-        let db = set_up(&tmp_dir, &ledger_infos_with_sigs);
+        let db = set_up(&ledger_infos_with_sigs);
 
         let actual = db.ledger_store.get_epoch(version).unwrap();
         // Find the first LI that is at or after version.
@@ -118,8 +114,7 @@ proptest! {
 
     #[test]
     fn test_get_epoch_state(ledger_infos_with_sigs in arb_ledger_infos_with_sigs()) {
-        let tmp_dir = TempPath::new();
-        let db = set_up(&tmp_dir, &ledger_infos_with_sigs);
+        let db = set_up(&ledger_infos_with_sigs);
 
         assert!(db.ledger_store.get_epoch_state(0).is_err());
 
@@ -146,8 +141,7 @@ proptest! {
                 )
             })
     ) {
-        let tmp_dir = TempPath::new();
-        let db = set_up(&tmp_dir, &ledger_infos_with_sigs);
+        let db = set_up(&ledger_infos_with_sigs);
         put_transaction_infos(&db, &txn_infos);
 
         let startup_info = db.ledger_store.get_startup_info().unwrap().unwrap();
@@ -176,8 +170,8 @@ proptest! {
     }
 }
 
-fn set_up(path: &impl AsRef<Path>, ledger_infos_with_sigs: &[LedgerInfoWithSignatures]) -> DiemDB {
-    let db = DiemDB::new_for_test(path);
+fn set_up(ledger_infos_with_sigs: &[LedgerInfoWithSignatures]) -> DiemDB {
+    let db = DiemDB::new_for_test();
     let store = &db.ledger_store;
 
     // Write LIs to DB.
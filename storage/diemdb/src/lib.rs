@@ -17,6 +17,7 @@ pub mod test_helper;
 
 pub mod backup;
 pub mod errors;
+pub mod inspector;
 pub mod metrics;
 pub mod schema;
 
@@ -25,6 +26,7 @@ mod event_store;
 mod ledger_counters;
 mod ledger_store;
 mod pruner;
+mod resource_store;
 mod state_store;
 mod system_store;
 mod transaction_store;
@@ -37,10 +39,14 @@ mod diemdb_test;
 pub use diemdb_test::test_save_blocks_impl;
 
 use crate::{
-    backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
+    backup::{
+        backup_handler::BackupHandler,
+        restore_handler::{self, RestoreHandler},
+    },
     change_set::{ChangeSet, SealedChangeSet},
     errors::DiemDbError,
     event_store::EventStore,
+    inspector::DbInspector,
     ledger_counters::LedgerCounters,
     ledger_store::LedgerStore,
     metrics::{
@@ -49,7 +55,8 @@ use crate::{
         DIEM_STORAGE_LEDGER_VERSION, DIEM_STORAGE_NEXT_BLOCK_EPOCH,
         DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_ROCKSDB_PROPERTIES,
     },
-    pruner::Pruner,
+    pruner::{LedgerPruner, Pruner},
+    resource_store::ResourceStore,
     schema::*,
     state_store::StateStore,
     system_store::SystemStore,
@@ -58,11 +65,15 @@ use crate::{
 use anyhow::{ensure, format_err, Result};
 use diem_config::config::RocksdbConfig;
 use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
+use diem_jellyfish_merkle::{iterator::JellyfishMerkleIterator, restore::JellyfishMerkleRestore};
 use diem_logger::prelude::*;
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
+    account_state_blob::{
+        default_protocol::AccountStateWithProof, AccountStateBlob, AccountStatesChunkWithProof,
+    },
     contract_event::{
         default_protocol::{EventByVersionWithProof, EventWithProof},
         ContractEvent,
@@ -76,6 +87,7 @@ use diem_types::{
     },
     protocol_spec::DpnProto,
     state_proof::StateProof,
+    state_value::StateValue,
     transaction::{
         default_protocol::{
             AccountTransactionsWithProof, TransactionListWithProof, TransactionOutputListWithProof,
@@ -101,7 +113,9 @@ use std::{
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use storage_interface::{DbReader, DbWriter, MoveDbReader, Order, StartupInfo, TreeState};
+use storage_interface::{
+    DbReader, DbWriter, MoveDbReader, Order, StartupInfo, StateSnapshotReceiver, TreeState,
+};
 
 const MAX_LIMIT: u64 = 1000;
 
@@ -220,10 +234,13 @@ pub struct DiemDB {
     ledger_store: Arc<LedgerStore>,
     transaction_store: Arc<TransactionStore>,
     state_store: Arc<StateStore>,
+    /// Only exists if the state is also stored by resource (see [`ResourceStore`]).
+    resource_store: Option<Arc<ResourceStore>>,
     event_store: Arc<EventStore>,
     system_store: SystemStore,
-    rocksdb_property_reporter: RocksdbPropertyReporter,
+    rocksdb_property_reporter: Option<RocksdbPropertyReporter>,
     pruner: Option<Pruner>,
+    ledger_pruner: Option<LedgerPruner>,
 }
 
 impl DiemDB {
@@ -237,7 +254,9 @@ impl DiemDB {
             EVENT_CF_NAME,
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
+            RESOURCE_MERKLE_NODE_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
+            STALE_RESOURCE_NODE_INDEX_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
@@ -247,18 +266,50 @@ impl DiemDB {
         ]
     }
 
-    fn new_with_db(db: DB, prune_window: Option<u64>, account_count_migration: bool) -> Self {
+    fn new_with_db(
+        db: DB,
+        prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
+        account_count_migration: bool,
+        resource_granular_state: bool,
+        state_value_cache_size: usize,
+        report_rocksdb_properties: bool,
+    ) -> Self {
         let db = Arc::new(db);
+        let state_store = Arc::new(StateStore::new(
+            Arc::clone(&db),
+            account_count_migration,
+            state_value_cache_size,
+        ));
 
         DiemDB {
             db: Arc::clone(&db),
             event_store: Arc::new(EventStore::new(Arc::clone(&db))),
             ledger_store: Arc::new(LedgerStore::new(Arc::clone(&db))),
-            state_store: Arc::new(StateStore::new(Arc::clone(&db), account_count_migration)),
-            transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db))),
+            state_store: Arc::clone(&state_store),
+            resource_store: if resource_granular_state {
+                Some(Arc::new(ResourceStore::new(Arc::clone(&db))))
+            } else {
+                None
+            },
+            transaction_store: Arc::new(TransactionStore::new(
+                Arc::clone(&db),
+                ledger_prune_window.is_some(),
+            )),
             system_store: SystemStore::new(Arc::clone(&db)),
-            rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
-            pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
+            rocksdb_property_reporter: if report_rocksdb_properties {
+                Some(RocksdbPropertyReporter::new(Arc::clone(&db)))
+            } else {
+                None
+            },
+            pruner: prune_window.map(|n| {
+                Pruner::new(
+                    Arc::clone(&db),
+                    n,
+                    Arc::clone(state_store.state_value_cache()),
+                )
+            }),
+            ledger_pruner: ledger_prune_window.map(|n| LedgerPruner::new(Arc::clone(&db), n)),
         }
     }
 
@@ -266,13 +317,20 @@ impl DiemDB {
         db_root_path: P,
         readonly: bool,
         prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
         rocksdb_config: RocksdbConfig,
         account_count_migration: bool, // ignored when opening readonly
+        resource_granular_state: bool,
+        state_value_cache_size: usize,
     ) -> Result<Self> {
         ensure!(
             prune_window.is_none() || !readonly,
             "Do not set prune_window when opening readonly.",
         );
+        ensure!(
+            ledger_prune_window.is_none() || !readonly,
+            "Do not set ledger_prune_window when opening readonly.",
+        );
 
         let path = db_root_path.as_ref().join("diemdb");
         let instant = Instant::now();
@@ -303,7 +361,20 @@ impl DiemDB {
             )
         };
 
-        let ret = Self::new_with_db(db, prune_window, account_count_migration);
+        let ret = Self::new_with_db(
+            db,
+            prune_window,
+            ledger_prune_window,
+            account_count_migration,
+            resource_granular_state,
+            state_value_cache_size,
+            true, /* report_rocksdb_properties */
+        );
+        ret.state_store.reset_cache(
+            ret.ledger_store
+                .get_latest_transaction_info_option()?
+                .map(|(version, _txn_info)| version),
+        );
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -331,22 +402,29 @@ impl DiemDB {
                 Self::column_families(),
                 &rocksdb_opts,
             )?,
-            None, // prune_window
-            true, // account_count_migration
+            None,  // prune_window
+            None,  // ledger_prune_window
+            true,  // account_count_migration
+            false, // resource_granular_state
+            0,     // state_value_cache_size: commits by the primary are not observed
+            true,  // report_rocksdb_properties
         ))
     }
 
-    /// This opens db in non-readonly mode, without the pruner.
+    /// This creates an empty db held entirely in memory, without the pruner. Nothing is
+    /// persisted, so tests that reopen a db must use [`DiemDB::open`].
     #[cfg(any(test, feature = "fuzzing"))]
-    pub fn new_for_test<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
-        Self::open(
-            db_root_path,
-            false, /* readonly */
-            None,  /* pruner */
-            RocksdbConfig::default(),
-            true, /* account_count_migration */
+    pub fn new_for_test() -> Self {
+        Self::new_with_db(
+            DB::open_in_memory("diemdb", Self::column_families())
+                .expect("Unable to create in-memory DiemDB"),
+            None,        // prune_window
+            None,        // ledger_prune_window
+            true,        // account_count_migration
+            false,       // resource_granular_state
+            1024 * 1024, // state_value_cache_size
+            false,       // report_rocksdb_properties: not supported in memory
         )
-        .expect("Unable to open DiemDB")
     }
 
     /// This force the db to update rocksdb properties immediately.
@@ -422,6 +500,7 @@ impl DiemDB {
         ledger_version: Version,
         fetch_events: bool,
     ) -> Result<TransactionWithProof> {
+        self.error_if_ledger_pruned("Transaction", version)?;
         let proof = self
             .ledger_store
             .get_transaction_info_with_proof(version, ledger_version)?;
@@ -454,12 +533,58 @@ impl DiemDB {
         )
     }
 
+    /// Gets an instance of `DbInspector` for browsing the DB at the schema level.
+    pub fn get_inspector(&self) -> DbInspector {
+        DbInspector::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.ledger_store),
+            Arc::clone(&self.event_store),
+        )
+    }
+
     /// Creates new physical DB checkpoint in directory specified by `path`.
     pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         self.db.create_checkpoint(path)
     }
 
     // ================================== Private APIs ==================================
+    /// If the state is also stored by resource, writes all resources in the account state snapshot
+    /// at `version` (which must have been saved already) to the DB.
+    fn save_resource_state_snapshot(&self, version: Version) -> Result<()> {
+        if let Some(resource_store) = &self.resource_store {
+            resource_store.put_account_state_snapshot(version, || {
+                Ok(JellyfishMerkleIterator::new(
+                    Arc::clone(&self.state_store),
+                    version,
+                    HashValue::zero(),
+                )?
+                .map(|result| result.map(|(_account_key, blob)| blob)))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Gets the value at `access_path` at the latest version, reading only that resource (or
+    /// module) if the state is also stored by resource.
+    fn get_latest_value_by_access_path(&self, access_path: AccessPath) -> Result<Option<Vec<u8>>> {
+        let version = self.get_latest_version()?;
+        if let Some(resource_store) = &self.resource_store {
+            let (value, _proof) =
+                resource_store.get_state_value_with_proof_by_version(&access_path, version)?;
+            return Ok(value.and_then(|value| value.maybe_bytes));
+        }
+
+        let account_state_blob = self
+            .state_store
+            .get_account_state_by_version(access_path.address, version)?;
+        if let Some(account_state_blob) = account_state_blob {
+            let account_state = AccountState::try_from(&account_state_blob)?;
+            Ok(account_state.get(&access_path.path).cloned())
+        } else {
+            Ok(None)
+        }
+    }
+
     fn get_events_with_proof_by_event_key(
         &self,
         event_key: &EventKey,
@@ -509,6 +634,7 @@ impl DiemDB {
         let mut events_with_proof = event_indices
             .into_iter()
             .map(|(seq, ver, idx)| {
+                self.error_if_ledger_pruned("Event", ver)?;
                 let (event, event_proof) = self
                     .event_store
                     .get_event_with_proof_by_version_and_index(ver, idx)?;
@@ -581,6 +707,18 @@ impl DiemDB {
             &mut cs,
         )?;
 
+        // Resource updates, if the state is also stored by resource.
+        if let Some(resource_store) = &self.resource_store {
+            resource_store.put_write_sets(
+                txns_to_commit
+                    .iter()
+                    .map(|txn_to_commit| txn_to_commit.write_set())
+                    .collect(),
+                first_version,
+                &mut cs,
+            )?;
+        }
+
         // Event updates. Gather event accumulator root hashes.
         let event_root_hashes = zip_eq(first_version..=last_version, txns_to_commit)
             .map(|(ver, txn_to_commit)| {
@@ -636,6 +774,46 @@ impl DiemDB {
         if let Some(pruner) = self.pruner.as_ref() {
             pruner.wake(latest_version)
         }
+        if let Some(ledger_pruner) = self.ledger_pruner.as_ref() {
+            ledger_pruner.wake(latest_version)
+        }
+    }
+
+    /// Returns the version before which transactions, their infos, write sets and events have
+    /// been pruned.
+    fn get_least_readable_ledger_version(&self) -> Version {
+        self.ledger_pruner
+            .as_ref()
+            .map_or(0, LedgerPruner::least_readable_version)
+    }
+
+    /// Returns the version before which the state (i.e., the account state tree) has been
+    /// pruned.
+    fn get_least_readable_state_version(&self) -> Version {
+        self.pruner
+            .as_ref()
+            .map_or(0, Pruner::least_readable_version)
+    }
+
+    /// Returns the version following the latest transaction, i.e., the first version of which
+    /// nothing is stored yet.
+    fn get_next_version(&self) -> Result<Version> {
+        Ok(self
+            .ledger_store
+            .get_latest_transaction_info_option()?
+            .map_or(0, |(version, _txn_info)| version + 1))
+    }
+
+    fn error_if_ledger_pruned(&self, data_type: &str, version: Version) -> Result<()> {
+        let least_readable_version = self.get_least_readable_ledger_version();
+        if version < least_readable_version {
+            return Err(DiemDbError::Pruned(format!(
+                "{} at version {} (the least readable ledger version is {})",
+                data_type, version, least_readable_version
+            ))
+            .into());
+        }
+        Ok(())
     }
 }
 
@@ -659,10 +837,8 @@ impl DbReader<DpnProto> for DiemDB {
         gauged_api("get_latest_account_state", || {
             let ledger_info_with_sigs = self.ledger_store.get_latest_ledger_info()?;
             let version = ledger_info_with_sigs.ledger_info().version();
-            let (blob, _proof) = self
-                .state_store
-                .get_account_state_with_proof_by_version(address, version)?;
-            Ok(blob)
+            self.state_store
+                .get_account_state_by_version(address, version)
         })
     }
 
@@ -758,6 +934,7 @@ impl DbReader<DpnProto> for DiemDB {
             if start_version > ledger_version || limit == 0 {
                 return Ok(TransactionListWithProof::new_empty());
             }
+            self.error_if_ledger_pruned("Transaction", start_version)?;
 
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
 
@@ -809,12 +986,14 @@ impl DbReader<DpnProto> for DiemDB {
             if start_version > ledger_version || limit == 0 {
                 return Ok(TransactionOutputListWithProof::new_empty());
             }
+            self.error_if_ledger_pruned("Transaction output", start_version)?;
 
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
 
-            let (txn_infos, txn_outputs) = (start_version..start_version + limit)
+            let (txn_infos, txns_and_outputs) = (start_version..start_version + limit)
                 .map(|version| {
                     let txn_info = self.ledger_store.get_transaction_info(version)?;
+                    let txn = self.transaction_store.get_transaction(version)?;
                     let events = self.event_store.get_events_by_version(version)?;
                     let write_set = self.transaction_store.get_write_set(version)?;
                     let txn_output = TransactionOutput::new(
@@ -823,7 +1002,7 @@ impl DbReader<DpnProto> for DiemDB {
                         txn_info.gas_used(),
                         txn_info.status().clone().into(),
                     );
-                    Ok((txn_info, txn_output))
+                    Ok((txn_info, (txn, txn_output)))
                 })
                 .collect::<Result<Vec<_>>>()?
                 .into_iter()
@@ -838,7 +1017,7 @@ impl DbReader<DpnProto> for DiemDB {
             );
 
             Ok(TransactionOutputListWithProof::new(
-                txn_outputs,
+                txns_and_outputs,
                 Some(start_version),
                 proof,
             ))
@@ -971,6 +1150,7 @@ impl DbReader<DpnProto> for DiemDB {
                 );
             }
 
+            self.error_if_ledger_pruned("Transaction info", version)?;
             let txn_info_with_proof = self
                 .ledger_store
                 .get_transaction_info_with_proof(version, ledger_version)?;
@@ -1003,6 +1183,34 @@ impl DbReader<DpnProto> for DiemDB {
         })
     }
 
+    fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountStateBlob>> {
+        gauged_api("get_account_state_by_version", || {
+            self.state_store
+                .get_account_state_by_version(address, version)
+        })
+    }
+
+    fn is_state_stored_by_resource(&self) -> bool {
+        self.resource_store.is_some()
+    }
+
+    fn get_state_value_with_proof_by_version(
+        &self,
+        access_path: &AccessPath,
+        version: Version,
+    ) -> Result<(Option<StateValue>, SparseMerkleProof<StateValue>)> {
+        gauged_api("get_state_value_with_proof_by_version", || {
+            self.resource_store
+                .as_ref()
+                .ok_or_else(|| format_err!("The state is not stored by resource."))?
+                .get_state_value_with_proof_by_version(access_path, version)
+        })
+    }
+
     fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
         gauged_api("get_latest_state_root", || {
             let (version, txn_info) = self.ledger_store.get_latest_transaction_info()?;
@@ -1038,6 +1246,7 @@ impl DbReader<DpnProto> for DiemDB {
 
     fn get_block_timestamp(&self, version: u64) -> Result<u64> {
         gauged_api("get_block_timestamp", || {
+            self.error_if_ledger_pruned("Block metadata", version)?;
             let ts = match self.transaction_store.get_block_metadata(version)? {
                 Some((_v, block_meta)) => block_meta.into_inner().1,
                 // genesis timestamp is 0
@@ -1159,22 +1368,90 @@ impl DbReader<DpnProto> for DiemDB {
                 .get_consistency_proof(client_known_version, ledger_version)
         })
     }
+
+    fn get_first_txn_version(&self) -> Result<Version> {
+        gauged_api("get_first_txn_version", || {
+            // A DB bootstrapped from a state snapshot or restored from a backup doesn't hold the
+            // transactions preceding it, whether or not the pruner is enabled.
+            let first_stored_version = match self.transaction_store.get_first_txn_version()? {
+                Some(version) => version,
+                None => self.get_next_version()?,
+            };
+            Ok(std::cmp::max(
+                first_stored_version,
+                self.get_least_readable_ledger_version(),
+            ))
+        })
+    }
+
+    fn get_first_state_version(&self) -> Result<Version> {
+        gauged_api("get_first_state_version", || {
+            let least_readable_version = self.get_least_readable_state_version();
+            match self
+                .state_store
+                .get_first_root_version(least_readable_version)?
+            {
+                Some(version) => Ok(version),
+                None => self.get_next_version(),
+            }
+        })
+    }
+
+    fn get_account_count(&self, version: Version) -> Result<usize> {
+        gauged_api("get_account_count", || {
+            self.state_store
+                .get_account_count(version)?
+                .ok_or_else(|| format_err!("Account count not found at version {}", version))
+        })
+    }
+
+    fn get_account_chunk_with_proof(
+        &self,
+        version: Version,
+        first_index: usize,
+        chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        gauged_api("get_account_chunk_with_proof", || {
+            error_if_too_many_requested(chunk_size as u64, MAX_LIMIT)?;
+
+            let account_blobs: Vec<(HashValue, AccountStateBlob)> =
+                JellyfishMerkleIterator::new_by_index(
+                    Arc::clone(&self.state_store),
+                    version,
+                    first_index,
+                )?
+                .take(chunk_size)
+                .collect::<Result<_>>()?;
+            ensure!(
+                !account_blobs.is_empty(),
+                "No accounts found at version {} starting from index {}",
+                version,
+                first_index
+            );
+
+            let first_key = account_blobs.first().expect("checked to exist").0;
+            let last_key = account_blobs.last().expect("checked to exist").0;
+            let proof = self
+                .state_store
+                .get_account_state_range_proof(last_key, version)?;
+
+            Ok(AccountStatesChunkWithProof {
+                first_index: first_index as u64,
+                last_index: (first_index + account_blobs.len() - 1) as u64,
+                first_key,
+                last_key,
+                account_blobs,
+                proof,
+            })
+        })
+    }
 }
 
 impl ModuleResolver for DiemDB {
     type Error = anyhow::Error;
 
     fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>> {
-        let (account_state_with_proof, _) = self.get_account_state_with_proof_by_version(
-            *module_id.address(),
-            self.get_latest_version()?,
-        )?;
-        if let Some(account_state_blob) = account_state_with_proof {
-            let account_state = AccountState::try_from(&account_state_blob)?;
-            Ok(account_state.get(&module_id.access_vector()).cloned())
-        } else {
-            Ok(None)
-        }
+        self.get_latest_value_by_access_path(AccessPath::from(module_id))
     }
 }
 
@@ -1182,14 +1459,7 @@ impl ResourceResolver for DiemDB {
     type Error = anyhow::Error;
 
     fn get_resource(&self, address: &AccountAddress, tag: &StructTag) -> Result<Option<Vec<u8>>> {
-        let (account_state_with_proof, _) =
-            self.get_account_state_with_proof_by_version(*address, self.get_latest_version()?)?;
-        if let Some(account_state_blob) = account_state_with_proof {
-            let account_state = AccountState::try_from(&account_state_blob)?;
-            Ok(account_state.get(&tag.access_vector()).cloned())
-        } else {
-            Ok(None)
-        }
+        self.get_latest_value_by_access_path(AccessPath::new(*address, tag.access_vector()))
     }
 }
 
@@ -1254,6 +1524,12 @@ impl DbWriter<DpnProto> for DiemDB {
                     .start_timer();
                 self.commit(sealed_cs)?;
             }
+            self.state_store.update_cache(
+                first_version,
+                txns_to_commit
+                    .iter()
+                    .map(|txn_to_commit| txn_to_commit.account_states()),
+            );
 
             // Once everything is successfully persisted, update the latest in-memory ledger info.
             if let Some(x) = ledger_info_with_sigs {
@@ -1286,6 +1562,85 @@ impl DbWriter<DpnProto> for DiemDB {
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            let receiver: Box<dyn StateSnapshotReceiver<AccountStateBlob>> =
+                Box::new(JellyfishMerkleRestore::new_overwrite(
+                    Arc::clone(&self.state_store),
+                    version,
+                    expected_root_hash,
+                    true, /* account_count_migration */
+                )?);
+            Ok(receiver)
+        })
+    }
+
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        output_with_proof: TransactionOutputListWithProof,
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            // The output list should only contain the transaction at the snapshot version.
+            ensure!(
+                output_with_proof.transactions_and_outputs.len() == 1,
+                "Number of transaction outputs should == 1, but got: {}",
+                output_with_proof.transactions_and_outputs.len()
+            );
+
+            // Verify the output against the ledger info at the snapshot version.
+            let ledger_info = ledger_infos
+                .last()
+                .ok_or_else(|| format_err!("No ledger infos given to finalize the snapshot!"))?;
+            ensure!(
+                ledger_info.ledger_info().version() == version,
+                "The last ledger info version ({}) doesn't match the snapshot version ({})",
+                ledger_info.ledger_info().version(),
+                version
+            );
+            output_with_proof.verify(ledger_info.ledger_info(), Some(version))?;
+
+            // Save the frozen subtrees of the transaction accumulator preceding the snapshot.
+            restore_handler::confirm_or_save_frozen_subtrees(
+                &self.db,
+                version,
+                output_with_proof
+                    .proof
+                    .ledger_info_to_transaction_infos_proof
+                    .left_siblings(),
+            )?;
+
+            // Save the transaction, write set, events and info at the snapshot version.
+            let mut cs = ChangeSet::new();
+            let (transaction, output) = &output_with_proof.transactions_and_outputs[0];
+            self.transaction_store
+                .put_transaction(version, transaction, &mut cs)?;
+            self.transaction_store
+                .put_write_set(version, output.write_set(), &mut cs)?;
+            self.event_store
+                .put_events(version, output.events(), &mut cs)?;
+            self.ledger_store.put_transaction_infos(
+                version,
+                &output_with_proof.proof.transaction_infos,
+                &mut cs,
+            )?;
+            self.db.write_schemas(cs.batch)?;
+            self.save_resource_state_snapshot(version)?;
+            self.state_store.reset_cache(Some(version));
+
+            // Save the ledger infos (this updates the latest ledger info).
+            restore_handler::save_ledger_infos(&self.db, &self.ledger_store, ledger_infos)?;
+            DIEM_STORAGE_LEDGER_VERSION.set(version as i64);
+
+            Ok(())
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_metrics::{
-    register_histogram_vec, register_int_counter, register_int_gauge, register_int_gauge_vec,
-    HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -71,6 +71,58 @@ pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION: Lazy<IntGauge> = La
     .unwrap()
 });
 
+pub static DIEM_STORAGE_LEDGER_PRUNE_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_ledger_prune_window",
+        "Diem storage ledger prune window"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_LEDGER_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_pruner_least_readable_ledger_version",
+        "Diem storage pruner least readable ledger version"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_STATE_VALUE_CACHE_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "diem_storage_state_value_cache_requests",
+        // metric description
+        "Diem storage state value cache lookups",
+        // metric labels (dimensions)
+        &["type", "result"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_STATE_VALUE_CACHE_EVICTIONS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_storage_state_value_cache_evictions",
+        "Number of accounts evicted from the state value cache to keep it within its size limit."
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_STATE_VALUE_CACHE_SIZE_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_state_value_cache_size_bytes",
+        "Total size of the blobs and proofs in the state value cache."
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_STATE_VALUE_CACHE_NUM_ACCOUNTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_state_value_cache_num_accounts",
+        "Number of accounts in the state value cache."
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -0,0 +1,191 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    errors::DiemDbError,
+    schema::{
+        event::EventSchema, transaction::TransactionSchema,
+        transaction_by_hash::TransactionByHashSchema, transaction_info::TransactionInfoSchema,
+        write_set::WriteSetSchema,
+    },
+    test_helper::arb_blocks_to_commit,
+    DiemDB,
+};
+use diem_config::config::RocksdbConfig;
+use diem_crypto::hash::CryptoHash;
+use diem_temppath::TempPath;
+use diem_types::transaction::{TransactionToCommit, Version};
+use proptest::prelude::*;
+use schemadb::ReadOptions;
+use storage_interface::{DbReader, DbWriter, Order};
+
+fn has_events(db: &DiemDB, version: Version) -> bool {
+    let mut iter = db.db.iter::<EventSchema>(ReadOptions::default()).unwrap();
+    iter.seek(&version).unwrap();
+    matches!(iter.next().transpose().unwrap(), Some(((v, _), _)) if v == version)
+}
+
+fn verify_pruned(db: &DiemDB, txns_to_commit: &[TransactionToCommit], version: Version) {
+    let txn_to_commit = &txns_to_commit[version as usize];
+    assert!(db.db.get::<TransactionSchema>(&version).unwrap().is_none());
+    assert!(db
+        .db
+        .get::<TransactionInfoSchema>(&version)
+        .unwrap()
+        .is_none());
+    assert!(db.db.get::<WriteSetSchema>(&version).unwrap().is_none());
+    assert!(db
+        .db
+        .get::<TransactionByHashSchema>(&txn_to_commit.transaction().hash())
+        .unwrap()
+        .is_none());
+    assert!(!has_events(db, version));
+}
+
+fn is_pruned_error(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<DiemDbError>(),
+        Some(DiemDbError::Pruned(_))
+    )
+}
+
+/// Verifies that looking up the transaction of `version` by its sender and sequence number, as
+/// well as its events by key and sequence number, fails as pruned whenever the pruning can be
+/// told from what is left, i.e., a later transaction of the sender or event of the key exists.
+fn verify_lookups_pruned(
+    db: &DiemDB,
+    txns_to_commit: &[TransactionToCommit],
+    version: Version,
+    least_readable_version: Version,
+    latest_version: Version,
+) {
+    let readable_txns = &txns_to_commit[least_readable_version as usize..];
+
+    let txn = txns_to_commit[version as usize]
+        .transaction()
+        .as_signed_user_txn()
+        .unwrap();
+    let sender_has_readable_txn = readable_txns
+        .iter()
+        .any(|t| t.transaction().as_signed_user_txn().unwrap().sender() == txn.sender());
+    let res = db.get_account_transaction(txn.sender(), txn.sequence_number(), true, latest_version);
+    if sender_has_readable_txn {
+        assert!(is_pruned_error(&res.unwrap_err()));
+    } else {
+        assert!(res.unwrap().is_none());
+    }
+
+    for event in txns_to_commit[version as usize].events() {
+        let key_has_readable_event = readable_txns
+            .iter()
+            .flat_map(TransactionToCommit::events)
+            .any(|e| e.key() == event.key());
+        let res = db.get_events(event.key(), event.sequence_number(), Order::Ascending, 1);
+        if key_has_readable_event {
+            assert!(is_pruned_error(&res.unwrap_err()));
+        } else {
+            assert!(res.unwrap().is_empty());
+        }
+    }
+}
+
+fn verify_readable(db: &DiemDB, txns_to_commit: &[TransactionToCommit], version: Version) {
+    let txn_to_commit = &txns_to_commit[version as usize];
+    assert_eq!(
+        db.db.get::<TransactionSchema>(&version).unwrap().as_ref(),
+        Some(txn_to_commit.transaction())
+    );
+    assert!(db
+        .db
+        .get::<TransactionInfoSchema>(&version)
+        .unwrap()
+        .is_some());
+    assert!(db.db.get::<WriteSetSchema>(&version).unwrap().is_some());
+    assert_eq!(
+        db.db
+            .get::<TransactionByHashSchema>(&txn_to_commit.transaction().hash())
+            .unwrap(),
+        Some(version)
+    );
+    assert_eq!(has_events(db, version), !txn_to_commit.events().is_empty());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_ledger_pruner(input in arb_blocks_to_commit()) {
+        const HISTORICAL_VERSIONS_TO_KEEP: u64 = 2;
+
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::open(
+            &tmp_dir,
+            false, /* readonly */
+            None,  /* pruner */
+            Some(HISTORICAL_VERSIONS_TO_KEEP),
+            RocksdbConfig::default(),
+            true, /* account_count_migration */
+            false, /* resource_granular_state */
+            0, /* state_value_cache_size */
+        )
+        .unwrap();
+
+        let mut all_txns_to_commit = vec![];
+        for (txns_to_commit, ledger_info_with_sigs) in &input {
+            db.save_transactions(
+                txns_to_commit,
+                all_txns_to_commit.len() as Version, /* first_version */
+                Some(ledger_info_with_sigs),
+            )
+            .unwrap();
+            all_txns_to_commit.extend(txns_to_commit.iter().cloned());
+        }
+
+        let latest_version = all_txns_to_commit.len() as Version - 1;
+        let ledger_pruner = db.ledger_pruner.as_ref().unwrap();
+        ledger_pruner.wake_and_wait(latest_version).unwrap();
+        let least_readable_version = latest_version.saturating_sub(HISTORICAL_VERSIONS_TO_KEEP);
+        prop_assert_eq!(ledger_pruner.least_readable_version(), least_readable_version);
+        prop_assert_eq!(db.get_first_txn_version().unwrap(), least_readable_version);
+
+        for version in 0..least_readable_version {
+            verify_pruned(&db, &all_txns_to_commit, version);
+            let err = db
+                .get_transaction_by_version(version, latest_version, true)
+                .unwrap_err();
+            prop_assert!(is_pruned_error(&err));
+            verify_lookups_pruned(
+                &db,
+                &all_txns_to_commit,
+                version,
+                least_readable_version,
+                latest_version,
+            );
+        }
+        for version in least_readable_version..=latest_version {
+            verify_readable(&db, &all_txns_to_commit, version);
+            let txn_with_proof = db
+                .get_transaction_by_version(version, latest_version, true)
+                .unwrap();
+            prop_assert_eq!(
+                &txn_with_proof.transaction,
+                all_txns_to_commit[version as usize].transaction()
+            );
+        }
+
+        let txn_list = db
+            .get_transactions(least_readable_version, 100, latest_version, true)
+            .unwrap();
+        prop_assert_eq!(
+            txn_list.transactions.len() as u64,
+            latest_version - least_readable_version + 1
+        );
+        if least_readable_version > 0 {
+            prop_assert!(db.get_transactions(0, 100, latest_version, true).is_err());
+            // There are only user transactions, so the search for the block metadata runs into
+            // the pruned versions.
+            let err = db.get_block_timestamp(latest_version).unwrap_err();
+            prop_assert!(is_pruned_error(&err));
+        }
+    }
+}
@@ -4,14 +4,21 @@
 //! This module provides `Pruner` which manages a thread pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
 
+mod ledger_pruner;
+
+pub(crate) use ledger_pruner::LedgerPruner;
+
 use crate::{
     metrics::{
         DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION,
         DIEM_STORAGE_PRUNE_WINDOW,
     },
     schema::{
-        jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+        resource_merkle_node::ResourceMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        stale_resource_node_index::StaleResourceNodeIndexSchema,
     },
+    state_store::state_value_cache::StateValueCache,
 };
 use anyhow::Result;
 use diem_infallible::Mutex;
@@ -45,15 +52,19 @@ pub(crate) struct Pruner {
     worker_thread: Option<JoinHandle<()>>,
     /// The sender side of the channel talking to the worker thread.
     command_sender: Mutex<Sender<Command>>,
-    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
-    /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
-    #[allow(dead_code)]
+    /// A way for the worker thread to inform the `Pruner` the pruning progress. If it sets this
+    /// atomic value to `V`, all versions before `V` can no longer be accessed.
     worker_progress: Arc<AtomicU64>,
 }
 
 impl Pruner {
-    /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, historical_versions_to_keep: u64) -> Self {
+    /// Creates a worker thread that waits on a channel for pruning commands. The pruned versions
+    /// are invalidated in `state_value_cache` as well.
+    pub fn new(
+        db: Arc<DB>,
+        historical_versions_to_keep: u64,
+        state_value_cache: Arc<StateValueCache>,
+    ) -> Self {
         let (command_sender, command_receiver) = channel();
 
         let worker_progress = Arc::new(AtomicU64::new(0));
@@ -62,7 +73,15 @@ impl Pruner {
         DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
         let worker_thread = std::thread::Builder::new()
             .name("diemdb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work())
+            .spawn(move || {
+                Worker::new(
+                    db,
+                    state_value_cache,
+                    command_receiver,
+                    worker_progress_clone,
+                )
+                .work()
+            })
             .expect("Creating pruner thread should succeed.");
 
         Self {
@@ -73,6 +92,11 @@ impl Pruner {
         }
     }
 
+    /// Returns the version before which the state has been pruned.
+    pub fn least_readable_version(&self) -> Version {
+        self.worker_progress.load(Ordering::Relaxed)
+    }
+
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
         if latest_version > self.historical_versions_to_keep {
@@ -99,7 +123,7 @@ impl Pruner {
             let end = Instant::now() + TIMEOUT;
 
             while Instant::now() < end {
-                if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version {
+                if self.least_readable_version() >= least_readable_version {
                     return Ok(());
                 }
                 sleep(Duration::from_millis(1));
@@ -131,6 +155,7 @@ enum Command {
 
 struct Worker {
     db: Arc<DB>,
+    state_value_cache: Arc<StateValueCache>,
     command_receiver: Receiver<Command>,
     target_least_readable_version: Version,
     /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
@@ -149,11 +174,13 @@ impl Worker {
 
     fn new(
         db: Arc<DB>,
+        state_value_cache: Arc<StateValueCache>,
         command_receiver: Receiver<Command>,
         least_readable_version: Arc<AtomicU64>,
     ) -> Self {
         Self {
             db,
+            state_value_cache,
             command_receiver,
             least_readable_version,
             target_least_readable_version: 0,
@@ -245,6 +272,7 @@ impl Worker {
 
     /// Log the progress.
     fn record_progress(&mut self, least_readable_version: Version) {
+        self.state_value_cache.prune(least_readable_version);
         self.least_readable_version
             .store(least_readable_version, Ordering::Relaxed);
         DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
@@ -311,6 +339,11 @@ impl Worker {
                     &self.index_min_nonpurged_version,
                     &new_min_non_purged_version, // end is exclusive
                 )?;
+                self.db
+                    .range_delete::<StaleResourceNodeIndexSchema, Version>(
+                        &self.index_min_nonpurged_version,
+                        &new_min_non_purged_version, // end is exclusive
+                    )?;
                 self.index_min_nonpurged_version = new_min_non_purged_version;
                 self.index_purged_at = now;
             }
@@ -403,10 +436,37 @@ pub fn prune_state(
         indices
             .into_iter()
             .try_for_each(|index| batch.delete::<JellyfishMerkleNodeSchema>(&index.node_key))?;
+        // The resource-granular state tree (if any) becomes unreadable at the same versions.
+        get_stale_resource_node_indices(&db, least_readable_version, new_least_readable_version)?
+            .into_iter()
+            .try_for_each(|index| batch.delete::<ResourceMerkleNodeSchema>(&index.node_key))?;
         db.write_schemas(batch)?;
         Ok(new_least_readable_version)
     }
 }
 
+/// Returns the indices of the resource-granular state tree nodes that became stale in
+/// [`least_readable_version`, `target_least_readable_version`].
+fn get_stale_resource_node_indices(
+    db: &DB,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+) -> Result<Vec<StaleNodeIndex>> {
+    let mut iter = db.iter::<StaleResourceNodeIndexSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+
+    let mut indices = vec![];
+    while let Some((index, _)) = iter.next().transpose()? {
+        if index.stale_since_version > target_least_readable_version {
+            break;
+        }
+        indices.push(index);
+    }
+    Ok(indices)
+}
+
 #[cfg(test)]
 mod test;
+
+#[cfg(test)]
+mod ledger_pruner_test;
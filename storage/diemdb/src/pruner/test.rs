@@ -4,7 +4,6 @@
 use super::*;
 use crate::{change_set::ChangeSet, state_store::StateStore, DiemDB};
 use diem_crypto::HashValue;
-use diem_temppath::TempPath;
 use diem_types::{account_address::AccountAddress, account_state_blob::AccountStateBlob};
 use std::collections::HashMap;
 
@@ -14,16 +13,13 @@ fn put_account_state_set(
     account_state_set: Vec<(AccountAddress, AccountStateBlob)>,
     version: Version,
 ) -> HashValue {
+    let account_state_set = account_state_set.into_iter().collect::<HashMap<_, _>>();
     let mut cs = ChangeSet::new();
     let root = state_store
-        .put_account_state_sets(
-            vec![account_state_set.into_iter().collect::<HashMap<_, _>>()],
-            None,
-            version,
-            &mut cs,
-        )
+        .put_account_state_sets(vec![account_state_set.clone()], None, version, &mut cs)
         .unwrap()[0];
     db.write_schemas(cs.batch).unwrap();
+    state_store.update_cache(version, vec![&account_state_set]);
 
     root
 }
@@ -47,10 +43,17 @@ fn test_pruner() {
     let value1 = AccountStateBlob::from(vec![0x02]);
     let value2 = AccountStateBlob::from(vec![0x03]);
 
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir).db;
-    let state_store = &StateStore::new(Arc::clone(&db), true /* account_count_migration */);
-    let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
+    let db = DiemDB::new_for_test().db;
+    let state_store = &StateStore::new(
+        Arc::clone(&db),
+        true,        /* account_count_migration */
+        1024 * 1024, /* state_value_cache_size */
+    );
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        0, /* historical_versions_to_keep */
+        Arc::clone(state_store.state_value_cache()),
+    );
 
     let _root0 = put_account_state_set(
         &db,
@@ -58,6 +61,8 @@ fn test_pruner() {
         vec![(address, value0.clone())],
         0, /* version */
     );
+    // Get the account cached since version 0, so that pruning has to invalidate the cache.
+    verify_state_in_store(state_store, address, Some(&value0), 0);
     let _root1 = put_account_state_set(
         &db,
         state_store,
@@ -81,6 +86,7 @@ fn test_pruner() {
     // Prune till version=1.
     {
         pruner.wake_and_wait(1 /* latest_version */).unwrap();
+        assert_eq!(pruner.least_readable_version(), 1);
         // root0 is gone.
         assert!(state_store
             .get_account_state_with_proof_by_version(address, 0)
@@ -92,6 +98,7 @@ fn test_pruner() {
     // Prune till version=2.
     {
         pruner.wake_and_wait(2 /* latest_version */).unwrap();
+        assert_eq!(pruner.least_readable_version(), 2);
         // root1 is gone.
         assert!(state_store
             .get_account_state_with_proof_by_version(address, 1)
@@ -108,9 +115,12 @@ fn test_worker_quit_eagerly() {
     let value1 = AccountStateBlob::from(vec![0x02]);
     let value2 = AccountStateBlob::from(vec![0x03]);
 
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir).db;
-    let state_store = &StateStore::new(Arc::clone(&db), true /* account_count_migration */);
+    let db = DiemDB::new_for_test().db;
+    let state_store = &StateStore::new(
+        Arc::clone(&db),
+        true, /* account_count_migration */
+        0,    /* state_value_cache_size */
+    );
 
     let _root0 = put_account_state_set(
         &db,
@@ -135,6 +145,7 @@ fn test_worker_quit_eagerly() {
         let (command_sender, command_receiver) = channel();
         let worker = Worker::new(
             Arc::clone(&db),
+            Arc::clone(state_store.state_value_cache()),
             command_receiver,
             Arc::new(AtomicU64::new(0)), /* progress */
         );
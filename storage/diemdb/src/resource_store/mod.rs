@@ -0,0 +1,221 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file defines resource store APIs that are related to the resource-granular state Merkle
+//! tree. Unlike the account state Merkle tree, where each leaf holds the whole state of an account,
+//! each resource or module (identified by an [`AccessPath`]) is a leaf of its own in this tree, so
+//! updating a resource doesn't rewrite the rest of the account, and a proof covers only the
+//! resource being read. The root of this tree is not part of the transaction info, so it isn't
+//! authenticated by the ledger.
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    change_set::ChangeSet,
+    schema::{
+        resource_merkle_node::ResourceMerkleNodeSchema,
+        stale_resource_node_index::StaleResourceNodeIndexSchema,
+    },
+    state_store::get_rightmost_leaf,
+};
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_jellyfish_merkle::{
+    node_type::NodeKey, restore::JellyfishMerkleRestore, JellyfishMerkleTree, TreeReader,
+    TreeUpdateBatch, TreeWriter,
+};
+use diem_types::{
+    access_path::AccessPath,
+    account_state::AccountState,
+    account_state_blob::AccountStateBlob,
+    proof::SparseMerkleProof,
+    state_value::StateValue,
+    transaction::Version,
+    write_set::{WriteOp, WriteSet},
+};
+use schemadb::{SchemaBatch, DB};
+use std::{collections::BTreeMap, convert::TryFrom, sync::Arc};
+
+/// The max number of leaves written to the DB at once when putting a state snapshot.
+const SNAPSHOT_CHUNK_SIZE: usize = 10_000;
+
+type LeafNode = diem_jellyfish_merkle::node_type::LeafNode<StateValue>;
+type Node = diem_jellyfish_merkle::node_type::Node<StateValue>;
+type NodeBatch = diem_jellyfish_merkle::NodeBatch<StateValue>;
+
+#[derive(Debug)]
+pub(crate) struct ResourceStore {
+    db: Arc<DB>,
+}
+
+impl ResourceStore {
+    pub fn new(db: Arc<DB>) -> Self {
+        Self { db }
+    }
+
+    /// Get the value of a single resource or module given its access path and the version. A
+    /// resource or module that doesn't exist at `version` comes with a proof of its non-existence.
+    ///
+    /// The proof is against the root of the resource-granular state tree, which isn't committed to
+    /// by the ledger, so it only shows the value is consistent with the tree stored in this DB and
+    /// doesn't authenticate it against a ledger info.
+    pub fn get_state_value_with_proof_by_version(
+        &self,
+        access_path: &AccessPath,
+        version: Version,
+    ) -> Result<(Option<StateValue>, SparseMerkleProof<StateValue>)> {
+        JellyfishMerkleTree::new_migration(self, true /* leaf_count_migration */)
+            .get_with_proof(access_path.hash(), version)
+    }
+
+    /// Put the updates in `write_sets` (one for each version, starting from `first_version`) to
+    /// `cs` and return the result root hashes for each write set.
+    pub fn put_write_sets(
+        &self,
+        write_sets: Vec<&WriteSet>,
+        first_version: Version,
+        cs: &mut ChangeSet,
+    ) -> Result<Vec<HashValue>> {
+        if let Some(previous_version) = first_version.checked_sub(1) {
+            ensure!(
+                self.get_root_hash_option(previous_version)?.is_some(),
+                "Resource-granular state at version {} not found. A DB without it needs to be \
+                 restored from a backup to turn on the resource-granular state storage.",
+                previous_version,
+            );
+        }
+
+        // A deleted resource or module is removed from the tree. An empty write set leaves the
+        // tree as it is, with the root carried over to the new version.
+        let value_sets = write_sets
+            .into_iter()
+            .map(|write_set| {
+                write_set
+                    .iter()
+                    .map(|(access_path, write_op)| {
+                        let value = match write_op {
+                            WriteOp::Value(bytes) => Some(StateValue::new(bytes.clone())),
+                            WriteOp::Deletion => None,
+                        };
+                        (access_path.hash(), value)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.put_value_sets(value_sets, first_version, cs)
+    }
+
+    /// Write the resources and modules of all accounts in a state snapshot at `version` to the
+    /// DB, as a tree of its own (which is the case when the snapshot is restored from a backup),
+    /// and return the root hash.
+    ///
+    /// The leaves don't come in the order of their keys, so `account_state_blobs` is scanned once
+    /// for each possible first nibble of the keys, and only the leaves under that nibble are
+    /// sorted in memory at a time, before they are streamed to the DB in chunks.
+    pub fn put_account_state_snapshot<I>(
+        self: &Arc<Self>,
+        version: Version,
+        account_state_blobs: impl Fn() -> Result<I>,
+    ) -> Result<HashValue>
+    where
+        I: Iterator<Item = Result<AccountStateBlob>>,
+    {
+        let mut restore = JellyfishMerkleRestore::new_unverified(
+            Arc::clone(self),
+            version,
+            true, /* leaf_count_migration */
+        );
+        let mut num_leaves = 0;
+        for nibble in 0..16u8 {
+            let mut leaves = BTreeMap::new();
+            for blob in account_state_blobs()? {
+                let account_state = AccountState::try_from(&blob?)?;
+                let address = account_state.get_account_address()?.ok_or_else(|| {
+                    format_err!("Account without an AccountResource found in the state snapshot.")
+                })?;
+                for (path, bytes) in account_state.iter() {
+                    let key = AccessPath::new(address, path.clone()).hash();
+                    if key.nibble(0) == nibble {
+                        leaves.insert(key, StateValue::new(bytes.clone()));
+                    }
+                }
+            }
+            num_leaves += leaves.len();
+
+            let mut leaves = leaves.into_iter().peekable();
+            while leaves.peek().is_some() {
+                restore
+                    .add_chunk_unverified(leaves.by_ref().take(SNAPSHOT_CHUNK_SIZE).collect())?;
+            }
+        }
+        ensure!(num_leaves > 0, "Empty state snapshot.");
+        restore.finish()?;
+
+        self.get_root_hash_option(version)?.ok_or_else(|| {
+            format_err!(
+                "Root of the resource state snapshot at version {} not found.",
+                version
+            )
+        })
+    }
+
+    pub fn get_root_hash_option(&self, version: Version) -> Result<Option<HashValue>> {
+        JellyfishMerkleTree::new_migration(self, true /* leaf_count_migration */)
+            .get_root_hash_option(version)
+    }
+
+    fn put_value_sets(
+        &self,
+        value_sets: Vec<Vec<(HashValue, Option<StateValue>)>>,
+        first_version: Version,
+        cs: &mut ChangeSet,
+    ) -> Result<Vec<HashValue>> {
+        let (new_root_hash_vec, tree_update_batch) =
+            JellyfishMerkleTree::new_migration(self, true /* leaf_count_migration */)
+                .put_value_sets_with_deletions(value_sets, first_version)?;
+        add_tree_update_batch(&mut cs.batch, tree_update_batch)?;
+
+        Ok(new_root_hash_vec)
+    }
+}
+
+impl TreeReader<StateValue> for ResourceStore {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        self.db.get::<ResourceMerkleNodeSchema>(node_key)
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
+        get_rightmost_leaf::<ResourceMerkleNodeSchema, _>(&self.db)
+    }
+}
+
+impl TreeWriter<StateValue> for ResourceStore {
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        add_node_batch(&mut batch, node_batch)?;
+        self.db.write_schemas(batch)
+    }
+}
+
+fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()> {
+    node_batch
+        .iter()
+        .map(|(node_key, node)| batch.put::<ResourceMerkleNodeSchema>(node_key, node))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(())
+}
+
+fn add_tree_update_batch(
+    batch: &mut SchemaBatch,
+    tree_update_batch: TreeUpdateBatch<StateValue>,
+) -> Result<()> {
+    add_node_batch(batch, &tree_update_batch.node_batch)?;
+    tree_update_batch
+        .stale_node_index_batch
+        .iter()
+        .map(|row| batch.put::<StaleResourceNodeIndexSchema>(row, &()))
+        .collect::<Result<Vec<()>>>()?;
+    Ok(())
+}
@@ -0,0 +1,231 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::DiemDB;
+use diem_types::{
+    account_address::AccountAddress,
+    account_config::{AccountResource, BalanceResource},
+    event::EventHandle,
+    write_set::{WriteOp, WriteSetMut},
+};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+fn new_store() -> Arc<ResourceStore> {
+    Arc::new(ResourceStore::new(Arc::new(
+        DB::open_in_memory("resource_store_test", DiemDB::column_families()).unwrap(),
+    )))
+}
+
+fn account_state_blob(address: AccountAddress, balance: u64) -> AccountStateBlob {
+    let account_resource = AccountResource::new(
+        0,
+        vec![],
+        None,
+        None,
+        EventHandle::new_from_address(&address, 0),
+        EventHandle::new_from_address(&address, 1),
+    );
+    let balance_resource = BalanceResource::new(balance);
+    AccountStateBlob::try_from((&account_resource, &balance_resource)).unwrap()
+}
+
+fn module_path(address: AccountAddress, name: &str) -> AccessPath {
+    AccessPath::code_access_path(ModuleId::new(address, Identifier::new(name).unwrap()))
+}
+
+fn put_write_sets(
+    store: &ResourceStore,
+    write_sets: Vec<Vec<(AccessPath, WriteOp)>>,
+    first_version: Version,
+) -> Vec<HashValue> {
+    let write_sets = write_sets
+        .into_iter()
+        .map(|write_set| WriteSetMut::new(write_set).freeze().unwrap())
+        .collect::<Vec<_>>();
+    let mut cs = ChangeSet::new();
+    let root_hashes = store
+        .put_write_sets(write_sets.iter().collect(), first_version, &mut cs)
+        .unwrap();
+    store.db.write_schemas(cs.batch).unwrap();
+    root_hashes
+}
+
+fn verify_value_in_store(
+    store: &ResourceStore,
+    access_path: &AccessPath,
+    expected_value: Option<StateValue>,
+    version: Version,
+    root: HashValue,
+) {
+    let (value, proof) = store
+        .get_state_value_with_proof_by_version(access_path, version)
+        .unwrap();
+    assert_eq!(value, expected_value);
+    proof
+        .verify(root, access_path.hash(), value.as_ref())
+        .unwrap();
+}
+
+#[test]
+fn test_put_write_sets_and_get_with_proof() {
+    let store = new_store();
+    let address = AccountAddress::random();
+    let path_a = module_path(address, "A");
+    let path_b = module_path(address, "B");
+    let path_c = module_path(address, "C");
+
+    let roots = put_write_sets(
+        &store,
+        vec![
+            vec![
+                (path_a.clone(), WriteOp::Value(vec![1])),
+                (path_b.clone(), WriteOp::Value(vec![2])),
+            ],
+            vec![(path_a.clone(), WriteOp::Value(vec![3]))],
+        ],
+        0, /* first_version */
+    );
+    let roots2 = put_write_sets(
+        &store,
+        vec![vec![
+            (path_b.clone(), WriteOp::Deletion),
+            (path_c.clone(), WriteOp::Value(vec![4])),
+        ]],
+        2, /* first_version */
+    );
+
+    verify_value_in_store(&store, &path_a, Some(StateValue::new(vec![1])), 0, roots[0]);
+    verify_value_in_store(&store, &path_b, Some(StateValue::new(vec![2])), 0, roots[0]);
+    verify_value_in_store(&store, &path_c, None, 0, roots[0]);
+
+    verify_value_in_store(&store, &path_a, Some(StateValue::new(vec![3])), 1, roots[1]);
+    verify_value_in_store(&store, &path_b, Some(StateValue::new(vec![2])), 1, roots[1]);
+
+    verify_value_in_store(
+        &store,
+        &path_a,
+        Some(StateValue::new(vec![3])),
+        2,
+        roots2[0],
+    );
+    verify_value_in_store(&store, &path_b, None, 2, roots2[0]);
+    verify_value_in_store(
+        &store,
+        &path_c,
+        Some(StateValue::new(vec![4])),
+        2,
+        roots2[0],
+    );
+
+    assert_eq!(store.get_root_hash_option(2).unwrap(), Some(roots2[0]));
+    assert_eq!(store.get_root_hash_option(3).unwrap(), None);
+}
+
+#[test]
+fn test_put_empty_write_set() {
+    let store = new_store();
+    let path = module_path(AccountAddress::random(), "A");
+    let roots = put_write_sets(
+        &store,
+        vec![vec![(path.clone(), WriteOp::Value(vec![1]))], vec![]],
+        0, /* first_version */
+    );
+
+    // The empty write set leaves the tree as it is, at the new version.
+    assert_eq!(roots[1], roots[0]);
+    verify_value_in_store(&store, &path, Some(StateValue::new(vec![1])), 1, roots[1]);
+}
+
+#[test]
+fn test_put_write_sets_without_previous_state() {
+    let store = new_store();
+    let write_set = WriteSetMut::new(vec![(
+        module_path(AccountAddress::random(), "A"),
+        WriteOp::Value(vec![1]),
+    )])
+    .freeze()
+    .unwrap();
+
+    // Nothing at version 9 to build the tree at version 10 upon.
+    let mut cs = ChangeSet::new();
+    assert!(store.put_write_sets(vec![&write_set], 10, &mut cs).is_err());
+}
+
+#[test]
+fn test_put_account_state_snapshot() {
+    let store = new_store();
+    let address = AccountAddress::random();
+    let blob = account_state_blob(address, 100);
+    let account_state = AccountState::try_from(&blob).unwrap();
+
+    // Put some unrelated state at earlier versions, which the snapshot should not be built upon.
+    let stale_path = module_path(address, "A");
+    put_write_sets(
+        &store,
+        vec![vec![(stale_path.clone(), WriteOp::Value(vec![1]))]],
+        0, /* first_version */
+    );
+
+    let version = 10;
+    let root = store
+        .put_account_state_snapshot(version, || Ok(std::iter::once(Ok(blob.clone()))))
+        .unwrap();
+
+    for (path, bytes) in account_state.iter() {
+        verify_value_in_store(
+            &store,
+            &AccessPath::new(address, path.clone()),
+            Some(StateValue::new(bytes.clone())),
+            version,
+            root,
+        );
+    }
+    verify_value_in_store(&store, &stale_path, None, version, root);
+
+    // The tree can be built upon once the snapshot is in.
+    let roots = put_write_sets(
+        &store,
+        vec![vec![(stale_path.clone(), WriteOp::Value(vec![2]))]],
+        version + 1,
+    );
+    verify_value_in_store(
+        &store,
+        &stale_path,
+        Some(StateValue::new(vec![2])),
+        version + 1,
+        roots[0],
+    );
+}
+
+#[test]
+fn test_put_account_state_snapshot_many_accounts() {
+    // Plenty of leaves under each first nibble of the keys.
+    let blobs: Vec<_> = (0..500)
+        .map(|balance| account_state_blob(AccountAddress::random(), balance))
+        .collect();
+    let write_set = blobs
+        .iter()
+        .flat_map(|blob| {
+            let account_state = AccountState::try_from(blob).unwrap();
+            let address = account_state.get_account_address().unwrap().unwrap();
+            account_state
+                .iter()
+                .map(|(path, bytes)| {
+                    (
+                        AccessPath::new(address, path.clone()),
+                        WriteOp::Value(bytes.clone()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // The snapshot builds the same tree as writing all the resources at once.
+    let expected_root = put_write_sets(&new_store(), vec![write_set], 0 /* first_version */)[0];
+    let store = new_store();
+    let root = store
+        .put_account_state_snapshot(0, || Ok(blobs.iter().cloned().map(Ok)))
+        .unwrap();
+    assert_eq!(root, expected_root);
+}
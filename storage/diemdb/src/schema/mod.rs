@@ -14,7 +14,9 @@ pub(crate) mod event_by_version;
 pub(crate) mod jellyfish_merkle_node;
 pub(crate) mod ledger_counters;
 pub(crate) mod ledger_info;
+pub(crate) mod resource_merkle_node;
 pub(crate) mod stale_node_index;
+pub(crate) mod stale_resource_node_index;
 pub(crate) mod transaction;
 pub(crate) mod transaction_accumulator;
 pub(crate) mod transaction_by_account;
@@ -32,7 +34,9 @@ pub const EVENT_BY_VERSION_CF_NAME: ColumnFamilyName = "event_by_version";
 pub const EVENT_CF_NAME: ColumnFamilyName = "event";
 pub const JELLYFISH_MERKLE_NODE_CF_NAME: ColumnFamilyName = "jellyfish_merkle_node";
 pub const LEDGER_COUNTERS_CF_NAME: ColumnFamilyName = "ledger_counters";
+pub const RESOURCE_MERKLE_NODE_CF_NAME: ColumnFamilyName = "resource_merkle_node";
 pub const STALE_NODE_INDEX_CF_NAME: ColumnFamilyName = "stale_node_index";
+pub const STALE_RESOURCE_NODE_INDEX_CF_NAME: ColumnFamilyName = "stale_resource_node_index";
 pub const TRANSACTION_CF_NAME: ColumnFamilyName = "transaction";
 pub const TRANSACTION_ACCUMULATOR_CF_NAME: ColumnFamilyName = "transaction_accumulator";
 pub const TRANSACTION_BY_ACCOUNT_CF_NAME: ColumnFamilyName = "transaction_by_account";
@@ -85,7 +89,12 @@ pub mod fuzzing {
             );
             decode_key_value!(super::ledger_counters::LedgerCountersSchema, data);
             decode_key_value!(super::ledger_info::LedgerInfoSchema, data);
+            decode_key_value!(super::resource_merkle_node::ResourceMerkleNodeSchema, data);
             decode_key_value!(super::stale_node_index::StaleNodeIndexSchema, data);
+            decode_key_value!(
+                super::stale_resource_node_index::StaleResourceNodeIndexSchema,
+                data
+            );
             decode_key_value!(super::transaction::TransactionSchema, data);
             decode_key_value!(
                 super::transaction_accumulator::TransactionAccumulatorSchema,
@@ -0,0 +1,22 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use diem_crypto::HashValue;
+use diem_jellyfish_merkle::node_type::Node;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_resource_merkle_node_schema(
+        node_key in any::<NodeKey>(),
+        resource_key in any::<HashValue>(),
+        value in any::<StateValue>(),
+    ) {
+        assert_encode_decode::<ResourceMerkleNodeSchema>(
+            &node_key,
+            &Node::new_leaf(resource_key, value),
+        );
+    }
+}
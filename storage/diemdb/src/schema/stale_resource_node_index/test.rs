@@ -0,0 +1,15 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        stale_node_index in any::<StaleNodeIndex>(),
+    ) {
+        assert_encode_decode::<StaleResourceNodeIndexSchema>(&stale_node_index, &());
+    }
+}
@@ -5,6 +5,9 @@
 
 #[cfg(test)]
 mod state_store_test;
+pub(crate) mod state_value_cache;
+#[cfg(test)]
+mod state_value_cache_test;
 
 use crate::{
     change_set::ChangeSet,
@@ -15,15 +18,22 @@ use crate::{
 };
 use anyhow::Result;
 use diem_crypto::HashValue;
-use diem_jellyfish_merkle::{node_type::NodeKey, JellyfishMerkleTree, TreeReader, TreeWriter};
+use diem_jellyfish_merkle::{
+    node_type::{self, NodeKey},
+    JellyfishMerkleTree, TreeReader, TreeWriter, Value,
+};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
     nibble::{nibble_path::NibblePath, ROOT_NIBBLE_HEIGHT},
     proof::{SparseMerkleProof, SparseMerkleRangeProof},
-    transaction::Version,
+    transaction::{Version, PRE_GENESIS_VERSION},
 };
-use schemadb::{SchemaBatch, DB};
+use schemadb::{
+    schema::{Schema, SeekKeyCodec},
+    SchemaBatch, DB,
+};
+use state_value_cache::StateValueCache;
 use std::{collections::HashMap, sync::Arc};
 
 type LeafNode = diem_jellyfish_merkle::node_type::LeafNode<AccountStateBlob>;
@@ -34,13 +44,15 @@ type NodeBatch = diem_jellyfish_merkle::NodeBatch<AccountStateBlob>;
 pub(crate) struct StateStore {
     db: Arc<DB>,
     account_count_migration: bool,
+    state_value_cache: Arc<StateValueCache>,
 }
 
 impl StateStore {
-    pub fn new(db: Arc<DB>, account_count_migration: bool) -> Self {
+    pub fn new(db: Arc<DB>, account_count_migration: bool, state_value_cache_size: usize) -> Self {
         Self {
             db,
             account_count_migration,
+            state_value_cache: Arc::new(StateValueCache::new(state_value_cache_size)),
         }
     }
 
@@ -53,8 +65,51 @@ impl StateStore {
         Option<AccountStateBlob>,
         SparseMerkleProof<AccountStateBlob>,
     )> {
-        JellyfishMerkleTree::new_migration(self, self.account_count_migration)
-            .get_with_proof(address.hash(), version)
+        if let Some(value_with_proof) = self.state_value_cache.get_with_proof(address, version) {
+            return Ok(value_with_proof);
+        }
+        let (blob, proof) = JellyfishMerkleTree::new_migration(self, self.account_count_migration)
+            .get_with_proof(address.hash(), version)?;
+        self.state_value_cache
+            .insert(address, version, blob.clone(), Some(proof.clone()));
+        Ok((blob, proof))
+    }
+
+    /// Get the account state blob given account address and version, without the proof.
+    pub fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountStateBlob>> {
+        if let Some(blob) = self.state_value_cache.get(address, version) {
+            return Ok(blob);
+        }
+        let (blob, _proof) = JellyfishMerkleTree::new_migration(self, self.account_count_migration)
+            .get_with_proof(address.hash(), version)?;
+        self.state_value_cache
+            .insert(address, version, blob.clone(), None /* proof */);
+        Ok(blob)
+    }
+
+    /// Updates the cached account states once the account state sets starting at
+    /// `first_version` have been committed.
+    pub fn update_cache<'a>(
+        &self,
+        first_version: Version,
+        account_state_sets: impl IntoIterator<Item = &'a HashMap<AccountAddress, AccountStateBlob>>,
+    ) {
+        self.state_value_cache
+            .commit(first_version, account_state_sets)
+    }
+
+    /// Drops all cached account states, after the state is modified other than by committing
+    /// transactions. `committed_version` is the latest version of the state after that.
+    pub fn reset_cache(&self, committed_version: Option<Version>) {
+        self.state_value_cache.reset(committed_version)
+    }
+
+    pub fn state_value_cache(&self) -> &Arc<StateValueCache> {
+        &self.state_value_cache
     }
 
     /// Gets the proof that proves a range of accounts.
@@ -125,6 +180,30 @@ impl StateStore {
             .get_root_hash_option(version)
     }
 
+    /// Gets the first version, starting at `min_version`, at which the state tree has a root in
+    /// the DB, if any.
+    pub fn get_first_root_version(&self, min_version: Version) -> Result<Option<Version>> {
+        let mut iter = self
+            .db
+            .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+        let mut version = min_version;
+        // Node keys are ordered by version first, and the root is the first node of a version.
+        // The nodes of a version without a root are ones still referenced by later versions.
+        loop {
+            iter.seek(&NodeKey::new_empty_path(version))?;
+            match iter.next().transpose()? {
+                Some((node_key, _node)) if node_key.nibble_path().num_nibbles() == 0 => {
+                    return Ok(Some(node_key.version()).filter(|v| *v != PRE_GENESIS_VERSION));
+                }
+                Some((node_key, _node)) => match node_key.version().checked_add(1) {
+                    Some(next_version) => version = next_version,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            }
+        }
+    }
+
     /// Finds the rightmost leaf by scanning the entire DB.
     #[cfg(test)]
     pub fn get_rightmost_leaf_naive(&self) -> Result<Option<(NodeKey, LeafNode)>> {
@@ -163,64 +242,7 @@ impl TreeReader<AccountStateBlob> for StateStore {
     }
 
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
-        // Since everything has the same version during restore, we seek to the first node and get
-        // its version.
-        let mut iter = self
-            .db
-            .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
-        iter.seek_to_first();
-        let version = match iter.next().transpose()? {
-            Some((node_key, _node)) => node_key.version(),
-            None => return Ok(None),
-        };
-
-        // The encoding of key and value in DB looks like:
-        //
-        // | <-------------- key --------------> | <- value -> |
-        // | version | num_nibbles | nibble_path |    node     |
-        //
-        // Here version is fixed. For each num_nibbles, there could be a range of nibble paths
-        // of the same length. If one of them is the rightmost leaf R, it must be at the end of this
-        // range. Otherwise let's assume the R is in the middle of the range, so we
-        // call the node at the end of this range X:
-        //   1. If X is leaf, then X.account_key() > R.account_key(), because the nibble path is a
-        //      prefix of the account key. So R is not the rightmost leaf.
-        //   2. If X is internal node, then X must be on the right side of R, so all its children's
-        //      account keys are larger than R.account_key(). So R is not the rightmost leaf.
-        //
-        // Given that num_nibbles ranges from 0 to ROOT_NIBBLE_HEIGHT, there are only
-        // ROOT_NIBBLE_HEIGHT+1 ranges, so we can just find the node at the end of each range and
-        // then pick the one with the largest account key.
-        let mut ret = None;
-
-        for num_nibbles in 1..=ROOT_NIBBLE_HEIGHT + 1 {
-            let mut iter = self
-                .db
-                .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
-            // nibble_path is always non-empty except for the root, so if we use an empty nibble
-            // path as the seek key, the iterator will end up pointing to the end of the previous
-            // range.
-            let seek_key = (version, num_nibbles as u8);
-            iter.seek_for_prev(&seek_key)?;
-
-            if let Some((node_key, node)) = iter.next().transpose()? {
-                debug_assert_eq!(node_key.version(), version);
-                debug_assert!(node_key.nibble_path().num_nibbles() < num_nibbles);
-
-                if let Node::Leaf(leaf_node) = node {
-                    match ret {
-                        None => ret = Some((node_key, leaf_node)),
-                        Some(ref other) => {
-                            if leaf_node.account_key() > other.1.account_key() {
-                                ret = Some((node_key, leaf_node));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(ret)
+        get_rightmost_leaf::<JellyfishMerkleNodeSchema, _>(&self.db)
     }
 }
 
@@ -239,3 +261,67 @@ fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()>
         .collect::<Result<Vec<_>>>()?;
     Ok(())
 }
+
+/// Finds the rightmost leaf of the Jellyfish Merkle tree stored in schema `S`, assuming all nodes
+/// in the tree are of the same version, which is the case during restore.
+pub(crate) fn get_rightmost_leaf<S, V>(db: &DB) -> Result<Option<(NodeKey, node_type::LeafNode<V>)>>
+where
+    S: Schema<Key = NodeKey, Value = node_type::Node<V>>,
+    (Version, u8): SeekKeyCodec<S>,
+    V: Value,
+{
+    // Since everything has the same version during restore, we seek to the first node and get
+    // its version.
+    let mut iter = db.iter::<S>(Default::default())?;
+    iter.seek_to_first();
+    let version = match iter.next().transpose()? {
+        Some((node_key, _node)) => node_key.version(),
+        None => return Ok(None),
+    };
+
+    // The encoding of key and value in DB looks like:
+    //
+    // | <-------------- key --------------> | <- value -> |
+    // | version | num_nibbles | nibble_path |    node     |
+    //
+    // Here version is fixed. For each num_nibbles, there could be a range of nibble paths
+    // of the same length. If one of them is the rightmost leaf R, it must be at the end of this
+    // range. Otherwise let's assume the R is in the middle of the range, so we
+    // call the node at the end of this range X:
+    //   1. If X is leaf, then X.account_key() > R.account_key(), because the nibble path is a
+    //      prefix of the account key. So R is not the rightmost leaf.
+    //   2. If X is internal node, then X must be on the right side of R, so all its children's
+    //      account keys are larger than R.account_key(). So R is not the rightmost leaf.
+    //
+    // Given that num_nibbles ranges from 0 to ROOT_NIBBLE_HEIGHT, there are only
+    // ROOT_NIBBLE_HEIGHT+1 ranges, so we can just find the node at the end of each range and
+    // then pick the one with the largest account key.
+    let mut ret = None;
+
+    for num_nibbles in 1..=ROOT_NIBBLE_HEIGHT + 1 {
+        let mut iter = db.iter::<S>(Default::default())?;
+        // nibble_path is always non-empty except for the root, so if we use an empty nibble
+        // path as the seek key, the iterator will end up pointing to the end of the previous
+        // range.
+        let seek_key = (version, num_nibbles as u8);
+        iter.seek_for_prev(&seek_key)?;
+
+        if let Some((node_key, node)) = iter.next().transpose()? {
+            debug_assert_eq!(node_key.version(), version);
+            debug_assert!(node_key.nibble_path().num_nibbles() < num_nibbles);
+
+            if let node_type::Node::Leaf(leaf_node) = node {
+                match ret {
+                    None => ret = Some((node_key, leaf_node)),
+                    Some(ref other) => {
+                        if leaf_node.account_key() > other.1.account_key() {
+                            ret = Some((node_key, leaf_node));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
@@ -84,8 +84,7 @@ fn verify_state_in_store(
 
 #[test]
 fn test_empty_store() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
     let store = &db.state_store;
     let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
     assert!(store
@@ -95,8 +94,7 @@ fn test_empty_store() {
 
 #[test]
 fn test_state_store_reader_writer() {
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
     let store = &db.state_store;
     let address1 = AccountAddress::new([1u8; AccountAddress::LENGTH]);
     let address2 = AccountAddress::new([2u8; AccountAddress::LENGTH]);
@@ -149,8 +147,7 @@ fn test_retired_records() {
     let value3 = AccountStateBlob::from(vec![0x03]);
     let value3_update = AccountStateBlob::from(vec![0x13]);
 
-    let tmp_dir = TempPath::new();
-    let db = DiemDB::new_for_test(&tmp_dir);
+    let db = DiemDB::new_for_test();
     let store = &db.state_store;
 
     // Update.
@@ -197,6 +194,7 @@ fn test_retired_records() {
             0, /* limit */
         );
         verify_state_in_store(store, address1, Some(&value1), 0, root0);
+        assert_eq!(store.get_first_root_version(0).unwrap(), Some(0));
     }
     // Prune till version=1.
     {
@@ -213,6 +211,8 @@ fn test_retired_records() {
         verify_state_in_store(store, address1, Some(&value1), 1, root1);
         verify_state_in_store(store, address2, Some(&value2_update), 1, root1);
         verify_state_in_store(store, address3, Some(&value3), 1, root1);
+        // The leaf of address1 created at version 0 is still there, but not root0.
+        assert_eq!(store.get_first_root_version(0).unwrap(), Some(1));
     }
     // Prune till version=2.
     {
@@ -229,6 +229,8 @@ fn test_retired_records() {
         verify_state_in_store(store, address1, Some(&value1), 2, root2);
         verify_state_in_store(store, address2, Some(&value2_update), 2, root2);
         verify_state_in_store(store, address3, Some(&value3_update), 2, root2);
+        assert_eq!(store.get_first_root_version(0).unwrap(), Some(2));
+        assert_eq!(store.get_first_root_version(3).unwrap(), None);
     }
 }
 
@@ -242,8 +244,7 @@ proptest! {
         // Convert to a vector so iteration order becomes deterministic.
         let kvs: Vec<_> = input.into_iter().collect();
 
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.state_store;
         init_store(store, kvs.clone().into_iter());
 
@@ -272,16 +273,14 @@ proptest! {
                 (Just(input), 1..len)
             })
     ) {
-        let tmp_dir1 = TempPath::new();
-        let db1 = DiemDB::new_for_test(&tmp_dir1);
+        let db1 = DiemDB::new_for_test();
         let store1 = &db1.state_store;
         init_store(store1, input.clone().into_iter());
 
         let version = (input.len() - 1) as Version;
         let expected_root_hash = store1.get_root_hash(version).unwrap();
 
-        let tmp_dir2 = TempPath::new();
-        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let db2 = DiemDB::new_for_test();
         let store2 = &db2.state_store;
 
         let mut restore =
@@ -320,6 +319,9 @@ proptest! {
 
         let actual_root_hash = store2.get_root_hash(version).unwrap();
         prop_assert_eq!(actual_root_hash, expected_root_hash);
+        // The restored store holds no state preceding the snapshot.
+        prop_assert_eq!(store1.get_first_root_version(0).unwrap(), Some(0));
+        prop_assert_eq!(store2.get_first_root_version(0).unwrap(), Some(version));
     }
 
     #[test]
@@ -329,8 +331,7 @@ proptest! {
         // turned on, without any internal node to be created in the new format.
         input in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 17..1000)
     ) {
-        let src_tmp_dir = TempPath::new();
-        let src_db = DiemDB::new_for_test(&src_tmp_dir);
+        let src_db = DiemDB::new_for_test();
         let src_store = &src_db.state_store;
         init_store(src_store, input.clone().into_iter());
 
@@ -350,7 +351,16 @@ proptest! {
 
         // restore in non-migration mode
         {
-            let db1 = DiemDB::new_for_test(&tgt_tmp_dir);
+            let db1 = DiemDB::open(
+                &tgt_tmp_dir,
+                false, /* readonly */
+                None,  /* pruner */
+                None, /* ledger_pruner */
+                RocksdbConfig::default(),
+                true, /* account_count_migration */
+                false, /* resource_granular_state */
+                0, /* state_value_cache_size */
+            ).unwrap();
             let store1 = &db1.state_store;
             let mut restore1 =
                 JellyfishMerkleRestore::new(Arc::clone(store1), version, expected_root_hash, false /* leaf_count_migration */).unwrap();
@@ -364,8 +374,11 @@ proptest! {
                 &tgt_tmp_dir,
                 false, /* readonly */
                 None,  /* pruner */
+                None, /* ledger_pruner */
                 RocksdbConfig::default(),
                 true, /* account_count_migration */
+                false, /* resource_granular_state */
+                0, /* state_value_cache_size */
             ).unwrap();
             let store2 = &db2.state_store;
             // confirm that leaf counts were not written
@@ -387,16 +400,14 @@ proptest! {
                 (Just(input), 1..len)
             })
     ) {
-        let tmp_dir1 = TempPath::new();
-        let db1 = DiemDB::new_for_test(&tmp_dir1);
+        let db1 = DiemDB::new_for_test();
         let store1 = &db1.state_store;
         init_store(store1, input.clone().into_iter());
 
         let version = (input.len() - 1) as Version;
         let expected_root_hash = store1.get_root_hash(version).unwrap();
 
-        let tmp_dir2 = TempPath::new();
-        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let db2 = DiemDB::new_for_test();
         let store2 = &db2.state_store;
 
         let mut restore =
@@ -431,8 +442,7 @@ proptest! {
         let version = (input.len() - 1) as Version;
         let account_count = input.iter().map(|(k, _)| k).collect::<HashSet<_>>().len();
 
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.state_store;
         init_store(store, input.into_iter());
         assert_eq!(store.get_account_count(version).unwrap().unwrap(), account_count);
@@ -454,8 +464,11 @@ proptest! {
                 &tmp_dir,
                 false, /* read_only */
                 None,
+                None,
                 RocksdbConfig::default(),
                 false, /* account_count_migration */
+                false, /* resource_granular_state */
+                0, /* state_value_cache_size */
             ).unwrap();
             let store = &db.state_store;
             init_store(store, before.into_iter());
@@ -464,7 +477,16 @@ proptest! {
 
         // migrate by touching all accounts
         {
-            let db = DiemDB::new_for_test(&tmp_dir);
+            let db = DiemDB::open(
+                &tmp_dir,
+                false, /* read_only */
+                None,
+                None,
+                RocksdbConfig::default(),
+                true, /* account_count_migration */
+                false, /* resource_granular_state */
+                0, /* state_value_cache_size */
+            ).unwrap();
             let store = &db.state_store;
             update_store(store, after.into_iter(), num_updates as Version);
             assert_eq!(
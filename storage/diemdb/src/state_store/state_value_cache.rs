@@ -0,0 +1,329 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file defines the in-memory cache of account states that sits in front of the account
+//! state Merkle tree, so that reading hot accounts (e.g. the on-chain configs under `0x1`)
+//! doesn't walk the tree from the root on every read.
+//!
+//! For each cached account, the cache keeps the values the account took since the version it was
+//! first cached at, and the cache is kept up to date by every commit. So the cached value of an
+//! account is known for any version between its first cached version and the latest committed
+//! version. The proof of an account changes with any update in the tree, so only the proof read
+//! at a single version is kept for each account.
+
+use crate::metrics::{
+    DIEM_STORAGE_STATE_VALUE_CACHE_EVICTIONS, DIEM_STORAGE_STATE_VALUE_CACHE_NUM_ACCOUNTS,
+    DIEM_STORAGE_STATE_VALUE_CACHE_REQUESTS, DIEM_STORAGE_STATE_VALUE_CACHE_SIZE_BYTES,
+};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_types::{
+    account_address::AccountAddress, account_state_blob::AccountStateBlob,
+    proof::SparseMerkleProof, transaction::Version,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// The result of a lookup: `None` for a miss, `Some(None)` for a cached non-existent account.
+pub(crate) type CachedValue = Option<Option<AccountStateBlob>>;
+
+#[derive(Debug)]
+struct CacheEntry {
+    /// The values of the account, keyed by the version since which each value holds. The entry
+    /// is complete from its first key up to the latest committed version.
+    values: BTreeMap<Version, Option<AccountStateBlob>>,
+    /// The proof of the account value at a single version.
+    proof: Option<(Version, SparseMerkleProof<AccountStateBlob>)>,
+    /// The key of the entry in the LRU index.
+    last_access: u64,
+    size_bytes: usize,
+}
+
+impl CacheEntry {
+    fn get(&self, version: Version) -> CachedValue {
+        self.values
+            .range(..=version)
+            .next_back()
+            .map(|(_version, value)| value.clone())
+    }
+
+    fn update_size(&mut self) {
+        self.size_bytes = self
+            .values
+            .values()
+            .map(|value| value.as_ref().map_or(0, blob_size))
+            .sum::<usize>()
+            + self
+                .proof
+                .as_ref()
+                .map_or(0, |(_version, proof)| proof_size(proof));
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    max_size_bytes: usize,
+    size_bytes: usize,
+    /// The latest version known to have been committed. Values are only cached when read at this
+    /// version, and nothing is cached before the version is known.
+    committed_version: Option<Version>,
+    /// Versions before this have been pruned and are never served.
+    min_readable_version: Version,
+    entries: HashMap<AccountAddress, CacheEntry>,
+    /// Access tick -> address, from the least recently used.
+    lru_index: BTreeMap<u64, AccountAddress>,
+    next_tick: u64,
+}
+
+impl Inner {
+    fn is_readable(&self, version: Version) -> bool {
+        version >= self.min_readable_version
+            && self
+                .committed_version
+                .map_or(false, |committed_version| version <= committed_version)
+    }
+
+    /// Looks up the entry of `address` and marks it as the most recently used.
+    fn touch(&mut self, address: &AccountAddress) -> Option<&mut CacheEntry> {
+        let entry = self.entries.get_mut(address)?;
+        self.lru_index.remove(&entry.last_access);
+        entry.last_access = self.next_tick;
+        self.lru_index.insert(self.next_tick, *address);
+        self.next_tick += 1;
+        Some(entry)
+    }
+
+    /// Updates the size of the entry of `address` after it's modified, evicting entries if the
+    /// cache becomes oversized.
+    fn resize(&mut self, address: &AccountAddress) {
+        if let Some(entry) = self.entries.get_mut(address) {
+            self.size_bytes -= entry.size_bytes;
+            entry.update_size();
+            self.size_bytes += entry.size_bytes;
+        }
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.size_bytes > self.max_size_bytes {
+            let address = match self.lru_index.values().next() {
+                Some(address) => *address,
+                None => break,
+            };
+            self.remove(&address);
+            DIEM_STORAGE_STATE_VALUE_CACHE_EVICTIONS.inc();
+        }
+        self.update_gauges();
+    }
+
+    fn remove(&mut self, address: &AccountAddress) {
+        if let Some(entry) = self.entries.remove(address) {
+            self.lru_index.remove(&entry.last_access);
+            self.size_bytes -= entry.size_bytes;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.lru_index.clear();
+        self.size_bytes = 0;
+        self.update_gauges();
+    }
+
+    fn update_gauges(&self) {
+        DIEM_STORAGE_STATE_VALUE_CACHE_SIZE_BYTES.set(self.size_bytes as i64);
+        DIEM_STORAGE_STATE_VALUE_CACHE_NUM_ACCOUNTS.set(self.entries.len() as i64);
+    }
+}
+
+/// A version-aware, size-bounded LRU cache of account state blobs (and their proofs).
+#[derive(Debug)]
+pub(crate) struct StateValueCache {
+    inner: Mutex<Inner>,
+}
+
+impl StateValueCache {
+    /// Creates an empty cache holding up to `max_size_bytes` bytes of blobs and proofs. A cache
+    /// of size 0 caches nothing.
+    pub fn new(max_size_bytes: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                max_size_bytes,
+                size_bytes: 0,
+                committed_version: None,
+                min_readable_version: 0,
+                entries: HashMap::new(),
+                lru_index: BTreeMap::new(),
+                next_tick: 0,
+            }),
+        }
+    }
+
+    /// Gets the value of the account at `address` at `version`, if cached.
+    pub fn get(&self, address: AccountAddress, version: Version) -> CachedValue {
+        let mut inner = self.inner.lock();
+        let value = if inner.is_readable(version) {
+            inner.touch(&address).and_then(|entry| entry.get(version))
+        } else {
+            None
+        };
+        record_request("value", value.is_some());
+        value
+    }
+
+    /// Gets the value of the account at `address` at `version` together with its proof, if both
+    /// are cached.
+    pub fn get_with_proof(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Option<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        let mut inner = self.inner.lock();
+        let value_with_proof = if inner.is_readable(version) {
+            inner.touch(&address).and_then(|entry| match &entry.proof {
+                Some((proof_version, proof)) if *proof_version == version => {
+                    entry.get(version).map(|value| (value, proof.clone()))
+                }
+                _ => None,
+            })
+        } else {
+            None
+        };
+        record_request("value_with_proof", value_with_proof.is_some());
+        value_with_proof
+    }
+
+    /// Caches the value (and optionally the proof) of the account at `address` read from the DB
+    /// at `version`. Ignored unless `version` is still the latest committed version, since
+    /// updates committed in the meantime might not have been seen by the cache.
+    pub fn insert(
+        &self,
+        address: AccountAddress,
+        version: Version,
+        value: Option<AccountStateBlob>,
+        proof: Option<SparseMerkleProof<AccountStateBlob>>,
+    ) {
+        let mut inner = self.inner.lock();
+        if inner.max_size_bytes == 0 || inner.committed_version != Some(version) {
+            return;
+        }
+
+        if inner.touch(&address).is_none() {
+            let last_access = inner.next_tick;
+            inner.next_tick += 1;
+            inner.lru_index.insert(last_access, address);
+            inner.entries.insert(
+                address,
+                CacheEntry {
+                    values: BTreeMap::new(),
+                    proof: None,
+                    last_access,
+                    size_bytes: 0,
+                },
+            );
+        }
+        let entry = inner
+            .entries
+            .get_mut(&address)
+            .expect("Entry inserted above.");
+        // The value is known since the latest update, so keep the existing entry if any.
+        if entry.values.is_empty() {
+            entry.values.insert(version, value);
+        }
+        if let Some(proof) = proof {
+            entry.proof = Some((version, proof));
+        }
+        inner.resize(&address);
+    }
+
+    /// Applies the account state updates committed at versions starting from `first_version`,
+    /// one set of updates per version.
+    pub fn commit<'a>(
+        &self,
+        first_version: Version,
+        account_state_sets: impl IntoIterator<Item = &'a HashMap<AccountAddress, AccountStateBlob>>,
+    ) {
+        let mut inner = self.inner.lock();
+        // Entries missed the updates in between if the versions are not contiguous.
+        if inner.committed_version.map_or(false, |committed_version| {
+            committed_version + 1 != first_version
+        }) {
+            inner.clear();
+        }
+
+        let mut next_version = first_version;
+        for account_states in account_state_sets {
+            for (address, blob) in account_states {
+                if let Some(entry) = inner.entries.get_mut(address) {
+                    entry.values.insert(next_version, Some(blob.clone()));
+                    inner.resize(address);
+                }
+            }
+            next_version += 1;
+        }
+        if next_version > first_version {
+            inner.committed_version = Some(next_version - 1);
+        }
+    }
+
+    /// Drops everything cached, after the state is modified other than by committing
+    /// transactions (e.g. by restoring a state snapshot). `committed_version` is the latest
+    /// version after the modification.
+    pub fn reset(&self, committed_version: Option<Version>) {
+        let mut inner = self.inner.lock();
+        inner.clear();
+        inner.committed_version = committed_version;
+    }
+
+    /// Invalidates everything cached for versions before `min_readable_version`, after they've
+    /// been pruned from the DB.
+    pub fn prune(&self, min_readable_version: Version) {
+        let mut inner = self.inner.lock();
+        if min_readable_version <= inner.min_readable_version {
+            return;
+        }
+        inner.min_readable_version = min_readable_version;
+
+        let addresses = inner.entries.keys().cloned().collect::<Vec<_>>();
+        for address in addresses {
+            let entry = match inner.entries.get_mut(&address) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            // Keep the value that holds at `min_readable_version`, if known.
+            let mut retained = entry.values.split_off(&min_readable_version);
+            if !retained.contains_key(&min_readable_version) {
+                if let Some((_version, value)) = entry.values.iter().next_back() {
+                    retained.insert(min_readable_version, value.clone());
+                }
+            }
+            entry.values = retained;
+            if matches!(&entry.proof, Some((version, _proof)) if *version < min_readable_version) {
+                entry.proof = None;
+            }
+
+            if entry.values.is_empty() {
+                inner.remove(&address);
+            } else {
+                inner.resize(&address);
+            }
+        }
+        inner.update_gauges();
+    }
+}
+
+fn blob_size(blob: &AccountStateBlob) -> usize {
+    AsRef::<[u8]>::as_ref(blob).len()
+}
+
+fn proof_size(proof: &SparseMerkleProof<AccountStateBlob>) -> usize {
+    (proof.siblings().len() + 2) * HashValue::LENGTH
+}
+
+fn record_request(request_type: &str, hit: bool) {
+    DIEM_STORAGE_STATE_VALUE_CACHE_REQUESTS
+        .with_label_values(&[request_type, if hit { "hit" } else { "miss" }])
+        .inc();
+}
@@ -0,0 +1,156 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::state_value_cache::*;
+use diem_crypto::HashValue;
+use diem_types::{
+    account_address::AccountAddress, account_state_blob::AccountStateBlob,
+    proof::SparseMerkleProof, transaction::Version,
+};
+use std::collections::HashMap;
+
+fn blob(byte: u8, len: usize) -> AccountStateBlob {
+    AccountStateBlob::from(vec![byte; len])
+}
+
+fn proof(num_siblings: usize) -> SparseMerkleProof<AccountStateBlob> {
+    SparseMerkleProof::new(None, vec![HashValue::zero(); num_siblings])
+}
+
+fn commit(
+    cache: &StateValueCache,
+    version: Version,
+    account_states: Vec<(AccountAddress, AccountStateBlob)>,
+) {
+    let account_state_set = account_states.into_iter().collect::<HashMap<_, _>>();
+    cache.commit(version, vec![&account_state_set]);
+}
+
+#[test]
+fn test_get_and_insert() {
+    let cache = StateValueCache::new(1024);
+    let address = AccountAddress::random();
+
+    // Nothing is cached before the latest committed version is known.
+    cache.insert(address, 5, Some(blob(1, 10)), None);
+    assert_eq!(cache.get(address, 5), None);
+
+    cache.reset(Some(5));
+    cache.insert(address, 5, Some(blob(1, 10)), Some(proof(2)));
+    assert_eq!(cache.get(address, 5), Some(Some(blob(1, 10))));
+    assert_eq!(
+        cache.get_with_proof(address, 5),
+        Some((Some(blob(1, 10)), proof(2)))
+    );
+    // Not known before it's cached, or after the latest committed version.
+    assert_eq!(cache.get(address, 4), None);
+    assert_eq!(cache.get(address, 6), None);
+    // Other accounts are not cached.
+    assert_eq!(cache.get(AccountAddress::random(), 5), None);
+
+    // Non-existent accounts can be cached.
+    let non_existent = AccountAddress::random();
+    cache.insert(non_existent, 5, None, None);
+    assert_eq!(cache.get(non_existent, 5), Some(None));
+    assert_eq!(cache.get_with_proof(non_existent, 5), None);
+}
+
+#[test]
+fn test_commit() {
+    let cache = StateValueCache::new(1024);
+    let address = AccountAddress::random();
+    let other_address = AccountAddress::random();
+    cache.reset(Some(5));
+    cache.insert(address, 5, Some(blob(1, 10)), Some(proof(2)));
+
+    commit(&cache, 6, vec![(other_address, blob(2, 10))]);
+    commit(&cache, 7, vec![(address, blob(3, 10))]);
+
+    assert_eq!(cache.get(address, 5), Some(Some(blob(1, 10))));
+    assert_eq!(cache.get(address, 6), Some(Some(blob(1, 10))));
+    assert_eq!(cache.get(address, 7), Some(Some(blob(3, 10))));
+    // Only the proof at version 5 is known.
+    assert!(cache.get_with_proof(address, 5).is_some());
+    assert!(cache.get_with_proof(address, 7).is_none());
+    // Updates to accounts not cached are not cached.
+    assert_eq!(cache.get(other_address, 6), None);
+
+    // A read at a version older than the latest committed version is not cached, as it might
+    // have missed updates committed since.
+    cache.insert(other_address, 6, Some(blob(2, 10)), None);
+    assert_eq!(cache.get(other_address, 7), None);
+
+    // Updates at versions that are not contiguous drop everything cached.
+    commit(&cache, 9, vec![(other_address, blob(4, 10))]);
+    assert_eq!(cache.get(address, 9), None);
+    cache.insert(address, 9, Some(blob(3, 10)), None);
+    assert_eq!(cache.get(address, 9), Some(Some(blob(3, 10))));
+}
+
+#[test]
+fn test_eviction() {
+    // Room for 3 blobs of 10 bytes.
+    let cache = StateValueCache::new(30);
+    let addresses = (0..4).map(|_| AccountAddress::random()).collect::<Vec<_>>();
+    cache.reset(Some(0));
+
+    for address in &addresses[..3] {
+        cache.insert(*address, 0, Some(blob(1, 10)), None);
+    }
+    // Use the first account so that the second one becomes the least recently used.
+    assert!(cache.get(addresses[0], 0).is_some());
+    cache.insert(addresses[3], 0, Some(blob(1, 10)), None);
+    assert!(cache.get(addresses[0], 0).is_some());
+    assert!(cache.get(addresses[1], 0).is_none());
+    assert!(cache.get(addresses[2], 0).is_some());
+    assert!(cache.get(addresses[3], 0).is_some());
+
+    // Growing an entry by an update evicts others.
+    commit(&cache, 1, vec![(addresses[3], blob(2, 10))]);
+    assert!(cache.get(addresses[0], 1).is_none());
+    assert_eq!(cache.get(addresses[3], 0), Some(Some(blob(1, 10))));
+    assert_eq!(cache.get(addresses[3], 1), Some(Some(blob(2, 10))));
+
+    // Values larger than the cache are not cached.
+    cache.insert(addresses[0], 1, Some(blob(1, 31)), None);
+    assert!(cache.get(addresses[0], 1).is_none());
+
+    // A cache of size 0 caches nothing.
+    let cache = StateValueCache::new(0);
+    cache.reset(Some(0));
+    cache.insert(addresses[0], 0, None, None);
+    assert!(cache.get(addresses[0], 0).is_none());
+}
+
+#[test]
+fn test_prune() {
+    let cache = StateValueCache::new(1024);
+    let address = AccountAddress::random();
+    cache.reset(Some(0));
+    cache.insert(address, 0, Some(blob(1, 10)), Some(proof(2)));
+    commit(&cache, 1, vec![(address, blob(2, 10))]);
+    commit(&cache, 2, vec![]);
+    commit(&cache, 3, vec![(address, blob(3, 10))]);
+
+    cache.prune(2);
+    assert_eq!(cache.get(address, 0), None);
+    assert_eq!(cache.get(address, 1), None);
+    assert_eq!(cache.get_with_proof(address, 0), None);
+    assert_eq!(cache.get(address, 2), Some(Some(blob(2, 10))));
+    assert_eq!(cache.get(address, 3), Some(Some(blob(3, 10))));
+
+    cache.prune(4);
+    assert_eq!(cache.get(address, 3), None);
+}
+
+#[test]
+fn test_reset() {
+    let cache = StateValueCache::new(1024);
+    let address = AccountAddress::random();
+    cache.reset(Some(0));
+    cache.insert(address, 0, Some(blob(1, 10)), None);
+
+    cache.reset(Some(10));
+    assert_eq!(cache.get(address, 0), None);
+    assert_eq!(cache.get(address, 10), None);
+}
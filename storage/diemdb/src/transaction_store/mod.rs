@@ -25,30 +25,60 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub(crate) struct TransactionStore {
     db: Arc<DB>,
+    /// Whether the ledger pruner may have deleted old transactions.
+    ledger_pruner_enabled: bool,
 }
 
 impl TransactionStore {
-    pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<DB>, ledger_pruner_enabled: bool) -> Self {
+        Self {
+            db,
+            ledger_pruner_enabled,
+        }
     }
 
     /// Gets the version of a transaction by the sender `address` and `sequence_number`.
+    ///
+    /// With the ledger pruner enabled, fails if the transaction has been pruned, which is the case
+    /// if it's missing while a transaction of the account with a higher sequence number exists,
+    /// since pruning deletes the oldest transactions first.
     pub fn get_account_transaction_version(
         &self,
         address: AccountAddress,
         sequence_number: u64,
         ledger_version: Version,
     ) -> Result<Option<Version>> {
-        if let Some(version) = self
+        match self
             .db
             .get::<TransactionByAccountSchema>(&(address, sequence_number))?
         {
-            if version <= ledger_version {
-                return Ok(Some(version));
+            Some(version) if version <= ledger_version => Ok(Some(version)),
+            Some(_) => Ok(None),
+            None if !self.ledger_pruner_enabled => Ok(None),
+            None => {
+                let mut iter = self
+                    .db
+                    .iter::<TransactionByAccountSchema>(ReadOptions::default())?;
+                iter.seek(&(address, sequence_number))?;
+                match iter.next().transpose()? {
+                    Some(((next_address, _), version))
+                        if next_address == address && version <= ledger_version =>
+                    {
+                        Err(account_transaction_pruned(address, sequence_number))
+                    }
+                    _ => Ok(None),
+                }
             }
         }
+    }
 
-        Ok(None)
+    /// Gets the version of the oldest transaction in the DB, if any.
+    pub fn get_first_txn_version(&self) -> Result<Option<Version>> {
+        let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.next()
+            .map(|res| res.map(|(version, _txn)| version))
+            .transpose()
     }
 
     /// Gets the version of a transaction by its hash.
@@ -119,7 +149,7 @@ impl TransactionStore {
     /// Returns the block metadata carried on the block metadata transaction at or preceding
     /// `version`, together with the version of the block metadata transaction.
     /// Returns None if there's no such transaction at or preceding `version` (it's likely the genesis
-    /// version 0).
+    /// version 0), and fails if the transactions preceding `version` have been pruned.
     pub fn get_block_metadata(&self, version: Version) -> Result<Option<(Version, BlockMetadata)>> {
         // Maximum TPS from benchmark is around 1000.
         const MAX_VERSIONS_TO_SEARCH: usize = 1000 * 3;
@@ -129,6 +159,7 @@ impl TransactionStore {
         // each block.
         let mut iter = self.db.rev_iter::<TransactionSchema>(Default::default())?;
         iter.seek(&version)?;
+        let mut num_searched = 0;
         for res in iter.take(MAX_VERSIONS_TO_SEARCH) {
             let (v, txn) = res?;
             if let Transaction::BlockMetadata(block_meta) = txn {
@@ -136,6 +167,16 @@ impl TransactionStore {
             } else if v == 0 {
                 return Ok(None);
             }
+            num_searched += 1;
+        }
+        // Running out of transactions before reaching genesis means the older ones, among which
+        // the block metadata, have been pruned.
+        if num_searched < MAX_VERSIONS_TO_SEARCH {
+            return Err(DiemDbError::Pruned(format!(
+                "BlockMetadata preceding version {}",
+                version
+            ))
+            .into());
         }
 
         Err(DiemDbError::NotFound(format!("BlockMetadata preceding version {}", version)).into())
@@ -252,6 +293,14 @@ impl<'a> AccountTransactionVersionIter<'a> {
                     return Ok(None);
                 }
 
+                // The transactions at the beginning of the requested range have been pruned.
+                if self.prev_version.is_none() && seq_num > self.expected_next_seq_num {
+                    return Err(account_transaction_pruned(
+                        address,
+                        self.expected_next_seq_num,
+                    ));
+                }
+
                 // Ensure seq_num_{i+1} == seq_num_{i} + 1
                 ensure!(
                     seq_num == self.expected_next_seq_num,
@@ -294,5 +343,13 @@ impl<'a> Iterator for AccountTransactionVersionIter<'a> {
     }
 }
 
+fn account_transaction_pruned(address: AccountAddress, sequence_number: u64) -> anyhow::Error {
+    DiemDbError::Pruned(format!(
+        "Txn of account {} with seq num {}",
+        address, sequence_number
+    ))
+    .into()
+}
+
 #[cfg(test)]
 mod test;
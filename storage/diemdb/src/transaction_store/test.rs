@@ -4,7 +4,6 @@
 use super::*;
 use crate::DiemDB;
 use diem_proptest_helpers::Index;
-use diem_temppath::TempPath;
 use diem_types::{
     block_metadata::BlockMetadata,
     proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen},
@@ -24,8 +23,7 @@ proptest! {
             1..10
         ),
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
         let (gens, write_sets):(Vec<_>, Vec<_>) = gens_and_write_sets.into_iter().unzip();
         let txns = init_store(universe, gens, store);
@@ -69,8 +67,7 @@ proptest! {
             1..10
         ),
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
         let txns = init_store(universe, gens, store);
 
@@ -125,8 +122,7 @@ proptest! {
             1..100,
         )
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
 
         let mut cs = ChangeSet::new();
@@ -174,8 +170,7 @@ proptest! {
         ledger_version in 0_u64..50,
         num_versions in 0_u64..=50,
     ) {
-        let tmp_dir = TempPath::new();
-        let db = DiemDB::new_for_test(&tmp_dir);
+        let db = DiemDB::new_for_test();
         let store = &db.transaction_store;
         let txns = init_store(universe, gens, store);
 
@@ -7,7 +7,7 @@ use anyhow::Result;
 use diem_config::config::RocksdbConfig;
 use diem_framework_releases::name_for_script;
 use diem_logger::info;
-use diemdb::DiemDB;
+use diemdb::{inspector::ConsistencyCheck, DiemDB};
 use std::path::PathBuf;
 use storage_interface::DbReader;
 
@@ -39,6 +39,28 @@ enum Command {
     },
     #[structopt(name = "list-accounts")]
     ListAccounts,
+    /// Prints the names of all the column families in the DB, in JSON.
+    #[structopt(name = "list-column-families")]
+    ListColumnFamilies,
+    /// Prints the rows of a column family with the keys and values decoded, in JSON.
+    #[structopt(name = "dump-column-family")]
+    DumpColumnFamily {
+        cf_name: String,
+        #[structopt(long, default_value = "100")]
+        limit: usize,
+        /// Dump from the last key backwards.
+        #[structopt(long)]
+        reverse: bool,
+    },
+    /// Checks the consistency of the data across schemas and prints the reports in JSON. Exits
+    /// with an error if any inconsistency is found.
+    #[structopt(name = "check-consistency")]
+    CheckConsistency {
+        /// Checks to run, all if none specified. One of: transaction_by_hash,
+        /// event_accumulator, stale_node_index, stale_resource_node_index.
+        #[structopt(long = "check")]
+        checks: Vec<ConsistencyCheck>,
+    },
 }
 
 /// Print out latest information stored in the DB.
@@ -152,6 +174,37 @@ fn list_accounts(db: &DiemDB) {
     info!("Total Accounts: {}", num_account);
 }
 
+fn list_column_families(db: &DiemDB) -> Result<()> {
+    let cf_names = db.get_inspector().column_families();
+    println!("{}", serde_json::to_string_pretty(&cf_names)?);
+    Ok(())
+}
+
+fn dump_column_family(db: &DiemDB, cf_name: &str, limit: usize, reverse: bool) -> Result<()> {
+    let rows = db
+        .get_inspector()
+        .dump_column_family(cf_name, limit, reverse)?;
+    println!("{}", serde_json::to_string_pretty(&rows)?);
+    Ok(())
+}
+
+/// Returns whether the DB is consistent.
+fn check_consistency(db: &DiemDB, mut checks: Vec<ConsistencyCheck>) -> Result<bool> {
+    if checks.is_empty() {
+        checks = ConsistencyCheck::all();
+    }
+    let inspector = db.get_inspector();
+    let reports = checks
+        .into_iter()
+        .map(|check| {
+            info!("Running consistency check {}.", check);
+            inspector.check_consistency(check)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    println!("{}", serde_json::to_string_pretty(&reports)?);
+    Ok(reports.iter().all(|report| report.is_consistent()))
+}
+
 fn main() {
     ::diem_logger::DiemLogger::builder().build();
 
@@ -171,8 +224,11 @@ fn main() {
         p,
         true, /* readonly */
         None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
-        true, /* account_count_migration, ignored anyway */
+        true,  /* account_count_migration, ignored anyway */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
     )
     .expect("Unable to open DiemDB");
     info!("DB opened successfully.");
@@ -191,6 +247,22 @@ fn main() {
             Command::ListAccounts => {
                 list_accounts(&db);
             }
+            Command::ListColumnFamilies => {
+                list_column_families(&db).expect("Unable to list column families");
+            }
+            Command::DumpColumnFamily {
+                cf_name,
+                limit,
+                reverse,
+            } => {
+                dump_column_family(&db, &cf_name, limit, reverse)
+                    .expect("Unable to dump column family");
+            }
+            Command::CheckConsistency { checks } => {
+                if !check_consistency(&db, checks).expect("Unable to check consistency") {
+                    std::process::exit(1);
+                }
+            }
         }
     } else {
         print_head(&db).expect("Unable to read information from DB");
@@ -109,4 +109,12 @@ where
         .unwrap();
         assert_eq!(iter.collect::<Result<Vec<_>>>().unwrap(), vec![]);
     }
+
+    for i in 0..=btree.len() {
+        let iter = JellyfishMerkleIterator::new_by_index(Arc::clone(&db), version, i).unwrap();
+        assert_eq!(
+            iter.collect::<Result<Vec<_>>>().unwrap(),
+            btree.clone().into_iter().skip(i).collect::<Vec<_>>(),
+        );
+    }
 }
@@ -13,10 +13,10 @@ use crate::{
     node_type::{InternalNode, Node, NodeKey},
     TreeReader,
 };
-use anyhow::{format_err, Result};
+use anyhow::{bail, ensure, format_err, Result};
 use diem_crypto::HashValue;
 use diem_types::{
-    nibble::{nibble_path::NibblePath, Nibble},
+    nibble::{nibble_path::NibblePath, Nibble, ROOT_NIBBLE_HEIGHT},
     transaction::Version,
 };
 use std::{marker::PhantomData, sync::Arc};
@@ -189,6 +189,87 @@ where
         })
     }
 
+    /// Constructs a new iterator that starts at the `start_idx`-th leaf (i.e., the
+    /// leaf with the `start_idx`-th smallest key) in this version of the tree. This
+    /// requires the leaf counts of all internal nodes on the path to be known.
+    pub fn new_by_index(reader: Arc<R>, version: Version, start_idx: usize) -> Result<Self> {
+        let mut parent_stack = vec![];
+
+        let mut current_node_key = NodeKey::new_empty_path(version);
+        let mut current_node = reader.get_node(&current_node_key)?;
+        let total_leaves = current_node
+            .leaf_count()
+            .ok_or_else(|| format_err!("Leaf counts not available."))?;
+        if start_idx >= total_leaves {
+            return Ok(Self {
+                reader,
+                version,
+                parent_stack,
+                done: true,
+                phantom_value: PhantomData,
+            });
+        }
+
+        let mut leaves_skipped = 0;
+        for _ in 0..=ROOT_NIBBLE_HEIGHT {
+            match current_node {
+                Node::Null => unreachable!("The Node::Null case has already been covered."),
+                Node::Leaf(_) => {
+                    ensure!(
+                        leaves_skipped == start_idx,
+                        "Bug: The leaf should be the exact one we are looking for.",
+                    );
+                    return Ok(Self {
+                        reader,
+                        version,
+                        parent_stack,
+                        done: false,
+                        phantom_value: PhantomData,
+                    });
+                }
+                Node::Internal(internal_node) => {
+                    let (child_index, child_version) =
+                        Self::skip_leaves(&internal_node, &mut leaves_skipped, start_idx)?;
+                    let next_node_key =
+                        current_node_key.gen_child_node_key(child_version, child_index);
+                    parent_stack.push(NodeVisitInfo::new_next_child_to_visit(
+                        current_node_key,
+                        internal_node,
+                        child_index,
+                    ));
+                    current_node_key = next_node_key;
+                }
+            };
+            current_node = reader.get_node(&current_node_key)?;
+        }
+
+        bail!("Bug: potential infinite loop.");
+    }
+
+    /// Skips the children (of the given internal node) that hold leaves before
+    /// the target leaf, and returns the index and version of the child holding it.
+    fn skip_leaves(
+        internal_node: &InternalNode,
+        leaves_skipped: &mut usize,
+        target_leaf_idx: usize,
+    ) -> Result<(Nibble, Version)> {
+        for child_index in (0..16u8).map(Nibble::from) {
+            if let Some(child) = internal_node.child(child_index) {
+                let child_leaf_count = child
+                    .leaf_count()
+                    .ok_or_else(|| format_err!("Leaf counts not available."))?;
+                // The index is 0-based, so N leaves must be skipped to reach the N-th leaf
+                if *leaves_skipped + child_leaf_count <= target_leaf_idx {
+                    *leaves_skipped += child_leaf_count;
+                } else {
+                    return Ok((child_index, child.version));
+                }
+            }
+        }
+
+        bail!("Bug: Internal node has less leaves than expected.");
+    }
+
     fn cleanup_stack(parent_stack: &mut Vec<NodeVisitInfo>) {
         while let Some(info) = parent_stack.last_mut() {
             if info.is_rightmost() {
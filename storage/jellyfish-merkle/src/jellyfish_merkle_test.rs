@@ -7,7 +7,7 @@ use crate::test_helper::{
     arb_tree_with_index, test_get_leaf_count, test_get_range_proof, test_get_with_proof,
     test_get_with_proof_with_distinct_last_nibble, ValueBlob,
 };
-use diem_crypto::HashValue;
+use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
 use diem_types::{nibble::Nibble, transaction::PRE_GENESIS_VERSION};
 use mock_tree_store::MockTreeStore;
 use proptest::{collection::hash_set, prelude::*};
@@ -570,6 +570,142 @@ fn test_put_value_sets() {
     }
 }
 
+/// Builds a tree out of `kvs` at version 0 and returns its store and root hash.
+fn build_tree(kvs: &[(HashValue, ValueBlob)]) -> (MockTreeStore<ValueBlob>, HashValue) {
+    let db = MockTreeStore::default();
+    let (roots, batch) = JellyfishMerkleTree::new(&db)
+        .batch_put_value_sets(vec![kvs.to_vec()], None, 0 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+    (db, roots[0])
+}
+
+#[test]
+fn test_delete() {
+    let mut rng: StdRng = StdRng::from_seed([0u8; 32]);
+    let kvs: Vec<_> = (0..200)
+        .map(|_| {
+            (
+                HashValue::random_with_rng(&mut rng),
+                ValueBlob::from(HashValue::random_with_rng(&mut rng).to_vec()),
+            )
+        })
+        .collect();
+    let (db, _root) = build_tree(&kvs);
+    let tree = JellyfishMerkleTree::new(&db);
+
+    // Delete every other key, a few keys a version.
+    let (kvs_kept, kvs_deleted): (Vec<_>, Vec<_>) = kvs
+        .iter()
+        .cloned()
+        .enumerate()
+        .partition(|(i, _)| i % 2 == 0);
+    let value_sets: Vec<_> = kvs_deleted
+        .chunks(10)
+        .map(|chunk| chunk.iter().map(|(_, (key, _))| (*key, None)).collect())
+        .collect();
+    let num_versions = value_sets.len() as Version;
+    let (roots, batch) = tree
+        .put_value_sets_with_deletions(value_sets, 1 /* first_version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    // The tree is the same as one that never had the deleted keys.
+    let kvs_kept: Vec<_> = kvs_kept.into_iter().map(|(_, kv)| kv).collect();
+    let (expected_db, expected_root) = build_tree(&kvs_kept);
+    assert_eq!(*roots.last().unwrap(), expected_root);
+    for (key, value) in &kvs_kept {
+        let (actual_value, proof) = tree.get_with_proof(*key, num_versions).unwrap();
+        assert_eq!(actual_value.as_ref(), Some(value));
+        proof.verify(expected_root, *key, Some(value)).unwrap();
+    }
+    for (_, (key, _)) in &kvs_deleted {
+        let (actual_value, proof) = tree.get_with_proof(*key, num_versions).unwrap();
+        assert!(actual_value.is_none());
+        proof.verify(expected_root, *key, None).unwrap();
+    }
+
+    // Earlier versions are intact, and all the nodes replaced along the way are stale.
+    assert_eq!(
+        tree.get(kvs_deleted[0].1 .0, 0).unwrap(),
+        Some(kvs_deleted[0].1 .1.clone())
+    );
+    db.purge_stale_nodes(num_versions).unwrap();
+    assert_eq!(db.num_nodes(), expected_db.num_nodes());
+}
+
+#[test]
+fn test_delete_collapses_internal_nodes() {
+    // Both keys share the first 10 nibbles, so there is a chain of internal nodes above them.
+    let key1 = HashValue::new([0x00u8; HashValue::LENGTH]);
+    let key2 = update_nibble(&key1, 10, 1);
+    let key3 = update_nibble(&key1, 0, 1);
+    let value = ValueBlob::from(vec![1u8]);
+    let (db, _root) = build_tree(&[
+        (key1, value.clone()),
+        (key2, value.clone()),
+        (key3, value.clone()),
+    ]);
+    let tree = JellyfishMerkleTree::new(&db);
+
+    let (roots, batch) = tree
+        .put_value_sets_with_deletions(vec![vec![(key2, None)]], 1 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+    let (expected_db, expected_root) = build_tree(&[(key1, value.clone()), (key3, value)]);
+    assert_eq!(roots[0], expected_root);
+    db.purge_stale_nodes(1).unwrap();
+    assert_eq!(db.num_nodes(), expected_db.num_nodes());
+}
+
+#[test]
+fn test_delete_all_and_reinsert() {
+    let key1 = HashValue::random();
+    let key2 = HashValue::random();
+    let value = ValueBlob::from(vec![1u8]);
+    let (db, root) = build_tree(&[(key1, value.clone())]);
+    let tree = JellyfishMerkleTree::new(&db);
+
+    let (roots, batch) = tree
+        .put_value_sets_with_deletions(
+            vec![
+                vec![(key1, None)],
+                vec![(key1, None)],
+                vec![(key2, Some(value.clone()))],
+                vec![(key2, None), (key1, Some(value.clone()))],
+            ],
+            1, /* first_version */
+        )
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    assert_eq!(roots[0], *SPARSE_MERKLE_PLACEHOLDER_HASH);
+    assert_eq!(roots[1], *SPARSE_MERKLE_PLACEHOLDER_HASH);
+    assert_eq!(tree.get(key2, 3).unwrap(), Some(value.clone()));
+    assert_eq!(roots[3], root);
+    assert_eq!(tree.get(key1, 4).unwrap(), Some(value));
+    assert_eq!(tree.get(key2, 4).unwrap(), None);
+}
+
+#[test]
+fn test_put_value_sets_with_deletions_carries_root_over() {
+    let key = HashValue::random();
+    let value = ValueBlob::from(vec![1u8]);
+    let (db, root) = build_tree(&[(key, value.clone())]);
+    let tree = JellyfishMerkleTree::new(&db);
+
+    // Neither an empty value set nor the deletion of a missing key changes the tree, yet each
+    // version gets a root.
+    let (roots, batch) = tree
+        .put_value_sets_with_deletions(vec![vec![], vec![(HashValue::random(), None)]], 1)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+    assert_eq!(roots, vec![root, root]);
+    assert_eq!(tree.get(key, 2).unwrap(), Some(value));
+    assert_eq!(tree.get_root_hash(1).unwrap(), root);
+    assert_eq!(tree.get_root_hash(2).unwrap(), root);
+}
+
 fn many_keys_get_proof_and_verify_tree_root(seed: &[u8], num_keys: usize) {
     assert!(seed.len() < 32);
     let mut actual_seed = [0u8; 32];
@@ -141,11 +141,14 @@ pub trait Value: Clone + CryptoHash + Serialize + DeserializeOwned {}
 pub trait TestValue: Value + Arbitrary + std::fmt::Debug + Eq + PartialEq + 'static {}
 
 // This crate still depends on types for a few things, therefore we implement `Value` and
-// `TestValue` for `AccountStateBlob` here. Ideally the module that defines the specific value like
-// `AccountStateBlob` should import the `Value` trait and implement it there.
+// `TestValue` for `AccountStateBlob` and `StateValue` here. Ideally the module that defines the
+// specific value like `AccountStateBlob` should import the `Value` trait and implement it there.
 impl Value for diem_types::account_state_blob::AccountStateBlob {}
 #[cfg(any(test, feature = "fuzzing"))]
 impl TestValue for diem_types::account_state_blob::AccountStateBlob {}
+impl Value for diem_types::state_value::StateValue {}
+#[cfg(any(test, feature = "fuzzing"))]
+impl TestValue for diem_types::state_value::StateValue {}
 
 /// Node batch that will be written into db atomically with other batches.
 pub type NodeBatch<V> = BTreeMap<NodeKey, Node<V>>;
@@ -227,6 +230,18 @@ impl<'a, V> std::iter::Iterator for NibbleRangeIterator<'a, V> {
     }
 }
 
+/// The outcome of deleting a key from a subtree.
+enum Deletion<V> {
+    /// The key wasn't in the subtree.
+    Unchanged,
+    /// The subtree was the leaf of the key, which got removed.
+    Removed,
+    /// The subtree is down to a single leaf, which is to take the place of the subtree root.
+    Leaf(LeafNode<V>),
+    /// The new root of the subtree.
+    Internal(NodeKey, InternalNode),
+}
+
 /// The Jellyfish Merkle tree data structure. See [`crate`] for description.
 pub struct JellyfishMerkleTree<'a, R, V> {
     reader: &'a R,
@@ -620,6 +635,41 @@ where
         Ok(tree_cache.into())
     }
 
+    /// Like [`put_value_sets`](struct.JellyfishMerkleTree.html#method.put_value_sets), except that
+    /// a key with a `None` value is removed from the tree. A value set may be empty, or may leave
+    /// the tree unchanged, in which case the root of the previous version is carried over to the
+    /// new version.
+    pub fn put_value_sets_with_deletions(
+        &self,
+        value_sets: Vec<Vec<(HashValue, Option<V>)>>,
+        first_version: Version,
+    ) -> Result<(Vec<HashValue>, TreeUpdateBatch<V>)> {
+        let mut tree_cache = TreeCache::new(self.reader, first_version)?;
+        for (idx, value_set) in value_sets.into_iter().enumerate() {
+            let version = first_version + idx as u64;
+            for (key, value) in value_set {
+                match value {
+                    Some(value) => self.put(key, value, version, &mut tree_cache)?,
+                    None => self.delete(key, version, &mut tree_cache)?,
+                }
+            }
+
+            // Every version needs a root for the next version to be built upon.
+            let root_node_key = tree_cache.get_root_node_key().clone();
+            if root_node_key.version() != version {
+                let root_node = tree_cache.get_node(&root_node_key)?;
+                tree_cache.delete_node(&root_node_key, root_node.is_leaf());
+                let new_root_node_key = NodeKey::new_empty_path(version);
+                tree_cache.put_node(new_root_node_key.clone(), root_node)?;
+                tree_cache.set_root_node_key(new_root_node_key);
+            }
+            // Freezes the current cache to make all contents in the current cache immutable.
+            tree_cache.freeze();
+        }
+
+        Ok(tree_cache.into())
+    }
+
     fn put(
         &self,
         key: HashValue,
@@ -856,6 +906,115 @@ where
         Ok((node_key, next_internal_node))
     }
 
+    fn delete(
+        &self,
+        key: HashValue,
+        version: Version,
+        tree_cache: &mut TreeCache<R, V>,
+    ) -> Result<()> {
+        let root_node_key = tree_cache.get_root_node_key().clone();
+        let new_root_node = match self.delete_at(root_node_key, key, version, tree_cache)? {
+            Deletion::Unchanged => return Ok(()),
+            Deletion::Internal(new_root_node_key, _) => {
+                tree_cache.set_root_node_key(new_root_node_key);
+                return Ok(());
+            }
+            Deletion::Removed => Node::new_null(),
+            Deletion::Leaf(leaf_node) => leaf_node.into(),
+        };
+        let new_root_node_key = NodeKey::new_empty_path(version);
+        tree_cache.put_node(new_root_node_key.clone(), new_root_node)?;
+        tree_cache.set_root_node_key(new_root_node_key);
+        Ok(())
+    }
+
+    /// Helper function for recursive deletion of `key` from the subtree that starts from the
+    /// current [`NodeKey`](node_type/struct.NodeKey.html). An internal node left with a single
+    /// leaf is replaced by that leaf, which is handed up to the parent to be put where the
+    /// internal node was, so that the tree ends up the same as if the key had never been inserted.
+    fn delete_at(
+        &self,
+        mut node_key: NodeKey,
+        key: HashValue,
+        version: Version,
+        tree_cache: &mut TreeCache<R, V>,
+    ) -> Result<Deletion<V>> {
+        let internal_node = match tree_cache.get_node(&node_key)? {
+            Node::Null => return Ok(Deletion::Unchanged),
+            Node::Leaf(leaf_node) => {
+                if leaf_node.account_key() != key {
+                    return Ok(Deletion::Unchanged);
+                }
+                tree_cache.delete_node(&node_key, true /* is_leaf */);
+                return Ok(Deletion::Removed);
+            }
+            Node::Internal(internal_node) => internal_node,
+        };
+
+        let child_index = key.get_nibble(node_key.nibble_path().num_nibbles());
+        let child_version = match internal_node.child(child_index) {
+            Some(child) => child.version,
+            None => return Ok(Deletion::Unchanged),
+        };
+        let child_node_key = node_key.gen_child_node_key(child_version, child_index);
+        let deletion = self.delete_at(child_node_key, key, version, tree_cache)?;
+
+        let mut children: Children = internal_node.into();
+        // The leaf the child collapsed into, which is yet to be put under this node.
+        let mut collapsed_leaf = None;
+        match deletion {
+            Deletion::Unchanged => return Ok(Deletion::Unchanged),
+            Deletion::Removed => {
+                children.remove(&child_index);
+            }
+            Deletion::Leaf(leaf_node) => {
+                children.insert(
+                    child_index,
+                    Child::new(leaf_node.hash(), version, NodeType::Leaf),
+                );
+                collapsed_leaf = Some(leaf_node);
+            }
+            Deletion::Internal(_, new_child_node) => {
+                children.insert(
+                    child_index,
+                    Child::new(new_child_node.hash(), version, new_child_node.node_type()),
+                );
+            }
+        }
+        // We always delete the existing internal node here because it will not be referenced anyway
+        // since this version.
+        tree_cache.delete_node(&node_key, false /* is_leaf */);
+
+        if children.len() == 1 {
+            let (only_child_index, only_child) = children.iter().next().expect("Has one child.");
+            if only_child.is_leaf() {
+                if let Some(leaf_node) = collapsed_leaf {
+                    return Ok(Deletion::Leaf(leaf_node));
+                }
+                let leaf_node_key =
+                    node_key.gen_child_node_key(only_child.version, *only_child_index);
+                return match tree_cache.get_node(&leaf_node_key)? {
+                    Node::Leaf(leaf_node) => {
+                        tree_cache.delete_node(&leaf_node_key, true /* is_leaf */);
+                        Ok(Deletion::Leaf(leaf_node))
+                    }
+                    _ => bail!("Leaf node expected at {:?}", leaf_node_key),
+                };
+            }
+        }
+
+        if let Some(leaf_node) = collapsed_leaf {
+            tree_cache.put_node(
+                node_key.gen_child_node_key(version, child_index),
+                leaf_node.into(),
+            )?;
+        }
+        let new_internal_node = InternalNode::new_migration(children, self.leaf_count_migration);
+        node_key.set_version(version);
+        tree_cache.put_node(node_key.clone(), new_internal_node.clone().into())?;
+        Ok(Deletion::Internal(node_key, new_internal_node))
+    }
+
     /// Helper function for creating leaf nodes. Returns the newly created leaf node.
     fn create_leaf_node(
         node_key: NodeKey,
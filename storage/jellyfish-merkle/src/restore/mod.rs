@@ -14,7 +14,7 @@ use crate::{
     },
     NibbleExt, NodeBatch, TreeReader, TreeWriter, ROOT_NIBBLE_HEIGHT,
 };
-use anyhow::{bail, ensure, Result};
+use anyhow::{bail, ensure, format_err, Result};
 use diem_crypto::{
     hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
@@ -116,7 +116,7 @@ where
 
 pub struct JellyfishMerkleRestore<V> {
     /// The underlying storage.
-    store: Arc<dyn TreeWriter<V>>,
+    store: Arc<dyn TreeWriter<V> + Send + Sync>,
 
     /// The version of the tree we are restoring.
     version: Version,
@@ -163,8 +163,9 @@ pub struct JellyfishMerkleRestore<V> {
     /// The number of keys we have received since the most recent restart.
     num_keys_received: u64,
 
-    /// When the restoration process finishes, we expect the tree to have this root hash.
-    expected_root_hash: HashValue,
+    /// When the restoration process finishes, we expect the tree to have this root hash. `None` if
+    /// the tree is built from trusted keys that come without proofs.
+    expected_root_hash: Option<HashValue>,
 
     /// Whether to use the new internal node format where leaf counts are written.
     leaf_count_migration: bool,
@@ -174,7 +175,7 @@ impl<V> JellyfishMerkleRestore<V>
 where
     V: crate::Value,
 {
-    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V>>(
+    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
@@ -204,18 +205,42 @@ where
             frozen_nodes: NodeBatch::new(),
             previous_leaf,
             num_keys_received: 0,
-            expected_root_hash,
+            expected_root_hash: Some(expected_root_hash),
             leaf_count_migration,
         })
     }
 
-    pub fn new_overwrite<D: 'static + TreeWriter<V>>(
+    pub fn new_overwrite<D: 'static + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
         leaf_count_migration: bool,
     ) -> Result<Self> {
-        Ok(Self {
+        Ok(Self::new_overwrite_impl(
+            store,
+            version,
+            Some(expected_root_hash),
+            leaf_count_migration,
+        ))
+    }
+
+    /// Like [`Self::new_overwrite`], but for a tree built from trusted keys (e.g. derived from
+    /// another tree in the same DB), which are added by [`Self::add_chunk_unverified`].
+    pub fn new_unverified<D: 'static + TreeWriter<V> + Send + Sync>(
+        store: Arc<D>,
+        version: Version,
+        leaf_count_migration: bool,
+    ) -> Self {
+        Self::new_overwrite_impl(store, version, None, leaf_count_migration)
+    }
+
+    fn new_overwrite_impl<D: 'static + TreeWriter<V> + Send + Sync>(
+        store: Arc<D>,
+        version: Version,
+        expected_root_hash: Option<HashValue>,
+        leaf_count_migration: bool,
+    ) -> Self {
+        Self {
             store,
             version,
             partial_nodes: vec![InternalInfo::new_empty(NodeKey::new_empty_path(version))],
@@ -224,7 +249,7 @@ where
             num_keys_received: 0,
             expected_root_hash,
             leaf_count_migration,
-        })
+        }
     }
 
     /// Recovers partial nodes from storage. We do this by looking at all the ancestors of the
@@ -309,6 +334,34 @@ where
         chunk: Vec<(HashValue, V)>,
         proof: SparseMerkleRangeProof,
     ) -> Result<()> {
+        self.add_keys(chunk)?;
+
+        // Verify what we have added so far is all correct.
+        self.verify(proof)?;
+
+        // Write the frozen nodes to storage.
+        self.store.write_node_batch(&self.frozen_nodes)?;
+        self.frozen_nodes.clear();
+
+        Ok(())
+    }
+
+    /// Restores a chunk of trusted keys without a proof, for a restore created by
+    /// [`Self::new_unverified`]. The keys must still come in increasing order.
+    pub fn add_chunk_unverified(&mut self, chunk: Vec<(HashValue, V)>) -> Result<()> {
+        ensure!(
+            self.expected_root_hash.is_none(),
+            "Chunks of a tree with a known root hash must be verified.",
+        );
+        self.add_keys(chunk)?;
+
+        self.store.write_node_batch(&self.frozen_nodes)?;
+        self.frozen_nodes.clear();
+
+        Ok(())
+    }
+
+    fn add_keys(&mut self, chunk: Vec<(HashValue, V)>) -> Result<()> {
         ensure!(!chunk.is_empty(), "Should not add empty chunks.");
 
         for (key, value) in chunk {
@@ -323,13 +376,6 @@ where
             self.num_keys_received += 1;
         }
 
-        // Verify what we have added so far is all correct.
-        self.verify(proof)?;
-
-        // Write the frozen nodes to storage.
-        self.store.write_node_batch(&self.frozen_nodes)?;
-        self.frozen_nodes.clear();
-
         Ok(())
     }
 
@@ -548,6 +594,9 @@ where
     /// by combining all existing accounts and `proof`.
     #[allow(clippy::collapsible_if)]
     fn verify(&self, proof: SparseMerkleRangeProof) -> Result<()> {
+        let expected_root_hash = self
+            .expected_root_hash
+            .ok_or_else(|| format_err!("Chunks without a known root hash can't be verified."))?;
         let previous_leaf = self
             .previous_leaf
             .as_ref()
@@ -610,7 +659,7 @@ where
 
         // Verify the proof now that we have all the siblings
         proof.verify(
-            self.expected_root_hash,
+            expected_root_hash,
             SparseMerkleLeafNode::new(previous_key, previous_leaf.value_hash()),
             left_siblings,
         )
@@ -689,3 +738,34 @@ where
         self.store.write_node_batch(&self.frozen_nodes)
     }
 }
+
+/// The interface used to incrementally receive and persist a snapshot of the state tree (e.g.,
+/// when a node synchronizes account states from other nodes).
+pub trait StateSnapshotReceiver<V>: Send {
+    /// Adds a chunk of accounts (verified using the given proof) to the snapshot.
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, V)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Finishes the snapshot once all accounts have been received.
+    fn finish_box(self: Box<Self>) -> Result<()>;
+}
+
+impl<V> StateSnapshotReceiver<V> for JellyfishMerkleRestore<V>
+where
+    V: crate::Value + Send,
+{
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, V)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        JellyfishMerkleRestore::add_chunk(self, chunk, proof)
+    }
+
+    fn finish_box(self: Box<Self>) -> Result<()> {
+        self.finish()
+    }
+}
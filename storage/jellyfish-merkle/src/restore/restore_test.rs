@@ -114,7 +114,7 @@ fn restore_without_interruption<V>(
     target_db: &Arc<MockTreeStore<V>>,
     try_resume: bool,
 ) where
-    V: crate::TestValue,
+    V: crate::TestValue + Send + Sync,
 {
     let (db, source_version) = init_mock_db(&btree.iter().map(|(k, v)| (*k, v.clone())).collect());
     let tree = JellyfishMerkleTree::new(&db);
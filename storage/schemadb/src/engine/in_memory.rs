@@ -0,0 +1,214 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    engine::{RawIterator, StorageEngine},
+    ColumnFamilyName, ReadOptions, SchemaBatch, WriteOp,
+};
+use anyhow::{bail, format_err, Result};
+use diem_infallible::RwLock;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Bound::{Excluded, Included, Unbounded},
+    path::Path,
+    sync::Arc,
+};
+
+type ColumnFamilyData = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// A [`StorageEngine`] holding all data in memory, one ordered map per column family. Nothing is
+/// persisted, so the data is lost once the engine is dropped.
+///
+/// Column families are copy-on-write: iterators hold a reference to the data at the time they
+/// were created, and a write only copies a column family while an iterator still refers to it.
+pub struct InMemoryEngine {
+    column_families: RwLock<HashMap<ColumnFamilyName, Arc<ColumnFamilyData>>>,
+}
+
+impl InMemoryEngine {
+    /// Creates an empty engine with all the given column families.
+    pub fn new(column_families: &[ColumnFamilyName]) -> Self {
+        Self {
+            column_families: RwLock::new(
+                column_families
+                    .iter()
+                    .map(|cf_name| (*cf_name, Arc::new(ColumnFamilyData::new())))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn get_cf_data(&self, cf_name: &str) -> Result<Arc<ColumnFamilyData>> {
+        self.column_families
+            .read()
+            .get(cf_name)
+            .cloned()
+            .ok_or_else(|| column_family_not_found(cf_name))
+    }
+}
+
+impl fmt::Debug for InMemoryEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Only summarize the data, as dumping it all is rarely useful.
+        let num_keys_by_cf: BTreeMap<_, _> = self
+            .column_families
+            .read()
+            .iter()
+            .map(|(cf_name, cf_data)| (*cf_name, cf_data.len()))
+            .collect();
+        f.debug_struct("InMemoryEngine")
+            .field("num_keys_by_cf", &num_keys_by_cf)
+            .finish()
+    }
+}
+
+impl StorageEngine for InMemoryEngine {
+    fn get_cf(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_cf_data(cf_name)?.get(key).cloned())
+    }
+
+    fn write_batch(&self, batch: &SchemaBatch) -> Result<usize> {
+        let mut column_families = self.column_families.write();
+
+        // Make sure the whole batch can be applied before touching any data.
+        for (cf_name, _) in batch.rows() {
+            if !column_families.contains_key(cf_name) {
+                return Err(column_family_not_found(cf_name));
+            }
+        }
+
+        let mut size = 0;
+        for (cf_name, rows) in batch.rows() {
+            let cf_data = Arc::make_mut(
+                column_families
+                    .get_mut(cf_name)
+                    .expect("Column family existence checked above."),
+            );
+            for (key, write_op) in rows {
+                match write_op {
+                    WriteOp::Value(value) => {
+                        size += key.len() + value.len();
+                        cf_data.insert(key.clone(), value.clone());
+                    }
+                    WriteOp::Deletion => {
+                        size += key.len();
+                        cf_data.remove(key);
+                    }
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    fn delete_range_cf(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()> {
+        let mut column_families = self.column_families.write();
+        let cf_data = Arc::make_mut(
+            column_families
+                .get_mut(cf_name)
+                .ok_or_else(|| column_family_not_found(cf_name))?,
+        );
+
+        let mut deleted = cf_data.split_off(begin);
+        let mut retained = deleted.split_off(end);
+        cf_data.append(&mut retained);
+        Ok(())
+    }
+
+    fn raw_iterator_cf(
+        &self,
+        cf_name: &str,
+        _opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>> {
+        Ok(Box::new(InMemoryIterator {
+            data: self.get_cf_data(cf_name)?,
+            position: None,
+        }))
+    }
+
+    fn flush_cf(&self, cf_name: &str) -> Result<()> {
+        // There's nothing buffered to flush.
+        self.get_cf_data(cf_name)?;
+        Ok(())
+    }
+
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
+        bail!(
+            "Property \"{}\" of column family \"{}\" is not supported by the in-memory engine.",
+            property_name,
+            cf_name,
+        )
+    }
+
+    fn create_checkpoint(&self, _path: &Path) -> Result<()> {
+        bail!("Checkpoints are not supported by the in-memory engine.")
+    }
+}
+
+fn column_family_not_found(cf_name: &str) -> anyhow::Error {
+    format_err!(
+        "Column family not found in the in-memory engine: {}",
+        cf_name
+    )
+}
+
+/// A [`RawIterator`] over a snapshot of an in-memory column family.
+struct InMemoryIterator {
+    data: Arc<ColumnFamilyData>,
+    /// The key the iterator is positioned at, `None` if the iterator is invalid.
+    position: Option<Vec<u8>>,
+}
+
+/// Returns the key of the first entry in `entries` (if any).
+fn first_key<'a>(mut entries: impl Iterator<Item = (&'a Vec<u8>, &'a Vec<u8>)>) -> Option<Vec<u8>> {
+    entries.next().map(|(key, _value)| key.clone())
+}
+
+impl RawIterator for InMemoryIterator {
+    fn seek_to_first(&mut self) {
+        self.position = self.data.keys().next().cloned();
+    }
+
+    fn seek_to_last(&mut self) {
+        self.position = self.data.keys().next_back().cloned();
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.position = first_key(self.data.range::<[u8], _>((Included(key), Unbounded)));
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        self.position = first_key(self.data.range::<[u8], _>((Unbounded, Included(key))).rev());
+    }
+
+    fn next(&mut self) {
+        if let Some(key) = self.position.take() {
+            self.position = first_key(self.data.range((Excluded(key), Unbounded)));
+        }
+    }
+
+    fn prev(&mut self) {
+        if let Some(key) = self.position.take() {
+            self.position = first_key(self.data.range((Unbounded, Excluded(key))).rev());
+        }
+    }
+
+    fn valid(&self) -> bool {
+        self.position.is_some()
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        self.position.as_deref()
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        self.position
+            .as_ref()
+            .and_then(|key| self.data.get(key))
+            .map(Vec::as_slice)
+    }
+
+    fn status(&self) -> Result<()> {
+        Ok(())
+    }
+}
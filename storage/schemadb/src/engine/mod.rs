@@ -0,0 +1,86 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the key-value storage engine interface that [`DB`](crate::DB) is built on,
+//! together with the engines currently supported:
+//! - [`RocksdbEngine`]: the persistent engine backed by RocksDB, used in production.
+//! - [`InMemoryEngine`]: a volatile engine backed by ordered maps, mostly useful for tests.
+//!
+//! Engines only deal with raw bytes. All (de)serialization according to
+//! [`Schema`](crate::schema::Schema)s happens in the layers above.
+
+mod in_memory;
+mod rocksdb_engine;
+
+pub use in_memory::InMemoryEngine;
+pub use rocksdb_engine::RocksdbEngine;
+
+use crate::{ReadOptions, SchemaBatch};
+use anyhow::Result;
+use std::{fmt::Debug, path::Path};
+
+/// A key-value storage engine organized in column families, where each column family holds keys
+/// in (binary) lexicographic order.
+pub trait StorageEngine: Debug + Send + Sync {
+    /// Reads the value of `key` in the given column family.
+    fn get_cf(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Applies all updates in the batch atomically. Returns the size of the written data in bytes
+    /// (as serialized by the engine).
+    fn write_batch(&self, batch: &SchemaBatch) -> Result<usize>;
+
+    /// Deletes all keys in range [begin, end) of the given column family.
+    fn delete_range_cf(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()>;
+
+    /// Returns an (unpositioned) iterator over a consistent view of the given column family. The
+    /// iterator doesn't observe updates written after its creation.
+    fn raw_iterator_cf(
+        &self,
+        cf_name: &str,
+        opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>>;
+
+    /// Flushes all buffered data of the given column family.
+    fn flush_cf(&self, cf_name: &str) -> Result<()>;
+
+    /// Returns the value of an engine specific integer property of the given column family.
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64>;
+
+    /// Creates a physical copy of the data in directory specified by `path`.
+    fn create_checkpoint(&self, path: &Path) -> Result<()>;
+}
+
+/// A bidirectional cursor over the raw key-value pairs of a column family. It's valid only when
+/// positioned at an existing key. Iterators are `Send` so that they can be consumed by async
+/// tasks, e.g. when streaming backups.
+pub trait RawIterator: Send {
+    /// Seeks to the first key.
+    fn seek_to_first(&mut self);
+
+    /// Seeks to the last key.
+    fn seek_to_last(&mut self);
+
+    /// Seeks to the first key equal to or greater than `key`.
+    fn seek(&mut self, key: &[u8]);
+
+    /// Seeks to the last key less than or equal to `key`.
+    fn seek_for_prev(&mut self, key: &[u8]);
+
+    /// Moves to the next key. The iterator must be valid.
+    fn next(&mut self);
+
+    /// Moves to the previous key. The iterator must be valid.
+    fn prev(&mut self);
+
+    /// Returns true iff the iterator is positioned at a key.
+    fn valid(&self) -> bool;
+
+    /// Returns the key at the current position, if valid.
+    fn key(&self) -> Option<&[u8]>;
+
+    /// Returns the value at the current position, if valid.
+    fn value(&self) -> Option<&[u8]>;
+
+    /// Returns the error (if any) the iterator has encountered.
+    fn status(&self) -> Result<()>;
+}
@@ -0,0 +1,191 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    engine::{RawIterator, StorageEngine},
+    ColumnFamilyName, ReadOptions, SchemaBatch, WriteOp,
+};
+use anyhow::{format_err, Result};
+use std::path::Path;
+
+/// A [`StorageEngine`] backed by [RocksDB](https://rocksdb.org/).
+#[derive(Debug)]
+pub struct RocksdbEngine {
+    inner: rocksdb::DB,
+}
+
+impl RocksdbEngine {
+    /// Opens (or creates, depending on `db_opts`) the RocksDB at `path` with all the given column
+    /// families.
+    pub fn open(
+        path: impl AsRef<Path>,
+        column_families: &[ColumnFamilyName],
+        db_opts: &rocksdb::Options,
+    ) -> Result<Self> {
+        let inner = rocksdb::DB::open_cf_descriptors(
+            db_opts,
+            path,
+            column_families.iter().map(|cf_name| {
+                let mut cf_opts = rocksdb::Options::default();
+                cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+                rocksdb::ColumnFamilyDescriptor::new((*cf_name).to_string(), cf_opts)
+            }),
+        )?;
+        Ok(Self { inner })
+    }
+
+    /// Opens the RocksDB at `path` in readonly mode.
+    pub fn open_readonly(
+        path: impl AsRef<Path>,
+        column_families: &[ColumnFamilyName],
+        db_opts: &rocksdb::Options,
+    ) -> Result<Self> {
+        let error_if_log_file_exists = false;
+        let inner = rocksdb::DB::open_cf_for_read_only(
+            db_opts,
+            path,
+            column_families,
+            error_if_log_file_exists,
+        )?;
+        Ok(Self { inner })
+    }
+
+    /// Opens the RocksDB at `primary_path` as a secondary instance.
+    pub fn open_as_secondary<P: AsRef<Path>>(
+        primary_path: P,
+        secondary_path: P,
+        column_families: &[ColumnFamilyName],
+        db_opts: &rocksdb::Options,
+    ) -> Result<Self> {
+        let inner = rocksdb::DB::open_cf_as_secondary(
+            db_opts,
+            primary_path,
+            secondary_path,
+            column_families,
+        )?;
+        Ok(Self { inner })
+    }
+
+    fn get_cf_handle(&self, cf_name: &str) -> Result<&rocksdb::ColumnFamily> {
+        self.inner.cf_handle(cf_name).ok_or_else(|| {
+            format_err!(
+                "DB::cf_handle not found for column family name: {}",
+                cf_name
+            )
+        })
+    }
+}
+
+impl StorageEngine for RocksdbEngine {
+    fn get_cf(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(self.inner.get_cf(cf_handle, key)?)
+    }
+
+    fn write_batch(&self, batch: &SchemaBatch) -> Result<usize> {
+        let mut db_batch = rocksdb::WriteBatch::default();
+        for (cf_name, rows) in batch.rows() {
+            let cf_handle = self.get_cf_handle(cf_name)?;
+            for (key, write_op) in rows {
+                match write_op {
+                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
+                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
+                }
+            }
+        }
+        let serialized_size = db_batch.size_in_bytes();
+
+        self.inner.write_opt(db_batch, &default_write_options())?;
+        Ok(serialized_size)
+    }
+
+    fn delete_range_cf(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        self.inner.delete_range_cf(cf_handle, begin, end)?;
+        Ok(())
+    }
+
+    fn raw_iterator_cf(
+        &self,
+        cf_name: &str,
+        opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(Box::new(self.inner.raw_iterator_cf_opt(cf_handle, opts)))
+    }
+
+    fn flush_cf(&self, cf_name: &str) -> Result<()> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        self.inner.flush_cf(cf_handle)?;
+        Ok(())
+    }
+
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
+        self.inner
+            .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
+            .ok_or_else(|| {
+                format_err!(
+                    "Unable to get property \"{}\" of  column family \"{}\".",
+                    property_name,
+                    cf_name,
+                )
+            })
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
+        Ok(())
+    }
+}
+
+impl<'a> RawIterator for rocksdb::DBRawIterator<'a> {
+    fn seek_to_first(&mut self) {
+        rocksdb::DBRawIterator::seek_to_first(self)
+    }
+
+    fn seek_to_last(&mut self) {
+        rocksdb::DBRawIterator::seek_to_last(self)
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        rocksdb::DBRawIterator::seek(self, key)
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        rocksdb::DBRawIterator::seek_for_prev(self, key)
+    }
+
+    fn next(&mut self) {
+        rocksdb::DBRawIterator::next(self)
+    }
+
+    fn prev(&mut self) {
+        rocksdb::DBRawIterator::prev(self)
+    }
+
+    fn valid(&self) -> bool {
+        rocksdb::DBRawIterator::valid(self)
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        rocksdb::DBRawIterator::key(self)
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        rocksdb::DBRawIterator::value(self)
+    }
+
+    fn status(&self) -> Result<()> {
+        rocksdb::DBRawIterator::status(self)?;
+        Ok(())
+    }
+}
+
+/// For now we always use synchronous writes. This makes sure that once the operation returns
+/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
+/// selectively turning this off for some non-critical writes to improve performance.
+fn default_write_options() -> rocksdb::WriteOptions {
+    let mut opts = rocksdb::WriteOptions::default();
+    opts.set_sync(true);
+    opts
+}
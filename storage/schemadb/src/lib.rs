@@ -3,21 +3,24 @@
 
 #![forbid(unsafe_code)]
 
-//! This library implements a schematized DB on top of [RocksDB](https://rocksdb.org/). It makes
-//! sure all data passed in and out are structured according to predefined schemas and prevents
-//! access to raw keys and values. This library also enforces a set of Diem specific DB options,
-//! like custom comparators and schema-to-column-family mapping.
+//! This library implements a schematized DB on top of a key-value storage engine, which is
+//! [RocksDB](https://rocksdb.org/) in production (see [`engine`] for the engines available). It
+//! makes sure all data passed in and out are structured according to predefined schemas and
+//! prevents access to raw keys and values. This library also enforces a set of Diem specific DB
+//! options, like custom comparators and schema-to-column-family mapping.
 //!
 //! It requires that different kinds of key-value pairs be stored in separate column
 //! families.  To use this library to store a kind of key-value pairs, the user needs to use the
 //! [`define_schema!`] macro to define the schema name, the types of key and value, and name of the
 //! column family.
 
+pub mod engine;
 mod metrics;
 #[macro_use]
 pub mod schema;
 
 use crate::{
+    engine::{InMemoryEngine, RawIterator, RocksdbEngine, StorageEngine},
     metrics::{
         DIEM_SCHEMADB_BATCH_COMMIT_BYTES, DIEM_SCHEMADB_BATCH_COMMIT_LATENCY_SECONDS,
         DIEM_SCHEMADB_DELETES, DIEM_SCHEMADB_GET_BYTES, DIEM_SCHEMADB_GET_LATENCY_SECONDS,
@@ -25,7 +28,7 @@ use crate::{
     },
     schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
 };
-use anyhow::{ensure, format_err, Result};
+use anyhow::{ensure, Result};
 use diem_logger::prelude::*;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
@@ -47,8 +50,9 @@ pub type ColumnFamilyName = &'static str;
 /// [`LedgerInfo`](../types/ledger_info/struct.LedgerInfo.html).
 pub const DEFAULT_CF_NAME: ColumnFamilyName = "default";
 
+/// A raw update of a single key in a [`SchemaBatch`].
 #[derive(Debug)]
-enum WriteOp {
+pub enum WriteOp {
     Value(Vec<u8>),
     Deletion,
 }
@@ -88,6 +92,11 @@ impl SchemaBatch {
 
         Ok(())
     }
+
+    /// Returns the raw updates in the batch, grouped by column family.
+    pub fn rows(&self) -> impl Iterator<Item = (ColumnFamilyName, &BTreeMap<Vec<u8>, WriteOp>)> {
+        self.rows.iter().map(|(cf_name, rows)| (*cf_name, rows))
+    }
 }
 
 pub enum ScanDirection {
@@ -98,7 +107,7 @@ pub enum ScanDirection {
 /// DB Iterator parameterized on [`Schema`] that seeks with [`Schema::Key`] and yields
 /// [`Schema::Key`] and [`Schema::Value`]
 pub struct SchemaIterator<'a, S> {
-    db_iter: rocksdb::DBRawIterator<'a>,
+    db_iter: Box<dyn RawIterator + 'a>,
     direction: ScanDirection,
     phantom: PhantomData<S>,
 }
@@ -107,7 +116,7 @@ impl<'a, S> SchemaIterator<'a, S>
 where
     S: Schema,
 {
-    fn new(db_iter: rocksdb::DBRawIterator<'a>, direction: ScanDirection) -> Self {
+    fn new(db_iter: Box<dyn RawIterator + 'a>, direction: ScanDirection) -> Self {
         SchemaIterator {
             db_iter,
             direction,
@@ -188,12 +197,12 @@ where
     }
 }
 
-/// This DB is a schematized RocksDB wrapper where all data passed in and out are typed according to
-/// [`Schema`]s.
+/// This DB is a schematized wrapper of a [`StorageEngine`] (RocksDB, unless opened otherwise) where
+/// all data passed in and out are typed according to [`Schema`]s.
 #[derive(Debug)]
 pub struct DB {
     name: &'static str, // for logging
-    inner: rocksdb::DB,
+    inner: Box<dyn StorageEngine>,
     column_families: Vec<ColumnFamilyName>,
 }
 
@@ -206,20 +215,10 @@ impl DB {
         column_families: Vec<ColumnFamilyName>,
         db_opts: &rocksdb::Options,
     ) -> Result<Self> {
-        {
-            let cfs_set: HashSet<_> = column_families.iter().collect();
-            ensure!(
-                cfs_set.contains(&DEFAULT_CF_NAME),
-                "No \"default\" column family name is provided.",
-            );
-            ensure!(
-                cfs_set.len() == column_families.len(),
-                "Duplicate column family name found.",
-            );
-        }
+        Self::verify_column_families(&column_families)?;
 
-        let db = DB::open_cf(db_opts, path, name, column_families)?;
-        Ok(db)
+        let inner = RocksdbEngine::open(path, &column_families, db_opts)?;
+        Ok(Self::log_construct(name, column_families, Box::new(inner)))
     }
 
     /// Open db in readonly mode
@@ -231,7 +230,8 @@ impl DB {
         column_families: Vec<ColumnFamilyName>,
         db_opts: &rocksdb::Options,
     ) -> Result<Self> {
-        DB::open_cf_readonly(db_opts, path, name, column_families)
+        let inner = RocksdbEngine::open_readonly(path, &column_families, db_opts)?;
+        Ok(Self::log_construct(name, column_families, Box::new(inner)))
     }
 
     /// Open db as secondary.
@@ -245,67 +245,58 @@ impl DB {
         column_families: Vec<ColumnFamilyName>,
         db_opts: &rocksdb::Options,
     ) -> Result<Self> {
-        DB::open_cf_as_secondary(db_opts, primary_path, secondary_path, name, column_families)
-    }
-
-    fn open_cf(
-        db_opts: &rocksdb::Options,
-        path: impl AsRef<Path>,
-        name: &'static str,
-        column_families: Vec<ColumnFamilyName>,
-    ) -> Result<DB> {
-        let inner = rocksdb::DB::open_cf_descriptors(
+        let inner = RocksdbEngine::open_as_secondary(
+            primary_path,
+            secondary_path,
+            &column_families,
             db_opts,
-            path,
-            column_families.iter().map(|cf_name| {
-                let mut cf_opts = rocksdb::Options::default();
-                cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
-                rocksdb::ColumnFamilyDescriptor::new((*cf_name).to_string(), cf_opts)
-            }),
         )?;
-        Ok(Self::log_construct(name, column_families, inner))
+        Ok(Self::log_construct(name, column_families, Box::new(inner)))
     }
 
-    fn open_cf_readonly(
-        opts: &rocksdb::Options,
-        path: impl AsRef<Path>,
+    /// Create an empty db with all the column families provided, held entirely in memory (see
+    /// [`InMemoryEngine`]). Nothing is persisted.
+    pub fn open_in_memory(
         name: &'static str,
         column_families: Vec<ColumnFamilyName>,
-    ) -> Result<DB> {
-        let error_if_log_file_exists = false;
-        let inner = rocksdb::DB::open_cf_for_read_only(
-            opts,
-            path,
-            &column_families,
-            error_if_log_file_exists,
-        )?;
+    ) -> Result<Self> {
+        Self::verify_column_families(&column_families)?;
 
-        Ok(Self::log_construct(name, column_families, inner))
+        let inner = InMemoryEngine::new(&column_families);
+        Ok(Self::log_construct(name, column_families, Box::new(inner)))
     }
 
-    fn open_cf_as_secondary<P: AsRef<Path>>(
-        opts: &rocksdb::Options,
-        primary_path: P,
-        secondary_path: P,
+    /// Create db on top of the given (already opened) storage engine, which must hold all the
+    /// column families provided.
+    pub fn open_with_engine(
         name: &'static str,
         column_families: Vec<ColumnFamilyName>,
-    ) -> Result<DB> {
-        let inner = rocksdb::DB::open_cf_as_secondary(
-            opts,
-            primary_path,
-            secondary_path,
-            &column_families,
-        )?;
+        engine: Box<dyn StorageEngine>,
+    ) -> Result<Self> {
+        Self::verify_column_families(&column_families)?;
 
-        Ok(Self::log_construct(name, column_families, inner))
+        Ok(Self::log_construct(name, column_families, engine))
+    }
+
+    fn verify_column_families(column_families: &[ColumnFamilyName]) -> Result<()> {
+        let cfs_set: HashSet<_> = column_families.iter().collect();
+        ensure!(
+            cfs_set.contains(&DEFAULT_CF_NAME),
+            "No \"default\" column family name is provided.",
+        );
+        ensure!(
+            cfs_set.len() == column_families.len(),
+            "Duplicate column family name found.",
+        );
+        Ok(())
     }
 
     fn log_construct(
         name: &'static str,
         column_families: Vec<&'static str>,
-        inner: rocksdb::DB,
+        inner: Box<dyn StorageEngine>,
     ) -> DB {
-        info!(rocksdb_name = name, "Opened RocksDB.");
+        info!(db_name = name, "Opened DB.");
         DB {
             name,
             inner,
@@ -320,9 +311,7 @@ impl DB {
             .start_timer();
 
         let k = <S::Key as KeyCodec<S>>::encode_key(schema_key)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
-
-        let result = self.inner.get_cf(cf_handle, &k)?;
+        let result = self.inner.get_cf(S::COLUMN_FAMILY_NAME, &k)?;
         DIEM_SCHEMADB_GET_BYTES
             .with_label_values(&[S::COLUMN_FAMILY_NAME])
             .observe(result.as_ref().map_or(0.0, |v| v.len() as f64));
@@ -352,11 +341,9 @@ impl DB {
     {
         let raw_begin = begin.encode_seek_key()?;
         let raw_end = end.encode_seek_key()?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
         self.inner
-            .delete_range_cf(cf_handle, &raw_begin, &raw_end)?;
-        Ok(())
+            .delete_range_cf(S::COLUMN_FAMILY_NAME, &raw_begin, &raw_end)
     }
 
     fn iter_with_direction<S: Schema>(
@@ -364,9 +351,8 @@ impl DB {
         opts: ReadOptions,
         direction: ScanDirection,
     ) -> Result<SchemaIterator<S>> {
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
         Ok(SchemaIterator::new(
-            self.inner.raw_iterator_cf_opt(cf_handle, opts),
+            self.inner.raw_iterator_cf(S::COLUMN_FAMILY_NAME, opts)?,
             direction,
         ))
     }
@@ -387,19 +373,7 @@ impl DB {
             .with_label_values(&[self.name])
             .start_timer();
 
-        let mut db_batch = rocksdb::WriteBatch::default();
-        for (cf_name, rows) in &batch.rows {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            for (key, write_op) in rows {
-                match write_op {
-                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
-                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
-                }
-            }
-        }
-        let serialized_size = db_batch.size_in_bytes();
-
-        self.inner.write_opt(db_batch, &default_write_options())?;
+        let serialized_size = self.inner.write_batch(&batch)?;
 
         // Bump counters only after DB write succeeds.
         for (cf_name, rows) in &batch.rows {
@@ -423,49 +397,21 @@ impl DB {
         Ok(())
     }
 
-    fn get_cf_handle(&self, cf_name: &str) -> Result<&rocksdb::ColumnFamily> {
-        self.inner.cf_handle(cf_name).ok_or_else(|| {
-            format_err!(
-                "DB::cf_handle not found for column family name: {}",
-                cf_name
-            )
-        })
-    }
-
     /// Flushes all memtable data. This is only used for testing `get_approximate_sizes_cf` in unit
     /// tests.
     pub fn flush_all(&self) -> Result<()> {
         for cf_name in &self.column_families {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            self.inner.flush_cf(cf_handle)?;
+            self.inner.flush_cf(cf_name)?;
         }
         Ok(())
     }
 
     pub fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
-        self.inner
-            .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
-            .ok_or_else(|| {
-                format_err!(
-                    "Unable to get property \"{}\" of  column family \"{}\".",
-                    property_name,
-                    cf_name,
-                )
-            })
+        self.inner.get_property(cf_name, property_name)
     }
 
     /// Creates new physical DB checkpoint in directory specified by `path`.
     pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
-        Ok(())
+        self.inner.create_checkpoint(path.as_ref())
     }
 }
-
-/// For now we always use synchronous writes. This makes sure that once the operation returns
-/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
-/// selectively turning this off for some non-critical writes to improve performance.
-fn default_write_options() -> rocksdb::WriteOptions {
-    let mut opts = rocksdb::WriteOptions::default();
-    opts.set_sync(true);
-    opts
-}
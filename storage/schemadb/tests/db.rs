@@ -107,8 +107,12 @@ fn open_db_as_secondary(dir: &diem_temppath::TempPath, dir_sec: &diem_temppath::
     .expect("Failed to open DB.")
 }
 
+fn open_db_in_memory() -> DB {
+    DB::open_in_memory("test", get_column_families()).expect("Failed to open DB.")
+}
+
 struct TestDB {
-    _tmpdir: diem_temppath::TempPath,
+    _tmpdir: Option<diem_temppath::TempPath>,
     db: DB,
 }
 
@@ -118,10 +122,22 @@ impl TestDB {
         let db = open_db(&tmpdir);
 
         TestDB {
-            _tmpdir: tmpdir,
+            _tmpdir: Some(tmpdir),
             db,
         }
     }
+
+    fn new_in_memory() -> Self {
+        TestDB {
+            _tmpdir: None,
+            db: open_db_in_memory(),
+        }
+    }
+
+    /// Creates a test DB on top of each storage engine.
+    fn new_for_all_engines() -> Vec<Self> {
+        vec![Self::new(), Self::new_in_memory()]
+    }
 }
 
 impl std::ops::Deref for TestDB {
@@ -134,42 +150,42 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_schema_put_get() {
-    let db = TestDB::new();
-
-    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
-    db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
-    db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
-    db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
-    db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
-    db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
+    for db in TestDB::new_for_all_engines() {
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+        db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
+        db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
+        db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
+        db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
 
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(0)).unwrap(),
-        Some(TestField(0)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(1)).unwrap(),
-        Some(TestField(1)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(2)).unwrap(),
-        Some(TestField(2)),
-    );
-    assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(1)).unwrap(),
+            Some(TestField(1)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(2)).unwrap(),
+            Some(TestField(2)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
 
-    assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(2)).unwrap(),
-        Some(TestField(3)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(3)).unwrap(),
-        Some(TestField(4)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(4)).unwrap(),
-        Some(TestField(5)),
-    );
+        assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(2)).unwrap(),
+            Some(TestField(3)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(3)).unwrap(),
+            Some(TestField(4)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(4)).unwrap(),
+            Some(TestField(5)),
+        );
+    }
 }
 
 proptest! {
@@ -180,23 +196,24 @@ proptest! {
         ranges_to_delete in vec(
             (0..100u32).prop_flat_map(|begin| (Just(begin), (begin..100u32))), 0..10)
     ) {
-        let db = TestDB::new();
-        for i in 0..100u32 {
-            db.put::<TestSchema1>(&TestField(i), &TestField(i)).unwrap();
-        }
-        let mut should_exist_vec = [true; 100];
-        for (begin, end) in ranges_to_delete {
-            db.range_delete::<TestSchema1, TestField>(&TestField(begin), &TestField(end)).unwrap();
-            for i in begin..end {
-                should_exist_vec[i as usize] = false;
+        for db in TestDB::new_for_all_engines() {
+            for i in 0..100u32 {
+                db.put::<TestSchema1>(&TestField(i), &TestField(i)).unwrap();
+            }
+            let mut should_exist_vec = [true; 100];
+            for (begin, end) in &ranges_to_delete {
+                db.range_delete::<TestSchema1, TestField>(&TestField(*begin), &TestField(*end)).unwrap();
+                for i in *begin..*end {
+                    should_exist_vec[i as usize] = false;
+                }
             }
-        }
 
-        for (i, should_exist) in should_exist_vec.iter().enumerate() {
-            assert_eq!(
-                db.get::<TestSchema1>(&TestField(i as u32)).unwrap().is_some(),
-                *should_exist,
-            )
+            for (i, should_exist) in should_exist_vec.iter().enumerate() {
+                assert_eq!(
+                    db.get::<TestSchema1>(&TestField(i as u32)).unwrap().is_some(),
+                    *should_exist,
+                )
+            }
         }
     }
 }
@@ -219,84 +236,84 @@ fn gen_expected_values(values: &[(u32, u32)]) -> Vec<(TestField, TestField)> {
 
 #[test]
 fn test_single_schema_batch() {
-    let db = TestDB::new();
-
-    let mut db_batch = SchemaBatch::new();
-    db_batch
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch).unwrap();
+    for db in TestDB::new_for_all_engines() {
+        let mut db_batch = SchemaBatch::new();
+        db_batch
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
 fn test_two_schema_batches() {
-    let db = TestDB::new();
-
-    let mut db_batch1 = SchemaBatch::new();
-    db_batch1
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
-    db.write_schemas(db_batch1).unwrap();
+    for db in TestDB::new_for_all_engines() {
+        let mut db_batch1 = SchemaBatch::new();
+        db_batch1
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
+        db.write_schemas(db_batch1).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
 
-    let mut db_batch2 = SchemaBatch::new();
-    db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch2).unwrap();
+        let mut db_batch2 = SchemaBatch::new();
+        db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch2).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
@@ -414,3 +431,39 @@ fn test_checkpoint() {
         assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
     }
 }
+
+#[test]
+fn test_in_memory_unsupported_operations() {
+    let db = TestDB::new_in_memory();
+    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+
+    // Flushing is a no-op, but properties and checkpoints aren't supported
+    db.flush_all().unwrap();
+    assert!(db
+        .get_property("TestCF1", "rocksdb.estimate-live-data-size")
+        .is_err());
+    let checkpoint = diem_temppath::TempPath::new();
+    assert!(db.create_checkpoint(&checkpoint).is_err());
+}
+
+#[test]
+fn test_in_memory_missing_column_family() {
+    let db = DB::open_in_memory(
+        "test",
+        vec![DEFAULT_CF_NAME, TestSchema1::COLUMN_FAMILY_NAME],
+    )
+    .expect("Failed to open DB.");
+    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+
+    // A batch touching a missing column family fails as a whole
+    let mut db_batch = SchemaBatch::new();
+    db_batch
+        .put::<TestSchema1>(&TestField(1), &TestField(1))
+        .unwrap();
+    db_batch
+        .put::<TestSchema2>(&TestField(1), &TestField(1))
+        .unwrap();
+    assert!(db.write_schemas(db_batch).is_err());
+    assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+    assert!(db.get::<TestSchema2>(&TestField(0)).is_err());
+}
@@ -6,7 +6,7 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use schemadb::{
     define_schema,
     schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
-    SchemaIterator, DB, DEFAULT_CF_NAME,
+    ColumnFamilyName, SchemaIterator, DB, DEFAULT_CF_NAME,
 };
 
 define_schema!(TestSchema, TestKey, TestValue, "TestCF");
@@ -66,24 +66,42 @@ impl SeekKeyCodec<TestSchema> for KeyPrefix2 {
     }
 }
 
+fn get_column_families() -> Vec<ColumnFamilyName> {
+    vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME]
+}
+
 fn collect_values(iter: SchemaIterator<TestSchema>) -> Vec<u32> {
     iter.map(|row| (row.unwrap().1).0).collect()
 }
 
 struct TestDB {
-    _tmpdir: diem_temppath::TempPath,
+    _tmpdir: Option<diem_temppath::TempPath>,
     db: DB,
 }
 
 impl TestDB {
     fn new() -> Self {
         let tmpdir = diem_temppath::TempPath::new();
-        let column_families = vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME];
         let mut db_opts = rocksdb::Options::default();
         db_opts.create_if_missing(true);
         db_opts.create_missing_column_families(true);
-        let db = DB::open(&tmpdir.path(), "test", column_families, &db_opts).unwrap();
+        let db = DB::open(&tmpdir.path(), "test", get_column_families(), &db_opts).unwrap();
+
+        Self::init(Some(tmpdir), db)
+    }
+
+    fn new_in_memory() -> Self {
+        let db = DB::open_in_memory("test", get_column_families()).unwrap();
 
+        Self::init(None, db)
+    }
+
+    /// Creates a test DB on top of each storage engine.
+    fn new_for_all_engines() -> Vec<Self> {
+        vec![Self::new(), Self::new_in_memory()]
+    }
+
+    fn init(tmpdir: Option<diem_temppath::TempPath>, db: DB) -> Self {
         db.put::<TestSchema>(&TestKey(1, 0, 0), &TestValue(100))
             .unwrap();
         db.put::<TestSchema>(&TestKey(1, 0, 2), &TestValue(102))
@@ -132,136 +150,158 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_seek_to_first() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_to_first();
-    assert_eq!(
-        collect_values(iter),
-        [100, 102, 104, 110, 112, 114, 200, 202]
-    );
-
-    let mut iter = db.rev_iter();
-    iter.seek_to_first();
-    assert_eq!(collect_values(iter), [100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_to_first();
+        assert_eq!(
+            collect_values(iter),
+            [100, 102, 104, 110, 112, 114, 200, 202]
+        );
+
+        let mut iter = db.rev_iter();
+        iter.seek_to_first();
+        assert_eq!(collect_values(iter), [100]);
+    }
 }
 
 #[test]
 fn test_seek_to_last() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_to_last();
-    assert_eq!(collect_values(iter), [202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_to_last();
-    assert_eq!(
-        collect_values(iter),
-        [202, 200, 114, 112, 110, 104, 102, 100]
-    );
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_to_last();
+        assert_eq!(collect_values(iter), [202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_to_last();
+        assert_eq!(
+            collect_values(iter),
+            [202, 200, 114, 112, 110, 104, 102, 100]
+        );
+    }
 }
 
 #[test]
 fn test_seek_by_existing_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_by_nonexistent_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [112, 110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_existing_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_nonexistent_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_by_1prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_1prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_by_2prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_2prefix() {
-    let db = TestDB::new();
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    }
+}
 
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
+#[test]
+fn test_iterator_snapshot() {
+    for db in TestDB::new_for_all_engines() {
+        let mut iter = db.iter();
+        iter.seek_to_first();
 
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+        // Updates written after the iterator was created aren't observed
+        db.put::<TestSchema>(&TestKey(1, 0, 1), &TestValue(101))
+            .unwrap();
+        db.range_delete::<TestSchema, KeyPrefix1>(&KeyPrefix1(2), &KeyPrefix1(3))
+            .unwrap();
+        assert_eq!(
+            collect_values(iter),
+            [100, 102, 104, 110, 112, 114, 200, 202]
+        );
+
+        let mut iter = db.iter();
+        iter.seek_to_first();
+        assert_eq!(collect_values(iter), [100, 101, 102, 104, 110, 112, 114]);
+    }
 }
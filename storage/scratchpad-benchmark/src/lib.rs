@@ -36,8 +36,11 @@ pub fn run_benchmark(num_updates: usize, max_accounts: u64, blob_size: usize, db
         &db_dir,
         false, /* readonly */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         RocksdbConfig::default(),
-        true, /* account_count_migration, ignored anyway */
+        true,  /* account_count_migration, ignored anyway */
+        false, /* resource_granular_state */
+        0,     /* state_value_cache_size */
     )
     .expect("DB should open.");
 
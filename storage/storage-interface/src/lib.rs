@@ -3,11 +3,12 @@
 
 use anyhow::{format_err, Result};
 use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+pub use diem_jellyfish_merkle::restore::StateSnapshotReceiver;
 use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesChunkWithProof},
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
@@ -20,6 +21,7 @@ use diem_types::{
     },
     protocol_spec::ProtocolSpec,
     state_proof::StateProof,
+    state_value::StateValue,
     transaction::{
         AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
         TransactionOutputListWithProof, TransactionToCommit, TransactionWithProof, Version,
@@ -355,6 +357,38 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         SparseMerkleProof<AccountStateBlob>,
     )>;
 
+    /// Gets an account state by account address at the given version, without a proof, which
+    /// allows implementations to serve it from a cache.
+    fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountStateBlob>> {
+        self.get_account_state_with_proof_by_version(address, version)
+            .map(|(blob, _proof)| blob)
+    }
+
+    /// Returns true if the state is also stored by resource, in which case
+    /// [`DbReader::get_state_value_with_proof_by_version`] is available.
+    fn is_state_stored_by_resource(&self) -> bool {
+        false
+    }
+
+    /// Gets the value of a single resource or module by its access path, with a sparse merkle
+    /// proof proving it (or its non-existence) against the root of the resource-granular state
+    /// tree at `version`. That root isn't committed to by the ledger, so the proof is not
+    /// authenticated by a ledger info. See [`DiemDB::get_state_value_with_proof_by_version`].
+    ///
+    /// [`DiemDB::get_state_value_with_proof_by_version`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_state_value_with_proof_by_version
+    fn get_state_value_with_proof_by_version(
+        &self,
+        _access_path: &AccessPath,
+        _version: Version,
+    ) -> Result<(Option<StateValue>, SparseMerkleProof<StateValue>)> {
+        unimplemented!()
+    }
+
     /// See [`DiemDB::get_latest_state_root`].
     ///
     /// [`DiemDB::get_latest_state_root`]:
@@ -402,6 +436,36 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         unimplemented!()
     }
 
+    /// Gets the first version at which transactions and their outputs (i.e., the transaction
+    /// infos, write sets and events) are readable. Data of older versions has been pruned.
+    fn get_first_txn_version(&self) -> Result<Version> {
+        unimplemented!()
+    }
+
+    /// Gets the first version at which the account states are readable. The state of older
+    /// versions has been pruned.
+    fn get_first_state_version(&self) -> Result<Version> {
+        unimplemented!()
+    }
+
+    /// Gets the total number of accounts (i.e., leaves in the state tree) at the
+    /// specified version.
+    fn get_account_count(&self, _version: Version) -> Result<usize> {
+        unimplemented!()
+    }
+
+    /// Gets a chunk of `chunk_size` account states at the specified version, starting
+    /// at the account with index `first_index` (ordered by hashed account address),
+    /// together with a range proof that can be verified against the state root hash.
+    fn get_account_chunk_with_proof(
+        &self,
+        _version: Version,
+        _first_index: usize,
+        _chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        unimplemented!()
+    }
+
     /// A convenience function for building a [`TransactionAccumulatorSummary`]
     /// at the given `ledger_version`.
     ///
@@ -430,6 +494,19 @@ impl<PS: ProtocolSpec> MoveStorage for &dyn DbReader<PS> {
         access_paths: Vec<AccessPath>,
         version: Version,
     ) -> Result<Vec<Vec<u8>>> {
+        if self.is_state_stored_by_resource() {
+            return access_paths
+                .iter()
+                .map(|path| {
+                    let (value, _proof) =
+                        self.get_state_value_with_proof_by_version(path, version)?;
+                    value
+                        .and_then(|value| value.maybe_bytes)
+                        .ok_or_else(|| format_err!("no value found for queried access path"))
+                })
+                .collect();
+        }
+
         let addresses: Vec<AccountAddress> = access_paths
             .iter()
             .collect::<HashSet<_>>()
@@ -439,14 +516,14 @@ impl<PS: ProtocolSpec> MoveStorage for &dyn DbReader<PS> {
 
         let results = addresses
             .iter()
-            .map(|addr| self.get_account_state_with_proof_by_version(*addr, version))
+            .map(|addr| self.get_account_state_by_version(*addr, version))
             .collect::<Result<Vec<_>>>()?;
 
         // Account address --> AccountState
         let account_states = addresses
             .iter()
             .zip_eq(results)
-            .map(|(addr, (blob, _proof))| {
+            .map(|(addr, blob)| {
                 let account_state = AccountState::try_from(&blob.ok_or_else(|| {
                     format_err!("missing blob in account state/account does not exist")
                 })?)?;
@@ -495,6 +572,30 @@ pub trait DbWriter<PS: ProtocolSpec>: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Gets a receiver that can be used to restore the account states at the given `version`,
+    /// verifying them against the `expected_root_hash`. Used by state sync to bootstrap
+    /// from a state snapshot.
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        unimplemented!()
+    }
+
+    /// Finalizes a state snapshot (already restored using a state snapshot receiver) by
+    /// persisting the transaction and output at the snapshot `version` (i.e., the last
+    /// item in `output_with_proof`), the frozen subtrees of the transaction accumulator and
+    /// the given ledger infos. The last ledger info must be at the snapshot version.
+    fn finalize_state_snapshot(
+        &self,
+        _version: Version,
+        _output_with_proof: TransactionOutputListWithProof<PS::TransactionInfo>,
+        _ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 pub trait MoveDbReader<PS: ProtocolSpec>:
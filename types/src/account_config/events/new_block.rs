@@ -6,13 +6,15 @@ use anyhow::Result;
 use move_core_types::{ident_str, identifier::IdentStr, move_resource::MoveStructType};
 use serde::{Deserialize, Serialize};
 
-/// Struct that represents a NewBlockEvent.
+/// Struct that represents a NewBlockEvent. The events emitted before the failed proposers were
+/// recorded don't have them, they're decoded with no failed proposers.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewBlockEvent {
     round: u64,
     proposer: AccountAddress,
     previous_block_votes: Vec<AccountAddress>,
     time_micro_seconds: u64,
+    failed_proposers: Vec<AccountAddress>,
 }
 
 impl NewBlockEvent {
@@ -28,8 +30,29 @@ impl NewBlockEvent {
         self.time_micro_seconds
     }
 
+    pub fn failed_proposers(&self) -> &[AccountAddress] {
+        &self.failed_proposers
+    }
+
     pub fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
-        bcs::from_bytes(bytes).map_err(Into::into)
+        #[derive(Deserialize)]
+        struct NewBlockEventV1 {
+            round: u64,
+            proposer: AccountAddress,
+            previous_block_votes: Vec<AccountAddress>,
+            time_micro_seconds: u64,
+        }
+
+        bcs::from_bytes(bytes).or_else(|_| {
+            let event = bcs::from_bytes::<NewBlockEventV1>(bytes)?;
+            Ok(Self {
+                round: event.round,
+                proposer: event.proposer,
+                previous_block_votes: event.previous_block_votes,
+                time_micro_seconds: event.time_micro_seconds,
+                failed_proposers: vec![],
+            })
+        })
     }
 
     #[cfg(any(test, feature = "fuzzing"))]
@@ -38,12 +61,14 @@ impl NewBlockEvent {
         proposer: AccountAddress,
         previous_block_votes: Vec<AccountAddress>,
         time_micro_seconds: u64,
+        failed_proposers: Vec<AccountAddress>,
     ) -> Self {
         Self {
             round,
             proposer,
             previous_block_votes,
             time_micro_seconds,
+            failed_proposers,
         }
     }
 }
@@ -69,6 +69,14 @@ impl AccountResource {
         &self.authentication_key
     }
 
+    /// Returns the AccountResource with its authentication_key field replaced
+    pub fn with_authentication_key(self, authentication_key: Vec<u8>) -> Self {
+        Self {
+            authentication_key,
+            ..self
+        }
+    }
+
     /// Return the sent_events handle for the given AccountResource
     pub fn sent_events(&self) -> &EventHandle {
         &self.sent_events
@@ -26,7 +26,13 @@ use serde::{Deserialize, Serialize};
 /// 3. Once that special resource is modified, the other user transactions can read the consensus
 ///    info by calling into the read method of that resource, which would thus give users the
 ///    information such as the current leader.
+///
+/// The failed proposers were added after the chain started: a transaction only carries them in
+/// its `BlockMetadataV2` variant, so that the block metadata committed before keeps its encoding
+/// (see [`BlockMetadataV1`]). Consensus only records them when its proposer election relies on
+/// them, and they're only passed to the block prologue from `DIEM_VERSION_5`.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "BlockMetadataV2")]
 pub struct BlockMetadata {
     id: HashValue,
     round: u64,
@@ -34,6 +40,8 @@ pub struct BlockMetadata {
     // The vector has to be sorted to ensure consistent result among all nodes
     previous_block_votes: Vec<AccountAddress>,
     proposer: AccountAddress,
+    // The proposers of the rounds skipped since the parent block, ordered by round
+    failed_proposers: Vec<AccountAddress>,
 }
 
 impl BlockMetadata {
@@ -43,6 +51,7 @@ impl BlockMetadata {
         timestamp_usecs: u64,
         previous_block_votes: Vec<AccountAddress>,
         proposer: AccountAddress,
+        failed_proposers: Vec<AccountAddress>,
     ) -> Self {
         Self {
             id,
@@ -50,6 +59,7 @@ impl BlockMetadata {
             timestamp_usecs,
             previous_block_votes,
             proposer,
+            failed_proposers,
         }
     }
 
@@ -57,12 +67,21 @@ impl BlockMetadata {
         self.id
     }
 
-    pub fn into_inner(self) -> (u64, u64, Vec<AccountAddress>, AccountAddress) {
+    pub fn into_inner(
+        self,
+    ) -> (
+        u64,
+        u64,
+        Vec<AccountAddress>,
+        AccountAddress,
+        Vec<AccountAddress>,
+    ) {
         (
             self.round,
             self.timestamp_usecs,
             self.previous_block_votes.clone(),
             self.proposer,
+            self.failed_proposers,
         )
     }
 
@@ -78,11 +97,52 @@ impl BlockMetadata {
         &self.previous_block_votes
     }
 
+    pub fn failed_proposers(&self) -> &Vec<AccountAddress> {
+        &self.failed_proposers
+    }
+
     pub fn round(&self) -> u64 {
         self.round
     }
 }
 
+/// The layout of the block metadata without failed proposers, the only one before they were
+/// recorded.
+#[derive(Deserialize, Serialize)]
+#[serde(rename = "BlockMetadata")]
+pub(crate) struct BlockMetadataV1 {
+    id: HashValue,
+    round: u64,
+    timestamp_usecs: u64,
+    previous_block_votes: Vec<AccountAddress>,
+    proposer: AccountAddress,
+}
+
+impl From<&BlockMetadata> for BlockMetadataV1 {
+    fn from(block_metadata: &BlockMetadata) -> Self {
+        Self {
+            id: block_metadata.id,
+            round: block_metadata.round,
+            timestamp_usecs: block_metadata.timestamp_usecs,
+            previous_block_votes: block_metadata.previous_block_votes.clone(),
+            proposer: block_metadata.proposer,
+        }
+    }
+}
+
+impl From<BlockMetadataV1> for BlockMetadata {
+    fn from(block_metadata: BlockMetadataV1) -> Self {
+        Self::new(
+            block_metadata.id,
+            block_metadata.round,
+            block_metadata.timestamp_usecs,
+            block_metadata.previous_block_votes,
+            block_metadata.proposer,
+            vec![],
+        )
+    }
+}
+
 pub fn new_block_event_key() -> EventKey {
     EventKey::new_from_address(&diem_root_address(), 17)
 }
@@ -118,12 +178,15 @@ impl MoveStructType for DiemBlockResource {
 
 impl MoveResource for DiemBlockResource {}
 
+/// A new block event. The events emitted before the failed proposers were recorded don't have
+/// them: [`NewBlockEvent::try_from_bytes`] decodes both layouts.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct NewBlockEvent {
     round: u64,
     proposer: AccountAddress,
     votes: Vec<AccountAddress>,
     timestamp: u64,
+    failed_proposers: Vec<AccountAddress>,
 }
 
 impl NewBlockEvent {
@@ -132,12 +195,14 @@ impl NewBlockEvent {
         proposer: AccountAddress,
         votes: Vec<AccountAddress>,
         timestamp: u64,
+        failed_proposers: Vec<AccountAddress>,
     ) -> Self {
         Self {
             round,
             proposer,
             votes,
             timestamp,
+            failed_proposers,
         }
     }
     pub fn round(&self) -> u64 {
@@ -151,4 +216,29 @@ impl NewBlockEvent {
     pub fn votes(&self) -> Vec<AccountAddress> {
         self.votes.clone()
     }
+
+    pub fn failed_proposers(&self) -> &[AccountAddress] {
+        &self.failed_proposers
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        #[derive(Deserialize)]
+        struct NewBlockEventV1 {
+            round: u64,
+            proposer: AccountAddress,
+            votes: Vec<AccountAddress>,
+            timestamp: u64,
+        }
+
+        bcs::from_bytes(bytes).or_else(|_| {
+            let event = bcs::from_bytes::<NewBlockEventV1>(bytes)?;
+            Ok(Self::new(
+                event.round,
+                event.proposer,
+                event.votes,
+                event.timestamp,
+                vec![],
+            ))
+        })
+    }
 }
@@ -29,6 +29,7 @@ pub mod proptest_types;
 pub mod protocol_spec;
 pub mod serde_helper;
 pub mod state_proof;
+pub mod state_value;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod test_helpers;
 pub mod transaction;
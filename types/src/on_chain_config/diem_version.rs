@@ -29,5 +29,10 @@ pub const DIEM_VERSION_3: DiemVersion = DiemVersion { major: 3 };
 //  - Conflict-Resistant Sequence Numbers
 pub const DIEM_VERSION_4: DiemVersion = DiemVersion { major: 4 };
 
+// NOTE: version number for release 1.5 of Diem
+// Items gated by this version number include:
+//  - Failed proposers in the block prologue
+pub const DIEM_VERSION_5: DiemVersion = DiemVersion { major: 5 };
+
 // Maximum current known version
-pub const DIEM_MAX_KNOWN_VERSION: DiemVersion = DIEM_VERSION_4;
+pub const DIEM_MAX_KNOWN_VERSION: DiemVersion = DIEM_VERSION_5;
@@ -28,6 +28,7 @@ pub use self::{
     consensus_config::{ConsensusConfigV1, OnChainConsensusConfig},
     diem_version::{
         DiemVersion, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
+        DIEM_VERSION_5,
     },
     registered_currencies::RegisteredCurrencies,
     validator_set::ValidatorSet,
@@ -557,6 +557,7 @@ fn test_transaction_list_with_proof() {
         0,
         vec![],
         AccountAddress::random(),
+        vec![],
     ))];
 
     // Create transaction list with proof
@@ -620,8 +621,16 @@ fn test_transaction_list_with_proof() {
 
 #[test]
 fn test_transaction_output_list_with_proof() {
-    // Create test event and transaction output
+    // Create test event, transaction and transaction output
     let event = create_event();
+    let transaction = Transaction::BlockMetadata(BlockMetadata::new(
+        HashValue::random(),
+        0,
+        0,
+        vec![],
+        AccountAddress::random(),
+        vec![],
+    ));
     let transaction_output = TransactionOutput::new(
         WriteSet::default(),
         vec![event.clone()],
@@ -632,7 +641,7 @@ fn test_transaction_output_list_with_proof() {
     // Create transaction output list with proof
     let transaction_info_list_proof = create_single_transaction_info_proof(None, None);
     let transaction_output_list_proof = TransactionOutputListWithProof::new(
-        vec![transaction_output.clone()],
+        vec![(transaction.clone(), transaction_output.clone())],
         Some(1),
         transaction_info_list_proof.clone(),
     );
@@ -643,7 +652,7 @@ fn test_transaction_output_list_with_proof() {
         .verify(&empty_ledger_info, None)
         .unwrap_err();
 
-    // Verify correct info hash but event verification now fails (event hash mismatch)
+    // Verify correct info hash but transaction verification now fails (transaction hash mismatch)
     let expected_info_hash = transaction_info_list_proof.transaction_infos[0].hash();
     let block_info = BlockInfo::new(0, 0, HashValue::random(), expected_info_hash, 0, 0, None);
     let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
@@ -651,12 +660,27 @@ fn test_transaction_output_list_with_proof() {
         .verify(&ledger_info, Some(1))
         .unwrap_err();
 
+    // Verify correct transaction hash but event verification now fails (event hash mismatch)
+    let transaction_info_list_proof =
+        create_single_transaction_info_proof(Some(transaction.hash()), None);
+    let expected_info_hash = transaction_info_list_proof.transaction_infos[0].hash();
+    let transaction_output_list_proof = TransactionOutputListWithProof::new(
+        vec![(transaction.clone(), transaction_output.clone())],
+        Some(1),
+        transaction_info_list_proof,
+    );
+    let block_info = BlockInfo::new(0, 0, HashValue::random(), expected_info_hash, 0, 0, None);
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    transaction_output_list_proof
+        .verify(&ledger_info, Some(1))
+        .unwrap_err();
+
     // Construct a new transaction output list proof where the transaction info and event hashes match
     let transaction_info_list_proof =
-        create_single_transaction_info_proof(None, Some(event.hash()));
+        create_single_transaction_info_proof(Some(transaction.hash()), Some(event.hash()));
     let expected_info_hash = transaction_info_list_proof.transaction_infos[0].hash();
     let transaction_output_list_proof = TransactionOutputListWithProof::new(
-        vec![transaction_output],
+        vec![(transaction, transaction_output)],
         Some(1),
         transaction_info_list_proof,
     );
@@ -948,10 +948,13 @@ impl Arbitrary for BlockMetadata {
             any::<u64>(),
             addr_strategy,
             any::<AccountAddress>(),
+            prop::collection::vec(any::<AccountAddress>(), 0..3),
         )
-            .prop_map(|(id, round, timestamp, addresses, proposer)| {
-                BlockMetadata::new(id, round, timestamp, addresses, proposer)
-            })
+            .prop_map(
+                |(id, round, timestamp, addresses, proposer, failed_proposers)| {
+                    BlockMetadata::new(id, round, timestamp, addresses, proposer, failed_proposers)
+                },
+            )
             .boxed()
     }
 
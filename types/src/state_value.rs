@@ -0,0 +1,29 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines [`StateValue`], the value of a single resource or module (identified by an
+//! [`AccessPath`](crate::access_path::AccessPath)) as stored in the resource-granular state tree.
+
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest_derive::Arbitrary;
+use serde::{Deserialize, Serialize};
+
+/// The value of a resource or module at a certain version. A deleted resource has no leaf in the
+/// state tree, so it is proven by a proof of non-existence.
+#[derive(
+    Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, CryptoHasher, BCSCryptoHash,
+)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct StateValue {
+    #[serde(with = "serde_bytes")]
+    pub maybe_bytes: Option<Vec<u8>>,
+}
+
+impl StateValue {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            maybe_bytes: Some(bytes),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     account_address::AccountAddress,
     account_config::XUS_NAME,
     account_state_blob::AccountStateBlob,
-    block_metadata::BlockMetadata,
+    block_metadata::{BlockMetadata, BlockMetadataV1},
     chain_id::ChainId,
     contract_event::ContractEvent,
     ledger_info::LedgerInfo,
@@ -29,7 +29,10 @@ use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use move_core_types::transaction_argument::convert_txn_args;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{
+    de::{DeserializeOwned, Error as _},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use std::{
     collections::HashMap,
     convert::TryFrom,
@@ -1185,26 +1188,26 @@ impl<T: TransactionInfoTrait> TransactionListWithProof<T> {
 }
 
 /// This differs from TransactionListWithProof in that TransactionOutputs are
-/// stored (no transactions). Events are stored inside each TransactionOutput.
+/// stored alongside the transactions. Events are stored inside each TransactionOutput.
 ///
 /// Note: the proof cannot verify the TransactionOutputs themselves. This
 /// requires speculative execution of each TransactionOutput to verify that the
 /// resulting state matches the expected state in the proof (for each version).
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub struct TransactionOutputListWithProof<T> {
-    pub transaction_outputs: Vec<TransactionOutput>,
+    pub transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
     pub first_transaction_output_version: Option<Version>,
     pub proof: TransactionInfoListWithProof<T>,
 }
 
 impl<T: TransactionInfoTrait> TransactionOutputListWithProof<T> {
     pub fn new(
-        transaction_outputs: Vec<TransactionOutput>,
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
         first_transaction_output_version: Option<Version>,
         proof: TransactionInfoListWithProof<T>,
     ) -> Self {
         Self {
-            transaction_outputs,
+            transactions_and_outputs,
             first_transaction_output_version,
             proof,
         }
@@ -1220,7 +1223,8 @@ impl<T: TransactionInfoTrait> TransactionOutputListWithProof<T> {
     /// 1. All transaction infos exist on the given `ledger_info`.
     /// 2. If `first_transaction_output_version` is None, the transaction output list is empty.
     ///    Otherwise, the list starts at `first_transaction_output_version`.
-    /// 3. Events in each transaction output match the expected event root hashes in the proof.
+    /// 3. Transactions in each pair match the expected transaction hashes in the proof.
+    /// 4. Events in each transaction output match the expected event root hashes in the proof.
     ///
     /// Note: the proof cannot verify the TransactionOutputs themselves. This
     /// requires speculative execution of each TransactionOutput to verify that the
@@ -1242,12 +1246,23 @@ impl<T: TransactionInfoTrait> TransactionOutputListWithProof<T> {
         self.proof
             .verify(ledger_info, self.first_transaction_output_version)?;
 
-        // Verify the events
-        itertools::zip_eq(&self.transaction_outputs, &self.proof.transaction_infos)
-            .map(|(txn_output, txn_info)| {
-                verify_events_against_root_hash(&txn_output.events, txn_info)
-            })
-            .collect::<Result<Vec<_>>>()?;
+        // Verify the transactions and events
+        itertools::zip_eq(
+            &self.transactions_and_outputs,
+            &self.proof.transaction_infos,
+        )
+        .map(|((txn, txn_output), txn_info)| {
+            let txn_hash = txn.hash();
+            ensure!(
+                txn_hash == txn_info.transaction_hash(),
+                "The transaction hash doesn't match that carried on the transaction info! \
+                     Transaction hash {:?}, transaction info hash {:?}",
+                txn_hash,
+                txn_info.transaction_hash()
+            );
+            verify_events_against_root_hash(&txn_output.events, txn_info)
+        })
+        .collect::<Result<Vec<_>>>()?;
 
         Ok(())
     }
@@ -1362,7 +1377,7 @@ impl<T: TransactionInfoTrait> AccountTransactionsWithProof<T> {
 /// transaction.
 #[allow(clippy::large_enum_variant)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
+#[derive(Clone, Debug, Eq, PartialEq, CryptoHasher, BCSCryptoHash)]
 pub enum Transaction {
     /// Transaction submitted by the user. e.g: P2P payment transaction, publishing module
     /// transaction, etc.
@@ -1377,6 +1392,75 @@ pub enum Transaction {
     BlockMetadata(BlockMetadata),
 }
 
+/// The block metadata without failed proposers is encoded the same way as before they were
+/// recorded, so that the transactions committed before (and their hashes) don't change. The block
+/// metadata with failed proposers is encoded as a variant of its own.
+impl Serialize for Transaction {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename = "Transaction")]
+        enum TransactionRef<'a> {
+            UserTransaction(&'a SignedTransaction),
+            GenesisTransaction(&'a WriteSetPayload),
+            BlockMetadata(BlockMetadataV1),
+            BlockMetadataV2(&'a BlockMetadata),
+        }
+
+        match self {
+            Transaction::UserTransaction(txn) => TransactionRef::UserTransaction(txn),
+            Transaction::GenesisTransaction(payload) => TransactionRef::GenesisTransaction(payload),
+            Transaction::BlockMetadata(block_metadata)
+                if block_metadata.failed_proposers().is_empty() =>
+            {
+                TransactionRef::BlockMetadata(block_metadata.into())
+            }
+            Transaction::BlockMetadata(block_metadata) => {
+                TransactionRef::BlockMetadataV2(block_metadata)
+            }
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Transaction {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Deserialize)]
+        #[serde(rename = "Transaction")]
+        enum TransactionEncoding {
+            UserTransaction(SignedTransaction),
+            GenesisTransaction(WriteSetPayload),
+            BlockMetadata(BlockMetadataV1),
+            BlockMetadataV2(BlockMetadata),
+        }
+
+        Ok(match TransactionEncoding::deserialize(deserializer)? {
+            TransactionEncoding::UserTransaction(txn) => Transaction::UserTransaction(txn),
+            TransactionEncoding::GenesisTransaction(payload) => {
+                Transaction::GenesisTransaction(payload)
+            }
+            TransactionEncoding::BlockMetadata(block_metadata) => {
+                Transaction::BlockMetadata(block_metadata.into())
+            }
+            TransactionEncoding::BlockMetadataV2(block_metadata) => {
+                // Keep a single encoding per transaction.
+                if block_metadata.failed_proposers().is_empty() {
+                    return Err(D::Error::custom(
+                        "block metadata without failed proposers must be encoded as BlockMetadata",
+                    ));
+                }
+                Transaction::BlockMetadata(block_metadata)
+            }
+        })
+    }
+}
+
 impl Transaction {
     pub fn as_signed_user_txn(&self) -> Result<&SignedTransaction> {
         match self {
@@ -1,8 +1,13 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::block_metadata::BlockMetadata;
+use crate::{
+    account_address::AccountAddress,
+    block_metadata::{BlockMetadata, NewBlockEvent},
+    transaction::Transaction,
+};
 use bcs::test_helpers::assert_canonical_encode_decode;
+use diem_crypto::HashValue;
 use proptest::prelude::*;
 
 proptest! {
@@ -13,3 +18,70 @@ proptest! {
         assert_canonical_encode_decode(data);
     }
 }
+
+#[test]
+fn test_block_metadata_transaction_encoding() {
+    let block_metadata = |failed_proposers| {
+        BlockMetadata::new(
+            HashValue::random(),
+            1,
+            2,
+            vec![AccountAddress::random()],
+            AccountAddress::random(),
+            failed_proposers,
+        )
+    };
+
+    // Without failed proposers, the block metadata is encoded as before they were recorded.
+    let block_metadata_v1 = block_metadata(vec![]);
+    let (round, timestamp_usecs, previous_block_votes, proposer, _) =
+        block_metadata_v1.clone().into_inner();
+    let txn = Transaction::BlockMetadata(block_metadata_v1.clone());
+    let bytes = bcs::to_bytes(&txn).unwrap();
+    assert_eq!(
+        bytes,
+        bcs::to_bytes(&(
+            2u8,
+            block_metadata_v1.id(),
+            round,
+            timestamp_usecs,
+            previous_block_votes,
+            proposer
+        ))
+        .unwrap()
+    );
+    assert_eq!(bcs::from_bytes::<Transaction>(&bytes).unwrap(), txn);
+
+    let txn = Transaction::BlockMetadata(block_metadata(vec![AccountAddress::random()]));
+    let bytes = bcs::to_bytes(&txn).unwrap();
+    assert_eq!(bytes[0], 3);
+    assert_eq!(bcs::from_bytes::<Transaction>(&bytes).unwrap(), txn);
+
+    // A single encoding is accepted for each transaction.
+    let mut bytes = bcs::to_bytes(&block_metadata_v1).unwrap();
+    bytes.insert(0, 3);
+    assert!(bcs::from_bytes::<Transaction>(&bytes).is_err());
+}
+
+#[test]
+fn test_new_block_event_decoding() {
+    let proposer = AccountAddress::random();
+    let failed_proposers = vec![AccountAddress::random()];
+    let bytes = bcs::to_bytes(&NewBlockEvent::new(
+        1,
+        proposer,
+        vec![],
+        2,
+        failed_proposers.clone(),
+    ))
+    .unwrap();
+    let event = NewBlockEvent::try_from_bytes(&bytes).unwrap();
+    assert_eq!(event.failed_proposers(), failed_proposers.as_slice());
+
+    // The events emitted before the failed proposers were recorded don't have them.
+    let bytes = bcs::to_bytes(&(1u64, proposer, Vec::<AccountAddress>::new(), 2u64)).unwrap();
+    let event = NewBlockEvent::try_from_bytes(&bytes).unwrap();
+    assert_eq!(event.round(), 1);
+    assert_eq!(event.proposer(), proposer);
+    assert!(event.failed_proposers().is_empty());
+}
@@ -21,14 +21,11 @@ use storage_interface::DbReaderWriter;
 
 struct TestValidator {
     vm_validator: VMValidator,
-    _db_path: diem_temppath::TempPath,
 }
 
 impl TestValidator {
     fn new() -> Self {
-        let _db_path = diem_temppath::TempPath::new();
-        _db_path.create_as_dir().unwrap();
-        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(_db_path.path()));
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test());
         executor_test_helpers::bootstrap_genesis::<DiemVM>(
             &db_rw,
             &vm_genesis::test_genesis_transaction(),
@@ -38,10 +35,7 @@ impl TestValidator {
         // Create another client for the vm_validator since the one used for the executor will be
         // run on another runtime which will be dropped before this function returns.
         let vm_validator = VMValidator::new(db);
-        TestValidator {
-            vm_validator,
-            _db_path,
-        }
+        TestValidator { vm_validator }
     }
 }
 